@@ -0,0 +1,230 @@
+//! Per-backend circuit breaker
+//!
+//! A backend that fails every task (e.g. after a GPU driver crash) would
+//! otherwise keep advertising its task types and keep drawing work it can't
+//! do. After `max_failures` consecutive backend faults within `window`, the
+//! breaker opens: the backend is skipped and its task types are withdrawn
+//! from the advertised capabilities. Once `open_for` has passed the breaker
+//! is half-open and the backend takes tasks again; the next outcome either
+//! closes the breaker or reopens it.
+//!
+//! Callers that gate individual requests use [`CircuitBreaker::try_acquire`],
+//! which lets a single probe through while half-open.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Circuit breaker settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerConfig {
+    /// Consecutive failures that open the breaker (0 = never open)
+    pub max_failures: u32,
+
+    /// Failures older than this don't count toward `max_failures`
+    pub window: Duration,
+
+    /// How long an open breaker stays open before a probe is let through
+    pub open_for: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            window: Duration::from_secs(60),
+            open_for: Duration::from_secs(120),
+        }
+    }
+}
+
+/// Breaker state as seen by task routing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Backend healthy
+    Closed,
+    /// Backend disabled after repeated failures
+    Open,
+    /// Open period over; the next outcome decides
+    HalfOpen,
+}
+
+impl std::fmt::Display for BreakerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreakerState::Closed => write!(f, "closed"),
+            BreakerState::Open => write!(f, "open"),
+            BreakerState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+/// Failure tracking for one backend
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    /// Times of the current run of consecutive failures
+    failures: VecDeque<Instant>,
+    /// When the breaker last opened (None = closed)
+    opened_at: Option<Instant>,
+    /// When the half-open probe was let through, until its outcome arrives
+    probe_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker
+    pub fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            failures: VecDeque::new(),
+            opened_at: None,
+            probe_at: None,
+        }
+    }
+
+    /// Current state
+    pub fn state(&self) -> BreakerState {
+        match self.opened_at {
+            None => BreakerState::Closed,
+            Some(at) if at.elapsed() < self.config.open_for => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Whether the backend may take tasks (closed or half-open)
+    pub fn is_available(&self) -> bool {
+        self.state() != BreakerState::Open
+    }
+
+    /// Whether a request may go ahead: always when closed, never when open,
+    /// and when half-open only as the single probe. A probe whose outcome
+    /// never arrives is replaced after another `open_for`.
+    pub fn try_acquire(&mut self) -> bool {
+        match self.state() {
+            BreakerState::Closed => true,
+            BreakerState::Open => false,
+            BreakerState::HalfOpen => {
+                if self.probe_at.is_some_and(|at| at.elapsed() < self.config.open_for) {
+                    return false;
+                }
+                self.probe_at = Some(Instant::now());
+                true
+            }
+        }
+    }
+
+    /// Record a successful task. Returns the new state if this closed the
+    /// breaker.
+    pub fn record_success(&mut self) -> Option<BreakerState> {
+        self.failures.clear();
+        self.probe_at = None;
+        self.opened_at.take().map(|_| BreakerState::Closed)
+    }
+
+    /// Record a backend failure. Returns the new state if this opened the
+    /// breaker.
+    pub fn record_failure(&mut self) -> Option<BreakerState> {
+        if self.config.max_failures == 0 {
+            return None;
+        }
+
+        let now = Instant::now();
+        self.probe_at = None;
+        match self.state() {
+            // Failed probe: back to open for another period
+            BreakerState::HalfOpen => {
+                self.opened_at = Some(now);
+                Some(BreakerState::Open)
+            }
+            // Stragglers that started before the breaker opened
+            BreakerState::Open => None,
+            BreakerState::Closed => {
+                self.failures.push_back(now);
+                while let Some(&first) = self.failures.front() {
+                    if now.duration_since(first) <= self.config.window {
+                        break;
+                    }
+                    self.failures.pop_front();
+                }
+                if self.failures.len() < self.config.max_failures as usize {
+                    return None;
+                }
+                self.failures.clear();
+                self.opened_at = Some(now);
+                Some(BreakerState::Open)
+            }
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(open_for: Duration) -> BreakerConfig {
+        BreakerConfig {
+            max_failures: 3,
+            window: Duration::from_secs(60),
+            open_for,
+        }
+    }
+
+    #[test]
+    fn test_success_resets_consecutive_failures() {
+        let mut breaker = CircuitBreaker::new(config(Duration::from_secs(60)));
+        assert_eq!(breaker.record_failure(), None);
+        assert_eq!(breaker.record_failure(), None);
+        assert_eq!(breaker.record_success(), None);
+        assert_eq!(breaker.record_failure(), None);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        assert_eq!(breaker.record_failure(), None);
+        assert_eq!(breaker.record_failure(), Some(BreakerState::Open));
+        assert!(!breaker.is_available());
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let mut breaker = CircuitBreaker::new(config(Duration::ZERO));
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        // Zero open period: immediately half-open
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(breaker.is_available());
+        assert_eq!(breaker.record_failure(), Some(BreakerState::Open));
+        assert_eq!(breaker.record_success(), Some(BreakerState::Closed));
+    }
+
+    #[test]
+    fn test_half_open_admits_single_probe() {
+        let mut breaker = CircuitBreaker::new(config(Duration::from_millis(50)));
+        assert!(breaker.try_acquire());
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        assert!(!breaker.try_acquire());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+        assert_eq!(breaker.record_success(), Some(BreakerState::Closed));
+        assert!(breaker.try_acquire());
+        assert!(breaker.try_acquire());
+    }
+
+    #[test]
+    fn test_disabled_never_opens() {
+        let mut breaker = CircuitBreaker::new(BreakerConfig {
+            max_failures: 0,
+            ..BreakerConfig::default()
+        });
+        for _ in 0..100 {
+            assert_eq!(breaker.record_failure(), None);
+        }
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+}
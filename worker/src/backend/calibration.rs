@@ -0,0 +1,219 @@
+//! Classification score calibration
+//!
+//! Turns raw backend signals (embedding similarities, token logprobs) into
+//! scores that form a probability distribution over the requested label set.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{ClassificationPrediction, ScoreCalibration};
+
+/// Temperature applied to cosine similarities before the softmax.
+///
+/// Cosine similarities live in [-1, 1]; without scaling the softmax is
+/// nearly uniform.
+const SIMILARITY_TEMPERATURE: f32 = 0.05;
+
+// ─────────────────────────────────────────────────────────────────
+// Strategy
+// ─────────────────────────────────────────────────────────────────
+
+/// How a backend produces classification scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassificationStrategy {
+    /// Prompt the model for a label (scores from token logprobs when calibrated)
+    #[default]
+    Generative,
+    /// Compare text and label embeddings (scores from softmax when calibrated)
+    Embeddings,
+}
+
+impl ClassificationStrategy {
+    /// Parse from config string
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "generative" => Some(ClassificationStrategy::Generative),
+            "embeddings" => Some(ClassificationStrategy::Embeddings),
+            _ => None,
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Calibration helpers
+// ─────────────────────────────────────────────────────────────────
+
+/// Numerically stable softmax
+pub fn softmax(logits: &[f32]) -> Vec<f32> {
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = logits.iter().map(|l| (l - max).exp()).collect();
+    let sum: f32 = exps.iter().sum();
+    if sum <= 0.0 || !sum.is_finite() {
+        return vec![1.0 / logits.len().max(1) as f32; logits.len()];
+    }
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+/// Cosine similarity between two vectors (0.0 for degenerate inputs)
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Score labels from text/label cosine similarities.
+///
+/// Calibrated scores are a softmax over the similarities; raw scores are the
+/// similarities clamped to [0, 1].
+pub fn score_similarities(
+    labels: &[String],
+    similarities: &[f32],
+    calibrate: bool,
+) -> (Vec<ClassificationPrediction>, ScoreCalibration) {
+    let (scores, calibration) = if calibrate {
+        let scaled: Vec<f32> = similarities
+            .iter()
+            .map(|s| s / SIMILARITY_TEMPERATURE)
+            .collect();
+        (softmax(&scaled), ScoreCalibration::Softmax)
+    } else {
+        (
+            similarities.iter().map(|s| s.clamp(0.0, 1.0)).collect(),
+            ScoreCalibration::Raw,
+        )
+    };
+    (rank(labels, &scores), calibration)
+}
+
+/// Score labels from the logprobs of the first generated token.
+///
+/// Each candidate token's probability mass is credited to the labels it is a
+/// (case-insensitive) prefix of, then renormalized over the label set.
+/// Returns `None` when no candidate matches any label.
+pub fn score_logprobs(
+    labels: &[String],
+    candidates: &[(String, f32)],
+) -> Option<Vec<ClassificationPrediction>> {
+    let lowered: Vec<String> = labels.iter().map(|l| l.to_lowercase()).collect();
+    let mut mass = vec![0.0f32; labels.len()];
+
+    for (token, logprob) in candidates {
+        let token = token.trim().to_lowercase();
+        if token.is_empty() {
+            continue;
+        }
+        let matches: Vec<usize> = lowered
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.starts_with(&token))
+            .map(|(i, _)| i)
+            .collect();
+        if matches.is_empty() {
+            continue;
+        }
+        let share = logprob.exp() / matches.len() as f32;
+        for i in matches {
+            mass[i] += share;
+        }
+    }
+
+    let total: f32 = mass.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let scores: Vec<f32> = mass.iter().map(|m| m / total).collect();
+    Some(rank(labels, &scores))
+}
+
+/// Score a generated answer by matching it against the label set: the
+/// matched label gets 1.0 and the rest 0.0.
+pub fn score_generated_label(labels: &[String], generated: &str) -> Vec<ClassificationPrediction> {
+    let answer = generated.trim().to_lowercase();
+    let chosen = labels
+        .iter()
+        .position(|l| answer == l.to_lowercase())
+        .or_else(|| labels.iter().position(|l| answer.contains(&l.to_lowercase())));
+    let scores: Vec<f32> = (0..labels.len())
+        .map(|i| if Some(i) == chosen { 1.0 } else { 0.0 })
+        .collect();
+    rank(labels, &scores)
+}
+
+/// Pair labels with scores, sorted by score (highest first)
+fn rank(labels: &[String], scores: &[f32]) -> Vec<ClassificationPrediction> {
+    let mut predictions: Vec<ClassificationPrediction> = labels
+        .iter()
+        .zip(scores)
+        .map(|(label, &score)| ClassificationPrediction {
+            label: label.clone(),
+            score,
+        })
+        .collect();
+    predictions.sort_by(|a, b| b.score.total_cmp(&a.score));
+    predictions
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels() -> Vec<String> {
+        vec!["positive".to_string(), "negative".to_string(), "neutral".to_string()]
+    }
+
+    #[test]
+    fn test_logprob_calibration_sums_to_one_and_ranks() {
+        // Synthetic top_logprobs for the first token: "neg" is most likely
+        let candidates = vec![
+            ("neg".to_string(), (0.6f32).ln()),
+            (" positive".to_string(), (0.25f32).ln()),
+            ("Neutral".to_string(), (0.1f32).ln()),
+            ("maybe".to_string(), (0.05f32).ln()),
+        ];
+
+        let predictions = score_logprobs(&labels(), &candidates).unwrap();
+        let total: f32 = predictions.iter().map(|p| p.score).sum();
+
+        assert!((total - 1.0).abs() < 1e-5);
+        assert_eq!(predictions[0].label, "negative");
+        assert_eq!(predictions[1].label, "positive");
+        assert_eq!(predictions[2].label, "neutral");
+        assert!((predictions[0].score - 0.6 / 0.95).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_logprob_calibration_no_match() {
+        let candidates = vec![("banana".to_string(), -0.1)];
+        assert!(score_logprobs(&labels(), &candidates).is_none());
+    }
+
+    #[test]
+    fn test_similarity_calibration() {
+        let (calibrated, kind) = score_similarities(&labels(), &[0.2, 0.8, 0.5], true);
+        let total: f32 = calibrated.iter().map(|p| p.score).sum();
+        assert_eq!(kind, ScoreCalibration::Softmax);
+        assert!((total - 1.0).abs() < 1e-5);
+        assert_eq!(calibrated[0].label, "negative");
+
+        let (raw, kind) = score_similarities(&labels(), &[-0.2, 0.8, 0.5], false);
+        assert_eq!(kind, ScoreCalibration::Raw);
+        assert_eq!(raw[0].score, 0.8);
+        assert_eq!(raw[2].score, 0.0);
+    }
+
+    #[test]
+    fn test_generated_label_match() {
+        let predictions = score_generated_label(&labels(), "  Neutral.\n");
+        assert_eq!(predictions[0].label, "neutral");
+        assert_eq!(predictions[0].score, 1.0);
+    }
+}
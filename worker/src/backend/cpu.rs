@@ -10,6 +10,7 @@ use std::path::Path;
 use std::time::Instant;
 
 use crate::error::{Error, Result};
+use crate::system::CpuTopology;
 use crate::types::{
     EmbeddingsInput, EmbeddingsOutput,
     FinishReason, GgufMetadata, LoadedModelInfo, ModelFormat, ModelSpec,
@@ -46,6 +47,16 @@ pub struct CpuBackendConfig {
 
     /// Random seed for sampling
     pub seed: Option<u64>,
+
+    /// Run a throwaway generation after loading a model
+    pub warmup_after_load: bool,
+
+    /// Detect model file format from content rather than the extension
+    pub detect_model_format: bool,
+
+    /// When auto-detecting threads, use one per physical core (skipping
+    /// hyperthreads)
+    pub use_physical_cores_only: bool,
 }
 
 impl Default for CpuBackendConfig {
@@ -57,6 +68,9 @@ impl Default for CpuBackendConfig {
             use_mmap: true,
             use_mlock: false,
             seed: None,
+            warmup_after_load: false,
+            detect_model_format: true,
+            use_physical_cores_only: false,
         }
     }
 }
@@ -70,6 +84,9 @@ impl From<BackendConfig> for CpuBackendConfig {
             use_mmap: config.use_mmap,
             use_mlock: config.use_mlock,
             seed: config.seed,
+            warmup_after_load: config.warmup_after_load,
+            detect_model_format: config.detect_model_format,
+            use_physical_cores_only: config.use_physical_cores_only,
         }
     }
 }
@@ -123,14 +140,23 @@ impl CpuBackend {
 
     /// Create a new CPU backend with custom configuration
     pub fn with_config(config: CpuBackendConfig) -> Self {
+        Self::with_topology(config, CpuTopology::detect())
+    }
+
+    /// Create a CPU backend sizing auto-detected threads from `topology`
+    pub fn with_topology(config: CpuBackendConfig, topology: CpuTopology) -> Self {
         let actual_threads = if config.num_threads == 0 {
-            num_cpus::get() as u32
+            topology.inference_threads(config.use_physical_cores_only)
         } else {
             config.num_threads
         };
 
         tracing::info!(
             threads = actual_threads,
+            physical_cores = topology.physical_cores,
+            logical_cores = topology.logical_cores,
+            smt = topology.has_smt(),
+            physical_only = config.use_physical_cores_only,
             context_size = config.context_size,
             "Initializing CPU backend"
         );
@@ -147,20 +173,12 @@ impl CpuBackend {
         Self::with_config(config.into())
     }
 
-    /// Get the number of threads being used
-    pub fn num_threads(&self) -> u32 {
-        self.actual_threads
-    }
-
-    /// Parse GGUF metadata from file
-    fn parse_gguf_metadata(&self, _path: &Path) -> GgufMetadata {
-        // TODO: Implement actual GGUF parsing
-        // For now, return defaults
-        GgufMetadata {
-            architecture: Some("llama".to_string()),
-            context_length: Some(self.config.context_size),
-            ..Default::default()
-        }
+    /// Parse GGUF metadata from the file header, taking the context length
+    /// from config if the model doesn't declare one
+    fn parse_gguf_metadata(&self, path: &Path) -> Result<GgufMetadata> {
+        let mut metadata = GgufMetadata::read(path)?;
+        metadata.context_length.get_or_insert(self.config.context_size);
+        Ok(metadata)
     }
 
     /// Estimate model size in MB
@@ -169,6 +187,15 @@ impl CpuBackend {
             .map(|m| m.len() / (1024 * 1024))
             .unwrap_or(0)
     }
+
+    /// Format of the model file at `path`, by content or by extension
+    fn model_format(&self, path: &Path) -> Result<ModelFormat> {
+        if self.config.detect_model_format {
+            ModelFormat::detect(path)
+        } else {
+            Ok(ModelFormat::from_path(path).unwrap_or(ModelFormat::Gguf))
+        }
+    }
 }
 
 impl Default for CpuBackend {
@@ -198,6 +225,7 @@ impl InferenceBackend for CpuBackend {
             max_batch_size: self.config.batch_size,
             gpu_available: false,
             gpu_device: None,
+            gpu_memory_mb: None,
         }
     }
 
@@ -209,6 +237,7 @@ impl InferenceBackend for CpuBackend {
             memory_used_mb: state.memory_used_mb,
             gpu_memory_used_mb: None,
             error: Some("llama feature not enabled - stub implementation".to_string()),
+            breaker: None,
         })
     }
 
@@ -246,11 +275,16 @@ impl InferenceBackend for CpuBackend {
             });
         }
 
-        let metadata = self.parse_gguf_metadata(&spec.path);
+        let metadata = self.parse_gguf_metadata(&spec.path)?;
         let memory_used_mb = self.estimate_model_size(&spec.path);
 
+        let mut spec = spec.clone();
+        if spec.family.is_none() {
+            spec.family = metadata.model_family();
+        }
+
         let info = LoadedModelInfo {
-            spec: spec.clone(),
+            spec,
             metadata,
             memory_used_mb,
             load_time_ms: start.elapsed().as_millis() as u64,
@@ -282,7 +316,7 @@ impl InferenceBackend for CpuBackend {
                 .to_string(),
             family: None,
             path: path.to_path_buf(),
-            format: ModelFormat::from_path(path).unwrap_or(ModelFormat::Gguf),
+            format: self.model_format(path)?,
             quantization: None,
             parameters_b: None,
             context_length: self.config.context_size,
@@ -292,6 +326,7 @@ impl InferenceBackend for CpuBackend {
             num_heads: None,
             file_size: path.metadata().map(|m| m.len()).unwrap_or(0),
             sha256: None,
+            source_url: None,
         };
 
         self.load_model(&spec).await
@@ -314,6 +349,10 @@ impl InferenceBackend for CpuBackend {
         self.state.read().loaded_model.is_some()
     }
 
+    fn loaded_model_id(&self) -> Option<String> {
+        self.state.read().loaded_model.as_ref().map(|m| m.spec.id.clone())
+    }
+
     async fn text_completion(
         &self,
         input: TextCompletionInput,
@@ -356,9 +395,14 @@ impl InferenceBackend for CpuBackend {
             max_batch_size: self.config.batch_size,
             gpu_available: false,
             gpu_device: None,
+            gpu_memory_mb: None,
         }
     }
 
+    fn warms_up_after_load(&self) -> bool {
+        self.config.warmup_after_load
+    }
+
     async fn health_check(&self) -> Result<BackendHealth> {
         let state = self.state.read();
         Ok(BackendHealth {
@@ -367,6 +411,7 @@ impl InferenceBackend for CpuBackend {
             memory_used_mb: state.memory_used_mb,
             gpu_memory_used_mb: None,
             error: None,
+            breaker: None,
         })
     }
 
@@ -419,21 +464,28 @@ impl InferenceBackend for CpuBackend {
                 message: format!("Failed to create context: {}", e),
             })?;
 
-        let metadata = self.parse_gguf_metadata(&spec.path);
+        let metadata = self.parse_gguf_metadata(&spec.path)?;
         let memory_used_mb = self.estimate_model_size(&spec.path);
 
+        let mut spec = spec.clone();
+        if spec.family.is_none() {
+            spec.family = metadata.model_family();
+        }
+
         let info = LoadedModelInfo {
-            spec: spec.clone(),
+            spec,
             metadata,
             memory_used_mb,
             load_time_ms: start.elapsed().as_millis() as u64,
             ready: true,
         };
 
-        let mut state = self.state.write();
-        state.llama_context = Some(ctx);
-        state.loaded_model = Some(info.clone());
-        state.memory_used_mb = memory_used_mb;
+        {
+            let mut state = self.state.write();
+            state.llama_context = Some(ctx);
+            state.loaded_model = Some(info.clone());
+            state.memory_used_mb = memory_used_mb;
+        }
 
         tracing::info!(
             model_id = %spec.id,
@@ -442,6 +494,10 @@ impl InferenceBackend for CpuBackend {
             "Model loaded successfully"
         );
 
+        if self.config.warmup_after_load {
+            super::warmup(self).await;
+        }
+
         Ok(info)
     }
 
@@ -463,7 +519,7 @@ impl InferenceBackend for CpuBackend {
                 .to_string(),
             family: None,
             path: path.to_path_buf(),
-            format: ModelFormat::from_path(path).unwrap_or(ModelFormat::Gguf),
+            format: self.model_format(path)?,
             quantization: None,
             parameters_b: None,
             context_length: self.config.context_size,
@@ -473,6 +529,7 @@ impl InferenceBackend for CpuBackend {
             num_heads: None,
             file_size: path.metadata().map(|m| m.len()).unwrap_or(0),
             sha256: None,
+            source_url: None,
         };
 
         self.load_model(&spec).await
@@ -495,6 +552,10 @@ impl InferenceBackend for CpuBackend {
         self.state.read().loaded_model.is_some()
     }
 
+    fn loaded_model_id(&self) -> Option<String> {
+        self.state.read().loaded_model.as_ref().map(|m| m.spec.id.clone())
+    }
+
     async fn text_completion(
         &self,
         input: TextCompletionInput,
@@ -625,7 +686,7 @@ mod tests {
     fn test_cpu_backend_creation() {
         let backend = CpuBackend::new();
         assert_eq!(backend.name(), "cpu");
-        assert!(backend.num_threads() > 0);
+        assert!(backend.actual_threads > 0);
     }
 
     #[test]
@@ -650,6 +711,9 @@ mod tests {
             use_mlock: true,
             seed: Some(42),
             openai: None,
+            warmup_after_load: true,
+            detect_model_format: false,
+            use_physical_cores_only: true,
         };
 
         let cpu_config: CpuBackendConfig = config.into();
@@ -658,6 +722,24 @@ mod tests {
         assert_eq!(cpu_config.context_size, 8192);
         assert!(!cpu_config.use_mmap);
         assert!(cpu_config.use_mlock);
+        assert!(cpu_config.warmup_after_load);
+        assert!(!cpu_config.detect_model_format);
+        assert!(cpu_config.use_physical_cores_only);
+    }
+
+    #[test]
+    fn test_threads_sized_from_topology() {
+        let topology = CpuTopology { physical_cores: 6, logical_cores: 12 };
+
+        let physical = CpuBackendConfig { use_physical_cores_only: true, ..Default::default() };
+        assert_eq!(CpuBackend::with_topology(physical.clone(), topology).actual_threads, 6);
+
+        let logical = CpuBackendConfig::default();
+        assert_eq!(CpuBackend::with_topology(logical, topology).actual_threads, 12);
+
+        // An explicit thread count wins over topology
+        let explicit = CpuBackendConfig { num_threads: 3, ..physical };
+        assert_eq!(CpuBackend::with_topology(explicit, topology).actual_threads, 3);
     }
 
     #[tokio::test]
@@ -669,6 +751,56 @@ mod tests {
         assert!(!health.model_loaded);
     }
 
+    #[cfg(not(feature = "llama"))]
+    #[tokio::test]
+    async fn test_load_populates_family() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiny.gguf");
+        std::fs::copy(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tiny_header.gguf"),
+            &path,
+        )
+        .unwrap();
+
+        let mut backend = CpuBackend::new();
+        let info = backend.load_model_from_path(&path).await.unwrap();
+        assert_eq!(info.spec.family, Some(crate::types::ModelFamily::Llama));
+        assert_eq!(info.metadata.block_count, Some(22));
+        assert_eq!(info.metadata.context_length, Some(2048));
+
+        // A bare magic with no header is corrupt
+        std::fs::write(&path, b"GGUF").unwrap();
+        let err = backend.load_model_from_path(&path).await.unwrap_err();
+        assert!(matches!(err, Error::ModelCorrupted { .. }));
+    }
+
+    #[cfg(not(feature = "llama"))]
+    #[tokio::test]
+    async fn test_load_detects_format_from_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let header = br#"{"__metadata__":{}}"#;
+        let mut safetensors = (header.len() as u64).to_le_bytes().to_vec();
+        safetensors.extend_from_slice(header);
+        let path = dir.path().join("mislabeled.gguf");
+        std::fs::write(&path, &safetensors).unwrap();
+
+        let mut backend = CpuBackend::new();
+        let err = backend.load_model_from_path(&path).await.unwrap_err();
+        match err {
+            Error::ModelIncompatible { reason, .. } => assert!(reason.contains("SafeTensors")),
+            other => panic!("Expected ModelIncompatible, got {:?}", other),
+        }
+
+        // Extension-only detection trusts the file name, leaving the bad
+        // header to be caught when the metadata is read
+        let mut backend = CpuBackend::with_config(CpuBackendConfig {
+            detect_model_format: false,
+            ..Default::default()
+        });
+        let err = backend.load_model_from_path(&path).await.unwrap_err();
+        assert!(matches!(err, Error::ModelCorrupted { .. }));
+    }
+
     #[test]
     fn test_resource_usage() {
         let backend = CpuBackend::new();
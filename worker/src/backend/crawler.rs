@@ -2,27 +2,295 @@
 //!
 //! Implements the WebCrawl task type: fetches pages via HTTP, extracts clean text
 //! using CSS selectors, follows links up to a configurable BFS depth, and optionally
-//! generates vector embeddings via the configured OpenAI-compatible endpoint.
+//! generates vector embeddings via an OpenAI-compatible endpoint (`crawler.embedding_*`,
+//! falling back to `[openai]`).
 
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
 use sha2::{Digest, Sha256};
 use tracing::{debug, info, warn};
 use url::Url;
 
+use crate::backend::{OpenAiBackend, OpenAiConfig};
 use crate::backend::traits::{
     BackendCapabilities, BackendHealth, InferenceBackend, ResourceUsage,
 };
 use crate::config::{CrawlerSettings, OpenAiSettings};
 use crate::error::{Error, Result};
+use crate::jitter::jitter;
+use crate::sandbox::{self, EgressPolicy};
 use crate::types::{
-    CrawledPage, LoadedModelInfo, ModelSpec, TaskType, TextCompletionInput,
-    TextCompletionOutput, WebCrawlInput, WebCrawlOutput,
+    CrawlError, CrawlErrorKind, CrawlSortBy, CrawledPage, EmbeddingsInput, LoadedModelInfo,
+    ModelSpec, TaskType,
+    TextCompletionInput, TextCompletionOutput, WebCrawlInput, WebCrawlOutput,
 };
 
+/// Largest page body the crawler will parse
+const MAX_PAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Redirect hops followed for one fetch
+const MAX_REDIRECTS: usize = 10;
+
+/// Largest robots.txt read; a bigger one is treated as missing
+const MAX_ROBOTS_BYTES: usize = 512 * 1024;
+
+// ─────────────────────────────────────────────────────────────────
+// Domain denylist
+// ─────────────────────────────────────────────────────────────────
+
+/// Operator-controlled set of domains no crawl may fetch.
+///
+/// Cloning shares the list, so a handle kept by the caller can swap in a
+/// reloaded list while crawls are running.
+#[derive(Debug, Clone, Default)]
+pub struct DomainDenylist(Arc<RwLock<Vec<String>>>);
+
+impl DomainDenylist {
+    /// Create a denylist from config entries
+    pub fn new(domains: &[String]) -> Self {
+        let list = Self::default();
+        list.replace(domains);
+        list
+    }
+
+    /// Replace the denied domains
+    pub fn replace(&self, domains: &[String]) {
+        *self.0.write() = domains
+            .iter()
+            .map(|d| d.trim().trim_start_matches("*.").trim_matches('.').to_lowercase())
+            .filter(|d| !d.is_empty())
+            .collect();
+    }
+
+    /// Whether `url`'s host is a denied domain or a subdomain of one
+    pub fn is_denied(&self, url: &str) -> bool {
+        let Some(host) = Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_lowercase))
+        else {
+            return false;
+        };
+        let host = host.trim_end_matches('.');
+        self.0
+            .read()
+            .iter()
+            .any(|d| host == d || host.ends_with(&format!(".{}", d)))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Per-host rate limiting
+// ─────────────────────────────────────────────────────────────────
+
+/// Hosts tracked before finished slots are pruned
+const RATE_LIMIT_PRUNE_AT: usize = 256;
+
+/// Longest robots.txt `Crawl-delay` honored
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(30);
+
+/// Per-host politeness limit: a token bucket holding one token, refilled
+/// every `interval` (or the host's robots.txt `Crawl-delay`, if longer) plus
+/// a random jitter of up to `jitter`.
+///
+/// Cloning shares the buckets, so concurrent crawls to the same host queue
+/// up behind each other while crawls to different hosts run in parallel.
+#[derive(Debug, Clone)]
+pub struct HostRateLimiter {
+    interval: Duration,
+    jitter: Duration,
+    buckets: Arc<Mutex<HashMap<String, HostBucket>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct HostBucket {
+    /// When the host's next token is available
+    next: Instant,
+    /// Refill interval the host asked for in robots.txt
+    crawl_delay: Duration,
+}
+
+impl HostRateLimiter {
+    /// Space requests to a host at least `interval` apart, plus up to
+    /// `jitter` so that crawls started together drift apart
+    pub fn new(interval: Duration, jitter: Duration) -> Self {
+        Self { interval, jitter, buckets: Arc::default() }
+    }
+
+    /// Wait for `host`'s next token
+    pub async fn acquire(&self, host: &str) {
+        let wait = self.reserve(host, Instant::now());
+        if !wait.is_zero() {
+            debug!(host = %host, wait_ms = wait.as_millis() as u64, "Rate limited");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Space requests to `host` at least `delay` apart (robots.txt
+    /// `Crawl-delay`, capped at [`MAX_CRAWL_DELAY`])
+    pub fn set_crawl_delay(&self, host: &str, delay: Duration) {
+        let now = Instant::now();
+        self.buckets
+            .lock()
+            .entry(host_key(host))
+            .or_insert(HostBucket { next: now, crawl_delay: Duration::ZERO })
+            .crawl_delay = delay.min(MAX_CRAWL_DELAY);
+    }
+
+    /// Claim `host`'s next token, returning how long to wait for it from `now`
+    fn reserve(&self, host: &str, now: Instant) -> Duration {
+        let mut buckets = self.buckets.lock();
+        if buckets.len() >= RATE_LIMIT_PRUNE_AT {
+            buckets.retain(|_, b| b.next > now);
+        }
+        let bucket = buckets
+            .entry(host_key(host))
+            .or_insert(HostBucket { next: now, crawl_delay: Duration::ZERO });
+        let refill = self.interval.max(bucket.crawl_delay);
+        if refill.is_zero() {
+            return Duration::ZERO;
+        }
+        let slot = bucket.next.max(now);
+        bucket.next = slot + refill + self.sample_jitter();
+        slot - now
+    }
+
+    fn sample_jitter(&self) -> Duration {
+        jitter(self.jitter)
+    }
+}
+
+fn host_key(host: &str) -> String {
+    host.trim_end_matches('.').to_lowercase()
+}
+
+// ─────────────────────────────────────────────────────────────────
+// robots.txt
+// ─────────────────────────────────────────────────────────────────
+
+/// The robots.txt rules that apply to our user agent
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RobotsRules {
+    /// `(path pattern, allow)` in file order
+    rules: Vec<(String, bool)>,
+    crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    /// Parse robots.txt for `agent`, our User-Agent product token.
+    ///
+    /// Groups naming `agent` (case-insensitively) take precedence over `*`
+    /// groups; several matching groups are combined.
+    fn parse(text: &str, agent: &str) -> Self {
+        let mut named = Self::default();
+        let mut wildcard = Self::default();
+        let mut saw_named = false;
+        // Whether the group being read applies to us or to `*`
+        let (mut group_named, mut group_wildcard) = (false, false);
+        // A User-agent line after a rule starts a new group
+        let mut in_rules = false;
+
+        for raw in text.lines() {
+            let line = raw.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+            match key.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        (group_named, group_wildcard, in_rules) = (false, false, false);
+                    }
+                    let token = value.split('/').next().unwrap_or_default().trim();
+                    if token == "*" {
+                        group_wildcard = true;
+                    } else if token.eq_ignore_ascii_case(agent) {
+                        group_named = true;
+                        saw_named = true;
+                    }
+                }
+                "allow" | "disallow" | "crawl-delay" => {
+                    in_rules = true;
+                    if group_named {
+                        named.add(&key, value);
+                    }
+                    if group_wildcard {
+                        wildcard.add(&key, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if saw_named { named } else { wildcard }
+    }
+
+    fn add(&mut self, key: &str, value: &str) {
+        match key {
+            "crawl-delay" => {
+                if let Ok(secs) = value.parse::<f64>() {
+                    if secs.is_finite() && secs >= 0.0 {
+                        self.crawl_delay = Some(Duration::from_secs_f64(secs.min(86_400.0)));
+                    }
+                }
+            }
+            // An empty Disallow allows everything, so it adds no rule
+            _ if value.is_empty() => {}
+            _ => self.rules.push((value.to_string(), key == "allow")),
+        }
+    }
+
+    /// Whether `path` (with any query string) may be fetched: the longest
+    /// matching pattern decides, and `Allow` wins a tie
+    fn is_allowed(&self, path: &str) -> bool {
+        let mut best: Option<(usize, bool)> = None;
+        for (pattern, allow) in &self.rules {
+            if !robots_pattern_matches(pattern, path) {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some((len, best_allow)) => {
+                    pattern.len() > len || (pattern.len() == len && *allow && !best_allow)
+                }
+            };
+            if better {
+                best = Some((pattern.len(), *allow));
+            }
+        }
+        best.map(|(_, allow)| allow).unwrap_or(true)
+    }
+}
+
+/// Match a robots.txt path pattern: a prefix match, where `*` matches any
+/// run of characters and a trailing `$` anchors the end of the path
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    if parts.is_empty() {
+        return !anchored || rest.is_empty();
+    }
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
 // ─────────────────────────────────────────────────────────────────
 // CrawlerBackend
 // ─────────────────────────────────────────────────────────────────
@@ -30,16 +298,17 @@ use crate::types::{
 /// Backend that handles WEB_CRAWL tasks.
 ///
 /// Uses `reqwest` for HTTP and `scraper` for HTML parsing.
-/// Embeddings (optional) are generated by calling the OpenAI-compatible
-/// `/embeddings` endpoint directly — no circular registry dependency.
+/// Embeddings (optional) are generated by the crawler's own OpenAI-compatible
+/// backend instance — no circular registry dependency, and the embedding
+/// model can differ from the one serving inference.
 pub struct CrawlerBackend {
     http_client: reqwest::Client,
-    openai_base_url: String,
-    openai_api_key: String,
-    embed_model: String,
-    rate_limit_ms: u64,
+    embedder: Option<OpenAiBackend>,
+    rate_limiter: HostRateLimiter,
     respect_robots: bool,
     user_agent: String,
+    denylist: DomainDenylist,
+    egress: EgressPolicy,
 }
 
 impl CrawlerBackend {
@@ -51,40 +320,117 @@ impl CrawlerBackend {
             crawler.user_agent.clone()
         };
 
-        let http_client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(20))
-            .user_agent(&user_agent)
-            .build()
-            .unwrap_or_default();
-
+        let egress = EgressPolicy::default();
         Self {
-            http_client,
-            openai_base_url: openai.base_url.clone(),
-            openai_api_key: openai.api_key.clone(),
-            embed_model: openai.default_model.clone(),
-            rate_limit_ms: crawler.rate_limit_ms,
+            http_client: crawl_client(&user_agent, &egress),
+            embedder: page_embedder(crawler, openai),
+            rate_limiter: HostRateLimiter::new(
+                Duration::from_millis(crawler.rate_limit_ms),
+                Duration::from_millis(crawler.rate_limit_jitter_ms),
+            ),
             respect_robots: crawler.respect_robots,
             user_agent,
+            denylist: DomainDenylist::new(&crawler.domain_denylist),
+            egress,
         }
     }
 
+    /// Restrict the hosts crawls may contact to the sandbox egress allowlist
+    pub fn with_egress(mut self, egress: EgressPolicy) -> Self {
+        self.http_client = crawl_client(&self.user_agent, &egress);
+        self.egress = egress;
+        self
+    }
+
+    /// Use a shared denylist (e.g. one reloaded at runtime) instead of the
+    /// one built from config
+    pub fn with_denylist(mut self, denylist: DomainDenylist) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    /// Share per-host rate limits with other crawlers (e.g. the background
+    /// crawler) instead of keeping separate ones
+    pub fn with_rate_limiter(mut self, rate_limiter: HostRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
     // ── Internal helpers ──────────────────────────────────────────
 
-    /// Fetch a URL, parse HTML, return (title, body_text, outbound_links).
-    async fn fetch_page(&self, url: &str) -> Result<(Option<String>, String, Vec<String>)> {
-        let resp = self
-            .http_client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| Error::Execution(format!("HTTP fetch failed for {}: {}", url, e)))?;
+    /// GET a URL, following redirects by hand so every hop passes the
+    /// denylist, egress and `allowed_domains` checks the URL itself did.
+    /// Returns the final URL with its response.
+    async fn get_following(
+        &self,
+        url: &str,
+        allowed_domains: &[String],
+        timeout: Option<Duration>,
+    ) -> std::result::Result<(Url, reqwest::Response), CrawlError> {
+        let mut current = Url::parse(url)
+            .map_err(|e| CrawlError::new(url, CrawlErrorKind::ParseFailed, format!("Bad URL: {}", e)))?;
+        for _ in 0..=MAX_REDIRECTS {
+            let mut request = self.http_client.get(current.clone());
+            if let Some(timeout) = timeout {
+                request = request.timeout(timeout);
+            }
+            let resp = request
+                .send()
+                .await
+                .map_err(|e| request_error(url, "HTTP fetch failed", e))?;
+
+            let location = resp
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok());
+            let Some(location) = location.filter(|_| resp.status().is_redirection()) else {
+                return Ok((current, resp));
+            };
+            let next = current.join(location).map_err(|e| {
+                CrawlError::new(url, CrawlErrorKind::ParseFailed, format!("Bad redirect: {}", e))
+            })?;
+
+            if self.denylist.is_denied(next.as_str()) {
+                return Err(CrawlError::new(
+                    url,
+                    CrawlErrorKind::Denylisted,
+                    format!("redirect to {} is denylisted", next),
+                ));
+            }
+            if let Err(denied) = self.egress.check_url(&next) {
+                return Err(CrawlError::new(url, CrawlErrorKind::EgressBlocked, denied.to_string()));
+            }
+            if !allowed_domains.is_empty() && !domain_allowed(allowed_domains, &next) {
+                return Err(CrawlError::new(
+                    url,
+                    CrawlErrorKind::OffDomain,
+                    format!("redirect to {} leaves allowed_domains", next),
+                ));
+            }
+            debug!(url = %url, to = %next, "Following redirect");
+            current = next;
+        }
+        Err(CrawlError::new(url, CrawlErrorKind::Network, "too many redirects"))
+    }
+
+    /// Fetch a URL and parse its HTML into a [`ParsedPage`].
+    async fn fetch_page(
+        &self,
+        url: &str,
+        allowed_domains: &[String],
+    ) -> std::result::Result<ParsedPage, CrawlError> {
+        let (final_url, resp) = self.get_following(url, allowed_domains, None).await?;
 
         if !resp.status().is_success() {
-            return Err(Error::Execution(format!(
-                "HTTP {} fetching {}",
-                resp.status(),
-                url
-            )));
+            return Err(CrawlError::new(
+                url,
+                CrawlErrorKind::HttpStatus,
+                format!("HTTP {}", resp.status()),
+            ));
+        }
+
+        if let Some(len) = resp.content_length().filter(|len| *len as usize > MAX_PAGE_BYTES) {
+            return Err(too_large(url, len as usize));
         }
 
         // Only parse text/html content
@@ -95,67 +441,39 @@ impl CrawlerBackend {
             .unwrap_or("")
             .to_string();
         if !content_type.contains("html") {
-            return Err(Error::Execution(format!(
-                "Non-HTML content-type '{}' at {}",
-                content_type, url
-            )));
+            return Err(CrawlError::new(
+                url,
+                CrawlErrorKind::ParseFailed,
+                format!("Non-HTML content-type '{}'", content_type),
+            ));
         }
 
-        let html = resp
-            .text()
+        // Chunked responses carry no Content-Length, so the cap is also
+        // enforced while reading
+        let body = read_capped(resp, MAX_PAGE_BYTES)
             .await
-            .map_err(|e| Error::Execution(format!("Failed to read body of {}: {}", url, e)))?;
-
-        let document = scraper::Html::parse_document(&html);
-
-        // Title
-        let title_sel = scraper::Selector::parse("title").expect("valid selector");
-        let title = document
-            .select(&title_sel)
-            .next()
-            .map(|e| e.text().collect::<Vec<_>>().join(""))
-            .map(|t| t.trim().to_string())
-            .filter(|t| !t.is_empty());
-
-        // Body text — target semantic elements, skipping script/style implicitly
-        let text_sel = scraper::Selector::parse(
-            "p, h1, h2, h3, h4, h5, h6, li, article, section, main, \
-             td, th, blockquote, pre, figcaption, dt, dd",
-        )
-        .expect("valid selector");
-        let text: String = document
-            .select(&text_sel)
-            .flat_map(|e| e.text())
-            .map(|t| t.trim())
-            .filter(|t| !t.is_empty())
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        // Outbound links — resolve relative URLs against the page base
-        let base = Url::parse(url).ok();
-        let link_sel = scraper::Selector::parse("a[href]").expect("valid selector");
-        let links: Vec<String> = document
-            .select(&link_sel)
-            .filter_map(|e| e.value().attr("href"))
-            .filter_map(|href| {
-                if let Some(ref b) = base {
-                    b.join(href).ok().map(|u| u.to_string())
-                } else {
-                    Url::parse(href).ok().map(|u| u.to_string())
-                }
-            })
-            .filter(|u| u.starts_with("http://") || u.starts_with("https://"))
-            .collect();
+            .map_err(|e| request_error(url, "Failed to read body", e))?
+            .ok_or_else(|| {
+                CrawlError::new(
+                    url,
+                    CrawlErrorKind::TooLarge,
+                    format!("page exceeds the {} byte limit", MAX_PAGE_BYTES),
+                )
+            })?;
+        let html = decode_body(&body, &content_type);
 
-        Ok((title, text, links))
+        // Relative links resolve against where the page was served from
+        Ok(parse_html(final_url.as_str(), &html, self.respect_robots))
     }
 
     /// Check robots.txt for the URL.  Returns `true` if crawling is allowed.
     ///
-    /// `robots_cache` maps host → list of disallowed path prefixes.
+    /// `robots_cache` maps origin → the rules that apply to us. robots.txt
+    /// is fetched under the host's rate limit, and its `Crawl-delay` is
+    /// handed to the rate limiter once fetched.
     async fn is_robots_allowed(
         &self,
-        robots_cache: &mut HashMap<String, Vec<String>>,
+        robots_cache: &mut HashMap<String, RobotsRules>,
         url: &str,
     ) -> bool {
         if !self.respect_robots {
@@ -165,90 +483,289 @@ impl CrawlerBackend {
             Ok(u) => u,
             Err(_) => return true,
         };
-        let host = match parsed.host_str() {
-            Some(h) => h.to_string(),
-            None => return true,
+        let Some(host) = parsed.host_str() else {
+            return true;
         };
-        let path = parsed.path().to_string();
+        // robots.txt applies per origin (scheme, host and port)
+        let origin = parsed.origin().ascii_serialization();
 
-        if !robots_cache.contains_key(&host) {
-            let robots_url = format!("{}://{}/robots.txt", parsed.scheme(), host);
-            let disallowed = self.fetch_robots_disallowed(&robots_url).await;
-            robots_cache.insert(host.clone(), disallowed);
+        if !robots_cache.contains_key(&origin) {
+            let robots_url = format!("{}/robots.txt", origin);
+            self.rate_limiter.acquire(host).await;
+            let rules = self.fetch_robots_rules(&robots_url).await;
+            if let Some(delay) = rules.crawl_delay {
+                debug!(host = %host, delay_ms = delay.as_millis() as u64, "robots.txt crawl delay");
+                self.rate_limiter.set_crawl_delay(host, delay);
+            }
+            robots_cache.insert(origin.clone(), rules);
         }
 
-        let disallowed = robots_cache.get(&host).unwrap();
-        !disallowed
-            .iter()
-            .any(|prefix| path.starts_with(prefix.as_str()))
+        robots_cache[&origin].is_allowed(&robots_path(&parsed))
     }
 
-    /// Fetch and parse robots.txt, returning a list of Disallow path prefixes
-    /// that apply to `User-agent: *` or `User-agent: AI4All`.
-    async fn fetch_robots_disallowed(&self, robots_url: &str) -> Vec<String> {
-        let resp = match self
-            .http_client
-            .get(robots_url)
-            .timeout(Duration::from_secs(5))
-            .send()
-            .await
-        {
-            Ok(r) if r.status().is_success() => r,
-            _ => return vec![],
+    /// Fetch and parse robots.txt. A missing or unreadable file allows
+    /// everything.
+    async fn fetch_robots_rules(&self, robots_url: &str) -> RobotsRules {
+        let resp = match self.get_following(robots_url, &[], Some(Duration::from_secs(5))).await {
+            Ok((_, r)) if r.status().is_success() => r,
+            _ => return RobotsRules::default(),
         };
-        let text = match resp.text().await {
-            Ok(t) => t,
-            Err(_) => return vec![],
+        let text = match read_capped(resp, MAX_ROBOTS_BYTES).await {
+            Ok(Some(body)) => String::from_utf8_lossy(&body).into_owned(),
+            Ok(None) => {
+                debug!(url = %robots_url, limit = MAX_ROBOTS_BYTES, "robots.txt too large, ignoring it");
+                return RobotsRules::default();
+            }
+            Err(_) => return RobotsRules::default(),
         };
+        // Groups are matched on the product token, e.g. "AI4All" in "AI4All/1.0"
+        let agent = self.user_agent.split('/').next().unwrap_or_default().trim();
+        RobotsRules::parse(&text, agent)
+    }
 
-        let mut in_relevant = false;
-        let mut disallowed = Vec::new();
-        for raw in text.lines() {
-            let line = raw.trim();
-            if line.starts_with("User-agent:") {
-                let agent = line["User-agent:".len()..].trim();
-                in_relevant = agent == "*" || agent.eq_ignore_ascii_case("AI4All");
-            } else if in_relevant && line.starts_with("Disallow:") {
-                let path = line["Disallow:".len()..].trim();
-                if !path.is_empty() {
-                    disallowed.push(path.to_string());
-                }
+    /// Embed page text with the crawl embeddings backend
+    async fn embed_text(&self, text: &str) -> Option<Vec<f32>> {
+        let embedder = self.embedder.as_ref()?;
+        let input = EmbeddingsInput {
+            texts: vec![embedding_snippet(text).to_string()],
+            normalize: false,
+        };
+        match embedder.embeddings(input).await {
+            Ok(output) => output.embeddings.into_iter().next(),
+            Err(e) => {
+                debug!(error = %e, "Page embedding failed");
+                None
             }
         }
-        disallowed
     }
+}
 
-    /// Call the OpenAI-compatible /embeddings endpoint and return the vector.
-    async fn embed_text(&self, text: &str) -> Option<Vec<f32>> {
-        if self.openai_base_url.is_empty() {
-            return None;
+/// The start of `text` that gets embedded: up to 8 000 chars, to stay
+/// within typical token limits
+fn embedding_snippet(text: &str) -> &str {
+    text.char_indices().nth(8000).map_or(text, |(end, _)| &text[..end])
+}
+
+/// Embeddings backend for crawled pages: `crawler.embedding_model` and
+/// `crawler.embedding_base_url` where set, the `[openai]` ones otherwise.
+/// `None` when no endpoint is configured.
+fn page_embedder(crawler: &CrawlerSettings, openai: &OpenAiSettings) -> Option<OpenAiBackend> {
+    page_embedder_config(crawler, openai).map(OpenAiBackend::new)
+}
+
+/// Config for [`page_embedder`]. The `[openai]` API key and extra headers
+/// are only sent to the `[openai]` endpoint's origin, never to a different
+/// `crawler.embedding_base_url`.
+fn page_embedder_config(crawler: &CrawlerSettings, openai: &OpenAiSettings) -> Option<OpenAiConfig> {
+    let or_default = |own: &str, default: &str| {
+        if own.is_empty() { default.to_string() } else { own.to_string() }
+    };
+    let base_url = or_default(&crawler.embedding_base_url, &openai.base_url);
+    if base_url.is_empty() {
+        return None;
+    }
+    let origin = |url: &str| Url::parse(url).ok().map(|u| u.origin());
+    let same_endpoint = origin(&base_url).is_some_and(|o| Some(o) == origin(&openai.base_url));
+    if !same_endpoint && (!openai.api_key.is_empty() || !openai.extra_headers.is_empty()) {
+        debug!(base_url = %base_url, "Not sending [openai] credentials to a different crawl embeddings host");
+    }
+    Some(OpenAiConfig {
+        base_url,
+        api_key: if same_endpoint { openai.api_key.clone() } else { String::new() },
+        default_model: or_default(&crawler.embedding_model, &openai.default_model),
+        timeout_secs: openai.timeout_secs,
+        max_retries: openai.max_retries,
+        embeddings_batch_size: openai.embeddings_batch_size,
+        breaker_max_failures: openai.breaker_max_failures,
+        breaker_open_ms: openai.breaker_open_ms,
+        extra_headers: if same_endpoint {
+            openai.extra_headers.clone().into_iter().collect()
+        } else {
+            Default::default()
+        },
+        ..Default::default()
+    })
+}
+
+/// HTTP client for page and robots.txt fetches, enforcing `egress`.
+/// Redirects are not followed automatically; see
+/// [`CrawlerBackend::get_following`].
+fn crawl_client(user_agent: &str, egress: &EgressPolicy) -> reqwest::Client {
+    let builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(20))
+        .user_agent(user_agent);
+    egress
+        .apply(builder)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap_or_default()
+}
+
+/// `url`'s host is one of `allowed` or a subdomain of one
+fn domain_allowed(allowed: &[String], url: &Url) -> bool {
+    url.host_str()
+        .is_some_and(|host| allowed.iter().any(|d| host == d || host.ends_with(&format!(".{}", d))))
+}
+
+fn request_error(url: &str, context: &str, e: reqwest::Error) -> CrawlError {
+    let kind = if sandbox::is_egress_denied(&e) {
+        CrawlErrorKind::EgressBlocked
+    } else if e.is_timeout() {
+        CrawlErrorKind::Timeout
+    } else if e.is_decode() {
+        CrawlErrorKind::ParseFailed
+    } else {
+        CrawlErrorKind::Network
+    };
+    CrawlError::new(url, kind, format!("{}: {}", context, e))
+}
+
+fn too_large(url: &str, bytes: usize) -> CrawlError {
+    CrawlError::new(
+        url,
+        CrawlErrorKind::TooLarge,
+        format!("page is {} bytes (limit {})", bytes, MAX_PAGE_BYTES),
+    )
+}
+
+/// Read a response body chunk by chunk; `None` as soon as it passes `limit`
+/// bytes, without buffering the rest
+async fn read_capped(mut resp: reqwest::Response, limit: usize) -> reqwest::Result<Option<Vec<u8>>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            return Ok(None);
         }
-        // Truncate to ~8 000 chars to stay within typical token limits
-        let snippet = if text.len() > 8000 { &text[..8000] } else { text };
+        body.extend_from_slice(&chunk);
+    }
+    Ok(Some(body))
+}
+
+/// Decode a body with the charset named in its `content_type`, UTF-8 if
+/// none or an unknown one is given
+fn decode_body(body: &[u8], content_type: &str) -> String {
+    let encoding = content_type
+        .split(';')
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, label)| encoding_rs::Encoding::for_label(label.trim().trim_matches('"').as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(body).0.into_owned()
+}
+
+// ─────────────────────────────────────────────────────────────────
+// HTML extraction
+// ─────────────────────────────────────────────────────────────────
+
+/// Result of parsing a fetched HTML page.
+#[derive(Debug, Default)]
+struct ParsedPage {
+    title: Option<String>,
+    text: String,
+    /// Outbound links eligible for the BFS queue.
+    links: Vec<String>,
+    /// Page carries `<meta name="robots" content="noindex">`.
+    noindex: bool,
+    /// Links dropped because of `rel="nofollow"` or a page-level `nofollow`.
+    nofollow_skipped: usize,
+}
+
+/// Extract title, body text and outbound links from an HTML document.
+///
+/// When `respect_robots` is set, `<meta name="robots">` directives are
+/// honored: `noindex` is reported on the result (the caller decides not to
+/// store the content), and `nofollow` — page-level or per-link via
+/// `rel="nofollow"` — removes the affected links.
+fn parse_html(url: &str, html: &str, respect_robots: bool) -> ParsedPage {
+    let document = scraper::Html::parse_document(html);
 
-        #[derive(serde::Deserialize)]
-        struct EmbedData {
-            embedding: Vec<f32>,
+    // Title
+    let title_sel = scraper::Selector::parse("title").expect("valid selector");
+    let title = document
+        .select(&title_sel)
+        .next()
+        .map(|e| e.text().collect::<Vec<_>>().join(""))
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty());
+
+    // Body text — target semantic elements, skipping script/style implicitly
+    let text_sel = scraper::Selector::parse(
+        "p, h1, h2, h3, h4, h5, h6, li, article, section, main, \
+         td, th, blockquote, pre, figcaption, dt, dd",
+    )
+    .expect("valid selector");
+    let text: String = document
+        .select(&text_sel)
+        .flat_map(|e| e.text())
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // Meta robots directives (`robots` or our own agent name)
+    let (mut noindex, mut page_nofollow) = (false, false);
+    if respect_robots {
+        let meta_sel = scraper::Selector::parse("meta[name][content]").expect("valid selector");
+        for meta in document.select(&meta_sel) {
+            let name = meta.value().attr("name").unwrap_or("");
+            if !name.eq_ignore_ascii_case("robots") && !name.eq_ignore_ascii_case("ai4all") {
+                continue;
+            }
+            for directive in meta.value().attr("content").unwrap_or("").split(',') {
+                match directive.trim().to_ascii_lowercase().as_str() {
+                    "noindex" => noindex = true,
+                    "nofollow" => page_nofollow = true,
+                    "none" => {
+                        noindex = true;
+                        page_nofollow = true;
+                    }
+                    _ => {}
+                }
+            }
         }
-        #[derive(serde::Deserialize)]
-        struct EmbedResponse {
-            data: Vec<EmbedData>,
+    }
+
+    // Outbound links — resolve relative URLs against the page base
+    let base = Url::parse(url).ok();
+    let link_sel = scraper::Selector::parse("a[href]").expect("valid selector");
+    let mut links = Vec::new();
+    let mut nofollow_skipped = 0;
+    for anchor in document.select(&link_sel) {
+        let href = match anchor.value().attr("href") {
+            Some(h) => h,
+            None => continue,
+        };
+        let resolved = match base {
+            Some(ref b) => b.join(href).ok(),
+            None => Url::parse(href).ok(),
         }
+        .map(normalized);
+        let link = match resolved {
+            Some(u) if u.starts_with("http://") || u.starts_with("https://") => u,
+            _ => continue,
+        };
 
-        let resp = self
-            .http_client
-            .post(format!("{}/embeddings", self.openai_base_url))
-            .bearer_auth(&self.openai_api_key)
-            .json(&serde_json::json!({
-                "model": self.embed_model,
-                "input": snippet,
-            }))
-            .send()
-            .await
-            .ok()?;
+        let rel_nofollow = anchor
+            .value()
+            .attr("rel")
+            .map(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("nofollow"))
+            })
+            .unwrap_or(false);
+        if respect_robots && (page_nofollow || rel_nofollow) {
+            nofollow_skipped += 1;
+            continue;
+        }
+        links.push(link);
+    }
 
-        let result: EmbedResponse = resp.json().await.ok()?;
-        result.data.into_iter().next().map(|d| d.embedding)
+    ParsedPage {
+        title,
+        text,
+        links,
+        noindex,
+        nofollow_skipped,
     }
 }
 
@@ -272,6 +789,7 @@ impl InferenceBackend for CrawlerBackend {
             max_batch_size: 1,
             gpu_available: false,
             gpu_device: None,
+            gpu_memory_mb: None,
         }
     }
 
@@ -316,102 +834,152 @@ impl InferenceBackend for CrawlerBackend {
         let max_pages = input.max_pages.max(1) as usize;
 
         let mut pages: Vec<CrawledPage> = Vec::new();
-        let mut errors: Vec<String> = Vec::new();
+        let mut errors: Vec<CrawlError> = Vec::new();
+        // URLs queued or fetched, normalized so each is fetched once
         let mut visited: HashSet<String> = HashSet::new();
-        let mut robots_cache: HashMap<String, Vec<String>> = HashMap::new();
-        let mut rate_tracker: HashMap<String, Instant> = HashMap::new();
+        // Content hashes of collected pages, so mirrors are stored once
+        let mut seen_hashes: HashSet<String> = HashSet::new();
+        let mut duplicates: u32 = 0;
+        let mut robots_cache: HashMap<String, RobotsRules> = HashMap::new();
         // BFS queue: (url, depth)
         let mut queue: VecDeque<(String, u32)> = VecDeque::new();
-        queue.push_back((input.url.clone(), 0));
+        let seed = Url::parse(&input.url).map(normalized).unwrap_or_else(|_| input.url.clone());
+        visited.insert(seed.clone());
+        queue.push_back((seed, 0));
 
         while let Some((url, depth)) = queue.pop_front() {
             if pages.len() >= max_pages {
                 break;
             }
-            if visited.contains(&url) {
+
+            // Operator denylist overrides the task's allowed_domains and is
+            // checked before anything (robots.txt included) is fetched
+            if self.denylist.is_denied(&url) {
+                debug!(url = %url, "Skipped: domain denylisted");
+                errors.push(CrawlError::new(
+                    &url,
+                    CrawlErrorKind::Denylisted,
+                    "domain is denylisted",
+                ));
+                continue;
+            }
+
+            // Sandbox egress allowlist; redirects and resolved addresses are
+            // checked again by the HTTP client
+            if let Some(denied) = Url::parse(&url).ok().and_then(|u| self.egress.check_url(&u).err()) {
+                warn!(url = %url, "Blocked: host not on sandbox egress allowlist");
+                errors.push(CrawlError::new(&url, CrawlErrorKind::EgressBlocked, denied.to_string()));
                 continue;
             }
-            visited.insert(url.clone());
 
-            // robots.txt
+            // robots.txt, checked as each URL comes up so only hosts the
+            // crawl actually reaches have theirs fetched
             if !self.is_robots_allowed(&mut robots_cache, &url).await {
                 debug!(url = %url, "Skipped: disallowed by robots.txt");
+                errors.push(robots_blocked(&url));
                 continue;
             }
 
-            // Per-domain rate limiting
-            if let Ok(parsed) = Url::parse(&url) {
-                if let Some(host) = parsed.host_str().map(|h| h.to_string()) {
-                    if let Some(last) = rate_tracker.get(&host).copied() {
-                        let elapsed_ms = last.elapsed().as_millis() as u64;
-                        if elapsed_ms < self.rate_limit_ms {
-                            let wait = self.rate_limit_ms - elapsed_ms;
-                            tokio::time::sleep(Duration::from_millis(wait)).await;
-                        }
-                    }
-                    rate_tracker.insert(host, Instant::now());
-                }
+            // Per-host rate limiting, shared with concurrent crawls
+            if let Some(host) = Url::parse(&url).ok().and_then(|u| u.host_str().map(str::to_string)) {
+                self.rate_limiter.acquire(&host).await;
             }
 
             info!(url = %url, depth = depth, "Crawling page");
 
-            let (title, text, raw_links) = match self.fetch_page(&url).await {
+            let parsed = match self.fetch_page(&url, &input.allowed_domains).await {
                 Ok(r) => r,
                 Err(e) => {
-                    warn!(url = %url, error = %e, "Failed to fetch");
-                    errors.push(format!("{}: {}", url, e));
+                    warn!(url = %url, error = %e.message, kind = ?e.kind, "Failed to fetch");
+                    errors.push(e);
                     continue;
                 }
             };
 
-            if text.is_empty() {
-                debug!(url = %url, "Skipping empty page");
-                continue;
+            if parsed.nofollow_skipped > 0 {
+                debug!(url = %url, skipped = parsed.nofollow_skipped, "Skipped nofollow links");
+                errors.push(CrawlError::new(
+                    &url,
+                    CrawlErrorKind::RobotsDisallowed,
+                    format!("skipped {} nofollow link(s)", parsed.nofollow_skipped),
+                ));
             }
 
-            // Content hash for dedup
-            let mut hasher = Sha256::new();
-            hasher.update(text.as_bytes());
-            let content_hash = hex::encode(hasher.finalize());
+            let ParsedPage { title, text, links: raw_links, noindex, .. } = parsed;
 
-            // Embeddings (optional)
-            let embedding = if input.generate_embeddings {
-                self.embed_text(&text).await
-            } else {
-                None
-            };
+            if !noindex && text.is_empty() {
+                debug!(url = %url, "Skipping empty page");
+                continue;
+            }
 
             // BFS link expansion
             let outbound: Vec<String> = if depth < input.max_depth {
-                raw_links
+                let (outbound, off_domain): (Vec<String>, Vec<String>) = raw_links
                     .iter()
                     .filter(|link| !visited.contains(*link))
-                    .filter(|link| {
-                        if input.allowed_domains.is_empty() {
-                            return true;
-                        }
-                        Url::parse(link)
-                            .ok()
-                            .and_then(|u| u.host_str().map(|h| h.to_string()))
-                            .map(|host| {
-                                input.allowed_domains.iter().any(|d| {
-                                    host == *d || host.ends_with(&format!(".{}", d))
-                                })
-                            })
-                            .unwrap_or(false)
-                    })
                     .cloned()
-                    .collect()
+                    .partition(|link| {
+                        input.allowed_domains.is_empty()
+                            || Url::parse(link).is_ok_and(|u| domain_allowed(&input.allowed_domains, &u))
+                    });
+                if !off_domain.is_empty() {
+                    errors.push(CrawlError::new(
+                        &url,
+                        CrawlErrorKind::OffDomain,
+                        format!("skipped {} off-domain link(s)", off_domain.len()),
+                    ));
+                }
+                outbound
             } else {
                 vec![]
             };
 
+            // Drop links the robots.txt rules fetched so far disallow. Links
+            // to other origins are checked when they come up, so a host's
+            // robots.txt is never fetched just for being linked to.
+            let (outbound, disallowed): (Vec<String>, Vec<String>) = outbound
+                .into_iter()
+                .partition(|link| !self.respect_robots || !robots_disallows(&robots_cache, link));
+            for link in disallowed {
+                debug!(url = %link, "Not enqueued: disallowed by robots.txt");
+                errors.push(robots_blocked(&link));
+                visited.insert(link);
+            }
+
             for link in &outbound {
-                if !visited.contains(link) {
+                if visited.insert(link.clone()) {
                     queue.push_back((link.clone(), depth + 1));
                 }
             }
 
+            // noindex pages still feed the BFS queue, but their content is not stored
+            if noindex {
+                debug!(url = %url, "Skipped content: meta robots noindex");
+                errors.push(CrawlError::new(
+                    &url,
+                    CrawlErrorKind::RobotsDisallowed,
+                    "skipped content (meta robots noindex)",
+                ));
+                continue;
+            }
+
+            // Content hash for dedup
+            let mut hasher = Sha256::new();
+            hasher.update(text.as_bytes());
+            let content_hash = hex::encode(hasher.finalize());
+            if !seen_hashes.insert(content_hash.clone()) {
+                debug!(url = %url, "Skipped content: duplicate of a page already collected");
+                duplicates += 1;
+                continue;
+            }
+
+            // Embeddings (optional)
+            let embedding = if input.generate_embeddings {
+                self.embed_text(&text).await
+            } else {
+                None
+            };
+
             pages.push(CrawledPage {
                 url: url.clone(),
                 title,
@@ -421,17 +989,770 @@ impl InferenceBackend for CrawlerBackend {
                 fetched_at: chrono::Utc::now()
                     .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
                 content_hash,
+                depth,
             });
         }
 
         let total_text_chars: u64 = pages.iter().map(|p| p.text.len() as u64).sum();
-        let total_fetched = pages.len() as u32;
+        let total_fetched = pages.len() as u32 + duplicates;
+        rank_pages(&mut pages, input.sort_by, input.max_pages_returned);
 
         Ok(WebCrawlOutput {
             pages,
             total_fetched,
             total_text_chars,
-            errors,
+            errors: errors.iter().map(|e| e.to_string()).collect(),
+            crawl_errors: errors,
+        })
+    }
+}
+
+/// `url` as crawled: without its fragment, which never changes what the
+/// server returns
+fn normalized(mut url: Url) -> String {
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// Path and query of `url`, as robots.txt rules match them
+fn robots_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Whether the already-fetched robots.txt of `url`'s origin disallows it
+fn robots_disallows(robots_cache: &HashMap<String, RobotsRules>, url: &str) -> bool {
+    Url::parse(url).is_ok_and(|u| {
+        robots_cache
+            .get(&u.origin().ascii_serialization())
+            .is_some_and(|rules| !rules.is_allowed(&robots_path(&u)))
+    })
+}
+
+/// Error for a URL robots.txt excludes
+fn robots_blocked(url: &str) -> CrawlError {
+    CrawlError::new(url, CrawlErrorKind::RobotsDisallowed, "blocked by robots.txt")
+}
+
+/// Sort crawled pages for output and trim them to `max_returned`.
+///
+/// Sorts are stable, so ties keep their crawl order.
+fn rank_pages(pages: &mut Vec<CrawledPage>, sort_by: CrawlSortBy, max_returned: Option<u32>) {
+    match sort_by {
+        CrawlSortBy::CrawlOrder => {}
+        CrawlSortBy::TextLength => pages.sort_by_key(|p| std::cmp::Reverse(p.text.len())),
+        CrawlSortBy::Depth => pages.sort_by_key(|p| p.depth),
+    }
+    if let Some(max) = max_returned {
+        pages.truncate(max as usize);
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{http_stub, Response};
+
+    const NOINDEX_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>Private listing</title>
+  <meta name="robots" content="noindex">
+</head>
+<body>
+  <p>Content that must not be stored.</p>
+  <a href="/public">Public</a>
+  <a href="/sponsored" rel="sponsored nofollow">Sponsored</a>
+  <a href="https://other.example.org/page" rel="NoFollow">Other</a>
+</body>
+</html>"#;
+
+    fn page(url: &str, text: &str, depth: u32) -> CrawledPage {
+        CrawledPage {
+            url: url.to_string(),
+            title: None,
+            text: text.to_string(),
+            embedding: None,
+            links: vec![],
+            fetched_at: "2024-01-01T00:00:00.000Z".to_string(),
+            content_hash: String::new(),
+            depth,
+        }
+    }
+
+    #[test]
+    fn test_rank_pages_by_text_length_and_trim() {
+        let mut pages = vec![
+            page("https://example.com/", "short", 0),
+            page("https://example.com/long", "a much longer page body", 1),
+            page("https://example.com/mid", "medium text", 1),
+            page("https://example.com/tiny", "x", 2),
+        ];
+
+        rank_pages(&mut pages, CrawlSortBy::TextLength, Some(2));
+
+        let urls: Vec<&str> = pages.iter().map(|p| p.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/long", "https://example.com/mid"]);
+    }
+
+    #[test]
+    fn test_rank_pages_crawl_order_and_depth() {
+        let mut pages = vec![
+            page("https://example.com/b", "b", 1),
+            page("https://example.com/", "seed", 0),
+            page("https://example.com/c", "c", 1),
+        ];
+
+        rank_pages(&mut pages, CrawlSortBy::CrawlOrder, None);
+        assert_eq!(pages[0].url, "https://example.com/b");
+
+        rank_pages(&mut pages, CrawlSortBy::Depth, None);
+        let urls: Vec<&str> = pages.iter().map(|p| p.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec!["https://example.com/", "https://example.com/b", "https://example.com/c"]
+        );
+
+        let input: WebCrawlInput =
+            serde_json::from_str(r#"{"url": "https://example.com", "sort_by": "text_length"}"#)
+                .unwrap();
+        assert_eq!(input.sort_by, CrawlSortBy::TextLength);
+        assert_eq!(input.max_pages_returned, None);
+    }
+
+    #[test]
+    fn test_meta_noindex_and_rel_nofollow_honored() {
+        let parsed = parse_html("https://example.com/index.html", NOINDEX_PAGE, true);
+
+        assert!(parsed.noindex);
+        assert_eq!(parsed.links, vec!["https://example.com/public".to_string()]);
+        assert_eq!(parsed.nofollow_skipped, 2);
+        assert_eq!(parsed.title.as_deref(), Some("Private listing"));
+    }
+
+    #[test]
+    fn test_meta_nofollow_drops_all_links() {
+        let html = r#"<html><head><meta name="ROBOTS" content="index, nofollow"></head>
+            <body><p>Hi</p><a href="/a">A</a><a href="/b">B</a></body></html>"#;
+        let parsed = parse_html("https://example.com/", html, true);
+
+        assert!(!parsed.noindex);
+        assert!(parsed.links.is_empty());
+        assert_eq!(parsed.nofollow_skipped, 2);
+    }
+
+    #[test]
+    fn test_robots_directives_ignored_when_disabled() {
+        let parsed = parse_html("https://example.com/index.html", NOINDEX_PAGE, false);
+
+        assert!(!parsed.noindex);
+        assert_eq!(parsed.links.len(), 3);
+        assert_eq!(parsed.nofollow_skipped, 0);
+    }
+
+    /// Serve one canned response per path, simulating assorted failures
+    async fn serve_failures(listener: tokio::net::TcpListener) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else { return };
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+                // Chunked HTML without a length that never ends
+                if path == "/endless" {
+                    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nTransfer-Encoding: chunked\r\n\r\n";
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let chunk = format!("10000\r\n{}\r\n", "x".repeat(0x10000));
+                    while stream.write_all(chunk.as_bytes()).await.is_ok() {}
+                    return;
+                }
+
+                let response = match path.as_str() {
+                    "/" => {
+                        let body = r#"<html><body><p>Seed</p>
+                            <a href="/missing">a</a> <a href="/slow">b</a>
+                            <a href="/big">c</a> <a href="/binary">d</a>
+                            <a href="/private">e</a> <a href="http://off.example/x">f</a>
+                            <a href="/endless">g</a>
+                            </body></html>"#;
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                            body.len(),
+                            body
+                        )
+                    }
+                    "/robots.txt" => {
+                        let body = "User-agent: *\nDisallow: /private\n";
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+                            body.len(),
+                            body
+                        )
+                    }
+                    "/slow" => {
+                        tokio::time::sleep(Duration::from_secs(3)).await;
+                        "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_string()
+                    }
+                    "/big" => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n",
+                        MAX_PAGE_BYTES + 1
+                    ),
+                    "/binary" => "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\n\
+                                  Content-Length: 3\r\n\r\nabc"
+                        .to_string(),
+                    _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string(),
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crawl_error_kinds() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_failures(listener));
+
+        let settings = CrawlerSettings { rate_limit_ms: 0, ..Default::default() };
+        let mut backend = CrawlerBackend::new(&settings, &OpenAiSettings::default());
+        backend.http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(500))
+            .build()
+            .unwrap();
+
+        let base = format!("http://{}", addr);
+        let output = backend
+            .web_crawl(WebCrawlInput {
+                url: format!("{}/", base),
+                max_depth: 1,
+                max_pages: 10,
+                generate_embeddings: false,
+                allowed_domains: vec!["127.0.0.1".to_string()],
+                sort_by: CrawlSortBy::CrawlOrder,
+                max_pages_returned: None,
+            })
+            .await
+            .unwrap();
+
+        let kind_of = |path: &str| {
+            let url = format!("{}{}", base, path);
+            output.crawl_errors.iter().find(|e| e.url == url).map(|e| e.kind)
+        };
+        assert_eq!(kind_of("/missing"), Some(CrawlErrorKind::HttpStatus));
+        assert_eq!(kind_of("/slow"), Some(CrawlErrorKind::Timeout));
+        assert_eq!(kind_of("/big"), Some(CrawlErrorKind::TooLarge));
+        assert_eq!(kind_of("/endless"), Some(CrawlErrorKind::TooLarge));
+        assert_eq!(kind_of("/binary"), Some(CrawlErrorKind::ParseFailed));
+        assert_eq!(kind_of("/private"), Some(CrawlErrorKind::RobotsDisallowed));
+        assert_eq!(kind_of("/"), Some(CrawlErrorKind::OffDomain));
+        assert_eq!(output.pages.len(), 1);
+
+        // String rendering kept for existing consumers
+        assert_eq!(output.errors.len(), output.crawl_errors.len());
+        assert!(output.errors.contains(&format!("{}/missing: HTTP 404 Not Found", base)));
+    }
+
+    #[tokio::test]
+    async fn test_denylisted_domain_blocked_even_when_allowed() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while listener.accept().await.is_ok() {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        });
+
+        let settings = CrawlerSettings {
+            rate_limit_ms: 0,
+            domain_denylist: vec!["example.com".to_string()],
+            ..Default::default()
+        };
+        let backend = CrawlerBackend::new(&settings, &OpenAiSettings::default());
+        let denylist = DomainDenylist::new(&settings.domain_denylist);
+        assert!(denylist.is_denied("https://EXAMPLE.com/page"));
+        assert!(denylist.is_denied("https://docs.example.com/"));
+        assert!(!denylist.is_denied("https://notexample.com/"));
+
+        // Reloading swaps the list for crawls sharing the handle
+        let backend = backend.with_denylist(denylist.clone());
+        denylist.replace(&["127.0.0.1".to_string()]);
+
+        let url = format!("http://{}/", addr);
+        let output = backend
+            .web_crawl(WebCrawlInput {
+                url: url.clone(),
+                max_depth: 1,
+                max_pages: 10,
+                generate_embeddings: false,
+                allowed_domains: vec!["127.0.0.1".to_string()],
+                sort_by: CrawlSortBy::CrawlOrder,
+                max_pages_returned: None,
+            })
+            .await
+            .unwrap();
+
+        assert!(output.pages.is_empty());
+        assert_eq!(output.crawl_errors.len(), 1);
+        assert_eq!(output.crawl_errors[0].url, url);
+        assert_eq!(output.crawl_errors[0].kind, CrawlErrorKind::Denylisted);
+        // Nothing was fetched, not even robots.txt
+        assert_eq!(accepted.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_hops_rechecked() {
+        let requested = Arc::new(Mutex::new(Vec::<String>::new()));
+        let log = requested.clone();
+        let base = http_stub(move |request| {
+            // Every page bounces to the same server under another name
+            let host = request.header("host").unwrap_or_default().replace("127.0.0.1", "localhost");
+            log.lock().push(request.path);
+            Response::new(302).header("Location", format!("http://{}/target", host))
+        })
+        .await;
+
+        let crawl = |denylist: &[&str]| {
+            let settings = CrawlerSettings {
+                rate_limit_ms: 0,
+                respect_robots: false,
+                domain_denylist: denylist.iter().map(|d| d.to_string()).collect(),
+                ..Default::default()
+            };
+            let backend = CrawlerBackend::new(&settings, &OpenAiSettings::default());
+            let url = format!("{}/", base);
+            async move {
+                backend
+                    .web_crawl(WebCrawlInput {
+                        url,
+                        max_depth: 1,
+                        max_pages: 10,
+                        generate_embeddings: false,
+                        allowed_domains: vec!["127.0.0.1".to_string()],
+                        sort_by: CrawlSortBy::CrawlOrder,
+                        max_pages_returned: None,
+                    })
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Redirect into the denylist
+        let output = crawl(&["localhost"]).await;
+        assert!(output.pages.is_empty());
+        assert_eq!(output.crawl_errors.len(), 1);
+        assert_eq!(output.crawl_errors[0].kind, CrawlErrorKind::Denylisted);
+        assert!(output.crawl_errors[0].message.contains("localhost"));
+
+        // Redirect outside allowed_domains
+        let output = crawl(&[]).await;
+        assert!(output.pages.is_empty());
+        assert_eq!(output.crawl_errors.len(), 1);
+        assert_eq!(output.crawl_errors[0].kind, CrawlErrorKind::OffDomain);
+
+        // Neither redirect target was fetched
+        assert_eq!(*requested.lock(), vec!["/".to_string(), "/".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_off_allowlist_crawl_target_blocked() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while listener.accept().await.is_ok() {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        });
+
+        let settings = CrawlerSettings { rate_limit_ms: 0, ..Default::default() };
+        let crawl = |allowlist: &[&str], url: String| {
+            let egress =
+                EgressPolicy::new(&allowlist.iter().map(|e| e.to_string()).collect::<Vec<_>>()).unwrap();
+            let backend = CrawlerBackend::new(&settings, &OpenAiSettings::default()).with_egress(egress);
+            async move {
+                backend
+                    .web_crawl(WebCrawlInput {
+                        url,
+                        max_depth: 1,
+                        max_pages: 10,
+                        generate_embeddings: false,
+                        allowed_domains: vec![],
+                        sort_by: CrawlSortBy::CrawlOrder,
+                        max_pages_returned: None,
+                    })
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Address literal outside a domain-only allowlist
+        let url = format!("http://{}/", addr);
+        let output = crawl(&["example.com"], url.clone()).await;
+        assert!(output.pages.is_empty());
+        assert_eq!(output.crawl_errors.len(), 1);
+        assert_eq!(output.crawl_errors[0].url, url);
+        assert_eq!(output.crawl_errors[0].kind, CrawlErrorKind::EgressBlocked);
+        assert!(output.crawl_errors[0].message.contains("egress allowlist"));
+
+        // Hostname resolving outside the allowed ranges
+        let output = crawl(&["10.0.0.0/8"], format!("http://localhost:{}/", addr.port())).await;
+        assert!(output.pages.is_empty());
+        assert!(output
+            .crawl_errors
+            .iter()
+            .any(|e| e.kind == CrawlErrorKind::EgressBlocked));
+
+        // No connection was made, not even for robots.txt
+        assert_eq!(accepted.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    /// Serves one HTML page and an `/embeddings` endpoint that reports the
+    /// model each embeddings request named
+    async fn serve_page_and_embeddings(models: tokio::sync::mpsc::UnboundedSender<String>) -> String {
+        http_stub(move |request| {
+            if request.method == "POST" && request.path == "/embeddings" {
+                let _ = models.send(request.json()["model"].as_str().unwrap_or_default().to_string());
+                Response::json(200, r#"{"data":[{"embedding":[0.5,0.25]}]}"#)
+            } else {
+                Response::ok("text/html", "<html><body><p>Embed me</p></body></html>")
+            }
+        })
+        .await
+    }
+
+    /// Serve `page` at `/` and `robots` at `/robots.txt`, reporting each
+    /// requested path
+    async fn serve_site(
+        page: String,
+        robots: String,
+        hits: tokio::sync::mpsc::UnboundedSender<String>,
+    ) -> String {
+        http_stub(move |request| {
+            let body = if request.path == "/robots.txt" { &robots } else { &page };
+            let _ = hits.send(request.path);
+            Response::ok("text/html", body.as_str())
+        })
+        .await
+    }
+
+    async fn crawl_one_page(url: String) -> WebCrawlOutput {
+        let settings = CrawlerSettings { rate_limit_ms: 0, ..Default::default() };
+        CrawlerBackend::new(&settings, &OpenAiSettings::default())
+            .web_crawl(WebCrawlInput {
+                url,
+                max_depth: 1,
+                max_pages: 1,
+                generate_embeddings: false,
+                allowed_domains: vec![],
+                sort_by: CrawlSortBy::CrawlOrder,
+                max_pages_returned: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_robots_fetched_only_when_host_reached() {
+        let (other_tx, mut other_hits) = tokio::sync::mpsc::unbounded_channel();
+        let other = serve_site(String::new(), String::new(), other_tx).await;
+
+        let page = format!(
+            r#"<html><body><p>Seed</p><a href="/blocked">a</a>
+               <a href="{}/page">b</a></body></html>"#,
+            other
+        );
+        let (seed_tx, _seed_hits) = tokio::sync::mpsc::unbounded_channel();
+        let robots = "User-agent: *\nDisallow: /blocked\n".to_string();
+        let seed = serve_site(page, robots, seed_tx).await;
+
+        let output = crawl_one_page(format!("{}/", seed)).await;
+        assert_eq!(output.pages.len(), 1);
+        // The seed host's rules were known, so its link was dropped early
+        assert!(output
+            .crawl_errors
+            .iter()
+            .any(|e| e.url.ends_with("/blocked") && e.kind == CrawlErrorKind::RobotsDisallowed));
+        // The crawl never got to the other host, so it wasn't contacted
+        assert!(other_hits.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_robots_ignored() {
+        let robots = format!("User-agent: *\nDisallow: /\n#{}\n", "x".repeat(MAX_ROBOTS_BYTES));
+        let page = "<html><body><p>Seed</p></body></html>".to_string();
+        let (hits_tx, _hits) = tokio::sync::mpsc::unbounded_channel();
+        let base = serve_site(page, robots, hits_tx).await;
+
+        let output = crawl_one_page(format!("{}/", base)).await;
+        assert_eq!(output.pages.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_content_collected_once() {
+        let (hits_tx, mut hits_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let base = http_stub(move |request| {
+            let body = match request.path.as_str() {
+                "/" => r#"<html><body><p>Seed</p>
+                    <a href="/article">a</a> <a href="/article/print">b</a>
+                    <a href="/article#comments">c</a> <a href="/article">d</a>
+                    </body></html>"#,
+                "/article" | "/article/print" => "<html><body><p>Same story</p></body></html>",
+                _ => "",
+            };
+            let _ = hits_tx.send(request.path);
+            Response::ok("text/html", body)
         })
+        .await;
+
+        let settings = CrawlerSettings { rate_limit_ms: 0, ..Default::default() };
+        let backend = CrawlerBackend::new(&settings, &OpenAiSettings::default());
+        let output = backend
+            .web_crawl(WebCrawlInput {
+                url: format!("{}/#top", base),
+                max_depth: 1,
+                max_pages: 10,
+                generate_embeddings: false,
+                allowed_domains: vec![],
+                sort_by: CrawlSortBy::CrawlOrder,
+                max_pages_returned: None,
+            })
+            .await
+            .unwrap();
+
+        let urls: Vec<&str> = output.pages.iter().map(|p| p.url.as_str()).collect();
+        assert_eq!(urls, [format!("{}/", base), format!("{}/article", base)]);
+        // The print view was fetched but not collected
+        assert_eq!(output.total_fetched, 3);
+
+        // Each URL was requested once, fragments and repeats included
+        let mut hits = Vec::new();
+        while let Ok(path) = hits_rx.try_recv() {
+            hits.push(path);
+        }
+        hits.sort();
+        assert_eq!(hits, ["/", "/article", "/article/print", "/robots.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_configured_embedding_model_used() {
+        let (tx, mut models) = tokio::sync::mpsc::unbounded_channel();
+        let base = serve_page_and_embeddings(tx).await;
+        let settings = CrawlerSettings {
+            rate_limit_ms: 0,
+            respect_robots: false,
+            embedding_model: "tiny-embed".to_string(),
+            embedding_base_url: base.clone(),
+            ..Default::default()
+        };
+        // The inference endpoint is unreachable; embeddings must not use it
+        let openai = OpenAiSettings {
+            base_url: "http://127.0.0.1:1".to_string(),
+            default_model: "big-chat-model".to_string(),
+            ..Default::default()
+        };
+        let backend = CrawlerBackend::new(&settings, &openai);
+
+        let output = backend
+            .web_crawl(WebCrawlInput {
+                url: format!("{}/", base),
+                max_depth: 0,
+                max_pages: 1,
+                generate_embeddings: true,
+                allowed_domains: vec![],
+                sort_by: CrawlSortBy::CrawlOrder,
+                max_pages_returned: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(output.pages.len(), 1);
+        assert_eq!(output.pages[0].embedding, Some(vec![0.5, 0.25]));
+        assert_eq!(models.recv().await.unwrap(), "tiny-embed");
+    }
+
+    #[test]
+    fn test_embedding_settings_fall_back_to_openai() {
+        let openai = OpenAiSettings::default();
+        let embedder = page_embedder(&CrawlerSettings::default(), &openai).unwrap();
+        assert_eq!(embedder.loaded_model_id().as_deref(), Some(openai.default_model.as_str()));
+
+        let no_endpoint = OpenAiSettings { base_url: String::new(), ..Default::default() };
+        assert!(page_embedder(&CrawlerSettings::default(), &no_endpoint).is_none());
+    }
+
+    #[test]
+    fn test_openai_credentials_stay_with_openai_host() {
+        let openai = OpenAiSettings {
+            base_url: "https://api.example.com/v1".to_string(),
+            api_key: "sk-secret".to_string(),
+            extra_headers: [("X-Org".to_string(), "acme".to_string())].into_iter().collect(),
+            ..Default::default()
+        };
+        let config = |embedding_base_url: &str| {
+            let crawler = CrawlerSettings {
+                embedding_base_url: embedding_base_url.to_string(),
+                ..Default::default()
+            };
+            page_embedder_config(&crawler, &openai).unwrap()
+        };
+
+        for shared in ["", "https://api.example.com/embeddings-v2"] {
+            let config = config(shared);
+            assert_eq!(config.api_key, "sk-secret", "{}", shared);
+            assert_eq!(config.extra_headers.len(), 1, "{}", shared);
+        }
+        for other in ["https://embed.other.net/v1", "http://api.example.com/v1", "https://api.example.com:8443/v1"] {
+            let config = config(other);
+            assert!(config.api_key.is_empty(), "{}", other);
+            assert!(config.extra_headers.is_empty(), "{}", other);
+        }
+    }
+
+    #[test]
+    fn test_body_decoded_with_declared_charset() {
+        let latin1 = b"caf\xe9";
+        assert_eq!(decode_body(latin1, "text/html; charset=ISO-8859-1"), "café");
+        assert_eq!(decode_body(latin1, "text/html;Charset=\"latin1\""), "café");
+        assert_eq!(decode_body("café".as_bytes(), "text/html"), "café");
+        assert_eq!(decode_body("café".as_bytes(), "text/html; charset=bogus"), "café");
+    }
+
+    #[test]
+    fn test_embedding_snippet_respects_char_boundaries() {
+        // Two-byte chars, with byte 8000 on and off a char boundary
+        let text = "é".repeat(9000);
+        assert_eq!(embedding_snippet(&text).chars().count(), 8000);
+        let text = format!("a{}", "é".repeat(9000));
+        assert_eq!(embedding_snippet(&text).chars().count(), 8000);
+        assert_eq!(embedding_snippet("short"), "short");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_spaces_same_host_only() {
+        let delay = Duration::from_millis(200);
+        let limiter = HostRateLimiter::new(delay, Duration::ZERO);
+        let fetch_at = |host: &'static str, start: Instant| {
+            let limiter = limiter.clone();
+            async move {
+                limiter.acquire(host).await;
+                start.elapsed()
+            }
+        };
+
+        // Concurrent fetches to one host are serialized, `delay` apart
+        let start = Instant::now();
+        let (a, b) = tokio::join!(
+            fetch_at("same.example.com", start),
+            fetch_at("SAME.example.com", start)
+        );
+        assert!(a.max(b) - a.min(b) >= delay, "same host spaced {:?}", a.max(b) - a.min(b));
+
+        // Different hosts don't wait on each other
+        let start = Instant::now();
+        let (a, b) = tokio::join!(
+            fetch_at("one.example.com", start),
+            fetch_at("two.example.com", start)
+        );
+        assert!(a < delay && b < delay, "different hosts took {:?} and {:?}", a, b);
+    }
+
+    #[test]
+    fn test_rate_limit_jitter() {
+        let interval = Duration::from_millis(100);
+        let jitter = Duration::from_millis(50);
+        let limiter = HostRateLimiter::new(interval, jitter);
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve("example.com", now), Duration::ZERO);
+        let mut previous = Duration::ZERO;
+        for _ in 0..20 {
+            let wait = limiter.reserve("example.com", now);
+            let gap = wait - previous;
+            assert!(gap >= interval && gap <= interval + jitter, "gap {:?}", gap);
+            previous = wait;
+        }
+
+        // No limit when the interval is zero
+        let unlimited = HostRateLimiter::new(Duration::ZERO, jitter);
+        assert_eq!(unlimited.reserve("example.com", now), Duration::ZERO);
+        assert_eq!(unlimited.reserve("example.com", now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_robots_allow_disallow() {
+        let robots = "\
+# Everyone else
+User-agent: *
+Disallow: /
+
+user-agent: Googlebot
+User-Agent: ai4all/2.0   # our group
+disallow: /private
+Allow: /private/press
+Disallow: /*.pdf$
+Disallow: /search?
+Allow: /search?q=public
+Disallow:
+
+User-agent: Other
+Allow: /
+";
+        let rules = RobotsRules::parse(robots, "AI4All");
+        assert!(rules.is_allowed("/"));
+        assert!(rules.is_allowed("/public/page"));
+        assert!(!rules.is_allowed("/private"));
+        assert!(!rules.is_allowed("/private/notes"));
+        // Longest match wins
+        assert!(rules.is_allowed("/private/press/release"));
+        assert!(!rules.is_allowed("/docs/report.pdf"));
+        assert!(rules.is_allowed("/docs/report.pdf.html"));
+        assert!(!rules.is_allowed("/search?q=secret"));
+        assert!(rules.is_allowed("/search?q=public"));
+
+        // Agents without a group of their own fall back to `*`
+        let other = RobotsRules::parse(robots, "SomeBot");
+        assert!(!other.is_allowed("/public/page"));
+
+        // Allow wins a tie; no rules or no file allows everything
+        let tie = RobotsRules::parse("User-agent: *\nDisallow: /a\nAllow: /a\n", "AI4All");
+        assert!(tie.is_allowed("/a"));
+        assert!(RobotsRules::parse("User-agent: *\nDisallow:\n", "AI4All").is_allowed("/x"));
+        assert!(RobotsRules::default().is_allowed("/x"));
+    }
+
+    #[test]
+    fn test_robots_crawl_delay() {
+        let rules = RobotsRules::parse(
+            "User-agent: *\nCrawl-delay: 2.5\nDisallow: /tmp\n\nUser-agent: Other\nCrawl-delay: 9\n",
+            "AI4All",
+        );
+        assert_eq!(rules.crawl_delay, Some(Duration::from_millis(2500)));
+        assert!(!rules.is_allowed("/tmp/file"));
+        assert_eq!(RobotsRules::parse("User-agent: *\nCrawl-delay: soon\n", "AI4All").crawl_delay, None);
+
+        // The delay stretches the host's rate limit, up to the cap
+        let limiter = HostRateLimiter::new(Duration::from_millis(100), Duration::ZERO);
+        limiter.set_crawl_delay("slow.example.com", Duration::from_secs(2));
+        limiter.set_crawl_delay("slower.example.com", Duration::from_secs(3600));
+        let now = Instant::now();
+        limiter.reserve("slow.example.com", now);
+        limiter.reserve("slower.example.com", now);
+        limiter.reserve("fast.example.com", now);
+        assert_eq!(limiter.reserve("slow.example.com", now), Duration::from_secs(2));
+        assert_eq!(limiter.reserve("slower.example.com", now), MAX_CRAWL_DELAY);
+        assert_eq!(limiter.reserve("fast.example.com", now), Duration::from_millis(100));
     }
 }
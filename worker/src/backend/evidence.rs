@@ -0,0 +1,248 @@
+//! Evidence spans for question answering
+//!
+//! The model is asked to answer in JSON, quoting the context passages that
+//! support its answer. Quotes are located in the context (tolerating case
+//! and whitespace differences); when the model quoted nothing usable, the
+//! context sentence sharing the most words with the answer is used instead.
+//! Spans are byte offsets into the context, always on char boundaries, so
+//! `&context[start..end]` is the supporting text.
+
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
+/// System prompt asking for the answer, supporting quotes, and confidence
+pub const QA_SYSTEM_PROMPT: &str = "Answer the question based on the provided context. \
+    Be concise and accurate. Respond with JSON only, in the form \
+    {\"answer\": \"...\", \"quotes\": [\"...\"], \"confidence\": 0.0}, where \
+    quotes are passages copied exactly from the context that support the answer \
+    and confidence is between 0 and 1.";
+
+/// Fraction of the answer's words a sentence must contain to be used as
+/// evidence when the model gave no usable quote
+const MIN_OVERLAP: f32 = 0.5;
+
+/// A question-answering reply, structured or not
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct QaReply {
+    pub answer: String,
+    #[serde(default)]
+    pub quotes: Vec<String>,
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+/// Parse the model's reply. Tolerates code fences and surrounding prose;
+/// anything that isn't the requested JSON is taken as a plain answer.
+pub fn parse_reply(text: &str) -> QaReply {
+    let json = match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => "",
+    };
+
+    match serde_json::from_str::<QaReply>(json) {
+        Ok(mut reply) => {
+            reply.answer = reply.answer.trim().to_string();
+            reply.confidence = reply
+                .confidence
+                .filter(|c| c.is_finite())
+                .map(|c| c.clamp(0.0, 1.0));
+            reply
+        }
+        Err(_) => QaReply {
+            answer: text.trim().to_string(),
+            quotes: Vec::new(),
+            confidence: None,
+        },
+    }
+}
+
+/// Spans of `context` supporting `answer`, sorted and non-overlapping
+pub fn evidence_spans(context: &str, answer: &str, quotes: &[String]) -> Vec<(usize, usize)> {
+    let mut spans: Vec<(usize, usize)> = quotes
+        .iter()
+        .filter_map(|quote| locate(context, quote))
+        .collect();
+
+    if spans.is_empty() {
+        spans.extend(best_overlapping_sentence(context, answer));
+    }
+
+    spans.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Find `quote` in `context`, exactly or ignoring case and whitespace runs
+fn locate(context: &str, quote: &str) -> Option<(usize, usize)> {
+    let quote = quote.trim().trim_matches(|c| c == '"' || c == '\'' || c == '…').trim();
+    if quote.is_empty() {
+        return None;
+    }
+    if let Some(start) = context.find(quote) {
+        return Some((start, start + quote.len()));
+    }
+
+    let (haystack, offsets) = normalize(context);
+    let (needle, _) = normalize(quote);
+    let at = haystack.find(needle.trim())?;
+    let last = at + needle.trim().len() - 1;
+    Some((offsets[at].0, offsets[last].1))
+}
+
+/// Lowercase `text` and collapse whitespace runs to one space. Returns the
+/// normalized text and, for each of its bytes, the (start, end) byte range
+/// of the original char it came from.
+fn normalize(text: &str) -> (String, Vec<(usize, usize)>) {
+    let mut out = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len());
+    let mut in_space = false;
+
+    for (i, c) in text.char_indices() {
+        let range = (i, i + c.len_utf8());
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+                offsets.push(range);
+            }
+            in_space = true;
+            continue;
+        }
+        in_space = false;
+        for lower in c.to_lowercase() {
+            out.push(lower);
+            offsets.extend(std::iter::repeat_n(range, lower.len_utf8()));
+        }
+    }
+    (out, offsets)
+}
+
+/// Lowercased words of three or more alphanumeric chars
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// The context sentence containing most of the answer's words, if it
+/// covers at least [`MIN_OVERLAP`] of them
+fn best_overlapping_sentence(context: &str, answer: &str) -> Option<(usize, usize)> {
+    let answer_words = words(answer);
+    if answer_words.is_empty() {
+        return None;
+    }
+
+    let mut best: Option<((usize, usize), f32)> = None;
+    for (start, end) in sentences(context) {
+        let sentence_words = words(&context[start..end]);
+        let overlap = answer_words.intersection(&sentence_words).count() as f32
+            / answer_words.len() as f32;
+        if overlap >= MIN_OVERLAP && best.is_none_or(|(_, score)| overlap > score) {
+            best = Some(((start, end), overlap));
+        }
+    }
+    best.map(|(span, _)| span)
+}
+
+/// Sentence spans of `text`, trimmed of surrounding whitespace
+fn sentences(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if matches!(c, '.' | '!' | '?' | '\n') {
+            spans.push((start, i + c.len_utf8()));
+            start = i + c.len_utf8();
+        }
+    }
+    spans.push((start, text.len()));
+
+    spans
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let slice = &text[start..end];
+            let trimmed = slice.trim_start();
+            let start = start + (slice.len() - trimmed.len());
+            let end = start + trimmed.trim_end().len();
+            (start < end).then_some((start, end))
+        })
+        .collect()
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTEXT: &str = "The Eiffel Tower is in Paris. It was completed in 1889.\n\
+        Gustave Eiffel's company   designed and built it.";
+
+    fn texts<'a>(context: &'a str, spans: &[(usize, usize)]) -> Vec<&'a str> {
+        spans.iter().map(|&(s, e)| &context[s..e]).collect()
+    }
+
+    #[test]
+    fn test_parse_structured_and_plain_replies() {
+        let reply = parse_reply(
+            "```json\n{\"answer\": \" 1889 \", \"quotes\": [\"completed in 1889\"], \"confidence\": 1.4}\n```",
+        );
+        assert_eq!(reply.answer, "1889");
+        assert_eq!(reply.quotes, ["completed in 1889"]);
+        assert_eq!(reply.confidence, Some(1.0));
+
+        let reply = parse_reply("It was finished in 1889.");
+        assert_eq!(reply.answer, "It was finished in 1889.");
+        assert!(reply.quotes.is_empty());
+        assert_eq!(reply.confidence, None);
+    }
+
+    #[test]
+    fn test_quotes_located_as_real_substrings() {
+        let quotes = vec![
+            "It was completed in 1889.".to_string(),
+            // Different case and whitespace from the context
+            "\"gustave eiffel's company designed\"".to_string(),
+            "Not in the context at all".to_string(),
+        ];
+        let spans = evidence_spans(CONTEXT, "1889", &quotes);
+        assert_eq!(
+            texts(CONTEXT, &spans),
+            ["It was completed in 1889.", "Gustave Eiffel's company   designed"]
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_overlapping_sentence() {
+        let spans = evidence_spans(CONTEXT, "It was completed in 1889", &[]);
+        assert_eq!(texts(CONTEXT, &spans), ["It was completed in 1889."]);
+
+        assert!(evidence_spans(CONTEXT, "Berlin, Germany", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_spans_on_char_boundaries() {
+        let context = "Café au lait coûte 3 €. Straße ist lang.";
+        let spans = evidence_spans(context, "", &["CAFÉ AU LAIT coûte 3 €".to_string(), "STRASSE".to_string()]);
+        for &(start, end) in &spans {
+            assert!(context.is_char_boundary(start) && context.is_char_boundary(end));
+        }
+        assert_eq!(texts(context, &spans), ["Café au lait coûte 3 €"]);
+
+        // Overlapping quotes merge into one span
+        let spans = evidence_spans(
+            context,
+            "",
+            &["au lait coûte".to_string(), "coûte 3 €. Straße".to_string()],
+        );
+        assert_eq!(texts(context, &spans), ["au lait coûte 3 €. Straße"]);
+    }
+}
@@ -5,22 +5,25 @@
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::error::{Error, Result};
 use crate::types::{
-    ClassificationInput, ClassificationOutput, ClassificationPrediction,
-    EmbeddingsInput, EmbeddingsOutput,
-    FinishReason, GgufMetadata, LoadedModelInfo, ModelFormat, ModelSpec,
+    ClassificationInput, ClassificationOutput, ClassificationPrediction, ScoreCalibration,
+    DebugInput, DebugOutput,
+    EmbeddingsInput, EmbeddingsOutput, l2_normalize,
+    FinishReason, GenerationParams, GgufMetadata, LoadedModelInfo, ModelFormat, ModelSpec,
     QuestionAnsweringInput, QuestionAnsweringOutput,
     SummarizationInput, SummarizationOutput,
     TaskType, TextCompletionInput, TextCompletionOutput, TokenUsage,
     TrainingBatchInput, TrainingBatchOutput,
     ValidationInput, ValidationOutput,
+    WebCrawlInput, WebCrawlOutput,
 };
 
 use super::{
-    BackendCapabilities, BackendConfig, BackendHealth, InferenceBackend,
+    BackendCapabilities, BackendConfig, BackendHealth, InferenceBackend, LayerOutput,
     ResourceUsage, StreamCallback, StreamToken,
 };
 
@@ -34,6 +37,9 @@ pub struct MockConfig {
     /// Simulated latency per token (ms)
     pub token_latency_ms: u64,
 
+    /// Simulated prompt processing before the first streamed token (ms)
+    pub prompt_latency_ms: u64,
+
     /// Whether to fail on certain operations
     pub fail_load_model: bool,
     pub fail_text_completion: bool,
@@ -44,17 +50,49 @@ pub struct MockConfig {
 
     /// Embedding dimensions
     pub embedding_dims: usize,
+
+    /// Memory (MB) held while generating text, simulating activations
+    pub working_set_mb: usize,
+
+    /// Model IDs that fail to load as incompatible
+    pub incompatible_models: Vec<String>,
+
+    /// Handle and advertise DEBUG tasks
+    pub debug_tasks: bool,
+
+    /// Simulated GPU memory (MB); when set the mock reports a GPU
+    pub gpu_memory_mb: Option<u64>,
+
+    /// Handle and advertise WEB_CRAWL tasks (no network access; each page
+    /// costs one token of latency)
+    pub crawl_tasks: bool,
+
+    /// Handle and advertise TRAINING_BATCH tasks (each example per epoch
+    /// costs one token of latency)
+    pub training_tasks: bool,
+
+    /// Layers run per split-layer forward; each adds 1 to every tensor
+    /// byte (0 = split-layer inference unsupported)
+    pub shard_layers: u32,
 }
 
 impl Default for MockConfig {
     fn default() -> Self {
         Self {
             token_latency_ms: 10,
+            prompt_latency_ms: 0,
             fail_load_model: false,
             fail_text_completion: false,
             fail_embeddings: false,
             fixed_response: None,
             embedding_dims: 384,
+            working_set_mb: 0,
+            incompatible_models: Vec::new(),
+            debug_tasks: false,
+            gpu_memory_mb: None,
+            crawl_tasks: false,
+            training_tasks: false,
+            shard_layers: 0,
         }
     }
 }
@@ -68,7 +106,7 @@ pub struct MockBackend {
     config: MockConfig,
     backend_config: BackendConfig,
     loaded_model: RwLock<Option<LoadedModelInfo>>,
-    call_counts: RwLock<CallCounts>,
+    call_counts: Arc<RwLock<CallCounts>>,
 }
 
 /// Track method call counts for verification
@@ -80,6 +118,50 @@ struct CallCounts {
     summarize: u32,
     load_model: u32,
     unload_model: u32,
+    /// Start/end of each completed load, text completion and crawl call
+    windows: Vec<(&'static str, Instant, Instant)>,
+    /// System prompt of the most recent text completion
+    last_system_prompt: Option<String>,
+}
+
+/// Shared view of a mock backend's call counts, still readable after the
+/// backend has been boxed into a registry
+#[cfg(test)]
+#[derive(Clone)]
+pub struct MockCallCounts(Arc<RwLock<CallCounts>>);
+
+#[cfg(test)]
+impl MockCallCounts {
+    /// Get the number of times a method was called
+    pub fn get(&self, method: &str) -> u32 {
+        let counts = self.0.read();
+        match method {
+            "text_completion" => counts.text_completion,
+            "embeddings" => counts.embeddings,
+            "classify" => counts.classify,
+            "summarize" => counts.summarize,
+            "load_model" => counts.load_model,
+            "unload_model" => counts.unload_model,
+            _ => 0,
+        }
+    }
+
+    /// Get the system prompt the most recent text completion received
+    pub fn last_system_prompt(&self) -> Option<String> {
+        self.0.read().last_system_prompt.clone()
+    }
+
+    /// Get the start/end times of each `load_model`, `text_completion` or
+    /// `web_crawl` call
+    pub fn windows(&self, method: &str) -> Vec<(Instant, Instant)> {
+        self.0
+            .read()
+            .windows
+            .iter()
+            .filter(|(m, _, _)| *m == method)
+            .map(|(_, start, end)| (*start, *end))
+            .collect()
+    }
 }
 
 impl MockBackend {
@@ -94,7 +176,7 @@ impl MockBackend {
             config: mock_config,
             backend_config,
             loaded_model: RwLock::new(None),
-            call_counts: RwLock::new(CallCounts::default()),
+            call_counts: Arc::new(RwLock::new(CallCounts::default())),
         }
     }
 
@@ -112,6 +194,12 @@ impl MockBackend {
         }
     }
 
+    /// Get a shared handle to the call counts
+    #[cfg(test)]
+    pub fn counts_handle(&self) -> MockCallCounts {
+        MockCallCounts(self.call_counts.clone())
+    }
+
     /// Reset all call counts
     pub fn reset_counts(&self) {
         *self.call_counts.write() = CallCounts::default();
@@ -125,32 +213,67 @@ impl MockBackend {
         }
     }
 
+    /// Record the time window of a completed call
+    fn record_window(&self, method: &'static str, start: Instant) {
+        self.call_counts.write().windows.push((method, start, Instant::now()));
+    }
+
+    /// Allocate and touch the configured working set
+    fn allocate_working_set(&self) -> Vec<u8> {
+        std::hint::black_box(vec![1u8; self.config.working_set_mb * 1024 * 1024])
+    }
+
     /// Generate mock response text
+    ///
+    /// Exactly `max_tokens` whitespace-separated tokens: `seed=`,
+    /// `temperature=` and `tokens=` echo the sampling parameters, the rest
+    /// are drawn from [`MOCK_VOCAB`] by a generator seeded with `seed` (or
+    /// the prompt when unset). `top_k` and `top_p` narrow the draw to the
+    /// first words of the vocabulary; temperature 0 always picks the first.
     fn generate_response(&self, input: &TextCompletionInput) -> String {
+        use std::fmt::Write;
+
         if let Some(ref fixed) = self.config.fixed_response {
             return fixed.clone();
         }
 
-        // Generate a predictable response based on input
-        let words: Vec<&str> = input.prompt.split_whitespace().collect();
-        let response_words = vec![
-            "The", "answer", "to", "your", "question", "is", "that",
-            "we", "need", "to", "consider", "multiple", "factors",
-            "including", "the", "context", "and", "available", "data",
-        ];
+        let params = &input.params;
+        let tokens = params.max_tokens as usize;
 
-        let max_words = (input.params.max_tokens / 4) as usize;
-        response_words
-            .iter()
-            .cycle()
-            .take(max_words.min(response_words.len()))
-            .cloned()
-            .collect::<Vec<_>>()
-            .join(" ")
+        let mut candidates = MOCK_VOCAB.len();
+        if params.top_k > 0 {
+            candidates = candidates.min(params.top_k as usize);
+        }
+        candidates = ((candidates as f32 * params.top_p.clamp(0.0, 1.0)).ceil() as usize).max(1);
+        if params.temperature <= 0.0 {
+            candidates = 1;
+        }
+
+        let mut state = params.seed.unwrap_or_else(|| fnv1a(input.prompt.as_bytes()));
+        let mut text = String::with_capacity(tokens * 8);
+        for i in 0..tokens {
+            if i > 0 {
+                text.push(' ');
+            }
+            let _ = match i {
+                0 => match params.seed {
+                    Some(seed) => write!(text, "seed={}", seed),
+                    None => write!(text, "seed=none"),
+                },
+                1 => write!(text, "temperature={:.2}", params.temperature),
+                2 => write!(text, "tokens={}", tokens),
+                _ => {
+                    state = splitmix64(state);
+                    text.push_str(MOCK_VOCAB[(state % candidates as u64) as usize]);
+                    Ok(())
+                }
+            };
+        }
+        text
     }
 
     /// Generate mock embeddings
-    fn generate_embeddings(&self, text: &str) -> Vec<f32> {
+    fn generate_embeddings(&self, text: &str, normalize: bool) -> Vec<f32> {
         // Generate deterministic embeddings based on text hash
         use sha2::{Sha256, Digest};
 
@@ -165,14 +288,9 @@ impl MockBackend {
             embeddings.push(value);
         }
 
-        // Normalize
-        let magnitude: f32 = embeddings.iter().map(|x| x * x).sum::<f32>().sqrt();
-        if magnitude > 0.0 {
-            for e in &mut embeddings {
-                *e /= magnitude;
-            }
+        if normalize {
+            l2_normalize(&mut embeddings);
         }
-
         embeddings
     }
 }
@@ -190,24 +308,40 @@ impl InferenceBackend for MockBackend {
     }
 
     fn capabilities(&self) -> BackendCapabilities {
+        let mut supported_tasks = vec![
+            TaskType::TextCompletion,
+            TaskType::Embeddings,
+            TaskType::Classification,
+            TaskType::QuestionAnswering,
+            TaskType::Summarization,
+        ];
+        if self.config.debug_tasks {
+            supported_tasks.push(TaskType::Debug);
+        }
+        if self.config.crawl_tasks {
+            supported_tasks.push(TaskType::WebCrawl);
+        }
+        if self.config.training_tasks {
+            supported_tasks.push(TaskType::TrainingBatch);
+        }
+
         BackendCapabilities {
             name: "mock",
-            supported_tasks: vec![
-                TaskType::TextCompletion,
-                TaskType::Embeddings,
-                TaskType::Classification,
-                TaskType::QuestionAnswering,
-                TaskType::Summarization,
-            ],
-            supports_training: false,
+            supported_tasks,
+            supports_training: self.config.training_tasks,
             supports_streaming: true,
             max_context_length: self.backend_config.context_size,
             max_batch_size: self.backend_config.batch_size,
-            gpu_available: false,
+            gpu_available: self.config.gpu_memory_mb.is_some(),
             gpu_device: None,
+            gpu_memory_mb: self.config.gpu_memory_mb,
         }
     }
 
+    fn warms_up_after_load(&self) -> bool {
+        self.backend_config.warmup_after_load
+    }
+
     async fn health_check(&self) -> Result<BackendHealth> {
         Ok(BackendHealth {
             operational: true,
@@ -215,6 +349,7 @@ impl InferenceBackend for MockBackend {
             memory_used_mb: 100, // Mock value
             gpu_memory_used_mb: None,
             error: None,
+            breaker: None,
         })
     }
 
@@ -237,9 +372,17 @@ impl InferenceBackend for MockBackend {
                 message: "Mock failure".to_string(),
             });
         }
+        if self.config.incompatible_models.contains(&spec.id) {
+            return Err(Error::ModelIncompatible {
+                model_id: spec.id.clone(),
+                reason: "Mock incompatible model".to_string(),
+            });
+        }
 
         // Simulate loading time
+        let start = Instant::now();
         tokio::time::sleep(Duration::from_millis(100)).await;
+        self.record_window("load_model", start);
 
         let info = LoadedModelInfo {
             spec: spec.clone(),
@@ -255,6 +398,11 @@ impl InferenceBackend for MockBackend {
         };
 
         *self.loaded_model.write() = Some(info.clone());
+
+        if self.backend_config.warmup_after_load {
+            super::warmup(self).await;
+        }
+
         Ok(info)
     }
 
@@ -280,6 +428,7 @@ impl InferenceBackend for MockBackend {
             num_heads: None,
             file_size: 0,
             sha256: None,
+            source_url: None,
         };
 
         self.load_model(&spec).await
@@ -301,11 +450,19 @@ impl InferenceBackend for MockBackend {
         self.loaded_model.read().is_some()
     }
 
+    fn loaded_model_id(&self) -> Option<String> {
+        self.loaded_model.read().as_ref().map(|m| m.spec.id.clone())
+    }
+
     async fn text_completion(
         &self,
         input: TextCompletionInput,
     ) -> Result<TextCompletionOutput> {
-        self.call_counts.write().text_completion += 1;
+        {
+            let mut counts = self.call_counts.write();
+            counts.text_completion += 1;
+            counts.last_system_prompt = input.system_prompt.clone();
+        }
 
         if self.config.fail_text_completion {
             return Err(Error::ExecutionFailed {
@@ -315,10 +472,11 @@ impl InferenceBackend for MockBackend {
         }
 
         let start = Instant::now();
+        let _working_set = self.allocate_working_set();
 
         // Generate response
         let text = self.generate_response(&input);
-        let completion_tokens = (text.split_whitespace().count() * 4 / 3) as u32;
+        let completion_tokens = text.split_whitespace().count() as u32;
         let prompt_tokens = (input.prompt.split_whitespace().count() * 4 / 3) as u32;
 
         // Simulate generation time
@@ -337,7 +495,11 @@ impl InferenceBackend for MockBackend {
         input: TextCompletionInput,
         callback: StreamCallback,
     ) -> Result<TextCompletionOutput> {
-        self.call_counts.write().text_completion += 1;
+        {
+            let mut counts = self.call_counts.write();
+            counts.text_completion += 1;
+            counts.last_system_prompt = input.system_prompt.clone();
+        }
 
         if self.config.fail_text_completion {
             return Err(Error::ExecutionFailed {
@@ -347,12 +509,17 @@ impl InferenceBackend for MockBackend {
         }
 
         let start = Instant::now();
+        let _working_set = self.allocate_working_set();
         let text = self.generate_response(&input);
         let words: Vec<&str> = text.split_whitespace().collect();
 
         let mut generated_text = String::new();
         let mut token_id = 0u32;
 
+        if self.config.prompt_latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(self.config.prompt_latency_ms)).await;
+        }
+
         for (i, word) in words.iter().enumerate() {
             let is_final = i == words.len() - 1;
             let token_text = if i == 0 {
@@ -385,6 +552,7 @@ impl InferenceBackend for MockBackend {
 
         let completion_tokens = token_id;
         let prompt_tokens = (input.prompt.split_whitespace().count() * 4 / 3) as u32;
+        self.record_window("text_completion", start);
 
         Ok(TextCompletionOutput {
             text: generated_text,
@@ -407,7 +575,7 @@ impl InferenceBackend for MockBackend {
         let embeddings: Vec<Vec<f32>> = input
             .texts
             .iter()
-            .map(|text| self.generate_embeddings(text))
+            .map(|text| self.generate_embeddings(text, input.normalize))
             .collect();
 
         let total_tokens = input.texts.iter()
@@ -439,6 +607,7 @@ impl InferenceBackend for MockBackend {
 
         Ok(ClassificationOutput {
             predictions,
+            calibration: ScoreCalibration::Raw,
             usage: TokenUsage::new(tokens, 0),
         })
     }
@@ -474,6 +643,115 @@ impl InferenceBackend for MockBackend {
             usage: TokenUsage::new(prompt_tokens, completion_tokens),
         })
     }
+
+    async fn debug(&self, input: DebugInput) -> Result<DebugOutput> {
+        if !self.config.debug_tasks {
+            return Err(Error::NotSupported("Debug tasks are disabled".to_string()));
+        }
+        Ok(DebugOutput {
+            text: input.op.apply(&input.text),
+        })
+    }
+
+    async fn web_crawl(&self, input: WebCrawlInput) -> Result<WebCrawlOutput> {
+        if !self.config.crawl_tasks {
+            return Err(Error::NotSupported("Crawl tasks are disabled".to_string()));
+        }
+        let start = Instant::now();
+        self.simulate_latency(input.max_pages.max(1)).await;
+        self.record_window("web_crawl", start);
+
+        Ok(WebCrawlOutput {
+            pages: Vec::new(),
+            total_fetched: 0,
+            total_text_chars: 0,
+            errors: Vec::new(),
+            crawl_errors: Vec::new(),
+        })
+    }
+
+    async fn train(&self, input: TrainingBatchInput) -> Result<TrainingBatchOutput> {
+        if !self.config.training_tasks {
+            return Err(Error::NotSupported("Training tasks are disabled".to_string()));
+        }
+        let start = Instant::now();
+        let examples = input.examples.len() as u32;
+        self.simulate_latency((examples * input.epochs).max(1)).await;
+        self.record_window("train", start);
+
+        // Loss halves every epoch
+        let loss_history: Vec<f32> = (0..input.epochs).map(|epoch| 0.5f32.powi(epoch as i32)).collect();
+        Ok(TrainingBatchOutput {
+            final_loss: loss_history.last().copied().unwrap_or(1.0),
+            loss_history,
+            lora_weights: None,
+            examples_processed: examples,
+        })
+    }
+
+    async fn forward_layers(&self, layer_start: u32, tensor_data: Vec<u8>) -> Result<LayerOutput> {
+        if self.config.shard_layers == 0 {
+            return Err(Error::NotSupported("Split-layer inference is disabled".to_string()));
+        }
+        let layers = self.config.shard_layers;
+        self.simulate_latency(layers).await;
+        Ok(LayerOutput {
+            layer_end: layer_start + layers,
+            tensor_data: tensor_data
+                .into_iter()
+                .map(|b| b.wrapping_add(layers as u8))
+                .collect(),
+        })
+    }
+
+    async fn decode_layers(
+        &self,
+        tensor_data: Vec<u8>,
+        params: &GenerationParams,
+    ) -> Result<TextCompletionOutput> {
+        if self.config.shard_layers == 0 {
+            return Err(Error::NotSupported("Split-layer inference is disabled".to_string()));
+        }
+        // One word per activation byte, so the output tracks every shard
+        let words: Vec<&str> = tensor_data
+            .iter()
+            .take(params.max_tokens as usize)
+            .map(|&b| MOCK_VOCAB[b as usize % MOCK_VOCAB.len()])
+            .collect();
+        let finish_reason = if words.len() < tensor_data.len() {
+            FinishReason::Length
+        } else {
+            FinishReason::Stop
+        };
+        Ok(TextCompletionOutput {
+            text: words.join(" "),
+            finish_reason,
+            usage: TokenUsage::new(0, words.len() as u32),
+            generation_time_ms: 0,
+        })
+    }
+}
+
+/// Words the mock samples its text completions from, most likely first
+const MOCK_VOCAB: &[&str] = &[
+    "the", "answer", "to", "your", "question", "is", "that", "we", "need",
+    "consider", "multiple", "factors", "including", "context", "and",
+    "available", "data",
+];
+
+/// FNV-1a hash, seeding the mock's sampling when the task sets no seed
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Next value of a SplitMix64 sequence
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -483,7 +761,6 @@ impl InferenceBackend for MockBackend {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::GenerationParams;
 
     #[tokio::test]
     async fn test_mock_text_completion() {
@@ -505,6 +782,64 @@ mod tests {
         assert!(result.usage.total_tokens > 0);
     }
 
+    fn completion_input(max_tokens: u32, seed: Option<u64>) -> TextCompletionInput {
+        TextCompletionInput {
+            prompt: "Hello, world!".to_string(),
+            system_prompt: None,
+            params: GenerationParams { max_tokens, seed, ..Default::default() },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_output_echoes_params() {
+        let backend = MockBackend::with_config(
+            MockConfig { token_latency_ms: 0, ..Default::default() },
+            BackendConfig::default(),
+        );
+
+        for max_tokens in [0, 1, 3, 17, 64] {
+            let result = backend.text_completion(completion_input(max_tokens, Some(7))).await.unwrap();
+            assert_eq!(result.text.split_whitespace().count(), max_tokens as usize);
+            assert_eq!(result.usage.completion_tokens, max_tokens);
+        }
+
+        let result = backend.text_completion(completion_input(8, Some(7))).await.unwrap();
+        let header: Vec<&str> = result.text.split_whitespace().take(3).collect();
+        assert_eq!(header, ["seed=7", "temperature=0.70", "tokens=8"]);
+
+        // Streaming produces the same tokens
+        let streamed = backend
+            .text_completion_stream(completion_input(8, Some(7)), Box::new(|_| true))
+            .await
+            .unwrap();
+        assert_eq!(streamed.text, result.text);
+    }
+
+    #[tokio::test]
+    async fn test_mock_output_varies_with_seed() {
+        let backend = MockBackend::with_config(
+            MockConfig { token_latency_ms: 0, ..Default::default() },
+            BackendConfig::default(),
+        );
+        let words = |text: String| text.split_whitespace().skip(3).map(String::from).collect::<Vec<_>>();
+
+        let a = words(backend.text_completion(completion_input(32, Some(1))).await.unwrap().text);
+        let again = words(backend.text_completion(completion_input(32, Some(1))).await.unwrap().text);
+        let b = words(backend.text_completion(completion_input(32, Some(2))).await.unwrap().text);
+        assert_eq!(a, again);
+        assert_ne!(a, b);
+
+        // Greedy and top-k 1 sampling always pick the most likely word
+        let mut greedy = completion_input(16, Some(1));
+        greedy.params.temperature = 0.0;
+        let mut top_one = completion_input(16, Some(2));
+        top_one.params.top_k = 1;
+        for input in [greedy, top_one] {
+            let text = backend.text_completion(input).await.unwrap().text;
+            assert!(words(text).iter().all(|w| w == MOCK_VOCAB[0]));
+        }
+    }
+
     #[tokio::test]
     async fn test_mock_embeddings() {
         let backend = MockBackend::new();
@@ -559,6 +894,39 @@ mod tests {
         assert_eq!(backend.call_count("text_completion"), 3);
     }
 
+    #[tokio::test]
+    async fn test_warmup_after_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.gguf");
+        std::fs::write(&path, b"GGUF").unwrap();
+
+        let mut backend = MockBackend::new();
+        backend.load_model_from_path(&path).await.unwrap();
+        assert_eq!(backend.call_count("text_completion"), 0);
+
+        let mut backend = MockBackend::with_config(
+            MockConfig::default(),
+            BackendConfig { warmup_after_load: true, ..Default::default() },
+        );
+        backend.load_model_from_path(&path).await.unwrap();
+        assert_eq!(backend.call_count("load_model"), 1);
+        assert_eq!(backend.call_count("text_completion"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_warmup_failure_not_fatal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.gguf");
+        std::fs::write(&path, b"GGUF").unwrap();
+
+        let mut backend = MockBackend::with_config(
+            MockConfig { fail_text_completion: true, ..Default::default() },
+            BackendConfig { warmup_after_load: true, ..Default::default() },
+        );
+        assert!(backend.load_model_from_path(&path).await.is_ok());
+        assert_eq!(backend.call_count("text_completion"), 1);
+    }
+
     #[tokio::test]
     async fn test_health_check() {
         let backend = MockBackend::new();
@@ -577,5 +945,7 @@ mod tests {
         assert!(caps.supported_tasks.contains(&TaskType::TextCompletion));
         assert!(caps.supported_tasks.contains(&TaskType::Embeddings));
         assert!(!caps.supports_training);
+        // DEBUG tasks stay hidden unless explicitly enabled
+        assert!(!caps.supported_tasks.contains(&TaskType::Debug));
     }
 }
@@ -5,20 +5,29 @@
 
 mod traits;
 mod registry;
+mod breaker;
+mod calibration;
 mod cpu;
 mod crawler;
+mod evidence;
 mod mock;
 mod openai;
+mod openai_router;
 
 #[cfg(feature = "gpu")]
 mod vulkan;
 
 pub use traits::*;
 pub use registry::*;
+pub use breaker::{BreakerConfig, BreakerState, CircuitBreaker};
+pub use calibration::ClassificationStrategy;
 pub use cpu::CpuBackend;
-pub use crawler::CrawlerBackend;
-pub use mock::MockBackend;
+pub use crawler::{CrawlerBackend, DomainDenylist, HostRateLimiter};
+pub use mock::{MockBackend, MockConfig};
+#[cfg(test)]
+pub use mock::MockCallCounts;
 pub use openai::{OpenAiBackend, OpenAiConfig};
+pub use openai_router::OpenAiRouter;
 
 #[cfg(feature = "gpu")]
 pub use vulkan::{VulkanBackend, VulkanBackendConfig, create_vulkan_backend, create_vulkan_backend_for_device};
@@ -4,15 +4,19 @@
 //! API endpoint (OpenAI, Ollama, vLLM, LM Studio, etc.).
 
 use async_trait::async_trait;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
 use crate::error::{Error, Result};
 use crate::types::{
+    ClassificationInput, ClassificationOutput, ScoreCalibration,
     EmbeddingsInput, EmbeddingsOutput,
     FinishReason, GgufMetadata, LoadedModelInfo, ModelFormat, ModelSpec,
     QuestionAnsweringInput, QuestionAnsweringOutput,
@@ -20,11 +24,20 @@ use crate::types::{
     TaskType, TextCompletionInput, TextCompletionOutput, TokenUsage,
 };
 
+use super::calibration::{self, ClassificationStrategy};
+use super::evidence;
 use super::{
-    BackendCapabilities, BackendHealth, InferenceBackend,
-    ResourceUsage,
+    BackendCapabilities, BackendHealth, BreakerConfig, BreakerState, CircuitBreaker,
+    InferenceBackend, ResourceUsage,
 };
 
+/// Number of alternative first tokens requested for logprob scoring
+/// (the OpenAI API maximum)
+const CLASSIFY_TOP_LOGPROBS: u32 = 20;
+
+/// Longest wait honoured from a `Retry-After` header
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
 // ─────────────────────────────────────────────────────────────────
 // Configuration
 // ─────────────────────────────────────────────────────────────────
@@ -46,6 +59,58 @@ pub struct OpenAiConfig {
 
     /// Maximum retries on transient errors
     pub max_retries: u32,
+
+    /// How classification tasks are scored
+    #[serde(default)]
+    pub classification_strategy: ClassificationStrategy,
+
+    /// Calibrate classification scores into a distribution over the labels
+    #[serde(default = "default_true")]
+    pub calibrate_classification: bool,
+
+    /// Most texts sent in one embeddings request; larger inputs are split
+    #[serde(default = "default_embeddings_batch_size")]
+    pub embeddings_batch_size: usize,
+
+    /// How long a health check result is reused before `/models` is asked
+    /// again (ms, 0 = always ask)
+    #[serde(default = "default_health_cache_ttl_ms")]
+    pub health_cache_ttl_ms: u64,
+
+    /// Consecutive failed requests (endpoint unreachable or server errors,
+    /// after retries) that open the circuit breaker (0 = never open)
+    #[serde(default = "default_breaker_max_failures")]
+    pub breaker_max_failures: u32,
+
+    /// How long an open breaker fails requests fast before a single probe
+    /// request is let through (ms)
+    #[serde(default = "default_breaker_open_ms")]
+    pub breaker_open_ms: u64,
+
+    /// Headers added to every request (e.g. `OpenAI-Organization`, or
+    /// `HTTP-Referer`/`X-Title` for gateways)
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_embeddings_batch_size() -> usize {
+    64
+}
+
+fn default_health_cache_ttl_ms() -> u64 {
+    10_000
+}
+
+fn default_breaker_max_failures() -> u32 {
+    5
+}
+
+fn default_breaker_open_ms() -> u64 {
+    30_000
 }
 
 impl Default for OpenAiConfig {
@@ -56,6 +121,13 @@ impl Default for OpenAiConfig {
             default_model: "llama3".to_string(),
             timeout_secs: 120,
             max_retries: 2,
+            classification_strategy: ClassificationStrategy::default(),
+            calibrate_classification: true,
+            embeddings_batch_size: default_embeddings_batch_size(),
+            health_cache_ttl_ms: default_health_cache_ttl_ms(),
+            breaker_max_failures: default_breaker_max_failures(),
+            breaker_open_ms: default_breaker_open_ms(),
+            extra_headers: HashMap::new(),
         }
     }
 }
@@ -78,6 +150,10 @@ struct ChatCompletionRequest {
     stop: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_logprobs: Option<u32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -96,6 +172,28 @@ struct ChatCompletionResponse {
 struct ChatChoice {
     message: ChatChoiceMessage,
     finish_reason: Option<String>,
+    #[serde(default)]
+    logprobs: Option<ChoiceLogprobs>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChoiceLogprobs {
+    #[serde(default)]
+    content: Option<Vec<TokenLogprob>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenLogprob {
+    token: String,
+    logprob: f32,
+    #[serde(default)]
+    top_logprobs: Vec<TopLogprob>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TopLogprob {
+    token: String,
+    logprob: f32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -124,6 +222,9 @@ struct EmbeddingsApiResponse {
 
 #[derive(Debug, Deserialize)]
 struct EmbeddingData {
+    /// Position of the input text in the request
+    #[serde(default)]
+    index: Option<usize>,
     embedding: Vec<f32>,
 }
 
@@ -131,6 +232,44 @@ struct EmbeddingData {
 // OpenAI Backend
 // ─────────────────────────────────────────────────────────────────
 
+/// Header map for the configured extra headers. Invalid entries are logged
+/// and skipped (config validation normally rejects them earlier).
+fn extra_header_map(headers: &HashMap<String, String>) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let logged = crate::coordinator::redact_header(name, value);
+        match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            (Ok(header_name), Ok(header_value)) => {
+                debug!(header = %name, value = %logged, "OpenAI request header");
+                map.insert(header_name, header_value);
+            }
+            _ => warn!(header = %name, value = %logged, "Skipping invalid OpenAI request header"),
+        }
+    }
+    map
+}
+
+/// Wait requested by a `Retry-After` header, given as delay seconds or an
+/// HTTP date, capped at [`MAX_RETRY_AFTER`]
+fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(secs) => Duration::from_secs(secs),
+        Err(_) => {
+            let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+            (at - now).to_std().unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+/// Delay before retry `attempt` (from 1): exponential backoff, extended to
+/// any wait the server asked for
+fn retry_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    let backoff = Duration::from_millis(500 * 2u64.pow(attempt - 1));
+    retry_after.map_or(backoff, |wait| wait.max(backoff))
+}
+
 /// OpenAI-compatible API backend for inference
 pub struct OpenAiBackend {
     config: OpenAiConfig,
@@ -138,6 +277,10 @@ pub struct OpenAiBackend {
     model_id: RwLock<String>,
     total_requests: RwLock<u64>,
     total_tokens: RwLock<u64>,
+    /// Last health check result and when it was taken
+    health_cache: RwLock<Option<(Instant, BackendHealth)>>,
+    /// Fails requests fast while the endpoint is down
+    breaker: Mutex<CircuitBreaker>,
 }
 
 impl OpenAiBackend {
@@ -145,10 +288,17 @@ impl OpenAiBackend {
     pub fn new(config: OpenAiConfig) -> Self {
         let client = Client::builder()
             .timeout(Duration::from_secs(config.timeout_secs))
+            .default_headers(extra_header_map(&config.extra_headers))
             .build()
             .expect("Failed to create HTTP client");
 
         let model_id = config.default_model.clone();
+        let breaker = CircuitBreaker::new(BreakerConfig {
+            max_failures: config.breaker_max_failures,
+            // Only consecutive failures count, however far apart
+            window: Duration::MAX,
+            open_for: Duration::from_millis(config.breaker_open_ms),
+        });
 
         info!(
             base_url = %config.base_url,
@@ -162,9 +312,47 @@ impl OpenAiBackend {
             model_id: RwLock::new(model_id),
             total_requests: RwLock::new(0),
             total_tokens: RwLock::new(0),
+            health_cache: RwLock::new(None),
+            breaker: Mutex::new(breaker),
+        }
+    }
+
+    /// Fail fast while the circuit breaker is open, so the task goes back to
+    /// the coordinator instead of waiting out retries against a dead endpoint
+    fn admit(&self) -> Result<()> {
+        if self.breaker.lock().try_acquire() {
+            return Ok(());
+        }
+        Err(Error::ExecutionFailed {
+            task_id: None,
+            message: format!("Circuit breaker open for {}, not sending request", self.config.base_url),
+        })
+    }
+
+    /// Feed a request's outcome to the circuit breaker. Any answer from the
+    /// server short of a server error counts as `reachable`.
+    fn record_outcome(&self, reachable: bool) {
+        let transition = {
+            let mut breaker = self.breaker.lock();
+            if reachable { breaker.record_success() } else { breaker.record_failure() }
+        };
+        match transition {
+            Some(BreakerState::Open) => warn!(
+                base_url = %self.config.base_url,
+                open_ms = self.config.breaker_open_ms,
+                "Endpoint failing repeatedly, circuit breaker open"
+            ),
+            Some(state) => info!(base_url = %self.config.base_url, state = %state, "Endpoint recovered, circuit breaker closed"),
+            None => {}
         }
     }
 
+    /// Drop the cached health check, so the next one asks the server; called
+    /// when a request fails so a broken endpoint shows up promptly
+    fn invalidate_health(&self) {
+        *self.health_cache.write() = None;
+    }
+
     /// Build the authorization header value (if API key is set)
     fn auth_header(&self) -> Option<String> {
         if self.config.api_key.is_empty() {
@@ -174,6 +362,64 @@ impl OpenAiBackend {
         }
     }
 
+    /// Embed one batch of texts, returning a vector per text in input order
+    /// and the prompt tokens used
+    async fn embeddings_batch(&self, model_id: &str, texts: &[String]) -> Result<(Vec<Vec<f32>>, u32)> {
+        let url = format!("{}/embeddings", self.config.base_url);
+        let request_body = EmbeddingsApiRequest {
+            model: model_id.to_string(),
+            input: texts.to_vec(),
+        };
+
+        let mut req = self.client.post(&url).json(&request_body);
+        if let Some(ref auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+
+        self.admit()?;
+        let response = req.send().await.map_err(|e| {
+            self.record_outcome(false);
+            Error::ExecutionFailed {
+                task_id: None,
+                message: format!("Embeddings request failed: {}", e),
+            }
+        })?;
+        self.record_outcome(!response.status().is_server_error());
+
+        if !response.status().is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::ExecutionFailed {
+                task_id: None,
+                message: format!("Embeddings API error: {}", body),
+            });
+        }
+
+        let mut parsed: EmbeddingsApiResponse =
+            response.json().await.map_err(|e| Error::ExecutionFailed {
+                task_id: None,
+                message: format!("Failed to parse embeddings response: {}", e),
+            })?;
+        *self.total_requests.write() += 1;
+
+        if parsed.data.len() != texts.len() {
+            return Err(Error::ExecutionFailed {
+                task_id: None,
+                message: format!(
+                    "Embeddings API returned {} vectors for {} inputs",
+                    parsed.data.len(),
+                    texts.len()
+                ),
+            });
+        }
+        // Servers may list vectors out of order; `index` ties each to its input
+        if parsed.data.iter().all(|d| d.index.is_some()) {
+            parsed.data.sort_by_key(|d| d.index);
+        }
+
+        let tokens = parsed.usage.map_or(0, |u| u.prompt_tokens);
+        Ok((parsed.data.into_iter().map(|d| d.embedding).collect(), tokens))
+    }
+
     /// Make a chat completion request with retry logic
     async fn chat_completion(
         &self,
@@ -184,29 +430,53 @@ impl OpenAiBackend {
         stop: Option<Vec<String>>,
         seed: Option<u64>,
     ) -> Result<(String, FinishReason, TokenUsage)> {
-        let model_id = self.model_id.read().clone();
-
         let request_body = ChatCompletionRequest {
-            model: model_id.clone(),
+            model: self.model_id.read().clone(),
             messages,
             max_tokens,
             temperature,
             top_p,
             stop,
             seed,
+            logprobs: None,
+            top_logprobs: None,
+        };
+
+        let (choice, usage) = self
+            .send_chat_request(&request_body)
+            .await
+            .inspect_err(|_| self.invalidate_health())?;
+
+        let text = choice.message.content.unwrap_or_default();
+        let finish_reason = match choice.finish_reason.as_deref() {
+            Some("stop") => FinishReason::Stop,
+            Some("length") => FinishReason::Length,
+            Some("content_filter") => FinishReason::ContentFilter,
+            _ => FinishReason::Stop,
         };
 
+        Ok((text, finish_reason, usage))
+    }
+
+    /// POST a chat completion request, retrying transient failures, and
+    /// return the first choice together with token usage
+    async fn send_chat_request(
+        &self,
+        request_body: &ChatCompletionRequest,
+    ) -> Result<(ChatChoice, TokenUsage)> {
+        self.admit()?;
         let url = format!("{}/chat/completions", self.config.base_url);
         let mut last_error: Option<Error> = None;
+        let mut server_wait: Option<Duration> = None;
 
         for attempt in 0..=self.config.max_retries {
             if attempt > 0 {
-                let backoff = Duration::from_millis(500 * 2u64.pow(attempt - 1));
+                let backoff = retry_delay(attempt, server_wait.take());
                 debug!(attempt, ?backoff, "Retrying after error");
                 tokio::time::sleep(backoff).await;
             }
 
-            let mut req = self.client.post(&url).json(&request_body);
+            let mut req = self.client.post(&url).json(request_body);
             if let Some(ref auth) = self.auth_header() {
                 req = req.header("Authorization", auth);
             }
@@ -218,23 +488,9 @@ impl OpenAiBackend {
                     if status.is_success() {
                         match response.json::<ChatCompletionResponse>().await {
                             Ok(parsed) => {
+                                self.record_outcome(true);
                                 *self.total_requests.write() += 1;
 
-                                let choice = parsed.choices.first().ok_or_else(|| {
-                                    Error::ExecutionFailed {
-                                        task_id: None,
-                                        message: "No choices in API response".to_string(),
-                                    }
-                                })?;
-
-                                let text = choice.message.content.clone().unwrap_or_default();
-                                let finish_reason = match choice.finish_reason.as_deref() {
-                                    Some("stop") => FinishReason::Stop,
-                                    Some("length") => FinishReason::Length,
-                                    Some("content_filter") => FinishReason::ContentFilter,
-                                    _ => FinishReason::Stop,
-                                };
-
                                 let usage = if let Some(u) = parsed.usage {
                                     *self.total_tokens.write() += u.total_tokens as u64;
                                     TokenUsage::new(u.prompt_tokens, u.completion_tokens)
@@ -242,7 +498,14 @@ impl OpenAiBackend {
                                     TokenUsage::new(0, 0)
                                 };
 
-                                return Ok((text, finish_reason, usage));
+                                let choice = parsed.choices.into_iter().next().ok_or_else(|| {
+                                    Error::ExecutionFailed {
+                                        task_id: None,
+                                        message: "No choices in API response".to_string(),
+                                    }
+                                })?;
+
+                                return Ok((choice, usage));
                             }
                             Err(e) => {
                                 last_error = Some(Error::ExecutionFailed {
@@ -252,7 +515,10 @@ impl OpenAiBackend {
                             }
                         }
                     } else if status.as_u16() == 429 || status.is_server_error() {
-                        // Retryable error
+                        // Retryable error; a rate limit may say how long to wait
+                        if status.as_u16() == 429 {
+                            server_wait = retry_after(response.headers(), Utc::now());
+                        }
                         let body = response.text().await.unwrap_or_default();
                         warn!(status = %status, attempt, "Retryable API error: {}", body);
                         last_error = Some(Error::ExecutionFailed {
@@ -260,7 +526,8 @@ impl OpenAiBackend {
                             message: format!("API error {}: {}", status, body),
                         });
                     } else {
-                        // Non-retryable error
+                        // Non-retryable error; the server itself is up
+                        self.record_outcome(true);
                         let body = response.text().await.unwrap_or_default();
                         return Err(Error::ExecutionFailed {
                             task_id: None,
@@ -276,6 +543,7 @@ impl OpenAiBackend {
                             message: format!("Connection error: {}", e),
                         });
                     } else {
+                        self.record_outcome(false);
                         return Err(Error::ExecutionFailed {
                             task_id: None,
                             message: format!("Request error: {}", e),
@@ -285,11 +553,131 @@ impl OpenAiBackend {
             }
         }
 
+        self.record_outcome(false);
         Err(last_error.unwrap_or_else(|| Error::ExecutionFailed {
             task_id: None,
             message: "All retry attempts exhausted".to_string(),
         }))
     }
+
+    /// Classify by prompting the model for a label.
+    ///
+    /// When calibration is enabled, logprobs of the first generated token are
+    /// requested and turned into a distribution over the label set. Servers
+    /// that don't return logprobs fall back to raw label matching.
+    async fn classify_generative(&self, input: &ClassificationInput) -> Result<ClassificationOutput> {
+        let calibrate = self.config.calibrate_classification;
+        let request_body = ChatCompletionRequest {
+            model: self.model_id.read().clone(),
+            messages: vec![
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: format!(
+                        "Classify the text into exactly one of these labels: {}. \
+                         Respond with the label only.",
+                        input.labels.join(", ")
+                    ),
+                },
+                ChatMessage {
+                    role: "user".to_string(),
+                    content: input.text.clone(),
+                },
+            ],
+            max_tokens: Some(16),
+            temperature: Some(0.0),
+            top_p: None,
+            stop: None,
+            seed: None,
+            logprobs: calibrate.then_some(true),
+            top_logprobs: calibrate.then_some(CLASSIFY_TOP_LOGPROBS),
+        };
+
+        let (choice, usage) = self
+            .send_chat_request(&request_body)
+            .await
+            .inspect_err(|_| self.invalidate_health())?;
+
+        if calibrate {
+            let candidates: Option<Vec<(String, f32)>> = choice
+                .logprobs
+                .as_ref()
+                .and_then(|l| l.content.as_ref())
+                .and_then(|c| c.first())
+                .map(|first| {
+                    let mut candidates: Vec<(String, f32)> = first
+                        .top_logprobs
+                        .iter()
+                        .map(|t| (t.token.clone(), t.logprob))
+                        .collect();
+                    if candidates.is_empty() {
+                        candidates.push((first.token.clone(), first.logprob));
+                    }
+                    candidates
+                });
+
+            if let Some(predictions) = candidates
+                .as_deref()
+                .and_then(|c| calibration::score_logprobs(&input.labels, c))
+            {
+                return Ok(ClassificationOutput {
+                    predictions,
+                    calibration: ScoreCalibration::Logprob,
+                    usage,
+                });
+            }
+            debug!("No usable logprobs in response, falling back to raw label scores");
+        }
+
+        let text = choice.message.content.unwrap_or_default();
+        Ok(ClassificationOutput {
+            predictions: calibration::score_generated_label(&input.labels, &text),
+            calibration: ScoreCalibration::Raw,
+            usage,
+        })
+    }
+
+    /// Classify by cosine similarity between text and label embeddings
+    async fn classify_by_embeddings(&self, input: &ClassificationInput) -> Result<ClassificationOutput> {
+        let mut texts = Vec::with_capacity(input.labels.len() + 1);
+        texts.push(input.text.clone());
+        texts.extend(input.labels.iter().cloned());
+
+        let output = self
+            .embeddings(EmbeddingsInput {
+                texts,
+                normalize: true,
+            })
+            .await?;
+
+        if output.embeddings.len() != input.labels.len() + 1 {
+            return Err(Error::ExecutionFailed {
+                task_id: None,
+                message: format!(
+                    "Embeddings API returned {} vectors, expected {}",
+                    output.embeddings.len(),
+                    input.labels.len() + 1
+                ),
+            });
+        }
+
+        let text_vec = &output.embeddings[0];
+        let similarities: Vec<f32> = output.embeddings[1..]
+            .iter()
+            .map(|label_vec| calibration::cosine_similarity(text_vec, label_vec))
+            .collect();
+
+        let (predictions, calibration) = calibration::score_similarities(
+            &input.labels,
+            &similarities,
+            self.config.calibrate_classification,
+        );
+
+        Ok(ClassificationOutput {
+            predictions,
+            calibration,
+            usage: output.usage,
+        })
+    }
 }
 
 #[async_trait]
@@ -304,6 +692,7 @@ impl InferenceBackend for OpenAiBackend {
             supported_tasks: vec![
                 TaskType::TextCompletion,
                 TaskType::Embeddings,
+                TaskType::Classification,
                 TaskType::QuestionAnswering,
                 TaskType::Summarization,
             ],
@@ -313,39 +702,53 @@ impl InferenceBackend for OpenAiBackend {
             max_batch_size: 1,
             gpu_available: false,
             gpu_device: None,
+            gpu_memory_mb: None,
         }
     }
 
     async fn health_check(&self) -> Result<BackendHealth> {
+        let ttl = Duration::from_millis(self.config.health_cache_ttl_ms);
+        let breaker = Some(self.breaker.lock().state());
+        if let Some((at, health)) = self.health_cache.read().as_ref() {
+            if at.elapsed() < ttl {
+                return Ok(BackendHealth { breaker, ..health.clone() });
+            }
+        }
+
         let url = format!("{}/models", self.config.base_url);
         let mut req = self.client.get(&url);
         if let Some(ref auth) = self.auth_header() {
             req = req.header("Authorization", auth);
         }
 
-        match req.send().await {
-            Ok(resp) if resp.status().is_success() => Ok(BackendHealth {
+        let health = match req.send().await {
+            Ok(resp) if resp.status().is_success() => BackendHealth {
                 operational: true,
                 model_loaded: true,
                 memory_used_mb: 0,
                 gpu_memory_used_mb: None,
                 error: None,
-            }),
-            Ok(resp) => Ok(BackendHealth {
+                breaker,
+            },
+            Ok(resp) => BackendHealth {
                 operational: false,
                 model_loaded: false,
                 memory_used_mb: 0,
                 gpu_memory_used_mb: None,
                 error: Some(format!("API returned status {}", resp.status())),
-            }),
-            Err(e) => Ok(BackendHealth {
+                breaker,
+            },
+            Err(e) => BackendHealth {
                 operational: false,
                 model_loaded: false,
                 memory_used_mb: 0,
                 gpu_memory_used_mb: None,
                 error: Some(format!("Connection failed: {}", e)),
-            }),
-        }
+                breaker,
+            },
+        };
+        *self.health_cache.write() = Some((Instant::now(), health.clone()));
+        Ok(health)
     }
 
     fn resource_usage(&self) -> ResourceUsage {
@@ -397,6 +800,7 @@ impl InferenceBackend for OpenAiBackend {
             num_heads: None,
             file_size: 0,
             sha256: None,
+            source_url: None,
         };
 
         self.load_model(&spec).await
@@ -415,6 +819,10 @@ impl InferenceBackend for OpenAiBackend {
         true // API is always "ready" — model lives on the server
     }
 
+    fn loaded_model_id(&self) -> Option<String> {
+        Some(self.model_id.read().clone())
+    }
+
     async fn text_completion(
         &self,
         input: TextCompletionInput,
@@ -460,57 +868,59 @@ impl InferenceBackend for OpenAiBackend {
 
     async fn embeddings(&self, input: EmbeddingsInput) -> Result<EmbeddingsOutput> {
         let model_id = self.model_id.read().clone();
-        let url = format!("{}/embeddings", self.config.base_url);
+        let batch_size = self.config.embeddings_batch_size.max(1);
+
+        let mut embeddings = Vec::with_capacity(input.texts.len());
+        let mut dimensions = None;
+        let mut prompt_tokens = 0;
+        for batch in input.texts.chunks(batch_size) {
+            let (vectors, tokens) = self
+                .embeddings_batch(&model_id, batch)
+                .await
+                .inspect_err(|_| self.invalidate_health())?;
+            for vector in &vectors {
+                match dimensions {
+                    None => dimensions = Some(vector.len()),
+                    Some(d) if d != vector.len() => {
+                        return Err(Error::ExecutionFailed {
+                            task_id: None,
+                            message: format!(
+                                "Embeddings API returned {}-dimensional vectors after {}-dimensional ones",
+                                vector.len(),
+                                d
+                            ),
+                        });
+                    }
+                    Some(_) => {}
+                }
+            }
+            embeddings.extend(vectors);
+            prompt_tokens += tokens;
+        }
 
-        let request_body = EmbeddingsApiRequest {
-            model: model_id,
-            input: input.texts.clone(),
+        let mut output = EmbeddingsOutput {
+            embeddings,
+            dimensions: dimensions.unwrap_or(0),
+            usage: TokenUsage::new(prompt_tokens, 0),
         };
-
-        let mut req = self.client.post(&url).json(&request_body);
-        if let Some(ref auth) = self.auth_header() {
-            req = req.header("Authorization", auth);
+        // Servers differ in whether they normalize
+        if input.normalize {
+            output.l2_normalize();
         }
+        Ok(output)
+    }
 
-        let response = req.send().await.map_err(|e| Error::ExecutionFailed {
-            task_id: None,
-            message: format!("Embeddings request failed: {}", e),
-        })?;
-
-        if !response.status().is_success() {
-            let body = response.text().await.unwrap_or_default();
-            return Err(Error::ExecutionFailed {
-                task_id: None,
-                message: format!("Embeddings API error: {}", body),
-            });
+    async fn classify(&self, input: ClassificationInput) -> Result<ClassificationOutput> {
+        if input.labels.is_empty() {
+            return Err(Error::Execution(
+                "Classification requires at least one label".to_string(),
+            ));
         }
 
-        let parsed: EmbeddingsApiResponse =
-            response.json().await.map_err(|e| Error::ExecutionFailed {
-                task_id: None,
-                message: format!("Failed to parse embeddings response: {}", e),
-            })?;
-
-        let dimensions = parsed
-            .data
-            .first()
-            .map(|d| d.embedding.len())
-            .unwrap_or(0);
-
-        let embeddings: Vec<Vec<f32>> = parsed.data.into_iter().map(|d| d.embedding).collect();
-
-        let usage = parsed
-            .usage
-            .map(|u| TokenUsage::new(u.prompt_tokens, 0))
-            .unwrap_or_else(|| TokenUsage::new(0, 0));
-
-        *self.total_requests.write() += 1;
-
-        Ok(EmbeddingsOutput {
-            embeddings,
-            dimensions,
-            usage,
-        })
+        match self.config.classification_strategy {
+            ClassificationStrategy::Generative => self.classify_generative(&input).await,
+            ClassificationStrategy::Embeddings => self.classify_by_embeddings(&input).await,
+        }
     }
 
     async fn question_answering(
@@ -520,7 +930,7 @@ impl InferenceBackend for OpenAiBackend {
         let messages = vec![
             ChatMessage {
                 role: "system".to_string(),
-                content: "Answer the question based on the provided context. Be concise and accurate.".to_string(),
+                content: evidence::QA_SYSTEM_PROMPT.to_string(),
             },
             ChatMessage {
                 role: "user".to_string(),
@@ -542,10 +952,13 @@ impl InferenceBackend for OpenAiBackend {
             )
             .await?;
 
+        let reply = evidence::parse_reply(&text);
+        let evidence_spans = evidence::evidence_spans(&input.context, &reply.answer, &reply.quotes);
+
         Ok(QuestionAnsweringOutput {
-            answer: text,
-            confidence: None,
-            evidence_spans: vec![],
+            answer: reply.answer,
+            confidence: reply.confidence,
+            evidence_spans,
             usage,
         })
     }
@@ -609,6 +1022,7 @@ impl InferenceBackend for OpenAiBackend {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{http_stub, Response};
 
     #[test]
     fn test_default_config() {
@@ -634,6 +1048,7 @@ mod tests {
         assert_eq!(caps.name, "openai");
         assert!(caps.supported_tasks.contains(&TaskType::TextCompletion));
         assert!(caps.supported_tasks.contains(&TaskType::Embeddings));
+        assert!(caps.supported_tasks.contains(&TaskType::Classification));
         assert!(caps.supported_tasks.contains(&TaskType::QuestionAnswering));
         assert!(caps.supported_tasks.contains(&TaskType::Summarization));
         assert!(!caps.supports_training);
@@ -652,6 +1067,322 @@ mod tests {
         assert_eq!(no_key.auth_header(), None);
     }
 
+    #[tokio::test]
+    async fn test_extra_headers_sent_with_chat_completion() {
+        let (tx, mut requests) = tokio::sync::mpsc::unbounded_channel();
+        let base_url = http_stub(move |request| {
+            let _ = tx.send(request);
+            Response::json(
+                200,
+                r#"{"choices":[{"message":{"role":"assistant","content":"hi"},"finish_reason":"stop"}]}"#,
+            )
+        })
+        .await;
+
+        let config = OpenAiConfig {
+            base_url,
+            api_key: "sk-test-123".to_string(),
+            max_retries: 0,
+            extra_headers: HashMap::from([
+                ("OpenAI-Organization".to_string(), "org-42".to_string()),
+                ("X-Title".to_string(), "AI4All Worker".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let backend = OpenAiBackend::new(config);
+        let (text, _, _) = backend
+            .chat_completion(vec![], Some(4), None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(text, "hi");
+
+        let request = requests.recv().await.unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/chat/completions"));
+        assert_eq!(request.header("OpenAI-Organization"), Some("org-42"));
+        assert_eq!(request.header("X-Title"), Some("AI4All Worker"));
+        assert_eq!(request.header("Authorization"), Some("Bearer sk-test-123"));
+    }
+
+    #[tokio::test]
+    async fn test_question_answering_returns_evidence_spans() {
+        let base_url = http_stub(|_| {
+            let content = serde_json::json!({
+                "answer": "In 1889.",
+                "quotes": ["completed in 1889"],
+                "confidence": 0.9
+            });
+            Response::json(
+                200,
+                serde_json::json!({
+                    "choices": [{
+                        "message": { "role": "assistant", "content": content.to_string() },
+                        "finish_reason": "stop"
+                    }]
+                }),
+            )
+        })
+        .await;
+
+        let backend = OpenAiBackend::new(OpenAiConfig {
+            base_url,
+            max_retries: 0,
+            ..Default::default()
+        });
+        let context = "La tour Eiffel, à Paris, was completed in 1889.";
+        let output = backend
+            .question_answering(QuestionAnsweringInput {
+                question: "When was it completed?".to_string(),
+                context: context.to_string(),
+                params: Default::default(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(output.answer, "In 1889.");
+        assert_eq!(output.confidence, Some(0.9));
+        let quoted: Vec<&str> = output.evidence_spans.iter().map(|&(s, e)| &context[s..e]).collect();
+        assert_eq!(quoted, ["completed in 1889"]);
+    }
+
+    /// Embeddings endpoint answering each request with one vector per input
+    /// (`[i, 0, ...]` for input "text-i", `dims(request)` long, listed in
+    /// reverse with their indices), reporting each request's input count
+    async fn serve_embeddings(
+        dims: fn(usize) -> usize,
+    ) -> (String, tokio::sync::mpsc::UnboundedReceiver<usize>) {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let requests = std::sync::atomic::AtomicUsize::new(0);
+        let base_url = http_stub(move |request| {
+            let dims = dims(requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst));
+            let body = request.json();
+            let inputs = body["input"].as_array().unwrap();
+            let _ = tx.send(inputs.len());
+            let data: Vec<serde_json::Value> = inputs
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(index, text)| {
+                    let n: f32 = text.as_str().unwrap().trim_start_matches("text-").parse().unwrap();
+                    let mut embedding = vec![0.0; dims];
+                    embedding[0] = n;
+                    serde_json::json!({ "index": index, "embedding": embedding })
+                })
+                .collect();
+            Response::json(
+                200,
+                serde_json::json!({
+                    "data": data,
+                    "usage": { "prompt_tokens": inputs.len(), "completion_tokens": 0, "total_tokens": inputs.len() }
+                }),
+            )
+        })
+        .await;
+        (base_url, rx)
+    }
+
+    fn embeddings_input(count: usize) -> EmbeddingsInput {
+        EmbeddingsInput {
+            texts: (0..count).map(|i| format!("text-{}", i)).collect(),
+            normalize: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_batched_in_order() {
+        let (base_url, mut requests) = serve_embeddings(|_| 4).await;
+        let backend = OpenAiBackend::new(OpenAiConfig {
+            base_url,
+            max_retries: 0,
+            embeddings_batch_size: 64,
+            ..Default::default()
+        });
+
+        let mut input = embeddings_input(150);
+        input.normalize = false;
+        let output = backend.embeddings(input).await.unwrap();
+        assert_eq!(output.embeddings.len(), 150);
+        for (i, vector) in output.embeddings.iter().enumerate() {
+            assert_eq!(vector[0], i as f32);
+        }
+        assert_eq!(output.dimensions, 4);
+        assert_eq!(output.usage.prompt_tokens, 150);
+
+        let batches: Vec<usize> = std::iter::from_fn(|| requests.try_recv().ok()).collect();
+        assert_eq!(batches, [64, 64, 22]);
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_normalized_on_request() {
+        let (base_url, _) = serve_embeddings(|_| 4).await;
+        let backend = OpenAiBackend::new(OpenAiConfig {
+            base_url,
+            max_retries: 0,
+            ..Default::default()
+        });
+        let magnitude = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+        let output = backend.embeddings(embeddings_input(3)).await.unwrap();
+        assert_eq!(output.embeddings[0], vec![0.0; 4]);
+        for vector in &output.embeddings[1..] {
+            assert!((magnitude(vector) - 1.0).abs() < 1e-6);
+        }
+
+        let mut raw = embeddings_input(3);
+        raw.normalize = false;
+        let output = backend.embeddings(raw).await.unwrap();
+        assert_eq!(magnitude(&output.embeddings[1]), 1.0);
+        assert_eq!(magnitude(&output.embeddings[2]), 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_dimension_mismatch_across_batches() {
+        let (base_url, _) = serve_embeddings(|request| if request == 0 { 4 } else { 8 }).await;
+        let backend = OpenAiBackend::new(OpenAiConfig {
+            base_url,
+            max_retries: 0,
+            embeddings_batch_size: 2,
+            ..Default::default()
+        });
+
+        let err = backend.embeddings(embeddings_input(3)).await.unwrap_err();
+        assert!(err.to_string().contains("8-dimensional vectors after 4-dimensional"));
+    }
+
+    #[tokio::test]
+    async fn test_health_check_cached() {
+        // `/models` answers; anything else fails. Each request's path is reported.
+        let (tx, mut paths) = tokio::sync::mpsc::unbounded_channel();
+        let base_url = http_stub(move |request| {
+            let response = if request.path == "/models" {
+                Response::json(200, r#"{"data":[]}"#)
+            } else {
+                Response::json(500, r#"{"error":"down"}"#)
+            };
+            let _ = tx.send(request.path);
+            response
+        })
+        .await;
+
+        let backend = OpenAiBackend::new(OpenAiConfig {
+            base_url,
+            max_retries: 0,
+            health_cache_ttl_ms: 200,
+            ..Default::default()
+        });
+        let mut models_requests = || {
+            std::iter::from_fn(|| paths.try_recv().ok())
+                .filter(|p| p == "/models")
+                .count()
+        };
+
+        // Two rapid checks: one request
+        assert!(backend.health_check().await.unwrap().operational);
+        assert!(backend.health_check().await.unwrap().operational);
+        assert_eq!(models_requests(), 1);
+
+        // Past the TTL: asked again
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        backend.health_check().await.unwrap();
+        assert_eq!(models_requests(), 1);
+
+        // A failed request clears the cache
+        assert!(backend.embeddings(embeddings_input(1)).await.is_err());
+        backend.health_check().await.unwrap();
+        assert_eq!(models_requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_transitions() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // Chat completions fail with 500 until `up` is set
+        let up = Arc::new(AtomicBool::new(false));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let base_url = {
+            let (up, hits) = (up.clone(), hits.clone());
+            http_stub(move |_| {
+                hits.fetch_add(1, Ordering::SeqCst);
+                if up.load(Ordering::SeqCst) {
+                    Response::json(
+                        200,
+                        r#"{"choices":[{"message":{"role":"assistant","content":"hi"},"finish_reason":"stop"}]}"#,
+                    )
+                } else {
+                    Response::json(500, r#"{"error":"down"}"#)
+                }
+            })
+            .await
+        };
+
+        let backend = OpenAiBackend::new(OpenAiConfig {
+            base_url,
+            max_retries: 0,
+            health_cache_ttl_ms: 0,
+            breaker_max_failures: 2,
+            breaker_open_ms: 200,
+            ..Default::default()
+        });
+        let chat = || backend.chat_completion(vec![], Some(4), None, None, None, None);
+        let breaker = || backend.breaker.lock().state();
+
+        // Closed: failures reach the server until the threshold
+        assert!(chat().await.is_err());
+        assert_eq!(breaker(), BreakerState::Closed);
+        assert!(chat().await.is_err());
+        assert_eq!(breaker(), BreakerState::Open);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // Open: fails fast without a request, and health reports it
+        let err = chat().await.unwrap_err();
+        assert!(matches!(err, Error::ExecutionFailed { .. }));
+        assert!(err.to_string().contains("Circuit breaker open"));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert_eq!(backend.health_check().await.unwrap().breaker, Some(BreakerState::Open));
+
+        // Half-open after the cooldown: one probe, which closes it on success
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(breaker(), BreakerState::HalfOpen);
+        up.store(true, Ordering::SeqCst);
+        let (text, _, _) = chat().await.unwrap();
+        assert_eq!(text, "hi");
+        assert_eq!(breaker(), BreakerState::Closed);
+        assert_eq!(backend.health_check().await.unwrap().breaker, Some(BreakerState::Closed));
+    }
+
+    #[test]
+    fn test_retry_after_header() {
+        let now = Utc::now();
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(3)));
+        assert_eq!(retry_delay(1, retry_after(&headers, now)), Duration::from_secs(3));
+        // Backoff still applies when it is the longer wait
+        assert_eq!(retry_delay(4, retry_after(&headers, now)), Duration::from_secs(4));
+
+        let date = (now + chrono::Duration::seconds(10)).to_rfc2822().replace("+0000", "GMT");
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+        let wait = retry_after(&headers, now).unwrap();
+        assert!(wait > Duration::from_secs(9) && wait <= Duration::from_secs(10));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3600"));
+        assert_eq!(retry_after(&headers, now), Some(MAX_RETRY_AFTER));
+
+        assert_eq!(retry_delay(1, retry_after(&HeaderMap::new(), now)), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_invalid_extra_headers_skipped() {
+        let headers = HashMap::from([
+            ("Bad Header".to_string(), "x".to_string()),
+            ("X-Note".to_string(), "line\nbreak".to_string()),
+            ("OpenAI-Project".to_string(), "proj_1".to_string()),
+        ]);
+        let map = extra_header_map(&headers);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["openai-project"], "proj_1");
+    }
+
     #[test]
     fn test_is_model_loaded() {
         let backend = OpenAiBackend::new(OpenAiConfig::default());
@@ -0,0 +1,455 @@
+//! Routing across several OpenAI-compatible endpoints
+//!
+//! `[[openai.endpoints]]` lists servers together with the models and task
+//! types each one serves: one server for chat and another for embeddings,
+//! say, or fallback providers for the same model. The router is registered
+//! as the single OpenAI backend. Loading a model points every endpoint that
+//! serves it at that model; each request then goes to the endpoints able to
+//! run it, healthy ones first, falling back to the next on failure.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use async_trait::async_trait;
+use futures_util::future::{join_all, BoxFuture};
+use parking_lot::RwLock;
+use tracing::{info, warn};
+
+use crate::config::{OpenAiEndpointSettings, OpenAiSettings};
+use crate::error::{Error, Result};
+use crate::types::{
+    ClassificationInput, ClassificationOutput, EmbeddingsInput, EmbeddingsOutput, LoadedModelInfo,
+    ModelSpec, QuestionAnsweringInput, QuestionAnsweringOutput, SummarizationInput,
+    SummarizationOutput, TaskType, TextCompletionInput, TextCompletionOutput,
+};
+
+use super::{
+    BackendCapabilities, BackendHealth, BreakerState, ClassificationStrategy, InferenceBackend,
+    OpenAiBackend, OpenAiConfig, ResourceUsage,
+};
+
+// ─────────────────────────────────────────────────────────────────
+// Endpoint
+// ─────────────────────────────────────────────────────────────────
+
+/// One OpenAI-compatible server and what it serves
+pub struct OpenAiEndpoint {
+    name: String,
+    /// Models served (empty = any)
+    models: Vec<String>,
+    /// Task types served (empty = every task the API backend supports)
+    tasks: Vec<TaskType>,
+    backend: OpenAiBackend,
+    /// Result of the last health check, or of the last failover
+    healthy: AtomicBool,
+}
+
+impl OpenAiEndpoint {
+    /// Endpoint serving any model and task through `backend`
+    pub fn new(name: impl Into<String>, backend: OpenAiBackend) -> Self {
+        Self {
+            name: name.into(),
+            models: Vec::new(),
+            tasks: Vec::new(),
+            backend,
+            healthy: AtomicBool::new(true),
+        }
+    }
+
+    /// Only serve `models`
+    pub fn with_models(mut self, models: Vec<String>) -> Self {
+        self.models = models;
+        self
+    }
+
+    /// Only serve `tasks`
+    pub fn with_tasks(mut self, tasks: Vec<TaskType>) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    fn serves_model(&self, model_id: &str) -> bool {
+        self.models.is_empty() || self.models.iter().any(|m| m == model_id)
+    }
+
+    fn serves_task(&self, task_type: TaskType) -> bool {
+        if self.tasks.is_empty() {
+            self.backend.supports_task(task_type)
+        } else {
+            self.tasks.contains(&task_type)
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    async fn check_health(&self) -> BackendHealth {
+        let health = self.backend.health_check().await.unwrap_or_else(|e| BackendHealth {
+            operational: false,
+            error: Some(e.to_string()),
+            ..Default::default()
+        });
+        self.healthy.store(health.operational, Ordering::Relaxed);
+        health
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Router
+// ─────────────────────────────────────────────────────────────────
+
+/// Backend spreading tasks over several OpenAI-compatible endpoints
+pub struct OpenAiRouter {
+    endpoints: Vec<OpenAiEndpoint>,
+    /// Model requested by the last load
+    model_id: RwLock<String>,
+}
+
+impl OpenAiRouter {
+    /// Route across `endpoints`, in order of preference
+    pub fn new(endpoints: Vec<OpenAiEndpoint>) -> Self {
+        let model_id = endpoints
+            .first()
+            .and_then(|e| e.backend.loaded_model_id())
+            .unwrap_or_default();
+        Self { endpoints, model_id: RwLock::new(model_id) }
+    }
+
+    /// Build the endpoints configured in `[openai]`
+    pub fn from_settings(settings: &OpenAiSettings) -> Self {
+        let endpoints = settings
+            .resolved_endpoints()
+            .into_iter()
+            .map(|e: OpenAiEndpointSettings| {
+                let backend = OpenAiBackend::new(OpenAiConfig {
+                    base_url: e.base_url,
+                    api_key: e.api_key,
+                    default_model: e.default_model,
+                    timeout_secs: settings.timeout_secs,
+                    max_retries: settings.max_retries,
+                    classification_strategy: ClassificationStrategy::from_str(
+                        &settings.classification_strategy,
+                    )
+                    .unwrap_or_default(),
+                    calibrate_classification: settings.calibrate_classification,
+                    embeddings_batch_size: settings.embeddings_batch_size,
+                    health_cache_ttl_ms: settings.health_cache_ttl_ms,
+                    breaker_max_failures: settings.breaker_max_failures,
+                    breaker_open_ms: settings.breaker_open_ms,
+                    extra_headers: settings.extra_headers.clone().into_iter().collect(),
+                });
+                OpenAiEndpoint::new(e.name, backend)
+                    .with_models(e.models)
+                    .with_tasks(e.tasks)
+            })
+            .collect();
+        Self::new(endpoints)
+    }
+
+    /// Names of the configured endpoints
+    pub fn endpoint_names(&self) -> Vec<&str> {
+        self.endpoints.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    /// Route to `model_id` once the endpoints serving it have loaded it.
+    /// When several can serve it, find out which are up.
+    async fn select_model(&self, model_id: &str) {
+        *self.model_id.write() = model_id.to_string();
+        let serving: Vec<&OpenAiEndpoint> =
+            self.endpoints.iter().filter(|e| e.serves_model(model_id)).collect();
+        if serving.len() > 1 {
+            join_all(serving.iter().map(|e| e.check_health())).await;
+        }
+        info!(
+            model = %model_id,
+            endpoints = ?serving.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(),
+            "Model routed to OpenAI endpoints"
+        );
+    }
+
+    /// Endpoints to try for `task_type`, best first: those serving the
+    /// current model, or failing that any serving the task type with their
+    /// own model. Healthy endpoints come first, otherwise config order.
+    fn route(&self, task_type: TaskType) -> Vec<&OpenAiEndpoint> {
+        let model_id = self.model_id.read().clone();
+        let mut candidates: Vec<&OpenAiEndpoint> = self
+            .endpoints
+            .iter()
+            .filter(|e| e.serves_task(task_type) && e.serves_model(&model_id))
+            .collect();
+        if candidates.is_empty() {
+            candidates = self.endpoints.iter().filter(|e| e.serves_task(task_type)).collect();
+        }
+        candidates.sort_by_key(|e| !e.is_healthy());
+        candidates
+    }
+
+    /// Run `call` on the endpoints for `task_type` until one succeeds.
+    /// Endpoints that failed before another succeeded are marked unhealthy
+    /// until their next health check.
+    async fn dispatch<T, F>(&self, task_type: TaskType, call: F) -> Result<T>
+    where
+        F: for<'a> Fn(&'a OpenAiBackend) -> BoxFuture<'a, Result<T>>,
+    {
+        let candidates = self.route(task_type);
+        let mut failed: Vec<&OpenAiEndpoint> = Vec::new();
+        let mut last_error = None;
+        for endpoint in candidates {
+            match call(&endpoint.backend).await {
+                Ok(output) => {
+                    for f in failed {
+                        f.healthy.store(false, Ordering::Relaxed);
+                    }
+                    return Ok(output);
+                }
+                Err(e) => {
+                    warn!(
+                        endpoint = %endpoint.name,
+                        task_type = %task_type,
+                        error = %e,
+                        "OpenAI endpoint failed"
+                    );
+                    failed.push(endpoint);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            Error::NotSupported(format!("No OpenAI endpoint serves {} tasks", task_type))
+        }))
+    }
+}
+
+#[async_trait]
+impl InferenceBackend for OpenAiRouter {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        let mut caps = match self.endpoints.first() {
+            Some(e) => e.backend.capabilities(),
+            None => BackendCapabilities { name: "openai", ..Default::default() },
+        };
+        caps.supported_tasks
+            .retain(|t| self.endpoints.iter().any(|e| e.serves_task(*t)));
+        caps
+    }
+
+    async fn health_check(&self) -> Result<BackendHealth> {
+        let checks = join_all(self.endpoints.iter().map(|e| e.check_health())).await;
+        // The breaker of the endpoint closest to taking requests
+        let breaker = [BreakerState::Closed, BreakerState::HalfOpen, BreakerState::Open]
+            .into_iter()
+            .find(|state| checks.iter().any(|h| h.breaker == Some(*state)));
+        if checks.iter().any(|h| h.operational) {
+            return Ok(BackendHealth { operational: true, model_loaded: true, breaker, ..Default::default() });
+        }
+        let errors: Vec<String> = self
+            .endpoints
+            .iter()
+            .zip(&checks)
+            .map(|(e, h)| format!("{}: {}", e.name, h.error.as_deref().unwrap_or("not operational")))
+            .collect();
+        Ok(BackendHealth {
+            operational: false,
+            error: Some(errors.join("; ")),
+            breaker,
+            ..Default::default()
+        })
+    }
+
+    fn resource_usage(&self) -> ResourceUsage {
+        self.endpoints
+            .first()
+            .map(|e| e.backend.resource_usage())
+            .unwrap_or_default()
+    }
+
+    async fn load_model(&mut self, spec: &ModelSpec) -> Result<LoadedModelInfo> {
+        let mut loaded = None;
+        for endpoint in self.endpoints.iter_mut().filter(|e| e.serves_model(&spec.id)) {
+            loaded = Some(endpoint.backend.load_model(spec).await?);
+        }
+        let Some(info) = loaded else {
+            return Err(Error::ModelNotFound { model_id: spec.id.clone() });
+        };
+        self.select_model(&spec.id).await;
+        Ok(info)
+    }
+
+    async fn load_model_from_path(&mut self, path: &Path) -> Result<LoadedModelInfo> {
+        let model_id = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+        let mut loaded = None;
+        for endpoint in self.endpoints.iter_mut().filter(|e| e.serves_model(&model_id)) {
+            loaded = Some(endpoint.backend.load_model_from_path(path).await?);
+        }
+        let info = loaded.ok_or_else(|| Error::ModelNotFound { model_id: model_id.clone() })?;
+        self.select_model(&model_id).await;
+        Ok(info)
+    }
+
+    async fn unload_model(&mut self) -> Result<()> {
+        for endpoint in &mut self.endpoints {
+            endpoint.backend.unload_model().await?;
+        }
+        *self.model_id.write() = self
+            .endpoints
+            .first()
+            .and_then(|e| e.backend.loaded_model_id())
+            .unwrap_or_default();
+        Ok(())
+    }
+
+    fn loaded_model(&self) -> Option<&LoadedModelInfo> {
+        None // API backends don't hold a model in memory
+    }
+
+    fn is_model_loaded(&self) -> bool {
+        true
+    }
+
+    fn loaded_model_id(&self) -> Option<String> {
+        Some(self.model_id.read().clone())
+    }
+
+    async fn text_completion(&self, input: TextCompletionInput) -> Result<TextCompletionOutput> {
+        self.dispatch(TaskType::TextCompletion, |b| Box::pin(b.text_completion(input.clone())))
+            .await
+    }
+
+    async fn embeddings(&self, input: EmbeddingsInput) -> Result<EmbeddingsOutput> {
+        self.dispatch(TaskType::Embeddings, |b| Box::pin(b.embeddings(input.clone()))).await
+    }
+
+    async fn classify(&self, input: ClassificationInput) -> Result<ClassificationOutput> {
+        self.dispatch(TaskType::Classification, |b| Box::pin(b.classify(input.clone()))).await
+    }
+
+    async fn question_answering(
+        &self,
+        input: QuestionAnsweringInput,
+    ) -> Result<QuestionAnsweringOutput> {
+        self.dispatch(TaskType::QuestionAnswering, |b| {
+            Box::pin(b.question_answering(input.clone()))
+        })
+        .await
+    }
+
+    async fn summarize(&self, input: SummarizationInput) -> Result<SummarizationOutput> {
+        self.dispatch(TaskType::Summarization, |b| Box::pin(b.summarize(input.clone()))).await
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{http_stub, Response};
+    use crate::types::GenerationParams;
+
+    /// Serve an OpenAI-compatible API whose chat replies are `reply` and
+    /// whose embeddings are `[1.0]`
+    async fn serve(reply: &'static str) -> String {
+        http_stub(move |request| match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/embeddings") => Response::json(200, r#"{"data":[{"embedding":[1.0]}]}"#),
+            ("GET", "/models") => Response::json(200, r#"{"data":[]}"#),
+            _ => Response::json(
+                200,
+                format!(
+                    r#"{{"choices":[{{"message":{{"role":"assistant","content":"{}"}},"finish_reason":"stop"}}]}}"#,
+                    reply
+                ),
+            ),
+        })
+        .await
+    }
+
+    /// Base URL nothing listens on
+    async fn dead_url() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    fn endpoint(name: &str, base_url: String, models: &[&str]) -> OpenAiEndpoint {
+        let backend = OpenAiBackend::new(OpenAiConfig {
+            base_url,
+            default_model: models.first().copied().unwrap_or("llama3").to_string(),
+            max_retries: 0,
+            timeout_secs: 5,
+            ..Default::default()
+        });
+        OpenAiEndpoint::new(name, backend).with_models(models.iter().map(|m| m.to_string()).collect())
+    }
+
+    async fn complete(router: &OpenAiRouter) -> Result<String> {
+        let input = TextCompletionInput {
+            prompt: "Hello".to_string(),
+            system_prompt: None,
+            params: GenerationParams::default(),
+        };
+        router.text_completion(input).await.map(|out| out.text)
+    }
+
+    #[tokio::test]
+    async fn test_model_routed_to_serving_endpoint() {
+        let mut router = OpenAiRouter::new(vec![
+            endpoint("chat", serve("from-chat").await, &["llama3"]),
+            endpoint("other", serve("from-other").await, &["mistral"]),
+            endpoint("embed", serve("unused").await, &["nomic-embed-text"])
+                .with_tasks(vec![TaskType::Embeddings]),
+        ]);
+        assert_eq!(router.endpoint_names(), ["chat", "other", "embed"]);
+
+        router.load_model_from_path(Path::new("mistral.gguf")).await.unwrap();
+        assert_eq!(router.loaded_model_id().as_deref(), Some("mistral"));
+        assert_eq!(complete(&router).await.unwrap(), "from-other");
+
+        router.load_model_from_path(Path::new("llama3.gguf")).await.unwrap();
+        assert_eq!(complete(&router).await.unwrap(), "from-chat");
+
+        // No chat endpoint serves embeddings: routed by task type instead
+        let out = router
+            .embeddings(EmbeddingsInput { texts: vec!["x".to_string()], normalize: false })
+            .await
+            .unwrap();
+        assert_eq!(out.embeddings, vec![vec![1.0]]);
+
+        // A model no endpoint lists can't be loaded
+        let err = router.load_model_from_path(Path::new("phi3.gguf")).await.unwrap_err();
+        assert!(matches!(err, Error::ModelNotFound { .. }));
+        assert_eq!(router.loaded_model_id().as_deref(), Some("llama3"));
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_healthy_endpoint() {
+        let mut router = OpenAiRouter::new(vec![
+            endpoint("primary", dead_url().await, &["llama3"]),
+            endpoint("backup", serve("from-backup").await, &["llama3"]),
+        ]);
+
+        // The primary is tried first and fails over, then is passed over
+        assert_eq!(complete(&router).await.unwrap(), "from-backup");
+        assert!(!router.endpoints[0].is_healthy());
+        assert_eq!(router.route(TaskType::TextCompletion)[0].name, "backup");
+
+        // Health checks on load decide the order too
+        router.endpoints[0].healthy.store(true, Ordering::Relaxed);
+        router.load_model_from_path(Path::new("llama3.gguf")).await.unwrap();
+        assert_eq!(router.route(TaskType::TextCompletion)[0].name, "backup");
+        assert!(router.health_check().await.unwrap().operational);
+
+        // With every endpoint down the last error is returned
+        let down = OpenAiRouter::new(vec![endpoint("primary", dead_url().await, &[])]);
+        assert!(complete(&down).await.is_err());
+        assert!(!down.health_check().await.unwrap().operational);
+    }
+}
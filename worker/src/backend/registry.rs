@@ -1,16 +1,22 @@
 //! Backend Registry
 //!
 //! Manages available backends and provides dynamic backend selection.
+//! It also holds the memory budget for models loaded across backends
+//! (`resources.max_memory_mb`), which the executor keeps to by unloading
+//! the least recently used ones before a load that would exceed it.
 
 use std::collections::HashMap;
 use std::sync::Arc;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use tokio::sync::RwLock as TokioRwLock;
 
 use crate::error::{Error, Result};
 use crate::types::TaskType;
 
-use super::{BackendCapabilities, BackendConfig, CpuBackend, InferenceBackend, MockBackend, OpenAiBackend, OpenAiConfig};
+use super::{
+    BackendCapabilities, BackendConfig, BreakerConfig, BreakerState, CircuitBreaker, CpuBackend,
+    InferenceBackend, MockBackend, OpenAiBackend, OpenAiConfig,
+};
 
 // ─────────────────────────────────────────────────────────────────
 // Backend Type
@@ -204,9 +210,34 @@ impl BackendFactory {
 /// Registry for managing multiple backends
 ///
 /// Uses `tokio::sync::RwLock` for inner backend storage to support async operations.
+/// Capabilities are snapshotted at registration, so lookups never skip a
+/// backend that is exclusively locked (e.g. mid model load).
+///
+/// Each backend has a circuit breaker; while it is open the backend is
+/// skipped for tasks and left out of [`all_capabilities`](Self::all_capabilities).
+///
+/// With CPU fallback on, a model too large for the selected GPU's memory is
+/// routed to the CPU backend instead. GPU memory is the detected VRAM,
+/// capped by `resources.max_gpu_memory_mb`.
 pub struct BackendRegistry {
     backends: RwLock<HashMap<BackendType, Arc<TokioRwLock<Box<dyn InferenceBackend>>>>>,
+    capabilities: RwLock<HashMap<BackendType, BackendCapabilities>>,
     default_backend: RwLock<Option<BackendType>>,
+    breaker_config: RwLock<BreakerConfig>,
+    breakers: Mutex<HashMap<BackendType, CircuitBreaker>>,
+    auto_cpu_fallback: RwLock<bool>,
+    /// Memory all loaded models may use together (MB, 0 = unlimited)
+    memory_budget_mb: RwLock<u64>,
+    /// GPU memory a task may use, whatever the card has (MB, 0 = no cap)
+    gpu_memory_cap_mb: RwLock<u64>,
+}
+
+/// A model currently loaded in a registered backend
+#[derive(Debug, Clone)]
+pub struct LoadedModelEntry {
+    pub backend_type: BackendType,
+    pub model_id: String,
+    pub memory_used_mb: u64,
 }
 
 impl BackendRegistry {
@@ -214,10 +245,109 @@ impl BackendRegistry {
     pub fn new() -> Self {
         Self {
             backends: RwLock::new(HashMap::new()),
+            capabilities: RwLock::new(HashMap::new()),
             default_backend: RwLock::new(None),
+            breaker_config: RwLock::new(BreakerConfig::default()),
+            breakers: Mutex::new(HashMap::new()),
+            auto_cpu_fallback: RwLock::new(false),
+            memory_budget_mb: RwLock::new(0),
+            gpu_memory_cap_mb: RwLock::new(0),
+        }
+    }
+
+    /// Set the circuit breaker settings, resetting all breakers
+    pub fn set_breaker_config(&self, config: BreakerConfig) {
+        *self.breaker_config.write() = config;
+        self.breakers.lock().clear();
+    }
+
+    /// Route models too large for GPU memory to the CPU backend
+    pub fn set_auto_cpu_fallback(&self, enabled: bool) {
+        *self.auto_cpu_fallback.write() = enabled;
+    }
+
+    /// Set the memory budget for loaded models (MB, 0 = unlimited)
+    pub fn set_memory_budget(&self, budget_mb: u64) {
+        *self.memory_budget_mb.write() = budget_mb;
+    }
+
+    /// Memory all loaded models may use together (MB, 0 = unlimited)
+    pub fn memory_budget_mb(&self) -> u64 {
+        *self.memory_budget_mb.read()
+    }
+
+    /// Cap the GPU memory a task may use (MB, 0 = the card's full VRAM)
+    pub fn set_gpu_memory_cap(&self, cap_mb: u64) {
+        *self.gpu_memory_cap_mb.write() = cap_mb;
+    }
+
+    /// GPU memory tasks on `backend_type` may use: its detected VRAM under
+    /// the cap. `None` for backends without a GPU, or with neither a known
+    /// VRAM size nor a cap.
+    pub fn gpu_memory_available_mb(&self, backend_type: BackendType) -> Option<u64> {
+        let vram_mb = {
+            let capabilities = self.capabilities.read();
+            let caps = capabilities.get(&backend_type).filter(|caps| caps.gpu_available)?;
+            caps.gpu_memory_mb
+        };
+        let cap_mb = Some(*self.gpu_memory_cap_mb.read()).filter(|mb| *mb > 0);
+        match (vram_mb, cap_mb) {
+            (Some(vram), Some(cap)) => Some(vram.min(cap)),
+            (vram, cap) => vram.or(cap),
+        }
+    }
+
+    /// GPU memory still free for a task on `backend_type`: what it may use,
+    /// less what the models loaded in the other GPU backends hold. The
+    /// backend's own model is either the one the task needs or gets swapped
+    /// out for it, so it isn't counted. `None` as for
+    /// [`gpu_memory_available_mb`](Self::gpu_memory_available_mb).
+    pub fn gpu_memory_free_mb(&self, backend_type: BackendType) -> Option<u64> {
+        let available_mb = self.gpu_memory_available_mb(backend_type)?;
+        let gpu_backends: Vec<BackendType> = self
+            .capabilities
+            .read()
+            .iter()
+            .filter(|(t, caps)| caps.gpu_available && **t != backend_type)
+            .map(|(t, _)| *t)
+            .collect();
+        let used_mb: u64 = self
+            .loaded_models()
+            .iter()
+            .filter(|m| gpu_backends.contains(&m.backend_type))
+            .map(|m| m.memory_used_mb)
+            .sum();
+        Some(available_mb.saturating_sub(used_mb))
+    }
+
+    /// Check that a task needing `required_mb` of GPU memory fits in what is
+    /// free on `backend_type`. Backends without a GPU always pass.
+    pub fn check_gpu_memory(&self, backend_type: BackendType, required_mb: u64) -> Result<()> {
+        match self.gpu_memory_free_mb(backend_type) {
+            Some(available_mb) if required_mb > available_mb => {
+                Err(Error::GpuMemoryInsufficient { required_mb, available_mb })
+            }
+            _ => Ok(()),
         }
     }
 
+    /// Models loaded across the registered backends right now. Backends
+    /// locked for a load or unload are left out.
+    pub fn loaded_models(&self) -> Vec<LoadedModelEntry> {
+        self.backends
+            .read()
+            .iter()
+            .filter_map(|(backend_type, backend)| {
+                let backend = backend.try_read().ok()?;
+                Some(LoadedModelEntry {
+                    backend_type: *backend_type,
+                    model_id: backend.loaded_model_id()?,
+                    memory_used_mb: backend.resource_usage().memory_mb,
+                })
+            })
+            .collect()
+    }
+
     /// Create a registry with the best available backend
     pub fn with_default() -> Result<Self> {
         let registry = Self::new();
@@ -230,19 +360,13 @@ impl BackendRegistry {
     /// Register a backend
     pub fn register(&self, backend_type: BackendType, config: BackendConfig) -> Result<()> {
         let backend = BackendFactory::create(backend_type, config)?;
-        let mut backends = self.backends.write();
-        backends.insert(backend_type, Arc::new(TokioRwLock::new(backend)));
+        self.insert(backend_type, backend);
 
         tracing::info!(
             backend = %backend_type,
             "Backend registered"
         );
 
-        // Set as default if no default exists
-        if self.default_backend.read().is_none() {
-            *self.default_backend.write() = Some(backend_type);
-        }
-
         Ok(())
     }
 
@@ -251,14 +375,20 @@ impl BackendRegistry {
     /// Use this for backends that require constructor arguments not captured
     /// in `BackendConfig` (e.g., `CrawlerBackend`).
     pub fn register_boxed(&self, backend_type: BackendType, backend: Box<dyn InferenceBackend>) {
-        let mut backends = self.backends.write();
-        backends.insert(backend_type, Arc::new(TokioRwLock::new(backend)));
+        self.insert(backend_type, backend);
 
         tracing::info!(
             backend = %backend_type,
             "Backend registered (boxed)"
         );
+    }
 
+    fn insert(&self, backend_type: BackendType, backend: Box<dyn InferenceBackend>) {
+        self.breakers.lock().remove(&backend_type);
+        self.capabilities.write().insert(backend_type, backend.capabilities());
+        self.backends.write().insert(backend_type, Arc::new(TokioRwLock::new(backend)));
+
+        // Set as default if no default exists
         if self.default_backend.read().is_none() {
             *self.default_backend.write() = Some(backend_type);
         }
@@ -268,6 +398,7 @@ impl BackendRegistry {
     pub fn unregister(&self, backend_type: BackendType) {
         let mut backends = self.backends.write();
         backends.remove(&backend_type);
+        self.capabilities.write().remove(&backend_type);
 
         // Clear default if it was the unregistered backend
         let mut default = self.default_backend.write();
@@ -297,43 +428,63 @@ impl BackendRegistry {
         self.backends.read().keys().copied().collect()
     }
 
-    /// Get capabilities of all registered backends (sync version using blocking read)
+    /// Get capabilities of all registered backends whose breaker isn't open
     pub fn all_capabilities(&self) -> HashMap<BackendType, BackendCapabilities> {
-        let backends = self.backends.read();
-        backends
+        self.capabilities
+            .read()
             .iter()
-            .map(|(t, b)| (*t, b.blocking_read().capabilities()))
+            .filter(|(t, _)| self.is_available(**t))
+            .map(|(t, caps)| (*t, caps.clone()))
             .collect()
     }
 
+    /// Check whether any available backend supports a task type
+    pub fn supports_task(&self, task_type: TaskType) -> bool {
+        self.capabilities
+            .read()
+            .iter()
+            .any(|(t, caps)| caps.supported_tasks.contains(&task_type) && self.is_available(*t))
+    }
+
+    /// Circuit breaker state of a backend
+    #[cfg(test)]
+    pub fn breaker_state(&self, backend_type: BackendType) -> BreakerState {
+        self.breakers
+            .lock()
+            .get(&backend_type)
+            .map(|b| b.state())
+            .unwrap_or(BreakerState::Closed)
+    }
+
+    /// Whether a backend's breaker lets it take tasks
+    fn is_available(&self, backend_type: BackendType) -> bool {
+        self.breakers
+            .lock()
+            .get(&backend_type)
+            .is_none_or(|b| b.is_available())
+    }
+
+    /// Feed a task outcome on `backend_type` to its circuit breaker.
+    /// Returns the new breaker state if it changed.
+    pub fn record_outcome(&self, backend_type: BackendType, success: bool) -> Option<BreakerState> {
+        let config = *self.breaker_config.read();
+        let mut breakers = self.breakers.lock();
+        let breaker = breakers
+            .entry(backend_type)
+            .or_insert_with(|| CircuitBreaker::new(config));
+        if success {
+            breaker.record_success()
+        } else {
+            breaker.record_failure()
+        }
+    }
+
     /// Find the best backend for a task
     pub fn best_backend_for_task(
         &self,
         task_type: TaskType,
     ) -> Option<(BackendType, Arc<TokioRwLock<Box<dyn InferenceBackend>>>)> {
-        let backends = self.backends.read();
-
-        // Priority order for backend selection
-        let priority = [
-            BackendType::Cuda,
-            BackendType::Rocm,
-            BackendType::Vulkan,
-            BackendType::OpenAi,
-            BackendType::Cpu,
-            BackendType::Crawler,
-            BackendType::Mock,
-        ];
-
-        for backend_type in priority {
-            if let Some(backend) = backends.get(&backend_type) {
-                let caps = backend.blocking_read().capabilities();
-                if caps.supported_tasks.contains(&task_type) {
-                    return Some((backend_type, backend.clone()));
-                }
-            }
-        }
-
-        None
+        self.backends_for_task(task_type).into_iter().next()
     }
 
     /// Find a backend that supports a task (convenience method returning just the backend)
@@ -344,25 +495,91 @@ impl BackendRegistry {
         self.best_backend_for_task(task_type).map(|(_, b)| b)
     }
 
+    /// Find the backend to run a task for a specific model
+    ///
+    /// Prefers a backend that already has `model_id` loaded, then one with no
+    /// model loaded, then the best backend for the task (which will have to
+    /// swap models). Backends busy loading are only picked as a last resort.
+    ///
+    /// `model_mb` is the model's estimated memory need, if known; with CPU
+    /// fallback on, a GPU pick without that much memory free is swapped for
+    /// the CPU backend.
+    pub fn backend_for_model(
+        &self,
+        task_type: TaskType,
+        model_id: &str,
+        model_mb: Option<u64>,
+    ) -> Option<(BackendType, Arc<TokioRwLock<Box<dyn InferenceBackend>>>)> {
+        let candidates = self.backends_for_task(task_type);
+
+        let loaded = |b: &Arc<TokioRwLock<Box<dyn InferenceBackend>>>| {
+            b.try_read().ok().map(|b| b.loaded_model_id())
+        };
+
+        let selected = candidates
+            .iter()
+            .find(|(_, b)| loaded(b) == Some(Some(model_id.to_string())))
+            .or_else(|| candidates.iter().find(|(_, b)| loaded(b) == Some(None)))
+            .or_else(|| candidates.first())
+            .cloned()?;
+
+        let Some(required_mb) = model_mb.filter(|_| *self.auto_cpu_fallback.read()) else {
+            return Some(selected);
+        };
+        match self.gpu_memory_free_mb(selected.0) {
+            Some(available_mb) if required_mb > available_mb => {
+                match candidates.iter().find(|(t, _)| *t == BackendType::Cpu) {
+                    Some(cpu) => {
+                        tracing::info!(
+                            model = %model_id,
+                            required_mb,
+                            available_mb,
+                            gpu_backend = %selected.0,
+                            "Model too large for GPU memory, running on CPU"
+                        );
+                        Some(cpu.clone())
+                    }
+                    None => Some(selected),
+                }
+            }
+            _ => Some(selected),
+        }
+    }
+
     /// Iterate over all registered backends
     pub fn backends(&self) -> Vec<Arc<TokioRwLock<Box<dyn InferenceBackend>>>> {
         self.backends.read().values().cloned().collect()
     }
 
-    /// Find all backends that support a task
+    /// Find all available backends that support a task, in priority order
     pub fn backends_for_task(
         &self,
         task_type: TaskType,
     ) -> Vec<(BackendType, Arc<TokioRwLock<Box<dyn InferenceBackend>>>)> {
         let backends = self.backends.read();
+        let capabilities = self.capabilities.read();
 
-        backends
-            .iter()
-            .filter(|(_, b)| {
-                let caps = b.blocking_read().capabilities();
-                caps.supported_tasks.contains(&task_type)
+        // Priority order for backend selection
+        let priority = [
+            BackendType::Cuda,
+            BackendType::Rocm,
+            BackendType::Vulkan,
+            BackendType::OpenAi,
+            BackendType::Cpu,
+            BackendType::Crawler,
+            BackendType::Mock,
+        ];
+
+        priority
+            .into_iter()
+            .filter(|t| {
+                capabilities
+                    .get(t)
+                    .map(|caps| caps.supported_tasks.contains(&task_type))
+                    .unwrap_or(false)
             })
-            .map(|(t, b)| (*t, b.clone()))
+            .filter(|t| self.is_available(*t))
+            .filter_map(|t| backends.get(&t).map(|b| (t, b.clone())))
             .collect()
     }
 }
@@ -461,4 +678,75 @@ mod tests {
 
         assert!(registry.registered_backends().is_empty());
     }
+
+    #[test]
+    fn test_oversized_model_falls_back_to_cpu() {
+        use crate::backend::{MockBackend, MockConfig};
+
+        let registry = BackendRegistry::new();
+        let gpu = MockBackend::with_config(
+            MockConfig { gpu_memory_mb: Some(2048), ..Default::default() },
+            BackendConfig::default(),
+        );
+        registry.register_boxed(BackendType::Cuda, Box::new(gpu));
+        registry.register_boxed(BackendType::Cpu, Box::new(MockBackend::new()));
+        registry.set_auto_cpu_fallback(true);
+
+        let pick = |model_mb| {
+            registry
+                .backend_for_model(TaskType::TextCompletion, "model", Some(model_mb))
+                .map(|(t, _)| t)
+        };
+        assert_eq!(pick(1024), Some(BackendType::Cuda));
+        assert_eq!(pick(8192), Some(BackendType::Cpu));
+
+        // The cap applies as if the card were smaller
+        registry.set_gpu_memory_cap(512);
+        assert_eq!(pick(1024), Some(BackendType::Cpu));
+        registry.set_gpu_memory_cap(0);
+
+        // Without fallback the GPU keeps the task, for the pre-flight check
+        // to reject
+        registry.set_auto_cpu_fallback(false);
+        assert_eq!(pick(8192), Some(BackendType::Cuda));
+        let err = registry.check_gpu_memory(BackendType::Cuda, 8192).unwrap_err();
+        assert_eq!(err.code(), crate::error::ErrorCode::GpuMemoryInsufficient);
+        assert!(registry.check_gpu_memory(BackendType::Cuda, 2048).is_ok());
+        assert!(registry.check_gpu_memory(BackendType::Cpu, 8192).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_loaded_models_take_gpu_memory() {
+        use crate::backend::{MockBackend, MockConfig};
+
+        // Two GPU backends on a 2 GB card, and a model loaded in one
+        let registry = BackendRegistry::new();
+        for backend_type in [BackendType::Cuda, BackendType::Vulkan] {
+            let gpu = MockBackend::with_config(
+                MockConfig { gpu_memory_mb: Some(2048), ..Default::default() },
+                BackendConfig::default(),
+            );
+            registry.register_boxed(backend_type, Box::new(gpu));
+        }
+        registry.register_boxed(BackendType::Cpu, Box::new(MockBackend::new()));
+        registry.set_auto_cpu_fallback(true);
+        let vulkan = registry.get(BackendType::Vulkan).unwrap();
+        vulkan.write().await.load_model_from_path(std::path::Path::new("other.gguf")).await.unwrap();
+        let used_mb = vulkan.read().await.resource_usage().memory_mb;
+
+        // The other backend's model counts against the card, not its own
+        assert_eq!(registry.gpu_memory_free_mb(BackendType::Cuda), Some(2048 - used_mb));
+        assert_eq!(registry.gpu_memory_free_mb(BackendType::Vulkan), Some(2048));
+        let pick = |model_mb| {
+            registry
+                .backend_for_model(TaskType::TextCompletion, "model", model_mb)
+                .map(|(t, _)| t)
+        };
+        assert_eq!(pick(Some(2048 - used_mb)), Some(BackendType::Cuda));
+        assert_eq!(pick(Some(2048)), Some(BackendType::Cpu));
+        assert!(registry.check_gpu_memory(BackendType::Cuda, 2048).is_err());
+
+        // A model of unknown size stays on the GPU
+        assert_eq!(pick(None), Some(BackendType::Cuda));
+    }
 }
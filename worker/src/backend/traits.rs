@@ -5,11 +5,13 @@
 use async_trait::async_trait;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::error::{Error, Result};
 use crate::types::{
     ClassificationInput, ClassificationOutput,
-    EmbeddingsInput, EmbeddingsOutput,
+    DebugInput, DebugOutput,
+    EmbeddingsInput, EmbeddingsOutput, GenerationParams,
     LoadedModelInfo, ModelSpec, TaskType,
     QuestionAnsweringInput, QuestionAnsweringOutput,
     SummarizationInput, SummarizationOutput,
@@ -19,6 +21,8 @@ use crate::types::{
     WebCrawlInput, WebCrawlOutput,
 };
 
+use super::BreakerState;
+
 // ─────────────────────────────────────────────────────────────────
 // Backend Health & Status
 // ─────────────────────────────────────────────────────────────────
@@ -40,6 +44,9 @@ pub struct BackendHealth {
 
     /// Any error message
     pub error: Option<String>,
+
+    /// State of the backend's own circuit breaker, if it has one
+    pub breaker: Option<BreakerState>,
 }
 
 impl Default for BackendHealth {
@@ -50,6 +57,7 @@ impl Default for BackendHealth {
             memory_used_mb: 0,
             gpu_memory_used_mb: None,
             error: None,
+            breaker: None,
         }
     }
 }
@@ -99,6 +107,9 @@ pub struct BackendCapabilities {
 
     /// GPU device name (if available)
     pub gpu_device: Option<String>,
+
+    /// GPU memory (MB, if available)
+    pub gpu_memory_mb: Option<u64>,
 }
 
 impl Default for BackendCapabilities {
@@ -112,6 +123,7 @@ impl Default for BackendCapabilities {
             max_batch_size: 1,
             gpu_available: false,
             gpu_device: None,
+            gpu_memory_mb: None,
         }
     }
 }
@@ -143,6 +155,16 @@ pub type StreamCallback = Box<dyn Fn(StreamToken) -> bool + Send + Sync>;
 // InferenceBackend Trait
 // ─────────────────────────────────────────────────────────────────
 
+/// Activations produced by running a shard's layers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerOutput {
+    /// First layer the next shard runs
+    pub layer_end: u32,
+
+    /// Serialized activations for the next shard
+    pub tensor_data: Vec<u8>,
+}
+
 /// Core trait for inference backends
 ///
 /// All inference backends (CPU, CUDA, ROCm, Vulkan) must implement this trait.
@@ -169,6 +191,11 @@ pub trait InferenceBackend: Send + Sync {
         self.capabilities().supports_training
     }
 
+    /// Whether loading a model already runs a [`warmup`] generation
+    fn warms_up_after_load(&self) -> bool {
+        false
+    }
+
     // ─────────────────────────────────────────────────────────────
     // Health & Status
     // ─────────────────────────────────────────────────────────────
@@ -200,6 +227,11 @@ pub trait InferenceBackend: Send + Sync {
         self.loaded_model().is_some()
     }
 
+    /// Get the ID of the currently loaded model
+    fn loaded_model_id(&self) -> Option<String> {
+        self.loaded_model().map(|m| m.spec.id.clone())
+    }
+
     // ─────────────────────────────────────────────────────────────
     // Inference Methods
     // ─────────────────────────────────────────────────────────────
@@ -297,6 +329,45 @@ pub trait InferenceBackend: Send + Sync {
             self.name()
         )))
     }
+
+    /// Execute debug task
+    async fn debug(
+        &self,
+        _input: DebugInput,
+    ) -> Result<DebugOutput> {
+        Err(Error::NotSupported(format!(
+            "Backend '{}' does not support debug tasks",
+            self.name()
+        )))
+    }
+
+    /// Run this worker's shard of the loaded model on activations entering
+    /// at `layer_start` (split-layer inference across a shard group). At
+    /// layer 0 the activations are the UTF-8 prompt, which the shard embeds.
+    async fn forward_layers(
+        &self,
+        _layer_start: u32,
+        _tensor_data: Vec<u8>,
+    ) -> Result<LayerOutput> {
+        Err(Error::NotSupported(format!(
+            "Backend '{}' does not support split-layer inference",
+            self.name()
+        )))
+    }
+
+    /// Turn the activations leaving a split model's last layer into a
+    /// completion sampled with `params`; every shard keeps the model's
+    /// output head for this
+    async fn decode_layers(
+        &self,
+        _tensor_data: Vec<u8>,
+        _params: &GenerationParams,
+    ) -> Result<TextCompletionOutput> {
+        Err(Error::NotSupported(format!(
+            "Backend '{}' does not support split-layer inference",
+            self.name()
+        )))
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -336,6 +407,15 @@ pub struct BackendConfig {
 
     /// OpenAI-compatible API configuration (used by OpenAi backend type)
     pub openai: Option<super::OpenAiConfig>,
+
+    /// Run a tiny throwaway generation right after a model loads
+    pub warmup_after_load: bool,
+
+    /// Detect model file format from content rather than the extension
+    pub detect_model_format: bool,
+
+    /// Size auto-detected inference threads to physical cores only
+    pub use_physical_cores_only: bool,
 }
 
 impl Default for BackendConfig {
@@ -349,10 +429,46 @@ impl Default for BackendConfig {
             use_mlock: false,
             seed: None,
             openai: None,
+            warmup_after_load: false,
+            detect_model_format: true,
+            use_physical_cores_only: false,
         }
     }
 }
 
+/// Upper bound on a post-load warmup generation
+pub const WARMUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Generate a token or two and throw the result away, so cache and graph
+/// warmup happens before the first real task rather than during it.
+///
+/// Bounded by [`WARMUP_TIMEOUT`]; failures are logged, never returned.
+pub async fn warmup<B: InferenceBackend + ?Sized>(backend: &B) {
+    let input = TextCompletionInput {
+        prompt: "Hello".to_string(),
+        system_prompt: None,
+        params: crate::types::GenerationParams {
+            max_tokens: 2,
+            ..Default::default()
+        },
+    };
+
+    let start = std::time::Instant::now();
+    match tokio::time::timeout(WARMUP_TIMEOUT, backend.text_completion(input)).await {
+        Ok(Ok(_)) => tracing::debug!(
+            backend = backend.name(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "Model warmup complete"
+        ),
+        Ok(Err(e)) => tracing::warn!(backend = backend.name(), error = %e, "Model warmup failed"),
+        Err(_) => tracing::warn!(
+            backend = backend.name(),
+            timeout_secs = WARMUP_TIMEOUT.as_secs(),
+            "Model warmup timed out"
+        ),
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────
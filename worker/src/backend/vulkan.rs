@@ -209,6 +209,7 @@ impl InferenceBackend for VulkanBackend {
             max_batch_size: self.config.batch_size,
             gpu_available: true,
             gpu_device: Some(self.gpu_info.name.clone()),
+            gpu_memory_mb: Some(self.gpu_info.total_memory_mb),
         }
     }
 
@@ -221,6 +222,7 @@ impl InferenceBackend for VulkanBackend {
             memory_used_mb: 0, // CPU memory not tracked for GPU backend
             gpu_memory_used_mb: Some(state.gpu_memory_used_mb),
             error: state.last_error.clone(),
+            breaker: None,
         })
     }
 
@@ -265,6 +267,10 @@ impl InferenceBackend for VulkanBackend {
         None
     }
 
+    fn loaded_model_id(&self) -> Option<String> {
+        self.state.read().loaded_model.as_ref().map(|m| m.spec.id.clone())
+    }
+
     async fn text_completion(
         &self,
         input: TextCompletionInput,
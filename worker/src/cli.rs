@@ -4,6 +4,8 @@
 
 use clap::{Parser, Subcommand};
 
+use crate::config::ConfigFormat;
+
 /// AI4All Worker - Distributed AI compute worker
 ///
 /// Connects to the AI4All coordinator network, receives AI work assignments,
@@ -44,11 +46,94 @@ pub enum Commands {
         /// Output file for benchmark results (JSON)
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Compare against the previous run in the benchmark history
+        #[arg(long)]
+        compare: bool,
     },
 
     /// Display version and build information
     Version,
 
+    /// Show detected hardware, supported backends, and throughput estimates
+    Info {
+        /// Path to configuration file
+        #[arg(short, long, env = "AI4ALL_CONFIG")]
+        config: Option<String>,
+
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the state of a running worker via its control socket
+    ///
+    /// The socket address is `--addr` if given, otherwise `control.listen_addr`
+    /// from the configuration (the same file `run` would load). The worker
+    /// must be running with `control.enabled = true`.
+    Status {
+        /// Path to configuration file
+        #[arg(short, long, env = "AI4ALL_CONFIG")]
+        config: Option<String>,
+
+        /// Control socket address, overriding the configuration
+        #[arg(long)]
+        addr: Option<String>,
+
+        /// Output the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Stop a running worker from taking new tasks, e.g. before maintenance
+    ///
+    /// Tasks already running finish, and heartbeats report the worker as
+    /// paused so the coordinator sends work elsewhere. Reaches the worker
+    /// through its control socket, as `status` does.
+    Pause {
+        /// Path to configuration file
+        #[arg(short, long, env = "AI4ALL_CONFIG")]
+        config: Option<String>,
+
+        /// Control socket address, overriding the configuration
+        #[arg(long)]
+        addr: Option<String>,
+    },
+
+    /// Let a paused worker take tasks again
+    Resume {
+        /// Path to configuration file
+        #[arg(short, long, env = "AI4ALL_CONFIG")]
+        config: Option<String>,
+
+        /// Control socket address, overriding the configuration
+        #[arg(long)]
+        addr: Option<String>,
+    },
+
+    /// Run one synthetic text completion through the executor, offline
+    ///
+    /// Registers backends as `run` does, prints the resolved capabilities,
+    /// then serves the task from the chosen backend without contacting the
+    /// coordinator. Exits non-zero if that backend can't serve it.
+    SelfTest {
+        /// Path to configuration file
+        #[arg(short, long, env = "AI4ALL_CONFIG")]
+        config: Option<String>,
+
+        /// Backend to serve the task (mock, cpu, openai)
+        #[arg(long, default_value = "mock")]
+        backend: String,
+
+        /// Model to run (defaults to `openai.default_model` for openai)
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Prompt of the synthetic task
+        #[arg(long, default_value = "Say hello to the AI4All network.")]
+        prompt: String,
+    },
+
     /// Pair this worker with a wallet via QR code
     Pair {
         /// API server URL (e.g. http://localhost:3000)
@@ -64,6 +149,16 @@ pub enum Commands {
         force: bool,
     },
 
+    /// Replay a recorded session through the coordinator message handlers
+    Replay {
+        /// Session file written by `debug.record_session`
+        path: String,
+
+        /// Treat malformed frames as the strict protocol mode does
+        #[arg(long)]
+        strict: bool,
+    },
+
     /// Configuration management
     Config {
         #[command(subcommand)]
@@ -79,6 +174,10 @@ pub enum ConfigSubcommand {
         /// Path to configuration file
         #[arg(short, long)]
         config: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "toml")]
+        format: ConfigFormat,
     },
 
     /// Initialize a new configuration file
@@ -141,13 +240,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_self_test_defaults() {
+        let cli = Cli::parse_from(["ai4all-worker", "self-test"]);
+        match cli.command {
+            Commands::SelfTest { config, backend, model, .. } => {
+                assert!(config.is_none());
+                assert_eq!(backend, "mock");
+                assert!(model.is_none());
+            }
+            _ => panic!("Expected SelfTest command"),
+        }
+    }
+
     #[test]
     fn test_benchmark_defaults() {
         let cli = Cli::parse_from(["ai4all-worker", "benchmark"]);
         match cli.command {
-            Commands::Benchmark { iterations, output } => {
+            Commands::Benchmark { iterations, output, compare } => {
                 assert_eq!(iterations, 3);
                 assert!(output.is_none());
+                assert!(!compare);
             }
             _ => panic!("Expected Benchmark command"),
         }
@@ -164,7 +277,7 @@ mod tests {
             "results.json",
         ]);
         match cli.command {
-            Commands::Benchmark { iterations, output } => {
+            Commands::Benchmark { iterations, output, .. } => {
                 assert_eq!(iterations, 10);
                 assert_eq!(output, Some("results.json".to_string()));
             }
@@ -185,12 +298,46 @@ mod tests {
         assert!(cli.quiet);
     }
 
+    #[test]
+    fn test_replay_command() {
+        let cli = Cli::parse_from(["ai4all-worker", "replay", "session.jsonl", "--strict"]);
+        match cli.command {
+            Commands::Replay { path, strict } => {
+                assert_eq!(path, "session.jsonl");
+                assert!(strict);
+            }
+            _ => panic!("Expected Replay command"),
+        }
+    }
+
+    #[test]
+    fn test_status_command() {
+        let cli = Cli::parse_from(["ai4all-worker", "status", "--addr", "127.0.0.1:7900", "--json"]);
+        match cli.command {
+            Commands::Status { config, addr, json } => {
+                assert!(config.is_none());
+                assert_eq!(addr.as_deref(), Some("127.0.0.1:7900"));
+                assert!(json);
+            }
+            _ => panic!("Expected Status command"),
+        }
+    }
+
+    #[test]
+    fn test_pause_resume_commands() {
+        let cli = Cli::parse_from(["ai4all-worker", "pause", "--addr", "127.0.0.1:7900"]);
+        assert!(matches!(cli.command, Commands::Pause { addr: Some(_), .. }));
+        let cli = Cli::parse_from(["ai4all-worker", "resume"]);
+        assert!(matches!(cli.command, Commands::Resume { addr: None, .. }));
+    }
+
     #[test]
     fn test_config_show() {
         let cli = Cli::parse_from(["ai4all-worker", "config", "show"]);
         match cli.command {
-            Commands::Config { subcommand: ConfigSubcommand::Show { config } } => {
+            Commands::Config { subcommand: ConfigSubcommand::Show { config, format } } => {
                 assert!(config.is_none());
+                assert_eq!(format, ConfigFormat::Toml);
             }
             _ => panic!("Expected Config Show command"),
         }
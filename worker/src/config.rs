@@ -3,16 +3,119 @@
 //! Supports multiple configuration sources with the following precedence (highest to lowest):
 //! 1. CLI arguments
 //! 2. Environment variables (AI4ALL_* prefix)
-//! 3. Configuration file (TOML)
+//! 3. Configuration file (TOML, YAML or JSON, chosen by file extension)
 //! 4. Default values
 
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
+use url::Url;
 
 use crate::error::{Error, Result};
+use crate::types::{ModelSpec, TaskType};
+
+/// File extensions tried for each config file location, in order
+const CONFIG_EXTENSIONS: [&str; 4] = ["toml", "yaml", "yml", "json"];
+
+/// Settings a coordinator `CONFIG_UPDATE` may change on a running worker.
+/// Anything else needs a restart.
+pub const HOT_UPDATABLE_SETTINGS: [&str; 4] = [
+    "logging.level",
+    "coordinator.heartbeat_interval_ms",
+    "resources.max_concurrent_tasks",
+    "crawler.domain_denylist",
+];
+
+/// Settings touched by a coordinator config update, as dotted paths
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigUpdateOutcome {
+    /// Hot-updatable settings that were merged
+    pub applied: Vec<String>,
+    /// Settings left unchanged because they need a restart
+    pub rejected: Vec<String>,
+}
+
+/// Format of a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Format implied by a file's extension (TOML if unrecognized)
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .as_deref()
+        {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    /// Parse a configuration in this format
+    pub fn parse(self, content: &str) -> Result<WorkerConfig> {
+        let parsed = match self {
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+        };
+        parsed.map_err(|e| Error::Config(format!("Failed to parse config file: {}", e)))
+    }
+
+    /// Render a configuration in this format
+    pub fn render(self, config: &WorkerConfig) -> Result<String> {
+        match self {
+            ConfigFormat::Toml => Ok(toml::to_string_pretty(config)?),
+            ConfigFormat::Yaml => serde_yaml::to_string(config)
+                .map_err(|e| Error::Config(format!("Failed to render config: {}", e))),
+            ConfigFormat::Json => serde_json::to_string_pretty(config)
+                .map_err(|e| Error::Config(format!("Failed to render config: {}", e))),
+        }
+    }
+
+    /// Set the given key paths in a config file's content, leaving every
+    /// other key as written. TOML keeps its comments and layout; YAML and
+    /// JSON are re-rendered from the file's own keys only, so defaults are
+    /// never written out. A null value removes the key.
+    fn edit(self, content: &str, leaves: &[(Vec<&str>, &serde_json::Value)]) -> Result<String> {
+        let render_error = |e: String| Error::Config(format!("Failed to render config: {}", e));
+        match self {
+            ConfigFormat::Toml => {
+                let mut doc: toml_edit::DocumentMut = content
+                    .parse()
+                    .map_err(|e| Error::Config(format!("Failed to parse config file: {}", e)))?;
+                for (path, value) in leaves {
+                    set_toml_path(doc.as_table_mut(), path, value)?;
+                }
+                Ok(doc.to_string())
+            }
+            ConfigFormat::Yaml | ConfigFormat::Json => {
+                let parsed = match self {
+                    ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+                    _ => serde_json::from_str(content).map_err(|e| e.to_string()),
+                };
+                let mut root: serde_json::Value = parsed
+                    .map_err(|e| Error::Config(format!("Failed to parse config file: {}", e)))?;
+                for (path, value) in leaves {
+                    set_json_path(&mut root, path, (*value).clone());
+                }
+                match self {
+                    ConfigFormat::Yaml => serde_yaml::to_string(&root).map_err(|e| render_error(e.to_string())),
+                    _ => serde_json::to_string_pretty(&root).map_err(|e| render_error(e.to_string())),
+                }
+            }
+        }
+    }
+}
 
 /// Main worker configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +130,15 @@ pub struct WorkerConfig {
     /// Resource limits
     pub resources: ResourceSettings,
 
+    /// Task dispatch settings
+    pub executor: ExecutorSettings,
+
+    /// Backend selection settings
+    pub backend_routing: BackendRoutingSettings,
+
+    /// Text output post-processing
+    pub postprocess: PostprocessSettings,
+
     /// GPU settings
     pub gpu: GpuSettings,
 
@@ -47,6 +159,15 @@ pub struct WorkerConfig {
 
     /// Web crawler settings
     pub crawler: CrawlerSettings,
+
+    /// Local control socket settings
+    pub control: ControlSettings,
+
+    /// Limits on what crawl and training tasks may touch
+    pub sandbox: SandboxSettings,
+
+    /// Debugging aids
+    pub debug: DebugSettings,
 }
 
 /// Worker identity settings
@@ -72,6 +193,24 @@ pub struct WorkerSettings {
     /// ML-DSA-65 secret key (hex) for signing requests to the coordinator
     #[serde(skip_serializing_if = "Option::is_none")]
     pub secret_key: Option<String>,
+
+    /// System prompt enforced ahead of every text completion task's own
+    /// system prompt (e.g. safety guardrails)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mandatory_system_prompt: Option<String>,
+
+    /// Accept and advertise DEBUG tasks (requires a `debug-tasks` build)
+    pub debug_tasks: bool,
+
+    /// Fraction of completed non-canary tasks recorded with their inputs
+    /// for offline quality review (0.0 = none, 1.0 = all)
+    pub audit_sample_rate: f64,
+
+    /// Seed for picking audit samples; the same seed picks the same tasks
+    pub audit_sample_seed: u64,
+
+    /// Size in MB at which the audit samples file is rotated
+    pub audit_max_file_mb: u64,
 }
 
 /// Coordinator connection settings
@@ -84,14 +223,71 @@ pub struct CoordinatorSettings {
     /// Reconnection interval in milliseconds
     pub reconnect_interval_ms: u64,
 
+    /// Longest wait between reconnection attempts, jitter included
+    /// (milliseconds)
+    pub max_reconnect_delay_ms: u64,
+
+    /// Extra random wait of up to this long before the first reconnect
+    /// after a lost connection (milliseconds, 0 = none)
+    pub reconnect_spread_ms: u64,
+
     /// Maximum reconnection attempts (0 = infinite)
     pub max_reconnect_attempts: u32,
 
+    /// Exit (code 31) after this long without a registered connection,
+    /// across reconnect attempts, so an orchestrator restarts the worker
+    /// (seconds, 0 = keep retrying)
+    pub exit_after_failed_secs: u64,
+
     /// Connection timeout in milliseconds
     pub connect_timeout_ms: u64,
 
     /// Heartbeat interval in milliseconds
     pub heartbeat_interval_ms: u64,
+
+    /// WebSocket ping interval in milliseconds (0 = no pings)
+    pub ping_interval_ms: u64,
+
+    /// Drop the connection when a ping goes unanswered this long
+    /// (milliseconds)
+    pub ping_timeout_ms: u64,
+
+    /// Base interval between HTTP task polls in milliseconds
+    pub poll_interval_ms: u64,
+
+    /// Longest interval polls back off to while they find no tasks
+    /// (milliseconds)
+    pub max_poll_interval_ms: u64,
+
+    /// Reset the connection on unparseable messages instead of skipping them
+    pub strict_protocol: bool,
+
+    /// Re-send task results until the coordinator acknowledges them
+    pub require_result_ack: bool,
+
+    /// Wait for a result's ack before the first re-send, in milliseconds;
+    /// doubled for each further re-send
+    pub result_ack_timeout_ms: u64,
+
+    /// Cancel tasks that heartbeat acks report as reclaimed (reassigned to
+    /// another worker)
+    pub honor_task_reclamation: bool,
+
+    /// Offer gzip compression of large messages when registering
+    pub compression: bool,
+
+    /// Offer MessagePack (binary frames) instead of JSON when registering
+    pub msgpack: bool,
+
+    /// On a config reload (SIGHUP) with changed `account_id`/`secret_key`,
+    /// register again with the new credentials and reconnect
+    pub reconnect_on_credential_change: bool,
+
+    /// WebSocket subprotocol to request on connect
+    pub subprotocol: Option<String>,
+
+    /// Extra HTTP headers sent with the WebSocket upgrade request
+    pub headers: BTreeMap<String, String>,
 }
 
 /// Resource limit settings
@@ -110,8 +306,149 @@ pub struct ResourceSettings {
     /// Maximum CPU threads to use (0 = auto)
     pub max_threads: u32,
 
+    /// Tasks run at once (`None` = sized from CPU count and memory)
+    pub max_concurrent_tasks: Option<u32>,
+
+    /// Tasks accepted beyond those running that wait for a free slot,
+    /// highest priority first (0 = accept only what can run at once)
+    pub max_queued_tasks: u32,
+
     /// Enable GPU acceleration
     pub enable_gpu: bool,
+
+    /// Maximum model loads in flight at once
+    pub max_concurrent_loads: u32,
+
+    /// What to do with a task arriving while all task slots are taken:
+    /// "reject", "drop_oldest_low_priority", or "block_with_timeout"
+    pub queue_overflow_policy: String,
+
+    /// How long "block_with_timeout" waits for a free slot (milliseconds)
+    pub queue_block_timeout_ms: u64,
+
+    /// Run a tiny throwaway generation after each model load so the first
+    /// real task doesn't pay for cache/graph warmup
+    pub warmup_after_load: bool,
+
+    /// Load `warmup_model` and run a throwaway generation at startup, before
+    /// the worker takes tasks
+    pub warmup: bool,
+
+    /// Model preloaded by `warmup` (empty = `openai.default_model`)
+    pub warmup_model: String,
+
+    /// Detect model file format from its leading bytes instead of trusting
+    /// the file extension
+    pub detect_model_format: bool,
+
+    /// How long a model that fails to run here (missing, corrupt, or
+    /// incompatible) is refused and advertised as declined (0 = never)
+    pub model_decline_period_secs: u64,
+
+    /// Ceiling on any task's `max_tokens`, bounding per-task cost
+    /// independent of model context (0 = no ceiling)
+    pub max_generation_tokens: u32,
+
+    /// Slowest acceptable streaming generation rate; a task generating
+    /// below it for a whole window is aborted (0 = no minimum)
+    pub min_tokens_per_sec: f64,
+
+    /// Window the generation rate is measured over (seconds)
+    pub slow_generation_window_secs: u64,
+
+    /// Size auto-detected inference threads to physical cores, skipping
+    /// hyperthreads (ignored when max_threads is set)
+    pub use_physical_cores_only: bool,
+
+    /// Memory use above which the least recently used idle model is
+    /// evicted, and new tasks rejected if that isn't enough (MB, 0 = off)
+    pub memory_pressure_mb: u64,
+}
+
+/// Task dispatch settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutorSettings {
+    /// Window for coalescing concurrent embeddings tasks into a single
+    /// backend call (milliseconds, 0 = disabled)
+    pub embedding_batch_window_ms: u64,
+
+    /// Consecutive backend failures that disable the backend and withdraw
+    /// its task types (0 = never disable)
+    pub breaker_max_failures: u32,
+
+    /// Window the consecutive failures must fall within (seconds)
+    pub breaker_window_secs: u64,
+
+    /// How long a disabled backend stays off before a probe task (seconds)
+    pub breaker_open_secs: u64,
+
+    /// Web crawl tasks run at once, separate from inference concurrency
+    pub max_concurrent_crawls: u32,
+
+    /// Longest a task may wait queued before it is dropped unexecuted
+    /// (seconds, 0 = no limit)
+    pub max_queue_age_secs: u64,
+
+    /// How long running tasks may take to finish after SIGTERM before the
+    /// worker exits without them (seconds, 0 = exit at once)
+    pub drain_grace_secs: u64,
+
+    /// Forward generated tokens of tasks that ask for streaming to the
+    /// coordinator as they are produced
+    pub stream_progress: bool,
+
+    /// Tasks of a type run at once, within the global concurrency limit,
+    /// e.g. `TRAINING_BATCH = 1` (unlisted types: the global limit alone)
+    pub max_concurrent_per_type: HashMap<TaskType, u32>,
+}
+
+impl Default for ExecutorSettings {
+    fn default() -> Self {
+        Self {
+            embedding_batch_window_ms: 0,
+            breaker_max_failures: 5,
+            breaker_window_secs: 60,
+            breaker_open_secs: 120,
+            max_concurrent_crawls: 2,
+            max_queue_age_secs: 0,
+            drain_grace_secs: 60,
+            stream_progress: false,
+            max_concurrent_per_type: HashMap::new(),
+        }
+    }
+}
+
+/// Backend selection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendRoutingSettings {
+    /// Run models too large for the GPU's memory on the CPU backend instead
+    /// of failing the task
+    pub auto_cpu_fallback: bool,
+}
+
+impl Default for BackendRoutingSettings {
+    fn default() -> Self {
+        Self {
+            auto_cpu_fallback: true,
+        }
+    }
+}
+
+/// Text output post-processing settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostprocessSettings {
+    /// Transforms applied in order to text outputs: strip_code_fences,
+    /// trim, remove_prefix_regex, max_sentences (empty = output untouched)
+    pub steps: Vec<String>,
+
+    /// Pattern removed from the start of the output by remove_prefix_regex
+    pub prefix_regex: String,
+
+    /// Sentences kept by max_sentences (0 = no limit)
+    pub max_sentences: u32,
 }
 
 /// Logging settings
@@ -147,6 +484,24 @@ pub struct StorageSettings {
 
     /// Temporary files directory
     pub temp_dir: String,
+
+    /// Models downloaded into `model_dir` the first time a task needs them
+    #[serde(default)]
+    pub models: Vec<ModelSourceSettings>,
+}
+
+/// One `[[storage.models]]` entry: a GGUF model fetched on first use
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelSourceSettings {
+    /// Model ID tasks refer to it by; stored as `<id>.gguf`
+    pub id: String,
+
+    /// HTTP(S) URL of the model file
+    pub url: String,
+
+    /// Expected SHA-256 of the file, hex (empty = not checked)
+    pub sha256: String,
 }
 
 /// GPU configuration settings
@@ -183,6 +538,10 @@ pub struct PeerSettings {
     /// TCP listen port for peer connections (0 = auto-assign)
     pub listen_port: u16,
 
+    /// IP address or network interface name the mesh listens on and
+    /// announces to peers (empty = all interfaces)
+    pub listen_addr: String,
+
     /// Maximum number of peer connections
     pub max_peers: usize,
 
@@ -192,8 +551,55 @@ pub struct PeerSettings {
     /// Timeout in milliseconds before a peer is considered stale
     pub stale_timeout_ms: u64,
 
+    /// Consecutive unanswered pings (the oldest older than
+    /// `stale_timeout_ms`) after which a connected peer is marked stale
+    pub max_missed_pings: u32,
+
     /// Auto-connect to discovered peers
     pub auto_connect: bool,
+
+    /// Retries (with exponential backoff) after a failed peer connection
+    pub max_connect_retries: u32,
+
+    /// Delay before the first connection retry (ms); doubles per retry
+    pub retry_base_delay_ms: u64,
+
+    /// Cap on the delay between connection retries (ms)
+    pub retry_max_delay_ms: u64,
+
+    /// Ready shards a model-shard group needs before running tasks
+    /// (0 = all shards)
+    pub shard_quorum: u32,
+
+    /// Time a group may take to reach quorum before it is disbanded (ms)
+    pub group_ready_timeout_ms: u64,
+
+    /// Drop a peer that sends nothing for this long (ms); must exceed
+    /// `ping_interval_ms` since idle peers are kept alive by pings
+    pub read_timeout_ms: u64,
+
+    /// Drop a peer whose socket accepts no data for this long (ms)
+    pub write_timeout_ms: u64,
+
+    /// Fail a shard-group task when a peer shard returns no output within
+    /// this long (ms)
+    pub shard_timeout_ms: u64,
+
+    /// Ask the coordinator for a fresh peer directory when at least this
+    /// many entries of one have unparseable addresses (0 = never)
+    pub directory_refetch_min_malformed: u32,
+
+    /// Only accept peers that connect encrypted; connections are encrypted
+    /// with a key derived from `worker.secret_key`, so this needs account
+    /// credentials
+    pub require_encryption: bool,
+
+    /// Offer and accept gzip compression of large messages (shard tensors)
+    pub compression: bool,
+
+    /// Offer new tasks to idle peers once this many are queued locally
+    /// (0 = never offload)
+    pub offload_queue_depth: usize,
 }
 
 /// OpenAI-compatible API backend settings
@@ -217,6 +623,88 @@ pub struct OpenAiSettings {
 
     /// Maximum retries on transient failures
     pub max_retries: u32,
+
+    /// Classification scoring strategy: "generative" or "embeddings"
+    pub classification_strategy: String,
+
+    /// Calibrate classification scores (logprobs / softmax) instead of raw scores
+    pub calibrate_classification: bool,
+
+    /// Most texts sent in one embeddings request; larger inputs are split
+    pub embeddings_batch_size: usize,
+
+    /// Reuse a health check result for this long (ms, 0 = no caching)
+    pub health_cache_ttl_ms: u64,
+
+    /// Consecutive failed requests that open an endpoint's circuit breaker
+    /// (0 = never open)
+    pub breaker_max_failures: u32,
+
+    /// How long an open breaker fails requests fast before a probe (ms)
+    pub breaker_open_ms: u64,
+
+    /// Extra HTTP headers sent with every API request
+    pub extra_headers: BTreeMap<String, String>,
+
+    /// Several endpoints, each serving some models or task types. When
+    /// empty, `base_url`/`api_key`/`default_model` form the only endpoint.
+    #[serde(default)]
+    pub endpoints: Vec<OpenAiEndpointSettings>,
+}
+
+/// One `[[openai.endpoints]]` entry. Timeouts, retries, headers,
+/// classification and circuit breaker settings come from `[openai]`; each
+/// endpoint has its own breaker.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAiEndpointSettings {
+    /// Name used in logs (empty = `base_url`)
+    pub name: String,
+
+    /// API base URL
+    pub base_url: String,
+
+    /// API key (empty for local servers)
+    pub api_key: String,
+
+    /// Models this endpoint serves (empty = any model)
+    pub models: Vec<String>,
+
+    /// Task types this endpoint serves, e.g. `["EMBEDDINGS"]` (empty = all)
+    pub tasks: Vec<TaskType>,
+
+    /// Model used until a task asks for another (empty = the first of
+    /// `models`, or `openai.default_model`)
+    pub default_model: String,
+}
+
+impl OpenAiSettings {
+    /// The endpoints to register, with names and default models filled in
+    pub fn resolved_endpoints(&self) -> Vec<OpenAiEndpointSettings> {
+        if self.endpoints.is_empty() {
+            return vec![OpenAiEndpointSettings {
+                name: self.base_url.clone(),
+                base_url: self.base_url.clone(),
+                api_key: self.api_key.clone(),
+                default_model: self.default_model.clone(),
+                ..Default::default()
+            }];
+        }
+        self.endpoints
+            .iter()
+            .map(|e| {
+                let mut e = e.clone();
+                if e.name.is_empty() {
+                    e.name = e.base_url.clone();
+                }
+                if e.default_model.is_empty() {
+                    e.default_model =
+                        e.models.first().cloned().unwrap_or_else(|| self.default_model.clone());
+                }
+                e
+            })
+            .collect()
+    }
 }
 
 /// Plugin system settings
@@ -232,11 +720,22 @@ pub struct PluginSettings {
     /// Plugin registry URL
     pub registry_url: String,
 
-    /// Verify plugin checksums
+    /// Verify plugin checksums, refusing plugins without one
     pub verify_checksums: bool,
 
+    /// Hex ed25519 public key the registry signs plugin manifests with
+    /// (empty = signatures not checked)
+    pub registry_public_key: String,
+
     /// Download timeout in seconds
     pub download_timeout_secs: u64,
+
+    /// Plugin names allowed to be downloaded or loaded (empty = any)
+    pub allowed_plugins: Vec<String>,
+
+    /// GPU vendors ("amd", "nvidia", "intel", "apple") whose plugins may be
+    /// downloaded or loaded (empty = any)
+    pub allowed_vendors: Vec<String>,
 }
 
 /// Web crawler settings
@@ -256,10 +755,16 @@ pub struct CrawlerSettings {
     /// Maximum pages per seed per crawl run (safety cap)
     pub max_pages: u32,
 
-    /// Minimum milliseconds between requests to the same domain
+    /// Minimum milliseconds between requests to the same host, across all
+    /// crawls (0 = no limit)
     pub rate_limit_ms: u64,
 
-    /// Respect robots.txt exclusions
+    /// Random extra delay of up to this many milliseconds added to each
+    /// same-host gap, so crawls started together don't stay in lockstep
+    pub rate_limit_jitter_ms: u64,
+
+    /// Respect robots.txt rules and `Crawl-delay`, and meta robots
+    /// `noindex`/`nofollow` directives
     pub respect_robots: bool,
 
     /// User-Agent header (empty = "AI4All/{version}")
@@ -267,6 +772,53 @@ pub struct CrawlerSettings {
 
     /// Generate vector embeddings for crawled pages (requires [openai] backend)
     pub generate_embeddings: bool,
+
+    /// Model used to embed crawled pages (empty = `openai.default_model`)
+    pub embedding_model: String,
+
+    /// OpenAI-compatible endpoint serving `embedding_model`
+    /// (empty = `openai.base_url`)
+    pub embedding_base_url: String,
+
+    /// Domains never fetched by any crawl, whatever the task allows
+    /// (subdomains included). Reloaded on SIGHUP and coordinator config updates.
+    #[serde(default)]
+    pub domain_denylist: Vec<String>,
+}
+
+/// Local control socket settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlSettings {
+    /// Enable the control socket (task listing/cancellation for operators)
+    pub enabled: bool,
+
+    /// Listen address; must be a loopback address since commands are
+    /// unauthenticated
+    pub listen_addr: String,
+}
+
+/// Task execution sandbox settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxSettings {
+    /// Hosts crawl tasks may contact: domains (subdomains included),
+    /// addresses or CIDR ranges. Empty = no restriction.
+    pub egress_allowlist: Vec<String>,
+
+    /// Confine files written for tasks (model downloads) to
+    /// `storage.data_dir`
+    pub restrict_writes: bool,
+}
+
+/// Debugging settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DebugSettings {
+    /// Directory to record coordinator traffic and peer events to, for
+    /// offline replay (None = no recording)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record_session: Option<String>,
 }
 
 // Default implementations
@@ -277,6 +829,9 @@ impl Default for WorkerConfig {
             worker: WorkerSettings::default(),
             coordinator: CoordinatorSettings::default(),
             resources: ResourceSettings::default(),
+            executor: ExecutorSettings::default(),
+            backend_routing: BackendRoutingSettings::default(),
+            postprocess: PostprocessSettings::default(),
             gpu: GpuSettings::default(),
             plugins: PluginSettings::default(),
             logging: LoggingSettings::default(),
@@ -284,6 +839,18 @@ impl Default for WorkerConfig {
             peer: PeerSettings::default(),
             openai: OpenAiSettings::default(),
             crawler: CrawlerSettings::default(),
+            control: ControlSettings::default(),
+            sandbox: SandboxSettings::default(),
+            debug: DebugSettings::default(),
+        }
+    }
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_addr: "127.0.0.1:7878".to_string(),
         }
     }
 }
@@ -296,9 +863,13 @@ impl Default for CrawlerSettings {
             depth: 1,
             max_pages: 50,
             rate_limit_ms: 1000,
+            rate_limit_jitter_ms: 250,
             respect_robots: true,
             user_agent: String::new(), // resolved to "AI4All/{version}" at runtime
             generate_embeddings: false,
+            embedding_model: String::new(),
+            embedding_base_url: String::new(),
+            domain_denylist: vec![],
         }
     }
 }
@@ -311,6 +882,11 @@ impl Default for WorkerSettings {
             tags: vec![],
             account_id: None,
             secret_key: None,
+            mandatory_system_prompt: None,
+            debug_tasks: false,
+            audit_sample_rate: 0.0,
+            audit_sample_seed: 0,
+            audit_max_file_mb: 64,
         }
     }
 }
@@ -320,9 +896,25 @@ impl Default for CoordinatorSettings {
         Self {
             url: "wss://coordinator.ai4all.network".to_string(),
             reconnect_interval_ms: 5000,
+            max_reconnect_delay_ms: 60000,
+            reconnect_spread_ms: 0,
             max_reconnect_attempts: 0, // Infinite
+            exit_after_failed_secs: 0,
             connect_timeout_ms: 30000,
             heartbeat_interval_ms: 30000,
+            ping_interval_ms: 15000,
+            ping_timeout_ms: 10000,
+            poll_interval_ms: 5000,
+            max_poll_interval_ms: 30000,
+            strict_protocol: false,
+            require_result_ack: false,
+            result_ack_timeout_ms: 10000,
+            honor_task_reclamation: true,
+            compression: true,
+            msgpack: true,
+            reconnect_on_credential_change: true,
+            subprotocol: None,
+            headers: BTreeMap::new(),
         }
     }
 }
@@ -334,8 +926,41 @@ impl Default for ResourceSettings {
             max_gpu_memory_mb: 0,
             max_gpu_percent: 75,
             max_threads: 0, // Auto-detect
+            max_concurrent_tasks: None,
+            max_queued_tasks: 4,
             enable_gpu: true,
+            max_concurrent_loads: 1,
+            queue_overflow_policy: "reject".to_string(),
+            queue_block_timeout_ms: 5000,
+            warmup_after_load: false,
+            warmup: false,
+            warmup_model: String::new(),
+            detect_model_format: true,
+            model_decline_period_secs: 600,
+            max_generation_tokens: 0,
+            min_tokens_per_sec: 0.0,
+            slow_generation_window_secs: 10,
+            use_physical_cores_only: false,
+            memory_pressure_mb: 0,
+        }
+    }
+}
+
+impl ResourceSettings {
+    /// Tasks to run at once: the configured value, or else one per two
+    /// CPUs and per 2 GB of the memory budget (the lower of `max_memory_mb`
+    /// and `total_memory_mb`), between 1 and 8
+    pub fn concurrent_tasks(&self, cpu_count: usize, total_memory_mb: u64) -> u32 {
+        if let Some(n) = self.max_concurrent_tasks {
+            return n;
         }
+        let budget_mb = match self.max_memory_mb {
+            0 => total_memory_mb,
+            limit => limit.min(total_memory_mb),
+        };
+        let by_cpu = (cpu_count / 2) as u64;
+        let by_memory = budget_mb / 2048;
+        by_cpu.min(by_memory).clamp(1, 8) as u32
     }
 }
 
@@ -357,6 +982,7 @@ impl Default for StorageSettings {
             data_dir: "~/.ai4all/worker".to_string(),
             model_dir: "~/.ai4all/worker/models".to_string(),
             temp_dir: "~/.ai4all/worker/temp".to_string(),
+            models: Vec::new(),
         }
     }
 }
@@ -378,10 +1004,24 @@ impl Default for PeerSettings {
         Self {
             enabled: true,
             listen_port: 0, // Auto-assign
+            listen_addr: String::new(),
             max_peers: 32,
             ping_interval_ms: 15000,
             stale_timeout_ms: 60000,
+            max_missed_pings: 3,
             auto_connect: true,
+            max_connect_retries: 5,
+            retry_base_delay_ms: 1000,
+            retry_max_delay_ms: 60000,
+            shard_quorum: 0, // All shards
+            group_ready_timeout_ms: 120000,
+            read_timeout_ms: 45000,
+            write_timeout_ms: 30000,
+            shard_timeout_ms: 60000,
+            directory_refetch_min_malformed: 0,
+            require_encryption: false,
+            compression: true,
+            offload_queue_depth: 2,
         }
     }
 }
@@ -395,6 +1035,14 @@ impl Default for OpenAiSettings {
             default_model: "llama3".to_string(),
             timeout_secs: 120,
             max_retries: 2,
+            classification_strategy: "generative".to_string(),
+            calibrate_classification: true,
+            embeddings_batch_size: 64,
+            health_cache_ttl_ms: 10000,
+            breaker_max_failures: 5,
+            breaker_open_ms: 30000,
+            extra_headers: BTreeMap::new(),
+            endpoints: Vec::new(),
         }
     }
 }
@@ -406,7 +1054,10 @@ impl Default for PluginSettings {
             auto_download: true,
             registry_url: "https://plugins.ai4all.network".to_string(),
             verify_checksums: true,
+            registry_public_key: String::new(),
             download_timeout_secs: 300,
+            allowed_plugins: vec![],
+            allowed_vendors: vec![],
         }
     }
 }
@@ -422,8 +1073,7 @@ impl WorkerConfig {
             debug!(path = %path.display(), "Loading configuration file");
             let content = fs::read_to_string(&path)
                 .map_err(|e| Error::Config(format!("Failed to read config file: {}", e)))?;
-            config = toml::from_str(&content)
-                .map_err(|e| Error::Config(format!("Failed to parse config file: {}", e)))?;
+            config = ConfigFormat::from_path(&path).parse(&content)?;
             info!(path = %path.display(), "Configuration loaded from file");
         }
 
@@ -455,32 +1105,102 @@ impl WorkerConfig {
             }
         }
 
-        // Search in standard locations
-        let search_paths = [
+        for path in Self::search_paths() {
+            if path.exists() {
+                debug!(path = %path.display(), "Found configuration file");
+                return Ok(Some(path));
+            }
+        }
+
+        debug!("No configuration file found, using defaults");
+        Ok(None)
+    }
+
+    /// Config file locations searched in order. Worker-specific names are
+    /// tried with every supported extension; the generic `config` in the
+    /// current directory only as TOML, so another tool's `config.json` or
+    /// `config.yaml` is never picked up.
+    fn search_paths() -> Vec<PathBuf> {
+        let worker_stems = [
             // Current directory
-            PathBuf::from("ai4all-worker.toml"),
-            PathBuf::from("config.toml"),
+            PathBuf::from("ai4all-worker"),
             // User config directory
             dirs::config_dir()
-                .map(|p| p.join("ai4all").join("worker.toml"))
+                .map(|p| p.join("ai4all").join("worker"))
                 .unwrap_or_default(),
             // Home directory
             dirs::home_dir()
-                .map(|p| p.join(".ai4all").join("worker.toml"))
+                .map(|p| p.join(".ai4all").join("worker"))
                 .unwrap_or_default(),
             // System config (Linux)
-            PathBuf::from("/etc/ai4all/worker.toml"),
+            PathBuf::from("/etc/ai4all/worker"),
         ];
+        let mut paths: Vec<PathBuf> = CONFIG_EXTENSIONS
+            .iter()
+            .map(|ext| worker_stems[0].with_extension(ext))
+            .collect();
+        paths.push(PathBuf::from("config.toml"));
+        paths.extend(worker_stems[1..].iter().flat_map(|stem| {
+            CONFIG_EXTENSIONS.iter().map(move |ext| stem.with_extension(ext))
+        }));
+        paths
+    }
 
-        for path in &search_paths {
-            if path.exists() {
-                debug!(path = %path.display(), "Found configuration file");
-                return Ok(Some(path.clone()));
+    /// Merge a coordinator config update (a JSON object shaped like the
+    /// config) into this config. Only [`HOT_UPDATABLE_SETTINGS`] are taken;
+    /// other settings are reported as rejected. If the merged config is
+    /// invalid, nothing changes and the error is returned.
+    pub fn apply_update(&mut self, update: &serde_json::Value) -> Result<ConfigUpdateOutcome> {
+        let (merged, outcome) = self.merged_with(update)?;
+        merged.validate()?;
+        *self = merged;
+        Ok(outcome)
+    }
+
+    /// Write the hot-updatable part of a coordinator config update into the
+    /// config file found for `config_path`, in that file's format. Only the
+    /// updated keys are edited: the rest of the file, including comments in
+    /// TOML, is left as written, and neither environment overrides nor
+    /// defaults are saved. Returns the file written.
+    pub fn persist_update(config_path: Option<&str>, update: &serde_json::Value) -> Result<PathBuf> {
+        let path = Self::find_config_file(config_path)?
+            .ok_or_else(|| Error::Config("No configuration file to save the update to".to_string()))?;
+        let format = ConfigFormat::from_path(&path);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| Error::Config(format!("Failed to read config file: {}", e)))?;
+        // Merge into the parsed file first so an invalid update is refused
+        // before anything is written
+        let (_, outcome) = format.parse(&content)?.merged_with(update)?;
+
+        let mut leaves = Vec::new();
+        collect_update_leaves(update, &mut Vec::new(), &mut leaves);
+        leaves.retain(|(key_path, _)| outcome.applied.contains(&key_path.join(".")));
+        fs::write(&path, format.edit(&content, &leaves)?)
+            .map_err(|e| Error::Config(format!("Failed to write config file: {}", e)))?;
+        Ok(path)
+    }
+
+    /// This config with the hot-updatable settings of `update` merged in
+    fn merged_with(&self, update: &serde_json::Value) -> Result<(Self, ConfigUpdateOutcome)> {
+        let mut leaves = Vec::new();
+        collect_update_leaves(update, &mut Vec::new(), &mut leaves);
+
+        let mut merged = serde_json::to_value(self)
+            .map_err(|e| Error::Config(format!("Failed to serialize config: {}", e)))?;
+        let mut outcome = ConfigUpdateOutcome::default();
+        for (path, value) in leaves {
+            let dotted = path.join(".");
+            if HOT_UPDATABLE_SETTINGS.contains(&dotted.as_str()) {
+                set_json_path(&mut merged, &path, value.clone());
+                outcome.applied.push(dotted);
+            } else {
+                outcome.rejected.push(dotted);
             }
         }
 
-        debug!("No configuration file found, using defaults");
-        Ok(None)
+        let merged = serde_json::from_value(merged)
+            .map_err(|e| Error::Config(format!("Invalid configuration update: {}", e)))?;
+        Ok((merged, outcome))
     }
 
     /// Apply environment variable overrides
@@ -492,6 +1212,24 @@ impl WorkerConfig {
         if let Ok(val) = std::env::var("AI4ALL_WORKER_NAME") {
             self.worker.name = Some(val);
         }
+        if let Ok(val) = std::env::var("AI4ALL_DEBUG_TASKS") {
+            self.worker.debug_tasks = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_AUDIT_SAMPLE_RATE") {
+            if let Ok(n) = val.parse() {
+                self.worker.audit_sample_rate = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_AUDIT_SAMPLE_SEED") {
+            if let Ok(n) = val.parse() {
+                self.worker.audit_sample_seed = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_AUDIT_MAX_FILE_MB") {
+            if let Ok(n) = val.parse() {
+                self.worker.audit_max_file_mb = n;
+            }
+        }
         if let Ok(val) = std::env::var("AI4ALL_ACCOUNT_ID") {
             self.worker.account_id = Some(val);
         }
@@ -508,11 +1246,70 @@ impl WorkerConfig {
                 self.coordinator.reconnect_interval_ms = n;
             }
         }
+        if let Ok(val) = std::env::var("AI4ALL_MAX_RECONNECT_DELAY_MS") {
+            if let Ok(n) = val.parse() {
+                self.coordinator.max_reconnect_delay_ms = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_RECONNECT_SPREAD_MS") {
+            if let Ok(n) = val.parse() {
+                self.coordinator.reconnect_spread_ms = n;
+            }
+        }
         if let Ok(val) = std::env::var("AI4ALL_MAX_RECONNECT_ATTEMPTS") {
             if let Ok(n) = val.parse() {
                 self.coordinator.max_reconnect_attempts = n;
             }
         }
+        if let Ok(val) = std::env::var("AI4ALL_EXIT_AFTER_FAILED_SECS") {
+            if let Ok(n) = val.parse() {
+                self.coordinator.exit_after_failed_secs = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_PING_INTERVAL_MS") {
+            if let Ok(n) = val.parse() {
+                self.coordinator.ping_interval_ms = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_PING_TIMEOUT_MS") {
+            if let Ok(n) = val.parse() {
+                self.coordinator.ping_timeout_ms = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_POLL_INTERVAL_MS") {
+            if let Ok(n) = val.parse() {
+                self.coordinator.poll_interval_ms = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_MAX_POLL_INTERVAL_MS") {
+            if let Ok(n) = val.parse() {
+                self.coordinator.max_poll_interval_ms = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_STRICT_PROTOCOL") {
+            self.coordinator.strict_protocol = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_REQUIRE_RESULT_ACK") {
+            self.coordinator.require_result_ack = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_RESULT_ACK_TIMEOUT_MS") {
+            if let Ok(n) = val.parse() {
+                self.coordinator.result_ack_timeout_ms = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_HONOR_TASK_RECLAMATION") {
+            self.coordinator.honor_task_reclamation = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_COORDINATOR_COMPRESSION") {
+            self.coordinator.compression = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_COORDINATOR_MSGPACK") {
+            self.coordinator.msgpack = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_RECONNECT_ON_CREDENTIAL_CHANGE") {
+            self.coordinator.reconnect_on_credential_change =
+                val.to_lowercase() == "true" || val == "1";
+        }
 
         // Resource settings
         if let Ok(val) = std::env::var("AI4ALL_MAX_MEMORY_MB") {
@@ -535,26 +1332,129 @@ impl WorkerConfig {
                 self.resources.max_threads = n;
             }
         }
+        if let Ok(val) = std::env::var("AI4ALL_MAX_CONCURRENT_TASKS") {
+            if let Ok(n) = val.parse() {
+                self.resources.max_concurrent_tasks = Some(n);
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_MAX_QUEUED_TASKS") {
+            if let Ok(n) = val.parse() {
+                self.resources.max_queued_tasks = n;
+            }
+        }
         if let Ok(val) = std::env::var("AI4ALL_ENABLE_GPU") {
             self.resources.enable_gpu = val.to_lowercase() == "true" || val == "1";
         }
-
-        // Logging settings
-        if let Ok(val) = std::env::var("AI4ALL_LOG_LEVEL") {
-            self.logging.level = val;
+        if let Ok(val) = std::env::var("AI4ALL_MAX_CONCURRENT_LOADS") {
+            if let Ok(n) = val.parse() {
+                self.resources.max_concurrent_loads = n;
+            }
         }
-        if let Ok(val) = std::env::var("AI4ALL_LOG_FILE") {
-            self.logging.file = Some(val);
+        if let Ok(val) = std::env::var("AI4ALL_QUEUE_OVERFLOW_POLICY") {
+            self.resources.queue_overflow_policy = val;
         }
-        if let Ok(val) = std::env::var("AI4ALL_LOG_JSON") {
-            self.logging.json_format = val.to_lowercase() == "true" || val == "1";
+        if let Ok(val) = std::env::var("AI4ALL_WARMUP_AFTER_LOAD") {
+            self.resources.warmup_after_load = val.to_lowercase() == "true" || val == "1";
         }
-
-        // Storage settings
-        if let Ok(val) = std::env::var("AI4ALL_DATA_DIR") {
-            self.storage.data_dir = val;
+        if let Ok(val) = std::env::var("AI4ALL_WARMUP") {
+            self.resources.warmup = val.to_lowercase() == "true" || val == "1";
         }
-        if let Ok(val) = std::env::var("AI4ALL_MODEL_DIR") {
+        if let Ok(val) = std::env::var("AI4ALL_WARMUP_MODEL") {
+            self.resources.warmup_model = val;
+        }
+        if let Ok(val) = std::env::var("AI4ALL_DETECT_MODEL_FORMAT") {
+            self.resources.detect_model_format = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_MODEL_DECLINE_PERIOD_SECS") {
+            if let Ok(n) = val.parse() {
+                self.resources.model_decline_period_secs = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_MAX_GENERATION_TOKENS") {
+            if let Ok(n) = val.parse() {
+                self.resources.max_generation_tokens = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_MIN_TOKENS_PER_SEC") {
+            if let Ok(n) = val.parse() {
+                self.resources.min_tokens_per_sec = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_SLOW_GENERATION_WINDOW_SECS") {
+            if let Ok(n) = val.parse() {
+                self.resources.slow_generation_window_secs = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_USE_PHYSICAL_CORES_ONLY") {
+            self.resources.use_physical_cores_only = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_MEMORY_PRESSURE_MB") {
+            if let Ok(n) = val.parse() {
+                self.resources.memory_pressure_mb = n;
+            }
+        }
+
+        // Executor settings
+        if let Ok(val) = std::env::var("AI4ALL_EMBEDDING_BATCH_WINDOW_MS") {
+            if let Ok(n) = val.parse() {
+                self.executor.embedding_batch_window_ms = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_BREAKER_MAX_FAILURES") {
+            if let Ok(n) = val.parse() {
+                self.executor.breaker_max_failures = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_BREAKER_WINDOW_SECS") {
+            if let Ok(n) = val.parse() {
+                self.executor.breaker_window_secs = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_BREAKER_OPEN_SECS") {
+            if let Ok(n) = val.parse() {
+                self.executor.breaker_open_secs = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_MAX_CONCURRENT_CRAWLS") {
+            if let Ok(n) = val.parse() {
+                self.executor.max_concurrent_crawls = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_MAX_QUEUE_AGE_SECS") {
+            if let Ok(n) = val.parse() {
+                self.executor.max_queue_age_secs = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_DRAIN_GRACE_SECS") {
+            if let Ok(n) = val.parse() {
+                self.executor.drain_grace_secs = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_STREAM_PROGRESS") {
+            self.executor.stream_progress = val.to_lowercase() == "true" || val == "1";
+        }
+
+        // Backend routing settings
+        if let Ok(val) = std::env::var("AI4ALL_AUTO_CPU_FALLBACK") {
+            self.backend_routing.auto_cpu_fallback = val.to_lowercase() == "true" || val == "1";
+        }
+
+        // Logging settings
+        if let Ok(val) = std::env::var("AI4ALL_LOG_LEVEL") {
+            self.logging.level = val;
+        }
+        if let Ok(val) = std::env::var("AI4ALL_LOG_FILE") {
+            self.logging.file = Some(val);
+        }
+        if let Ok(val) = std::env::var("AI4ALL_LOG_JSON") {
+            self.logging.json_format = val.to_lowercase() == "true" || val == "1";
+        }
+
+        // Storage settings
+        if let Ok(val) = std::env::var("AI4ALL_DATA_DIR") {
+            self.storage.data_dir = val;
+        }
+        if let Ok(val) = std::env::var("AI4ALL_MODEL_DIR") {
             self.storage.model_dir = val;
         }
         if let Ok(val) = std::env::var("AI4ALL_TEMP_DIR") {
@@ -588,11 +1488,40 @@ impl WorkerConfig {
                 self.peer.listen_port = n;
             }
         }
+        if let Ok(val) = std::env::var("AI4ALL_PEER_LISTEN_ADDR") {
+            self.peer.listen_addr = val;
+        }
         if let Ok(val) = std::env::var("AI4ALL_PEER_MAX_PEERS") {
             if let Ok(n) = val.parse() {
                 self.peer.max_peers = n;
             }
         }
+        if let Ok(val) = std::env::var("AI4ALL_PEER_MAX_MISSED_PINGS") {
+            if let Ok(n) = val.parse() {
+                self.peer.max_missed_pings = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_PEER_SHARD_QUORUM") {
+            if let Ok(n) = val.parse() {
+                self.peer.shard_quorum = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_PEER_DIRECTORY_REFETCH_MIN_MALFORMED") {
+            if let Ok(n) = val.parse() {
+                self.peer.directory_refetch_min_malformed = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_PEER_REQUIRE_ENCRYPTION") {
+            self.peer.require_encryption = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_PEER_COMPRESSION") {
+            self.peer.compression = val.to_lowercase() == "true" || val == "1";
+        }
+        if let Ok(val) = std::env::var("AI4ALL_PEER_OFFLOAD_QUEUE_DEPTH") {
+            if let Ok(n) = val.parse() {
+                self.peer.offload_queue_depth = n;
+            }
+        }
 
         // OpenAI settings
         if let Ok(val) = std::env::var("AI4ALL_OPENAI_ENABLED") {
@@ -612,6 +1541,26 @@ impl WorkerConfig {
                 self.openai.timeout_secs = n;
             }
         }
+        if let Ok(val) = std::env::var("AI4ALL_OPENAI_EMBEDDINGS_BATCH_SIZE") {
+            if let Ok(n) = val.parse() {
+                self.openai.embeddings_batch_size = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_OPENAI_HEALTH_CACHE_TTL_MS") {
+            if let Ok(n) = val.parse() {
+                self.openai.health_cache_ttl_ms = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_OPENAI_BREAKER_MAX_FAILURES") {
+            if let Ok(n) = val.parse() {
+                self.openai.breaker_max_failures = n;
+            }
+        }
+        if let Ok(val) = std::env::var("AI4ALL_OPENAI_BREAKER_OPEN_MS") {
+            if let Ok(n) = val.parse() {
+                self.openai.breaker_open_ms = n;
+            }
+        }
 
         // Plugin settings
         if let Ok(val) = std::env::var("AI4ALL_PLUGIN_DIR") {
@@ -623,6 +1572,26 @@ impl WorkerConfig {
         if let Ok(val) = std::env::var("AI4ALL_PLUGIN_REGISTRY_URL") {
             self.plugins.registry_url = val;
         }
+        if let Ok(val) = std::env::var("AI4ALL_PLUGIN_REGISTRY_PUBLIC_KEY") {
+            self.plugins.registry_public_key = val;
+        }
+
+        // Sandbox settings
+        if let Ok(val) = std::env::var("AI4ALL_SANDBOX_EGRESS_ALLOWLIST") {
+            self.sandbox.egress_allowlist = val
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+        }
+        if let Ok(val) = std::env::var("AI4ALL_SANDBOX_RESTRICT_WRITES") {
+            self.sandbox.restrict_writes = val.to_lowercase() == "true" || val == "1";
+        }
+
+        // Debug settings
+        if let Ok(val) = std::env::var("AI4ALL_RECORD_SESSION") {
+            self.debug.record_session = Some(val);
+        }
     }
 
     /// Expand ~ and other path variables
@@ -635,6 +1604,9 @@ impl WorkerConfig {
         if let Some(ref file) = self.logging.file {
             self.logging.file = Some(expand_path(file));
         }
+        if let Some(ref dir) = self.debug.record_session {
+            self.debug.record_session = Some(expand_path(dir));
+        }
     }
 
     /// Validate the configuration
@@ -648,6 +1620,76 @@ impl WorkerConfig {
                 "Coordinator URL must start with ws:// or wss://".to_string(),
             ));
         }
+        if self.coordinator.heartbeat_interval_ms == 0 {
+            return Err(Error::Config(
+                "coordinator.heartbeat_interval_ms must be at least 1".to_string(),
+            ));
+        }
+        if self.coordinator.ping_interval_ms > 0 && self.coordinator.ping_timeout_ms == 0 {
+            return Err(Error::Config(
+                "coordinator.ping_timeout_ms must be at least 1".to_string(),
+            ));
+        }
+        if self.coordinator.poll_interval_ms == 0 {
+            return Err(Error::Config(
+                "coordinator.poll_interval_ms must be at least 1".to_string(),
+            ));
+        }
+        if self.coordinator.max_poll_interval_ms < self.coordinator.poll_interval_ms {
+            return Err(Error::Config(
+                "coordinator.max_poll_interval_ms must be at least coordinator.poll_interval_ms".to_string(),
+            ));
+        }
+        if self.coordinator.max_reconnect_delay_ms == 0 {
+            return Err(Error::Config(
+                "coordinator.max_reconnect_delay_ms must be at least 1".to_string(),
+            ));
+        }
+        if self.coordinator.require_result_ack && self.coordinator.result_ack_timeout_ms == 0 {
+            return Err(Error::Config(
+                "coordinator.result_ack_timeout_ms must be at least 1".to_string(),
+            ));
+        }
+        for (name, value) in &self.coordinator.headers {
+            if !is_valid_header_name(name) {
+                return Err(Error::Config(format!(
+                    "Invalid coordinator header name: {:?}",
+                    name
+                )));
+            }
+            if !is_valid_header_value(value) {
+                return Err(Error::Config(format!(
+                    "Invalid value for coordinator header {}",
+                    name
+                )));
+            }
+        }
+        if let Some(protocol) = &self.coordinator.subprotocol {
+            if !is_valid_header_name(protocol) {
+                return Err(Error::Config(format!(
+                    "Invalid coordinator subprotocol: {:?}",
+                    protocol
+                )));
+            }
+        }
+
+        // Debug tasks never reach production builds
+        if self.worker.debug_tasks && !cfg!(feature = "debug-tasks") {
+            return Err(Error::Config(
+                "worker.debug_tasks requires a build with the 'debug-tasks' feature".to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.worker.audit_sample_rate) {
+            return Err(Error::Config(
+                "worker.audit_sample_rate must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        if self.worker.audit_max_file_mb == 0 {
+            return Err(Error::Config(
+                "worker.audit_max_file_mb must be at least 1".to_string(),
+            ));
+        }
 
         // Validate GPU percentage
         if self.resources.max_gpu_percent > 100 {
@@ -656,6 +1698,276 @@ impl WorkerConfig {
             ));
         }
 
+        if self.resources.max_concurrent_tasks == Some(0) {
+            return Err(Error::Config(
+                "max_concurrent_tasks must be at least 1".to_string(),
+            ));
+        }
+
+        if self.resources.max_queued_tasks > 1000 {
+            return Err(Error::Config(
+                "max_queued_tasks must be at most 1000".to_string(),
+            ));
+        }
+
+        if self.resources.max_concurrent_loads == 0 {
+            return Err(Error::Config(
+                "max_concurrent_loads must be at least 1".to_string(),
+            ));
+        }
+
+        if !self.resources.min_tokens_per_sec.is_finite() || self.resources.min_tokens_per_sec < 0.0 {
+            return Err(Error::Config(
+                "min_tokens_per_sec must be a non-negative number".to_string(),
+            ));
+        }
+        if self.resources.min_tokens_per_sec > 0.0 && self.resources.slow_generation_window_secs == 0 {
+            return Err(Error::Config(
+                "slow_generation_window_secs must be at least 1".to_string(),
+            ));
+        }
+
+        // The worker alone uses a few hundred MB; a lower limit would reject
+        // every task
+        if self.resources.memory_pressure_mb > 0 && self.resources.memory_pressure_mb < 256 {
+            return Err(Error::Config(
+                "memory_pressure_mb must be 0 (disabled) or at least 256".to_string(),
+            ));
+        }
+
+        // Every embeddings task waits out the window, so keep it short
+        if self.executor.embedding_batch_window_ms > 1000 {
+            return Err(Error::Config(
+                "executor.embedding_batch_window_ms must be at most 1000".to_string(),
+            ));
+        }
+
+        if self.executor.breaker_max_failures > 0
+            && (self.executor.breaker_window_secs == 0 || self.executor.breaker_open_secs == 0)
+        {
+            return Err(Error::Config(
+                "executor.breaker_window_secs and executor.breaker_open_secs must be at least 1"
+                    .to_string(),
+            ));
+        }
+
+        if self.executor.max_concurrent_crawls == 0 {
+            return Err(Error::Config(
+                "executor.max_concurrent_crawls must be at least 1".to_string(),
+            ));
+        }
+
+        if let Some(task_type) = self
+            .executor
+            .max_concurrent_per_type
+            .iter()
+            .find_map(|(task_type, &limit)| (limit == 0).then_some(task_type))
+        {
+            return Err(Error::Config(format!(
+                "executor.max_concurrent_per_type: the limit for {} must be at least 1",
+                task_type
+            )));
+        }
+
+        // Validate post-processing steps
+        let valid_steps = ["strip_code_fences", "trim", "remove_prefix_regex", "max_sentences"];
+        for step in &self.postprocess.steps {
+            if !valid_steps.contains(&step.to_lowercase().as_str()) {
+                return Err(Error::Config(format!(
+                    "Invalid postprocess step '{}'. Must be one of: {}",
+                    step,
+                    valid_steps.join(", ")
+                )));
+            }
+        }
+        if self.postprocess.steps.iter().any(|s| s.eq_ignore_ascii_case("remove_prefix_regex")) {
+            regex::Regex::new(&self.postprocess.prefix_regex).map_err(|e| {
+                Error::Config(format!("Invalid postprocess.prefix_regex: {}", e))
+            })?;
+        }
+
+        // Validate queue overflow policy
+        let valid_policies = ["reject", "drop_oldest_low_priority", "block_with_timeout"];
+        if !valid_policies.contains(&self.resources.queue_overflow_policy.to_lowercase().as_str()) {
+            return Err(Error::Config(format!(
+                "Invalid resources.queue_overflow_policy '{}'. Must be one of: {}",
+                self.resources.queue_overflow_policy,
+                valid_policies.join(", ")
+            )));
+        }
+
+        if self.peer.group_ready_timeout_ms == 0 {
+            return Err(Error::Config(
+                "peer.group_ready_timeout_ms must be at least 1".to_string(),
+            ));
+        }
+
+        // A read timeout at or below the ping interval drops healthy idle peers
+        if self.peer.read_timeout_ms <= self.peer.ping_interval_ms {
+            return Err(Error::Config(format!(
+                "peer.read_timeout_ms ({}) must exceed peer.ping_interval_ms ({})",
+                self.peer.read_timeout_ms, self.peer.ping_interval_ms
+            )));
+        }
+        if self.peer.write_timeout_ms == 0 {
+            return Err(Error::Config(
+                "peer.write_timeout_ms must be at least 1".to_string(),
+            ));
+        }
+        if self.peer.shard_timeout_ms == 0 {
+            return Err(Error::Config(
+                "peer.shard_timeout_ms must be at least 1".to_string(),
+            ));
+        }
+        if self.peer.max_missed_pings == 0 {
+            return Err(Error::Config(
+                "peer.max_missed_pings must be at least 1".to_string(),
+            ));
+        }
+        if self.peer.retry_base_delay_ms == 0 {
+            return Err(Error::Config(
+                "peer.retry_base_delay_ms must be at least 1".to_string(),
+            ));
+        }
+        if self.peer.retry_max_delay_ms < self.peer.retry_base_delay_ms {
+            return Err(Error::Config(format!(
+                "peer.retry_max_delay_ms ({}) must be at least peer.retry_base_delay_ms ({})",
+                self.peer.retry_max_delay_ms, self.peer.retry_base_delay_ms
+            )));
+        }
+        if self.peer.enabled
+            && self.peer.require_encryption
+            && (self.worker.account_id.is_none() || self.worker.secret_key.is_none())
+        {
+            return Err(Error::Config(
+                "peer.require_encryption needs worker.account_id and worker.secret_key".to_string(),
+            ));
+        }
+
+        // The port comes from listen_port; interface names are looked up
+        // when the mesh starts
+        let listen_addr = self.peer.listen_addr.trim();
+        if !listen_addr.is_empty()
+            && listen_addr.trim_matches(|c| c == '[' || c == ']').parse::<std::net::IpAddr>().is_err()
+            && listen_addr.contains(|c: char| c == ':' || c == '/' || c.is_whitespace())
+        {
+            return Err(Error::Config(format!(
+                "peer.listen_addr '{}' must be an IP address or interface name, without a port",
+                self.peer.listen_addr
+            )));
+        }
+
+        // Denylist entries are bare domains, matched against URL hosts
+        if let Some(entry) = self.crawler.domain_denylist.iter().find(|d| {
+            let d = d.trim();
+            d.is_empty() || d.contains(['/', ':', ' '])
+        }) {
+            return Err(Error::Config(format!(
+                "Invalid crawler.domain_denylist entry '{}'. Use a bare domain like \"example.com\"",
+                entry
+            )));
+        }
+
+        // Validate control socket address (unauthenticated, so local only)
+        match self.control.listen_addr.parse::<std::net::SocketAddr>() {
+            Ok(addr) if addr.ip().is_loopback() => {}
+            Ok(_) => {
+                return Err(Error::Config(
+                    "control.listen_addr must be a loopback address".to_string(),
+                ));
+            }
+            Err(_) => {
+                return Err(Error::Config(format!(
+                    "Invalid control.listen_addr '{}'",
+                    self.control.listen_addr
+                )));
+            }
+        }
+
+        // Validate sandbox egress allowlist
+        crate::sandbox::EgressPolicy::new(&self.sandbox.egress_allowlist)?;
+
+        // Validate classification strategy
+        let valid_strategies = ["generative", "embeddings"];
+        if !valid_strategies.contains(&self.openai.classification_strategy.to_lowercase().as_str()) {
+            return Err(Error::Config(format!(
+                "Invalid openai.classification_strategy '{}'. Must be one of: {}",
+                self.openai.classification_strategy,
+                valid_strategies.join(", ")
+            )));
+        }
+        for (name, value) in &self.openai.extra_headers {
+            if !is_valid_header_name(name) {
+                return Err(Error::Config(format!(
+                    "Invalid openai.extra_headers name: {:?}",
+                    name
+                )));
+            }
+            if !is_valid_header_value(value) {
+                return Err(Error::Config(format!(
+                    "Invalid value for openai.extra_headers {}",
+                    name
+                )));
+            }
+        }
+
+        for (i, endpoint) in self.openai.endpoints.iter().enumerate() {
+            if Url::parse(&endpoint.base_url).is_err() {
+                return Err(Error::Config(format!(
+                    "Invalid openai.endpoints[{}].base_url '{}'",
+                    i, endpoint.base_url
+                )));
+            }
+        }
+
+        for (i, model) in self.storage.models.iter().enumerate() {
+            let valid_id = !model.id.is_empty()
+                && !model.id.contains(['/', '\\'])
+                && model.id != ".."
+                && model.id != ".";
+            if !valid_id {
+                return Err(Error::Config(format!(
+                    "Invalid storage.models[{}].id '{}': must be a non-empty file name",
+                    i, model.id
+                )));
+            }
+            if !Url::parse(&model.url).is_ok_and(|u| matches!(u.scheme(), "http" | "https")) {
+                return Err(Error::Config(format!(
+                    "Invalid storage.models[{}].url '{}'",
+                    i, model.url
+                )));
+            }
+            if !model.sha256.is_empty()
+                && (model.sha256.len() != 64 || !model.sha256.chars().all(|c| c.is_ascii_hexdigit()))
+            {
+                return Err(Error::Config(format!(
+                    "storage.models[{}].sha256 must be 64 hex digits",
+                    i
+                )));
+            }
+        }
+
+        // Validate plugin vendor allowlist
+        if let Some(bad) = self
+            .plugins
+            .allowed_vendors
+            .iter()
+            .find(|v| !["amd", "nvidia", "intel", "apple"].contains(&v.to_lowercase().as_str()))
+        {
+            return Err(Error::Config(format!(
+                "Invalid plugins.allowed_vendors entry '{}'. Must be one of: amd, nvidia, intel, apple",
+                bad
+            )));
+        }
+
+        // Validate the pinned plugin registry key
+        let key = &self.plugins.registry_public_key;
+        if !key.is_empty() && !hex::decode(key).is_ok_and(|k| k.len() == 32) {
+            return Err(Error::Config(
+                "plugins.registry_public_key must be a hex ed25519 public key (64 hex digits)".to_string(),
+            ));
+        }
+
         // Validate log level
         let valid_levels = ["trace", "debug", "info", "warn", "error"];
         if !valid_levels.contains(&self.logging.level.to_lowercase().as_str()) {
@@ -679,6 +1991,30 @@ impl WorkerConfig {
         PathBuf::from(&self.storage.model_dir)
     }
 
+    /// Model for work that names none, which is also the one preloaded at
+    /// startup: `resources.warmup_model`, else `openai.default_model`
+    pub fn default_model(&self) -> String {
+        if self.resources.warmup_model.is_empty() {
+            self.openai.default_model.clone()
+        } else {
+            self.resources.warmup_model.clone()
+        }
+    }
+
+    /// Specs of the `[[storage.models]]` entries, stored in the model directory
+    pub fn model_sources(&self) -> Vec<ModelSpec> {
+        let model_dir = self.model_dir();
+        self.storage
+            .models
+            .iter()
+            .map(|m| {
+                let sha256 = (!m.sha256.is_empty()).then(|| m.sha256.clone());
+                let path = model_dir.join(format!("{}.gguf", m.id));
+                ModelSpec::remote_gguf(&m.id, &m.url, sha256, path)
+            })
+            .collect()
+    }
+
     /// Get the plugin directory as a PathBuf
     pub fn plugin_dir(&self) -> PathBuf {
         PathBuf::from(&self.plugins.plugin_dir)
@@ -692,6 +2028,19 @@ fn expand_path(path: &str) -> String {
         .into_owned()
 }
 
+/// HTTP header name / token: visible ASCII without separators
+fn is_valid_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// HTTP header value: visible ASCII, spaces and tabs
+fn is_valid_header_value(value: &str) -> bool {
+    value.bytes().all(|b| b == b'\t' || (b' '..=b'~').contains(&b))
+}
+
 /// Initialize a new configuration file
 pub fn init_config(path: Option<&str>, force: bool) -> Result<()> {
     let config_path = path
@@ -743,6 +2092,22 @@ fn generate_default_config() -> String {
 # Tags for filtering work assignments
 tags = []
 
+# System prompt prepended to every task's own system prompt (tasks can't
+# remove or override it)
+# mandatory_system_prompt = "Follow the network safety guidelines."
+
+# Accept DEBUG tasks (echo/reverse/uppercase) for end-to-end pipeline
+# testing; only allowed in builds with the debug-tasks feature
+debug_tasks = false
+
+# Record this fraction of completed tasks (input, output and hashes) to
+# audit/samples.jsonl in the data directory for quality review (0.0 = none)
+audit_sample_rate = 0.0
+# Seed for choosing sampled tasks, for reproducible samples
+audit_sample_seed = 0
+# Size in MB at which samples.jsonl is rotated to samples.jsonl.1
+audit_max_file_mb = 64
+
 [coordinator]
 # Coordinator WebSocket URL
 url = "wss://coordinator.ai4all.network"
@@ -750,15 +2115,64 @@ url = "wss://coordinator.ai4all.network"
 # Reconnection interval in milliseconds
 reconnect_interval_ms = 5000
 
+# Reconnect delays are randomized and never exceed max_reconnect_delay_ms;
+# the first reconnect after a lost connection waits up to
+# reconnect_spread_ms longer (0 = no extra wait)
+max_reconnect_delay_ms = 60000
+reconnect_spread_ms = 0
+
 # Maximum reconnection attempts (0 = infinite)
 max_reconnect_attempts = 0
 
+# Exit with code 31 after this many seconds without a registered
+# connection, so an orchestrator can restart the worker (0 = keep retrying)
+exit_after_failed_secs = 0
+
 # Connection timeout in milliseconds
 connect_timeout_ms = 30000
 
 # Heartbeat interval in milliseconds
 heartbeat_interval_ms = 30000
 
+# WebSocket ping interval in milliseconds (0 = no pings); a ping unanswered
+# for ping_timeout_ms drops the connection and reconnects
+ping_interval_ms = 15000
+ping_timeout_ms = 10000
+
+# Interval between HTTP task polls in milliseconds; polls that find no
+# tasks back off up to max_poll_interval_ms
+poll_interval_ms = 5000
+max_poll_interval_ms = 30000
+
+# Reset the connection on unparseable coordinator messages
+# (unknown message types are always ignored)
+strict_protocol = false
+
+# Re-send task results until the coordinator acknowledges them, first
+# after result_ack_timeout_ms without an ack, then with doubling delays
+require_result_ack = false
+result_ack_timeout_ms = 10000
+
+# Stop work on tasks the coordinator reports as reclaimed in heartbeat acks
+honor_task_reclamation = true
+
+# Compress large messages (8 KiB and up) if the coordinator supports it
+compression = true
+
+# Use MessagePack binary frames instead of JSON if the coordinator supports it
+msgpack = true
+
+# Re-register and reconnect when account_id/secret_key change on SIGHUP
+reconnect_on_credential_change = true
+
+# WebSocket subprotocol to request on connect
+# subprotocol = "ai4all.v1"
+
+# Extra headers sent with the WebSocket upgrade request
+# (credential values are redacted from logs)
+# [coordinator.headers]
+# Authorization = "Bearer <token>"
+
 [resources]
 # Maximum memory usage in MB
 max_memory_mb = 8192
@@ -772,9 +2186,105 @@ max_gpu_percent = 75
 # Maximum CPU threads to use (0 = auto-detect)
 max_threads = 0
 
+# Tasks run at once; unset sizes it from CPU count and memory
+# max_concurrent_tasks = 4
+
+# Tasks accepted beyond those running, waiting for a free slot highest
+# priority first (0 = accept only what can run at once)
+max_queued_tasks = 4
+
 # Enable GPU acceleration
 enable_gpu = true
 
+# Maximum model loads in flight at once (loads are disk/memory heavy)
+max_concurrent_loads = 1
+
+# Task arriving while all task slots are taken: "reject" refuses it and
+# reports busy, "block_with_timeout" waits up to queue_block_timeout_ms for a
+# slot (see config.example.toml for the priority-eviction policy)
+queue_overflow_policy = "reject"
+queue_block_timeout_ms = 5000
+
+# Run a 1-2 token throwaway generation after each model load so the first
+# real task isn't slowed by warmup (failures are logged and ignored)
+warmup_after_load = false
+
+# Preload warmup_model (empty = openai.default_model) at startup
+warmup = false
+warmup_model = ""
+
+# Detect model file format from content (magic bytes) instead of the
+# file extension
+detect_model_format = true
+
+# Refuse a model that failed to run here (missing, corrupt, incompatible)
+# for this many seconds, and tell the coordinator (0 = never decline)
+model_decline_period_secs = 600
+
+# Clamp any task's max_tokens to this ceiling (0 = no ceiling)
+max_generation_tokens = 0
+
+# Abort a generation running below this many tokens per second for a whole
+# slow_generation_window_secs, measured from the first token (0 = no minimum)
+min_tokens_per_sec = 0.0
+slow_generation_window_secs = 10
+
+# Use one inference thread per physical core, skipping hyperthreads
+# (applies when max_threads = 0)
+use_physical_cores_only = false
+
+# Above this much memory in use (MB), unload the least recently used idle
+# model; if still above, reject new tasks until usage drops (0 = off)
+memory_pressure_mb = 0
+
+[executor]
+# Coalesce embeddings tasks arriving within this many milliseconds into one
+# backend call (0 = disabled; each task then pays no added latency)
+embedding_batch_window_ms = 0
+
+# Disable a backend after this many consecutive failures within
+# breaker_window_secs, withdrawing its task types; a probe task is let
+# through after breaker_open_secs (0 = never disable)
+breaker_max_failures = 5
+breaker_window_secs = 60
+breaker_open_secs = 120
+
+# Web crawl tasks run at once, limited separately from inference
+max_concurrent_crawls = 2
+
+# Drop tasks that waited queued longer than this many seconds without
+# running them (0 = no limit)
+max_queue_age_secs = 0
+
+# On SIGTERM, stop taking tasks and give running ones this many seconds to
+# finish before exiting without them (0 = exit at once)
+drain_grace_secs = 60
+
+# Send tokens of text completion tasks that ask for streaming to the
+# coordinator as they are generated, ahead of the final result
+stream_progress = false
+
+# Limit how many tasks of a type run at once, within the global limit
+# [executor.max_concurrent_per_type]
+# TRAINING_BATCH = 1
+# EMBEDDINGS = 8
+
+[backend_routing]
+# Run models too large for the GPU's memory on the CPU backend instead of
+# failing the task
+auto_cpu_fallback = true
+
+[postprocess]
+# Transforms applied in order to text outputs (completion, summary, answer):
+# strip_code_fences, trim, remove_prefix_regex, max_sentences
+steps = []
+
+# Pattern removed from the start of the output by remove_prefix_regex
+prefix_regex = ""
+
+# Sentences kept by max_sentences (0 = no limit)
+max_sentences = 0
+
 [logging]
 # Log level: trace, debug, info, warn, error
 level = "info"
@@ -801,6 +2311,13 @@ model_dir = "~/.ai4all/worker/models"
 # Temporary files directory
 temp_dir = "~/.ai4all/worker/temp"
 
+# Models downloaded into model_dir the first time a task needs them
+# (resumable; checked against sha256 when given)
+# [[storage.models]]
+# id = "tinyllama"
+# url = "https://huggingface.co/TheBloke/TinyLlama-1.1B-Chat-v1.0-GGUF/resolve/main/tinyllama-1.1b-chat-v1.0.Q4_K_M.gguf"
+# sha256 = ""
+
 [peer]
 # Enable peer-to-peer mesh networking
 enabled = true
@@ -808,6 +2325,10 @@ enabled = true
 # TCP listen port for peer connections (0 = auto-assign)
 listen_port = 0
 
+# IP address or interface name (e.g. "eth1") to listen on and announce to
+# peers (empty = all interfaces)
+listen_addr = ""
+
 # Maximum number of peer connections
 max_peers = 32
 
@@ -817,9 +2338,53 @@ ping_interval_ms = 15000
 # Timeout before a peer is considered stale (milliseconds)
 stale_timeout_ms = 60000
 
+# Unanswered pings in a row, the first sent over stale_timeout_ms ago,
+# before a connected peer is marked stale
+max_missed_pings = 3
+
 # Auto-connect to discovered peers
 auto_connect = true
 
+# Retries (with exponential backoff) after a failed peer connection
+max_connect_retries = 5
+
+# Delay before the first connection retry (ms); doubles on each retry
+retry_base_delay_ms = 1000
+
+# Cap on the delay between connection retries (ms)
+retry_max_delay_ms = 60000
+
+# Ready shards a model-shard group needs before running tasks (0 = all)
+shard_quorum = 0
+
+# Time a group may take to reach quorum before it is disbanded (ms)
+group_ready_timeout_ms = 120000
+
+# Drop a peer that sends nothing for this long (ms); must exceed ping_interval_ms
+read_timeout_ms = 45000
+
+# Drop a peer whose socket accepts no data for this long (ms)
+write_timeout_ms = 30000
+
+# Fail a shard-group task when a peer shard returns no output for this long (ms)
+shard_timeout_ms = 60000
+
+# Re-request the peer directory when this many of its entries have invalid
+# addresses (0 = never)
+directory_refetch_min_malformed = 0
+
+# Encrypt peer connections with a key derived from worker.secret_key
+# (always, when account credentials are set) and turn away peers that
+# connect in plaintext
+require_encryption = false
+
+# Compress large messages (8 KiB and up) with peers that support it
+compression = true
+
+# Offer new tasks to the idle peer with the most spare capacity once this
+# many are queued locally (0 = never)
+offload_queue_depth = 2
+
 [openai]
 # Enable OpenAI-compatible API backend
 enabled = true
@@ -839,6 +2404,44 @@ timeout_secs = 120
 # Maximum retries on transient failures
 max_retries = 2
 
+# Classification scoring: "generative" (prompt for a label) or "embeddings"
+# (text/label similarity)
+classification_strategy = "generative"
+
+# Calibrate classification scores into probabilities over the label set
+# (token logprobs for generative, softmax for embeddings)
+calibrate_classification = true
+
+# Most texts per embeddings request; larger inputs are sent in batches
+embeddings_batch_size = 64
+
+# Reuse a health check result for this long (ms, 0 = no caching)
+health_cache_ttl_ms = 10000
+
+# After this many consecutive failed requests, fail fast for breaker_open_ms,
+# then let one probe request through (0 = never fail fast)
+breaker_max_failures = 5
+breaker_open_ms = 30000
+
+# Extra headers sent with every API request
+# (credential values are redacted from logs)
+# [openai.extra_headers]
+# OpenAI-Organization = "org-..."
+
+# Several endpoints instead of base_url/api_key above: tasks go to an
+# endpoint serving their model (empty models = any) and task type (empty
+# tasks = all), preferring healthy ones and falling back on failure
+# [[openai.endpoints]]
+# name = "chat"
+# base_url = "http://localhost:8000/v1"
+# models = ["llama3", "mistral"]
+#
+# [[openai.endpoints]]
+# name = "embeddings"
+# base_url = "http://localhost:8001/v1"
+# models = ["nomic-embed-text"]
+# tasks = ["EMBEDDINGS"]
+
 [crawler]
 # Enable web crawling (coordinator-assigned WEB_CRAWL tasks always work when registered)
 enabled = false
@@ -852,17 +2455,147 @@ depth = 1
 # Maximum pages to fetch per seed per run
 max_pages = 50
 
-# Minimum milliseconds between requests to the same domain
+# Minimum milliseconds between requests to the same host, shared by all
+# concurrent crawls (0 = no limit)
 rate_limit_ms = 1000
 
-# Respect robots.txt exclusions
+# Random extra delay (up to this many ms) added between requests to a host
+rate_limit_jitter_ms = 250
+
+# Respect robots.txt (Allow/Disallow rules and Crawl-delay) and meta robots
+# noindex/nofollow
 respect_robots = true
 
 # Generate vector embeddings for each page (requires [openai] backend to be configured)
 generate_embeddings = false
+
+# Small embedding model and endpoint for page embeddings, kept apart from the
+# inference model (empty = the [openai] default_model and base_url)
+embedding_model = ""
+embedding_base_url = ""
+
+# Domains never fetched, even when a task allows them (subdomains included).
+# Reloaded on SIGHUP without restarting the worker.
+# domain_denylist = ["blocked.example.com"]
+
+[control]
+# Local control socket for operators: one command per line, JSON replies
+#   stats                    - worker status (used by `ai4all-worker status`)
+#   tasks                    - list running/queued tasks
+#   cancel <task_id> [force] - cancel a task
+enabled = false
+
+# Loopback address only (commands are unauthenticated)
+listen_addr = "127.0.0.1:7878"
+
+[sandbox]
+# Hosts crawl tasks may contact: domains (subdomains included), addresses
+# or CIDR ranges. Empty = no restriction.
+egress_allowlist = []
+
+# Confine files written for tasks (model downloads) to storage.data_dir
+restrict_writes = false
+
+[debug]
+# Record coordinator messages, sent results and peer events to a session file
+# in this directory; reproduce with `ai4all-worker replay <file>`.
+# Secrets are redacted, but recordings still contain task inputs and outputs.
+# record_session = "~/.ai4all/worker/sessions"
 "#.to_string()
 }
 
+/// Every non-object value in `value` with its key path
+fn collect_update_leaves<'a>(
+    value: &'a serde_json::Value,
+    path: &mut Vec<&'a str>,
+    leaves: &mut Vec<(Vec<&'a str>, &'a serde_json::Value)>,
+) {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() || path.is_empty() => {
+            for (key, child) in map {
+                path.push(key);
+                collect_update_leaves(child, path, leaves);
+                path.pop();
+            }
+        }
+        _ => leaves.push((path.clone(), value)),
+    }
+}
+
+/// Set the value at a key path, creating objects along the way
+fn set_json_path(root: &mut serde_json::Value, path: &[&str], value: serde_json::Value) {
+    let mut node = root;
+    for key in path {
+        if !node.is_object() {
+            *node = serde_json::Value::Object(Default::default());
+        }
+        node = node
+            .as_object_mut()
+            .expect("just made an object")
+            .entry(key.to_string())
+            .or_insert(serde_json::Value::Null);
+    }
+    *node = value;
+}
+
+/// Set the value at a key path in a TOML document, creating tables along
+/// the way. An existing value keeps its surrounding whitespace and trailing
+/// comment; a null value removes the key.
+fn set_toml_path(root: &mut toml_edit::Table, path: &[&str], value: &serde_json::Value) -> Result<()> {
+    let Some((key, parents)) = path.split_last() else {
+        return Ok(());
+    };
+    let mut table: &mut dyn toml_edit::TableLike = root;
+    for parent in parents {
+        table = table
+            .entry(parent)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| Error::Config(format!("Config key '{}' is not a table", parent)))?;
+    }
+
+    let Some(new) = toml_value(value) else {
+        table.remove(key);
+        return Ok(());
+    };
+    match table.get_mut(key) {
+        Some(toml_edit::Item::Value(existing)) => {
+            let decor = existing.decor().clone();
+            *existing = new;
+            *existing.decor_mut() = decor;
+        }
+        _ => {
+            table.insert(key, toml_edit::Item::Value(new));
+        }
+    }
+    Ok(())
+}
+
+/// A JSON value as a TOML value (None for null, which TOML can't express)
+fn toml_value(value: &serde_json::Value) -> Option<toml_edit::Value> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(b) => Some((*b).into()),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Some(i.into()),
+            None => n.as_f64().map(Into::into),
+        },
+        serde_json::Value::String(s) => Some(s.as_str().into()),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .map(toml_value)
+            .collect::<Option<toml_edit::Array>>()
+            .map(Into::into),
+        serde_json::Value::Object(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, child) in map {
+                table.insert(key, toml_value(child)?);
+            }
+            Some(table.into())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -903,6 +2636,186 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_coordinator_headers() {
+        let config: WorkerConfig = toml::from_str(
+            r#"
+[coordinator]
+url = "wss://custom.example.com"
+subprotocol = "ai4all.v1"
+
+[coordinator.headers]
+Authorization = "Bearer abc123"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.coordinator.subprotocol.as_deref(), Some("ai4all.v1"));
+        assert_eq!(config.coordinator.headers["Authorization"], "Bearer abc123");
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config.clone();
+        invalid.coordinator.headers.insert("Bad Header".to_string(), "x".to_string());
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = config;
+        invalid.coordinator.headers.insert("X-Note".to_string(), "line\nbreak".to_string());
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_sandbox_settings() {
+        let config: WorkerConfig = toml::from_str(
+            r#"
+[sandbox]
+egress_allowlist = ["example.com", "10.0.0.0/8"]
+restrict_writes = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.sandbox.egress_allowlist.len(), 2);
+        assert!(config.sandbox.restrict_writes);
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config;
+        invalid.sandbox.egress_allowlist.push("10.0.0.0/40".to_string());
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_max_concurrent_per_type() {
+        let config: WorkerConfig = toml::from_str(
+            r#"
+[executor.max_concurrent_per_type]
+TRAINING_BATCH = 1
+EMBEDDINGS = 8
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.executor.max_concurrent_per_type[&TaskType::TrainingBatch], 1);
+        assert_eq!(config.executor.max_concurrent_per_type[&TaskType::Embeddings], 8);
+        assert!(config.validate().is_ok());
+
+        // Survives a round trip through the config file format
+        let parsed: WorkerConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(parsed.executor.max_concurrent_per_type.len(), 2);
+
+        let mut invalid = config;
+        invalid.executor.max_concurrent_per_type.insert(TaskType::TrainingBatch, 0);
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_openai_extra_headers() {
+        let config: WorkerConfig = toml::from_str(
+            r#"
+[openai.extra_headers]
+OpenAI-Organization = "org-42"
+HTTP-Referer = "https://example.com"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.openai.extra_headers["OpenAI-Organization"], "org-42");
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config;
+        invalid.openai.extra_headers.insert("Bad Header".to_string(), "x".to_string());
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_apply_update_hot_settings_only() {
+        let mut config = WorkerConfig::default();
+        let outcome = config
+            .apply_update(&serde_json::json!({
+                "logging": { "level": "debug" },
+                "resources": { "max_concurrent_tasks": 2, "max_memory_mb": 1 },
+                "crawler": { "domain_denylist": ["blocked.example.com"] },
+                "storage": { "data_dir": "/elsewhere" },
+            }))
+            .unwrap();
+
+        assert_eq!(
+            outcome.applied,
+            ["crawler.domain_denylist", "logging.level", "resources.max_concurrent_tasks"]
+        );
+        assert_eq!(outcome.rejected, ["resources.max_memory_mb", "storage.data_dir"]);
+        assert_eq!(config.logging.level, "debug");
+        assert_eq!(config.resources.max_concurrent_tasks, Some(2));
+        assert_eq!(config.crawler.domain_denylist, ["blocked.example.com"]);
+        assert_eq!(config.resources.max_memory_mb, WorkerConfig::default().resources.max_memory_mb);
+
+        // An invalid value leaves the config as it was
+        assert!(config
+            .apply_update(&serde_json::json!({ "coordinator": { "heartbeat_interval_ms": 0 } }))
+            .is_err());
+        assert!(config
+            .apply_update(&serde_json::json!({ "logging": { "level": "loud" } }))
+            .is_err());
+        assert_eq!(config.logging.level, "debug");
+        assert_eq!(config.coordinator.heartbeat_interval_ms, 30000);
+    }
+
+    #[test]
+    fn test_persist_update_writes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("worker.toml");
+        fs::write(&path, "[logging]\nlevel = \"warn\"\n\n[resources]\nmax_memory_mb = 2048\n").unwrap();
+        let update = serde_json::json!({
+            "logging": { "level": "debug" },
+            "resources": { "max_memory_mb": 1 },
+        });
+
+        let written = WorkerConfig::persist_update(path.to_str(), &update).unwrap();
+        assert_eq!(written, path);
+
+        let saved = ConfigFormat::Toml.parse(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.logging.level, "debug");
+        // Settings needing a restart aren't saved either
+        assert_eq!(saved.resources.max_memory_mb, 2048);
+    }
+
+    #[test]
+    fn test_persist_update_keeps_comments_and_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("worker.toml");
+        let original = "# Operator notes\n[logging]\nlevel = \"warn\" # chatty otherwise\n";
+        fs::write(&path, original).unwrap();
+        let update = serde_json::json!({
+            "logging": { "level": "debug" },
+            "resources": { "max_concurrent_tasks": 3 },
+            "crawler": { "domain_denylist": ["example.com"] },
+        });
+
+        WorkerConfig::persist_update(path.to_str(), &update).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# Operator notes\n[logging]\nlevel = \"debug\" # chatty otherwise\n"));
+        // Only the updated keys are added; defaults stay out of the file
+        assert!(!content.contains("heartbeat_interval_ms"));
+        assert!(!content.contains("[gpu]"));
+
+        let saved = ConfigFormat::Toml.parse(&content).unwrap();
+        assert_eq!(saved.resources.max_concurrent_tasks, Some(3));
+        assert_eq!(saved.crawler.domain_denylist, vec!["example.com".to_string()]);
+
+        // Null clears an optional setting
+        let update = serde_json::json!({ "resources": { "max_concurrent_tasks": null } });
+        WorkerConfig::persist_update(path.to_str(), &update).unwrap();
+        let saved = ConfigFormat::Toml.parse(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.resources.max_concurrent_tasks, None);
+    }
+
+    #[test]
+    fn test_persist_update_json_writes_only_file_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("worker.json");
+        fs::write(&path, r#"{"logging": {"level": "warn"}}"#).unwrap();
+
+        let update = serde_json::json!({ "logging": { "level": "debug" } });
+        WorkerConfig::persist_update(path.to_str(), &update).unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved, serde_json::json!({ "logging": { "level": "debug" } }));
+    }
+
     #[test]
     fn test_validation_invalid_gpu_percent() {
         let mut config = WorkerConfig::default();
@@ -910,6 +2823,146 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_validation_invalid_overflow_policy() {
+        let mut config = WorkerConfig::default();
+        config.resources.queue_overflow_policy = "drop_everything".to_string();
+        assert!(config.validate().is_err());
+
+        config.resources.queue_overflow_policy = "block_with_timeout".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_postprocess_steps() {
+        let mut config = WorkerConfig::default();
+        config.postprocess.steps = vec!["trim".to_string(), "uppercase".to_string()];
+        assert!(config.validate().is_err());
+
+        config.postprocess.steps = vec!["remove_prefix_regex".to_string()];
+        config.postprocess.prefix_regex = "(unclosed".to_string();
+        assert!(config.validate().is_err());
+
+        config.postprocess.prefix_regex = r"^Answer:\s*".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_control_listen_addr() {
+        let mut config = WorkerConfig::default();
+        config.control.listen_addr = "0.0.0.0:7878".to_string();
+        assert!(config.validate().is_err());
+
+        config.control.listen_addr = "not-an-address".to_string();
+        assert!(config.validate().is_err());
+
+        config.control.listen_addr = "[::1]:7878".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_peer_listen_addr() {
+        let mut config = WorkerConfig::default();
+        for ok in ["", "10.0.0.5", "::1", "eth1"] {
+            config.peer.listen_addr = ok.to_string();
+            assert!(config.validate().is_ok(), "{} rejected", ok);
+        }
+        for bad in ["10.0.0.5:9000", "10.0.0.0/8", "eth 1"] {
+            config.peer.listen_addr = bad.to_string();
+            assert!(config.validate().is_err(), "{} accepted", bad);
+        }
+    }
+
+    #[test]
+    fn test_validation_model_sources() {
+        let mut config = WorkerConfig::default();
+        config.storage.model_dir = "/models".to_string();
+        config.storage.models = vec![ModelSourceSettings {
+            id: "tinyllama".to_string(),
+            url: "https://example.com/tinyllama.gguf".to_string(),
+            sha256: String::new(),
+        }];
+        assert!(config.validate().is_ok());
+        let spec = &config.model_sources()[0];
+        assert_eq!(spec.path, PathBuf::from("/models/tinyllama.gguf"));
+        assert_eq!(spec.source_url.as_deref(), Some("https://example.com/tinyllama.gguf"));
+        assert!(spec.sha256.is_none());
+
+        config.storage.models[0].sha256 = "ab".repeat(31);
+        assert!(config.validate().is_err());
+        config.storage.models[0].sha256 = "ab".repeat(32);
+        assert!(config.validate().is_ok());
+
+        config.storage.models[0].url = "file:///etc/passwd".to_string();
+        assert!(config.validate().is_err());
+        config.storage.models[0].url = "https://example.com/m.gguf".to_string();
+        config.storage.models[0].id = "../escape".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_result_ack_timeout() {
+        let mut config = WorkerConfig::default();
+        config.coordinator.result_ack_timeout_ms = 0;
+        assert!(config.validate().is_ok());
+
+        config.coordinator.require_result_ack = true;
+        assert!(config.validate().is_err());
+
+        config.coordinator.result_ack_timeout_ms = 2000;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_peer_retry_delays() {
+        let mut config = WorkerConfig::default();
+        config.peer.retry_base_delay_ms = 0;
+        assert!(config.validate().is_err());
+
+        config.peer.retry_base_delay_ms = 5000;
+        config.peer.retry_max_delay_ms = 1000;
+        assert!(config.validate().is_err());
+
+        config.peer.retry_max_delay_ms = 5000;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation_require_encryption_needs_credentials() {
+        let mut config = WorkerConfig::default();
+        config.peer.require_encryption = true;
+        assert!(config.validate().is_err());
+
+        config.worker.account_id = Some("acct".to_string());
+        config.worker.secret_key = Some("abcd".to_string());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_concurrent_tasks() {
+        let mut resources = ResourceSettings::default();
+        // Memory-bound: 8 GB budget allows 4
+        assert_eq!(resources.concurrent_tasks(16, 32768), 4);
+        // CPU-bound
+        assert_eq!(resources.concurrent_tasks(4, 32768), 2);
+        // Never below 1
+        assert_eq!(resources.concurrent_tasks(1, 1024), 1);
+
+        resources.max_concurrent_tasks = Some(12);
+        assert_eq!(resources.concurrent_tasks(1, 1024), 12);
+
+        let mut config = WorkerConfig::default();
+        config.resources.max_concurrent_tasks = Some(0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validation_debug_tasks_needs_feature() {
+        let mut config = WorkerConfig::default();
+        config.worker.debug_tasks = true;
+        assert_eq!(config.validate().is_ok(), cfg!(feature = "debug-tasks"));
+    }
+
     #[test]
     fn test_validation_invalid_log_level() {
         let mut config = WorkerConfig::default();
@@ -975,4 +3028,60 @@ level = "debug"
         assert_eq!(config.resources.max_gpu_percent, 90);
         assert_eq!(config.logging.level, "debug");
     }
+
+    #[test]
+    fn test_generic_config_name_only_as_toml() {
+        let paths = WorkerConfig::search_paths();
+        assert_eq!(paths[..5], [
+            PathBuf::from("ai4all-worker.toml"),
+            PathBuf::from("ai4all-worker.yaml"),
+            PathBuf::from("ai4all-worker.yml"),
+            PathBuf::from("ai4all-worker.json"),
+            PathBuf::from("config.toml"),
+        ]);
+        assert!(paths.contains(&PathBuf::from("/etc/ai4all/worker.json")));
+        for generic in ["config.json", "config.yaml", "config.yml"] {
+            assert!(!paths.contains(&PathBuf::from(generic)), "{} searched", generic);
+        }
+    }
+
+    #[test]
+    fn test_load_yaml_and_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let yaml = dir.path().join("worker.yaml");
+        fs::write(
+            &yaml,
+            "worker:\n  name: Yaml Worker\nresources:\n  max_memory_mb: 16384\n",
+        )
+        .unwrap();
+        let json = dir.path().join("worker.json");
+        fs::write(
+            &json,
+            r#"{"worker": {"name": "Json Worker"}, "resources": {"max_memory_mb": 16384}}"#,
+        )
+        .unwrap();
+
+        for (path, name) in [(&yaml, "Yaml Worker"), (&json, "Json Worker")] {
+            let config = WorkerConfig::load(path.to_str()).unwrap();
+            assert_eq!(config.worker.name.as_deref(), Some(name));
+            assert_eq!(config.resources.max_memory_mb, 16384);
+            assert_eq!(config.coordinator.url, WorkerConfig::default().coordinator.url);
+        }
+
+        // Validation applies whatever the format
+        fs::write(&yaml, "resources:\n  max_gpu_percent: 150\n").unwrap();
+        assert!(WorkerConfig::load(yaml.to_str()).is_err());
+    }
+
+    #[test]
+    fn test_config_format_roundtrip() {
+        let config = WorkerConfig::default();
+        for format in [ConfigFormat::Toml, ConfigFormat::Yaml, ConfigFormat::Json] {
+            let rendered = format.render(&config).unwrap();
+            let parsed = format.parse(&rendered).unwrap();
+            assert_eq!(parsed.coordinator.url, config.coordinator.url);
+        }
+        assert_eq!(ConfigFormat::from_path(Path::new("w.YML")), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path(Path::new("w.conf")), ConfigFormat::Toml);
+    }
 }
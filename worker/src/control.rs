@@ -0,0 +1,315 @@
+//! Local control socket
+//!
+//! Lets operators inspect and cancel tasks without going through the
+//! coordinator. Line-oriented over TCP on a loopback address: each request
+//! is one command line, each reply one line of JSON.
+//!
+//! Commands:
+//! - `stats` — worker ID, connection state, task counts, loaded models,
+//!   peers and uptime (what `ai4all-worker status` prints)
+//! - `tasks` — list running/queued tasks
+//! - `cancel <task_id> [force]` — cancel a task (graceful unless `force`)
+//! - `pause` / `resume` — stop taking new tasks while in-flight ones
+//!   finish, and take them again (what `ai4all-worker pause`/`resume` send)
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info};
+
+use crate::coordinator::{PauseHandle, StatsHandle};
+use crate::executor::{CancelMode, PauseSource, TaskExecutor};
+
+/// How long a control request may take, including connecting
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves control commands against the task executor
+pub struct ControlServer {
+    executor: Arc<TaskExecutor>,
+    stats: Option<StatsHandle>,
+    pause: Option<PauseHandle>,
+}
+
+impl ControlServer {
+    /// Create a control server for `executor`
+    pub fn new(executor: Arc<TaskExecutor>) -> Self {
+        Self { executor, stats: None, pause: None }
+    }
+
+    /// Answer `stats` from the coordinator client
+    pub fn with_stats(mut self, stats: StatsHandle) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Report `pause`/`resume` to the coordinator through the client
+    pub fn with_pause(mut self, pause: PauseHandle) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+
+    /// Bind `addr` and serve connections in the background
+    pub async fn start(self: Arc<Self>, addr: &str) -> std::io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        info!(addr = %local_addr, "Control socket listening");
+
+        tokio::spawn(async move {
+            self.accept_loop(listener).await;
+        });
+
+        Ok(local_addr)
+    }
+
+    async fn accept_loop(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer_addr)) => {
+                    debug!(peer_addr = %peer_addr, "Control connection");
+                    let server = Arc::clone(&self);
+                    tokio::spawn(async move {
+                        if let Err(e) = server.handle_connection(stream).await {
+                            debug!(error = %e, "Control connection closed");
+                        }
+                    });
+                }
+                Err(e) => {
+                    error!(error = %e, "Control accept failed");
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                }
+            }
+        }
+    }
+
+    async fn handle_connection(&self, stream: TcpStream) -> std::io::Result<()> {
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut reply = self.handle_command(&line).await.to_string();
+            reply.push('\n');
+            write.write_all(reply.as_bytes()).await?;
+        }
+        Ok(())
+    }
+
+    /// Execute one command line and build its JSON reply
+    pub async fn handle_command(&self, line: &str) -> Value {
+        let mut args = line.split_whitespace();
+        match (args.next(), args.next(), args.next(), args.next()) {
+            (Some("stats"), None, _, _) => match &self.stats {
+                Some(handle) => match handle.stats().await {
+                    Ok(stats) => json!({ "ok": true, "stats": stats }),
+                    Err(e) => error_reply(e.to_string()),
+                },
+                None => error_reply("Stats are not available".to_string()),
+            },
+            (Some("tasks"), None, _, _) => json!({
+                "ok": true,
+                "tasks": self.executor.task_details(),
+            }),
+            (Some("cancel"), Some(task_id), flag, None) => {
+                let mode = match flag {
+                    None => CancelMode::Graceful,
+                    Some("force") => CancelMode::Forced,
+                    Some(other) => return error_reply(format!("Unknown cancel flag '{}'", other)),
+                };
+                if self.executor.cancel(task_id, mode) {
+                    info!(task_id = %task_id, mode = %mode, "Task cancelled via control socket");
+                    json!({ "ok": true, "task_id": task_id, "mode": mode.to_string() })
+                } else {
+                    error_reply(format!("No running or queued task '{}'", task_id))
+                }
+            }
+            (Some(command @ ("pause" | "resume")), None, _, _) => {
+                let paused = command == "pause";
+                self.executor.set_paused(PauseSource::Operator, paused);
+                if let Some(handle) = &self.pause {
+                    handle.set_paused(paused);
+                }
+                info!(running = self.executor.running_count(), "Worker {} via control socket", if paused { "paused" } else { "resumed" });
+                // Still paused after a resume while the coordinator holds a pause
+                json!({ "ok": true, "paused": self.executor.is_paused() })
+            }
+            _ => error_reply(format!("Unknown command '{}'", line.trim())),
+        }
+    }
+}
+
+fn error_reply(message: String) -> Value {
+    json!({ "ok": false, "error": message })
+}
+
+/// Send one command to the control socket at `addr` and return its reply.
+/// A worker that isn't running (or has the socket disabled) shows up as
+/// [`std::io::ErrorKind::ConnectionRefused`].
+pub async fn request(addr: &str, command: &str) -> std::io::Result<Value> {
+    let exchange = async {
+        let (read, mut write) = TcpStream::connect(addr).await?.into_split();
+        write.write_all(format!("{}\n", command).as_bytes()).await?;
+        let line = BufReader::new(read).lines().next_line().await?.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "control socket closed without a reply")
+        })?;
+        serde_json::from_str(&line)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    };
+    tokio::time::timeout(REQUEST_TIMEOUT, exchange)
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "control request timed out"))?
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::RwLock;
+    use tokio::io::Lines;
+    use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+    use crate::backend::{BackendConfig, BackendRegistry, BackendType, MockBackend, MockConfig};
+    use crate::executor::ExecutorConfig;
+    use crate::protocol::{TaskAssignmentMessage, TaskPriority};
+    use crate::types::{GenerationParams, TaskInput, TextCompletionInput};
+
+    fn make_assignment(task_id: &str) -> TaskAssignmentMessage {
+        TaskAssignmentMessage {
+            task_id: task_id.to_string(),
+            block_id: None,
+            day_id: None,
+            priority: TaskPriority::Normal,
+            deadline: None,
+            model_id: "test-model".to_string(),
+            input: TaskInput::TextCompletion(TextCompletionInput {
+                prompt: "Hello".to_string(),
+                system_prompt: None,
+                params: GenerationParams::default(),
+            }),
+            is_canary: false,
+            expected_hash: None,
+            timeout_secs: 60,
+            group_id: None,
+            stream: false,
+            required_tags: vec![],
+        }
+    }
+
+    async fn request(
+        lines: &mut Lines<BufReader<OwnedReadHalf>>,
+        write: &mut OwnedWriteHalf,
+        command: &str,
+    ) -> Value {
+        write.write_all(format!("{}\n", command).as_bytes()).await.unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancel_over_control_socket() {
+        let registry = BackendRegistry::new();
+        let mock = MockBackend::with_config(
+            MockConfig { token_latency_ms: 100, ..Default::default() },
+            BackendConfig::default(),
+        );
+        registry.register_boxed(BackendType::Mock, Box::new(mock));
+        let (executor, mut rx) = TaskExecutor::new(
+            ExecutorConfig::default(),
+            Arc::new(RwLock::new(registry)),
+            "worker-1".to_string(),
+        );
+        let executor = Arc::new(executor);
+        executor.submit(make_assignment("task-1")).await.unwrap();
+
+        let server = Arc::new(ControlServer::new(executor.clone()));
+        let addr = server.start("127.0.0.1:0").await.unwrap();
+        let (read, mut write) = TcpStream::connect(addr).await.unwrap().into_split();
+        let mut lines = BufReader::new(read).lines();
+
+        let reply = request(&mut lines, &mut write, "tasks").await;
+        assert_eq!(reply["ok"], true);
+        assert_eq!(reply["tasks"][0]["task_id"], "task-1");
+        assert_eq!(reply["tasks"][0]["task_type"], "TEXT_COMPLETION");
+        assert_eq!(reply["tasks"][0]["model_id"], "test-model");
+        assert_eq!(reply["tasks"][0]["source"]["type"], "coordinator");
+
+        let reply = request(&mut lines, &mut write, "cancel task-1 force").await;
+        assert_eq!(reply["ok"], true);
+        assert_eq!(reply["mode"], "forced");
+
+        let result = rx.recv().await.unwrap();
+        assert_eq!(result.task_id, "task-1");
+        assert_eq!(result.error.unwrap().code, "E502");
+        assert!(executor.active_tasks().is_empty());
+
+        let reply = request(&mut lines, &mut write, "cancel task-1").await;
+        assert_eq!(reply["ok"], false);
+
+        let reply = request(&mut lines, &mut write, "pause").await;
+        assert_eq!(reply["paused"], true);
+        assert!(!executor.can_accept());
+        assert!(executor.submit(make_assignment("task-2")).await.is_err());
+        let reply = request(&mut lines, &mut write, "resume").await;
+        assert_eq!(reply["paused"], false);
+        assert!(executor.can_accept());
+
+        let reply = request(&mut lines, &mut write, "reboot").await;
+        assert_eq!(reply["ok"], false);
+    }
+
+    #[tokio::test]
+    async fn test_stats_over_control_socket() {
+        use crate::coordinator::{CoordinatorClient, CoordinatorClientConfig};
+        use crate::protocol::WorkerCapabilities;
+
+        // Nothing listens here, so the client stays disconnected
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let config = CoordinatorClientConfig {
+            url: format!("ws://{}", closed),
+            ..Default::default()
+        };
+        let caps = WorkerCapabilities {
+            supported_tasks: vec![],
+            max_concurrent_tasks: 1,
+            available_memory_mb: 1024,
+            gpu_available: false,
+            gpu_device: None,
+            gpu_memory_mb: None,
+            max_context_length: 4096,
+            worker_version: "0.1.0".to_string(),
+            supports_streaming: false,
+        };
+        let mut client = CoordinatorClient::new(config, "test".to_string(), caps, vec![]);
+        client.set_stats_source(Arc::new(|stats| {
+            stats.queued_tasks = 2;
+            stats.loaded_models = vec!["test-model".to_string()];
+        }));
+        let _events = client.start().await.unwrap();
+
+        let (executor, _rx) = TaskExecutor::new(
+            ExecutorConfig::default(),
+            Arc::new(RwLock::new(BackendRegistry::new())),
+            "worker-1".to_string(),
+        );
+        let server = Arc::new(ControlServer::new(Arc::new(executor)).with_stats(client.stats_handle()));
+        let addr = server.start("127.0.0.1:0").await.unwrap().to_string();
+
+        let reply = super::request(&addr, "stats").await.unwrap();
+        assert_eq!(reply["ok"], true);
+        assert_eq!(reply["stats"]["worker_id"], Value::Null);
+        assert_eq!(reply["stats"]["queued_tasks"], 2);
+        assert_eq!(reply["stats"]["loaded_models"][0], "test-model");
+
+        // A worker that isn't running is reported as refused
+        let err = super::request(&closed.to_string(), "stats").await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+    }
+}
@@ -1,30 +1,43 @@
 //! WebSocket client for coordinator communication
 //!
 //! Provides a robust WebSocket client with:
-//! - Automatic reconnection with exponential backoff
+//! - Automatic reconnection with jittered, capped exponential backoff
 //! - Heartbeat management
+//! - WebSocket ping/pong keepalive detecting silently dropped connections
 //! - Message queuing during disconnection
+//! - Optional acknowledged result delivery with re-sends
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use backoff::{backoff::Backoff, ExponentialBackoff};
 use futures_util::{SinkExt, StreamExt};
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, oneshot};
 use tokio_tungstenite::{
     connect_async,
-    tungstenite::{Error as WsError, Message as WsMessage},
+    tungstenite::{
+        client::IntoClientRequest,
+        handshake::client::Request as WsRequest,
+        http::{HeaderName, HeaderValue},
+        Error as WsError, Message as WsMessage,
+    },
 };
 use tracing::{debug, error, info, warn};
 use url::Url;
+use uuid::Uuid;
 
 use crate::error::{Error, Result};
+use crate::jitter::jitter;
+use crate::recording::SessionRecorder;
+use super::PeerCredentials;
 use crate::protocol::{
-    HeartbeatAckResponse, HeartbeatRequest, Message, MessageEnvelope,
-    PeerDirectoryEntry, GroupAssignedMessage,
+    CapabilitiesUpdateMessage, Compression, HeartbeatAckResponse, PeerDirectoryRequestMessage, HeartbeatRequest, Message, MessageEnvelope,
+    PeerDirectoryEntry, PendingAction, GroupAssignedMessage,
     RegisterAckResponse, RegisterRequest, ResourceUsageReport,
-    TaskResultMessage, WorkerCapabilities, WorkerStatus,
+    TaskCancelMessage, TaskProgressMessage, TaskResultMessage, WireFormat, WorkerCapabilities, WorkerStatus,
 };
 
 // ─────────────────────────────────────────────────────────────────
@@ -46,14 +59,57 @@ pub struct CoordinatorClientConfig {
     /// Initial reconnect delay
     pub initial_reconnect_delay: Duration,
 
-    /// Maximum reconnect delay
+    /// Maximum reconnect delay, jitter included
     pub max_reconnect_delay: Duration,
 
+    /// Extra random delay of up to this long before the first reconnect
+    /// after losing a connection, spreading out a fleet that lost its
+    /// coordinator together (zero = none)
+    pub reconnect_spread: Duration,
+
     /// Heartbeat interval
     pub heartbeat_interval: Duration,
 
+    /// WebSocket ping interval (zero = no pings)
+    pub ping_interval: Duration,
+
+    /// How long a ping may go unanswered before the connection is
+    /// considered dead
+    pub ping_timeout: Duration,
+
     /// Message queue size
     pub message_queue_size: usize,
+
+    /// Extra HTTP headers sent with the WebSocket upgrade request
+    pub headers: Vec<(String, String)>,
+
+    /// WebSocket subprotocol to request (`Sec-WebSocket-Protocol`)
+    pub subprotocol: Option<String>,
+
+    /// Reset the connection on unparseable messages instead of skipping them
+    pub strict_protocol: bool,
+
+    /// Re-send task results until the coordinator acknowledges them
+    pub require_result_ack: bool,
+
+    /// Wait for a result ack before the first re-send (doubles per re-send)
+    pub result_ack_timeout: Duration,
+
+    /// Cancel tasks the coordinator reports as reclaimed in heartbeat acks
+    pub honor_task_reclamation: bool,
+
+    /// Give up after going this long without a registered connection,
+    /// across reconnect attempts (zero = never)
+    pub exit_after_failed: Duration,
+
+    /// Account credentials signing the registration auth token, if set
+    pub credentials: Option<PeerCredentials>,
+
+    /// Offer compression of large messages at registration
+    pub compression: bool,
+
+    /// Offer MessagePack (binary frames) at registration
+    pub msgpack: bool,
 }
 
 impl Default for CoordinatorClientConfig {
@@ -64,18 +120,103 @@ impl Default for CoordinatorClientConfig {
             max_reconnect_attempts: 0, // Infinite
             initial_reconnect_delay: Duration::from_secs(1),
             max_reconnect_delay: Duration::from_secs(60),
+            reconnect_spread: Duration::ZERO,
             heartbeat_interval: Duration::from_secs(30),
+            ping_interval: Duration::from_secs(15),
+            ping_timeout: Duration::from_secs(10),
             message_queue_size: 100,
+            headers: Vec::new(),
+            subprotocol: None,
+            strict_protocol: false,
+            require_result_ack: false,
+            result_ack_timeout: Duration::from_secs(10),
+            honor_task_reclamation: true,
+            exit_after_failed: Duration::ZERO,
+            credentials: None,
+            compression: true,
+            msgpack: true,
         }
     }
 }
 
+/// Upper bound on the delay between re-sends of an unacknowledged result
+const MAX_RESULT_RESEND_DELAY: Duration = Duration::from_secs(60);
+
+/// Header names whose values are never logged
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie"];
+
+/// Build the WebSocket upgrade request for `url`, including the configured
+/// headers and subprotocol
+fn build_upgrade_request(config: &CoordinatorClientConfig, url: &Url) -> Result<WsRequest> {
+    let mut request = url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| Error::Config(format!("Invalid coordinator URL: {}", e)))?;
+
+    let headers = request.headers_mut();
+    for (name, value) in &config.headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| Error::Config(format!("Invalid header name: {}", name)))?;
+        let header_value = HeaderValue::from_str(value)
+            .map_err(|_| Error::Config(format!("Invalid value for header {}", name)))?;
+        headers.insert(header_name, header_value);
+    }
+
+    if let Some(protocol) = &config.subprotocol {
+        let value = HeaderValue::from_str(protocol)
+            .map_err(|_| Error::Config(format!("Invalid subprotocol: {}", protocol)))?;
+        headers.insert("Sec-WebSocket-Protocol", value);
+    }
+
+    Ok(request)
+}
+
+/// The coordinator's HTTP root (task API, result delivery, peer
+/// registration) for its WebSocket `url`: `ws`/`wss` become `http`/`https`,
+/// a trailing `/ws` path segment is dropped along with any query or
+/// fragment, and host, port and any path prefix are kept
+pub fn http_base_url(coordinator_url: &str) -> Result<String> {
+    // The query may carry a token, so it stays out of errors
+    let invalid = |reason: String| Error::Config(format!("Invalid coordinator URL: {}", reason));
+
+    let mut url = Url::parse(coordinator_url).map_err(|e| invalid(e.to_string()))?;
+    let scheme = match url.scheme() {
+        "ws" => "http",
+        "wss" => "https",
+        other => return Err(invalid(format!("expected ws:// or wss://, got {}://", other))),
+    };
+    url.set_scheme(scheme)
+        .map_err(|_| invalid(format!("can't be served over {}", scheme)))?;
+    url.set_query(None);
+    url.set_fragment(None);
+    let path = url.path().trim_end_matches('/');
+    let path = path.strip_suffix("/ws").unwrap_or(path).to_string();
+    url.set_path(&path);
+
+    let base = url.as_str().trim_end_matches('/').to_string();
+    Url::parse(&base).map_err(|e| invalid(format!("HTTP base '{}' is malformed: {}", base, e)))?;
+    Ok(base)
+}
+
+/// Header value safe for logging: credentials are replaced with `[REDACTED]`
+pub(crate) fn redact_header<'a>(name: &str, value: &'a str) -> &'a str {
+    let name = name.to_ascii_lowercase();
+    let sensitive = SENSITIVE_HEADERS.contains(&name.as_str())
+        || ["token", "key", "secret"].iter().any(|s| name.contains(s));
+    if sensitive {
+        "[REDACTED]"
+    } else {
+        value
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Connection State
 // ─────────────────────────────────────────────────────────────────
 
 /// Connection state
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ConnectionState {
     /// Not connected
     Disconnected,
@@ -97,6 +238,96 @@ impl Default for ConnectionState {
     }
 }
 
+// ─────────────────────────────────────────────────────────────────
+// Worker Stats
+// ─────────────────────────────────────────────────────────────────
+
+/// Snapshot of the running worker, answered by [`ClientCommand::GetStats`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerStats {
+    /// Assigned worker ID (after registration)
+    pub worker_id: Option<String>,
+    /// Current connection state
+    pub connection_state: ConnectionState,
+    /// Tasks executing now
+    pub running_tasks: usize,
+    /// Tasks waiting for a slot
+    pub queued_tasks: usize,
+    /// Tasks completed since startup
+    pub completed_tasks: u64,
+    /// Tasks failed since startup
+    pub failed_tasks: u64,
+    /// Models loaded in the backends
+    pub loaded_models: Vec<String>,
+    /// Connected peers
+    pub peer_count: usize,
+    /// Seconds since the client was created
+    pub uptime_secs: u64,
+}
+
+/// Asks a started [`CoordinatorClient`] for [`WorkerStats`]
+#[derive(Debug, Clone)]
+pub struct StatsHandle(mpsc::Sender<ClientCommand>);
+
+impl StatsHandle {
+    /// Current stats snapshot
+    pub async fn stats(&self) -> Result<WorkerStats> {
+        let (tx, rx) = oneshot::channel();
+        self.0
+            .send(ClientCommand::GetStats(tx))
+            .await
+            .map_err(|_| Error::Connection("Client channel closed".to_string()))?;
+        rx.await
+            .map_err(|_| Error::Connection("Client dropped stats request".to_string()))
+    }
+}
+
+/// Submits task results through a started [`CoordinatorClient`] from
+/// elsewhere (e.g. a spawned delivery task)
+#[derive(Clone)]
+pub struct ResultHandle {
+    state: Arc<RwLock<ClientState>>,
+    command_tx: mpsc::Sender<ClientCommand>,
+}
+
+impl ResultHandle {
+    /// Same as [`CoordinatorClient::submit_result`]
+    pub async fn submit_result(&self, result: TaskResultMessage) -> Result<()> {
+        submit_result(&self.state, &self.command_tx, result).await
+    }
+}
+
+async fn submit_result(
+    state: &RwLock<ClientState>,
+    command_tx: &mpsc::Sender<ClientCommand>,
+    result: TaskResultMessage,
+) -> Result<()> {
+    if state.read().connection_state == ConnectionState::Reconnecting {
+        return Err(Error::Connection("Not connected to coordinator".to_string()));
+    }
+    command_tx
+        .send(ClientCommand::SubmitResult(result))
+        .await
+        .map_err(|_| Error::Connection("Client channel closed".to_string()))
+}
+
+/// Pauses and resumes a [`CoordinatorClient`]'s reported status from
+/// elsewhere (e.g. the control socket)
+#[derive(Clone)]
+pub struct PauseHandle(Arc<RwLock<ClientState>>);
+
+impl PauseHandle {
+    /// Set or lift the operator's pause, reported from the next heartbeat
+    /// on. A pause requested by the coordinator is tracked separately.
+    pub fn set_paused(&self, paused: bool) {
+        self.0.write().operator_paused = paused;
+    }
+}
+
+/// Fills in the task and peer counts of a [`WorkerStats`] snapshot. Called
+/// on the client task, so it must not block.
+pub type StatsSource = Arc<dyn Fn(&mut WorkerStats) + Send + Sync>;
+
 // ─────────────────────────────────────────────────────────────────
 // Client State
 // ─────────────────────────────────────────────────────────────────
@@ -118,11 +349,64 @@ struct ClientState {
     /// Current worker status
     worker_status: WorkerStatus,
 
+    /// Paused by the operator. Kept apart from `worker_status` so
+    /// busy/ready updates and reconnects don't clear it.
+    operator_paused: bool,
+
+    /// Paused by the coordinator; its RESUME clears only this flag
+    coordinator_paused: bool,
+
     /// Reconnection attempt count
     reconnect_attempts: u32,
 
     /// Connection start time
     connected_at: Option<Instant>,
+
+    /// Tags from `worker.tags`, sent at registration for task routing
+    tags: Vec<String>,
+
+    /// Models this worker currently refuses to run
+    declined_models: Vec<String>,
+
+    /// Results awaiting a coordinator ack, by result ID
+    unacked_results: HashMap<String, UnackedResult>,
+
+    /// Capabilities replacing the initial ones, if they changed
+    capabilities: Option<WorkerCapabilities>,
+
+    /// Session recorder for debugging, if enabled
+    recorder: Option<Arc<SessionRecorder>>,
+
+    /// The current connection was closed on request; reconnect immediately
+    reconnect_requested: bool,
+
+    /// Surface tasks reclaimed in heartbeat acks as cancellations
+    honor_task_reclamation: bool,
+
+    /// Heartbeat interval replacing the configured one, if changed
+    heartbeat_interval: Option<Duration>,
+
+    /// When the client was created
+    started_at: Instant,
+
+    /// Task and peer counts for stats snapshots, if set
+    stats_source: Option<StatsSource>,
+
+    /// Compression agreed in the current connection's registration ack
+    compression: Option<Compression>,
+
+    /// Wire format agreed in the current connection's registration ack
+    wire_format: Option<WireFormat>,
+}
+
+/// A delivered task result the coordinator hasn't acknowledged yet
+struct UnackedResult {
+    result: TaskResultMessage,
+    /// Deliveries so far
+    attempts: u32,
+    /// Delay before the next re-send
+    retry_delay: Duration,
+    resend_at: Instant,
 }
 
 impl Default for ClientState {
@@ -133,8 +417,35 @@ impl Default for ClientState {
             session_token: None,
             last_heartbeat: None,
             worker_status: WorkerStatus::Ready,
+            operator_paused: false,
+            coordinator_paused: false,
             reconnect_attempts: 0,
             connected_at: None,
+            tags: Vec::new(),
+            declined_models: Vec::new(),
+            unacked_results: HashMap::new(),
+            capabilities: None,
+            recorder: None,
+            reconnect_requested: false,
+            honor_task_reclamation: true,
+            heartbeat_interval: None,
+            started_at: Instant::now(),
+            stats_source: None,
+            compression: None,
+            wire_format: None,
+        }
+    }
+}
+
+impl ClientState {
+    /// Status sent in heartbeats: paused wins over ready/busy, but not over
+    /// draining or an error
+    fn reported_status(&self) -> WorkerStatus {
+        match self.worker_status {
+            WorkerStatus::Ready | WorkerStatus::Busy if self.operator_paused || self.coordinator_paused => {
+                WorkerStatus::Paused
+            }
+            status => status,
         }
     }
 }
@@ -155,11 +466,17 @@ pub enum ClientCommand {
     /// Submit task result
     SubmitResult(TaskResultMessage),
 
-    /// Initiate graceful shutdown
-    Shutdown,
+    /// Close the connection and immediately establish a new one
+    Reconnect,
+
+    /// Initiate graceful shutdown, reporting tasks left unfinished
+    Shutdown { abandoned_tasks: Vec<String> },
 
     /// Get current connection state
     GetState(oneshot::Sender<ConnectionState>),
+
+    /// Get a snapshot of task counts, peers and uptime
+    GetStats(oneshot::Sender<WorkerStats>),
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -181,11 +498,20 @@ pub enum ClientEvent {
     /// Received task assignment
     TaskAssigned(crate::protocol::TaskAssignmentMessage),
 
-    /// Received task cancellation
-    TaskCancelled { task_id: String, reason: String },
+    /// Received task cancellation (`force` aborts instead of stopping gracefully)
+    TaskCancelled { task_id: String, reason: String, force: bool },
 
     /// Received configuration update
-    ConfigUpdate(serde_json::Value),
+    ConfigUpdate(crate::protocol::ConfigUpdateMessage),
+
+    /// The coordinator asked the worker to stop taking new tasks
+    Paused,
+
+    /// The coordinator asked the worker to take tasks again
+    Resumed,
+
+    /// The coordinator asked the worker to shut down gracefully
+    ShutdownRequested { reason: String },
 
     /// Error occurred
     Error { message: String, fatal: bool },
@@ -207,6 +533,15 @@ pub enum ClientEvent {
 
     /// Assigned to a work group
     GroupAssigned(GroupAssignedMessage),
+
+    /// A result was still unacknowledged when the connection dropped
+    ResultDeadLettered(TaskResultMessage),
+
+    /// No registered connection for `exit_after_failed`; the client stopped
+    GaveUp { failed_for: Duration },
+
+    /// The coordinator refused this worker's credentials; the client stopped
+    AuthenticationFailed { message: String },
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -229,12 +564,18 @@ impl CoordinatorClient {
         config: CoordinatorClientConfig,
         worker_name: String,
         worker_capabilities: WorkerCapabilities,
+        tags: Vec<String>,
     ) -> Self {
         let (command_tx, _command_rx) = mpsc::channel(config.message_queue_size);
+        let state = ClientState {
+            honor_task_reclamation: config.honor_task_reclamation,
+            tags,
+            ..Default::default()
+        };
 
         Self {
             config,
-            state: Arc::new(RwLock::new(ClientState::default())),
+            state: Arc::new(RwLock::new(state)),
             command_tx,
             event_rx: None,
             worker_name,
@@ -275,6 +616,11 @@ impl CoordinatorClient {
         self.state.read().connection_state
     }
 
+    /// Results sent over the WebSocket that the coordinator hasn't acked yet
+    pub fn unacked_result_count(&self) -> usize {
+        self.state.read().unacked_results.len()
+    }
+
     /// Get assigned worker ID
     pub fn worker_id(&self) -> Option<String> {
         self.state.read().worker_id.clone()
@@ -286,8 +632,29 @@ impl CoordinatorClient {
     }
 
     /// Submit a task result
+    ///
+    /// Fails while the client is waiting to reconnect, since results sent
+    /// then would not reach the coordinator.
     pub async fn submit_result(&self, result: TaskResultMessage) -> Result<()> {
-        self.send_command(ClientCommand::SubmitResult(result)).await
+        submit_result(&self.state, &self.command_tx, result).await
+    }
+
+    /// Handle for submitting results from tasks spawned after [`start`]
+    ///
+    /// [`start`]: CoordinatorClient::start
+    pub fn result_handle(&self) -> ResultHandle {
+        ResultHandle {
+            state: Arc::clone(&self.state),
+            command_tx: self.command_tx.clone(),
+        }
+    }
+
+    /// Forward tokens generated by a streaming task. Progress is
+    /// best-effort: nothing is re-sent after a disconnect, since the final
+    /// result carries the full output.
+    pub async fn send_task_progress(&self, progress: TaskProgressMessage) -> Result<()> {
+        let envelope = MessageEnvelope::new(Message::TaskProgress(progress));
+        self.send_command(ClientCommand::Send(envelope)).await
     }
 
     /// Update worker status
@@ -295,9 +662,83 @@ impl CoordinatorClient {
         self.send_command(ClientCommand::UpdateStatus(status)).await
     }
 
-    /// Request graceful shutdown
-    pub async fn shutdown(&self) -> Result<()> {
-        self.send_command(ClientCommand::Shutdown).await
+    /// Handle for pausing the client from elsewhere (the flag survives
+    /// reconnects)
+    pub fn pause_handle(&self) -> PauseHandle {
+        PauseHandle(Arc::clone(&self.state))
+    }
+
+    /// Set the models advertised as declined in registration and heartbeats
+    pub fn set_declined_models(&self, models: Vec<String>) {
+        self.state.write().declined_models = models;
+    }
+
+    /// Change the heartbeat interval, taking effect from the next heartbeat
+    /// without reconnecting
+    pub fn set_heartbeat_interval(&self, interval: Duration) {
+        self.state.write().heartbeat_interval = Some(interval);
+    }
+
+    /// Read task and peer counts for [`ClientCommand::GetStats`] from `source`
+    pub fn set_stats_source(&self, source: StatsSource) {
+        self.state.write().stats_source = Some(source);
+    }
+
+    /// Handle for asking the started client for stats from elsewhere (e.g.
+    /// the control socket)
+    pub fn stats_handle(&self) -> StatsHandle {
+        StatsHandle(self.command_tx.clone())
+    }
+
+    /// Record inbound frames and outbound results to `recorder`
+    pub fn set_recorder(&self, recorder: Arc<SessionRecorder>) {
+        self.state.write().recorder = Some(recorder);
+    }
+
+    /// Replace the advertised capabilities. They are sent to the coordinator
+    /// now if registered, and used for every later registration.
+    pub async fn update_capabilities(&self, capabilities: WorkerCapabilities) -> Result<()> {
+        let worker_id = {
+            let mut s = self.state.write();
+            s.capabilities = Some(capabilities.clone());
+            s.worker_id.clone()
+        };
+        match worker_id {
+            Some(worker_id) if self.is_ready() => {
+                let msg = Message::CapabilitiesUpdate(CapabilitiesUpdateMessage {
+                    worker_id,
+                    capabilities,
+                });
+                self.send_command(ClientCommand::Send(MessageEnvelope::new(msg))).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Ask the coordinator to send the peer directory again, reporting how
+    /// many entries of the last one were unusable. No-op while not registered.
+    pub async fn request_peer_directory(&self, malformed_entries: u32) -> Result<()> {
+        match self.worker_id() {
+            Some(worker_id) if self.is_ready() => {
+                let msg = Message::PeerDirectoryRequest(PeerDirectoryRequestMessage {
+                    worker_id,
+                    malformed_entries,
+                });
+                self.send_command(ClientCommand::Send(MessageEnvelope::new(msg))).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Re-establish the coordinator connection, registering afresh
+    pub async fn reconnect(&self) -> Result<()> {
+        self.send_command(ClientCommand::Reconnect).await
+    }
+
+    /// Request graceful shutdown. `abandoned_tasks` are still running and
+    /// will get no result; the coordinator reassigns them.
+    pub async fn shutdown(&self, abandoned_tasks: Vec<String>) -> Result<()> {
+        self.send_command(ClientCommand::Shutdown { abandoned_tasks }).await
     }
 }
 
@@ -305,6 +746,112 @@ impl CoordinatorClient {
 // Client Loop
 // ─────────────────────────────────────────────────────────────────
 
+/// Delays between reconnect attempts: exponential with full jitter and
+/// never above `max_reconnect_delay`, so workers that lost the coordinator
+/// together don't all come back at the same moment
+struct ReconnectBackoff {
+    backoff: ExponentialBackoff,
+    max: Duration,
+    spread: Duration,
+    /// The next delay is the first since the last connection
+    first: bool,
+}
+
+impl ReconnectBackoff {
+    fn new(config: &CoordinatorClientConfig) -> Self {
+        Self {
+            backoff: ExponentialBackoff {
+                initial_interval: config.initial_reconnect_delay,
+                max_interval: config.max_reconnect_delay,
+                // Jitter is applied on top, in `next_delay`
+                randomization_factor: 0.0,
+                max_elapsed_time: None, // Retry forever
+                ..Default::default()
+            },
+            max: config.max_reconnect_delay,
+            spread: config.reconnect_spread,
+            first: true,
+        }
+    }
+
+    /// Start over after a successful connection
+    fn reset(&mut self) {
+        self.backoff.reset();
+        self.first = true;
+    }
+
+    /// Delay before the next reconnect attempt
+    fn next_delay(&mut self) -> Duration {
+        let interval = self.backoff.next_backoff().unwrap_or(self.max);
+        let mut delay = jitter(interval.min(self.max));
+        if std::mem::take(&mut self.first) {
+            delay += jitter(self.spread);
+        }
+        delay.min(self.max)
+    }
+}
+
+/// What the keepalive timer calls for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeepaliveAction {
+    /// Send a ping
+    Ping,
+    /// A ping went unanswered for the timeout: drop the connection
+    Dead,
+    /// Nothing due yet
+    Wait,
+}
+
+/// Ping/pong liveness check for a connection that can drop without a FIN,
+/// which the heartbeat (worker → coordinator only) wouldn't notice until a
+/// send failed
+struct Keepalive {
+    interval: Duration,
+    timeout: Duration,
+    /// When the next ping is due
+    next_ping: Instant,
+    /// When the unanswered ping was sent
+    awaiting_since: Option<Instant>,
+}
+
+impl Keepalive {
+    fn new(interval: Duration, timeout: Duration, now: Instant) -> Self {
+        Self { interval, timeout, next_ping: now + interval, awaiting_since: None }
+    }
+
+    /// Whether pings are sent at all
+    fn enabled(&self) -> bool {
+        !self.interval.is_zero()
+    }
+
+    /// When [`Keepalive::poll`] next has something to do
+    fn deadline(&self) -> Instant {
+        match self.awaiting_since {
+            Some(sent) => sent + self.timeout,
+            None => self.next_ping,
+        }
+    }
+
+    fn poll(&mut self, now: Instant) -> KeepaliveAction {
+        match self.awaiting_since {
+            Some(sent) if now >= sent + self.timeout => KeepaliveAction::Dead,
+            Some(_) => KeepaliveAction::Wait,
+            None if now >= self.next_ping => {
+                self.awaiting_since = Some(now);
+                KeepaliveAction::Ping
+            }
+            None => KeepaliveAction::Wait,
+        }
+    }
+
+    /// A pong arrived: the connection is alive
+    fn pong(&mut self, now: Instant) {
+        if self.awaiting_since.take().is_some() {
+            self.next_ping = now + self.interval;
+        }
+    }
+}
+
 /// Main client loop with reconnection logic
 async fn run_client_loop(
     config: CoordinatorClientConfig,
@@ -326,13 +873,14 @@ async fn run_client_loop(
         }
     };
 
-    // Create exponential backoff for reconnection
-    let mut backoff = ExponentialBackoff {
-        initial_interval: config.initial_reconnect_delay,
-        max_interval: config.max_reconnect_delay,
-        max_elapsed_time: None, // Retry forever
-        ..Default::default()
-    };
+    for (name, value) in &config.headers {
+        debug!(header = %name, value = %redact_header(name, value), "Upgrade request header");
+    }
+
+    let mut backoff = ReconnectBackoff::new(&config);
+
+    // Start of the current stretch without a registered connection
+    let mut failing_since = Instant::now();
 
     loop {
         // Check if we should shutdown
@@ -352,8 +900,20 @@ async fn run_client_loop(
 
         info!(url = %url, "Connecting to coordinator");
 
+        let request = match build_upgrade_request(&config, &url) {
+            Ok(r) => r,
+            Err(e) => {
+                error!(error = %e, "Invalid coordinator connection settings");
+                let _ = event_tx.send(ClientEvent::Error {
+                    message: e.to_string(),
+                    fatal: true,
+                }).await;
+                return;
+            }
+        };
+
         // Attempt connection
-        match connect_async(url.clone()).await {
+        match connect_async(request).await {
             Ok((ws_stream, _response)) => {
                 info!("WebSocket connection established");
 
@@ -383,12 +943,44 @@ async fn run_client_loop(
                     &capabilities,
                 ).await;
 
+                // A registered session restarts the failure clock from
+                // when it ended
+                if state.read().worker_id.is_some() {
+                    failing_since = Instant::now();
+                }
+
+                // Bad credentials won't get better by reconnecting
+                if let Err(Error::AuthenticationFailed { message }) = &result {
+                    error!(error = %message, "Coordinator rejected worker credentials");
+                    let _ = event_tx.send(ClientEvent::AuthenticationFailed {
+                        message: message.clone(),
+                    }).await;
+                    state.write().connection_state = ConnectionState::ShuttingDown;
+                    break;
+                }
+
                 if let Err(e) = result {
                     warn!(error = %e, "Connection error");
                     let _ = event_tx.send(ClientEvent::Disconnected {
                         reason: e.to_string(),
                     }).await;
                 }
+
+                // Requested reconnects skip the backoff and don't count as
+                // failed attempts. Unacked results are kept and re-sent as
+                // soon as the worker is registered again.
+                if std::mem::take(&mut state.write().reconnect_requested) {
+                    let mut s = state.write();
+                    s.worker_id = None;
+                    s.session_token = None;
+                    let now = Instant::now();
+                    for unacked in s.unacked_results.values_mut() {
+                        unacked.resend_at = now;
+                    }
+                    continue;
+                }
+
+                dead_letter_unacked(&state, &event_tx).await;
             }
             Err(e) => {
                 error!(error = %e, "Failed to connect to coordinator");
@@ -423,35 +1015,111 @@ async fn run_client_loop(
             break;
         }
 
-        // Calculate next retry delay
-        let delay = backoff.next_backoff().unwrap_or(config.max_reconnect_delay);
+        // Give up once the failures have lasted long enough
+        let failed_for = failing_since.elapsed();
+        if !config.exit_after_failed.is_zero() && failed_for >= config.exit_after_failed {
+            error!(
+                failed_secs = failed_for.as_secs(),
+                limit_secs = config.exit_after_failed.as_secs(),
+                "No registered coordinator connection for too long, giving up"
+            );
+            let _ = event_tx.send(ClientEvent::GaveUp { failed_for }).await;
+            break;
+        }
+
+        // Calculate next retry delay, waking in time for the give-up deadline
+        let mut delay = backoff.next_delay();
+        if !config.exit_after_failed.is_zero() {
+            delay = delay.min(config.exit_after_failed - failed_for);
+        }
 
         let _ = event_tx.send(ClientEvent::Reconnecting {
             attempt: attempts,
         }).await;
 
         info!(
-            delay_secs = delay.as_secs(),
+            delay_ms = delay.as_millis() as u64,
             attempt = attempts,
             "Waiting before reconnection"
         );
 
-        // Wait before reconnecting (also check for shutdown commands)
-        tokio::select! {
-            _ = tokio::time::sleep(delay) => {}
-            Some(cmd) = command_rx.recv() => {
-                if matches!(cmd, ClientCommand::Shutdown) {
-                    let mut s = state.write();
-                    s.connection_state = ConnectionState::ShuttingDown;
-                    break;
+        // Wait before reconnecting, still answering status queries and
+        // watching for shutdown
+        let wait = tokio::time::sleep(delay);
+        tokio::pin!(wait);
+        let shutdown = loop {
+            tokio::select! {
+                _ = &mut wait => break false,
+                Some(cmd) = command_rx.recv() => match cmd {
+                    ClientCommand::Shutdown { .. } => break true,
+                    ClientCommand::GetState(tx) => {
+                        let _ = tx.send(state.read().connection_state);
+                    }
+                    ClientCommand::GetStats(tx) => {
+                        let _ = tx.send(stats_snapshot(&state));
+                    }
+                    _ => break false,
                 }
             }
+        };
+        if shutdown {
+            state.write().connection_state = ConnectionState::ShuttingDown;
+            break;
         }
     }
 
     info!("Client loop terminated");
 }
 
+/// How messages are encoded on a connection, as agreed at registration
+#[derive(Debug, Clone, Copy, Default)]
+struct Encoding {
+    format: WireFormat,
+    compression: Option<Compression>,
+}
+
+impl Encoding {
+    /// Frame `envelope` for sending: a text frame for JSON, a binary one
+    /// for MessagePack
+    fn frame(&self, envelope: &MessageEnvelope) -> Result<WsMessage> {
+        match self.format {
+            WireFormat::Json => envelope.to_wire_json(self.compression).map(WsMessage::Text),
+            WireFormat::Msgpack => envelope.to_wire_msgpack(self.compression).map(WsMessage::Binary),
+        }
+        .map_err(|e| Error::Protocol(e.to_string()))
+    }
+}
+
+/// `Message::Error` code for a registration whose credentials were refused
+const AUTH_FAILED: &str = "AUTH_FAILED";
+
+/// Registration for the current connection: configured tags followed by a
+/// `declined_model:<id>` tag per model the worker refuses, and the
+/// compression algorithms and wire formats offered
+fn register_request(
+    state: &ClientState,
+    worker_name: &str,
+    capabilities: &WorkerCapabilities,
+    auth_token: Option<String>,
+    compression: &[Compression],
+    wire_formats: &[WireFormat],
+) -> RegisterRequest {
+    RegisterRequest {
+        worker_id: state.worker_id.clone(),
+        name: worker_name.to_string(),
+        capabilities: state.capabilities.clone().unwrap_or_else(|| capabilities.clone()),
+        tags: state
+            .tags
+            .iter()
+            .cloned()
+            .chain(state.declined_models.iter().map(|model| format!("declined_model:{}", model)))
+            .collect(),
+        auth_token,
+        compression: compression.to_vec(),
+        wire_formats: wire_formats.to_vec(),
+    }
+}
+
 /// Handle an active WebSocket connection
 async fn handle_connection<S, R>(
     config: &CoordinatorClientConfig,
@@ -467,16 +1135,27 @@ where
     S: SinkExt<WsMessage, Error = WsError> + Unpin,
     R: StreamExt<Item = std::result::Result<WsMessage, WsError>> + Unpin,
 {
-    // Send registration message
-    let register_msg = Message::Register(RegisterRequest {
-        worker_id: state.read().worker_id.clone(),
-        name: worker_name.to_string(),
-        capabilities: capabilities.clone(),
-        tags: vec![],
-        auth_token: None,
-    });
-
-    send_message(&mut write, register_msg).await?;
+    // Send registration message, signed for now if credentials are set
+    let auth_token = match config.credentials {
+        Some(ref credentials) => Some(credentials.auth_token().ok_or_else(|| {
+            Error::AuthenticationFailed {
+                message: "Failed to sign registration token, check secret_key in config".to_string(),
+            }
+        })?),
+        None => None,
+    };
+    let compression_offer = if config.compression { Compression::SUPPORTED } else { &[] };
+    let format_offer = if config.msgpack { WireFormat::SUPPORTED } else { &[] };
+    let register_msg = Message::Register(register_request(
+        &state.read(),
+        worker_name,
+        capabilities,
+        auth_token,
+        compression_offer,
+        format_offer,
+    ));
+
+    send_message(&mut write, register_msg, Encoding::default()).await?;
     debug!("Sent registration request");
 
     // Wait for registration acknowledgment
@@ -487,65 +1166,118 @@ where
         });
     }
 
+    // Use what the coordinator chose, as long as it was offered
+    let encoding = {
+        let s = state.read();
+        Encoding {
+            format: s.wire_format.filter(|f| format_offer.contains(f)).unwrap_or_default(),
+            compression: s.compression.filter(|c| compression_offer.contains(c)),
+        }
+    };
+    debug!(format = ?encoding.format, compression = ?encoding.compression, "Message encoding agreed");
+
     // Start heartbeat timer
-    let heartbeat_interval = config.heartbeat_interval;
+    let heartbeat_interval = state.read().heartbeat_interval.unwrap_or(config.heartbeat_interval);
     let mut heartbeat_timer = tokio::time::interval(heartbeat_interval);
     heartbeat_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
+    // WebSocket-level liveness, independent of the heartbeat
+    let mut keepalive = Keepalive::new(config.ping_interval, config.ping_timeout, Instant::now());
+
     // Main message loop
     loop {
+        // Pick up an interval changed by a config update
+        let heartbeat_interval = state.read().heartbeat_interval.unwrap_or(config.heartbeat_interval);
+        if heartbeat_interval != heartbeat_timer.period() {
+            debug!(interval_ms = heartbeat_interval.as_millis() as u64, "Heartbeat interval changed");
+            heartbeat_timer = tokio::time::interval_at(
+                tokio::time::Instant::now() + heartbeat_interval,
+                heartbeat_interval,
+            );
+            heartbeat_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        }
+
+        let next_resend = state.read().unacked_results.values().map(|u| u.resend_at).min();
+        let resend_at = tokio::time::Instant::from_std(next_resend.unwrap_or_else(Instant::now));
+
         tokio::select! {
             // Heartbeat tick
             _ = heartbeat_timer.tick() => {
                 let worker_id = state.read().worker_id.clone()
                     .unwrap_or_else(|| "unknown".to_string());
+                let (gpu_percent, gpu_memory_used_mb) = tokio::task::spawn_blocking(crate::system::gpu_usage)
+                    .await
+                    .unwrap_or_default();
 
                 let heartbeat = Message::Heartbeat(HeartbeatRequest {
                     worker_id,
-                    status: state.read().worker_status,
-                    resources: ResourceUsageReport::default(), // TODO: Get actual usage
+                    status: state.read().reported_status(),
+                    resources: ResourceUsageReport {
+                        gpu_percent,
+                        gpu_memory_used_mb,
+                        ..ResourceUsageReport::default() // TODO: Get actual CPU/memory usage
+                    },
                     active_tasks: vec![], // TODO: Track active tasks
                     completed_task_count: 0, // TODO: Track completed count
                     uptime_secs: state.read().connected_at
                         .map(|t| t.elapsed().as_secs())
                         .unwrap_or(0),
+                    declined_models: state.read().declined_models.clone(),
                 });
 
-                if let Err(e) = send_message(&mut write, heartbeat).await {
+                if let Err(e) = send_message(&mut write, heartbeat, encoding).await {
                     warn!(error = %e, "Failed to send heartbeat");
                     return Err(e);
                 }
                 debug!("Sent heartbeat");
             }
 
+            // Ping, or give up on a connection that stopped answering
+            _ = tokio::time::sleep_until(tokio::time::Instant::from_std(keepalive.deadline())), if keepalive.enabled() => {
+                match keepalive.poll(Instant::now()) {
+                    KeepaliveAction::Ping => {
+                        write.send(WsMessage::Ping(Vec::new())).await?;
+                        debug!("Sent keepalive ping");
+                    }
+                    KeepaliveAction::Dead => {
+                        warn!(timeout_ms = config.ping_timeout.as_millis() as u64, "No pong from coordinator, connection is dead");
+                        return Err(Error::ConnectionLost {
+                            message: format!("no pong within {}ms", config.ping_timeout.as_millis()),
+                        });
+                    }
+                    KeepaliveAction::Wait => {}
+                }
+            }
+
+            // Overdue result acks
+            _ = tokio::time::sleep_until(resend_at), if next_resend.is_some() => {
+                resend_unacked_results(state, &mut write, encoding).await?;
+            }
+
             // Incoming message from coordinator
             msg = read.next() => {
                 match msg {
                     Some(Ok(WsMessage::Text(text))) => {
-                        match MessageEnvelope::from_json(&text) {
-                            Ok(envelope) => {
-                                handle_incoming_message(envelope, state, event_tx).await?;
-                            }
-                            Err(e) => {
-                                warn!(error = %e, "Failed to parse message");
-                            }
+                        if let Some(recorder) = state.read().recorder.clone() {
+                            recorder.record_inbound(text.as_bytes());
                         }
+                        handle_frame(text.as_bytes(), WireFormat::Json, config.strict_protocol, state, event_tx).await?;
                     }
                     Some(Ok(WsMessage::Binary(data))) => {
-                        match MessageEnvelope::from_json_bytes(&data) {
-                            Ok(envelope) => {
-                                handle_incoming_message(envelope, state, event_tx).await?;
-                            }
-                            Err(e) => {
-                                warn!(error = %e, "Failed to parse binary message");
+                        // MessagePack once agreed, JSON otherwise
+                        if let Some(recorder) = state.read().recorder.clone() {
+                            match encoding.format {
+                                WireFormat::Json => recorder.record_inbound(&data),
+                                WireFormat::Msgpack => recorder.record_inbound(&msgpack_as_json(&data)),
                             }
                         }
+                        handle_frame(&data, encoding.format, config.strict_protocol, state, event_tx).await?;
                     }
                     Some(Ok(WsMessage::Ping(data))) => {
                         write.send(WsMessage::Pong(data)).await?;
                     }
                     Some(Ok(WsMessage::Pong(_))) => {
-                        // Ignore pong
+                        keepalive.pong(Instant::now());
                     }
                     Some(Ok(WsMessage::Close(frame))) => {
                         info!(frame = ?frame, "Received close frame");
@@ -567,18 +1299,35 @@ where
             cmd = command_rx.recv() => {
                 match cmd {
                     Some(ClientCommand::Send(envelope)) => {
-                        let json = envelope.to_json()
-                            .map_err(|e| Error::Protocol(e.to_string()))?;
-                        write.send(WsMessage::Text(json)).await?;
+                        write.send(encoding.frame(&envelope)?).await?;
                     }
                     Some(ClientCommand::UpdateStatus(status)) => {
                         state.write().worker_status = status;
                     }
-                    Some(ClientCommand::SubmitResult(result)) => {
+                    Some(ClientCommand::SubmitResult(mut result)) => {
+                        if config.require_result_ack {
+                            let result_id = Uuid::new_v4().to_string();
+                            result.result_id = Some(result_id.clone());
+                            state.write().unacked_results.insert(result_id, UnackedResult {
+                                result: result.clone(),
+                                attempts: 1,
+                                retry_delay: config.result_ack_timeout,
+                                resend_at: Instant::now() + config.result_ack_timeout,
+                            });
+                        }
                         let msg = Message::TaskResult(result);
-                        send_message(&mut write, msg).await?;
+                        if let Some(recorder) = state.read().recorder.clone() {
+                            recorder.record_outbound(&msg);
+                        }
+                        send_message(&mut write, msg, encoding).await?;
+                    }
+                    Some(ClientCommand::Reconnect) => {
+                        info!("Reconnect requested, closing coordinator connection");
+                        let _ = write.send(WsMessage::Close(None)).await;
+                        state.write().reconnect_requested = true;
+                        return Ok(());
                     }
-                    Some(ClientCommand::Shutdown) => {
+                    Some(ClientCommand::Shutdown { abandoned_tasks }) => {
                         info!("Shutdown command received");
                         let worker_id = state.read().worker_id.clone()
                             .unwrap_or_else(|| "unknown".to_string());
@@ -587,9 +1336,9 @@ where
                             worker_id,
                             reason: "Graceful shutdown".to_string(),
                             graceful: true,
-                            abandoned_tasks: vec![],
+                            abandoned_tasks,
                         });
-                        let _ = send_message(&mut write, shutdown_msg).await;
+                        let _ = send_message(&mut write, shutdown_msg, encoding).await;
 
                         // Send close frame
                         let _ = write.send(WsMessage::Close(None)).await;
@@ -599,6 +1348,9 @@ where
                     Some(ClientCommand::GetState(tx)) => {
                         let _ = tx.send(state.read().connection_state);
                     }
+                    Some(ClientCommand::GetStats(tx)) => {
+                        let _ = tx.send(stats_snapshot(state));
+                    }
                     None => {
                         info!("Command channel closed");
                         return Ok(());
@@ -609,17 +1361,93 @@ where
     }
 }
 
-/// Send a protocol message
-async fn send_message<S>(write: &mut S, msg: Message) -> Result<()>
+/// Build a [`WorkerStats`] snapshot. The state lock is released before the
+/// stats source runs.
+fn stats_snapshot(state: &Arc<RwLock<ClientState>>) -> WorkerStats {
+    let (mut stats, source) = {
+        let s = state.read();
+        let stats = WorkerStats {
+            worker_id: s.worker_id.clone(),
+            connection_state: s.connection_state,
+            uptime_secs: s.started_at.elapsed().as_secs(),
+            ..Default::default()
+        };
+        (stats, s.stats_source.clone())
+    };
+    if let Some(source) = source {
+        source(&mut stats);
+    }
+    stats
+}
+
+/// Re-send results whose ack is overdue, doubling each one's retry delay
+async fn resend_unacked_results<S>(
+    state: &Arc<RwLock<ClientState>>,
+    write: &mut S,
+    encoding: Encoding,
+) -> Result<()>
 where
     S: SinkExt<WsMessage, Error = WsError> + Unpin,
 {
-    let envelope = MessageEnvelope::new(msg);
-    let json = envelope.to_json().map_err(|e| Error::Protocol(e.to_string()))?;
-    write.send(WsMessage::Text(json)).await
+    let now = Instant::now();
+    let due: Vec<(TaskResultMessage, u32)> = {
+        let mut s = state.write();
+        s.unacked_results
+            .values_mut()
+            .filter(|u| u.resend_at <= now)
+            .map(|u| {
+                u.attempts += 1;
+                u.retry_delay = (u.retry_delay * 2).min(MAX_RESULT_RESEND_DELAY);
+                u.resend_at = now + u.retry_delay;
+                (u.result.clone(), u.attempts)
+            })
+            .collect()
+    };
+
+    let recorder = state.read().recorder.clone();
+    for (result, attempt) in due {
+        warn!(task_id = %result.task_id, attempt = attempt, "Task result not acknowledged, re-sending");
+        let msg = Message::TaskResult(result);
+        if let Some(ref recorder) = recorder {
+            recorder.record_outbound(&msg);
+        }
+        send_message(write, msg, encoding).await?;
+    }
+    Ok(())
+}
+
+/// Hand results still unacknowledged at disconnect back to the application
+async fn dead_letter_unacked(state: &Arc<RwLock<ClientState>>, event_tx: &mpsc::Sender<ClientEvent>) {
+    let unacked: Vec<UnackedResult> = state.write().unacked_results.drain().map(|(_, u)| u).collect();
+    for u in unacked {
+        warn!(
+            task_id = %u.result.task_id,
+            attempts = u.attempts,
+            "Connection dropped before task result was acknowledged"
+        );
+        let _ = event_tx.send(ClientEvent::ResultDeadLettered(u.result)).await;
+    }
+}
+
+/// Send a protocol message in the connection's `encoding`
+async fn send_message<S>(write: &mut S, msg: Message, encoding: Encoding) -> Result<()>
+where
+    S: SinkExt<WsMessage, Error = WsError> + Unpin,
+{
+    let frame = encoding.frame(&MessageEnvelope::new(msg))?;
+    write.send(frame).await
         .map_err(|e| Error::Connection(e.to_string()))
 }
 
+/// A MessagePack frame as JSON, for the session recording (which replays
+/// frames as JSON)
+fn msgpack_as_json(data: &[u8]) -> Vec<u8> {
+    crate::protocol::from_msgpack::<serde_json::Value>(data)
+        .ok()
+        .and_then(|value| serde_json::to_vec(&value).ok())
+        .unwrap_or_else(|| data.to_vec())
+}
+
 /// Wait for registration acknowledgment
 async fn wait_for_registration<R>(
     read: &mut R,
@@ -629,19 +1457,30 @@ async fn wait_for_registration<R>(
 where
     R: StreamExt<Item = std::result::Result<WsMessage, WsError>> + Unpin,
 {
+    let recorder = state.read().recorder.clone();
+
     // Wait for registration response (with timeout)
     let timeout = tokio::time::timeout(Duration::from_secs(30), async {
         while let Some(msg) = read.next().await {
             match msg {
                 Ok(WsMessage::Text(text)) => {
+                    if let Some(ref recorder) = recorder {
+                        recorder.record_inbound(text.as_bytes());
+                    }
                     if let Ok(envelope) = MessageEnvelope::from_json(&text) {
                         if let Message::RegisterAck(ack) = envelope.payload {
                             return Ok(ack);
                         }
                         if let Message::Error(err) = envelope.payload {
-                            return Err(Error::AuthenticationFailed {
-                                message: err.message,
-                            });
+                            if err.code == AUTH_FAILED {
+                                return Err(Error::AuthenticationFailed {
+                                    message: err.message,
+                                });
+                            }
+                            return Err(Error::Protocol(format!(
+                                "Registration failed ({}): {}",
+                                err.code, err.message
+                            )));
                         }
                     }
                 }
@@ -663,6 +1502,16 @@ where
             timeout_secs: 30,
         })??;
 
+    Ok(apply_register_ack(ack, state, event_tx).await)
+}
+
+/// Apply the coordinator's answer to our registration, returning whether it
+/// was accepted
+async fn apply_register_ack(
+    ack: RegisterAckResponse,
+    state: &Arc<RwLock<ClientState>>,
+    event_tx: &mpsc::Sender<ClientEvent>,
+) -> bool {
     if ack.success {
         // Update state within a scope to ensure guard is dropped before await
         let worker_id_clone = {
@@ -670,6 +1519,8 @@ where
             s.worker_id = Some(ack.worker_id.clone());
             s.session_token = ack.session_token;
             s.connection_state = ConnectionState::Registered;
+            s.compression = ack.compression;
+            s.wire_format = ack.wire_format;
             ack.worker_id.clone()
         };
 
@@ -678,7 +1529,7 @@ where
             worker_id: worker_id_clone,
         }).await;
 
-        Ok(true)
+        true
     } else {
         let error_msg = ack.error.unwrap_or_else(|| "Unknown error".to_string());
         error!(error = %error_msg, "Registration failed");
@@ -687,11 +1538,126 @@ where
             fatal: true,
         }).await;
 
-        Ok(false)
+        false
     }
 }
 
-/// Handle incoming message from coordinator
+/// Decode an incoming frame and handle it.
+///
+/// Well-formed frames with an unknown message type are always skipped. Other
+/// unparseable frames are skipped in lenient mode; in strict mode they raise
+/// a `ProtocolMalformed` error event and fail the connection so it resets.
+async fn handle_frame(
+    data: &[u8],
+    format: WireFormat,
+    strict: bool,
+    state: &Arc<RwLock<ClientState>>,
+    event_tx: &mpsc::Sender<ClientEvent>,
+) -> Result<()> {
+    let parsed = match format {
+        WireFormat::Json => MessageEnvelope::from_json_bytes(data).map_err(|e| e.to_string()),
+        WireFormat::Msgpack => MessageEnvelope::from_msgpack_bytes(data).map_err(|e| e.to_string()),
+    };
+    let parse_error = match parsed {
+        Ok(envelope) => match envelope.decompressed(format) {
+            Ok(envelope) => return handle_incoming_message(envelope, state, event_tx).await,
+            Err(e) => e.to_string(),
+        },
+        Err(e) => e,
+    };
+
+    if let Some(message_type) = unknown_message_type(data, format) {
+        debug!(message_type = %message_type, "Ignoring unknown message type");
+        return Ok(());
+    }
+
+    if !strict {
+        warn!(error = %parse_error, "Failed to parse message");
+        return Ok(());
+    }
+
+    let err = Error::ProtocolMalformed {
+        message: parse_error,
+    };
+    error!(error = %err, "Malformed message in strict protocol mode");
+    let _ = event_tx.send(ClientEvent::Error {
+        message: err.to_string(),
+        fatal: false,
+    }).await;
+    Err(err)
+}
+
+/// Cancellations requested by a heartbeat ack: `CANCEL_TASK` pending actions
+/// (graceful) and, if honored, reclaimed tasks. A reclaimed task is already
+/// running elsewhere, so it's aborted rather than allowed to finish.
+fn heartbeat_cancellations(ack: &HeartbeatAckResponse, honor_reclamation: bool) -> Vec<TaskCancelMessage> {
+    let mut cancels = Vec::new();
+    if honor_reclamation {
+        cancels.extend(ack.reclaimed_tasks.iter().map(|task_id| TaskCancelMessage {
+            task_id: task_id.clone(),
+            reason: "reclaimed by coordinator".to_string(),
+            force: true,
+        }));
+    } else if !ack.reclaimed_tasks.is_empty() {
+        debug!(count = ack.reclaimed_tasks.len(), "Ignoring reclaimed tasks (reclamation disabled)");
+    }
+
+    for action in &ack.pending_actions {
+        if let PendingAction::CancelTask { task_id } = action {
+            if !cancels.iter().any(|c| &c.task_id == task_id) {
+                cancels.push(TaskCancelMessage {
+                    task_id: task_id.clone(),
+                    reason: "cancel requested by coordinator".to_string(),
+                    force: false,
+                });
+            }
+        }
+    }
+    cancels
+}
+
+/// Act on a heartbeat ack's pending action other than `CANCEL_TASK` (see
+/// [`heartbeat_cancellations`]). Pausing and resuming change the status the
+/// next heartbeat reports, so the coordinator sees it even before the main
+/// loop reacts to the event.
+fn apply_pending_action(action: PendingAction, state: &Arc<RwLock<ClientState>>) -> Option<ClientEvent> {
+    match action {
+        PendingAction::CancelTask { .. } => None,
+        PendingAction::Pause => {
+            info!("Coordinator requested pause");
+            state.write().coordinator_paused = true;
+            Some(ClientEvent::Paused)
+        }
+        PendingAction::Resume => {
+            info!("Coordinator requested resume");
+            state.write().coordinator_paused = false;
+            Some(ClientEvent::Resumed)
+        }
+        PendingAction::UpdateConfig { config } => {
+            info!("Configuration update received via heartbeat");
+            Some(ClientEvent::ConfigUpdate(crate::protocol::ConfigUpdateMessage {
+                config,
+                persist: false,
+            }))
+        }
+        PendingAction::Shutdown { reason } => {
+            info!(reason = %reason, "Coordinator requested shutdown");
+            Some(ClientEvent::ShutdownRequested { reason })
+        }
+    }
+}
+
+/// The `type` of a well-formed object whose message type this worker
+/// doesn't know
+fn unknown_message_type(data: &[u8], format: WireFormat) -> Option<String> {
+    let value: serde_json::Value = match format {
+        WireFormat::Json => serde_json::from_slice(data).ok()?,
+        WireFormat::Msgpack => crate::protocol::from_msgpack(data).ok()?,
+    };
+    let message_type = value.get("type")?.as_str()?;
+    (!Message::TYPE_NAMES.contains(&message_type)).then(|| message_type.to_string())
+}
+
 async fn handle_incoming_message(
     envelope: MessageEnvelope,
     state: &Arc<RwLock<ClientState>>,
@@ -701,7 +1667,24 @@ async fn handle_incoming_message(
 
     match envelope.payload {
         Message::HeartbeatAck(ack) => {
-            state.write().last_heartbeat = Some(Instant::now());
+            let honor_reclamation = {
+                let mut state = state.write();
+                state.last_heartbeat = Some(Instant::now());
+                state.honor_task_reclamation
+            };
+            for cancel in heartbeat_cancellations(&ack, honor_reclamation) {
+                info!(task_id = %cancel.task_id, reason = %cancel.reason, force = cancel.force, "Task cancelled via heartbeat");
+                let _ = event_tx.send(ClientEvent::TaskCancelled {
+                    task_id: cancel.task_id,
+                    reason: cancel.reason,
+                    force: cancel.force,
+                }).await;
+            }
+            for action in ack.pending_actions {
+                if let Some(event) = apply_pending_action(action, state) {
+                    let _ = event_tx.send(event).await;
+                }
+            }
             let _ = event_tx.send(ClientEvent::HeartbeatAck).await;
         }
 
@@ -711,16 +1694,25 @@ async fn handle_incoming_message(
         }
 
         Message::TaskCancel(cancel) => {
-            info!(task_id = %cancel.task_id, reason = %cancel.reason, "Task cancelled");
+            info!(task_id = %cancel.task_id, reason = %cancel.reason, force = cancel.force, "Task cancelled");
             let _ = event_tx.send(ClientEvent::TaskCancelled {
                 task_id: cancel.task_id,
                 reason: cancel.reason,
+                force: cancel.force,
             }).await;
         }
 
+        Message::TaskResultAck(ack) => {
+            if state.write().unacked_results.remove(&ack.result_id).is_some() {
+                debug!(task_id = %ack.task_id, result_id = %ack.result_id, "Task result acknowledged");
+            } else {
+                debug!(result_id = %ack.result_id, "Ack for unknown or already acknowledged result");
+            }
+        }
+
         Message::ConfigUpdate(update) => {
             info!("Received configuration update");
-            let _ = event_tx.send(ClientEvent::ConfigUpdate(update.config)).await;
+            let _ = event_tx.send(ClientEvent::ConfigUpdate(update)).await;
         }
 
         Message::Error(err) => {
@@ -761,6 +1753,53 @@ async fn handle_incoming_message(
     Ok(())
 }
 
+// ─────────────────────────────────────────────────────────────────
+// Replay
+// ─────────────────────────────────────────────────────────────────
+
+/// Feeds recorded coordinator frames through the same handlers a live
+/// connection uses, without a socket, timers or reconnects
+pub struct ReplaySession {
+    strict: bool,
+    state: Arc<RwLock<ClientState>>,
+    event_tx: mpsc::Sender<ClientEvent>,
+    event_rx: mpsc::Receiver<ClientEvent>,
+}
+
+impl ReplaySession {
+    /// Start a replay; `strict` mirrors `coordinator.strict_protocol`
+    pub fn new(strict: bool) -> Self {
+        let (event_tx, event_rx) = mpsc::channel(16);
+        Self {
+            strict,
+            state: Arc::new(RwLock::new(ClientState::default())),
+            event_tx,
+            event_rx,
+        }
+    }
+
+    /// Handle one frame, returning the events it produced. Registration
+    /// acks are applied as during the registration handshake. A frame that
+    /// would reset a live connection (strict mode) shows up as its `Error`
+    /// event and the replay carries on.
+    pub async fn feed(&mut self, frame: &str) -> Vec<ClientEvent> {
+        match MessageEnvelope::from_json(frame) {
+            Ok(MessageEnvelope { payload: Message::RegisterAck(ack), .. }) => {
+                apply_register_ack(ack, &self.state, &self.event_tx).await;
+            }
+            _ => {
+                let _ = handle_frame(frame.as_bytes(), WireFormat::Json, self.strict, &self.state, &self.event_tx).await;
+            }
+        }
+
+        let mut events = Vec::new();
+        while let Ok(event) = self.event_rx.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────
@@ -768,13 +1807,788 @@ async fn handle_incoming_message(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::ws_stub;
     use crate::types::TaskType;
 
+    /// Capabilities of a small CPU-only worker
+    fn caps(supported_tasks: Vec<TaskType>) -> WorkerCapabilities {
+        WorkerCapabilities {
+            supported_tasks,
+            max_concurrent_tasks: 1,
+            available_memory_mb: 1024,
+            gpu_available: false,
+            gpu_device: None,
+            gpu_memory_mb: None,
+            max_context_length: 4096,
+            worker_version: "0.1.0".to_string(),
+            supports_streaming: false,
+        }
+    }
+
+    /// Successful registration as `worker-1`
+    fn register_ack() -> RegisterAckResponse {
+        RegisterAckResponse {
+            success: true,
+            worker_id: "worker-1".to_string(),
+            session_token: None,
+            heartbeat_interval_secs: 30,
+            coordinator_version: Default::default(),
+            error: None,
+            compression: None,
+            wire_format: None,
+        }
+    }
+
+    /// `message` as a JSON text frame
+    fn text_frame(message: Message) -> WsMessage {
+        WsMessage::Text(MessageEnvelope::new(message).to_json().unwrap())
+    }
+
+    /// The message in a JSON text frame
+    fn payload(frame: &WsMessage) -> Message {
+        MessageEnvelope::from_json(frame.to_text().unwrap()).unwrap().payload
+    }
+
     #[test]
     fn test_config_default() {
         let config = CoordinatorClientConfig::default();
         assert_eq!(config.heartbeat_interval, Duration::from_secs(30));
         assert_eq!(config.max_reconnect_attempts, 0);
+        assert!(config.headers.is_empty());
+        assert!(config.subprotocol.is_none());
+    }
+
+    #[test]
+    fn test_reconnect_delays_jittered_and_capped() {
+        let config = CoordinatorClientConfig {
+            initial_reconnect_delay: Duration::from_millis(100),
+            max_reconnect_delay: Duration::from_secs(2),
+            reconnect_spread: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let mut backoff = ReconnectBackoff::new(&config);
+
+        let mut first_delays = std::collections::HashSet::new();
+        let mut late_delays = Vec::new();
+        for _ in 0..200 {
+            backoff.reset();
+            for attempt in 0..10 {
+                let delay = backoff.next_delay();
+                assert!(delay <= config.max_reconnect_delay, "delay {:?} over the cap", delay);
+                if attempt == 0 {
+                    first_delays.insert(delay);
+                } else if attempt == 9 {
+                    late_delays.push(delay);
+                }
+            }
+        }
+
+        // Jittered both before the first attempt and once at the cap
+        assert!(first_delays.len() > 50, "first delays barely vary: {:?}", first_delays);
+        let distinct: std::collections::HashSet<_> = late_delays.iter().collect();
+        assert!(distinct.len() > 50, "capped delays barely vary: {:?}", late_delays);
+        // Full jitter: delays at the cap spread over [0, max] rather than
+        // piling up on it
+        let below = late_delays.iter().filter(|d| **d < config.max_reconnect_delay).count();
+        assert!(below > 190, "only {} of 200 capped delays below the max", below);
+    }
+
+    #[test]
+    fn test_keepalive_detects_dead_connection() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut keepalive = Keepalive::new(Duration::from_millis(100), Duration::from_millis(50), start);
+        assert!(keepalive.enabled());
+
+        // Ping once the interval is up, then wait for the pong
+        assert_eq!(keepalive.deadline(), at(100));
+        assert_eq!(keepalive.poll(at(99)), KeepaliveAction::Wait);
+        assert_eq!(keepalive.poll(at(100)), KeepaliveAction::Ping);
+        assert_eq!(keepalive.deadline(), at(150));
+        assert_eq!(keepalive.poll(at(120)), KeepaliveAction::Wait);
+
+        // Answered: the next ping is an interval after the pong
+        keepalive.pong(at(130));
+        assert_eq!(keepalive.deadline(), at(230));
+        assert_eq!(keepalive.poll(at(200)), KeepaliveAction::Wait);
+        assert_eq!(keepalive.poll(at(230)), KeepaliveAction::Ping);
+
+        // Unanswered: dead once the timeout passes
+        assert_eq!(keepalive.poll(at(279)), KeepaliveAction::Wait);
+        assert_eq!(keepalive.poll(at(280)), KeepaliveAction::Dead);
+
+        // A stray pong with no ping outstanding changes nothing
+        let mut idle = Keepalive::new(Duration::from_millis(100), Duration::from_millis(50), start);
+        idle.pong(at(10));
+        assert_eq!(idle.deadline(), at(100));
+
+        assert!(!Keepalive::new(Duration::ZERO, Duration::from_millis(50), start).enabled());
+    }
+
+    #[test]
+    fn test_http_base_url() {
+        let base = |url: &str| http_base_url(url).unwrap();
+        assert_eq!(base("wss://coordinator.ai4all.network"), "https://coordinator.ai4all.network");
+        assert_eq!(base("ws://localhost:3000/"), "http://localhost:3000");
+        assert_eq!(base("ws://10.0.0.5:3000/ws"), "http://10.0.0.5:3000");
+
+        // Only the scheme is swapped, not a "ws://" elsewhere in the URL
+        assert_eq!(
+            base("wss://host.example/ws?token=x&next=ws://other"),
+            "https://host.example"
+        );
+        assert_eq!(base("wss://host.example:8443/api/v1/ws/"), "https://host.example:8443/api/v1");
+        assert_eq!(base("wss://host.example/api/wsgate#frag"), "https://host.example/api/wsgate");
+        assert_eq!(base("wss://host.example/ws/"), "https://host.example");
+
+        let err = http_base_url("https://host.example").unwrap_err();
+        assert!(matches!(err, Error::Config(_)));
+        assert!(http_base_url("not a url").is_err());
+        let err = http_base_url("ftp://host.example/?token=secret").unwrap_err();
+        assert!(!err.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn test_upgrade_request_headers_and_subprotocol() {
+        let config = CoordinatorClientConfig {
+            url: "wss://coordinator.example.com/ws".to_string(),
+            headers: vec![
+                ("Authorization".to_string(), "Bearer abc123".to_string()),
+                ("X-Worker-Region".to_string(), "eu-west".to_string()),
+            ],
+            subprotocol: Some("ai4all.v1".to_string()),
+            ..Default::default()
+        };
+        let url = Url::parse(&config.url).unwrap();
+
+        let request = build_upgrade_request(&config, &url).unwrap();
+        let headers = request.headers();
+        assert_eq!(request.uri(), "wss://coordinator.example.com/ws");
+        assert_eq!(headers["authorization"], "Bearer abc123");
+        assert_eq!(headers["x-worker-region"], "eu-west");
+        assert_eq!(headers["sec-websocket-protocol"], "ai4all.v1");
+        // Standard handshake headers are still present
+        assert!(headers.contains_key("sec-websocket-key"));
+
+        let bad = CoordinatorClientConfig {
+            headers: vec![("Bad Header".to_string(), "x".to_string())],
+            ..config
+        };
+        assert!(matches!(build_upgrade_request(&bad, &url), Err(Error::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_frame_strict_mode() {
+        let state = Arc::new(RwLock::new(ClientState::default()));
+        let (event_tx, mut event_rx) = mpsc::channel(8);
+
+        let result = handle_frame(br#"{"type": "HEARTBEAT_ACK", "id": 42"#, WireFormat::Json, true, &state, &event_tx).await;
+
+        assert!(matches!(result, Err(Error::ProtocolMalformed { .. })));
+        match event_rx.try_recv() {
+            Ok(ClientEvent::Error { message, fatal }) => {
+                assert!(message.contains("malformed") || message.contains("Malformed"));
+                assert!(!fatal);
+            }
+            other => panic!("Expected error event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_frame_lenient_mode() {
+        let state = Arc::new(RwLock::new(ClientState::default()));
+        let (event_tx, mut event_rx) = mpsc::channel(8);
+
+        let result = handle_frame(br#"{"type": "HEARTBEAT_ACK", "id": 42"#, WireFormat::Json, false, &state, &event_tx).await;
+
+        assert!(result.is_ok());
+        assert!(event_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_message_type_tolerated() {
+        let state = Arc::new(RwLock::new(ClientState::default()));
+        let (event_tx, mut event_rx) = mpsc::channel(8);
+
+        let frame = br#"{"type": "FUTURE_FEATURE", "id": "x", "payload": {}}"#;
+        assert!(handle_frame(frame, WireFormat::Json, true, &state, &event_tx).await.is_ok());
+        assert!(event_rx.try_recv().is_err());
+    }
+
+    fn heartbeat_ack_frame(reclaimed: &[&str], pending_actions: serde_json::Value) -> Vec<u8> {
+        serde_json::json!({
+            "id": Uuid::new_v4().to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "version": { "major": 1, "minor": 0, "patch": 0 },
+            "type": "HEARTBEAT_ACK",
+            "accepted": true,
+            "next_heartbeat": chrono::Utc::now().to_rfc3339(),
+            "pending_actions": pending_actions,
+            "reclaimed_tasks": reclaimed,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_reclaimed_task_cancelled() {
+        let state = Arc::new(RwLock::new(ClientState::default()));
+        let (event_tx, mut event_rx) = mpsc::channel(8);
+
+        let frame = heartbeat_ack_frame(
+            &["task-running"],
+            serde_json::json!([
+                { "action": "CANCEL_TASK", "task_id": "task-running" },
+                { "action": "CANCEL_TASK", "task_id": "task-queued" },
+            ]),
+        );
+        handle_frame(&frame, WireFormat::Json, true, &state, &event_tx).await.unwrap();
+
+        // Reclaimed tasks are aborted; a cancel action for the same task
+        // doesn't downgrade that
+        match event_rx.try_recv() {
+            Ok(ClientEvent::TaskCancelled { task_id, reason, force }) => {
+                assert_eq!(task_id, "task-running");
+                assert!(reason.contains("reclaimed"));
+                assert!(force);
+            }
+            other => panic!("Expected reclaimed task cancellation, got {:?}", other),
+        }
+        match event_rx.try_recv() {
+            Ok(ClientEvent::TaskCancelled { task_id, force, .. }) => {
+                assert_eq!(task_id, "task-queued");
+                assert!(!force);
+            }
+            other => panic!("Expected pending cancel action, got {:?}", other),
+        }
+        assert!(matches!(event_rx.try_recv(), Ok(ClientEvent::HeartbeatAck)));
+        assert!(state.read().last_heartbeat.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_reclamation_disabled() {
+        let state = Arc::new(RwLock::new(ClientState {
+            honor_task_reclamation: false,
+            ..Default::default()
+        }));
+        let (event_tx, mut event_rx) = mpsc::channel(8);
+
+        let frame = heartbeat_ack_frame(&["task-running"], serde_json::json!([]));
+        handle_frame(&frame, WireFormat::Json, true, &state, &event_tx).await.unwrap();
+
+        assert!(matches!(event_rx.try_recv(), Ok(ClientEvent::HeartbeatAck)));
+        assert!(event_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_pending_actions() {
+        let state = Arc::new(RwLock::new(ClientState::default()));
+        let (event_tx, mut event_rx) = mpsc::channel(8);
+
+        let frame = heartbeat_ack_frame(&[], serde_json::json!([{ "action": "PAUSE" }]));
+        handle_frame(&frame, WireFormat::Json, true, &state, &event_tx).await.unwrap();
+        assert!(matches!(event_rx.try_recv(), Ok(ClientEvent::Paused)));
+        assert!(matches!(event_rx.try_recv(), Ok(ClientEvent::HeartbeatAck)));
+        assert_eq!(state.read().reported_status(), WorkerStatus::Paused);
+
+        let frame = heartbeat_ack_frame(&[], serde_json::json!([{ "action": "RESUME" }]));
+        handle_frame(&frame, WireFormat::Json, true, &state, &event_tx).await.unwrap();
+        assert!(matches!(event_rx.try_recv(), Ok(ClientEvent::Resumed)));
+        assert!(matches!(event_rx.try_recv(), Ok(ClientEvent::HeartbeatAck)));
+        assert_eq!(state.read().reported_status(), WorkerStatus::Ready);
+
+        // Resuming doesn't undo a drain
+        state.write().worker_status = WorkerStatus::Draining;
+        handle_frame(&frame, WireFormat::Json, true, &state, &event_tx).await.unwrap();
+        assert!(matches!(event_rx.try_recv(), Ok(ClientEvent::Resumed)));
+        assert!(matches!(event_rx.try_recv(), Ok(ClientEvent::HeartbeatAck)));
+        assert_eq!(state.read().reported_status(), WorkerStatus::Draining);
+
+        let frame = heartbeat_ack_frame(
+            &[],
+            serde_json::json!([
+                { "action": "UPDATE_CONFIG", "config": { "logging": { "level": "debug" } } },
+                { "action": "SHUTDOWN", "reason": "maintenance" },
+            ]),
+        );
+        handle_frame(&frame, WireFormat::Json, true, &state, &event_tx).await.unwrap();
+        match event_rx.try_recv() {
+            Ok(ClientEvent::ConfigUpdate(update)) => {
+                assert_eq!(update.config["logging"]["level"], "debug");
+                assert!(!update.persist);
+            }
+            other => panic!("Expected config update, got {:?}", other),
+        }
+        match event_rx.try_recv() {
+            Ok(ClientEvent::ShutdownRequested { reason }) => assert_eq!(reason, "maintenance"),
+            other => panic!("Expected shutdown request, got {:?}", other),
+        }
+        assert!(matches!(event_rx.try_recv(), Ok(ClientEvent::HeartbeatAck)));
+        assert!(event_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_failing_for_configured_time() {
+        // Nothing listens on a port freed right after binding
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+        let config = CoordinatorClientConfig {
+            url: format!("ws://{}", addr),
+            connect_timeout: Duration::from_millis(200),
+            initial_reconnect_delay: Duration::from_millis(50),
+            max_reconnect_delay: Duration::from_millis(200),
+            exit_after_failed: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let caps = caps(vec![]);
+        let mut client = CoordinatorClient::new(config, "test".to_string(), caps, vec![]);
+        let started = Instant::now();
+        let mut events = client.start().await.unwrap();
+
+        let mut attempts = 0;
+        let failed_for = loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ClientEvent::GaveUp { failed_for })) => break failed_for,
+                Ok(Some(ClientEvent::Reconnecting { .. })) => attempts += 1,
+                Ok(Some(_)) => continue,
+                other => panic!("Client never gave up: {:?}", other),
+            }
+        };
+
+        assert!(attempts > 1, "gave up after {} attempts", attempts);
+        assert!(failed_for >= Duration::from_secs(1));
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert!(started.elapsed() < Duration::from_secs(3));
+        // The client loop stopped
+        assert!(events.recv().await.is_none());
+    }
+
+    #[test]
+    fn test_configured_tags_sent_at_registration() {
+        let state = ClientState {
+            tags: vec!["gpu".to_string(), "eu-west".to_string()],
+            declined_models: vec!["llama-70b".to_string()],
+            ..Default::default()
+        };
+        let caps = caps(vec![]);
+
+        let json = MessageEnvelope::new(Message::Register(register_request(&state, "test", &caps, None, &[], &[])))
+            .to_json()
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value["tags"],
+            serde_json::json!(["gpu", "eu-west", "declined_model:llama-70b"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auth_failed_registration_is_fatal() {
+        let (token_tx, mut token_rx) = mpsc::unbounded_channel::<Option<String>>();
+
+        // Refuses every registration
+        let url = ws_stub(move |frame| {
+            if let Message::Register(request) = payload(&frame) {
+                let _ = token_tx.send(request.auth_token);
+            }
+            vec![text_frame(Message::Error(crate::protocol::ErrorMessage {
+                code: "AUTH_FAILED".to_string(),
+                message: "unknown account".to_string(),
+                related_message_id: None,
+                fatal: true,
+            }))]
+        })
+        .await;
+
+        let (_, sk) = pqcrypto_dilithium::dilithium3::keypair();
+        let config = CoordinatorClientConfig {
+            url,
+            initial_reconnect_delay: Duration::from_millis(50),
+            credentials: Some(PeerCredentials {
+                account_id: "acct".to_string(),
+                secret_key: hex::encode(pqcrypto_traits::sign::SecretKey::as_bytes(&sk)),
+            }),
+            ..Default::default()
+        };
+        let caps = caps(vec![]);
+        let mut client = CoordinatorClient::new(config, "test".to_string(), caps, vec![]);
+        let mut events = client.start().await.unwrap();
+
+        let message = loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ClientEvent::AuthenticationFailed { message })) => break message,
+                Ok(Some(ClientEvent::Reconnecting { .. })) => panic!("Reconnected after auth failure"),
+                Ok(Some(_)) => continue,
+                other => panic!("Auth failure never surfaced: {:?}", other),
+            }
+        };
+        assert_eq!(message, "unknown account");
+        assert!(Error::AuthenticationFailed { message }.is_fatal());
+
+        // The token was presented, and the client stopped instead of retrying
+        let token = token_rx.recv().await.unwrap().unwrap();
+        assert!(token.starts_with("AI4ALL:v1:acct:"));
+        assert!(events.recv().await.is_none());
+        assert!(token_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_redact_header() {
+        assert_eq!(redact_header("Authorization", "Bearer abc"), "[REDACTED]");
+        assert_eq!(redact_header("X-Api-Key", "k"), "[REDACTED]");
+        assert_eq!(redact_header("X-Session-Token", "t"), "[REDACTED]");
+        assert_eq!(redact_header("X-Worker-Region", "eu-west"), "eu-west");
+    }
+
+    #[tokio::test]
+    async fn test_update_capabilities_sends_envelope() {
+        let (seen_tx, mut seen_rx) = mpsc::unbounded_channel::<CapabilitiesUpdateMessage>();
+        let url = ws_stub(move |frame| match payload(&frame) {
+            Message::Register(_) => vec![text_frame(Message::RegisterAck(register_ack()))],
+            Message::CapabilitiesUpdate(update) => {
+                let _ = seen_tx.send(update);
+                vec![]
+            }
+            _ => vec![],
+        })
+        .await;
+
+        let caps = caps(vec![TaskType::TextCompletion]);
+        let config = CoordinatorClientConfig {
+            url,
+            ..Default::default()
+        };
+        let mut client = CoordinatorClient::new(config, "test".to_string(), caps.clone(), vec![]);
+        let mut events = client.start().await.unwrap();
+        loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ClientEvent::Registered { .. })) => break,
+                Ok(Some(_)) => continue,
+                other => panic!("Worker never registered: {:?}", other),
+            }
+        }
+
+        // A backend's breaker opened, withdrawing its task type
+        let updated = WorkerCapabilities {
+            supported_tasks: vec![],
+            ..caps
+        };
+        client.update_capabilities(updated.clone()).await.unwrap();
+
+        let update = tokio::time::timeout(Duration::from_secs(5), seen_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.worker_id, "worker-1");
+        assert_eq!(update.capabilities, updated);
+    }
+
+    #[tokio::test]
+    async fn test_started_client_answers_get_stats() {
+        let url = ws_stub(|frame| match payload(&frame) {
+            Message::Register(_) => vec![text_frame(Message::RegisterAck(register_ack()))],
+            _ => vec![],
+        })
+        .await;
+
+        let config = CoordinatorClientConfig {
+            url,
+            ..Default::default()
+        };
+        let caps = caps(vec![TaskType::TextCompletion]);
+        let mut client = CoordinatorClient::new(config, "test".to_string(), caps, vec![]);
+        client.set_stats_source(Arc::new(|stats| {
+            stats.running_tasks = 2;
+            stats.queued_tasks = 1;
+            stats.completed_tasks = 7;
+            stats.failed_tasks = 3;
+            stats.loaded_models = vec!["test-model".to_string()];
+            stats.peer_count = 4;
+        }));
+        let mut events = client.start().await.unwrap();
+        loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ClientEvent::Registered { .. })) => break,
+                Ok(Some(_)) => continue,
+                other => panic!("Worker never registered: {:?}", other),
+            }
+        }
+
+        let stats = tokio::time::timeout(Duration::from_secs(5), client.stats_handle().stats())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stats,
+            WorkerStats {
+                worker_id: Some("worker-1".to_string()),
+                connection_state: ConnectionState::Registered,
+                running_tasks: 2,
+                queued_tasks: 1,
+                completed_tasks: 7,
+                failed_tasks: 3,
+                loaded_models: vec!["test-model".to_string()],
+                peer_count: 4,
+                uptime_secs: stats.uptime_secs,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unacked_result_resent_until_acked() {
+        let (seen_tx, mut seen_rx) = mpsc::unbounded_channel::<TaskResultMessage>();
+
+        // Coordinator that ignores the first delivery of a result
+        let deliveries = std::sync::atomic::AtomicUsize::new(0);
+        let url = ws_stub(move |frame| match payload(&frame) {
+            Message::Register(_) => vec![text_frame(Message::RegisterAck(register_ack()))],
+            Message::TaskResult(result) => {
+                let _ = seen_tx.send(result.clone());
+                if deliveries.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                    return vec![];
+                }
+                vec![text_frame(Message::TaskResultAck(crate::protocol::TaskResultAckMessage {
+                    result_id: result.result_id.unwrap(),
+                    task_id: result.task_id,
+                }))]
+            }
+            _ => vec![],
+        })
+        .await;
+
+        let config = CoordinatorClientConfig {
+            url,
+            require_result_ack: true,
+            result_ack_timeout: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let caps = caps(vec![]);
+        let mut client = CoordinatorClient::new(config, "test".to_string(), caps, vec![]);
+        let mut events = client.start().await.unwrap();
+        loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ClientEvent::Registered { .. })) => break,
+                Ok(Some(_)) => continue,
+                other => panic!("Worker never registered: {:?}", other),
+            }
+        }
+
+        client.submit_result(TaskResultMessage {
+            task_id: "task-1".to_string(),
+            worker_id: "worker-1".to_string(),
+            success: true,
+            output: None,
+            error: None,
+            metrics: Default::default(),
+            result_id: None,
+        }).await.unwrap();
+
+        let wait = Duration::from_secs(5);
+        let first = tokio::time::timeout(wait, seen_rx.recv()).await.unwrap().unwrap();
+        let second = tokio::time::timeout(wait, seen_rx.recv()).await.unwrap().unwrap();
+        assert!(first.result_id.is_some());
+        assert_eq!(first.result_id, second.result_id);
+        assert_eq!(second.task_id, "task-1");
+
+        // Acked: no further re-sends
+        assert!(tokio::time::timeout(Duration::from_millis(500), seen_rx.recv()).await.is_err());
+        assert!(client.state.read().unacked_results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_requested_reconnect_keeps_unacked_results() {
+        let (seen_tx, mut seen_rx) = mpsc::unbounded_channel::<Message>();
+
+        // Coordinator that never acks results
+        let url = ws_stub(move |frame| {
+            let message = payload(&frame);
+            let replies = match message {
+                Message::Register(_) => vec![text_frame(Message::RegisterAck(register_ack()))],
+                _ => vec![],
+            };
+            let _ = seen_tx.send(message);
+            replies
+        })
+        .await;
+
+        let config = CoordinatorClientConfig {
+            url,
+            require_result_ack: true,
+            result_ack_timeout: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let mut client = CoordinatorClient::new(config, "test".to_string(), caps(vec![]), vec![]);
+        let mut events = client.start().await.unwrap();
+
+        // Registrations and results, in order, skipping heartbeats
+        async fn next_seen(seen_rx: &mut mpsc::UnboundedReceiver<Message>) -> Message {
+            loop {
+                let message = tokio::time::timeout(Duration::from_secs(5), seen_rx.recv())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                if matches!(message, Message::Register(_) | Message::TaskResult(_)) {
+                    return message;
+                }
+            }
+        }
+        assert!(matches!(next_seen(&mut seen_rx).await, Message::Register(_)));
+        loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ClientEvent::Registered { .. })) => break,
+                Ok(Some(_)) => continue,
+                other => panic!("Worker never registered: {:?}", other),
+            }
+        }
+
+        client.submit_result(TaskResultMessage {
+            task_id: "task-1".to_string(),
+            worker_id: "worker-1".to_string(),
+            success: true,
+            output: None,
+            error: None,
+            metrics: Default::default(),
+            result_id: None,
+        }).await.unwrap();
+        let Message::TaskResult(first) = next_seen(&mut seen_rx).await else {
+            panic!("Expected the task result");
+        };
+
+        // Re-sent on the new connection right after registering, long
+        // before the ack timeout
+        client.reconnect().await.unwrap();
+        assert!(matches!(next_seen(&mut seen_rx).await, Message::Register(_)));
+        let Message::TaskResult(resent) = next_seen(&mut seen_rx).await else {
+            panic!("Expected the task result again");
+        };
+        assert_eq!(resent.result_id, first.result_id);
+        assert_eq!(client.unacked_result_count(), 1);
+        while let Ok(event) = events.try_recv() {
+            assert!(!matches!(event, ClientEvent::ResultDeadLettered(_)), "Result was dead-lettered");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recorded_session_replays_same_events() {
+        use crate::protocol::{
+            ConfigUpdateMessage, PeerDiscoverMessage, TaskAssignmentMessage, TaskCancelMessage,
+            TaskPriority,
+        };
+        use crate::recording::{read_recording, RecordKind};
+        use crate::types::{GenerationParams, TaskInput, TextCompletionInput};
+
+        let caps = caps(vec![TaskType::TextCompletion]);
+        let frames = [
+            Message::TaskAssignment(TaskAssignmentMessage {
+                task_id: "task-1".to_string(),
+                block_id: None,
+                day_id: None,
+                priority: TaskPriority::Normal,
+                deadline: None,
+                model_id: "test-model".to_string(),
+                input: TaskInput::TextCompletion(TextCompletionInput {
+                    prompt: "Hello".to_string(),
+                    system_prompt: None,
+                    params: GenerationParams::default(),
+                }),
+                is_canary: false,
+                expected_hash: None,
+                timeout_secs: 60,
+                group_id: None,
+                stream: false,
+                required_tags: vec![],
+            }),
+            Message::PeerDiscover(PeerDiscoverMessage {
+                worker_id: "worker-2".to_string(),
+                listen_addr: "127.0.0.1:9100".to_string(),
+                capabilities: caps.clone(),
+            }),
+            Message::TaskCancel(TaskCancelMessage {
+                task_id: "task-1".to_string(),
+                reason: "superseded".to_string(),
+                force: true,
+            }),
+            Message::ConfigUpdate(ConfigUpdateMessage {
+                config: serde_json::json!({ "max_concurrent_tasks": 2 }),
+                persist: false,
+            }),
+        ];
+
+        // Coordinator that registers the worker and pushes the frames
+        let url = ws_stub(move |frame| match payload(&frame) {
+            Message::Register(_) => {
+                let ack = RegisterAckResponse {
+                    session_token: Some("very-secret-session".to_string()),
+                    ..register_ack()
+                };
+                let mut replies = vec![text_frame(Message::RegisterAck(ack))];
+                replies.extend(frames.iter().cloned().map(text_frame));
+                replies.push(WsMessage::Text(r#"{"type":"FUTURE_THING"}"#.to_string()));
+                replies
+            }
+            _ => vec![],
+        })
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = Arc::new(SessionRecorder::create(dir.path()).unwrap());
+        let config = CoordinatorClientConfig {
+            url,
+            max_reconnect_attempts: 1,
+            ..Default::default()
+        };
+        let mut client = CoordinatorClient::new(config, "test".to_string(), caps, vec![]);
+        client.set_recorder(recorder.clone());
+        let mut events = client.start().await.unwrap();
+
+        let mut live = Vec::new();
+        loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ClientEvent::Connected)) => continue,
+                Ok(Some(event)) => {
+                    let done = matches!(event, ClientEvent::ConfigUpdate(_));
+                    live.push(event);
+                    if done {
+                        break;
+                    }
+                }
+                other => panic!("Session ended early: {:?}", other),
+            }
+        }
+        client.submit_result(TaskResultMessage {
+            task_id: "task-1".to_string(),
+            worker_id: "worker-1".to_string(),
+            success: false,
+            output: None,
+            error: None,
+            metrics: Default::default(),
+            result_id: None,
+        }).await.unwrap();
+
+        // Wait for the result to land in the recording
+        let entries = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let entries = read_recording(recorder.path()).unwrap();
+                if entries.iter().any(|e| e.kind == RecordKind::Outbound) {
+                    return entries;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("result never recorded");
+
+        let raw = std::fs::read_to_string(recorder.path()).unwrap();
+        assert!(!raw.contains("very-secret-session"));
+
+        let mut replay = ReplaySession::new(false);
+        let mut replayed = Vec::new();
+        for frame in entries.iter().filter_map(|e| e.frame()) {
+            replayed.extend(replay.feed(&frame).await);
+        }
+
+        assert_eq!(live.len(), 5);
+        assert_eq!(format!("{:?}", replayed), format!("{:?}", live));
     }
 
     #[test]
@@ -782,6 +2596,29 @@ mod tests {
         assert_eq!(ConnectionState::default(), ConnectionState::Disconnected);
     }
 
+    #[test]
+    fn test_paused_status_transitions() {
+        let caps = caps(vec![]);
+        let client = CoordinatorClient::new(CoordinatorClientConfig::default(), "test".to_string(), caps, vec![]);
+        let status = || client.state.read().reported_status();
+
+        client.pause_handle().set_paused(true);
+        assert_eq!(status(), WorkerStatus::Paused);
+
+        // Busy/ready updates and a reconnect leave the pause in place
+        client.state.write().worker_status = WorkerStatus::Busy;
+        client.state.write().connection_state = ConnectionState::Connecting;
+        assert_eq!(status(), WorkerStatus::Paused);
+
+        // Draining is reported as such
+        client.state.write().worker_status = WorkerStatus::Draining;
+        assert_eq!(status(), WorkerStatus::Draining);
+
+        client.state.write().worker_status = WorkerStatus::Ready;
+        client.pause_handle().set_paused(false);
+        assert_eq!(status(), WorkerStatus::Ready);
+    }
+
     #[test]
     fn test_client_state_default() {
         let state = ClientState::default();
@@ -793,18 +2630,151 @@ mod tests {
     #[test]
     fn test_worker_capabilities() {
         let caps = WorkerCapabilities {
-            supported_tasks: vec![TaskType::TextCompletion, TaskType::Embeddings],
             max_concurrent_tasks: 4,
             available_memory_mb: 16384,
             gpu_available: true,
             gpu_device: Some("NVIDIA RTX 4090".to_string()),
             gpu_memory_mb: Some(24576),
             max_context_length: 8192,
-            worker_version: "0.1.0".to_string(),
+            ..caps(vec![TaskType::TextCompletion, TaskType::Embeddings])
         };
 
         let json = serde_json::to_string(&caps).unwrap();
         assert!(json.contains("TEXT_COMPLETION"));
         assert!(json.contains("RTX 4090"));
     }
+
+    /// How a large task result reached the mock coordinator
+    #[derive(Debug, PartialEq)]
+    struct SeenResult {
+        compressed: bool,
+        binary: bool,
+    }
+
+    /// Run a session with a coordinator that takes whatever the worker
+    /// offers, pushing a large task and waiting for a large result
+    async fn large_message_session(compression: bool, msgpack: bool) -> SeenResult {
+        use crate::protocol::{TaskAssignmentMessage, TaskPriority};
+        use crate::types::{GenerationParams, TaskInput, TextCompletionInput};
+
+        let prompt = "lorem ipsum ".repeat(4096);
+        let (seen_tx, mut seen_rx) = mpsc::unbounded_channel::<SeenResult>();
+
+        let task_prompt = prompt.clone();
+        let url = ws_stub(move |frame| {
+            let (envelope, format) = match frame {
+                WsMessage::Text(text) => (MessageEnvelope::from_json(&text).unwrap(), WireFormat::Json),
+                WsMessage::Binary(data) => (MessageEnvelope::from_msgpack_bytes(&data).unwrap(), WireFormat::Msgpack),
+                _ => return vec![],
+            };
+            let compressed = envelope.encoding.is_some();
+            match envelope.decompressed(format).unwrap().payload {
+                Message::Register(request) => {
+                    assert_eq!(request.compression.is_empty(), !compression);
+                    assert_eq!(request.wire_formats.is_empty(), !msgpack);
+                    let encoding = Encoding {
+                        format: WireFormat::SUPPORTED
+                            .iter()
+                            .copied()
+                            .find(|f| request.wire_formats.contains(f))
+                            .unwrap_or_default(),
+                        compression: Compression::negotiate(&request.compression),
+                    };
+                    let ack = Message::RegisterAck(RegisterAckResponse {
+                        compression: encoding.compression,
+                        wire_format: Some(encoding.format),
+                        ..register_ack()
+                    });
+
+                    let task = Message::TaskAssignment(TaskAssignmentMessage {
+                        task_id: "task-1".to_string(),
+                        block_id: None,
+                        day_id: None,
+                        priority: TaskPriority::Normal,
+                        deadline: None,
+                        model_id: "test-model".to_string(),
+                        input: TaskInput::TextCompletion(TextCompletionInput {
+                            prompt: task_prompt.clone(),
+                            system_prompt: None,
+                            params: GenerationParams::default(),
+                        }),
+                        is_canary: false,
+                        expected_hash: None,
+                        timeout_secs: 60,
+                        group_id: None,
+                        stream: false,
+                        required_tags: vec![],
+                    });
+                    vec![text_frame(ack), encoding.frame(&MessageEnvelope::new(task)).unwrap()]
+                }
+                Message::TaskResult(result) => {
+                    assert_eq!(result.error.unwrap().message.len(), 4096 * 12);
+                    let binary = format == WireFormat::Msgpack;
+                    let _ = seen_tx.send(SeenResult { compressed, binary });
+                    vec![]
+                }
+                _ => vec![],
+            }
+        })
+        .await;
+
+        let config = CoordinatorClientConfig {
+            url,
+            compression,
+            msgpack,
+            ..Default::default()
+        };
+        let caps = caps(vec![TaskType::TextCompletion]);
+        let mut client = CoordinatorClient::new(config, "test".to_string(), caps, vec![]);
+        let mut events = client.start().await.unwrap();
+        let task = loop {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(ClientEvent::TaskAssigned(task))) => break task,
+                Ok(Some(_)) => continue,
+                other => panic!("Task never arrived: {:?}", other),
+            }
+        };
+        match task.input {
+            TaskInput::TextCompletion(input) => assert_eq!(input.prompt, prompt),
+            other => panic!("Unexpected input: {:?}", other),
+        }
+
+        client.submit_result(TaskResultMessage {
+            task_id: task.task_id,
+            worker_id: "worker-1".to_string(),
+            success: false,
+            output: None,
+            error: Some(crate::protocol::TaskError {
+                code: "TEST".to_string(),
+                message: "x".repeat(4096 * 12),
+                retryable: false,
+                details: None,
+            }),
+            metrics: Default::default(),
+            result_id: None,
+        }).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), seen_rx.recv()).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_large_messages_compressed_when_negotiated() {
+        let seen = large_message_session(true, false).await;
+        assert_eq!(seen, SeenResult { compressed: true, binary: false });
+    }
+
+    #[tokio::test]
+    async fn test_large_messages_uncompressed_without_offer() {
+        let seen = large_message_session(false, false).await;
+        assert_eq!(seen, SeenResult { compressed: false, binary: false });
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_session_uses_binary_frames() {
+        let seen = large_message_session(false, true).await;
+        assert_eq!(seen, SeenResult { compressed: false, binary: true });
+
+        let seen = large_message_session(true, true).await;
+        assert_eq!(seen, SeenResult { compressed: true, binary: true });
+    }
 }
@@ -0,0 +1,310 @@
+//! Task result delivery
+//!
+//! Results go back to the coordinator either as a `POST /tasks/complete`
+//! (tasks picked up by HTTP polling) or over the WebSocket. Each delivery
+//! is retried a few times with exponential backoff; results that still
+//! can't be delivered wait in a bounded [`ResultBuffer`] and are re-sent
+//! once the coordinator answers again (a heartbeat ack or a task poll).
+//! Deliveries and flushes run on spawned tasks, so a slow coordinator never
+//! holds up the worker's main loop.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{debug, warn};
+
+use crate::error::{Error, Result};
+use crate::protocol::TaskResultMessage;
+
+use super::ResultHandle;
+
+/// Delivery attempts per result before it is buffered
+pub const RESULT_DELIVERY_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry, doubled for each further one
+pub const RESULT_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Undelivered results kept for re-sending; the oldest is dropped beyond this
+pub const MAX_BUFFERED_RESULTS: usize = 256;
+
+/// Run `op` up to `attempts` times, sleeping `delay`, `2 * delay`, ...
+/// between attempts. Only retryable errors are retried.
+pub async fn with_retry<T, F, Fut>(attempts: u32, delay: Duration, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut delay = delay;
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < attempts && e.is_retryable() => {
+                debug!(attempt, error = %e, "Result delivery failed, retrying");
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// A task result on its way to the coordinator
+#[derive(Debug, Clone)]
+pub enum PendingResult {
+    /// Body for `POST /tasks/complete`
+    Http { task_id: String, body: serde_json::Value },
+    /// Result for the WebSocket connection
+    WebSocket(Box<TaskResultMessage>),
+}
+
+impl PendingResult {
+    pub fn task_id(&self) -> &str {
+        match self {
+            PendingResult::Http { task_id, .. } => task_id,
+            PendingResult::WebSocket(result) => &result.task_id,
+        }
+    }
+}
+
+/// Sends task results to the coordinator over HTTP or the WebSocket
+#[derive(Clone)]
+pub struct ResultDelivery {
+    http_client: reqwest::Client,
+    /// `{coordinator}/tasks/complete`
+    complete_url: String,
+    /// Deliveries started and not yet finished, across clones
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl ResultDelivery {
+    pub fn new(http_client: reqwest::Client, http_base: &str) -> Self {
+        Self {
+            http_client,
+            complete_url: format!("{}/tasks/complete", http_base),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Deliveries still running, retries included
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Deliver `result`, retrying transient failures. WebSocket results go
+    /// through `client`.
+    pub async fn deliver(&self, client: &ResultHandle, result: &PendingResult) -> Result<()> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let outcome = with_retry(RESULT_DELIVERY_ATTEMPTS, RESULT_RETRY_DELAY, || {
+            self.deliver_once(client, result)
+        })
+        .await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        outcome
+    }
+
+    async fn deliver_once(&self, client: &ResultHandle, result: &PendingResult) -> Result<()> {
+        match result {
+            PendingResult::Http { body, .. } => {
+                let resp = self
+                    .http_client
+                    .post(&self.complete_url)
+                    .json(body)
+                    .send()
+                    .await
+                    .map_err(|e| Error::Connection(format!("Could not reach coordinator: {}", e)))?;
+                let status = resp.status();
+                if status.is_success() {
+                    Ok(())
+                } else if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    Err(Error::Connection(format!("Coordinator returned {}", status)))
+                } else {
+                    let text = resp.text().await.unwrap_or_default();
+                    Err(Error::Protocol(format!("Task result rejected ({}): {}", status, text)))
+                }
+            }
+            PendingResult::WebSocket(result) => client.submit_result((**result).clone()).await,
+        }
+    }
+}
+
+/// Results that could not be delivered, oldest first
+pub struct ResultBuffer {
+    pending: VecDeque<PendingResult>,
+    capacity: usize,
+}
+
+impl ResultBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self { pending: VecDeque::new(), capacity: capacity.max(1) }
+    }
+
+    /// Queue `result`, returning the oldest result if it had to be dropped
+    /// to make room
+    pub fn push(&mut self, result: PendingResult) -> Option<PendingResult> {
+        let dropped = if self.pending.len() >= self.capacity {
+            self.pending.pop_front()
+        } else {
+            None
+        };
+        if let Some(ref dropped) = dropped {
+            warn!(task_id = %dropped.task_id(), "Result buffer full, dropping oldest task result");
+        }
+        self.pending.push_back(result);
+        dropped
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Move the buffered results out, e.g. to flush them on another task,
+    /// leaving this buffer empty
+    pub fn take(&mut self) -> ResultBuffer {
+        ResultBuffer {
+            pending: std::mem::take(&mut self.pending),
+            capacity: self.capacity,
+        }
+    }
+
+    /// Put `earlier` (results buffered before any of ours, e.g. what a
+    /// flush left over) back in front. Returns the oldest results dropped
+    /// to stay within capacity.
+    pub fn prepend(&mut self, mut earlier: ResultBuffer) -> Vec<PendingResult> {
+        earlier.pending.append(&mut self.pending);
+        let excess = earlier.pending.len().saturating_sub(self.capacity);
+        let dropped: Vec<_> = earlier.pending.drain(..excess).collect();
+        for result in &dropped {
+            warn!(task_id = %result.task_id(), "Result buffer full, dropping oldest task result");
+        }
+        self.pending = earlier.pending;
+        dropped
+    }
+
+    /// Re-send buffered results in order with `deliver`, stopping at the
+    /// first one that fails again. Returns the delivered results.
+    pub async fn flush<F, Fut>(&mut self, mut deliver: F) -> Vec<PendingResult>
+    where
+        F: FnMut(PendingResult) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut delivered = Vec::new();
+        while let Some(result) = self.pending.front() {
+            if let Err(e) = deliver(result.clone()).await {
+                warn!(
+                    task_id = %result.task_id(),
+                    buffered = self.pending.len(),
+                    error = %e,
+                    "Buffered task result still undeliverable"
+                );
+                break;
+            }
+            delivered.extend(self.pending.pop_front());
+        }
+        delivered
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn http_result(task_id: &str) -> PendingResult {
+        PendingResult::Http {
+            task_id: task_id.to_string(),
+            body: serde_json::json!({ "taskId": task_id }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_success_or_permanent_error() {
+        let calls = AtomicU32::new(0);
+        let result = with_retry(3, Duration::from_millis(1), || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(Error::Connection("503".to_string()))
+            } else {
+                Ok(())
+            }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = with_retry(3, Duration::from_millis(1), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(Error::Protocol("400".to_string()))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_buffer_reflushes_in_order_after_recovery() {
+        let mut buffer = ResultBuffer::new(2);
+        assert!(buffer.push(http_result("a")).is_none());
+        assert!(buffer.push(http_result("b")).is_none());
+        let dropped = buffer.push(http_result("c")).unwrap();
+        assert_eq!(dropped.task_id(), "a");
+
+        // Coordinator still down: nothing is delivered or lost
+        let delivered = buffer
+            .flush(|_| async { Err(Error::Connection("refused".to_string())) })
+            .await;
+        assert!(delivered.is_empty());
+        assert_eq!(buffer.len(), 2);
+
+        // Fails again partway: the rest stays buffered
+        let delivered = buffer
+            .flush(|r| {
+                let ok = r.task_id() == "b";
+                async move {
+                    if ok {
+                        Ok(())
+                    } else {
+                        Err(Error::Connection("refused".to_string()))
+                    }
+                }
+            })
+            .await;
+        assert_eq!(delivered.iter().map(|r| r.task_id()).collect::<Vec<_>>(), ["b"]);
+        assert_eq!(buffer.len(), 1);
+
+        let delivered = buffer.flush(|_| async { Ok(()) }).await;
+        assert_eq!(delivered.iter().map(|r| r.task_id()).collect::<Vec<_>>(), ["c"]);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_flush_leftovers_go_back_in_front() {
+        let mut buffer = ResultBuffer::new(3);
+        buffer.push(http_result("a"));
+        buffer.push(http_result("b"));
+        let flushing = buffer.take();
+        assert!(buffer.is_empty());
+
+        // Results that fail while the flush runs queue behind its leftovers
+        buffer.push(http_result("c"));
+        buffer.push(http_result("d"));
+        let dropped = buffer.prepend(flushing);
+        assert_eq!(dropped.iter().map(|r| r.task_id()).collect::<Vec<_>>(), ["a"]);
+        assert_eq!(
+            buffer.pending.iter().map(|r| r.task_id()).collect::<Vec<_>>(),
+            ["b", "c", "d"]
+        );
+    }
+}
@@ -5,7 +5,16 @@
 //! - Message sending and receiving
 //! - Heartbeat management
 //! - Task lifecycle coordination
+//! - HTTP peer registration with account credentials
+//! - Task result delivery with retries and buffering
+//! - Adaptive HTTP task polling interval
 
 mod client;
+mod delivery;
+mod polling;
+mod registration;
 
 pub use client::*;
+pub use delivery::{PendingResult, ResultBuffer, ResultDelivery, MAX_BUFFERED_RESULTS};
+pub use polling::{poll_limit, release_body, PollBackoff};
+pub use registration::{PeerCredentials, PeerRegistration};
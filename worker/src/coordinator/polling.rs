@@ -0,0 +1,108 @@
+//! HTTP task polling cadence
+//!
+//! The worker polls the coordinator's `/tasks/pending` endpoint for
+//! on-demand tasks. While polls keep coming back empty the interval doubles
+//! up to a cap, so an idle worker doesn't hammer the coordinator; as soon
+//! as a poll finds work it drops back to the base interval to pick up the
+//! rest of a burst quickly. Each poll asks for as many tasks as there are
+//! free slots; tasks beyond that are handed straight back.
+
+use std::time::Duration;
+
+/// Adaptive interval between task polls
+#[derive(Debug, Clone)]
+pub struct PollBackoff {
+    base: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl PollBackoff {
+    /// Poll every `base`, backing off to at most `max` while idle
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max: max.max(base),
+            current: base,
+        }
+    }
+
+    /// Wait before the next poll
+    pub fn interval(&self) -> Duration {
+        self.current
+    }
+
+    /// Record a poll's outcome and return the wait before the next one
+    pub fn next_interval(&mut self, found_tasks: bool) -> Duration {
+        self.current = if found_tasks {
+            self.base
+        } else {
+            (self.current * 2).min(self.max)
+        };
+        self.current
+    }
+}
+
+/// How many tasks to request in a poll: the free task slots, at least one
+pub fn poll_limit(max_concurrent_tasks: usize, running: usize, queued: usize) -> usize {
+    max_concurrent_tasks.saturating_sub(running + queued).max(1)
+}
+
+/// `POST /tasks/complete` body handing back a polled task this worker
+/// won't run, so the coordinator can reassign it instead of waiting for it
+/// to time out
+pub fn release_body(worker_id: &str, task_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "workerId": worker_id,
+        "taskId": task_id,
+        "output": "",
+        "finishReason": "error",
+        "error": "Worker has no free task slot",
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_while_idle_and_resets_on_work() {
+        let secs = Duration::from_secs;
+        let mut backoff = PollBackoff::new(secs(2), secs(15));
+        assert_eq!(backoff.interval(), secs(2));
+
+        let idle: Vec<_> = (0..5).map(|_| backoff.next_interval(false)).collect();
+        assert_eq!(idle, vec![secs(4), secs(8), secs(15), secs(15), secs(15)]);
+
+        assert_eq!(backoff.next_interval(true), secs(2));
+        assert_eq!(backoff.next_interval(true), secs(2));
+        assert_eq!(backoff.next_interval(false), secs(4));
+        assert_eq!(backoff.interval(), secs(4));
+    }
+
+    #[test]
+    fn test_poll_limit_is_free_slots() {
+        assert_eq!(poll_limit(8, 3, 1), 4);
+        assert_eq!(poll_limit(8, 0, 0), 8);
+        assert_eq!(poll_limit(4, 4, 2), 1);
+    }
+
+    #[test]
+    fn test_release_body_fails_task() {
+        let body = release_body("worker-1", "task-9");
+        assert_eq!(body["workerId"], "worker-1");
+        assert_eq!(body["taskId"], "task-9");
+        assert_eq!(body["finishReason"], "error");
+        assert!(body["error"].is_string());
+    }
+
+    #[test]
+    fn test_cap_below_base_disables_backoff() {
+        let mut backoff = PollBackoff::new(Duration::from_secs(5), Duration::from_secs(1));
+        assert_eq!(backoff.next_interval(false), Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,251 @@
+//! HTTP peer registration
+//!
+//! A worker with `account_id`/`secret_key` configured registers with the
+//! coordinator's HTTP API (`POST /peers/register`), signing
+//! `AI4ALL:v1:{accountId}:{timestamp}` with its ML-DSA-65 key. The returned
+//! `workerId` is the identity used for HTTP task polling and results. The
+//! same signed message, with its signature appended, is the auth token of
+//! the WebSocket registration.
+
+use pqcrypto_dilithium::dilithium3;
+use pqcrypto_traits::sign::{DetachedSignature, SecretKey as PqSecretKey};
+use tracing::info;
+
+use crate::config::WorkerSettings;
+use crate::error::{Error, Result};
+
+/// Account credentials used to sign coordinator requests
+#[derive(Clone, PartialEq, Eq)]
+pub struct PeerCredentials {
+    pub account_id: String,
+    /// ML-DSA-65 secret key (hex)
+    pub secret_key: String,
+}
+
+impl std::fmt::Debug for PeerCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PeerCredentials")
+            .field("account_id", &self.account_id)
+            .field("secret_key", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl PeerCredentials {
+    /// Credentials from `[worker]`, if both parts are set
+    pub fn from_settings(worker: &WorkerSettings) -> Option<Self> {
+        match (&worker.account_id, &worker.secret_key) {
+            (Some(account_id), Some(secret_key)) => Some(Self {
+                account_id: account_id.clone(),
+                secret_key: secret_key.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Sign the canonical auth message for now, returning the timestamp and
+    /// hex signature
+    fn sign(&self) -> Option<(String, String)> {
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let message = format!("AI4ALL:v1:{}:{}", self.account_id, timestamp);
+        let sk_bytes = hex::decode(&self.secret_key).ok()?;
+        let sk = dilithium3::SecretKey::from_bytes(&sk_bytes).ok()?;
+        let sig = dilithium3::detached_sign(message.as_bytes(), &sk);
+        Some((timestamp, hex::encode(sig.as_bytes())))
+    }
+
+    /// Auth token for WebSocket registration: the canonical auth message
+    /// signed for now, followed by the hex signature
+    /// (`AI4ALL:v1:{accountId}:{timestamp}:{signature}`)
+    pub fn auth_token(&self) -> Option<String> {
+        let (timestamp, signature) = self.sign()?;
+        Some(format!("AI4ALL:v1:{}:{}:{}", self.account_id, timestamp, signature))
+    }
+}
+
+/// Registration of this worker as a peer with the coordinator HTTP API
+pub struct PeerRegistration {
+    http: reqwest::Client,
+    base_url: String,
+    credentials: Option<PeerCredentials>,
+}
+
+impl PeerRegistration {
+    /// Create a registration against `base_url` (the coordinator's HTTP root)
+    pub fn new(http: reqwest::Client, base_url: String, credentials: Option<PeerCredentials>) -> Self {
+        Self {
+            http,
+            base_url,
+            credentials,
+        }
+    }
+
+    /// Credentials currently in use
+    pub fn credentials(&self) -> Option<&PeerCredentials> {
+        self.credentials.as_ref()
+    }
+
+    /// Register with the current credentials, returning the coordinator's
+    /// worker ID
+    pub async fn register(
+        &self,
+        listen_addr: &str,
+        capabilities: &serde_json::Value,
+    ) -> Result<String> {
+        let credentials = self.credentials.as_ref().ok_or_else(|| Error::AuthenticationFailed {
+            message: "No account_id/secret_key configured".to_string(),
+        })?;
+        let (timestamp, signature) = credentials.sign().ok_or_else(|| Error::AuthenticationFailed {
+            message: "Failed to sign peer registration, check secret_key in config".to_string(),
+        })?;
+
+        let body = serde_json::json!({
+            "accountId": credentials.account_id,
+            "timestamp": timestamp,
+            "signature": signature,
+            "listenAddr": listen_addr,
+            "capabilities": capabilities,
+        });
+
+        let url = format!("{}/peers/register", self.base_url);
+        let resp = self
+            .http
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| Error::Connection(format!("Could not reach coordinator: {}", e)))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            return Err(Error::AuthenticationFailed {
+                message: format!("Peer registration rejected ({}): {}", status, text),
+            });
+        }
+
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| Error::Protocol(format!("Invalid peer registration response: {}", e)))?;
+        let worker_id = body["workerId"]
+            .as_str()
+            .ok_or_else(|| Error::Protocol("Peer registration response has no workerId".to_string()))?;
+
+        info!(
+            worker_id = %worker_id,
+            account_id = %credentials.account_id,
+            "Registered as peer with coordinator"
+        );
+        Ok(worker_id.to_string())
+    }
+
+    /// Adopt reloaded credentials. If they changed, registers again with the
+    /// new ones and returns the outcome; `None` means nothing changed.
+    pub async fn reload(
+        &mut self,
+        credentials: Option<PeerCredentials>,
+        listen_addr: &str,
+        capabilities: &serde_json::Value,
+    ) -> Option<Result<String>> {
+        if credentials == self.credentials {
+            return None;
+        }
+        self.credentials = credentials;
+        Some(self.register(listen_addr, capabilities).await)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{http_stub, Response};
+    use tokio::sync::mpsc;
+
+    fn credentials(account_id: &str) -> PeerCredentials {
+        let (_, sk) = dilithium3::keypair();
+        PeerCredentials {
+            account_id: account_id.to_string(),
+            secret_key: hex::encode(sk.as_bytes()),
+        }
+    }
+
+    /// Minimal `/peers/register` endpoint that reports each request body
+    async fn spawn_coordinator() -> (String, mpsc::UnboundedReceiver<serde_json::Value>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let base_url = http_stub(move |request| {
+            let body = request.json();
+            let account = body["accountId"].as_str().unwrap().to_string();
+            let _ = tx.send(body);
+            Response::json(200, format!(r#"{{"success":true,"workerId":"worker-{}"}}"#, account))
+        })
+        .await;
+        (base_url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_changed_credentials_reregister() {
+        let (base_url, mut requests) = spawn_coordinator().await;
+        let caps = serde_json::json!({ "supportedTasks": ["TEXT_COMPLETION"] });
+        let old = credentials("acct-a");
+        let mut registration = PeerRegistration::new(reqwest::Client::new(), base_url, Some(old.clone()));
+
+        assert_eq!(registration.register("127.0.0.1:9100", &caps).await.unwrap(), "worker-acct-a");
+        assert_eq!(requests.recv().await.unwrap()["accountId"], "acct-a");
+
+        // Same credentials on reload: no call
+        assert!(registration.reload(Some(old), "127.0.0.1:9100", &caps).await.is_none());
+
+        let new = credentials("acct-b");
+        let worker_id = registration
+            .reload(Some(new.clone()), "127.0.0.1:9100", &caps)
+            .await
+            .expect("changed credentials should re-register")
+            .unwrap();
+        assert_eq!(worker_id, "worker-acct-b");
+
+        let body = requests.recv().await.unwrap();
+        assert_eq!(body["accountId"], "acct-b");
+        assert_eq!(body["listenAddr"], "127.0.0.1:9100");
+        assert!(!body["signature"].as_str().unwrap().is_empty());
+        assert_eq!(registration.credentials(), Some(&new));
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_auth_token_signs_canonical_message() {
+        use pqcrypto_traits::sign::PublicKey as _;
+
+        let (pk, sk) = dilithium3::keypair();
+        let creds = PeerCredentials {
+            account_id: "acct".to_string(),
+            secret_key: hex::encode(sk.as_bytes()),
+        };
+
+        let token = creds.auth_token().unwrap();
+        let (message, signature) = token.rsplit_once(':').unwrap();
+        let timestamp = message.strip_prefix("AI4ALL:v1:acct:").unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok());
+
+        let signature = dilithium3::DetachedSignature::from_bytes(&hex::decode(signature).unwrap()).unwrap();
+        let pk = dilithium3::PublicKey::from_bytes(pk.as_bytes()).unwrap();
+        assert!(dilithium3::verify_detached_signature(&signature, message.as_bytes(), &pk).is_ok());
+
+        // An unusable key yields no token rather than a bogus one
+        let creds = PeerCredentials { secret_key: "not hex".to_string(), ..creds };
+        assert!(creds.auth_token().is_none());
+    }
+
+    #[test]
+    fn test_credentials_debug_redacts_secret() {
+        let creds = PeerCredentials {
+            account_id: "acct".to_string(),
+            secret_key: "deadbeef".to_string(),
+        };
+        assert!(!format!("{:?}", creds).contains("deadbeef"));
+    }
+}
@@ -11,8 +11,9 @@ use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
-use crate::backend::{CrawlerBackend, InferenceBackend};
+use crate::backend::{CrawlerBackend, DomainDenylist, HostRateLimiter, InferenceBackend};
 use crate::config::{CrawlerSettings, OpenAiSettings};
+use crate::sandbox::EgressPolicy;
 use crate::types::WebCrawlInput;
 
 // ─────────────────────────────────────────────────────────────────
@@ -55,11 +56,39 @@ struct DataIngestResponse {
 pub struct CrawlerService {
     crawler_config: CrawlerSettings,
     openai_config: OpenAiSettings,
+    denylist: DomainDenylist,
+    rate_limiter: Option<HostRateLimiter>,
+    egress: EgressPolicy,
 }
 
 impl CrawlerService {
     pub fn new(crawler_config: CrawlerSettings, openai_config: OpenAiSettings) -> Self {
-        Self { crawler_config, openai_config }
+        let denylist = DomainDenylist::new(&crawler_config.domain_denylist);
+        Self {
+            crawler_config,
+            openai_config,
+            denylist,
+            rate_limiter: None,
+            egress: EgressPolicy::default(),
+        }
+    }
+
+    /// Share a denylist that is reloaded at runtime
+    pub fn with_denylist(mut self, denylist: DomainDenylist) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    /// Share per-host rate limits with the crawler backend serving tasks
+    pub fn with_rate_limiter(mut self, rate_limiter: HostRateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Restrict crawled hosts to the sandbox egress allowlist
+    pub fn with_egress(mut self, egress: EgressPolicy) -> Self {
+        self.egress = egress;
+        self
     }
 
     /// Spawn a background tokio task.  Returns immediately.
@@ -80,7 +109,12 @@ impl CrawlerService {
             .build()
             .unwrap_or_default();
 
-        let backend = CrawlerBackend::new(&self.crawler_config, &self.openai_config);
+        let mut backend = CrawlerBackend::new(&self.crawler_config, &self.openai_config)
+            .with_denylist(self.denylist.clone())
+            .with_egress(self.egress.clone());
+        if let Some(rate_limiter) = &self.rate_limiter {
+            backend = backend.with_rate_limiter(rate_limiter.clone());
+        }
         let mut seen_urls: HashSet<String> = HashSet::new();
 
         let sk_bytes = match hex::decode(&secret_key) {
@@ -105,6 +139,8 @@ impl CrawlerService {
                     max_pages: self.crawler_config.max_pages,
                     generate_embeddings: self.crawler_config.generate_embeddings,
                     allowed_domains: vec![],
+                    sort_by: Default::default(),
+                    max_pages_returned: None,
                 };
 
                 let output = match backend.web_crawl(input).await {
@@ -36,6 +36,7 @@ pub enum ErrorCode {
     ConnectionRefused = 302,
     ConnectionLost = 303,
     TlsError = 304,
+    CoordinatorUnreachable = 305,
 
     // Protocol errors (4xx)
     ProtocolVersion = 400,
@@ -48,18 +49,29 @@ pub enum ErrorCode {
     ExecutionTimeout = 501,
     ExecutionCancelled = 502,
     ExecutionOom = 503,
+    ExecutionGroupNotReady = 504,
+    ExecutionTooSlow = 505,
+    ExecutionSandboxed = 506,
+    ExecutionStale = 507,
+    ExecutionShardTimeout = 508,
+    ExecutionDraining = 509,
+    ExecutionMissingTags = 510,
+    ExecutionPaused = 511,
+    ExecutionDuplicate = 512,
 
     // Model errors (6xx)
     ModelNotFound = 600,
     ModelLoadFailed = 601,
     ModelIncompatible = 602,
     ModelCorrupted = 603,
+    ModelDeclined = 604,
 
     // Resource errors (7xx)
     ResourceMemory = 700,
     ResourceGpu = 701,
     ResourceDisk = 702,
     ResourceCpu = 703,
+    ResourceQueueFull = 704,
 
     // GPU/Plugin errors (8xx)
     GpuNotFound = 810,
@@ -70,6 +82,8 @@ pub enum ErrorCode {
     PluginLoadFailed = 822,
     PluginChecksumMismatch = 823,
     PluginIncompatible = 824,
+    PluginNotTrusted = 825,
+    PluginUnverified = 826,
     VulkanError = 830,
 
     // Internal errors (9xx)
@@ -87,6 +101,9 @@ impl ErrorCode {
     /// Get the exit code for CLI (maps to 1-125 range)
     pub fn exit_code(&self) -> i32 {
         match *self as u16 {
+            // Own code so orchestrators can tell "restart me" from other
+            // connection failures
+            305 => 31,       // Gave up on reaching the coordinator
             100..=199 => 10, // Config errors
             200..=299 => 20, // IO errors
             300..=399 => 30, // Connection errors
@@ -186,6 +203,10 @@ pub enum Error {
     #[error("Lost connection to coordinator: {message}")]
     ConnectionLost { message: String },
 
+    /// No registered coordinator connection for `coordinator.exit_after_failed_secs`
+    #[error("Could not register with coordinator at {url} for {failed_secs}s")]
+    CoordinatorUnreachable { url: String, failed_secs: u64 },
+
     /// Generic connection error
     #[error("Connection error: {0}")]
     Connection(String),
@@ -225,6 +246,42 @@ pub enum Error {
     #[error("Task {task_id} timed out after {timeout_secs}s")]
     TaskTimeout { task_id: String, timeout_secs: u64 },
 
+    /// Task for a work group that hasn't assembled
+    #[error("Work group {group_id} not ready: {reason}")]
+    GroupNotReady { group_id: String, reason: String },
+
+    /// Generation fell below the configured tokens-per-second minimum
+    #[error("Generation too slow: {tokens_per_sec:.1} tokens/s, minimum is {min_tokens_per_sec} tokens/s")]
+    GenerationTooSlow { tokens_per_sec: f64, min_tokens_per_sec: f64 },
+
+    /// Task waited in the queue longer than `executor.max_queue_age_secs`
+    #[error("Task went stale in the queue: waited {waited_secs}s, maximum is {max_secs}s")]
+    StaleQueued { waited_secs: u64, max_secs: u64 },
+
+    /// Worker is draining for shutdown and takes no new tasks
+    #[error("Worker is draining for shutdown and not accepting tasks")]
+    Draining,
+
+    /// Worker is paused and takes no new tasks until resumed
+    #[error("Worker is paused and not accepting tasks")]
+    Paused,
+
+    /// Task requires worker tags this worker isn't configured with
+    #[error("Task {task_id} requires tags this worker doesn't carry: {}", .missing.join(", "))]
+    MissingTags { task_id: String, missing: Vec<String> },
+
+    /// A task with this ID is already queued or running here
+    #[error("Task {task_id} is already queued or running")]
+    DuplicateTask { task_id: String },
+
+    /// A peer shard didn't return its layers' output in time
+    #[error("Shard on peer {peer_id} in group {group_id} returned no output within {timeout_secs}s")]
+    ShardTimeout { group_id: String, peer_id: String, timeout_secs: u64 },
+
+    /// Task tried to reach a host or path outside the `[sandbox]` limits
+    #[error("Sandbox violation: {message}")]
+    SandboxViolation { message: String },
+
     /// Generic execution error
     #[error("Execution error: {0}")]
     Execution(String),
@@ -249,6 +306,14 @@ pub enum Error {
     #[error("Model {model_id} incompatible: {reason}")]
     ModelIncompatible { model_id: String, reason: String },
 
+    /// Model recently failed to run here and is refused until its cooldown ends
+    #[error("Model {model_id} declined on this worker for {retry_after_secs}s after failing to run")]
+    ModelDeclined { model_id: String, retry_after_secs: u64 },
+
+    /// Model file content isn't a recognizable model format
+    #[error("Model {model_id} is corrupted or unrecognized: {reason}")]
+    ModelCorrupted { model_id: String, reason: String },
+
     /// Generic model error
     #[error("Model error: {0}")]
     Model(String),
@@ -272,6 +337,10 @@ pub enum Error {
     #[error("Resource limit exceeded: {0}")]
     ResourceLimit(String),
 
+    /// All task slots taken and the overflow policy refused the task
+    #[error("Resource limit exceeded: task queue full ({active}/{max} tasks active)")]
+    QueueFull { active: usize, max: usize },
+
     // ─────────────────────────────────────────────────────────────
     // GPU/Plugin Errors
     // ─────────────────────────────────────────────────────────────
@@ -308,6 +377,14 @@ pub enum Error {
     #[error("Plugin {name} incompatible: {reason}")]
     PluginIncompatible { name: String, reason: String },
 
+    /// Plugin rejected by the configured trust policy
+    #[error("Plugin {name} not trusted: {reason}")]
+    PluginNotTrusted { name: String, reason: String },
+
+    /// Plugin has no checksum or signature that could be verified
+    #[error("Plugin {name} could not be verified: {reason}")]
+    PluginUnverified { name: String, reason: String },
+
     /// Vulkan error
     #[error("Vulkan error: {message}")]
     VulkanError { message: String, error_code: Option<i32> },
@@ -351,6 +428,7 @@ impl Error {
             Error::ConnectionFailed { .. } => ErrorCode::ConnectionFailed,
             Error::ConnectionTimeout { .. } => ErrorCode::ConnectionTimeout,
             Error::ConnectionLost { .. } => ErrorCode::ConnectionLost,
+            Error::CoordinatorUnreachable { .. } => ErrorCode::CoordinatorUnreachable,
             Error::Connection(_) => ErrorCode::ConnectionFailed,
 
             Error::ProtocolVersion { .. } => ErrorCode::ProtocolVersion,
@@ -360,17 +438,29 @@ impl Error {
 
             Error::ExecutionFailed { .. } => ErrorCode::ExecutionFailed,
             Error::TaskTimeout { .. } => ErrorCode::ExecutionTimeout,
+            Error::GroupNotReady { .. } => ErrorCode::ExecutionGroupNotReady,
+            Error::GenerationTooSlow { .. } => ErrorCode::ExecutionTooSlow,
+            Error::SandboxViolation { .. } => ErrorCode::ExecutionSandboxed,
+            Error::StaleQueued { .. } => ErrorCode::ExecutionStale,
+            Error::ShardTimeout { .. } => ErrorCode::ExecutionShardTimeout,
+            Error::Draining => ErrorCode::ExecutionDraining,
+            Error::Paused => ErrorCode::ExecutionPaused,
+            Error::MissingTags { .. } => ErrorCode::ExecutionMissingTags,
+            Error::DuplicateTask { .. } => ErrorCode::ExecutionDuplicate,
             Error::Execution(_) => ErrorCode::ExecutionFailed,
             Error::Timeout(_) => ErrorCode::ExecutionTimeout,
 
             Error::ModelNotFound { .. } => ErrorCode::ModelNotFound,
             Error::ModelLoadFailed { .. } => ErrorCode::ModelLoadFailed,
             Error::ModelIncompatible { .. } => ErrorCode::ModelIncompatible,
+            Error::ModelCorrupted { .. } => ErrorCode::ModelCorrupted,
+            Error::ModelDeclined { .. } => ErrorCode::ModelDeclined,
             Error::Model(_) => ErrorCode::ModelLoadFailed,
 
             Error::MemoryLimit { .. } => ErrorCode::ResourceMemory,
             Error::GpuError { .. } => ErrorCode::ResourceGpu,
             Error::ResourceLimit(_) => ErrorCode::ResourceMemory,
+            Error::QueueFull { .. } => ErrorCode::ResourceQueueFull,
 
             Error::GpuNotFound { .. } => ErrorCode::GpuNotFound,
             Error::GpuDetectionFailed { .. } => ErrorCode::GpuDetectionFailed,
@@ -380,6 +470,8 @@ impl Error {
             Error::PluginLoadFailed { .. } => ErrorCode::PluginLoadFailed,
             Error::PluginChecksumMismatch { .. } => ErrorCode::PluginChecksumMismatch,
             Error::PluginIncompatible { .. } => ErrorCode::PluginIncompatible,
+            Error::PluginNotTrusted { .. } => ErrorCode::PluginNotTrusted,
+            Error::PluginUnverified { .. } => ErrorCode::PluginUnverified,
             Error::VulkanError { .. } => ErrorCode::VulkanError,
 
             Error::NotSupported(_) => ErrorCode::NotSupported,
@@ -400,6 +492,36 @@ impl Error {
                 | Error::Io(_)
                 | Error::IoRead { .. }
                 | Error::IoWrite { .. }
+                | Error::QueueFull { .. }
+                | Error::GroupNotReady { .. }
+                | Error::GenerationTooSlow { .. }
+                | Error::Draining
+                | Error::Paused
+                | Error::MissingTags { .. }
+                | Error::ShardTimeout { .. }
+        )
+    }
+
+    /// Check if the error means the model can't run on this worker at all
+    pub fn is_model_unsupported(&self) -> bool {
+        matches!(
+            self,
+            Error::ModelNotFound { .. }
+                | Error::ModelIncompatible { .. }
+                | Error::ModelCorrupted { .. }
+        )
+    }
+
+    /// Check if the error points at the backend itself failing (as opposed
+    /// to the task, the model, or the worker's load)
+    pub fn is_backend_fault(&self) -> bool {
+        matches!(
+            self,
+            Error::ExecutionFailed { .. }
+                | Error::Execution(_)
+                | Error::ModelLoadFailed { .. }
+                | Error::GpuError { .. }
+                | Error::VulkanError { .. }
         )
     }
 
@@ -448,9 +570,12 @@ impl Error {
             Error::ConnectionLost { .. } => Some(
                 "Connection was interrupted. The worker will automatically attempt to reconnect."
             ),
+            Error::CoordinatorUnreachable { .. } => Some(
+                "The worker exited so it can be restarted. Check the coordinator URL and network, or raise 'exit_after_failed_secs'."
+            ),
 
             Error::AuthenticationFailed { .. } => Some(
-                "Verify your worker credentials. You may need to re-register with the coordinator."
+                "Verify 'worker.account_id' and 'worker.secret_key' in your config. The key must be the account's ML-DSA-65 secret key (hex) registered with the coordinator."
             ),
             Error::ProtocolVersion { .. } => Some(
                 "Your worker version may be outdated. Run 'ai4all-worker --version' and check for updates."
@@ -465,6 +590,33 @@ impl Error {
             Error::ModelIncompatible { .. } => Some(
                 "This model requires hardware capabilities your system doesn't have."
             ),
+            Error::ModelCorrupted { .. } => Some(
+                "The file is not a supported model format. Check the download or re-download it."
+            ),
+
+            Error::StaleQueued { .. } => Some(
+                "The worker is taking more work than it can start in time. Lower 'max_concurrent_tasks' upstream or raise 'executor.max_queue_age_secs'."
+            ),
+
+            Error::Draining => Some(
+                "The worker received SIGTERM and is finishing its current tasks before exiting. The coordinator should send new tasks elsewhere."
+            ),
+
+            Error::Paused => Some(
+                "The worker was paused with 'ai4all-worker pause' or by the coordinator. Run 'ai4all-worker resume' to take tasks again."
+            ),
+
+            Error::MissingTags { .. } => Some(
+                "The coordinator sent a task meant for differently tagged workers. Add the tags to 'worker.tags' if this worker should run such tasks."
+            ),
+
+            Error::ShardTimeout { .. } => Some(
+                "A peer holding part of the model stopped responding. Check its connection or raise 'peer.shard_timeout_ms'."
+            ),
+
+            Error::SandboxViolation { .. } => Some(
+                "The task needed a host or path outside the [sandbox] settings. Extend egress_allowlist or disable restrict_writes if it should be allowed."
+            ),
 
             Error::MemoryLimit { .. } => Some(
                 "Reduce 'max_memory_mb' in config or close other applications to free memory."
@@ -494,6 +646,12 @@ impl Error {
             Error::PluginChecksumMismatch { .. } => Some(
                 "The downloaded plugin is corrupted. Delete it and try again."
             ),
+            Error::PluginNotTrusted { .. } => Some(
+                "Add the plugin to 'plugins.allowed_plugins' or its vendor to 'plugins.allowed_vendors' in config."
+            ),
+            Error::PluginUnverified { .. } => Some(
+                "Check 'plugins.registry_url' and 'plugins.registry_public_key'. Only set 'plugins.verify_checksums = false' for plugins you built yourself."
+            ),
             Error::VulkanError { .. } => Some(
                 "Update your GPU drivers and ensure Vulkan is properly installed."
             ),
@@ -624,6 +782,7 @@ mod tests {
         assert_eq!(ErrorCode::ConfigNotFound.exit_code(), 10);
         assert_eq!(ErrorCode::IoRead.exit_code(), 20);
         assert_eq!(ErrorCode::ConnectionFailed.exit_code(), 30);
+        assert_eq!(ErrorCode::CoordinatorUnreachable.exit_code(), 31);
         assert_eq!(ErrorCode::ExecutionFailed.exit_code(), 50);
         assert_eq!(ErrorCode::InternalError.exit_code(), 90);
     }
@@ -655,6 +814,7 @@ mod tests {
         assert!(Error::ConnectionTimeout { url: "url".into(), timeout_secs: 30 }.is_retryable());
         assert!(!Error::config_not_found("/test").is_retryable());
         assert!(!Error::AuthenticationFailed { message: "test".into() }.is_retryable());
+        assert!(Error::QueueFull { active: 4, max: 4 }.is_retryable());
     }
 
     #[test]
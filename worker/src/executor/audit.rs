@@ -0,0 +1,256 @@
+//! Result sampling for quality audits
+//!
+//! Canaries only check the tasks the coordinator chooses to seed. With
+//! `worker.audit_sample_rate` set, a fraction of ordinary completed tasks is
+//! also appended to `audit/samples.jsonl` under the data directory, with
+//! the task input, output and their hashes, for offline review.
+//!
+//! Whether a task is sampled is derived from a hash of the task ID and
+//! `worker.audit_sample_seed`, so the same seed picks the same tasks on
+//! every run.
+//!
+//! Once the samples file reaches `worker.audit_max_file_mb` it is moved to
+//! `samples.jsonl.1`, replacing the previous one, and a new file is started.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::protocol::TaskAssignmentMessage;
+use crate::types::{TaskInput, TaskOutput};
+
+/// File samples are appended to, under the audit directory
+const SAMPLES_FILE: &str = "samples.jsonl";
+
+/// The samples file before the last rotation
+const ROTATED_FILE: &str = "samples.jsonl.1";
+
+/// One sampled task, as written to the samples file
+#[derive(Debug, Serialize)]
+pub struct AuditSample<'a> {
+    pub timestamp: DateTime<Utc>,
+    pub task_id: &'a str,
+    pub model_id: &'a str,
+    /// SHA-256 of the serialized input
+    pub input_hash: String,
+    /// SHA-256 of the serialized output
+    pub output_hash: String,
+    pub input: &'a TaskInput,
+    pub output: &'a TaskOutput,
+}
+
+/// Picks completed tasks to record for audit
+#[derive(Debug, Clone, Default)]
+pub struct AuditSampler {
+    /// Fraction of tasks sampled (0.0 = none, 1.0 = all)
+    rate: f64,
+    /// Seed mixed into the sampling hash
+    seed: u64,
+    /// Directory the samples file is written to (`None` = sampling off)
+    dir: Option<PathBuf>,
+    /// Size at which the samples file is rotated (0 = never)
+    max_file_bytes: u64,
+}
+
+impl AuditSampler {
+    /// Sample `rate` of tasks into `dir`
+    pub fn new(rate: f64, seed: u64, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            rate: rate.clamp(0.0, 1.0),
+            seed,
+            dir: Some(dir.into()),
+            max_file_bytes: 0,
+        }
+    }
+
+    /// Rotate the samples file once it reaches `mb` megabytes
+    pub fn with_max_file_mb(mut self, mb: u64) -> Self {
+        self.max_file_bytes = mb * 1024 * 1024;
+        self
+    }
+
+    /// Whether the task with `task_id` is sampled
+    pub fn is_sampled(&self, task_id: &str) -> bool {
+        if self.dir.is_none() || self.rate <= 0.0 {
+            return false;
+        }
+        if self.rate >= 1.0 {
+            return true;
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(self.seed.to_le_bytes());
+        hasher.update(task_id.as_bytes());
+        let digest = hasher.finalize();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&digest[..8]);
+        (u64::from_le_bytes(bytes) as f64 / u64::MAX as f64) < self.rate
+    }
+
+    /// Record a completed task if it is sampled. Canaries are audited by
+    /// the coordinator already and are never sampled. The file is written
+    /// on the blocking pool. Returns whether a sample was written.
+    pub async fn record(&self, assignment: &TaskAssignmentMessage, output: &TaskOutput) -> bool {
+        if assignment.is_canary || !self.is_sampled(&assignment.task_id) {
+            return false;
+        }
+        let Some(dir) = &self.dir else {
+            return false;
+        };
+
+        let sample = AuditSample {
+            timestamp: Utc::now(),
+            task_id: &assignment.task_id,
+            model_id: &assignment.model_id,
+            input_hash: json_hash(&assignment.input),
+            output_hash: json_hash(output),
+            input: &assignment.input,
+            output,
+        };
+        let written = match serde_json::to_string(&sample) {
+            Ok(line) => {
+                let (dir, max_file_bytes) = (dir.clone(), self.max_file_bytes);
+                tokio::task::spawn_blocking(move || append(&dir, &line, max_file_bytes))
+                    .await
+                    .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = written {
+            warn!(task_id = %assignment.task_id, error = %e, "Failed to record audit sample");
+            return false;
+        }
+        true
+    }
+}
+
+/// Append `line` to the samples file in `dir`, first rotating the file if
+/// the line would take it past `max_file_bytes`
+fn append(dir: &Path, line: &str, max_file_bytes: u64) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(SAMPLES_FILE);
+    if max_file_bytes > 0 {
+        let len = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if len > 0 && len + line.len() as u64 + 1 > max_file_bytes {
+            fs::rename(&path, dir.join(ROTATED_FILE))?;
+        }
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+/// Hex SHA-256 of a value's JSON form
+fn json_hash<T: Serialize>(value: &T) -> String {
+    let bytes = serde_json::to_vec(value).unwrap_or_default();
+    hex::encode(Sha256::digest(&bytes))
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FinishReason, TextCompletionOutput, TokenUsage};
+
+    fn assignment(task_id: &str) -> TaskAssignmentMessage {
+        serde_json::from_value(serde_json::json!({
+            "task_id": task_id,
+            "model_id": "test-model",
+            "input": { "task_type": "TEXT_COMPLETION", "prompt": "Hello" },
+        }))
+        .unwrap()
+    }
+
+    fn output() -> TaskOutput {
+        TaskOutput::TextCompletion(TextCompletionOutput {
+            text: "Hi".to_string(),
+            finish_reason: FinishReason::Stop,
+            usage: TokenUsage::default(),
+            generation_time_ms: 0,
+        })
+    }
+
+    fn recorded(dir: &std::path::Path) -> usize {
+        fs::read_to_string(dir.join(SAMPLES_FILE))
+            .map(|s| s.lines().count())
+            .unwrap_or(0)
+    }
+
+    #[tokio::test]
+    async fn test_sample_rate_bounds() {
+        let all_dir = tempfile::tempdir().unwrap();
+        let none_dir = tempfile::tempdir().unwrap();
+        let all = AuditSampler::new(1.0, 7, all_dir.path());
+        let none = AuditSampler::new(0.0, 7, none_dir.path());
+
+        for i in 0..50 {
+            let task = assignment(&format!("task-{}", i));
+            assert!(all.record(&task, &output()).await);
+            assert!(!none.record(&task, &output()).await);
+        }
+        assert_eq!(recorded(all_dir.path()), 50);
+        assert_eq!(recorded(none_dir.path()), 0);
+    }
+
+    #[test]
+    fn test_sampling_deterministic_per_seed() {
+        let dir = tempfile::tempdir().unwrap();
+        let picks = |seed| -> Vec<bool> {
+            let sampler = AuditSampler::new(0.5, seed, dir.path());
+            (0..200).map(|i| sampler.is_sampled(&format!("task-{}", i))).collect()
+        };
+
+        assert_eq!(picks(1), picks(1));
+        assert_ne!(picks(1), picks(2));
+        let sampled = picks(1).into_iter().filter(|&s| s).count();
+        assert!((60..140).contains(&sampled), "{} of 200 sampled at rate 0.5", sampled);
+    }
+
+    #[tokio::test]
+    async fn test_canary_never_sampled() {
+        let dir = tempfile::tempdir().unwrap();
+        let sampler = AuditSampler::new(1.0, 0, dir.path());
+        let mut task = assignment("canary-1");
+        task.is_canary = true;
+
+        assert!(!sampler.record(&task, &output()).await);
+        assert_eq!(recorded(dir.path()), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sample_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let sampler = AuditSampler::new(1.0, 0, dir.path());
+        sampler.record(&assignment("task-1"), &output()).await;
+
+        let line = fs::read_to_string(dir.path().join(SAMPLES_FILE)).unwrap();
+        let sample: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(sample["task_id"], "task-1");
+        assert_eq!(sample["output_hash"], json_hash(&output()));
+        assert_eq!(sample["input"]["prompt"], "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_samples_file_rotated_at_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sampler = AuditSampler::new(1.0, 0, dir.path());
+        sampler.record(&assignment("task-0"), &output()).await;
+        let line_len = fs::metadata(dir.path().join(SAMPLES_FILE)).unwrap().len();
+        // Room for three samples per file
+        sampler.max_file_bytes = line_len * 3 + line_len / 2;
+
+        for i in 1..5 {
+            assert!(sampler.record(&assignment(&format!("task-{}", i)), &output()).await);
+        }
+        assert_eq!(recorded(dir.path()), 2);
+        let rotated = fs::read_to_string(dir.path().join(ROTATED_FILE)).unwrap();
+        assert_eq!(rotated.lines().count(), 3);
+        assert!(rotated.contains("task-0"));
+    }
+}
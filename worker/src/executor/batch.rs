@@ -0,0 +1,153 @@
+//! Embeddings micro-batching
+//!
+//! Small embeddings tasks arriving close together each cost a backend
+//! round-trip. Within a short window (`executor.embedding_batch_window_ms`),
+//! pending requests for the same model and backend are coalesced into one
+//! backend call and the vectors are split back to each task. A zero window
+//! disables batching for latency-sensitive deployments.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::sync::{oneshot, RwLock as TokioRwLock};
+use tracing::debug;
+
+use crate::backend::InferenceBackend;
+use crate::error::{Error, Result};
+use crate::types::{EmbeddingsInput, EmbeddingsOutput, TokenUsage};
+
+type SharedBackend = Arc<TokioRwLock<Box<dyn InferenceBackend>>>;
+
+/// Batch identity: model ID + backend instance
+type BatchKey = (String, usize);
+
+/// One task's share of a batch
+struct BatchEntry {
+    texts: Vec<String>,
+    tx: oneshot::Sender<Result<EmbeddingsOutput>>,
+}
+
+/// Coalesces concurrent embeddings requests into shared backend calls
+pub struct EmbeddingBatcher {
+    window: Duration,
+    /// Open batches, flushed when their window closes
+    pending: Arc<Mutex<HashMap<BatchKey, Vec<BatchEntry>>>>,
+}
+
+impl EmbeddingBatcher {
+    /// Create a batcher collecting requests for `window` (zero = disabled)
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Embed `texts` on `backend`, sharing the call with other requests for
+    /// the same model that arrive within the window.
+    ///
+    /// Vectors are returned raw; normalization is left to the caller since
+    /// tasks in one batch may differ on it.
+    pub async fn embed(
+        &self,
+        backend: &SharedBackend,
+        model_id: &str,
+        texts: Vec<String>,
+    ) -> Result<EmbeddingsOutput> {
+        if self.window.is_zero() {
+            let guard = backend.read().await;
+            return guard.embeddings(EmbeddingsInput { texts, normalize: false }).await;
+        }
+
+        let key = (model_id.to_string(), Arc::as_ptr(backend) as *const () as usize);
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut pending = self.pending.lock();
+            let opened = !pending.contains_key(&key);
+            pending.entry(key.clone()).or_default().push(BatchEntry { texts, tx });
+
+            // The first request of a batch schedules its flush. The flush runs
+            // detached so a cancelled task can't strand the others.
+            if opened {
+                let pending = Arc::clone(&self.pending);
+                let backend = Arc::clone(backend);
+                let window = self.window;
+                tokio::spawn(async move {
+                    tokio::time::sleep(window).await;
+                    let entries = pending.lock().remove(&key).unwrap_or_default();
+                    flush(&backend, &key.0, entries).await;
+                });
+            }
+        }
+
+        rx.await.map_err(|_| {
+            Error::Internal("Embeddings batch dropped without a result".to_string())
+        })?
+    }
+}
+
+/// Run one backend call for the whole batch and split the output
+async fn flush(backend: &SharedBackend, model_id: &str, entries: Vec<BatchEntry>) {
+    let counts: Vec<usize> = entries.iter().map(|e| e.texts.len()).collect();
+    let total: usize = counts.iter().sum();
+    debug!(model = %model_id, tasks = entries.len(), texts = total, "Flushing embeddings batch");
+
+    let mut texts = Vec::with_capacity(total);
+    let mut senders = Vec::with_capacity(entries.len());
+    for entry in entries {
+        texts.extend(entry.texts);
+        senders.push(entry.tx);
+    }
+
+    let result = {
+        let guard = backend.read().await;
+        guard.embeddings(EmbeddingsInput { texts, normalize: false }).await
+    };
+
+    let output = match result {
+        Ok(output) if output.embeddings.len() == total => output,
+        Ok(output) => {
+            let message = format!(
+                "Backend returned {} embeddings for {} texts",
+                output.embeddings.len(),
+                total
+            );
+            for tx in senders {
+                let _ = tx.send(Err(batch_error(&message)));
+            }
+            return;
+        }
+        Err(e) => {
+            let message = e.to_string();
+            for tx in senders {
+                let _ = tx.send(Err(batch_error(&message)));
+            }
+            return;
+        }
+    };
+
+    let mut vectors = output.embeddings.into_iter();
+    for (tx, count) in senders.into_iter().zip(counts) {
+        let _ = tx.send(Ok(EmbeddingsOutput {
+            embeddings: vectors.by_ref().take(count).collect(),
+            dimensions: output.dimensions,
+            usage: share_usage(&output.usage, count, total),
+        }));
+    }
+}
+
+/// Failure of a shared backend call, reported to every task in the batch
+fn batch_error(message: &str) -> Error {
+    Error::ExecutionFailed {
+        task_id: None,
+        message: format!("Batched embeddings failed: {}", message),
+    }
+}
+
+/// Apportion a batch's token usage by each task's share of the texts
+fn share_usage(usage: &TokenUsage, count: usize, total: usize) -> TokenUsage {
+    let share = |tokens: u32| (tokens as u64 * count as u64 / total.max(1) as u64) as u32;
+    TokenUsage::new(share(usage.prompt_tokens), share(usage.completion_tokens))
+}
@@ -0,0 +1,156 @@
+//! Canary verification
+//!
+//! Canary tasks carry the SHA-256 of their expected answer. The produced
+//! output is reduced to a canonical form and hashed the same way:
+//!
+//! - text completions: the text with surrounding whitespace trimmed
+//! - embeddings: every value rounded to [`EMBEDDING_DECIMALS`] places and
+//!   written with that many decimals, comma-separated, one vector per line
+//!
+//! The result is reported as a [`ValidationOutput`] carrying the original
+//! output. A mismatch still counts as a successful task; `valid: false`
+//! lets the coordinator score the worker's honesty.
+
+use sha2::{Digest, Sha256};
+
+use crate::types::{TaskOutput, ValidationOutput};
+
+/// Decimal places embedding values are rounded to before hashing
+pub const EMBEDDING_DECIMALS: usize = 4;
+
+/// Canonical bytes of an answer, for the task types canaries cover
+fn canonical_answer(output: &TaskOutput) -> Option<Vec<u8>> {
+    match output {
+        TaskOutput::TextCompletion(output) => Some(output.text.trim().as_bytes().to_vec()),
+        TaskOutput::Embeddings(output) => {
+            let scale = 10f32.powi(EMBEDDING_DECIMALS as i32);
+            let lines: Vec<String> = output
+                .embeddings
+                .iter()
+                .map(|vector| {
+                    vector
+                        .iter()
+                        .map(|value| {
+                            // Adding 0.0 turns -0.0 into 0.0
+                            let rounded = (value * scale).round() / scale + 0.0;
+                            format!("{:.*}", EMBEDDING_DECIMALS, rounded)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .collect();
+            Some(lines.join("\n").into_bytes())
+        }
+        _ => None,
+    }
+}
+
+/// SHA-256 hex digest of the canonical answer
+pub fn answer_hash(output: &TaskOutput) -> Option<String> {
+    canonical_answer(output).map(|answer| hex::encode(Sha256::digest(answer)))
+}
+
+/// Check `output` against `expected_hash`, returning the validation result
+/// in its place. Outputs of task types canaries don't cover are returned
+/// unchanged.
+pub fn verify(output: TaskOutput, expected_hash: &str) -> TaskOutput {
+    let Some(answer_hash) = answer_hash(&output) else {
+        return output;
+    };
+    TaskOutput::Validation(ValidationOutput {
+        valid: answer_hash.eq_ignore_ascii_case(expected_hash.trim()),
+        answer_hash,
+        result: serde_json::to_value(&output).ok(),
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EmbeddingsOutput, FinishReason, TextCompletionOutput, TokenUsage};
+
+    fn completion(text: &str) -> TaskOutput {
+        TaskOutput::TextCompletion(TextCompletionOutput {
+            text: text.to_string(),
+            finish_reason: FinishReason::Stop,
+            usage: TokenUsage::default(),
+            generation_time_ms: 0,
+        })
+    }
+
+    fn embeddings(embeddings: Vec<Vec<f32>>) -> TaskOutput {
+        TaskOutput::Embeddings(EmbeddingsOutput {
+            dimensions: embeddings.first().map_or(0, Vec::len),
+            embeddings,
+            usage: TokenUsage::default(),
+        })
+    }
+
+    fn sha256_hex(data: &str) -> String {
+        hex::encode(Sha256::digest(data.as_bytes()))
+    }
+
+    fn validation(output: TaskOutput) -> ValidationOutput {
+        match output {
+            TaskOutput::Validation(validation) => validation,
+            other => panic!("Expected validation output, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_completion_canary() {
+        let expected = sha256_hex("Paris");
+
+        let matching = validation(verify(completion("  Paris\n"), &expected));
+        assert!(matching.valid);
+        assert_eq!(matching.answer_hash, expected);
+        assert_eq!(matching.result.unwrap()["text"], "  Paris\n");
+
+        // Hex case doesn't matter
+        assert!(validation(verify(completion("Paris"), &expected.to_uppercase())).valid);
+
+        let mismatching = validation(verify(completion("Lyon"), &expected));
+        assert!(!mismatching.valid);
+        assert_eq!(mismatching.answer_hash, sha256_hex("Lyon"));
+    }
+
+    #[test]
+    fn test_embeddings_canary() {
+        let expected = sha256_hex("0.1235,-0.5000,0.0000\n1.0000,0.0000,0.0000");
+
+        let matching = validation(verify(
+            embeddings(vec![vec![0.123_46, -0.5, -0.000_01], vec![0.999_99, 0.0, 0.0]]),
+            &expected,
+        ));
+        assert!(matching.valid, "{}", matching.answer_hash);
+        assert_eq!(matching.answer_hash, expected);
+
+        // Differences beyond the rounding precision still match
+        assert!(validation(verify(
+            embeddings(vec![vec![0.123_49, -0.500_02, 0.0], vec![1.0, 0.0, 0.000_04]]),
+            &expected,
+        ))
+        .valid);
+
+        let mismatching = validation(verify(
+            embeddings(vec![vec![0.124, -0.5, 0.0], vec![1.0, 0.0, 0.0]]),
+            &expected,
+        ));
+        assert!(!mismatching.valid);
+    }
+
+    #[test]
+    fn test_uncovered_output_unchanged() {
+        let output = TaskOutput::Validation(ValidationOutput {
+            valid: true,
+            answer_hash: "abc".to_string(),
+            result: None,
+        });
+        assert!(answer_hash(&output).is_none());
+        assert!(validation(verify(output, "def")).valid);
+    }
+}
@@ -0,0 +1,68 @@
+//! Models declined after failing to run here
+//!
+//! When a task fails because its model can't run on this worker (not found,
+//! wrong format, incompatible), the model is declined for a cooldown period:
+//! further tasks for it are refused upfront, and the set is advertised to
+//! the coordinator so it stops routing the model here.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Models refused until their cooldown expires
+pub struct DeclinedModels {
+    cooldown: Duration,
+    /// Model ID → when the decline expires
+    entries: Mutex<HashMap<String, Instant>>,
+}
+
+impl DeclinedModels {
+    /// Create an empty set; a zero `cooldown` disables declining
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cooldown applied to newly declined models
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    /// Decline `model_id` for the cooldown period. Returns false if
+    /// declining is disabled.
+    pub fn decline(&self, model_id: &str) -> bool {
+        if self.cooldown.is_zero() {
+            return false;
+        }
+        self.entries
+            .lock()
+            .insert(model_id.to_string(), Instant::now() + self.cooldown);
+        true
+    }
+
+    /// Time left on `model_id`'s decline, if it is declined
+    pub fn remaining(&self, model_id: &str) -> Option<Duration> {
+        let mut entries = self.entries.lock();
+        let until = *entries.get(model_id)?;
+        let now = Instant::now();
+        if until <= now {
+            entries.remove(model_id);
+            return None;
+        }
+        Some(until - now)
+    }
+
+    /// Currently declined model IDs, sorted
+    pub fn active(&self) -> Vec<String> {
+        let mut entries = self.entries.lock();
+        let now = Instant::now();
+        entries.retain(|_, until| *until > now);
+
+        let mut models: Vec<String> = entries.keys().cloned().collect();
+        models.sort();
+        models
+    }
+}
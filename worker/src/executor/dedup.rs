@@ -0,0 +1,115 @@
+//! In-flight task deduplication
+//!
+//! When the coordinator assigns identical work concurrently (e.g. speculative
+//! assignment), only the first task runs the backend. Later duplicates wait
+//! for its outcome and reuse it under their own task IDs.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use tokio::sync::watch;
+
+use crate::protocol::{TaskAssignmentMessage, TaskError};
+use crate::types::TaskOutput;
+
+/// Outcome shared between a leading task and its duplicates
+pub type SharedOutcome = std::result::Result<TaskOutput, TaskError>;
+
+type OutcomeRx = watch::Receiver<Option<SharedOutcome>>;
+
+/// Content hash identifying duplicate work: model + input (incl. params).
+///
+/// Returns `None` for tasks that must never be deduplicated (canaries are
+/// audited per task, so each one has to run on its own).
+pub fn content_hash(assignment: &TaskAssignmentMessage) -> Option<String> {
+    if assignment.is_canary {
+        return None;
+    }
+    let input = serde_json::to_vec(&assignment.input).ok()?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(assignment.model_id.trim().as_bytes());
+    hasher.update([0u8]);
+    hasher.update(&input);
+    Some(hex::encode(hasher.finalize()))
+}
+
+// ─────────────────────────────────────────────────────────────────
+// In-flight registry
+// ─────────────────────────────────────────────────────────────────
+
+/// Registry of currently executing task hashes
+#[derive(Default)]
+pub struct InflightTasks {
+    entries: Mutex<HashMap<String, OutcomeRx>>,
+}
+
+/// Role of a task with respect to identical in-flight work
+pub enum InflightRole {
+    /// First task with this hash: runs the backend and publishes the outcome
+    Leader(InflightGuard),
+    /// Duplicate: waits for the leader's outcome
+    Follower(OutcomeRx),
+}
+
+impl InflightTasks {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Join the in-flight set for `key`, becoming the leader if no identical
+    /// task is currently running
+    pub fn join(self: &Arc<Self>, key: String) -> InflightRole {
+        let mut entries = self.entries.lock();
+        if let Some(rx) = entries.get(&key) {
+            return InflightRole::Follower(rx.clone());
+        }
+
+        let (tx, rx) = watch::channel(None);
+        entries.insert(key.clone(), rx);
+        InflightRole::Leader(InflightGuard {
+            key,
+            tx,
+            registry: Arc::clone(self),
+        })
+    }
+}
+
+/// Held by the leading task; removes the in-flight entry when dropped.
+///
+/// Dropping without [`publish`](Self::publish) (e.g. the leader was
+/// cancelled) releases followers with no outcome so they run on their own.
+pub struct InflightGuard {
+    key: String,
+    tx: watch::Sender<Option<SharedOutcome>>,
+    registry: Arc<InflightTasks>,
+}
+
+impl InflightGuard {
+    /// Publish the leader's outcome to all duplicates
+    pub fn publish(self, outcome: SharedOutcome) {
+        let _ = self.tx.send(Some(outcome));
+    }
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.registry.entries.lock().remove(&self.key);
+    }
+}
+
+/// Wait for the leader's outcome. Returns `None` if the leader went away
+/// without publishing one.
+pub async fn await_leader(mut rx: OutcomeRx) -> Option<SharedOutcome> {
+    loop {
+        if let Some(outcome) = rx.borrow().clone() {
+            return Some(outcome);
+        }
+        if rx.changed().await.is_err() {
+            return rx.borrow().clone();
+        }
+    }
+}
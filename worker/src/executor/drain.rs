@@ -0,0 +1,133 @@
+//! Graceful drain
+//!
+//! On SIGTERM the worker stops taking tasks and lets the ones it holds
+//! finish, for up to `executor.drain_grace_secs`. It shuts down as soon as
+//! every task is done and its result delivered (and acked, where the
+//! coordinator acks results).
+//! Tasks still running at the deadline are reported as abandoned so the
+//! coordinator can reassign them.
+
+use std::time::{Duration, Instant};
+
+/// What the worker should do next while draining
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DrainStep {
+    /// Not draining; keep working
+    Idle,
+    /// Tasks or results still in flight; keep waiting
+    Waiting,
+    /// Everything finished; shut down
+    Done,
+    /// Grace period over; shut down, abandoning these tasks
+    Expired { abandoned: Vec<String> },
+}
+
+/// Drain progress, from SIGTERM to shutdown
+#[derive(Debug)]
+pub struct Drain {
+    grace: Duration,
+    /// When the grace period ends (`None` = not draining)
+    deadline: Option<Instant>,
+}
+
+impl Drain {
+    /// Drain allowing `grace` for tasks to finish
+    pub fn new(grace: Duration) -> Self {
+        Self { grace, deadline: None }
+    }
+
+    /// Start draining at `now`. Returns `false` if already draining, in
+    /// which case the original deadline stands.
+    pub fn begin(&mut self, now: Instant) -> bool {
+        if self.deadline.is_some() {
+            return false;
+        }
+        self.deadline = Some(now + self.grace);
+        true
+    }
+
+    /// Whether a drain has started
+    pub fn is_draining(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    /// Next step at `now`, given the tasks still running or queued and
+    /// whether finished results are still waiting to be delivered or acked
+    pub fn step(&self, now: Instant, in_flight: Vec<String>, results_pending: bool) -> DrainStep {
+        let Some(deadline) = self.deadline else {
+            return DrainStep::Idle;
+        };
+        if in_flight.is_empty() && !results_pending {
+            DrainStep::Done
+        } else if now >= deadline {
+            DrainStep::Expired { abandoned: in_flight }
+        } else {
+            DrainStep::Waiting
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_drain_transitions() {
+        let start = Instant::now();
+        let mut drain = Drain::new(Duration::from_secs(30));
+        assert!(!drain.is_draining());
+        assert_eq!(drain.step(start, tasks(&["task-1"]), false), DrainStep::Idle);
+
+        assert!(drain.begin(start));
+        assert!(drain.is_draining());
+        assert_eq!(drain.step(start, tasks(&["task-1"]), false), DrainStep::Waiting);
+        // Finished, but the result isn't submitted yet
+        assert_eq!(drain.step(start, vec![], true), DrainStep::Waiting);
+        assert_eq!(drain.step(start, vec![], false), DrainStep::Done);
+    }
+
+    #[test]
+    fn test_drain_deadline() {
+        let start = Instant::now();
+        let mut drain = Drain::new(Duration::from_secs(30));
+        drain.begin(start);
+
+        // A second SIGTERM doesn't extend the grace period
+        assert!(!drain.begin(start + Duration::from_secs(20)));
+
+        let deadline = start + Duration::from_secs(30);
+        assert_eq!(
+            drain.step(deadline - Duration::from_millis(1), tasks(&["task-1"]), false),
+            DrainStep::Waiting
+        );
+        assert_eq!(
+            drain.step(deadline, tasks(&["task-1", "task-2"]), false),
+            DrainStep::Expired { abandoned: tasks(&["task-1", "task-2"]) }
+        );
+        // Only finished results left at the deadline: nothing abandoned
+        assert_eq!(
+            drain.step(deadline, vec![], true),
+            DrainStep::Expired { abandoned: vec![] }
+        );
+    }
+
+    #[test]
+    fn test_zero_grace_abandons_at_once() {
+        let start = Instant::now();
+        let mut drain = Drain::new(Duration::ZERO);
+        drain.begin(start);
+        assert_eq!(
+            drain.step(start, tasks(&["task-1"]), false),
+            DrainStep::Expired { abandoned: tasks(&["task-1"]) }
+        );
+        assert_eq!(drain.step(start, vec![], false), DrainStep::Done);
+    }
+}
@@ -0,0 +1,220 @@
+//! Model loading for task execution
+//!
+//! Loads a task's model into its backend before inference. Loads are gated
+//! by a global semaphore (`resources.max_concurrent_loads`) so simultaneous
+//! tasks for different models don't thrash disk or overrun the memory
+//! budget, while inference on already-loaded models proceeds in parallel.
+//! The loader also records when each model was last used, so memory
+//! pressure relief and the memory budget can evict the least recently used
+//! one. A model with a
+//! configured download source is fetched into the model directory by the
+//! [`ModelManager`] the first time a task needs it.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+
+use tokio::sync::{RwLock as TokioRwLock, Semaphore};
+use tracing::{debug, info};
+
+use crate::backend::InferenceBackend;
+use crate::error::{Error, Result};
+use crate::models::ModelManager;
+use crate::sandbox::WriteScope;
+use crate::types::ModelSpec;
+
+/// Loads task models into backends, serializing loads
+pub struct ModelLoader {
+    /// Directory holding local model files (`None` = never load)
+    model_dir: Option<PathBuf>,
+    /// Load permits
+    permits: Semaphore,
+    /// When each local model was last needed by a task
+    last_used: Mutex<HashMap<String, Instant>>,
+    /// Fetches models in `sources` missing from `model_dir`
+    downloader: Option<ModelManager>,
+    /// Models that can be downloaded, by ID
+    sources: HashMap<String, ModelSpec>,
+}
+
+impl ModelLoader {
+    /// Create a loader allowing `max_concurrent_loads` loads at once, and
+    /// downloading the models in `sources` (within `write_scope`) when they
+    /// aren't on disk
+    pub fn new(
+        model_dir: Option<PathBuf>,
+        max_concurrent_loads: usize,
+        sources: Vec<ModelSpec>,
+        write_scope: WriteScope,
+    ) -> Self {
+        Self {
+            downloader: model_dir
+                .as_ref()
+                .map(|dir| ModelManager::new(dir).with_write_scope(write_scope)),
+            model_dir,
+            permits: Semaphore::new(max_concurrent_loads.max(1)),
+            last_used: Mutex::new(HashMap::new()),
+            sources: sources.into_iter().map(|spec| (spec.id.clone(), spec)).collect(),
+        }
+    }
+
+    /// Local model file for `model_id`, if one exists. Model IDs come from
+    /// the coordinator, so one that isn't a plain file name (API models such
+    /// as `org/model`, or a path escaping `model_dir`) never maps to a file.
+    fn model_path(&self, model_id: &str) -> Option<PathBuf> {
+        if !is_plain_model_id(model_id) {
+            return None;
+        }
+        let path = self.model_dir.as_ref()?.join(format!("{}.gguf", model_id));
+        path.is_file().then_some(path)
+    }
+
+    /// Estimated memory needed to load `model_id` (MB), taken from the size
+    /// of its local file
+    pub fn model_size_mb(&self, model_id: &str) -> Option<u64> {
+        let path = self.model_path(model_id)?;
+        let bytes = std::fs::metadata(path).ok()?.len();
+        Some(bytes / (1024 * 1024))
+    }
+
+    /// When `model_id` was last needed by a task (`None` = not since startup)
+    pub fn last_used(&self, model_id: &str) -> Option<Instant> {
+        self.last_used.lock().get(model_id).copied()
+    }
+
+    /// Local model file for `model_id`, downloading it first if it has a
+    /// configured source; `None` if there is neither file nor source
+    async fn fetch_model(&self, model_id: &str) -> Result<Option<PathBuf>> {
+        if let Some(path) = self.model_path(model_id) {
+            return Ok(Some(path));
+        }
+        match (&self.downloader, self.sources.get(model_id)) {
+            (Some(downloader), Some(spec)) => downloader.ensure(spec).await.map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Make sure `model_id` is loaded into `backend`, downloading it first
+    /// if it isn't on disk but has a configured source.
+    ///
+    /// Models without a local file (API and crawler backends, or models the
+    /// backend brings itself) are left to the backend. The load permit is
+    /// held only around the load itself, not the task or a download.
+    pub async fn ensure_loaded(
+        &self,
+        backend: &Arc<TokioRwLock<Box<dyn InferenceBackend>>>,
+        model_id: &str,
+    ) -> Result<()> {
+        let Some(path) = self.fetch_model(model_id).await? else {
+            return Ok(());
+        };
+        self.last_used.lock().insert(model_id.to_string(), Instant::now());
+
+        if backend.read().await.loaded_model_id().as_deref() == Some(model_id) {
+            return Ok(());
+        }
+
+        // Take the permit before the write lock, so tasks queued for a
+        // permit don't block inference on the backend meanwhile
+        debug!(model = %model_id, "Waiting for model load permit");
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|_| Error::Internal("Model load semaphore closed".to_string()))?;
+
+        let mut guard = backend.write().await;
+        // Another task may have loaded it while we waited
+        if guard.loaded_model_id().as_deref() == Some(model_id) {
+            return Ok(());
+        }
+
+        info!(model = %model_id, backend = guard.name(), path = %path.display(), "Loading model");
+        guard.load_model_from_path(&path).await?;
+        Ok(())
+    }
+}
+
+/// `model_id` names a single file in the model directory: no separators,
+/// and not `.` or `..`
+fn is_plain_model_id(model_id: &str) -> bool {
+    let mut components = Path::new(model_id).components();
+    !model_id.contains(['/', '\\'])
+        && matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::backend::MockBackend;
+    use crate::testing::{http_stub, Response};
+
+    /// Serve `body` to every request, counting the requests
+    async fn serve(body: &'static [u8]) -> (String, Arc<Mutex<usize>>) {
+        let requests = Arc::new(Mutex::new(0));
+        let counter = requests.clone();
+        let base_url = http_stub(move |_| {
+            *counter.lock() += 1;
+            Response::new(200).body(body)
+        })
+        .await;
+        (format!("{}/model.gguf", base_url), requests)
+    }
+
+    #[tokio::test]
+    async fn test_missing_model_downloaded_before_load() {
+        let (url, requests) = serve(b"GGUF model bytes").await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiny.gguf");
+        let loader = ModelLoader::new(
+            Some(dir.path().to_path_buf()),
+            1,
+            vec![ModelSpec::remote_gguf("tiny", &url, None, path.clone())],
+            WriteScope::default(),
+        );
+        let backend: Arc<TokioRwLock<Box<dyn InferenceBackend>>> =
+            Arc::new(TokioRwLock::new(Box::new(MockBackend::new())));
+
+        loader.ensure_loaded(&backend, "tiny").await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"GGUF model bytes");
+        assert_eq!(backend.read().await.loaded_model_id().as_deref(), Some("tiny"));
+        assert_eq!(*requests.lock(), 1);
+
+        // Already on disk: loaded without another download
+        backend.write().await.unload_model().await.unwrap();
+        loader.ensure_loaded(&backend, "tiny").await.unwrap();
+        assert_eq!(*requests.lock(), 1);
+
+        // Models without a file or a source are left to the backend
+        loader.ensure_loaded(&backend, "api-model").await.unwrap();
+        assert_eq!(*requests.lock(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_model_id_cannot_leave_model_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let model_dir = root.path().join("models");
+        std::fs::create_dir(&model_dir).unwrap();
+        std::fs::write(root.path().join("outside.gguf"), b"GGUF").unwrap();
+        std::fs::write(model_dir.join("llama-3.1-8b.gguf"), b"GGUF").unwrap();
+
+        let loader = ModelLoader::new(Some(model_dir), 1, vec![], WriteScope::default());
+        let backend: Arc<TokioRwLock<Box<dyn InferenceBackend>>> =
+            Arc::new(TokioRwLock::new(Box::new(MockBackend::new())));
+
+        for model_id in ["../outside", "sub/../../outside", "..", ".", "/tmp/outside", "a\\b", ""] {
+            assert!(loader.model_size_mb(model_id).is_none(), "{}", model_id);
+            // Left to the backend, as for a model without a file
+            loader.ensure_loaded(&backend, model_id).await.unwrap();
+        }
+        assert!(backend.read().await.loaded_model_id().is_none());
+
+        // Dots inside a name are fine
+        assert_eq!(loader.model_size_mb("llama-3.1-8b"), Some(0));
+    }
+}
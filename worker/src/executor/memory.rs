@@ -0,0 +1,99 @@
+//! Per-task memory sampling
+//!
+//! Periodically samples process (and, where available, GPU) memory while a
+//! task runs and keeps the peak for the task's result metrics.
+//!
+//! Samples are process-wide: with several tasks running concurrently each
+//! task's peak includes the others' usage.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::system::{gpu_memory_used_mb, process_memory_mb};
+
+/// Interval between memory samples
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Sentinel for "no sample taken"
+const NO_SAMPLE: u64 = u64::MAX;
+
+/// Peak memory observed during a task
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryPeak {
+    /// Peak process resident memory (MB)
+    pub memory_mb: Option<u64>,
+    /// Peak GPU memory in use (MB)
+    pub gpu_memory_mb: Option<u64>,
+}
+
+/// Background sampler tracking peak memory until stopped
+pub struct MemorySampler {
+    memory: Arc<AtomicU64>,
+    gpu_memory: Arc<AtomicU64>,
+    handle: JoinHandle<()>,
+}
+
+impl MemorySampler {
+    /// Take an initial sample and start sampling in the background
+    pub fn start() -> Self {
+        let memory = Arc::new(AtomicU64::new(NO_SAMPLE));
+        let gpu_memory = Arc::new(AtomicU64::new(NO_SAMPLE));
+        sample(&memory, &gpu_memory);
+
+        let handle = {
+            let memory = memory.clone();
+            let gpu_memory = gpu_memory.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+                loop {
+                    interval.tick().await;
+                    sample(&memory, &gpu_memory);
+                }
+            })
+        };
+
+        Self { memory, gpu_memory, handle }
+    }
+
+    /// Stop sampling and return the peak, including a final sample
+    pub fn stop(self) -> MemoryPeak {
+        self.handle.abort();
+        sample(&self.memory, &self.gpu_memory);
+        MemoryPeak {
+            memory_mb: load(&self.memory),
+            gpu_memory_mb: load(&self.gpu_memory),
+        }
+    }
+}
+
+impl Drop for MemorySampler {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+fn sample(memory: &AtomicU64, gpu_memory: &AtomicU64) {
+    if let Some(mb) = process_memory_mb() {
+        record(memory, mb);
+    }
+    if let Some(mb) = gpu_memory_used_mb() {
+        record(gpu_memory, mb);
+    }
+}
+
+fn record(peak: &AtomicU64, mb: u64) {
+    let _ = peak.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+        (current == NO_SAMPLE || mb > current).then_some(mb)
+    });
+}
+
+fn load(peak: &AtomicU64) -> Option<u64> {
+    match peak.load(Ordering::Relaxed) {
+        NO_SAMPLE => None,
+        mb => Some(mb),
+    }
+}
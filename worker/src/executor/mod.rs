@@ -6,8 +6,25 @@
 //! - Tracking execution state
 //! - Submitting results
 
+mod audit;
+mod batch;
+mod canary;
+mod declined;
+mod dedup;
+mod drain;
+mod loader;
+mod memory;
+mod postprocess;
+mod pressure;
+mod queue;
 mod runner;
+mod shard;
 mod state;
+mod throughput;
 
+pub use audit::AuditSampler;
+pub use drain::{Drain, DrainStep};
+pub use postprocess::PostProcessor;
 pub use runner::*;
+pub use shard::{ShardHop, ShardRoute};
 pub use state::*;
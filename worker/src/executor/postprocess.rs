@@ -0,0 +1,196 @@
+//! Output post-processing
+//!
+//! Light clean-up applied to text outputs after the backend returns, such as
+//! removing markdown fences around a completion or a model's chatty
+//! preamble. Steps come from `[postprocess]` and run in the configured order.
+
+use regex::Regex;
+
+use crate::config::PostprocessSettings;
+use crate::error::{Error, Result};
+use crate::types::TaskOutput;
+
+/// A single transform
+#[derive(Debug, Clone)]
+pub enum PostProcessStep {
+    /// Drop markdown code fence lines (those starting with three backticks)
+    StripCodeFences,
+    /// Remove leading and trailing whitespace
+    Trim,
+    /// Remove a match of the pattern at the start of the text
+    RemovePrefix(Regex),
+    /// Keep at most this many sentences
+    MaxSentences(usize),
+}
+
+impl PostProcessStep {
+    /// Apply the step to `text`
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            PostProcessStep::StripCodeFences => text
+                .lines()
+                .filter(|line| !line.trim_start().starts_with("```"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            PostProcessStep::Trim => text.trim().to_string(),
+            PostProcessStep::RemovePrefix(pattern) => match pattern.find(text) {
+                Some(m) if m.start() == 0 => text[m.end()..].to_string(),
+                _ => text.to_string(),
+            },
+            PostProcessStep::MaxSentences(max) => truncate_sentences(text, *max),
+        }
+    }
+}
+
+/// Ordered chain of transforms applied to text outputs
+#[derive(Debug, Clone, Default)]
+pub struct PostProcessor {
+    steps: Vec<PostProcessStep>,
+}
+
+impl PostProcessor {
+    /// Build the chain described by `[postprocess]`
+    pub fn from_settings(settings: &PostprocessSettings) -> Result<Self> {
+        let mut steps = Vec::with_capacity(settings.steps.len());
+        for name in &settings.steps {
+            let step = match name.to_lowercase().as_str() {
+                "strip_code_fences" => PostProcessStep::StripCodeFences,
+                "trim" => PostProcessStep::Trim,
+                "remove_prefix_regex" => {
+                    let pattern = Regex::new(&settings.prefix_regex).map_err(|e| {
+                        Error::Config(format!("Invalid postprocess.prefix_regex: {}", e))
+                    })?;
+                    PostProcessStep::RemovePrefix(pattern)
+                }
+                "max_sentences" => PostProcessStep::MaxSentences(settings.max_sentences as usize),
+                other => {
+                    return Err(Error::Config(format!(
+                        "Unknown postprocess step '{}'",
+                        other
+                    )))
+                }
+            };
+            steps.push(step);
+        }
+        Ok(Self { steps })
+    }
+
+    /// Whether there is nothing to apply
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Run every step over `text` in order
+    pub fn apply(&self, text: &str) -> String {
+        self.steps
+            .iter()
+            .fold(text.to_string(), |text, step| step.apply(&text))
+    }
+
+    /// Clean the text of a task output in place. Outputs that aren't free
+    /// text (embeddings, classification, ...) are left alone.
+    pub fn apply_to_output(&self, output: &mut TaskOutput) {
+        if self.is_empty() {
+            return;
+        }
+        let text = match output {
+            TaskOutput::TextCompletion(o) => &mut o.text,
+            TaskOutput::Summarization(o) => &mut o.summary,
+            TaskOutput::QuestionAnswering(o) => &mut o.answer,
+            _ => return,
+        };
+        *text = self.apply(text);
+    }
+}
+
+/// Keep the first `max` sentences. A sentence ends at `.`, `!` or `?`
+/// followed by whitespace or the end of the text.
+fn truncate_sentences(text: &str, max: usize) -> String {
+    if max == 0 {
+        return text.to_string();
+    }
+    let mut count = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if !matches!(c, '.' | '!' | '?') {
+            continue;
+        }
+        let at_boundary = chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+        if at_boundary {
+            count += 1;
+            if count == max {
+                return text[..i + c.len_utf8()].to_string();
+            }
+        }
+    }
+    text.to_string()
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(steps: &[&str]) -> PostprocessSettings {
+        PostprocessSettings {
+            steps: steps.iter().map(|s| s.to_string()).collect(),
+            prefix_regex: r"(?i)^(sure|certainly)[^\n]*\n+".to_string(),
+            max_sentences: 2,
+        }
+    }
+
+    #[test]
+    fn test_strip_code_fences() {
+        let text = "```json\n{\"a\": 1}\n```";
+        assert_eq!(PostProcessStep::StripCodeFences.apply(text), "{\"a\": 1}");
+        assert_eq!(PostProcessStep::StripCodeFences.apply("no fences"), "no fences");
+    }
+
+    #[test]
+    fn test_trim() {
+        assert_eq!(PostProcessStep::Trim.apply("  \n hello \n\n"), "hello");
+    }
+
+    #[test]
+    fn test_remove_prefix_regex() {
+        let step = PostProcessStep::RemovePrefix(Regex::new(r"^Answer:\s*").unwrap());
+        assert_eq!(step.apply("Answer: 42"), "42");
+        // Only a match at the start counts
+        assert_eq!(step.apply("The Answer: 42"), "The Answer: 42");
+    }
+
+    #[test]
+    fn test_max_sentences() {
+        let step = PostProcessStep::MaxSentences(2);
+        assert_eq!(step.apply("One. Two! Three? Four."), "One. Two!");
+        // Decimal points don't end a sentence
+        assert_eq!(step.apply("Pi is 3.14. It is irrational. Really."), "Pi is 3.14. It is irrational.");
+        assert_eq!(step.apply("Just one"), "Just one");
+    }
+
+    #[test]
+    fn test_composed_chain() {
+        let processor = PostProcessor::from_settings(&settings(&[
+            "remove_prefix_regex",
+            "strip_code_fences",
+            "trim",
+            "max_sentences",
+        ]))
+        .unwrap();
+
+        let raw = "Sure! Here is the summary you asked for:\n\n```\nRust is fast. It is safe. It is fun.\n```\n";
+        assert_eq!(processor.apply(raw), "Rust is fast. It is safe.");
+    }
+
+    #[test]
+    fn test_invalid_settings_rejected() {
+        assert!(PostProcessor::from_settings(&settings(&["shout"])).is_err());
+
+        let mut bad_regex = settings(&["remove_prefix_regex"]);
+        bad_regex.prefix_regex = "(".to_string();
+        assert!(PostProcessor::from_settings(&bad_regex).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! Memory pressure relief
+//!
+//! With `resources.memory_pressure_mb` set, memory use is checked
+//! periodically. Above the limit, models are unloaded from idle backends,
+//! least recently used first, until usage drops back under it. Models that
+//! an active task needs are never evicted. If nothing more can be evicted
+//! and usage is still over the limit, new tasks are rejected until the
+//! pressure subsides.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::RwLock as TokioRwLock;
+use tracing::{info, warn};
+
+use crate::backend::InferenceBackend;
+use crate::error::Error;
+use crate::system::HealthMonitor;
+
+use super::loader::ModelLoader;
+
+/// Source of the worker's current memory use
+pub trait MemorySource: Send + Sync {
+    /// Memory in use (MB)
+    fn memory_used_mb(&self) -> u64;
+}
+
+impl MemorySource for HealthMonitor {
+    fn memory_used_mb(&self) -> u64 {
+        self.resource_usage().memory_used_mb
+    }
+}
+
+/// Memory limit state shared between the relief check and task submission
+pub struct MemoryPressure {
+    /// Usage above which models are evicted (`None` = disabled)
+    limit_mb: Option<u64>,
+    /// Still over the limit after evicting everything possible
+    over_limit: AtomicBool,
+    /// Usage at the last check (MB)
+    last_used_mb: AtomicU64,
+}
+
+impl MemoryPressure {
+    /// Pressure state for a limit in MB (0 = disabled)
+    pub fn new(limit_mb: u64) -> Self {
+        Self {
+            limit_mb: (limit_mb > 0).then_some(limit_mb),
+            over_limit: AtomicBool::new(false),
+            last_used_mb: AtomicU64::new(0),
+        }
+    }
+
+    /// Error to reject a new task with while memory is over the limit
+    pub fn rejection(&self) -> Option<Error> {
+        let limit = self.limit_mb?;
+        self.over_limit.load(Ordering::Relaxed).then(|| {
+            Error::ResourceLimit(format!(
+                "Memory pressure: {}MB in use, limit {}MB",
+                self.last_used_mb.load(Ordering::Relaxed),
+                limit
+            ))
+        })
+    }
+
+    /// Check memory use and, if over the limit, unload least recently used
+    /// models from idle backends until it isn't. `busy` holds the models
+    /// active tasks need. Returns whether usage is still over the limit.
+    pub async fn relieve(
+        &self,
+        source: &dyn MemorySource,
+        backends: Vec<Arc<TokioRwLock<Box<dyn InferenceBackend>>>>,
+        busy: &HashSet<String>,
+        loader: &ModelLoader,
+    ) -> bool {
+        let Some(limit) = self.limit_mb else {
+            return false;
+        };
+        let mut used = source.memory_used_mb();
+
+        if used > limit {
+            // A backend locked by a task is in use and can't be evicted
+            let mut candidates: Vec<_> = backends
+                .into_iter()
+                .filter_map(|backend| {
+                    let model_id = backend.try_read().ok()?.loaded_model_id()?;
+                    (!busy.contains(&model_id)).then_some((model_id, backend))
+                })
+                .collect();
+            candidates.sort_by_key(|(model_id, _)| loader.last_used(model_id));
+
+            for (model_id, backend) in candidates {
+                let Ok(mut guard) = backend.try_write() else {
+                    continue;
+                };
+                if guard.loaded_model_id().as_deref() != Some(model_id.as_str()) {
+                    continue;
+                }
+                info!(
+                    model = %model_id,
+                    backend = guard.name(),
+                    used_mb = used,
+                    limit_mb = limit,
+                    "Evicting least recently used model under memory pressure"
+                );
+                if let Err(e) = guard.unload_model().await {
+                    warn!(model = %model_id, error = %e, "Failed to unload model");
+                    continue;
+                }
+                drop(guard);
+
+                used = source.memory_used_mb();
+                if used <= limit {
+                    break;
+                }
+            }
+        }
+
+        self.last_used_mb.store(used, Ordering::Relaxed);
+        let over = used > limit;
+        let was_over = self.over_limit.swap(over, Ordering::Relaxed);
+        if over && !was_over {
+            warn!(
+                used_mb = used,
+                limit_mb = limit,
+                "Memory still over limit with no idle models to evict, rejecting new tasks"
+            );
+        } else if !over && was_over {
+            info!(used_mb = used, limit_mb = limit, "Memory pressure subsided, accepting tasks");
+        }
+        over
+    }
+}
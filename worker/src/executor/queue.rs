@@ -0,0 +1,279 @@
+//! Priority run queue
+//!
+//! Admitted tasks take one of `max_concurrent_tasks` run slots before they
+//! start. When every slot is taken they wait in a heap ordered by
+//! [`TaskPriority`], then arrival, so a freed slot goes to the most urgent
+//! task and tasks of equal priority run in the order they arrived.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+use crate::protocol::TaskPriority;
+
+/// A task waiting for a run slot
+struct Waiter {
+    priority: TaskPriority,
+    /// Arrival order, lower first
+    seq: u64,
+    /// Receives the slot when it is handed over
+    wake: oneshot::Sender<RunSlot>,
+}
+
+impl Waiter {
+    fn key(&self) -> (TaskPriority, Reverse<u64>) {
+        (self.priority, Reverse(self.seq))
+    }
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+struct Inner {
+    /// Slots currently held
+    running: usize,
+    next_seq: u64,
+    waiting: BinaryHeap<Waiter>,
+}
+
+/// Run slots handed out highest priority first
+pub struct RunQueue {
+    slots: AtomicUsize,
+    inner: Mutex<Inner>,
+}
+
+/// A held run slot, released to the next waiter when dropped
+pub struct RunSlot {
+    queue: Arc<RunQueue>,
+}
+
+impl Drop for RunSlot {
+    fn drop(&mut self) {
+        self.queue.release();
+    }
+}
+
+impl RunQueue {
+    /// Queue with `slots` tasks running at once (at least 1)
+    pub fn new(slots: usize) -> Arc<Self> {
+        Arc::new(Self {
+            slots: AtomicUsize::new(slots.max(1)),
+            inner: Mutex::new(Inner {
+                running: 0,
+                next_seq: 0,
+                waiting: BinaryHeap::new(),
+            }),
+        })
+    }
+
+    /// Wait for a run slot. Dropping the future gives up the place in the
+    /// queue.
+    pub async fn acquire(self: &Arc<Self>, priority: TaskPriority) -> RunSlot {
+        let rx = {
+            let mut inner = self.inner.lock();
+            if inner.running < self.slots() && inner.waiting.is_empty() {
+                inner.running += 1;
+                return RunSlot { queue: self.clone() };
+            }
+            let (wake, rx) = oneshot::channel();
+            let seq = inner.next_seq;
+            inner.next_seq += 1;
+            inner.waiting.push(Waiter { priority, seq, wake });
+            rx
+        };
+        // The sender is only dropped along with the queue, which the slot
+        // itself keeps alive
+        rx.await.expect("run queue dropped with waiters")
+    }
+
+    /// Tasks that may run at once
+    pub fn slots(&self) -> usize {
+        self.slots.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Change how many tasks may run at once (at least 1). Added slots go
+    /// to waiters straight away; removed ones are retired as running tasks
+    /// finish.
+    pub fn set_slots(self: &Arc<Self>, slots: usize) {
+        self.slots.store(slots.max(1), AtomicOrdering::Relaxed);
+        let woken: Vec<Waiter> = {
+            let mut inner = self.inner.lock();
+            let mut woken = Vec::new();
+            while inner.running < self.slots() {
+                let Some(waiter) = inner.waiting.pop() else {
+                    break;
+                };
+                inner.running += 1;
+                woken.push(waiter);
+            }
+            woken
+        };
+        // Sent outside the lock: a slot refused by a waiter that gave up is
+        // released again
+        for waiter in woken {
+            let _ = waiter.wake.send(RunSlot { queue: self.clone() });
+        }
+    }
+
+    /// Hand a freed slot to the next waiter, or return it to the pool
+    fn release(self: &Arc<Self>) {
+        let waiter = {
+            let mut inner = self.inner.lock();
+            if inner.running > self.slots() {
+                inner.running -= 1;
+                return;
+            }
+            match inner.waiting.pop() {
+                Some(waiter) => waiter,
+                None => {
+                    inner.running -= 1;
+                    return;
+                }
+            }
+        };
+        // A waiter that gave up hands the slot straight back by dropping it,
+        // which moves on to the next one
+        let _ = waiter.wake.send(RunSlot { queue: self.clone() });
+    }
+
+    /// Tasks waiting for a slot, by priority
+    pub fn queued_by_priority(&self) -> BTreeMap<TaskPriority, usize> {
+        let inner = self.inner.lock();
+        let mut counts = BTreeMap::new();
+        for waiter in inner.waiting.iter().filter(|w| !w.wake.is_closed()) {
+            *counts.entry(waiter.priority).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_priority_then_arrival_order() {
+        let queue = RunQueue::new(1);
+        let held = queue.acquire(TaskPriority::Normal).await;
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        let waiters = [
+            ("low-1", TaskPriority::Low),
+            ("high-1", TaskPriority::High),
+            ("low-2", TaskPriority::Low),
+            ("high-2", TaskPriority::High),
+        ];
+        let mut handles = Vec::new();
+        for (queued, (name, priority)) in waiters.into_iter().enumerate() {
+            let waiting = queue.clone();
+            let order_tx = order_tx.clone();
+            handles.push(tokio::spawn(async move {
+                let _slot = waiting.acquire(priority).await;
+                order_tx.send(name).unwrap();
+            }));
+            // Let the waiter enqueue before the next arrives
+            while queue.queued_by_priority().values().sum::<usize>() <= queued {
+                tokio::task::yield_now().await;
+            }
+        }
+        assert_eq!(
+            queue.queued_by_priority(),
+            BTreeMap::from([(TaskPriority::Low, 2), (TaskPriority::High, 2)])
+        );
+
+        drop(held);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        drop(order_tx);
+        let mut order = Vec::new();
+        while let Some(name) = order_rx.recv().await {
+            order.push(name);
+        }
+        assert_eq!(order, ["high-1", "high-2", "low-1", "low-2"]);
+        assert!(queue.queued_by_priority().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resize_slots() {
+        let queue = RunQueue::new(1);
+        let first = queue.acquire(TaskPriority::Normal).await;
+        let second = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(TaskPriority::Normal).await }
+        });
+        while queue.queued_by_priority().is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        // A new slot goes to the waiter at once
+        queue.set_slots(2);
+        let second = second.await.unwrap();
+
+        // Shrinking retires the next freed slot instead of handing it on
+        queue.set_slots(1);
+        let third = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(TaskPriority::Normal).await }
+        });
+        while queue.queued_by_priority().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        drop(first);
+        tokio::task::yield_now().await;
+        assert!(!third.is_finished());
+        drop(second);
+        drop(third.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waiter_passes_slot_on() {
+        let queue = RunQueue::new(1);
+        let held = queue.acquire(TaskPriority::Normal).await;
+
+        let abandoned = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(TaskPriority::Critical).await }
+        });
+        while queue.queued_by_priority().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        abandoned.abort();
+        let _ = abandoned.await;
+        assert!(queue.queued_by_priority().is_empty());
+
+        let next = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(TaskPriority::Low).await }
+        });
+        drop(held);
+        drop(next.await.unwrap());
+        // Every slot is back in the pool
+        let _again = queue.acquire(TaskPriority::Low).await;
+    }
+}
@@ -0,0 +1,94 @@
+//! Split-layer inference
+//!
+//! A text completion assigned to a model shard group runs through every
+//! shard in layer order: this worker's own shard on its backend, the others
+//! on their owners over the peer mesh. The prompt enters the first shard and
+//! the local backend turns the last shard's activations into the completion,
+//! sampled with the task's parameters. A cancelled task stops before its
+//! next shard.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use async_trait::async_trait;
+
+use crate::backend::{InferenceBackend, LayerOutput};
+use crate::error::{Error, Result};
+use crate::types::{TextCompletionInput, TextCompletionOutput};
+
+/// Where one shard of a split model runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardHop {
+    /// On this worker's backend
+    Local,
+    /// On the group member with this worker ID
+    Peer(String),
+}
+
+/// Finds and reaches the shards of a task's group
+#[async_trait]
+pub trait ShardRoute: Send + Sync {
+    /// The shards of `group_id` in layer order, or `None` if it is not a
+    /// model shard group this worker belongs to. Fails while a shard has
+    /// no owner.
+    fn shard_hops(&self, group_id: &str) -> Result<Option<Vec<ShardHop>>>;
+
+    /// Run activations through the shard on `peer_id`
+    async fn forward(
+        &self,
+        peer_id: &str,
+        group_id: &str,
+        task_id: &str,
+        layer_start: u32,
+        tensor_data: Vec<u8>,
+    ) -> Result<LayerOutput>;
+}
+
+/// Run `input` through every shard in `hops`, failing if any shard fails or
+/// goes silent, or `stop` is set. [`Error::NotSupported`] means a shard's
+/// backend can't run split layers.
+pub(crate) async fn run_split(
+    route: &dyn ShardRoute,
+    hops: &[ShardHop],
+    group_id: &str,
+    task_id: &str,
+    backend: &dyn InferenceBackend,
+    input: &TextCompletionInput,
+    stop: &AtomicBool,
+) -> Result<TextCompletionOutput> {
+    if !hops.contains(&ShardHop::Local) {
+        return Err(Error::GroupNotReady {
+            group_id: group_id.to_string(),
+            reason: "this worker holds none of the group's shards".to_string(),
+        });
+    }
+
+    let started = Instant::now();
+    let mut activations = LayerOutput {
+        layer_end: 0,
+        tensor_data: input.prompt.clone().into_bytes(),
+    };
+    for hop in hops {
+        if stop.load(Ordering::SeqCst) {
+            return Err(Error::ExecutionFailed {
+                task_id: Some(task_id.to_string()),
+                message: "cancelled between shards".to_string(),
+            });
+        }
+        let LayerOutput { layer_end, tensor_data } = activations;
+        activations = match hop {
+            ShardHop::Local => backend.forward_layers(layer_end, tensor_data).await?,
+            ShardHop::Peer(peer_id) => {
+                route
+                    .forward(peer_id, group_id, task_id, layer_end, tensor_data)
+                    .await?
+            }
+        };
+    }
+
+    let mut output = backend
+        .decode_layers(activations.tensor_data, &input.params)
+        .await?;
+    output.generation_time_ms = started.elapsed().as_millis() as u64;
+    Ok(output)
+}
@@ -4,21 +4,26 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
-use tokio::sync::oneshot;
+use serde::Serialize;
+use tokio::sync::{oneshot, Notify};
 
+use crate::error::{Error, Result};
 use crate::protocol::{TaskAssignmentMessage, TaskMetrics, TaskPriority};
 use crate::types::TaskType;
 
+use super::memory::MemoryPeak;
+
 // ─────────────────────────────────────────────────────────────────
 // Task Execution State
 // ─────────────────────────────────────────────────────────────────
 
 /// Where a task originated from
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum TaskSource {
     /// Task assigned by the central coordinator (WebSocket)
     Coordinator,
@@ -35,7 +40,8 @@ impl Default for TaskSource {
 }
 
 /// State of a task being executed
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TaskState {
     /// Task is queued waiting for execution
     Queued,
@@ -55,6 +61,35 @@ impl Default for TaskState {
     }
 }
 
+/// How a cancellation request should stop a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelMode {
+    /// Cooperative stop: let the current token/step finish, then stop
+    Graceful,
+    /// Abort the backend call immediately
+    Forced,
+}
+
+impl CancelMode {
+    /// Map the protocol `force` flag to a cancel mode
+    pub fn from_force(force: bool) -> Self {
+        if force {
+            CancelMode::Forced
+        } else {
+            CancelMode::Graceful
+        }
+    }
+}
+
+impl std::fmt::Display for CancelMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CancelMode::Graceful => write!(f, "graceful"),
+            CancelMode::Forced => write!(f, "forced"),
+        }
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Active Task
 // ─────────────────────────────────────────────────────────────────
@@ -78,7 +113,10 @@ pub struct ActiveTask {
     pub completed_at: Option<Instant>,
 
     /// Cancellation signal sender
-    pub cancel_tx: Option<oneshot::Sender<()>>,
+    pub cancel_tx: Option<oneshot::Sender<CancelMode>>,
+
+    /// Cancellation requested but not yet observed by the running task
+    pub cancel_requested: Option<CancelMode>,
 
     /// Error message if failed
     pub error: Option<String>,
@@ -88,6 +126,13 @@ pub struct ActiveTask {
 
     /// Where this task originated from
     pub source: TaskSource,
+
+    /// Peak memory sampled during execution (detailed metrics only)
+    pub memory_peak: MemoryPeak,
+
+    /// Queued behind a crawl or per-type limit; such tasks don't take a
+    /// task slot until they get past it
+    pub waiting_on_limit: bool,
 }
 
 impl ActiveTask {
@@ -100,9 +145,12 @@ impl ActiveTask {
             started_at: None,
             completed_at: None,
             cancel_tx: None,
+            cancel_requested: None,
             error: None,
             tokens_processed: 0,
             source: TaskSource::Coordinator,
+            memory_peak: MemoryPeak::default(),
+            waiting_on_limit: false,
         }
     }
 
@@ -121,6 +169,16 @@ impl ActiveTask {
         self.assignment.priority
     }
 
+    /// Queued or running, i.e. not finished
+    pub fn is_active(&self) -> bool {
+        self.state == TaskState::Running || self.state == TaskState::Queued
+    }
+
+    /// Active and taking a task slot
+    fn holds_slot(&self) -> bool {
+        self.is_active() && !self.waiting_on_limit
+    }
+
     /// Mark the task as running
     pub fn mark_running(&mut self) {
         self.state = TaskState::Running;
@@ -181,8 +239,8 @@ impl ActiveTask {
                 None
             },
             tokens_per_second: self.calculate_tokens_per_second(),
-            peak_memory_mb: None, // TODO: Track memory usage
-            peak_gpu_memory_mb: None,
+            peak_memory_mb: self.memory_peak.memory_mb,
+            peak_gpu_memory_mb: self.memory_peak.gpu_memory_mb,
         }
     }
 
@@ -198,6 +256,39 @@ impl ActiveTask {
     }
 }
 
+/// Snapshot of a live task, for operator tooling
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskDetails {
+    /// Task ID
+    pub task_id: String,
+    /// Task type
+    pub task_type: TaskType,
+    /// Model requested by the task
+    pub model_id: String,
+    /// Queued or running
+    pub state: TaskState,
+    /// Where the task came from
+    pub source: TaskSource,
+    /// Task priority
+    pub priority: TaskPriority,
+    /// Time since the task was received (ms)
+    pub elapsed_ms: u64,
+}
+
+impl From<&ActiveTask> for TaskDetails {
+    fn from(task: &ActiveTask) -> Self {
+        Self {
+            task_id: task.task_id().to_string(),
+            task_type: task.task_type(),
+            model_id: task.assignment.model_id.clone(),
+            state: task.state,
+            source: task.source.clone(),
+            priority: task.priority(),
+            elapsed_ms: task.total_time_ms(),
+        }
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Task Tracker
 // ─────────────────────────────────────────────────────────────────
@@ -208,13 +299,16 @@ pub struct TaskTracker {
     tasks: RwLock<HashMap<String, ActiveTask>>,
 
     /// Maximum concurrent tasks
-    max_concurrent: usize,
+    max_concurrent: RwLock<usize>,
 
     /// Completed task count (since startup)
     completed_count: RwLock<u64>,
 
     /// Failed task count (since startup)
     failed_count: RwLock<u64>,
+
+    /// Signalled whenever a task leaves the running/queued set
+    slot_freed: Notify,
 }
 
 impl TaskTracker {
@@ -222,47 +316,112 @@ impl TaskTracker {
     pub fn new(max_concurrent: usize) -> Self {
         Self {
             tasks: RwLock::new(HashMap::new()),
-            max_concurrent,
+            max_concurrent: RwLock::new(max_concurrent),
             completed_count: RwLock::new(0),
             failed_count: RwLock::new(0),
+            slot_freed: Notify::new(),
         }
     }
 
-    /// Add a new task
-    pub fn add_task(&self, assignment: TaskAssignmentMessage) -> bool {
+    /// Add a new task. Fails with [`Error::DuplicateTask`] if a task with
+    /// its ID is already queued or running, or [`Error::QueueFull`] if no
+    /// slot is free.
+    pub fn add_task(&self, assignment: TaskAssignmentMessage) -> Result<()> {
         let mut tasks = self.tasks.write();
 
-        // Check if we can accept more tasks
-        let running_count = tasks.values()
-            .filter(|t| t.state == TaskState::Running || t.state == TaskState::Queued)
-            .count();
+        if tasks.get(&assignment.task_id).is_some_and(ActiveTask::is_active) {
+            return Err(Error::DuplicateTask { task_id: assignment.task_id });
+        }
 
-        if running_count >= self.max_concurrent {
-            return false;
+        // Check if we can accept more tasks
+        let running_count = tasks.values().filter(|t| t.holds_slot()).count();
+        let max = self.max_concurrent();
+        if running_count >= max {
+            return Err(Error::QueueFull { active: running_count, max });
         }
 
         let task_id = assignment.task_id.clone();
         tasks.insert(task_id, ActiveTask::new(assignment));
-        true
+        Ok(())
+    }
+
+    /// Add a task that first waits on a crawl or per-type limit. It doesn't
+    /// count against `max_concurrent` until [`Self::stop_waiting`], so tasks
+    /// stuck behind one limit don't keep other tasks out. Fails with
+    /// [`Error::DuplicateTask`] like [`Self::add_task`].
+    pub fn add_waiting_task(&self, assignment: TaskAssignmentMessage) -> Result<()> {
+        let mut tasks = self.tasks.write();
+
+        if tasks.get(&assignment.task_id).is_some_and(ActiveTask::is_active) {
+            return Err(Error::DuplicateTask { task_id: assignment.task_id });
+        }
+
+        let task_id = assignment.task_id.clone();
+        let mut task = ActiveTask::new(assignment);
+        task.waiting_on_limit = true;
+        tasks.insert(task_id, task);
+        Ok(())
     }
 
-    /// Mark a task as running
+    /// A waiting task got past its limit and takes a task slot from now
+    /// on. This can leave more than `max_concurrent` tasks counted for a
+    /// while, as lowering the limit does.
+    pub fn stop_waiting(&self, task_id: &str) {
+        if let Some(task) = self.tasks.write().get_mut(task_id) {
+            task.waiting_on_limit = false;
+        }
+    }
+
+    /// Whether a task with this ID is queued or running
+    pub fn is_active(&self, task_id: &str) -> bool {
+        self.tasks.read().get(task_id).is_some_and(ActiveTask::is_active)
+    }
+
+    /// Attach the cancellation signal sender for a task
+    pub fn set_cancel_handle(&self, task_id: &str, cancel_tx: oneshot::Sender<CancelMode>) {
+        if let Some(task) = self.tasks.write().get_mut(task_id) {
+            task.cancel_tx = Some(cancel_tx);
+        }
+    }
+
+    /// Record the peak memory sampled while a task ran
+    pub fn record_memory_peak(&self, task_id: &str, peak: MemoryPeak) {
+        if let Some(task) = self.tasks.write().get_mut(task_id) {
+            task.memory_peak = peak;
+        }
+    }
+
+    /// Mark a task as running (only valid for queued tasks)
     pub fn mark_running(&self, task_id: &str) -> bool {
         let mut tasks = self.tasks.write();
-        if let Some(task) = tasks.get_mut(task_id) {
-            task.mark_running();
-            true
-        } else {
-            false
+        match tasks.get_mut(task_id) {
+            Some(task) if task.state == TaskState::Queued => {
+                task.mark_running();
+                true
+            }
+            _ => false,
         }
     }
 
+    /// How long a still-queued task has waited since it was received
+    pub fn queued_for(&self, task_id: &str) -> Option<Duration> {
+        self.tasks
+            .read()
+            .get(task_id)
+            .filter(|t| t.state == TaskState::Queued)
+            .map(|t| t.received_at.elapsed())
+    }
+
     /// Mark a task as completed
     pub fn mark_completed(&self, task_id: &str) {
         let mut tasks = self.tasks.write();
         if let Some(task) = tasks.get_mut(task_id) {
+            if task.state == TaskState::Cancelled {
+                return;
+            }
             task.mark_completed();
             *self.completed_count.write() += 1;
+            self.slot_freed.notify_waiters();
         }
     }
 
@@ -270,21 +429,45 @@ impl TaskTracker {
     pub fn mark_failed(&self, task_id: &str, error: String) {
         let mut tasks = self.tasks.write();
         if let Some(task) = tasks.get_mut(task_id) {
+            if task.state == TaskState::Cancelled {
+                return;
+            }
             task.mark_failed(error);
             *self.failed_count.write() += 1;
+            self.slot_freed.notify_waiters();
+        }
+    }
+
+    /// Mark a task as cancelled (once its execution has stopped)
+    pub fn mark_cancelled(&self, task_id: &str) {
+        let mut tasks = self.tasks.write();
+        if let Some(task) = tasks.get_mut(task_id) {
+            if task.state != TaskState::Cancelled {
+                task.mark_cancelled();
+                self.slot_freed.notify_waiters();
+            }
         }
     }
 
     /// Cancel a task
-    pub fn cancel_task(&self, task_id: &str) -> bool {
+    ///
+    /// Forced cancellation frees the task slot immediately; graceful
+    /// cancellation leaves the task running until it reaches a safe stop
+    /// point and the executor marks it cancelled.
+    pub fn cancel_task(&self, task_id: &str, mode: CancelMode) -> bool {
         let mut tasks = self.tasks.write();
         if let Some(task) = tasks.get_mut(task_id) {
             if task.state == TaskState::Running || task.state == TaskState::Queued {
+                task.cancel_requested = Some(mode);
                 // Send cancellation signal if available
-                if let Some(tx) = task.cancel_tx.take() {
-                    let _ = tx.send(());
+                let signalled = match task.cancel_tx.take() {
+                    Some(tx) => tx.send(mode).is_ok(),
+                    None => false,
+                };
+                if mode == CancelMode::Forced || !signalled {
+                    task.mark_cancelled();
+                    self.slot_freed.notify_waiters();
                 }
-                task.mark_cancelled();
                 return true;
             }
         }
@@ -305,6 +488,16 @@ impl TaskTracker {
             .collect()
     }
 
+    /// Details of running and queued tasks, oldest first
+    pub fn task_details(&self) -> Vec<TaskDetails> {
+        let tasks = self.tasks.read();
+        let mut live: Vec<&ActiveTask> = tasks.values()
+            .filter(|t| t.state == TaskState::Running || t.state == TaskState::Queued)
+            .collect();
+        live.sort_by_key(|t| t.received_at);
+        live.into_iter().map(TaskDetails::from).collect()
+    }
+
     /// Get count of running tasks
     pub fn running_count(&self) -> usize {
         self.tasks.read()
@@ -324,10 +517,60 @@ impl TaskTracker {
     /// Check if we can accept more tasks
     pub fn can_accept(&self) -> bool {
         let tasks = self.tasks.read();
-        let active = tasks.values()
+        let active = tasks.values().filter(|t| t.holds_slot()).count();
+        active < self.max_concurrent()
+    }
+
+    /// Maximum number of running/queued tasks
+    pub fn max_concurrent(&self) -> usize {
+        *self.max_concurrent.read()
+    }
+
+    /// Change the maximum number of running/queued tasks. Tasks already
+    /// over a lowered limit are left to finish.
+    pub fn set_max_concurrent(&self, max_concurrent: usize) {
+        *self.max_concurrent.write() = max_concurrent;
+        self.slot_freed.notify_waiters();
+    }
+
+    /// Wait until a task slot is free and take it for a task added with
+    /// [`Self::add_waiting_task`]
+    pub async fn claim_slot(&self, task_id: &str) {
+        loop {
+            let notified = self.slot_freed.notified();
+            tokio::pin!(notified);
+            // Register before checking so a slot freed in between isn't missed
+            notified.as_mut().enable();
+            if self.try_claim_slot(task_id) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Take a free task slot for a waiting task, checking and claiming
+    /// under one lock so two waiters can't take the same slot
+    fn try_claim_slot(&self, task_id: &str) -> bool {
+        let mut tasks = self.tasks.write();
+        let counted = tasks.values().filter(|t| t.holds_slot()).count();
+        if counted >= self.max_concurrent() {
+            return false;
+        }
+        if let Some(task) = tasks.get_mut(task_id) {
+            task.waiting_on_limit = false;
+        }
+        true
+    }
+
+    /// Oldest active task with a priority below `priority` that has no
+    /// cancellation pending
+    pub fn oldest_below_priority(&self, priority: TaskPriority) -> Option<String> {
+        self.tasks.read()
+            .values()
             .filter(|t| t.state == TaskState::Running || t.state == TaskState::Queued)
-            .count();
-        active < self.max_concurrent
+            .filter(|t| t.cancel_requested.is_none() && t.priority() < priority)
+            .min_by_key(|t| t.received_at)
+            .map(|t| t.task_id().to_string())
     }
 
     /// Get total completed count
@@ -391,6 +634,9 @@ mod tests {
             is_canary: false,
             expected_hash: None,
             timeout_secs: 60,
+            group_id: None,
+            stream: false,
+            required_tags: vec![],
         }
     }
 
@@ -415,17 +661,69 @@ mod tests {
     fn test_task_tracker_add() {
         let tracker = TaskTracker::new(2);
 
-        assert!(tracker.add_task(make_test_assignment("task-1")));
-        assert!(tracker.add_task(make_test_assignment("task-2")));
+        assert!(tracker.add_task(make_test_assignment("task-1")).is_ok());
+        // A task already queued or running isn't added twice
+        assert!(matches!(
+            tracker.add_task(make_test_assignment("task-1")),
+            Err(Error::DuplicateTask { .. })
+        ));
+        assert!(tracker.add_task(make_test_assignment("task-2")).is_ok());
         // Should reject third task (max concurrent = 2)
-        assert!(!tracker.add_task(make_test_assignment("task-3")));
+        assert!(matches!(
+            tracker.add_task(make_test_assignment("task-3")),
+            Err(Error::QueueFull { active: 2, max: 2 })
+        ));
+        assert!(tracker.is_active("task-1"));
+
+        // A finished task's ID can be assigned again
+        tracker.mark_completed("task-1");
+        assert!(!tracker.is_active("task-1"));
+        assert!(tracker.add_task(make_test_assignment("task-1")).is_ok());
+    }
+
+    #[test]
+    fn test_waiting_tasks_hold_no_slot() {
+        let tracker = TaskTracker::new(1);
+
+        tracker.add_waiting_task(make_test_assignment("crawl-1")).unwrap();
+        assert!(tracker.can_accept());
+        assert!(matches!(
+            tracker.add_waiting_task(make_test_assignment("crawl-1")),
+            Err(Error::DuplicateTask { .. })
+        ));
+        tracker.add_task(make_test_assignment("task-1")).unwrap();
+        assert!(!tracker.can_accept());
+        assert_eq!(tracker.queued_count(), 2);
+
+        // Past its limit it counts again
+        tracker.mark_completed("task-1");
+        tracker.stop_waiting("crawl-1");
+        assert!(!tracker.can_accept());
+    }
+
+    #[tokio::test]
+    async fn test_claim_slot_waits_for_a_free_one() {
+        let tracker = Arc::new(TaskTracker::new(1));
+        tracker.add_task(make_test_assignment("task-1")).unwrap();
+        tracker.add_waiting_task(make_test_assignment("task-2")).unwrap();
+
+        let claim = tokio::spawn({
+            let tracker = tracker.clone();
+            async move { tracker.claim_slot("task-2").await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!claim.is_finished());
+
+        tracker.mark_completed("task-1");
+        tokio::time::timeout(Duration::from_secs(1), claim).await.unwrap().unwrap();
+        assert!(!tracker.can_accept());
     }
 
     #[test]
     fn test_task_tracker_lifecycle() {
         let tracker = TaskTracker::new(4);
 
-        tracker.add_task(make_test_assignment("task-1"));
+        tracker.add_task(make_test_assignment("task-1")).unwrap();
         assert_eq!(tracker.queued_count(), 1);
         assert_eq!(tracker.running_count(), 0);
 
@@ -441,18 +739,38 @@ mod tests {
     #[test]
     fn test_task_tracker_cancel() {
         let tracker = TaskTracker::new(4);
-        tracker.add_task(make_test_assignment("task-1"));
+        tracker.add_task(make_test_assignment("task-1")).unwrap();
+        tracker.mark_running("task-1");
+
+        assert!(tracker.cancel_task("task-1", CancelMode::Forced));
+        assert_eq!(tracker.running_count(), 0);
+    }
+
+    #[test]
+    fn test_task_tracker_graceful_cancel_keeps_running() {
+        let tracker = TaskTracker::new(4);
+        tracker.add_task(make_test_assignment("task-1")).unwrap();
+        let (tx, mut rx) = oneshot::channel();
+        tracker.set_cancel_handle("task-1", tx);
         tracker.mark_running("task-1");
 
-        assert!(tracker.cancel_task("task-1"));
+        assert!(tracker.cancel_task("task-1", CancelMode::Graceful));
+        assert_eq!(rx.try_recv().unwrap(), CancelMode::Graceful);
+        // Still running until the executor reaches a safe stop point
+        assert_eq!(tracker.running_count(), 1);
+        assert_eq!(tracker.tasks.read()["task-1"].cancel_requested, Some(CancelMode::Graceful));
+
+        tracker.mark_cancelled("task-1");
+        tracker.mark_completed("task-1");
         assert_eq!(tracker.running_count(), 0);
+        assert_eq!(tracker.total_completed(), 0);
     }
 
     #[test]
     fn test_active_task_ids() {
         let tracker = TaskTracker::new(4);
-        tracker.add_task(make_test_assignment("task-1"));
-        tracker.add_task(make_test_assignment("task-2"));
+        tracker.add_task(make_test_assignment("task-1")).unwrap();
+        tracker.add_task(make_test_assignment("task-2")).unwrap();
         tracker.mark_running("task-1");
 
         let ids = tracker.active_task_ids();
@@ -0,0 +1,217 @@
+//! Generation speed floor
+//!
+//! With `resources.min_tokens_per_sec` set, streaming generation is measured
+//! over consecutive windows. Measurement starts at the first generated token,
+//! so prompt processing never counts against the rate. A window that ends
+//! below the floor stops generation, and the task fails with
+//! [`Error::GenerationTooSlow`] so the coordinator can send it elsewhere.
+//! [`RateMonitor::watch`] also checks the rate between tokens, so a
+//! generation that stalls outright is stopped too.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::error::Error;
+
+/// Minimum generation rate, sustained over `window`
+#[derive(Debug, Clone, Copy)]
+pub struct ThroughputFloor {
+    pub min_tokens_per_sec: f64,
+    pub window: Duration,
+}
+
+impl ThroughputFloor {
+    /// Floor from config values; `None` if disabled (rate of 0)
+    pub fn new(min_tokens_per_sec: f64, window: Duration) -> Option<Self> {
+        (min_tokens_per_sec > 0.0 && !window.is_zero()).then_some(Self {
+            min_tokens_per_sec,
+            window,
+        })
+    }
+
+    /// The error to fail a generation with that produced `tokens` in
+    /// `elapsed` without streaming them (e.g. across a shard group), if
+    /// that was below the floor. One shorter than `window` always passes.
+    pub fn check(&self, tokens: u32, elapsed: Duration) -> Option<Error> {
+        if elapsed < self.window {
+            return None;
+        }
+        let rate = tokens as f64 / elapsed.as_secs_f64();
+        (rate < self.min_tokens_per_sec).then_some(Error::GenerationTooSlow {
+            tokens_per_sec: rate,
+            min_tokens_per_sec: self.min_tokens_per_sec,
+        })
+    }
+}
+
+/// Rate measurement for one generation
+pub struct RateMonitor {
+    floor: ThroughputFloor,
+    state: Mutex<WindowState>,
+}
+
+#[derive(Default)]
+struct WindowState {
+    /// Start of the current window (`None` until the first token)
+    start: Option<Instant>,
+    /// Tokens generated since `start`
+    tokens: u32,
+    /// Rate of the window that fell below the floor
+    breach: Option<f64>,
+}
+
+impl RateMonitor {
+    pub fn new(floor: ThroughputFloor) -> Self {
+        Self {
+            floor,
+            state: Mutex::new(WindowState::default()),
+        }
+    }
+
+    /// Count a generated token. Returns false once a full window ran below
+    /// the floor, meaning generation should stop.
+    pub fn on_token(&self) -> bool {
+        self.measure(true)
+    }
+
+    /// Check the rate without a new token. Returns false once a full window
+    /// ran below the floor.
+    pub fn check(&self) -> bool {
+        self.measure(false)
+    }
+
+    /// Run `generation`, checking the rate every so often between tokens,
+    /// and drop it with the breach error once the floor is missed
+    pub async fn watch<T>(&self, generation: impl Future<Output = T>) -> Result<T, Error> {
+        tokio::pin!(generation);
+        let mut ticks = tokio::time::interval(self.floor.window.min(Duration::from_secs(1)));
+        loop {
+            tokio::select! {
+                output = &mut generation => return Ok(output),
+                _ = ticks.tick() => {
+                    if !self.check() {
+                        if let Some(e) = self.breach() {
+                            return Err(e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Close the current window if it has run its length, counting a new
+    /// token first if `token`
+    fn measure(&self, token: bool) -> bool {
+        let now = Instant::now();
+        let mut state = self.state.lock();
+        if state.breach.is_some() {
+            return false;
+        }
+        let Some(start) = state.start else {
+            if token {
+                state.start = Some(now);
+            }
+            return true;
+        };
+
+        if token {
+            state.tokens += 1;
+        }
+        let elapsed = now - start;
+        if elapsed < self.floor.window {
+            return true;
+        }
+
+        let rate = state.tokens as f64 / elapsed.as_secs_f64();
+        if rate < self.floor.min_tokens_per_sec {
+            state.breach = Some(rate);
+            return false;
+        }
+        state.start = Some(now);
+        state.tokens = 0;
+        true
+    }
+
+    /// The error to fail the task with, if generation was stopped for
+    /// being too slow
+    pub fn breach(&self) -> Option<Error> {
+        self.state.lock().breach.map(|rate| Error::GenerationTooSlow {
+            tokens_per_sec: rate,
+            min_tokens_per_sec: self.floor.min_tokens_per_sec,
+        })
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_floor() {
+        assert!(ThroughputFloor::new(0.0, Duration::from_secs(5)).is_none());
+        assert!(ThroughputFloor::new(5.0, Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_check_whole_generation() {
+        let floor = ThroughputFloor::new(10.0, Duration::from_secs(2)).unwrap();
+        // Too short to judge
+        assert!(floor.check(1, Duration::from_secs(1)).is_none());
+        assert!(floor.check(40, Duration::from_secs(3)).is_none());
+        assert!(matches!(
+            floor.check(20, Duration::from_secs(4)),
+            Some(Error::GenerationTooSlow { tokens_per_sec, .. }) if tokens_per_sec == 5.0
+        ));
+    }
+
+    #[test]
+    fn test_first_token_delay_not_counted() {
+        let floor = ThroughputFloor::new(100.0, Duration::from_millis(20)).unwrap();
+        let monitor = RateMonitor::new(floor);
+
+        // Long prompt processing before the first token is fine
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(monitor.on_token());
+        assert!(monitor.breach().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stalled_generation_stopped() {
+        let floor = ThroughputFloor::new(100.0, Duration::from_millis(50)).unwrap();
+        let monitor = RateMonitor::new(floor);
+
+        // Not judged before the first token
+        let quick = monitor.watch(tokio::time::sleep(Duration::from_millis(120))).await;
+        assert!(quick.is_ok());
+
+        // One token, then nothing
+        let stalled = async {
+            assert!(monitor.on_token());
+            std::future::pending::<()>().await
+        };
+        let result = tokio::time::timeout(Duration::from_secs(5), monitor.watch(stalled))
+            .await
+            .expect("stalled generation was not stopped");
+        assert!(matches!(result, Err(Error::GenerationTooSlow { .. })));
+        assert!(!monitor.on_token());
+    }
+
+    #[test]
+    fn test_slow_window_breaches() {
+        let floor = ThroughputFloor::new(100.0, Duration::from_millis(20)).unwrap();
+        let monitor = RateMonitor::new(floor);
+
+        assert!(monitor.on_token());
+        std::thread::sleep(Duration::from_millis(30));
+        // One token in 30 ms is ~33 tokens/s
+        assert!(!monitor.on_token());
+        assert!(!monitor.on_token());
+        assert!(matches!(monitor.breach(), Some(Error::GenerationTooSlow { .. })));
+    }
+}
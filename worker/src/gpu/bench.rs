@@ -0,0 +1,476 @@
+//! GPU throughput measurement over Vulkan compute
+//!
+//! Two short measurements feed the benchmark's GPU phase:
+//! - Arithmetic throughput: the kernel in `shaders/fma.comp`, where every
+//!   invocation runs a dependent chain of fused multiply-adds. It ships
+//!   precompiled as `shaders/fma.spv`, so no shader compiler is needed at
+//!   build time.
+//! - VRAM bandwidth: `vkCmdCopyBuffer` between two device-local buffers.
+//!
+//! Every Vulkan object is owned by [`ComputeContext`] and released on drop,
+//! including on early error returns. Handles are only ever passed back to
+//! the device that created them, which is what the `unsafe` calls rely on.
+
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use ash::vk;
+use tracing::debug;
+
+use super::GpuInfo;
+use crate::error::{Error, Result};
+
+/// Compiled `shaders/fma.comp`
+const FMA_SHADER: &[u8] = include_bytes!("shaders/fma.spv");
+
+/// Fused multiply-adds per kernel invocation (matches `fma.comp`)
+const FMA_CHAIN: u32 = 256;
+
+/// Workgroup size of the kernel (matches `fma.comp`)
+const LOCAL_SIZE: u32 = 64;
+
+/// Workgroups per dispatch
+const WORKGROUPS: u32 = 4096;
+
+/// Largest buffer used for the bandwidth copy
+const MAX_COPY_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Smallest buffer used for the bandwidth copy
+const MIN_COPY_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Raw throughput measured on one GPU
+#[derive(Debug, Clone, Copy)]
+pub struct GpuThroughput {
+    /// Single-precision FMA throughput (2 FLOPs per FMA)
+    pub gflops: f64,
+    /// Device-local copy bandwidth, counting both read and write
+    pub vram_bandwidth_gbps: f64,
+}
+
+/// Measure `gpu`, scaling the amount of work with `iterations`
+pub fn measure_throughput(gpu: &GpuInfo, iterations: u32) -> Result<GpuThroughput> {
+    let iterations = iterations.max(1);
+    let mut ctx = ComputeContext::new(gpu)?;
+
+    // Arithmetic throughput
+    ctx.create_fma_pipeline()?;
+    let output_bytes = (LOCAL_SIZE * WORKGROUPS) as u64 * 4;
+    let output = ctx.create_buffer(output_bytes, vk::BufferUsageFlags::STORAGE_BUFFER)?;
+    ctx.bind_output(output, output_bytes);
+
+    ctx.submit(|ctx, cb| ctx.record_dispatches(cb, 1))?; // warm-up
+    let elapsed = ctx.submit(|ctx, cb| ctx.record_dispatches(cb, iterations))?;
+    let flops = 2.0 * FMA_CHAIN as f64 * output_bytes as f64 / 4.0 * iterations as f64;
+    let gflops = flops / elapsed.as_secs_f64() / 1e9;
+
+    // VRAM bandwidth
+    let copy_bytes = (gpu.total_memory_mb * 1024 * 1024 / 8).clamp(MIN_COPY_BYTES, MAX_COPY_BYTES);
+    let src = ctx.create_buffer(copy_bytes, vk::BufferUsageFlags::TRANSFER_SRC)?;
+    let dst = ctx.create_buffer(copy_bytes, vk::BufferUsageFlags::TRANSFER_DST)?;
+
+    ctx.submit(|ctx, cb| ctx.record_copies(cb, src, dst, copy_bytes, 1))?; // warm-up
+    let elapsed = ctx.submit(|ctx, cb| ctx.record_copies(cb, src, dst, copy_bytes, iterations))?;
+    let vram_bandwidth_gbps = 2.0 * copy_bytes as f64 * iterations as f64 / elapsed.as_secs_f64() / 1e9;
+
+    debug!(gpu = %gpu.name, gflops, vram_bandwidth_gbps, "GPU throughput measured");
+    Ok(GpuThroughput { gflops, vram_bandwidth_gbps })
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Vulkan Context
+// ─────────────────────────────────────────────────────────────────
+
+/// Device, queue and every object the measurements create
+struct ComputeContext {
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    device: ash::Device,
+    device_id: u32,
+    queue: vk::Queue,
+    memory_props: vk::PhysicalDeviceMemoryProperties,
+    command_pool: vk::CommandPool,
+    command_buffer: vk::CommandBuffer,
+    fence: vk::Fence,
+    buffers: Vec<(vk::Buffer, vk::DeviceMemory)>,
+    shader: vk::ShaderModule,
+    set_layout: vk::DescriptorSetLayout,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+}
+
+impl ComputeContext {
+    fn new(gpu: &GpuInfo) -> Result<Self> {
+        let err = |what: &str, e: vk::Result| Error::GpuError {
+            message: format!("{}: {:?}", what, e),
+            device_id: Some(gpu.id),
+        };
+
+        // SAFETY: loads the system Vulkan loader; nothing else is loaded
+        // into the process through it
+        let entry = unsafe { ash::Entry::load() }.map_err(|e| Error::GpuError {
+            message: format!("Failed to load Vulkan: {}", e),
+            device_id: Some(gpu.id),
+        })?;
+
+        // The compiled kernel uses the storage buffer storage class, core
+        // since Vulkan 1.1
+        let app_info = vk::ApplicationInfo::builder()
+            .application_name(c"AI4All Worker")
+            .engine_name(c"AI4All")
+            .api_version(vk::API_VERSION_1_1);
+        let create_info = vk::InstanceCreateInfo::builder().application_info(&app_info);
+        // SAFETY: `create_info` and everything it points to outlive the call
+        let instance = unsafe { entry.create_instance(&create_info, None) }
+            .map_err(|e| err("Failed to create Vulkan instance", e))?;
+
+        let (physical, device, queue_family) = match open_device(&instance, gpu) {
+            Ok(opened) => opened,
+            Err(e) => {
+                // SAFETY: nothing has been created from the instance yet
+                unsafe { instance.destroy_instance(None) };
+                return Err(e);
+            }
+        };
+
+        // SAFETY: `physical` came from this instance and `queue_family` was
+        // requested when the device was created
+        let (memory_props, queue) = unsafe {
+            (
+                instance.get_physical_device_memory_properties(physical),
+                device.get_device_queue(queue_family, 0),
+            )
+        };
+
+        // From here on Drop cleans up whatever has been created
+        let mut ctx = Self {
+            memory_props,
+            queue,
+            _entry: entry,
+            instance,
+            device,
+            device_id: gpu.id,
+            command_pool: vk::CommandPool::null(),
+            command_buffer: vk::CommandBuffer::null(),
+            fence: vk::Fence::null(),
+            buffers: Vec::new(),
+            shader: vk::ShaderModule::null(),
+            set_layout: vk::DescriptorSetLayout::null(),
+            descriptor_pool: vk::DescriptorPool::null(),
+            descriptor_set: vk::DescriptorSet::null(),
+            pipeline_layout: vk::PipelineLayout::null(),
+            pipeline: vk::Pipeline::null(),
+        };
+
+        let pool_info = vk::CommandPoolCreateInfo::builder()
+            .queue_family_index(queue_family)
+            .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER);
+        ctx.command_pool = unsafe { ctx.device.create_command_pool(&pool_info, None) }
+            .map_err(|e| err("Failed to create command pool", e))?;
+
+        let alloc_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(ctx.command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+        ctx.command_buffer = unsafe { ctx.device.allocate_command_buffers(&alloc_info) }
+            .map_err(|e| err("Failed to allocate command buffer", e))?[0];
+
+        ctx.fence = unsafe { ctx.device.create_fence(&vk::FenceCreateInfo::default(), None) }
+            .map_err(|e| err("Failed to create fence", e))?;
+
+        Ok(ctx)
+    }
+
+    fn error(&self, what: &str, e: vk::Result) -> Error {
+        Error::GpuError {
+            message: format!("{}: {:?}", what, e),
+            device_id: Some(self.device_id),
+        }
+    }
+
+    /// Allocate a device-local buffer
+    fn create_buffer(&mut self, size: u64, usage: vk::BufferUsageFlags) -> Result<vk::Buffer> {
+        let info = vk::BufferCreateInfo::builder()
+            .size(size)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer = unsafe { self.device.create_buffer(&info, None) }
+            .map_err(|e| self.error("Failed to create buffer", e))?;
+        self.buffers.push((buffer, vk::DeviceMemory::null()));
+
+        let requirements = unsafe { self.device.get_buffer_memory_requirements(buffer) };
+        let memory_type = (0..self.memory_props.memory_type_count)
+            .find(|&i| {
+                requirements.memory_type_bits & (1 << i) != 0
+                    && self.memory_props.memory_types[i as usize]
+                        .property_flags
+                        .contains(vk::MemoryPropertyFlags::DEVICE_LOCAL)
+            })
+            .ok_or_else(|| Error::GpuError {
+                message: "No device-local memory type for benchmark buffer".to_string(),
+                device_id: Some(self.device_id),
+            })?;
+
+        let alloc_info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(requirements.size)
+            .memory_type_index(memory_type);
+        let memory = unsafe { self.device.allocate_memory(&alloc_info, None) }
+            .map_err(|e| self.error("Failed to allocate device memory", e))?;
+        if let Some(last) = self.buffers.last_mut() {
+            last.1 = memory;
+        }
+
+        // SAFETY: `memory` is a fresh allocation of a type the buffer accepts,
+        // at least as large as the buffer requires
+        unsafe { self.device.bind_buffer_memory(buffer, memory, 0) }
+            .map_err(|e| self.error("Failed to bind buffer memory", e))?;
+        Ok(buffer)
+    }
+
+    /// Build the FMA kernel and its single storage-buffer binding
+    fn create_fma_pipeline(&mut self) -> Result<()> {
+        let code = ash::util::read_spv(&mut Cursor::new(FMA_SHADER)).map_err(|e| Error::GpuError {
+            message: format!("Invalid benchmark shader: {}", e),
+            device_id: Some(self.device_id),
+        })?;
+        // SAFETY: `code` is the SPIR-V compiled from `fma.comp`
+        self.shader = unsafe {
+            self.device
+                .create_shader_module(&vk::ShaderModuleCreateInfo::builder().code(&code), None)
+        }
+        .map_err(|e| self.error("Failed to create shader module", e))?;
+
+        let bindings = [vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .build()];
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::builder().bindings(&bindings);
+        self.set_layout = unsafe { self.device.create_descriptor_set_layout(&layout_info, None) }
+            .map_err(|e| self.error("Failed to create descriptor set layout", e))?;
+
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_BUFFER,
+            descriptor_count: 1,
+        }];
+        let pool_info = vk::DescriptorPoolCreateInfo::builder().max_sets(1).pool_sizes(&pool_sizes);
+        self.descriptor_pool = unsafe { self.device.create_descriptor_pool(&pool_info, None) }
+            .map_err(|e| self.error("Failed to create descriptor pool", e))?;
+
+        let set_layouts = [self.set_layout];
+        let set_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(self.descriptor_pool)
+            .set_layouts(&set_layouts);
+        self.descriptor_set = unsafe { self.device.allocate_descriptor_sets(&set_info) }
+            .map_err(|e| self.error("Failed to allocate descriptor set", e))?[0];
+
+        let pipeline_layout_info = vk::PipelineLayoutCreateInfo::builder().set_layouts(&set_layouts);
+        self.pipeline_layout =
+            unsafe { self.device.create_pipeline_layout(&pipeline_layout_info, None) }
+                .map_err(|e| self.error("Failed to create pipeline layout", e))?;
+
+        let stage = vk::PipelineShaderStageCreateInfo::builder()
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .module(self.shader)
+            .name(c"main");
+        let pipeline_info = vk::ComputePipelineCreateInfo::builder()
+            .stage(*stage)
+            .layout(self.pipeline_layout);
+        // SAFETY: `stage` outlives the call; the layout matches the shader's
+        // one binding
+        self.pipeline = unsafe {
+            self.device
+                .create_compute_pipelines(vk::PipelineCache::null(), &[*pipeline_info], None)
+        }
+        .map_err(|(_, e)| self.error("Failed to create compute pipeline", e))?[0];
+        Ok(())
+    }
+
+    /// Point the kernel's output binding at `buffer`
+    fn bind_output(&self, buffer: vk::Buffer, size: u64) {
+        let buffer_info = [vk::DescriptorBufferInfo { buffer, offset: 0, range: size }];
+        let write = vk::WriteDescriptorSet::builder()
+            .dst_set(self.descriptor_set)
+            .dst_binding(0)
+            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+            .buffer_info(&buffer_info);
+        // SAFETY: the set is not in use by any command buffer yet
+        unsafe { self.device.update_descriptor_sets(&[*write], &[]) };
+    }
+
+    fn record_dispatches(&self, cb: vk::CommandBuffer, count: u32) {
+        let sets = [self.descriptor_set];
+        // SAFETY (this and the other `cmd_*` calls): `cb` is recording, see
+        // `submit`
+        unsafe {
+            self.device.cmd_bind_pipeline(cb, vk::PipelineBindPoint::COMPUTE, self.pipeline);
+            self.device.cmd_bind_descriptor_sets(
+                cb,
+                vk::PipelineBindPoint::COMPUTE,
+                self.pipeline_layout,
+                0,
+                &sets,
+                &[],
+            );
+        }
+        for i in 0..count {
+            if i > 0 {
+                self.barrier(cb, vk::PipelineStageFlags::COMPUTE_SHADER, vk::AccessFlags::SHADER_WRITE);
+            }
+            unsafe { self.device.cmd_dispatch(cb, WORKGROUPS, 1, 1) };
+        }
+    }
+
+    fn record_copies(
+        &self,
+        cb: vk::CommandBuffer,
+        src: vk::Buffer,
+        dst: vk::Buffer,
+        size: u64,
+        count: u32,
+    ) {
+        let region = [vk::BufferCopy { src_offset: 0, dst_offset: 0, size }];
+        for i in 0..count {
+            if i > 0 {
+                self.barrier(cb, vk::PipelineStageFlags::TRANSFER, vk::AccessFlags::TRANSFER_WRITE);
+            }
+            unsafe { self.device.cmd_copy_buffer(cb, src, dst, &region) };
+        }
+    }
+
+    /// Order consecutive writes to the same buffer
+    fn barrier(&self, cb: vk::CommandBuffer, stage: vk::PipelineStageFlags, access: vk::AccessFlags) {
+        let barrier = vk::MemoryBarrier::builder().src_access_mask(access).dst_access_mask(access);
+        unsafe {
+            self.device.cmd_pipeline_barrier(
+                cb,
+                stage,
+                stage,
+                vk::DependencyFlags::empty(),
+                &[*barrier],
+                &[],
+                &[],
+            )
+        };
+    }
+
+    /// Record with `record`, submit, and wait; returns the time on the GPU
+    /// queue as seen from the host
+    fn submit(&self, record: impl FnOnce(&Self, vk::CommandBuffer)) -> Result<Duration> {
+        // SAFETY (this function): there is one command buffer and one fence,
+        // and both are idle here because every submission is waited on
+        let cb = self.command_buffer;
+        unsafe { self.device.reset_command_buffer(cb, vk::CommandBufferResetFlags::empty()) }
+            .map_err(|e| self.error("Failed to reset command buffer", e))?;
+        let begin_info =
+            vk::CommandBufferBeginInfo::builder().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        unsafe { self.device.begin_command_buffer(cb, &begin_info) }
+            .map_err(|e| self.error("Failed to begin command buffer", e))?;
+        record(self, cb);
+        unsafe { self.device.end_command_buffer(cb) }
+            .map_err(|e| self.error("Failed to record command buffer", e))?;
+
+        let command_buffers = [cb];
+        let submit = vk::SubmitInfo::builder().command_buffers(&command_buffers);
+        unsafe { self.device.reset_fences(&[self.fence]) }
+            .map_err(|e| self.error("Failed to reset fence", e))?;
+
+        let start = Instant::now();
+        unsafe { self.device.queue_submit(self.queue, &[*submit], self.fence) }
+            .map_err(|e| self.error("Failed to submit benchmark work", e))?;
+        unsafe { self.device.wait_for_fences(&[self.fence], true, u64::MAX) }
+            .map_err(|e| self.error("Benchmark work did not complete", e))?;
+        Ok(start.elapsed())
+    }
+}
+
+impl Drop for ComputeContext {
+    fn drop(&mut self) {
+        // SAFETY: the device is idle before anything is destroyed, children
+        // go before their parents, and destroying a null handle is a no-op,
+        // so partially built contexts clean up the same way
+        unsafe {
+            let _ = self.device.device_wait_idle();
+            self.device.destroy_pipeline(self.pipeline, None);
+            self.device.destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device.destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device.destroy_descriptor_set_layout(self.set_layout, None);
+            self.device.destroy_shader_module(self.shader, None);
+            for (buffer, memory) in self.buffers.drain(..) {
+                self.device.destroy_buffer(buffer, None);
+                self.device.free_memory(memory, None);
+            }
+            self.device.destroy_fence(self.fence, None);
+            self.device.destroy_command_pool(self.command_pool, None);
+            self.device.destroy_device(None);
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+/// Find the physical device `gpu` was detected as and open it with one
+/// compute queue. Returns the device and its queue family.
+fn open_device(
+    instance: &ash::Instance,
+    gpu: &GpuInfo,
+) -> Result<(vk::PhysicalDevice, ash::Device, u32)> {
+    let err = |what: &str, e: vk::Result| Error::GpuError {
+        message: format!("{}: {:?}", what, e),
+        device_id: Some(gpu.id),
+    };
+
+    let devices = unsafe { instance.enumerate_physical_devices() }
+        .map_err(|e| err("Failed to enumerate devices", e))?;
+    let physical = devices
+        .iter()
+        .copied()
+        .find(|&d| {
+            let props = unsafe { instance.get_physical_device_properties(d) };
+            props.vendor_id == gpu.vendor_id
+                && props.device_id == gpu.device_id
+                && props.api_version >= vk::API_VERSION_1_1
+        })
+        .ok_or_else(|| Error::GpuError {
+            message: format!("{} is not visible to Vulkan 1.1", gpu.name),
+            device_id: Some(gpu.id),
+        })?;
+
+    let queue_family = unsafe { instance.get_physical_device_queue_family_properties(physical) }
+        .iter()
+        .position(|qf| qf.queue_flags.contains(vk::QueueFlags::COMPUTE))
+        .ok_or_else(|| Error::GpuError {
+            message: format!("{} has no compute queue", gpu.name),
+            device_id: Some(gpu.id),
+        })? as u32;
+
+    let priorities = [1.0];
+    let queue_info = [vk::DeviceQueueCreateInfo::builder()
+        .queue_family_index(queue_family)
+        .queue_priorities(&priorities)
+        .build()];
+    let device_info = vk::DeviceCreateInfo::builder().queue_create_infos(&queue_info);
+    // SAFETY: `physical` came from `instance`; `device_info` and the arrays
+    // it points to outlive the call
+    let device = unsafe { instance.create_device(physical, &device_info, None) }
+        .map_err(|e| err("Failed to create logical device", e))?;
+
+    Ok((physical, device, queue_family))
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fma_shader_is_spirv() {
+        let code = ash::util::read_spv(&mut Cursor::new(FMA_SHADER)).unwrap();
+        assert_eq!(code[0], 0x0723_0203);
+        assert!(code.len() > 5);
+    }
+}
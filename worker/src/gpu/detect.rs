@@ -1,7 +1,8 @@
 //! GPU detection via Vulkan
 //!
 //! Uses the Vulkan API (via ash crate) to enumerate GPUs
-//! and gather hardware information.
+//! and gather hardware information. AMD cards are cross-checked against
+//! sysfs (see [`super::sysfs`]), whose VRAM and ROCm details win.
 
 use crate::error::{Error, Result};
 
@@ -14,6 +15,25 @@ use super::{GpuApi, GpuInfo, GpuVendor};
 /// Detect all GPUs in the system
 #[cfg(feature = "gpu")]
 pub fn detect_gpus() -> Result<Vec<GpuInfo>> {
+    use tracing::warn;
+
+    let amd = super::sysfs::AmdSysfs::system().detect();
+    match detect_vulkan_gpus() {
+        Ok(mut gpus) => {
+            super::sysfs::merge_amd(&mut gpus, amd);
+            Ok(gpus)
+        }
+        Err(e) if !amd.is_empty() => {
+            warn!("{}; using {} AMD GPU(s) found in sysfs", e, amd.len());
+            Ok(amd)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Enumerate GPUs through Vulkan
+#[cfg(feature = "gpu")]
+fn detect_vulkan_gpus() -> Result<Vec<GpuInfo>> {
     use ash::vk;
     use tracing::{debug, info, warn};
 
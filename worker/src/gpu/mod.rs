@@ -4,10 +4,18 @@
 //! - GPU hardware detection (vendor, VRAM, capabilities)
 //! - Vulkan-based device enumeration
 //! - GPU vendor identification and prioritization
+//! - AMD details from sysfs on Linux
+//! - Live utilization and memory (NVIDIA via NVML, AMD via sysfs)
+//! - Compute throughput and VRAM bandwidth measurement
 
+mod bench;
 mod detect;
+mod sysfs;
+mod usage;
 
+pub use bench::*;
 pub use detect::*;
+pub use usage::*;
 
 use serde::{Deserialize, Serialize};
 
@@ -51,6 +59,17 @@ impl GpuVendor {
         }
     }
 
+    /// Parse a vendor from its name (case-insensitive), as used in config
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "amd" => Some(GpuVendor::Amd),
+            "nvidia" => Some(GpuVendor::Nvidia),
+            "intel" => Some(GpuVendor::Intel),
+            "apple" => Some(GpuVendor::Apple),
+            _ => None,
+        }
+    }
+
     /// Get the vendor ID
     pub fn vendor_id(&self) -> u32 {
         match self {
@@ -265,7 +284,7 @@ pub fn select_best_gpu(gpus: &[GpuInfo]) -> Option<&GpuInfo> {
 }
 
 /// Select GPUs by vendor preference order
-pub fn select_by_vendor_priority(gpus: &[GpuInfo], priorities: &[GpuVendor]) -> Option<&GpuInfo> {
+pub fn select_by_vendor_priority<'a>(gpus: &'a [GpuInfo], priorities: &[GpuVendor]) -> Option<&'a GpuInfo> {
     for vendor in priorities {
         if let Some(gpu) = gpus.iter()
             .filter(|g| g.compute_capable && g.vendor == *vendor)
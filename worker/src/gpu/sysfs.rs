@@ -0,0 +1,316 @@
+//! AMD GPU detection via sysfs
+//!
+//! The amdgpu driver exposes each card under `/sys/class/drm/cardN/device/`:
+//! PCI `vendor` and `device` IDs, `mem_info_vram_total` / `mem_info_vram_used`
+//! in bytes, and `gpu_busy_percent`. These are exact where Vulkan's heap
+//! sizes are not, so they take precedence for AMD cards, and they also find
+//! cards when the Vulkan loader is missing. ROCm is reported as supported
+//! when it is installed under `/opt/rocm` or the card runs on amdgpu.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{GpuApi, GpuInfo, GpuUsage, GpuVendor};
+
+/// Where the kernel lists DRM devices
+const DRM_ROOT: &str = "/sys/class/drm";
+
+/// Default ROCm install location
+const ROCM_ROOT: &str = "/opt/rocm";
+
+/// Smallest VRAM taken to mean a discrete card; APUs carve out less
+const DISCRETE_MIN_VRAM_MB: u64 = 2048;
+
+/// AMD cards as seen through sysfs
+pub struct AmdSysfs {
+    drm_root: PathBuf,
+    rocm_root: PathBuf,
+}
+
+/// One `cardN` entry with an AMD device behind it
+struct Card {
+    index: u32,
+    device: PathBuf,
+    device_id: u32,
+}
+
+impl AmdSysfs {
+    /// The running system's sysfs and ROCm install
+    pub fn system() -> Self {
+        Self::at(DRM_ROOT, ROCM_ROOT)
+    }
+
+    /// Read from another DRM class directory and ROCm location
+    pub fn at(drm_root: impl Into<PathBuf>, rocm_root: impl Into<PathBuf>) -> Self {
+        Self {
+            drm_root: drm_root.into(),
+            rocm_root: rocm_root.into(),
+        }
+    }
+
+    /// AMD cards, ordered by card number
+    fn cards(&self) -> Vec<Card> {
+        let Ok(entries) = fs::read_dir(&self.drm_root) else {
+            return Vec::new();
+        };
+        let mut cards: Vec<Card> = entries
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                // `card0`, not connectors like `card0-DP-1`
+                let index = name.to_str()?.strip_prefix("card")?.parse().ok()?;
+                let device = self.drm_root.join(&name).join("device");
+                if read_hex(&device.join("vendor"))? != GpuVendor::AMD_VENDOR_ID {
+                    return None;
+                }
+                let device_id = read_hex(&device.join("device"))?;
+                Some(Card { index, device, device_id })
+            })
+            .collect();
+        cards.sort_by_key(|card| card.index);
+        cards
+    }
+
+    /// Every AMD card that reports its VRAM
+    pub fn detect(&self) -> Vec<GpuInfo> {
+        let rocm_installed = self.rocm_root.is_dir();
+        self.cards()
+            .into_iter()
+            .filter_map(|card| {
+                let total_memory_mb = read_u64(&card.device.join("mem_info_vram_total"))? / (1024 * 1024);
+                let driver = fs::read_link(card.device.join("driver"))
+                    .ok()
+                    .and_then(|link| link.file_name()?.to_str().map(str::to_string));
+                let amdgpu = driver.as_deref() == Some("amdgpu");
+
+                let mut api_support = vec![GpuApi::Vulkan];
+                if rocm_installed || amdgpu {
+                    api_support.push(GpuApi::Rocm);
+                }
+
+                Some(GpuInfo {
+                    id: card.index,
+                    name: read_string(&card.device.join("product_name"))
+                        .unwrap_or_else(|| format!("AMD GPU {:#06x}", card.device_id)),
+                    vendor: GpuVendor::Amd,
+                    vendor_id: GpuVendor::AMD_VENDOR_ID,
+                    device_id: card.device_id,
+                    total_memory_mb,
+                    driver_version: driver.unwrap_or_else(|| "unknown".to_string()),
+                    api_support,
+                    vulkan_version: None,
+                    is_discrete: total_memory_mb >= DISCRETE_MIN_VRAM_MB,
+                    compute_capable: true,
+                })
+            })
+            .collect()
+    }
+
+    /// Current load of the first card with PCI device ID `device_id`
+    pub fn usage(&self, device_id: u32) -> Option<GpuUsage> {
+        let card = self.cards().into_iter().find(|card| card.device_id == device_id)?;
+        card_usage(&card)
+    }
+
+    /// Current load of every AMD card that reports it
+    pub fn all_usage(&self) -> Vec<GpuUsage> {
+        self.cards().iter().filter_map(card_usage).collect()
+    }
+}
+
+fn card_usage(card: &Card) -> Option<GpuUsage> {
+    let busy = read_u64(&card.device.join("gpu_busy_percent"))?;
+    let total = read_u64(&card.device.join("mem_info_vram_total"))?;
+    let used = read_u64(&card.device.join("mem_info_vram_used"))?;
+    Some(GpuUsage {
+        utilization_percent: busy.min(100) as f32,
+        memory_used_mb: used / (1024 * 1024),
+        memory_free_mb: total.saturating_sub(used) / (1024 * 1024),
+    })
+}
+
+/// Overlay sysfs details onto the Vulkan-detected AMD cards, matched by PCI
+/// device ID in order. Sysfs cards Vulkan didn't see are appended.
+pub fn merge_amd(gpus: &mut Vec<GpuInfo>, sysfs: Vec<GpuInfo>) {
+    let mut unmatched = Vec::new();
+    let mut taken = vec![false; gpus.len()];
+    for card in sysfs {
+        let matched = gpus.iter().enumerate().position(|(i, gpu)| {
+            !taken[i] && gpu.vendor == GpuVendor::Amd && gpu.device_id == card.device_id
+        });
+        let Some(i) = matched else {
+            unmatched.push(card);
+            continue;
+        };
+        taken[i] = true;
+        let gpu = &mut gpus[i];
+        gpu.total_memory_mb = card.total_memory_mb;
+        gpu.api_support.retain(|api| *api != GpuApi::Rocm);
+        if card.supports_api(GpuApi::Rocm) {
+            gpu.api_support.push(GpuApi::Rocm);
+        }
+    }
+    let next_id = gpus.iter().map(|gpu| gpu.id + 1).max().unwrap_or(0);
+    for (offset, mut card) in unmatched.into_iter().enumerate() {
+        card.id = next_id + offset as u32;
+        gpus.push(card);
+    }
+}
+
+fn read_string(path: &Path) -> Option<String> {
+    let value = fs::read_to_string(path).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_string(path)?.parse().ok()
+}
+
+/// A `0x`-prefixed hex ID, as in the PCI `vendor` and `device` files
+fn read_hex(path: &Path) -> Option<u32> {
+    let value = read_string(path)?;
+    u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lay out `cardN/device/` with the given attribute files
+    fn add_card(drm: &Path, card: &str, files: &[(&str, &str)]) {
+        let device = drm.join(card).join("device");
+        fs::create_dir_all(&device).unwrap();
+        for (name, value) in files {
+            fs::write(device.join(name), format!("{}\n", value)).unwrap();
+        }
+    }
+
+    fn amd_card(drm: &Path, card: &str, device_id: &str, vram_mb: u64) {
+        let vram = (vram_mb * 1024 * 1024).to_string();
+        add_card(
+            drm,
+            card,
+            &[
+                ("vendor", "0x1002"),
+                ("device", device_id),
+                ("mem_info_vram_total", &vram),
+                ("mem_info_vram_used", "1073741824"),
+                ("gpu_busy_percent", "37"),
+            ],
+        );
+    }
+
+    fn vulkan_gpu(id: u32, vendor: GpuVendor, device_id: u32, total_memory_mb: u64) -> GpuInfo {
+        let mut api_support = vec![GpuApi::Vulkan];
+        if vendor == GpuVendor::Amd {
+            api_support.push(GpuApi::Rocm);
+        }
+        GpuInfo {
+            id,
+            name: format!("Vulkan GPU {}", id),
+            vendor,
+            vendor_id: vendor.vendor_id(),
+            device_id,
+            total_memory_mb,
+            driver_version: "2.0.279".to_string(),
+            api_support,
+            vulkan_version: Some("1.3.260".to_string()),
+            is_discrete: true,
+            compute_capable: true,
+        }
+    }
+
+    #[test]
+    fn test_detect_amd_cards() {
+        let dir = tempfile::tempdir().unwrap();
+        let drm = dir.path().join("drm");
+        amd_card(&drm, "card1", "0x744c", 24560);
+        amd_card(&drm, "card0", "0x15bf", 512);
+        add_card(&drm, "card0-DP-1", &[("vendor", "0x1002")]);
+        // NVIDIA card alongside
+        add_card(&drm, "card2", &[("vendor", "0x10de"), ("device", "0x2684")]);
+        fs::write(drm.join("card1/device/product_name"), "Radeon RX 7900 XTX\n").unwrap();
+
+        let sysfs = AmdSysfs::at(&drm, dir.path().join("rocm"));
+        let gpus = sysfs.detect();
+        assert_eq!(gpus.len(), 2);
+
+        assert_eq!(gpus[0].id, 0);
+        assert_eq!(gpus[0].device_id, 0x15bf);
+        assert_eq!(gpus[0].name, "AMD GPU 0x15bf");
+        assert_eq!(gpus[0].total_memory_mb, 512);
+        assert!(!gpus[0].is_discrete);
+
+        assert_eq!(gpus[1].name, "Radeon RX 7900 XTX");
+        assert_eq!(gpus[1].total_memory_mb, 24560);
+        assert!(gpus[1].is_discrete);
+        // Neither ROCm nor the amdgpu driver
+        assert!(!gpus[1].supports_api(GpuApi::Rocm));
+
+        fs::create_dir(dir.path().join("rocm")).unwrap();
+        assert!(sysfs.detect().iter().all(|gpu| gpu.supports_api(GpuApi::Rocm)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_amdgpu_driver_means_rocm() {
+        let dir = tempfile::tempdir().unwrap();
+        let drm = dir.path().join("drm");
+        amd_card(&drm, "card0", "0x744c", 24560);
+        let driver = dir.path().join("drivers/amdgpu");
+        fs::create_dir_all(&driver).unwrap();
+        std::os::unix::fs::symlink(&driver, drm.join("card0/device/driver")).unwrap();
+
+        let gpus = AmdSysfs::at(&drm, dir.path().join("rocm")).detect();
+        assert_eq!(gpus[0].driver_version, "amdgpu");
+        assert!(gpus[0].supports_api(GpuApi::Rocm));
+    }
+
+    #[test]
+    fn test_amd_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let drm = dir.path().join("drm");
+        amd_card(&drm, "card0", "0x744c", 24576);
+        let sysfs = AmdSysfs::at(&drm, dir.path().join("rocm"));
+
+        let usage = sysfs.usage(0x744c).unwrap();
+        assert_eq!(usage.utilization_percent, 37.0);
+        assert_eq!(usage.memory_used_mb, 1024);
+        assert_eq!(usage.memory_free_mb, 23552);
+        assert!(sysfs.usage(0x1234).is_none());
+        assert_eq!(sysfs.all_usage(), vec![usage]);
+
+        // Nothing to read on machines without the layout
+        assert!(AmdSysfs::at(dir.path().join("missing"), dir.path()).all_usage().is_empty());
+    }
+
+    #[test]
+    fn test_merge_prefers_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let drm = dir.path().join("drm");
+        amd_card(&drm, "card0", "0x744c", 24560);
+        amd_card(&drm, "card1", "0x73bf", 16368);
+        let sysfs = AmdSysfs::at(&drm, dir.path().join("rocm")).detect();
+
+        let mut gpus = vec![
+            vulkan_gpu(0, GpuVendor::Nvidia, 0x744c, 8192),
+            vulkan_gpu(1, GpuVendor::Amd, 0x744c, 25000),
+        ];
+        merge_amd(&mut gpus, sysfs);
+
+        // The NVIDIA card with a clashing device ID is left alone
+        assert_eq!(gpus[0].total_memory_mb, 8192);
+        // The AMD card takes sysfs VRAM and ROCm support
+        assert_eq!(gpus[1].total_memory_mb, 24560);
+        assert_eq!(gpus[1].name, "Vulkan GPU 1");
+        assert!(!gpus[1].supports_api(GpuApi::Rocm));
+        // A card Vulkan missed is added
+        assert_eq!(gpus.len(), 3);
+        assert_eq!(gpus[2].id, 2);
+        assert_eq!(gpus[2].device_id, 0x73bf);
+    }
+}
@@ -0,0 +1,255 @@
+//! Live GPU usage
+//!
+//! NVIDIA GPUs report utilization and memory through NVML, which is loaded
+//! at runtime from the driver's `libnvidia-ml` (as plugins are loaded), so
+//! building needs no NVIDIA libraries. AMD GPUs report the same through
+//! amdgpu's sysfs files. Usage is `None` where neither is available and for
+//! Intel GPUs.
+
+use serde::{Deserialize, Serialize};
+
+use super::sysfs::AmdSysfs;
+use super::{GpuInfo, GpuVendor};
+
+/// Current load of a GPU
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GpuUsage {
+    /// Time the GPU was busy over the last sample period (0-100)
+    pub utilization_percent: f32,
+
+    /// Video memory in use (MB)
+    pub memory_used_mb: u64,
+
+    /// Video memory free (MB)
+    pub memory_free_mb: u64,
+}
+
+impl GpuInfo {
+    /// Current utilization and memory of this GPU, if its driver reports
+    /// them.
+    ///
+    /// NVIDIA devices are matched to NVML by PCI vendor and device ID, AMD
+    /// devices to sysfs by device ID; of several identical cards, the first
+    /// is reported.
+    pub fn live_usage(&self) -> Option<GpuUsage> {
+        match self.vendor {
+            GpuVendor::Nvidia => nvml::device_usage(self.vendor_id, self.device_id),
+            GpuVendor::Amd => AmdSysfs::system().usage(self.device_id),
+            _ => None,
+        }
+    }
+}
+
+/// Usage across every GPU that reports it: mean utilization and summed
+/// memory. `None` if no GPU reports usage.
+pub fn total_usage() -> Option<GpuUsage> {
+    let mut devices = nvml::all_usage();
+    devices.extend(AmdSysfs::system().all_usage());
+    if devices.is_empty() {
+        return None;
+    }
+    Some(GpuUsage {
+        utilization_percent: devices.iter().map(|u| u.utilization_percent).sum::<f32>()
+            / devices.len() as f32,
+        memory_used_mb: devices.iter().map(|u| u.memory_used_mb).sum(),
+        memory_free_mb: devices.iter().map(|u| u.memory_free_mb).sum(),
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────
+// NVML
+// ─────────────────────────────────────────────────────────────────
+
+mod nvml {
+    use std::ffi::{c_char, c_int, c_uint, c_void};
+    use std::sync::OnceLock;
+
+    use tracing::debug;
+
+    use super::GpuUsage;
+
+    #[cfg(windows)]
+    const LIBRARY: &str = "nvml.dll";
+    #[cfg(not(windows))]
+    const LIBRARY: &str = "libnvidia-ml.so.1";
+
+    const NVML_SUCCESS: c_int = 0;
+
+    type Device = *mut c_void;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Utilization {
+        gpu: c_uint,
+        memory: c_uint,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Memory {
+        total: u64,
+        free: u64,
+        used: u64,
+    }
+
+    #[repr(C)]
+    #[allow(dead_code)] // Filled in by NVML; only the device ID is read
+    struct PciInfo {
+        bus_id_legacy: [c_char; 16],
+        domain: c_uint,
+        bus: c_uint,
+        device: c_uint,
+        /// Device ID in the high 16 bits, vendor ID in the low
+        pci_device_id: c_uint,
+        pci_sub_system_id: c_uint,
+        bus_id: [c_char; 32],
+    }
+
+    /// Loaded NVML entry points; NVML stays initialized for the process
+    struct Nvml {
+        _library: libloading::Library,
+        device_count: unsafe extern "C" fn(*mut c_uint) -> c_int,
+        device_by_index: unsafe extern "C" fn(c_uint, *mut Device) -> c_int,
+        utilization: unsafe extern "C" fn(Device, *mut Utilization) -> c_int,
+        memory: unsafe extern "C" fn(Device, *mut Memory) -> c_int,
+        pci_info: unsafe extern "C" fn(Device, *mut PciInfo) -> c_int,
+    }
+
+    impl Nvml {
+        fn load() -> Option<Self> {
+            unsafe {
+                let library = libloading::Library::new(LIBRARY).ok()?;
+                let init = *library.get::<unsafe extern "C" fn() -> c_int>(b"nvmlInit_v2").ok()?;
+                let nvml = Self {
+                    device_count: *library.get(b"nvmlDeviceGetCount_v2").ok()?,
+                    device_by_index: *library.get(b"nvmlDeviceGetHandleByIndex_v2").ok()?,
+                    utilization: *library.get(b"nvmlDeviceGetUtilizationRates").ok()?,
+                    memory: *library.get(b"nvmlDeviceGetMemoryInfo").ok()?,
+                    pci_info: *library.get(b"nvmlDeviceGetPciInfo_v3").ok()?,
+                    _library: library,
+                };
+                let status = init();
+                if status != NVML_SUCCESS {
+                    debug!(status, "NVML failed to initialize");
+                    return None;
+                }
+                Some(nvml)
+            }
+        }
+
+        fn devices(&self) -> Vec<Device> {
+            let mut count = 0;
+            if unsafe { (self.device_count)(&mut count) } != NVML_SUCCESS {
+                return Vec::new();
+            }
+            (0..count)
+                .filter_map(|index| {
+                    let mut device = std::ptr::null_mut();
+                    let status = unsafe { (self.device_by_index)(index, &mut device) };
+                    (status == NVML_SUCCESS).then_some(device)
+                })
+                .collect()
+        }
+
+        fn pci_device_id(&self, device: Device) -> Option<u32> {
+            let mut info = PciInfo {
+                bus_id_legacy: [0; 16],
+                domain: 0,
+                bus: 0,
+                device: 0,
+                pci_device_id: 0,
+                pci_sub_system_id: 0,
+                bus_id: [0; 32],
+            };
+            let status = unsafe { (self.pci_info)(device, &mut info) };
+            (status == NVML_SUCCESS).then_some(info.pci_device_id)
+        }
+
+        fn usage(&self, device: Device) -> Option<GpuUsage> {
+            let mut utilization = Utilization::default();
+            let mut memory = Memory::default();
+            unsafe {
+                if (self.utilization)(device, &mut utilization) != NVML_SUCCESS
+                    || (self.memory)(device, &mut memory) != NVML_SUCCESS
+                {
+                    return None;
+                }
+            }
+            Some(GpuUsage {
+                utilization_percent: utilization.gpu as f32,
+                memory_used_mb: memory.used / (1024 * 1024),
+                memory_free_mb: memory.free / (1024 * 1024),
+            })
+        }
+    }
+
+    /// NVML, loaded on first use (`None` where it isn't installed)
+    fn nvml() -> Option<&'static Nvml> {
+        static NVML: OnceLock<Option<Nvml>> = OnceLock::new();
+        NVML.get_or_init(|| {
+            let nvml = Nvml::load();
+            debug!(available = nvml.is_some(), "NVML probed");
+            nvml
+        })
+        .as_ref()
+    }
+
+    /// Usage of the first NVML device with the given PCI IDs
+    pub fn device_usage(vendor_id: u32, device_id: u32) -> Option<GpuUsage> {
+        let nvml = nvml()?;
+        let wanted = (device_id << 16) | (vendor_id & 0xFFFF);
+        nvml.devices()
+            .into_iter()
+            .find(|device| nvml.pci_device_id(*device) == Some(wanted))
+            .and_then(|device| nvml.usage(device))
+    }
+
+    /// Usage of every NVML device
+    pub fn all_usage() -> Vec<GpuUsage> {
+        let Some(nvml) = nvml() else {
+            return Vec::new();
+        };
+        nvml.devices()
+            .into_iter()
+            .filter_map(|device| nvml.usage(device))
+            .collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::GpuApi;
+
+    fn gpu(vendor: GpuVendor) -> GpuInfo {
+        GpuInfo {
+            id: 0,
+            name: "Test GPU".to_string(),
+            vendor,
+            vendor_id: vendor.vendor_id(),
+            device_id: 0x2684,
+            total_memory_mb: 24576,
+            driver_version: "550.54.14".to_string(),
+            api_support: vec![GpuApi::Vulkan],
+            vulkan_version: None,
+            is_discrete: true,
+            compute_capable: true,
+        }
+    }
+
+    #[test]
+    fn test_usage_none_without_nvml() {
+        // Intel isn't queried
+        assert!(gpu(GpuVendor::Intel).live_usage().is_none());
+
+        // Without NVML, NVIDIA cards report nothing
+        let library = if cfg!(windows) { "nvml.dll" } else { "libnvidia-ml.so.1" };
+        if unsafe { libloading::Library::new(library) }.is_err() {
+            assert!(gpu(GpuVendor::Nvidia).live_usage().is_none());
+        }
+    }
+}
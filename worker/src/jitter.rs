@@ -0,0 +1,33 @@
+//! Random delays for spreading out timers
+//!
+//! Retries and rate-limited requests add jitter so that many workers (or
+//! many hosts) don't wake up in lockstep.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// A random duration in [0, max], at millisecond granularity
+pub fn jitter(max: Duration) -> Duration {
+    let max_ms = max.as_millis() as u64;
+    if max_ms == 0 {
+        return Duration::ZERO;
+    }
+    // Each RandomState is freshly keyed, which is random enough here
+    let r = RandomState::new().build_hasher().finish();
+    Duration::from_millis(r % (max_ms + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jitter_bounds() {
+        assert_eq!(jitter(Duration::ZERO), Duration::ZERO);
+        assert_eq!(jitter(Duration::from_micros(999)), Duration::ZERO);
+        for _ in 0..100 {
+            assert!(jitter(Duration::from_millis(50)) <= Duration::from_millis(50));
+        }
+    }
+}
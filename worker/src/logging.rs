@@ -4,12 +4,13 @@
 //! - Console output with colors
 //! - File logging with rotation (daily or size-based)
 //! - JSON format option
-//! - Dynamic log level filtering
+//! - Dynamic log level filtering, changeable at runtime
 //! - Per-module log levels via RUST_LOG
+//! - A `task` span (task_id, block_id, day_id) on every log line for a task
 
 use std::fs;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use tracing::Level;
 use tracing_appender::non_blocking::WorkerGuard;
@@ -17,7 +18,7 @@ use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{fmt, EnvFilter, Layer};
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
 
 use crate::config::LoggingSettings;
 use crate::error::{Error, Result};
@@ -28,6 +29,31 @@ pub struct LogGuards {
     _file_guard: Option<WorkerGuard>,
 }
 
+/// Handle for changing the level of an installed log filter
+#[derive(Clone)]
+pub struct LogLevelHandle(reload::Handle<EnvFilter, Registry>);
+
+impl LogLevelHandle {
+    /// Log at `level` from now on; `RUST_LOG` still applies to other crates
+    pub fn set_level(&self, level: &str) -> Result<()> {
+        let filter = build_env_filter(level, parse_level(level))?;
+        self.0
+            .reload(filter)
+            .map_err(|e| Error::Internal(format!("Failed to change log level: {}", e)))
+    }
+}
+
+/// Level handle of the filter installed by [`init_logging`]
+static LEVEL_HANDLE: OnceLock<LogLevelHandle> = OnceLock::new();
+
+/// Change the log level of the running worker
+pub fn set_level(level: &str) -> Result<()> {
+    LEVEL_HANDLE
+        .get()
+        .ok_or_else(|| Error::Internal("Logging not initialized".to_string()))?
+        .set_level(level)
+}
+
 /// Initialize the logging system
 ///
 /// Returns guards that must be kept alive for the duration of the program.
@@ -40,8 +66,8 @@ pub fn init_logging(
     // Determine the effective log level
     let level = determine_level(settings, verbose, quiet);
 
-    // Build the environment filter
-    let env_filter = build_env_filter(&settings.level, level)?;
+    // Build the environment filter, reloadable for level changes
+    let (env_filter, level_handle) = reload::Layer::new(build_env_filter(&settings.level, level)?);
 
     // Create the console layer
     let console_layer = build_console_layer(settings.json_format, level);
@@ -66,6 +92,7 @@ pub fn init_logging(
         .with(console_layer)
         .with(file_layer)
         .init();
+    let _ = LEVEL_HANDLE.set(LogLevelHandle(level_handle));
 
     tracing::info!(
         level = %level,
@@ -300,6 +327,27 @@ mod tests {
         assert!(filter.is_ok());
     }
 
+    #[test]
+    fn test_config_update_changes_level() {
+        let mut config = crate::config::WorkerConfig::default();
+        let level = &config.logging.level;
+        let (filter, handle) =
+            reload::Layer::new(build_env_filter(level, parse_level(level)).unwrap());
+        let handle = LogLevelHandle(handle);
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(filter), || {
+            assert!(!tracing::enabled!(Level::DEBUG));
+
+            config
+                .apply_update(&serde_json::json!({ "logging": { "level": "debug" } }))
+                .unwrap();
+            handle.set_level(&config.logging.level).unwrap();
+
+            // Same subscriber, now at the new level
+            assert!(tracing::enabled!(Level::DEBUG));
+        });
+    }
+
     #[test]
     fn test_file_layer_creates_directory() {
         let temp_dir = TempDir::new().unwrap();
@@ -7,42 +7,73 @@
 mod backend;
 mod cli;
 mod config;
+mod control;
 mod coordinator;
 mod crawler;
 mod error;
 mod executor;
 #[cfg(feature = "gpu")]
 mod gpu;
+mod jitter;
 mod logging;
+mod models;
 mod pairing;
 mod peer;
 #[cfg(feature = "gpu")]
 mod plugins;
 mod protocol;
+mod recording;
+mod sandbox;
 mod system;
+#[cfg(test)]
+mod testing;
 mod types;
 mod version;
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use clap::Parser;
 use parking_lot::RwLock;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
-use crate::backend::{BackendConfig, BackendRegistry, BackendType};
+use crate::backend::{
+    BackendConfig, BackendRegistry, BackendType, DomainDenylist, HostRateLimiter, MockBackend,
+    MockConfig,
+};
 use crate::cli::{Cli, Commands};
 use crate::config::WorkerConfig;
-use crate::coordinator::{ClientEvent, CoordinatorClient, CoordinatorClientConfig};
+use crate::coordinator::{
+    http_base_url, poll_limit, release_body, ClientEvent, ConnectionState, CoordinatorClient,
+    CoordinatorClientConfig, PeerCredentials, PeerRegistration, PendingResult, PollBackoff,
+    ResultBuffer, ResultDelivery, ResultHandle, WorkerStats, MAX_BUFFERED_RESULTS,
+};
 use crate::error::{Error, Result};
-use crate::executor::{ExecutorConfig, TaskExecutor};
+use crate::executor::{
+    AuditSampler, CancelMode, Drain, DrainStep, ExecutorConfig, OverflowPolicy, PauseSource,
+    PostProcessor, TaskExecutor,
+};
 use crate::logging::LogGuards;
-use crate::peer::{GroupManager, GroupRole, MeshConfig, PeerEvent, PeerMesh, PeerRegistry};
+use crate::peer::{
+    GroupAdmission, GroupManager, GroupPurpose, GroupRole, MeshConfig, MeshKey, PeerEvent,
+    PeerMesh, PeerRegistry, ShardRequest,
+};
 use crate::protocol::{PeerMessage, WorkerCapabilities, WorkerStatus};
-use crate::system::{BenchmarkRunner, FirstRunExperience, HealthMonitor};
+use crate::recording::{RecordKind, SessionRecorder};
+use crate::system::{
+    BenchmarkComparison, BenchmarkResults, BenchmarkRunner, FirstRunExperience, HealthMonitor,
+    COMPUTE_REGRESSION_WARN_PCT,
+};
 use crate::types::TaskType;
 
+/// Minimum time between requests for a fresh peer directory
+const DIRECTORY_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a pipeline stream may go without a chunk before it is dropped
+const PIPELINE_STREAM_IDLE: Duration = Duration::from_secs(120);
+
 fn main() -> Result<()> {
     // Parse CLI arguments first (before logging, so we know verbosity)
     let cli = Cli::parse();
@@ -58,6 +89,38 @@ fn main() -> Result<()> {
             logging::init_simple(tracing::Level::WARN)?;
             return handle_config_command(subcommand.clone());
         }
+        Commands::Info { config, json } => {
+            logging::init_simple(tracing::Level::WARN)?;
+            return run_info(config.as_deref(), *json);
+        }
+        Commands::Replay { path, strict } => {
+            logging::init_simple(tracing::Level::WARN)?;
+            return run_replay(path, *strict);
+        }
+        Commands::Status { config, addr, json } => {
+            logging::init_simple(tracing::Level::WARN)?;
+            return run_status(config.as_deref(), addr.as_deref(), *json);
+        }
+        Commands::Pause { config, addr } => {
+            logging::init_simple(tracing::Level::WARN)?;
+            return run_pause(config.as_deref(), addr.as_deref(), true);
+        }
+        Commands::Resume { config, addr } => {
+            logging::init_simple(tracing::Level::WARN)?;
+            return run_pause(config.as_deref(), addr.as_deref(), false);
+        }
+        Commands::SelfTest { config, backend, model, prompt } => {
+            logging::init_simple(if cli.verbose > 0 {
+                tracing::Level::DEBUG
+            } else {
+                tracing::Level::WARN
+            })?;
+            if let Err(e) = run_self_test(config.as_deref(), backend, model.as_deref(), prompt) {
+                eprint!("{}", e.format_for_terminal());
+                std::process::exit(e.exit_code());
+            }
+            return Ok(());
+        }
         Commands::Pair { ref api_url, ref name, force } => {
             logging::init_simple(if cli.verbose > 0 {
                 tracing::Level::DEBUG
@@ -109,12 +172,30 @@ fn main() -> Result<()> {
     // Execute the appropriate command
     match cli.command {
         Commands::Run { .. } => {
-            run_worker(config)?;
+            if let Err(e) = run_worker(config, config_path) {
+                // Exit with the error's own code so orchestrators can
+                // restart a worker that gave up on the coordinator
+                if matches!(e, Error::CoordinatorUnreachable { .. }) {
+                    error!(error = %e, "Worker exiting");
+                    drop(_log_guards);
+                    eprint!("{}", e.format_for_terminal());
+                    std::process::exit(e.exit_code());
+                }
+                return Err(e);
+            }
         }
-        Commands::Benchmark { iterations, output } => {
-            run_benchmark(iterations, output)?;
+        Commands::Benchmark { iterations, output, compare } => {
+            run_benchmark(&config, iterations, output, compare)?;
         }
-        Commands::Version | Commands::Config { .. } | Commands::Pair { .. } => {
+        Commands::Version
+        | Commands::Config { .. }
+        | Commands::Pair { .. }
+        | Commands::Info { .. }
+        | Commands::Replay { .. }
+        | Commands::Status { .. }
+        | Commands::Pause { .. }
+        | Commands::Resume { .. }
+        | Commands::SelfTest { .. } => {
             // Already handled above
             unreachable!();
         }
@@ -133,7 +214,7 @@ fn init_logging_from_config(
 }
 
 /// Run the worker in normal operation mode
-fn run_worker(config: WorkerConfig) -> Result<()> {
+fn run_worker(config: WorkerConfig, config_path: Option<String>) -> Result<()> {
     info!(
         worker_id = %config.worker.id.as_deref().unwrap_or("(auto)"),
         coordinator_url = %config.coordinator.url,
@@ -172,7 +253,7 @@ fn run_worker(config: WorkerConfig) -> Result<()> {
         .build()
         .map_err(|e| Error::Internal(format!("Failed to create async runtime: {}", e)))?;
 
-    runtime.block_on(async_worker_main(config))
+    runtime.block_on(async_worker_main(config, config_path))
 }
 
 /// Ensure required storage directories exist
@@ -198,8 +279,38 @@ fn ensure_directories(config: &WorkerConfig) -> Result<()> {
     Ok(())
 }
 
+/// Load the backend plugin for the best local GPU, downloading it if
+/// `plugins.auto_download` allows. `None` without a GPU or usable plugin.
+#[cfg(feature = "gpu")]
+async fn load_gpu_plugin(config: &WorkerConfig) -> Option<plugins::PluginManager> {
+    if !config.resources.enable_gpu {
+        return None;
+    }
+    let gpus = gpu::detect_gpus()
+        .inspect_err(|e| warn!(error = %e, "GPU detection failed, not loading plugins"))
+        .ok()?;
+    let gpu = gpu::select_best_gpu(&gpus)?;
+
+    let mut manager = plugins::PluginManager::new(plugins::PluginManagerConfig::from_settings(
+        &config.plugins,
+    ));
+    // Built-in plugins are only verifiable with the registry's checksums
+    if let Err(e) = manager.refresh_manifest().await {
+        warn!(error = %e, "Could not fetch plugin manifest");
+    }
+    let Some(plugin) = manager.find_plugin_for_gpu(gpu).map(|p| p.name.clone()) else {
+        info!(gpu = %gpu.name, "No trusted plugin for GPU");
+        return None;
+    };
+    match manager.ensure_plugin(&plugin).await {
+        Ok(_) => info!(plugin = %plugin, gpu = %gpu.name, "GPU plugin ready"),
+        Err(e) => warn!(plugin = %plugin, error = %e, "Could not load GPU plugin"),
+    }
+    Some(manager)
+}
+
 /// Async worker main loop
-async fn async_worker_main(config: WorkerConfig) -> Result<()> {
+async fn async_worker_main(mut config: WorkerConfig, config_path: Option<String>) -> Result<()> {
     // Initialize health monitor
     let health_monitor = HealthMonitor::new();
     let sys_info = health_monitor.system_info();
@@ -230,75 +341,44 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
         }
     }
 
-    // Initialize backend registry
-    let registry = Arc::new(RwLock::new(BackendRegistry::new()));
-
-    // Register the mock backend (always available, used for testing and as fallback)
-    {
-        let reg = registry.read();
-        if let Err(e) = reg.register(BackendType::Mock, BackendConfig::default()) {
-            warn!(error = %e, "Failed to register mock backend");
-        }
-    }
-
-    // Register CPU backend
-    {
-        let cpu_config = BackendConfig {
-            num_threads: if config.resources.max_threads > 0 {
-                Some(config.resources.max_threads)
-            } else {
-                None
-            },
-            context_size: 4096,
-            batch_size: 512,
-            gpu_layers: 0,
-            use_mmap: true,
-            use_mlock: false,
-            seed: None,
-            openai: None,
-        };
-
-        let reg = registry.read();
-        match reg.register(BackendType::Cpu, cpu_config) {
-            Ok(_) => info!("CPU backend registered"),
-            Err(e) => warn!(error = %e, "Failed to register CPU backend (llama feature may not be enabled)"),
-        }
+    // The crawl denylist is shared by every crawler and reloadable
+    let crawl_denylist = DomainDenylist::new(&config.crawler.domain_denylist);
+    // Per-host politeness limits apply across task crawls and background crawls
+    let crawl_rate_limiter = HostRateLimiter::new(
+        Duration::from_millis(config.crawler.rate_limit_ms),
+        Duration::from_millis(config.crawler.rate_limit_jitter_ms),
+    );
+    // Validated with the config, so this can't fail here
+    let crawl_egress = sandbox::EgressPolicy::new(&config.sandbox.egress_allowlist)?;
+    if !crawl_egress.is_unrestricted() {
+        info!(
+            allowlist = ?config.sandbox.egress_allowlist,
+            "Crawl egress restricted to sandbox allowlist"
+        );
     }
 
-    // Register OpenAI backend (for API-based inference via OpenAI, Ollama, vLLM, etc.)
-    if config.openai.enabled {
-        use crate::backend::OpenAiConfig;
-
-        let openai_config = BackendConfig {
-            openai: Some(OpenAiConfig {
-                base_url: config.openai.base_url.clone(),
-                api_key: config.openai.api_key.clone(),
-                default_model: config.openai.default_model.clone(),
-                timeout_secs: config.openai.timeout_secs,
-                max_retries: config.openai.max_retries,
-            }),
-            ..BackendConfig::default()
-        };
+    // GPU backend plugins are checked against the [plugins] trust policy
+    // and stay loaded for the worker's lifetime
+    #[cfg(feature = "gpu")]
+    let _plugin_manager = load_gpu_plugin(&config).await;
 
-        let reg = registry.read();
-        match reg.register(BackendType::OpenAi, openai_config) {
-            Ok(_) => info!(
-                base_url = %config.openai.base_url,
-                model = %config.openai.default_model,
-                "OpenAI backend registered"
-            ),
-            Err(e) => warn!(error = %e, "Failed to register OpenAI backend"),
-        }
+    // Initialize backend registry
+    let registry = Arc::new(RwLock::new(new_backend_registry(
+        &config,
+        &crawl_denylist,
+        &crawl_rate_limiter,
+        &crawl_egress,
+    )));
+    if config.worker.debug_tasks {
+        warn!("DEBUG tasks enabled; this worker is for pipeline testing only");
     }
 
-    // Register Crawler backend if web crawling is enabled
-    if config.crawler.enabled {
-        use crate::backend::CrawlerBackend;
-        let crawler_backend = CrawlerBackend::new(&config.crawler, &config.openai);
-        let reg = registry.read();
-        reg.register_boxed(BackendType::Crawler, Box::new(crawler_backend));
-        info!("Crawler backend registered");
-    }
+    // Settings applied on SIGHUP without a restart
+    let (reload_tx, mut reload_rx) = tokio::sync::mpsc::channel::<WorkerConfig>(4);
+    #[cfg(unix)]
+    spawn_config_reload(config_path.clone(), crawl_denylist.clone(), reload_tx);
+    #[cfg(not(unix))]
+    drop(reload_tx);
 
     // Determine worker capabilities from registered backends
     let capabilities = build_worker_capabilities(&registry, &config);
@@ -316,27 +396,102 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
 
     let executor_config = ExecutorConfig {
         max_concurrent_tasks: capabilities.max_concurrent_tasks as usize,
+        max_queued_tasks: config.resources.max_queued_tasks as usize,
         default_timeout_secs: 300,
         detailed_metrics: true,
         queue_size: 100,
+        max_concurrent_loads: config.resources.max_concurrent_loads as usize,
+        model_dir: Some(config.model_dir()),
+        model_sources: config.model_sources(),
+        mandatory_system_prompt: config.worker.mandatory_system_prompt.clone(),
+        overflow_policy: OverflowPolicy::from_name(
+            &config.resources.queue_overflow_policy,
+            Duration::from_millis(config.resources.queue_block_timeout_ms),
+        )
+        .unwrap_or(OverflowPolicy::Reject),
+        model_decline_cooldown: Duration::from_secs(config.resources.model_decline_period_secs),
+        embedding_batch_window: Duration::from_millis(config.executor.embedding_batch_window_ms),
+        max_generation_tokens: config.resources.max_generation_tokens,
+        postprocess: PostProcessor::from_settings(&config.postprocess)?,
+        max_concurrent_crawls: config.executor.max_concurrent_crawls as usize,
+        max_concurrent_per_type: config
+            .executor
+            .max_concurrent_per_type
+            .iter()
+            .map(|(&task_type, &limit)| (task_type, limit as usize))
+            .collect(),
+        min_tokens_per_sec: config.resources.min_tokens_per_sec,
+        slow_generation_window: Duration::from_secs(config.resources.slow_generation_window_secs),
+        write_scope: if config.sandbox.restrict_writes {
+            sandbox::WriteScope::confined_to(config.data_dir())
+        } else {
+            sandbox::WriteScope::default()
+        },
+        memory_pressure_mb: config.resources.memory_pressure_mb,
+        audit: AuditSampler::new(
+            config.worker.audit_sample_rate,
+            config.worker.audit_sample_seed,
+            config.data_dir().join("audit"),
+        )
+        .with_max_file_mb(config.worker.audit_max_file_mb),
+        max_queue_age: Duration::from_secs(config.executor.max_queue_age_secs),
+        stream_progress: config.executor.stream_progress,
+        tags: config.worker.tags.clone(),
     };
 
-    let (executor, mut result_rx) = TaskExecutor::new(
+    let (mut executor, mut result_rx) = TaskExecutor::new(
         executor_config,
         registry.clone(),
         worker_id.clone(),
     );
+    let mut progress_rx = executor.subscribe_progress();
     let executor = Arc::new(executor);
 
+    // Preload the default model so the first task doesn't pay for the load
+    if config.resources.warmup {
+        let model_id = config.default_model();
+        let started = Instant::now();
+        match executor.warmup(&model_id).await {
+            Ok(()) => info!(
+                model = %model_id,
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "Warmup complete"
+            ),
+            Err(e) => warn!(
+                model = %model_id,
+                error = %e,
+                "Warmup failed; model will load with its first task"
+            ),
+        }
+    }
+
     // Create coordinator client
     let coordinator_config = CoordinatorClientConfig {
         url: config.coordinator.url.clone(),
         connect_timeout: Duration::from_millis(config.coordinator.connect_timeout_ms),
         max_reconnect_attempts: config.coordinator.max_reconnect_attempts,
         initial_reconnect_delay: Duration::from_millis(config.coordinator.reconnect_interval_ms),
-        max_reconnect_delay: Duration::from_secs(60),
+        max_reconnect_delay: Duration::from_millis(config.coordinator.max_reconnect_delay_ms),
+        reconnect_spread: Duration::from_millis(config.coordinator.reconnect_spread_ms),
         heartbeat_interval: Duration::from_millis(config.coordinator.heartbeat_interval_ms),
+        ping_interval: Duration::from_millis(config.coordinator.ping_interval_ms),
+        ping_timeout: Duration::from_millis(config.coordinator.ping_timeout_ms),
         message_queue_size: 100,
+        headers: config
+            .coordinator
+            .headers
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+        subprotocol: config.coordinator.subprotocol.clone(),
+        strict_protocol: config.coordinator.strict_protocol,
+        require_result_ack: config.coordinator.require_result_ack,
+        result_ack_timeout: Duration::from_millis(config.coordinator.result_ack_timeout_ms),
+        honor_task_reclamation: config.coordinator.honor_task_reclamation,
+        compression: config.coordinator.compression,
+        msgpack: config.coordinator.msgpack,
+        exit_after_failed: Duration::from_secs(config.coordinator.exit_after_failed_secs),
+        credentials: PeerCredentials::from_settings(&config.worker),
     };
 
     let worker_name = config.worker.name.clone()
@@ -347,16 +502,50 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
         .iter()
         .map(|t| t.to_string())
         .collect();
+    // Capabilities currently advertised; change when backend breakers trip
+    let mut advertised = capabilities.clone();
 
     // Initialize peer-to-peer mesh networking
     let peer_registry = Arc::new(PeerRegistry::new());
-    let group_manager = Arc::new(GroupManager::new(worker_id.clone()));
+    let group_manager = Arc::new(GroupManager::new(worker_id.clone()).with_readiness(
+        config.peer.shard_quorum,
+        Duration::from_millis(config.peer.group_ready_timeout_ms),
+    ));
+    // Partially received pipeline streams from upstream stages, and the
+    // output of our own stage tasks on its way to the next stage
+    let mut pipeline_streams = peer::PipelineReassembler::new();
+    let mut pipeline_writers: HashMap<String, peer::PipelineStreamWriter> = HashMap::new();
+    // Tasks offered to idle peers, and tasks run for busy ones
+    let mut offloader = peer::Offloader::new(worker_id.clone());
 
     let mesh_config = MeshConfig {
         listen_port: config.peer.listen_port,
+        listen_host: config.peer.listen_addr.clone(),
         max_peers: config.peer.max_peers,
+        max_connect_retries: config.peer.max_connect_retries,
+        retry_base_delay: Duration::from_millis(config.peer.retry_base_delay_ms),
+        retry_max_delay: Duration::from_millis(config.peer.retry_max_delay_ms),
+        ping_interval: Duration::from_millis(config.peer.ping_interval_ms),
+        stale_timeout: Duration::from_millis(config.peer.stale_timeout_ms),
+        max_missed_pings: config.peer.max_missed_pings,
+        read_timeout: Duration::from_millis(config.peer.read_timeout_ms),
+        write_timeout: Duration::from_millis(config.peer.write_timeout_ms),
+        shard_timeout: Duration::from_millis(config.peer.shard_timeout_ms),
+        mesh_key: PeerCredentials::from_settings(&config.worker)
+            .and_then(|credentials| MeshKey::from_credentials(&credentials)),
+        require_encryption: config.peer.require_encryption,
+        compression: config.peer.compression,
         ..MeshConfig::default()
     };
+    if config.peer.enabled && mesh_config.mesh_key.is_none() {
+        if config.peer.require_encryption {
+            return Err(Error::Config(
+                "peer.require_encryption is set, but worker.secret_key is not a valid ML-DSA-65 key"
+                    .to_string(),
+            ));
+        }
+        info!("No account key — peer connections are not encrypted");
+    }
 
     let (peer_event_tx, mut peer_event_rx) = tokio::sync::mpsc::channel::<PeerEvent>(100);
     let peer_mesh = Arc::new(PeerMesh::new(
@@ -369,10 +558,31 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
 
     // Start peer mesh listener if enabled
     if config.peer.enabled {
+        // Shard group tasks run through the group's shards in layer order,
+        // and ours runs on activations sent by group peers
+        executor.set_shard_route(Arc::new(peer::MeshShardRoute::new(
+            peer_mesh.clone(),
+            group_manager.clone(),
+        )));
+        tokio::spawn(serve_shard_requests(
+            peer_mesh.clone(),
+            peer_mesh.serve_shards(),
+            executor.clone(),
+            group_manager.clone(),
+        ));
+
         match peer_mesh.start().await {
             Ok(addr) => {
                 info!(listen_addr = %addr, "Peer mesh listener started");
             }
+            // An explicitly chosen address that can't be used is a
+            // misconfiguration, not a reason to run without P2P
+            Err(e) if !config.peer.listen_addr.trim().is_empty() => {
+                return Err(Error::Config(format!(
+                    "Cannot start peer mesh on peer.listen_addr '{}': {}",
+                    config.peer.listen_addr, e
+                )));
+            }
             Err(e) => {
                 warn!(error = %e, "Failed to start peer mesh listener, P2P disabled");
             }
@@ -383,7 +593,36 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
         coordinator_config,
         worker_name.clone(),
         capabilities,
+        config.worker.tags.clone(),
     );
+    {
+        let executor = executor.clone();
+        let mesh = peer_mesh.clone();
+        let registry = registry.clone();
+        client.set_stats_source(Arc::new(move |stats| {
+            stats.loaded_models =
+                registry.read().loaded_models().into_iter().map(|m| m.model_id).collect();
+            stats.running_tasks = executor.tracker().running_count();
+            stats.queued_tasks = executor.queued_count();
+            stats.completed_tasks = executor.completed_count();
+            stats.failed_tasks = executor.failed_count();
+            stats.peer_count = mesh.connected_peers().len();
+        }));
+    }
+
+    // Record the session for offline replay
+    let recorder = match config.debug.record_session {
+        Some(ref dir) => {
+            let recorder = Arc::new(SessionRecorder::create(std::path::Path::new(dir))?);
+            warn!(
+                path = %recorder.path().display(),
+                "Recording session; the file includes task inputs and outputs"
+            );
+            client.set_recorder(recorder.clone());
+            Some(recorder)
+        }
+        None => None,
+    };
 
     info!(
         worker_id = %worker_id,
@@ -395,10 +634,36 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
     // Start the coordinator client
     let mut event_rx = client.start().await?;
 
+    // Start the local control socket if enabled
+    if config.control.enabled {
+        let control = Arc::new(
+            control::ControlServer::new(executor.clone())
+                .with_stats(client.stats_handle())
+                .with_pause(client.pause_handle()),
+        );
+        if let Err(e) = control.start(&config.control.listen_addr).await {
+            warn!(
+                addr = %config.control.listen_addr,
+                error = %e,
+                "Failed to start control socket"
+            );
+        }
+    }
+
     // Set up graceful shutdown on Ctrl+C
     let shutdown_signal = tokio::signal::ctrl_c();
     tokio::pin!(shutdown_signal);
 
+    // SIGTERM drains instead: running tasks finish before the worker exits
+    let (term_tx, mut term_rx) = tokio::sync::mpsc::channel::<()>(1);
+    #[cfg(unix)]
+    spawn_sigterm_listener(term_tx);
+    #[cfg(not(unix))]
+    drop(term_tx);
+    let mut drain = Drain::new(Duration::from_secs(config.executor.drain_grace_secs));
+    let mut drain_timer = tokio::time::interval(Duration::from_millis(250));
+    drain_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
     // Periodic cleanup timer
     let mut cleanup_timer = tokio::time::interval(Duration::from_secs(300));
     cleanup_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
@@ -407,95 +672,70 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
     let mut health_timer = tokio::time::interval(Duration::from_secs(60));
     health_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
+    // Memory pressure check (evicts idle models over resources.memory_pressure_mb)
+    let mut memory_pressure_timer = tokio::time::interval(Duration::from_secs(5));
+    memory_pressure_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    // Work group readiness timer (disbands groups that never reach quorum)
+    let mut group_ready_timer = tokio::time::interval(Duration::from_secs(5));
+    group_ready_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    // Peer status broadcast (free capacity for task offloading)
+    let mut peer_status_timer = tokio::time::interval(Duration::from_secs(5));
+    peer_status_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
     // HTTP task polling setup (for on-demand task API)
-    let coordinator_http_base = config.coordinator.url
-        .replace("ws://", "http://")
-        .replace("wss://", "https://")
-        .trim_end_matches('/')
-        .to_string();
+    let coordinator_http_base = http_base_url(&config.coordinator.url)?;
 
     let http_client = reqwest::Client::builder()
         .timeout(Duration::from_secs(10))
         .build()
         .unwrap_or_default();
 
-    let mut task_poll_timer = tokio::time::interval(Duration::from_secs(5));
-    task_poll_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut poll_backoff = PollBackoff::new(
+        Duration::from_millis(config.coordinator.poll_interval_ms),
+        Duration::from_millis(config.coordinator.max_poll_interval_ms),
+    );
+    let mut next_poll = tokio::time::Instant::now() + poll_backoff.interval();
+
 
-    // Track task IDs received via HTTP polling (vs WebSocket)
+    // Track task IDs received via HTTP polling (vs WebSocket), until their
+    // result reaches the coordinator
     let mut http_polled_tasks: std::collections::HashSet<String> = std::collections::HashSet::new();
 
+    // Results are retried, then held here until the coordinator answers again
+    let result_delivery = ResultDelivery::new(http_client.clone(), &coordinator_http_base);
+    let result_handle = client.result_handle();
+    let mut result_buffer = ResultBuffer::new(MAX_BUFFERED_RESULTS);
+    // Deliveries and flushes run on their own tasks and report back here;
+    // only one flush runs at a time
+    let (delivery_tx, mut delivery_rx) = tokio::sync::mpsc::channel::<DeliveryReport>(100);
+    let mut flushing = false;
+
     // Self-register as a peer if account_id and secret_key are configured.
     // This makes the worker visible for HTTP task polling.
+    let peer_listen_addr = peer_mesh.listen_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_else(|| format!("127.0.0.1:{}", config.peer.listen_port));
+    let peer_capabilities = serde_json::json!({
+        "supportedTasks": supported_task_strings,
+        "maxConcurrentTasks": advertised.max_concurrent_tasks,
+        "availableMemoryMb": sys_info.total_memory_mb,
+        "gpuAvailable": false,
+        "maxContextLength": 4096,
+        "workerVersion": env!("CARGO_PKG_VERSION"),
+        "tags": config.worker.tags,
+    });
+    let mut peer_registration = PeerRegistration::new(
+        http_client.clone(),
+        coordinator_http_base.clone(),
+        PeerCredentials::from_settings(&config.worker),
+    );
     let mut coordinator_worker_id = worker_id.clone();
-    if let (Some(account_id), Some(secret_key)) = (&config.worker.account_id, &config.worker.secret_key) {
-        let listen_addr = peer_mesh.listen_addr()
-            .map(|a| a.to_string())
-            .unwrap_or_else(|| format!("127.0.0.1:{}", config.peer.listen_port));
-
-        // Sign canonical auth message: "AI4ALL:v1:{accountId}:{timestamp}"
-        use pqcrypto_dilithium::dilithium3;
-        use pqcrypto_traits::sign::{DetachedSignature, SecretKey as PqSecretKey};
-        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-        let message = format!("AI4ALL:v1:{}:{}", account_id, timestamp);
-        let maybe_sig_hex: Option<String> = (|| {
-            let sk_bytes = hex::decode(secret_key).ok()?;
-            let sk = dilithium3::SecretKey::from_bytes(&sk_bytes).ok()?;
-            let sig = dilithium3::detached_sign(message.as_bytes(), &sk);
-            Some(hex::encode(sig.as_bytes()))
-        })();
-
-        match maybe_sig_hex {
-            None => {
-                warn!("Failed to sign peer registration — check secret_key in config");
-            }
-            Some(sig_hex) => {
-                let register_body = serde_json::json!({
-                    "accountId": account_id,
-                    "timestamp": timestamp,
-                    "signature": sig_hex,
-                    "listenAddr": listen_addr,
-                    "capabilities": {
-                        "supportedTasks": supported_task_strings,
-                        "maxConcurrentTasks": 4,
-                        "availableMemoryMb": sys_info.total_memory_mb,
-                        "gpuAvailable": false,
-                        "maxContextLength": 4096,
-                        "workerVersion": env!("CARGO_PKG_VERSION"),
-                    }
-                });
-
-                let url = format!("{}/peers/register", coordinator_http_base);
-                match http_client.post(&url).json(&register_body).send().await {
-                    Ok(resp) if resp.status().is_success() => {
-                        if let Ok(body) = resp.json::<serde_json::Value>().await {
-                            if let Some(wid) = body["workerId"].as_str() {
-                                coordinator_worker_id = wid.to_string();
-                                info!(
-                                    worker_id = %coordinator_worker_id,
-                                    account_id = %account_id,
-                                    "Registered as peer with coordinator"
-                                );
-                            }
-                        }
-                    }
-                    Ok(resp) => {
-                        let status = resp.status();
-                        let text = resp.text().await.unwrap_or_default();
-                        warn!(
-                            status = %status,
-                            body = %text,
-                            "Peer registration failed (task polling will not work)"
-                        );
-                    }
-                    Err(e) => {
-                        warn!(
-                            error = %e,
-                            "Could not reach coordinator for peer registration"
-                        );
-                    }
-                }
-            }
+    if peer_registration.credentials().is_some() {
+        match peer_registration.register(&peer_listen_addr, &peer_capabilities).await {
+            Ok(wid) => coordinator_worker_id = wid,
+            Err(e) => warn!(error = %e, "Peer registration failed (task polling will not work)"),
         }
     } else {
         info!("No account_id/secret_key configured — skipping peer registration (task polling requires registration)");
@@ -505,7 +745,10 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
     if config.crawler.enabled && !config.crawler.seeds.is_empty() {
         if let (Some(account_id), Some(secret_key)) = (&config.worker.account_id, &config.worker.secret_key) {
             use crate::crawler::CrawlerService;
-            let svc = CrawlerService::new(config.crawler.clone(), config.openai.clone());
+            let svc = CrawlerService::new(config.crawler.clone(), config.openai.clone())
+                .with_denylist(crawl_denylist.clone())
+                .with_rate_limiter(crawl_rate_limiter.clone())
+                .with_egress(crawl_egress.clone());
             svc.start(
                 coordinator_http_base.clone(),
                 account_id.clone(),
@@ -523,15 +766,84 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
         "Worker event loop started"
     );
 
+    let mut last_directory_request: Option<std::time::Instant> = None;
+
+    // Held while a coordinator config update is saved, so overlapping saves
+    // don't interleave their read-modify-write of the config file
+    let config_save_lock = Arc::new(parking_lot::Mutex::new(()));
+
+    // Set when the worker stops because the coordinator stayed unreachable
+    let mut exit_error = None;
+
     // Main event loop
     loop {
         tokio::select! {
             // Ctrl+C shutdown
             _ = &mut shutdown_signal => {
                 info!("Shutdown signal received");
-                if let Err(e) = client.shutdown().await {
+                if let Err(e) = client.shutdown(vec![]).await {
+                    warn!(error = %e, "Error sending shutdown notification");
+                }
+                break;
+            }
+
+            // SIGTERM: stop taking tasks and let running ones finish
+            Some(()) = term_rx.recv(), if !drain.is_draining() => {
+                drain.begin(std::time::Instant::now());
+                executor.stop_accepting();
+                info!(
+                    running = executor.running_count(),
+                    queued = executor.queued_count(),
+                    grace_secs = config.executor.drain_grace_secs,
+                    "SIGTERM received, draining before shutdown"
+                );
+                let _ = client.update_status(WorkerStatus::Draining).await;
+            }
+
+            // Shut down once drained, or when the grace period runs out
+            _ = drain_timer.tick(), if drain.is_draining() => {
+                // Results not yet taken up, on their way, buffered for a
+                // retry or waiting for the coordinator's ack
+                let results_pending = !result_rx.is_empty()
+                    || result_delivery.in_flight() > 0
+                    || !delivery_rx.is_empty()
+                    || flushing
+                    || !result_buffer.is_empty()
+                    || client.unacked_result_count() > 0;
+                let abandoned = match drain.step(
+                    std::time::Instant::now(),
+                    executor.active_tasks(),
+                    results_pending,
+                ) {
+                    DrainStep::Idle | DrainStep::Waiting => continue,
+                    DrainStep::Done => {
+                        info!("All tasks finished, shutting down");
+                        vec![]
+                    }
+                    DrainStep::Expired { abandoned } => {
+                        warn!(
+                            abandoned = ?abandoned,
+                            undelivered_results = result_delivery.in_flight() + result_buffer.len(),
+                            unacked_results = client.unacked_result_count(),
+                            "Drain grace period over, abandoning running tasks"
+                        );
+                        abandoned
+                    }
+                };
+                if let Err(e) = client.shutdown(abandoned).await {
                     warn!(error = %e, "Error sending shutdown notification");
                 }
+                // Results are queued ahead of the shutdown notice; let the
+                // client send them all before the runtime goes away
+                let _ = tokio::time::timeout(Duration::from_secs(5), async {
+                    while !matches!(
+                        client.connection_state(),
+                        ConnectionState::ShuttingDown | ConnectionState::Disconnected
+                    ) {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                    }
+                })
+                .await;
                 break;
             }
 
@@ -559,37 +871,78 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                             "Task assigned"
                         );
 
-                        // Update status to busy
-                        let _ = client.update_status(WorkerStatus::Busy).await;
+                        // Group tasks wait until the group has quorum
+                        let assignment = match assignment.group_id.clone() {
+                            Some(group_id) => match group_manager.admit_task(&group_id, assignment) {
+                                GroupAdmission::Dispatch(assignment) => *assignment,
+                                GroupAdmission::Held { ready, required } => {
+                                    info!(
+                                        task_id = %task_id,
+                                        group = %group_id,
+                                        ready,
+                                        required,
+                                        "Task held until work group reaches quorum"
+                                    );
+                                    continue;
+                                }
+                                GroupAdmission::Rejected(reason) => {
+                                    let e = Error::GroupNotReady { group_id, reason };
+                                    warn!(task_id = %task_id, error = %e, "Rejecting group task");
+                                    let _ = client.submit_result(error_result(task_id, &worker_id, &e)).await;
+                                    continue;
+                                }
+                            },
+                            None => assignment,
+                        };
 
-                        // Submit task to executor
-                        match executor.submit(assignment).await {
-                            Ok(_) => {
-                                debug!(task_id = %task_id, "Task submitted to executor");
-                            }
-                            Err(e) => {
-                                error!(task_id = %task_id, error = %e, "Failed to submit task");
-                                // Send error result back to coordinator
-                                let error_result = protocol::TaskResultMessage {
-                                    task_id,
-                                    worker_id: worker_id.clone(),
-                                    success: false,
-                                    output: None,
-                                    error: Some(protocol::TaskError {
-                                        code: format!("E{}", e.code() as u16),
-                                        message: e.to_string(),
-                                        retryable: e.is_retryable(),
-                                        details: None,
-                                    }),
-                                    metrics: protocol::TaskMetrics::default(),
-                                };
-                                let _ = client.submit_result(error_result).await;
+                        // Offer it to an idle peer while the local queue is saturated.
+                        // A paused worker refuses the task instead.
+                        let depth = config.peer.offload_queue_depth;
+                        let saturated = depth > 0
+                            && !drain.is_draining()
+                            && !executor.is_paused()
+                            && (executor.queued_count() >= depth || !executor.has_capacity());
+                        let target = if saturated && offloader.can_offer(&assignment) {
+                            peer_registry.best_peer_for_offload(task_type)
+                        } else {
+                            None
+                        };
+                        let assignment = match target {
+                            Some(target) => {
+                                let offer = offloader.offer(&target.worker_id, assignment);
+                                match peer_mesh.send(&target.worker_id, offer).await {
+                                    Ok(()) => {
+                                        info!(
+                                            task_id = %task_id,
+                                            peer = %target.worker_id,
+                                            capacity_pct = ?target.capacity_pct,
+                                            "Queue saturated, offering task to peer"
+                                        );
+                                        continue;
+                                    }
+                                    Err(e) => {
+                                        debug!(task_id = %task_id, peer = %target.worker_id, error = %e, "Could not offer task to peer");
+                                        match offloader.withdraw(&task_id) {
+                                            Some(assignment) => assignment,
+                                            None => continue,
+                                        }
+                                    }
+                                }
                             }
+                            None => assignment,
+                        };
+
+                        let mut assignment = assignment;
+                        if let Some(writer) = peer::open_stage_stream(&peer_mesh, &group_manager, &mut assignment) {
+                            pipeline_writers.insert(task_id.clone(), writer);
                         }
+                        submit_task(&executor, &client, &worker_id, assignment).await;
                     }
-                    Some(ClientEvent::TaskCancelled { task_id, reason }) => {
-                        info!(task_id = %task_id, reason = %reason, "Task cancelled by coordinator");
-                        executor.cancel(&task_id);
+                    Some(ClientEvent::TaskCancelled { task_id, reason, force }) => {
+                        let mode = CancelMode::from_force(force);
+                        info!(task_id = %task_id, reason = %reason, mode = %mode, "Task cancelled by coordinator");
+                        offloader.cancel(&task_id);
+                        executor.cancel(&task_id, mode);
                     }
                     Some(ClientEvent::Disconnected { reason }) => {
                         warn!(reason = %reason, "Disconnected from coordinator");
@@ -599,42 +952,45 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                     }
                     Some(ClientEvent::HeartbeatAck) => {
                         debug!("Heartbeat acknowledged");
+                        flush_results(&mut result_buffer, &mut flushing, &result_delivery, &result_handle, &delivery_tx);
                     }
                     Some(ClientEvent::PeerDirectory(peers)) => {
                         info!(count = peers.len(), "Received peer directory");
-                        for entry in &peers {
-                            if entry.worker_id == worker_id {
-                                continue; // Skip self
-                            }
-                            if let Ok(addr) = entry.listen_addr.parse() {
-                                let peer_info = peer::PeerInfo {
-                                    worker_id: entry.worker_id.clone(),
-                                    name: entry.name.clone(),
-                                    listen_addr: addr,
-                                    capabilities: entry.capabilities.clone(),
-                                    status: WorkerStatus::Ready,
-                                    last_seen: std::time::Instant::now(),
-                                    latency_ms: None,
-                                    groups: vec![],
-                                };
-                                peer_registry.register(peer_info);
+                        let import = peer_registry.register_directory(&peers, &worker_id);
+                        let malformed = import.malformed.len() as u32;
+                        if malformed > 0 {
+                            warn!(
+                                malformed,
+                                registered = import.registered,
+                                "Peer directory had entries with invalid addresses"
+                            );
+                            let min_malformed = config.peer.directory_refetch_min_malformed;
+                            let due = last_directory_request
+                                .is_none_or(|at| at.elapsed() >= DIRECTORY_REFETCH_INTERVAL);
+                            if min_malformed > 0 && malformed >= min_malformed && due {
+                                last_directory_request = Some(std::time::Instant::now());
+                                info!(malformed, "Requesting a fresh peer directory");
+                                if let Err(e) = client.request_peer_directory(malformed).await {
+                                    warn!(error = %e, "Failed to request peer directory");
+                                }
                             }
                         }
                         // Auto-connect to discovered peers if enabled
                         if config.peer.auto_connect {
-                            let all_peers = peer_registry.all_peers();
-                            let mesh = peer_mesh.clone();
-                            tokio::spawn(async move {
-                                for p in &all_peers {
-                                    if let Err(e) = mesh.connect(p).await {
+                            // One task per peer so a peer being retried
+                            // doesn't hold up the others
+                            for p in peer_registry.all_peers() {
+                                let mesh = peer_mesh.clone();
+                                tokio::spawn(async move {
+                                    if let Err(e) = mesh.connect_with_retry(&p).await {
                                         debug!(
                                             peer = %p.worker_id,
                                             error = %e,
                                             "Failed to connect to peer"
                                         );
                                     }
-                                }
-                            });
+                                });
+                            }
                         }
                     }
                     Some(ClientEvent::PeerDiscovered(entry)) => {
@@ -649,13 +1005,15 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                                     status: WorkerStatus::Ready,
                                     last_seen: std::time::Instant::now(),
                                     latency_ms: None,
+                                    stale: false,
+                                    capacity_pct: None,
                                     groups: vec![],
                                 };
                                 peer_registry.register(peer_info.clone());
                                 if config.peer.auto_connect {
                                     let mesh = peer_mesh.clone();
                                     tokio::spawn(async move {
-                                        if let Err(e) = mesh.connect(&peer_info).await {
+                                        if let Err(e) = mesh.connect_with_retry(&peer_info).await {
                                             debug!(error = %e, "Failed to connect to new peer");
                                         }
                                     });
@@ -703,15 +1061,119 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                                     shard_index: m.shard_index,
                                     pipeline_stage: m.pipeline_stage.map(|s| s as usize),
                                     ready: false,
+                                    streaming: false,
                                 }
                             }).collect(),
                             created_at: chrono::Utc::now(),
                         };
                         group_manager.add_group(group);
+
+                        // Join handshake with connected members; advertises
+                        // that this worker accepts streamed pipeline chunks
+                        let role = group_msg.members.iter()
+                            .find(|m| m.worker_id == worker_id)
+                            .map(|m| m.role.clone())
+                            .unwrap_or_else(|| "member".to_string());
+                        for member in group_msg.members.iter().filter(|m| m.worker_id != worker_id) {
+                            let join = PeerMessage::GroupJoin {
+                                group_id: group_msg.group_id.clone(),
+                                role: role.clone(),
+                                streaming: true,
+                            };
+                            if let Err(e) = peer_mesh.send(&member.worker_id, join).await {
+                                debug!(peer = %member.worker_id, error = %e, "Group join not sent");
+                            }
+                        }
+                    }
+                    Some(ClientEvent::ConfigUpdate(update)) => {
+                        info!(persist = update.persist, "Configuration update received from coordinator");
+                        debug!(config = %update.config, "New config values");
+                        let outcome = match config.apply_update(&update.config) {
+                            Ok(outcome) => outcome,
+                            Err(e) => {
+                                warn!(error = %e, "Rejected configuration update");
+                                continue;
+                            }
+                        };
+                        for setting in &outcome.rejected {
+                            warn!(setting = %setting, "Setting needs a restart; ignoring coordinator update");
+                        }
+                        for setting in &outcome.applied {
+                            match setting.as_str() {
+                                "logging.level" => match logging::set_level(&config.logging.level) {
+                                    Ok(()) => info!(level = %config.logging.level, "Log level changed"),
+                                    Err(e) => warn!(error = %e, "Failed to change log level"),
+                                },
+                                "coordinator.heartbeat_interval_ms" => client.set_heartbeat_interval(
+                                    Duration::from_millis(config.coordinator.heartbeat_interval_ms),
+                                ),
+                                "resources.max_concurrent_tasks" => {
+                                    refresh_capabilities(&registry, &config, &client, &mut advertised).await;
+                                    executor.set_max_concurrent_tasks(advertised.max_concurrent_tasks as usize);
+                                }
+                                "crawler.domain_denylist" => {
+                                    crawl_denylist.replace(&config.crawler.domain_denylist);
+                                    info!(domains = config.crawler.domain_denylist.len(), "Crawl denylist updated");
+                                }
+                                _ => {}
+                            }
+                        }
+                        if update.persist && !outcome.applied.is_empty() {
+                            let (path, lock) = (config_path.clone(), config_save_lock.clone());
+                            tokio::task::spawn_blocking(move || {
+                                let _saving = lock.lock();
+                                match WorkerConfig::persist_update(path.as_deref(), &update.config) {
+                                    Ok(path) => info!(path = %path.display(), "Configuration update saved"),
+                                    Err(e) => warn!(error = %e, "Failed to save configuration update"),
+                                }
+                            });
+                        }
                     }
-                    Some(ClientEvent::ConfigUpdate(new_config)) => {
-                        info!("Configuration update received from coordinator");
-                        debug!(config = %new_config, "New config values");
+                    Some(ClientEvent::Paused) => {
+                        executor.set_paused(PauseSource::Coordinator, true);
+                        info!(running = executor.running_count(), "Paused by coordinator, finishing in-flight tasks");
+                    }
+                    Some(ClientEvent::Resumed) => {
+                        executor.set_paused(PauseSource::Coordinator, false);
+                        if executor.is_paused() {
+                            info!("Resumed by coordinator, but still paused by the operator");
+                        } else {
+                            info!("Resumed by coordinator");
+                        }
+                    }
+                    Some(ClientEvent::ShutdownRequested { reason }) => {
+                        if drain.is_draining() {
+                            continue;
+                        }
+                        drain.begin(std::time::Instant::now());
+                        executor.stop_accepting();
+                        info!(
+                            reason = %reason,
+                            running = executor.running_count(),
+                            queued = executor.queued_count(),
+                            grace_secs = config.executor.drain_grace_secs,
+                            "Shutdown requested by coordinator, draining"
+                        );
+                        let _ = client.update_status(WorkerStatus::Draining).await;
+                    }
+                    Some(ClientEvent::ResultDeadLettered(task_result)) => {
+                        warn!(
+                            task_id = %task_result.task_id,
+                            success = task_result.success,
+                            "Connection dropped before coordinator acknowledged task result, buffering it"
+                        );
+                        buffer_result(&mut result_buffer, &mut http_polled_tasks, PendingResult::WebSocket(Box::new(task_result)));
+                    }
+                    Some(ClientEvent::GaveUp { failed_for }) => {
+                        exit_error = Some(Error::CoordinatorUnreachable {
+                            url: config.coordinator.url.clone(),
+                            failed_secs: failed_for.as_secs(),
+                        });
+                        break;
+                    }
+                    Some(ClientEvent::AuthenticationFailed { message }) => {
+                        exit_error = Some(Error::AuthenticationFailed { message });
+                        break;
                     }
                     Some(ClientEvent::Error { message, fatal }) => {
                         if fatal {
@@ -728,18 +1190,81 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                 }
             }
 
+            // Tokens generated by streaming tasks; a pipeline stage's go to
+            // the next stage
+            Some(progress) = progress_rx.recv() => {
+                if let Some(writer) = pipeline_writers.get_mut(&progress.task_id) {
+                    if let Err(e) = writer.send(progress.delta.into_bytes()).await {
+                        debug!(task_id = %progress.task_id, error = %e, "Failed to stream pipeline output");
+                    }
+                } else if config.executor.stream_progress {
+                    if let Err(e) = client.send_task_progress(progress).await {
+                        debug!(error = %e, "Failed to forward task progress");
+                    }
+                }
+            }
+
+            // Finished result deliveries and flushes
+            Some(report) = delivery_rx.recv() => {
+                delivery_done(report, &mut result_buffer, &mut flushing, &mut http_polled_tasks);
+            }
+
             // Task results from executor
             result = result_rx.recv() => {
                 match result {
                     Some(task_result) => {
-                        let is_http_task = http_polled_tasks.remove(&task_result.task_id);
-                        info!(
-                            task_id = %task_result.task_id,
+                        // Nobody is waiting for a task run for a peer that left
+                        if offloader.take_orphaned(&task_result) {
+                            debug!(task_id = %task_result.task_id, "Dropping result of a task for a disconnected peer");
+                            continue;
+                        }
+                        // A task run for a peer goes back to that peer
+                        if let Some((peer_id, reply)) = offloader.finished(&task_result) {
+                            info!(
+                                task_id = %task_result.task_id,
+                                peer = %peer_id,
+                                success = task_result.success,
+                                "Peer task completed, returning it to the peer"
+                            );
+                            if let Err(e) = peer_mesh.send(&peer_id, reply).await {
+                                warn!(task_id = %task_result.task_id, peer = %peer_id, error = %e, "Could not return task result to peer");
+                            }
+                            continue;
+                        }
+                        // A pipeline stage's output goes on to the next stage;
+                        // the last stage reports the task. Failures are
+                        // reported here.
+                        if let Some(mut writer) = pipeline_writers.remove(&task_result.task_id) {
+                            let text = task_result
+                                .output
+                                .as_ref()
+                                .filter(|_| task_result.success)
+                                .and_then(peer::stage_output_text);
+                            if let Some(text) = text {
+                                match writer.finish_output(text.as_bytes()).await {
+                                    Ok(()) => {
+                                        info!(task_id = %task_result.task_id, "Pipeline stage output handed to the next stage");
+                                        continue;
+                                    }
+                                    Err(e) => {
+                                        warn!(task_id = %task_result.task_id, error = %e, "Could not hand pipeline stage output to the next stage");
+                                    }
+                                }
+                            }
+                        }
+                        // A failed task may have opened a backend's breaker
+                        if !task_result.success {
+                            refresh_capabilities(&registry, &config, &client, &mut advertised).await;
+                        }
+                        let is_http_task = http_polled_tasks.contains(&task_result.task_id);
+                        // Same span as the executor's, so delivery logs correlate
+                        let span = info_span!("task", task_id = %task_result.task_id);
+                        span.in_scope(|| info!(
                             success = task_result.success,
                             execution_ms = task_result.metrics.execution_time_ms,
                             source = if is_http_task { "http" } else { "ws" },
                             "Task completed"
-                        );
+                        ));
 
                         if is_http_task {
                             // POST result back to coordinator via HTTP task API
@@ -764,35 +1289,35 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                                 "error": task_result.error.as_ref().map(|e| &e.message),
                             });
 
-                            let url = format!("{}/tasks/complete", coordinator_http_base);
-                            match http_client.post(&url).json(&complete_body).send().await {
-                                Ok(resp) if resp.status().is_success() => {
-                                    info!(task_id = %task_result.task_id, "HTTP task result posted");
-                                }
-                                Ok(resp) => {
-                                    warn!(
-                                        task_id = %task_result.task_id,
-                                        status = %resp.status(),
-                                        "Failed to post HTTP task result"
-                                    );
-                                }
-                                Err(e) => {
-                                    error!(
-                                        task_id = %task_result.task_id,
-                                        error = %e,
-                                        "Failed to post HTTP task result"
-                                    );
-                                }
-                            }
+                            deliver_result(
+                                &result_delivery,
+                                &result_handle,
+                                &delivery_tx,
+                                PendingResult::Http {
+                                    task_id: task_result.task_id.clone(),
+                                    body: complete_body,
+                                },
+                                span,
+                            );
                         } else {
                             // Forward result to coordinator via WebSocket
-                            if let Err(e) = client.submit_result(task_result).await {
-                                error!(error = %e, "Failed to submit task result");
-                            }
+                            deliver_result(
+                                &result_delivery,
+                                &result_handle,
+                                &delivery_tx,
+                                PendingResult::WebSocket(Box::new(task_result)),
+                                span,
+                            );
                         }
 
+                        // Keep the declined model list current for heartbeats
+                        client.set_declined_models(executor.declined_models());
+
                         // Update status based on remaining work
-                        if executor.running_count() == 0 && executor.queued_count() == 0 {
+                        if !drain.is_draining()
+                            && executor.running_count() == 0
+                            && executor.queued_count() == 0
+                        {
                             let _ = client.update_status(WorkerStatus::Ready).await;
                         }
                     }
@@ -804,31 +1329,164 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
 
             // Events from peer mesh
             peer_event = peer_event_rx.recv() => {
+                if let (Some(recorder), Some(event)) = (&recorder, &peer_event) {
+                    recorder.record_peer_event(event);
+                }
                 match peer_event {
                     Some(PeerEvent::Connected { worker_id: peer_id }) => {
                         info!(peer = %peer_id, "Peer connected");
                     }
                     Some(PeerEvent::Disconnected { worker_id: peer_id, reason }) => {
                         info!(peer = %peer_id, reason = %reason, "Peer disconnected");
+                        let loss = offloader.peer_lost(&peer_id);
+                        for assignment in loss.reclaimed {
+                            info!(task_id = %assignment.task_id, peer = %peer_id, "Peer lost with offloaded task, running it locally");
+                            submit_task(&executor, &client, &worker_id, assignment).await;
+                        }
+                        for task_id in loss.orphaned {
+                            info!(task_id = %task_id, peer = %peer_id, "Peer lost while running its task, cancelling it");
+                            executor.cancel(&task_id, CancelMode::Forced);
+                        }
                     }
                     Some(PeerEvent::MessageReceived { from, message }) => {
                         match message {
-                            PeerMessage::PeerStatus { status, .. } => {
-                                debug!(peer = %from, status = ?status, "Peer status update");
+                            PeerMessage::PeerStatus { status, capacity_pct, .. } => {
+                                debug!(peer = %from, status = ?status, capacity_pct, "Peer status update");
                                 peer_registry.update_status(&from, status);
+                                peer_registry.update_capacity(&from, capacity_pct);
+                            }
+                            PeerMessage::TaskOffer { task_id, task_type, priority } => {
+                                let has_room = !drain.is_draining()
+                                    && !executor.is_paused()
+                                    && advertised.supported_tasks.contains(&task_type)
+                                    && executor.queued_count() == 0
+                                    && executor.running_count() + offloader.incoming_count()
+                                        < advertised.max_concurrent_tasks as usize;
+                                let reply = offloader.answer_offer(
+                                    &from,
+                                    &task_id,
+                                    has_room,
+                                    executor.is_tracked(&task_id),
+                                );
+                                info!(
+                                    peer = %from,
+                                    task_id = %task_id,
+                                    task_type = %task_type,
+                                    priority,
+                                    reply = %reply.type_name(),
+                                    "Task offered by peer"
+                                );
+                                if let Err(e) = peer_mesh.send(&from, reply).await {
+                                    warn!(peer = %from, task_id = %task_id, error = %e, "Could not answer task offer");
+                                    offloader.abandon(&task_id, "");
+                                }
+                            }
+                            PeerMessage::TaskAccept { task_id } => match offloader.accepted(&from, &task_id) {
+                                Ok(data) => {
+                                    info!(peer = %from, task_id = %task_id, "Peer accepted task, forwarding it");
+                                    if let Err(e) = peer_mesh.send(&from, data).await {
+                                        warn!(peer = %from, task_id = %task_id, error = %e, "Could not forward task, running it locally");
+                                        if let Some(assignment) = offloader.rejected(&from, &task_id) {
+                                            submit_task(&executor, &client, &worker_id, assignment).await;
+                                        }
+                                    }
+                                }
+                                Err(reject) => {
+                                    debug!(peer = %from, task_id = %task_id, "Peer accepted an expired or unknown offer, turning it down");
+                                    let _ = peer_mesh.send(&from, reject).await;
+                                }
+                            },
+                            PeerMessage::TaskReject { task_id, reason } => {
+                                if let Some(assignment) = offloader.rejected(&from, &task_id) {
+                                    info!(peer = %from, task_id = %task_id, reason = %reason, "Peer rejected task, running it locally");
+                                    submit_task(&executor, &client, &worker_id, assignment).await;
+                                }
+                            }
+                            PeerMessage::TaskData { task_id, data } => {
+                                let failure = match offloader.task_data(&from, &task_id, &data) {
+                                    Some(Ok(assignment)) => {
+                                        info!(peer = %from, task_id = %task_id, "Running task for peer");
+                                        executor.submit(assignment).await.err().map(|e| e.to_string())
+                                    }
+                                    Some(Err(e)) => Some(format!("invalid task data: {}", e)),
+                                    None => {
+                                        debug!(peer = %from, task_id = %task_id, "Task data for a task not accepted");
+                                        None
+                                    }
+                                };
+                                if let Some((peer_id, reply)) =
+                                    failure.and_then(|reason| offloader.abandon(&task_id, reason))
+                                {
+                                    warn!(peer = %peer_id, task_id = %task_id, "Could not run task for peer");
+                                    let _ = peer_mesh.send(&peer_id, reply).await;
+                                }
+                            }
+                            PeerMessage::TaskResultForward { task_id, output } => {
+                                if let Some(task_result) = offloader.completed(&from, &task_id, output) {
+                                    info!(peer = %from, task_id = %task_id, "Offloaded task completed by peer");
+                                    deliver_result(
+                                        &result_delivery,
+                                        &result_handle,
+                                        &delivery_tx,
+                                        PendingResult::WebSocket(Box::new(task_result)),
+                                        tracing::Span::current(),
+                                    );
+                                }
                             }
                             PeerMessage::Ping { seq } => {
                                 debug!(peer = %from, seq, "Peer ping");
-                                // Pong is handled by the mesh read loop
+                                // Pongs are sent and received by the mesh
                             }
-                            PeerMessage::GroupJoin { group_id, role } => {
+                            PeerMessage::GroupJoin { group_id, role, streaming } => {
                                 let role = if role == "coordinator" {
                                     GroupRole::Coordinator
                                 } else {
                                     GroupRole::Member
                                 };
                                 group_manager.add_member(&group_id, &from, role);
-                                info!(peer = %from, group = %group_id, "Peer joined group");
+                                group_manager.set_member_streaming(&group_id, &from, streaming);
+                                info!(peer = %from, group = %group_id, streaming, "Peer joined group");
+                            }
+                            PeerMessage::PipelineChunk { group_id, stage, task_id, seq, data, is_final, task } => {
+                                // Only the stage before ours feeds us
+                                if !peer::from_upstream_stage(&group_manager, &group_id, &from, stage) {
+                                    warn!(peer = %from, group = %group_id, task_id = %task_id, stage, "Dropping pipeline chunk from a peer that isn't our upstream stage");
+                                    continue;
+                                }
+                                let progress = match pipeline_streams.push(&group_id, &task_id, seq, data, is_final, task) {
+                                    Ok(progress) => progress,
+                                    Err(e) => {
+                                        warn!(peer = %from, group = %group_id, task_id = %task_id, seq, error = %e, "Dropping pipeline chunk");
+                                        continue;
+                                    }
+                                };
+                                debug!(
+                                    peer = %from,
+                                    group = %group_id,
+                                    task_id = %task_id,
+                                    stage,
+                                    seq,
+                                    released_bytes = progress.data.len(),
+                                    "Pipeline chunk received"
+                                );
+                                if let Some((task, payload)) = progress.payload {
+                                    info!(peer = %from, group = %group_id, task_id = %task_id, stage, "Pipeline stream complete");
+                                    // Our stage runs on the upstream stage's output
+                                    let text = String::from_utf8_lossy(&payload).into_owned();
+                                    let Some(mut assignment) = peer::next_stage_assignment(&group_manager, &group_id, &task_id, task, text) else {
+                                        let e = Error::NotSupported(format!(
+                                            "Pipeline stage {} of group {} can't run on streamed text",
+                                            stage + 1, group_id
+                                        ));
+                                        warn!(task_id = %task_id, error = %e, "Dropping pipeline stream");
+                                        let _ = client.submit_result(error_result(task_id, &worker_id, &e)).await;
+                                        continue;
+                                    };
+                                    if let Some(writer) = peer::open_stage_stream(&peer_mesh, &group_manager, &mut assignment) {
+                                        pipeline_writers.insert(task_id, writer);
+                                    }
+                                    submit_task(&executor, &client, &worker_id, assignment).await;
+                                }
                             }
                             PeerMessage::GroupLeave { group_id } => {
                                 group_manager.remove_member(&group_id, &from);
@@ -842,8 +1500,16 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                                     shard = shard_index,
                                     "Peer shard ready"
                                 );
-                                if group_manager.all_members_ready(&group_id) {
-                                    info!(group = %group_id, "All shards ready");
+                                if group_manager.quorum_met(&group_id) {
+                                    let released = group_manager.take_released_tasks(&group_id);
+                                    info!(
+                                        group = %group_id,
+                                        released = released.len(),
+                                        "Shard group reached quorum"
+                                    );
+                                    for assignment in released {
+                                        submit_task(&executor, &client, &worker_id, assignment).await;
+                                    }
                                 }
                             }
                             _ => {
@@ -858,6 +1524,9 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                     Some(PeerEvent::ListenerReady { addr }) => {
                         info!(addr = %addr, "Peer mesh listener ready");
                     }
+                    Some(PeerEvent::Stale { worker_id: peer_id, missed_pings }) => {
+                        warn!(peer = %peer_id, missed_pings, "Peer marked stale");
+                    }
                     Some(PeerEvent::Error { worker_id: peer_id, error }) => {
                         warn!(peer = ?peer_id, error = %error, "Peer error");
                     }
@@ -867,22 +1536,78 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                 }
             }
 
+            // Config reloaded on SIGHUP: pick up new credentials
+            Some(new_config) = reload_rx.recv() => {
+                if new_config.coordinator.reconnect_on_credential_change {
+                    let credentials = PeerCredentials::from_settings(&new_config.worker);
+                    if let Some(outcome) = peer_registration
+                        .reload(credentials, &peer_listen_addr, &peer_capabilities)
+                        .await
+                    {
+                        info!("Credentials changed, re-registering with coordinator");
+                        match outcome {
+                            Ok(wid) => coordinator_worker_id = wid,
+                            Err(e) => warn!(error = %e, "Re-registration with new credentials failed"),
+                        }
+                        if let Err(e) = client.reconnect().await {
+                            warn!(error = %e, "Failed to re-establish coordinator connection");
+                        }
+                    }
+                }
+            }
+
             // HTTP task polling (on-demand task API)
-            _ = task_poll_timer.tick() => {
+            _ = tokio::time::sleep_until(next_poll), if !drain.is_draining() => {
+                // No free slot: check again later without counting it as idle
+                next_poll = tokio::time::Instant::now() + poll_backoff.interval();
                 if executor.can_accept() {
+                    let mut found_tasks = false;
+                    let mut submitted = 0;
+                    let limit = poll_limit(
+                        advertised.max_concurrent_tasks as usize,
+                        executor.running_count(),
+                        executor.queued_count(),
+                    );
                     let url = format!(
-                        "{}/tasks/pending?workerId={}&limit=1",
-                        coordinator_http_base, coordinator_worker_id
+                        "{}/tasks/pending?workerId={}&limit={}",
+                        coordinator_http_base, coordinator_worker_id, limit
                     );
                     match http_client.get(&url).send().await {
                         Ok(resp) if resp.status().is_success() => {
+                            // The coordinator is answering again
+                            flush_results(&mut result_buffer, &mut flushing, &result_delivery, &result_handle, &delivery_tx);
                             if let Ok(body) = resp.json::<serde_json::Value>().await {
                                 if let Some(tasks) = body["tasks"].as_array() {
-                                    for task_json in tasks {
+                                    found_tasks = !tasks.is_empty();
+                                    if tasks.len() > limit {
+                                        warn!(returned = tasks.len(), limit, "Coordinator returned more tasks than requested, handing the rest back");
+                                    }
+                                    for task_id in tasks.iter().skip(limit).filter_map(|t| t["taskId"].as_str()) {
+                                        if !http_polled_tasks.insert(task_id.to_string()) {
+                                            continue;
+                                        }
+                                        deliver_result(
+                                            &result_delivery,
+                                            &result_handle,
+                                            &delivery_tx,
+                                            PendingResult::Http {
+                                                task_id: task_id.to_string(),
+                                                body: release_body(&coordinator_worker_id, task_id),
+                                            },
+                                            info_span!("task", task_id = %task_id),
+                                        );
+                                    }
+                                    for task_json in tasks.iter().take(limit) {
                                         if let (Some(task_id), Some(prompt)) = (
                                             task_json["taskId"].as_str(),
                                             task_json["prompt"].as_str(),
                                         ) {
+                                            // Already running, or its result is still being delivered
+                                            if http_polled_tasks.contains(task_id) {
+                                                debug!(task_id = %task_id, "HTTP-polled task already in progress, skipping");
+                                                continue;
+                                            }
+
                                             let model = task_json["model"]
                                                 .as_str()
                                                 .unwrap_or("default");
@@ -929,6 +1654,16 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                                                 is_canary: false,
                                                 expected_hash: None,
                                                 timeout_secs: 300,
+                                                group_id: None,
+                                                stream: false,
+                                                required_tags: task_json["requiredTags"]
+                                                    .as_array()
+                                                    .map(|tags| {
+                                                        tags.iter()
+                                                            .filter_map(|t| t.as_str().map(str::to_string))
+                                                            .collect()
+                                                    })
+                                                    .unwrap_or_default(),
                                             };
 
                                             // Track as HTTP-polled task
@@ -941,11 +1676,10 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                                                 "HTTP-polled task received"
                                             );
 
-                                            let _ = client.update_status(WorkerStatus::Busy).await;
-
                                             match executor.submit(assignment).await {
                                                 Ok(_) => {
                                                     debug!(task_id = %task_id, "HTTP task submitted to executor");
+                                                    submitted += 1;
                                                 }
                                                 Err(e) => {
                                                     error!(task_id = %task_id, error = %e, "Failed to submit HTTP task");
@@ -954,6 +1688,9 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                                             }
                                         }
                                     }
+                                    if submitted > 0 {
+                                        let _ = client.update_status(WorkerStatus::Busy).await;
+                                    }
                                 }
                             }
                         }
@@ -964,11 +1701,62 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                             debug!(error = %e, "Task poll request failed");
                         }
                     }
+                    next_poll = tokio::time::Instant::now() + poll_backoff.next_interval(found_tasks);
+                }
+            }
+
+            // Tell peers how much room this worker has; run offers nobody
+            // answered locally
+            _ = peer_status_timer.tick(), if config.peer.enabled => {
+                let max = advertised.max_concurrent_tasks as usize;
+                let used = executor.running_count() + executor.queued_count() + offloader.incoming_count();
+                let free = max.saturating_sub(used);
+                let status = if drain.is_draining() {
+                    WorkerStatus::Draining
+                } else if free > 0 {
+                    WorkerStatus::Ready
+                } else {
+                    WorkerStatus::Busy
+                };
+                let capacity_pct = if max > 0 { free as f32 / max as f32 } else { 0.0 };
+                peer_mesh
+                    .broadcast(PeerMessage::PeerStatus { status, active_tasks: used as u32, capacity_pct })
+                    .await;
+                for assignment in offloader.expired(peer::OFFER_TIMEOUT) {
+                    info!(task_id = %assignment.task_id, "Offloaded task not answered by peer, running it locally");
+                    submit_task(&executor, &client, &worker_id, assignment).await;
+                }
+            }
+
+            // Disband work groups that never assembled
+            _ = group_ready_timer.tick() => {
+                for (group_id, held) in group_manager.disband_unassembled() {
+                    warn!(
+                        group = %group_id,
+                        held_tasks = held.len(),
+                        "Work group did not reach quorum in time, disbanding"
+                    );
+                    for assignment in held {
+                        let e = Error::GroupNotReady {
+                            group_id: group_id.clone(),
+                            reason: format!(
+                                "quorum not reached within {}ms",
+                                config.peer.group_ready_timeout_ms
+                            ),
+                        };
+                        let _ = client
+                            .submit_result(error_result(assignment.task_id, &worker_id, &e))
+                            .await;
+                    }
                 }
             }
 
             // Periodic health check
             _ = health_timer.tick() => {
+                // Drop models whose decline cooldown has expired
+                client.set_declined_models(executor.declined_models());
+                // Re-advertise backends whose breaker is ready for a probe
+                refresh_capabilities(&registry, &config, &client, &mut advertised).await;
                 if !health_monitor.is_healthy() {
                     let status = health_monitor.health_status();
                     warn!(
@@ -978,13 +1766,24 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
                 }
             }
 
+            // Free memory by evicting idle models when over the limit
+            _ = memory_pressure_timer.tick(), if config.resources.memory_pressure_mb > 0 => {
+                executor.relieve_memory_pressure(&health_monitor).await;
+            }
+
             // Periodic cleanup of completed tasks from tracker
             _ = cleanup_timer.tick() => {
                 executor.tracker().cleanup_old_tasks(100);
+                // Upstream stages that stopped streaming have gone idle
+                let expired = pipeline_streams.expire(PIPELINE_STREAM_IDLE);
+                if expired > 0 {
+                    debug!(expired, "Dropped unfinished pipeline streams");
+                }
                 debug!(
                     completed = executor.completed_count(),
                     failed = executor.failed_count(),
                     running = executor.running_count(),
+                    queued = ?executor.queued_by_priority(),
                     "Task tracker cleanup"
                 );
             }
@@ -1002,7 +1801,317 @@ async fn async_worker_main(config: WorkerConfig) -> Result<()> {
     // Shut down peer mesh
     peer_mesh.shutdown();
 
-    Ok(())
+    match exit_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Run shard inputs from group peers through this worker's shard, one at a
+/// time, returning each output to its sender. Only a member holding one of
+/// the group's shards may send inputs.
+async fn serve_shard_requests(
+    mesh: Arc<PeerMesh>,
+    mut requests: tokio::sync::mpsc::Receiver<ShardRequest>,
+    executor: Arc<TaskExecutor>,
+    groups: Arc<GroupManager>,
+) {
+    while let Some(mut request) = requests.recv().await {
+        let tensor_data = std::mem::take(&mut request.tensor_data);
+        let group = groups.get_group(&request.group_id);
+        let sender_holds_shard = group.as_ref().is_some_and(|g| {
+            g.members
+                .iter()
+                .any(|m| m.worker_id == request.from && m.shard_index.is_some())
+        });
+        let result = match group.map(|g| g.purpose) {
+            Some(_) if !sender_holds_shard => Err(Error::Protocol(format!(
+                "{} holds no shard of group {}",
+                request.from, request.group_id
+            ))),
+            Some(GroupPurpose::ModelShard { model_id, .. }) => {
+                executor
+                    .forward_shard_layers(&model_id, request.layer_start, tensor_data)
+                    .await
+            }
+            _ => Err(Error::GroupNotReady {
+                group_id: request.group_id.clone(),
+                reason: "not a model shard group this worker belongs to".to_string(),
+            }),
+        };
+        if let Err(e) = &result {
+            warn!(peer = %request.from, group = %request.group_id, task_id = %request.task_id, error = %e, "Shard forward failed");
+        }
+        let from = request.from.clone();
+        if let Err(e) = request.respond(&mesh, result).await {
+            debug!(peer = %from, error = %e, "Failed to return shard output");
+        }
+    }
+}
+
+/// Submit a task to the executor, reporting a failed submission back to the
+/// coordinator
+async fn submit_task(
+    executor: &TaskExecutor,
+    client: &CoordinatorClient,
+    worker_id: &str,
+    assignment: protocol::TaskAssignmentMessage,
+) {
+    let task_id = assignment.task_id.clone();
+    match executor.submit(assignment).await {
+        Ok(_) => {
+            debug!(task_id = %task_id, "Task submitted to executor");
+            let _ = client.update_status(WorkerStatus::Busy).await;
+        }
+        // The task already runs here; a failure result would end it upstream
+        Err(e @ Error::DuplicateTask { .. }) => {
+            warn!(task_id = %task_id, error = %e, "Ignoring duplicate task assignment");
+        }
+        Err(e) => {
+            if matches!(e, Error::QueueFull { .. }) {
+                // Backpressure: tell the coordinator to hold off
+                warn!(task_id = %task_id, error = %e, "Executor full, rejecting task");
+                let _ = client.update_status(WorkerStatus::Busy).await;
+            } else {
+                error!(task_id = %task_id, error = %e, "Failed to submit task");
+            }
+            // Send error result back to coordinator
+            let _ = client.submit_result(error_result(task_id, worker_id, &e)).await;
+        }
+    }
+}
+
+/// Outcome of a delivery or flush run by [`deliver_result`] or
+/// [`flush_results`], handled back on the main loop
+enum DeliveryReport {
+    /// One result was delivered, or failed for good or for now
+    Sent { result: PendingResult, outcome: Result<()> },
+    /// A flush finished; `remaining` could still not be delivered
+    Flushed { delivered: Vec<PendingResult>, remaining: ResultBuffer },
+}
+
+/// Deliver a task result on a spawned task, reporting the outcome to
+/// `reports`
+fn deliver_result(
+    delivery: &ResultDelivery,
+    client: &ResultHandle,
+    reports: &tokio::sync::mpsc::Sender<DeliveryReport>,
+    result: PendingResult,
+    span: tracing::Span,
+) {
+    let delivery = delivery.clone();
+    let client = client.clone();
+    let reports = reports.clone();
+    tokio::spawn(
+        async move {
+            let outcome = delivery.deliver(&client, &result).await;
+            let _ = reports.send(DeliveryReport::Sent { result, outcome }).await;
+        }
+        .instrument(span),
+    );
+}
+
+/// Record a finished delivery or flush, buffering results that can be
+/// retried later
+fn delivery_done(
+    report: DeliveryReport,
+    buffer: &mut ResultBuffer,
+    flushing: &mut bool,
+    http_polled_tasks: &mut std::collections::HashSet<String>,
+) {
+    match report {
+        DeliveryReport::Sent { result, outcome: Ok(()) } => {
+            if let PendingResult::Http { task_id, .. } = &result {
+                http_polled_tasks.remove(task_id);
+                info!(task_id = %task_id, "HTTP task result posted");
+            }
+        }
+        DeliveryReport::Sent { result, outcome: Err(e) } if e.is_retryable() => {
+            warn!(task_id = %result.task_id(), error = %e, "Could not deliver task result, buffering it");
+            buffer_result(buffer, http_polled_tasks, result);
+        }
+        DeliveryReport::Sent { result, outcome: Err(e) } => {
+            error!(task_id = %result.task_id(), error = %e, "Failed to submit task result");
+            http_polled_tasks.remove(result.task_id());
+        }
+        DeliveryReport::Flushed { delivered, remaining } => {
+            *flushing = false;
+            for result in delivered.iter().chain(&buffer.prepend(remaining)) {
+                http_polled_tasks.remove(result.task_id());
+            }
+            info!(delivered = delivered.len(), remaining = buffer.len(), "Re-sent buffered task results");
+        }
+    }
+}
+
+/// Queue an undelivered result, forgetting any HTTP task it pushes out
+fn buffer_result(
+    buffer: &mut ResultBuffer,
+    http_polled_tasks: &mut std::collections::HashSet<String>,
+    result: PendingResult,
+) {
+    if let Some(PendingResult::Http { task_id, .. }) = buffer.push(result) {
+        http_polled_tasks.remove(&task_id);
+    }
+}
+
+/// Re-send buffered results on a spawned task now that the coordinator is
+/// reachable, unless a flush is already running
+fn flush_results(
+    buffer: &mut ResultBuffer,
+    flushing: &mut bool,
+    delivery: &ResultDelivery,
+    client: &ResultHandle,
+    reports: &tokio::sync::mpsc::Sender<DeliveryReport>,
+) {
+    if buffer.is_empty() || *flushing {
+        return;
+    }
+    *flushing = true;
+    let mut pending = buffer.take();
+    let delivery = delivery.clone();
+    let client = client.clone();
+    let reports = reports.clone();
+    tokio::spawn(async move {
+        let delivered = pending
+            .flush(|result| {
+                let (delivery, client) = (&delivery, &client);
+                async move { delivery.deliver(client, &result).await }
+            })
+            .await;
+        let _ = reports
+            .send(DeliveryReport::Flushed { delivered, remaining: pending })
+            .await;
+    });
+}
+
+/// Failed task result carrying `e`
+fn error_result(task_id: String, worker_id: &str, e: &Error) -> protocol::TaskResultMessage {
+    protocol::TaskResultMessage {
+        task_id,
+        worker_id: worker_id.to_string(),
+        success: false,
+        output: None,
+        error: Some(protocol::TaskError {
+            code: format!("E{}", e.code() as u16),
+            message: e.to_string(),
+            retryable: e.is_retryable(),
+            details: None,
+        }),
+        metrics: protocol::TaskMetrics::default(),
+        result_id: None,
+    }
+}
+
+/// Send updated capabilities to the coordinator when what this worker
+/// advertises changed (a backend's circuit breaker opened or became ready
+/// for a probe, or a backend's GPU or context limits changed)
+async fn refresh_capabilities(
+    registry: &Arc<RwLock<BackendRegistry>>,
+    config: &WorkerConfig,
+    client: &CoordinatorClient,
+    advertised: &mut WorkerCapabilities,
+) {
+    let capabilities = build_worker_capabilities(registry, config);
+    if capabilities == *advertised {
+        return;
+    }
+    info!(
+        previous = ?advertised.supported_tasks,
+        supported_tasks = ?capabilities.supported_tasks,
+        gpu_available = capabilities.gpu_available,
+        "Advertised capabilities changed"
+    );
+    *advertised = capabilities.clone();
+    if let Err(e) = client.update_capabilities(capabilities).await {
+        warn!(error = %e, "Failed to send capability update");
+    }
+}
+
+/// Create the backend registry and register every backend the config
+/// enables, exactly as `run` does
+fn new_backend_registry(
+    config: &WorkerConfig,
+    crawl_denylist: &DomainDenylist,
+    crawl_rate_limiter: &HostRateLimiter,
+    crawl_egress: &sandbox::EgressPolicy,
+) -> BackendRegistry {
+    let registry = BackendRegistry::new();
+    registry.set_breaker_config(backend::BreakerConfig {
+        max_failures: config.executor.breaker_max_failures,
+        window: Duration::from_secs(config.executor.breaker_window_secs),
+        open_for: Duration::from_secs(config.executor.breaker_open_secs),
+    });
+    registry.set_auto_cpu_fallback(config.backend_routing.auto_cpu_fallback);
+    registry.set_memory_budget(config.resources.max_memory_mb);
+    registry.set_gpu_memory_cap(config.resources.max_gpu_memory_mb);
+
+    // Register the mock backend (always available, used for testing and as fallback).
+    // It also serves DEBUG tasks when they are enabled.
+    {
+        let mock = MockBackend::with_config(
+            MockConfig {
+                debug_tasks: config.worker.debug_tasks,
+                ..Default::default()
+            },
+            BackendConfig::default(),
+        );
+        registry.register_boxed(BackendType::Mock, Box::new(mock));
+    }
+
+    // Register CPU backend
+    {
+        let cpu_config = BackendConfig {
+            num_threads: if config.resources.max_threads > 0 {
+                Some(config.resources.max_threads)
+            } else {
+                None
+            },
+            context_size: 4096,
+            batch_size: 512,
+            gpu_layers: 0,
+            use_mmap: true,
+            use_mlock: false,
+            seed: None,
+            openai: None,
+            warmup_after_load: config.resources.warmup_after_load,
+            detect_model_format: config.resources.detect_model_format,
+            use_physical_cores_only: config.resources.use_physical_cores_only,
+        };
+
+        match registry.register(BackendType::Cpu, cpu_config) {
+            Ok(_) => info!("CPU backend registered"),
+            Err(e) => warn!(error = %e, "Failed to register CPU backend (llama feature may not be enabled)"),
+        }
+    }
+
+    // Register OpenAI backend (for API-based inference via OpenAI, Ollama, vLLM, etc.),
+    // routing across the configured endpoints
+    if config.openai.enabled {
+        use crate::backend::OpenAiRouter;
+
+        let router = OpenAiRouter::from_settings(&config.openai);
+        let endpoints: Vec<String> = router.endpoint_names().into_iter().map(String::from).collect();
+        registry.register_boxed(BackendType::OpenAi, Box::new(router));
+        info!(
+            endpoints = ?endpoints,
+            model = %config.openai.default_model,
+            "OpenAI backend registered"
+        );
+    }
+
+    // Register Crawler backend if web crawling is enabled
+    if config.crawler.enabled {
+        use crate::backend::CrawlerBackend;
+        let crawler_backend = CrawlerBackend::new(&config.crawler, &config.openai)
+            .with_denylist(crawl_denylist.clone())
+            .with_rate_limiter(crawl_rate_limiter.clone())
+            .with_egress(crawl_egress.clone());
+        registry.register_boxed(BackendType::Crawler, Box::new(crawler_backend));
+        info!("Crawler backend registered");
+    }
+
+    registry
 }
 
 /// Build worker capabilities from the registered backends
@@ -1025,6 +2134,12 @@ fn build_worker_capabilities(
     let gpu_available = all_caps.values().any(|c| c.gpu_available);
     let gpu_device = all_caps.values()
         .find_map(|c| c.gpu_device.clone());
+    let gpu_memory_mb = all_caps.values()
+        .find_map(|c| c.gpu_memory_mb);
+
+    // Token streaming needs it enabled and a backend that streams
+    let supports_streaming =
+        config.executor.stream_progress && all_caps.values().any(|c| c.supports_streaming);
 
     // Max context length from all backends
     let max_context_length = all_caps.values()
@@ -1036,23 +2151,103 @@ fn build_worker_capabilities(
 
     WorkerCapabilities {
         supported_tasks,
-        max_concurrent_tasks: 4, // Default concurrency
+        max_concurrent_tasks: config
+            .resources
+            .concurrent_tasks(sys_info.cpu_count, sys_info.total_memory_mb),
         available_memory_mb: sys_info.total_memory_mb,
         gpu_available,
         gpu_device,
-        gpu_memory_mb: None,
+        gpu_memory_mb,
         max_context_length,
         worker_version: env!("CARGO_PKG_VERSION").to_string(),
+        supports_streaming,
     }
 }
 
+/// Reload the config file on each SIGHUP: the crawl denylist is replaced
+/// here, the rest of the config goes to the main loop on `reload_tx`
+#[cfg(unix)]
+fn spawn_config_reload(
+    config_path: Option<String>,
+    denylist: DomainDenylist,
+    reload_tx: tokio::sync::mpsc::Sender<WorkerConfig>,
+) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!(error = %e, "Cannot listen for SIGHUP; config won't reload");
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match WorkerConfig::load(config_path.as_deref()) {
+                Ok(config) => {
+                    denylist.replace(&config.crawler.domain_denylist);
+                    info!(
+                        domains = config.crawler.domain_denylist.len(),
+                        "Crawl denylist reloaded"
+                    );
+                    if reload_tx.send(config).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => warn!(error = %e, "Config reload failed; keeping current config"),
+            }
+        }
+    });
+}
+
+/// Signal `term_tx` on SIGTERM, which service managers send to stop the
+/// worker
+#[cfg(unix)]
+fn spawn_sigterm_listener(term_tx: tokio::sync::mpsc::Sender<()>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminations = match signal(SignalKind::terminate()) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!(error = %e, "Cannot listen for SIGTERM; it will stop the worker without draining");
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while terminations.recv().await.is_some() {
+            if term_tx.send(()).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
 /// Run benchmarks to measure local compute capability
-fn run_benchmark(iterations: u32, output: Option<String>) -> Result<()> {
+fn run_benchmark(
+    config: &WorkerConfig,
+    iterations: u32,
+    output: Option<String>,
+    compare: bool,
+) -> Result<()> {
     info!(iterations, "Running benchmarks...");
 
-    let mut runner = BenchmarkRunner::new(iterations);
-    if let Some(ref path) = output {
-        runner = runner.with_results_path(PathBuf::from(path));
+    // History sits next to the results file, or the first-run results in
+    // the data dir
+    let results_path = match output {
+        Some(ref path) => PathBuf::from(path),
+        None => PathBuf::from(shellexpand::tilde(&config.storage.data_dir).to_string())
+            .join("benchmark.json"),
+    };
+    let history_path = BenchmarkRunner::history_path_for(&results_path);
+    let previous = if compare {
+        BenchmarkRunner::load_history(&history_path)?.pop()
+    } else {
+        None
+    };
+
+    let mut runner = BenchmarkRunner::new(iterations).with_history_path(history_path.clone());
+    if output.is_some() {
+        runner = runner.with_results_path(results_path);
     }
 
     let results = runner.run()?;
@@ -1063,6 +2258,12 @@ fn run_benchmark(iterations: u32, output: Option<String>) -> Result<()> {
     println!("  CPU Multi-Thread Score:  {} ({} threads)",
         results.cpu.multi_thread_score, results.cpu.thread_count);
     println!("  Memory Score:            {}", results.memory.score);
+    if let Some(ref gpu) = results.gpu {
+        println!("  GPU:                     {}", gpu.device_name);
+        println!("    Compute Throughput:    {:.0} GFLOPS", gpu.fma_gflops);
+        println!("    VRAM Bandwidth:        {:.1} GB/s", gpu.vram_bandwidth_gbps);
+        println!("    GPU Score:             {}", gpu.score);
+    }
     println!("  Overall Compute Score:   {}", results.compute_score);
     println!("  Estimated Throughput:    ~{:.0} tokens/sec", results.estimated_tokens_per_second);
     println!("  Duration:                {:.2}s", results.duration_secs);
@@ -1070,7 +2271,297 @@ fn run_benchmark(iterations: u32, output: Option<String>) -> Result<()> {
     if let Some(ref path) = output {
         println!("  Results saved to: {}", path);
     }
+    println!("  History:                 {}", history_path.display());
+
+    if compare {
+        print_benchmark_comparison(previous.as_ref(), &results);
+    }
+
+    Ok(())
+}
+
+/// Print how `current` moved against the previous run in the history
+fn print_benchmark_comparison(previous: Option<&BenchmarkResults>, current: &BenchmarkResults) {
+    let Some(previous) = previous else {
+        println!();
+        println!("No previous run to compare against.");
+        return;
+    };
+
+    let delta = |pct: Option<f64>| match pct {
+        Some(pct) => format!("{:+.1}%", pct),
+        None => "n/a".to_string(),
+    };
+    let comparison = BenchmarkComparison::between(previous, current);
+    println!();
+    println!("Compared to {}:", previous.timestamp.format("%Y-%m-%d %H:%M UTC"));
+    println!("  Compute Score:           {}", delta(comparison.compute_score_pct));
+    println!("  Estimated Throughput:    {}", delta(comparison.tokens_per_second_pct));
+    println!("  Memory Read Bandwidth:   {}", delta(comparison.memory_read_pct));
+    println!("  Memory Write Bandwidth:  {}", delta(comparison.memory_write_pct));
+
+    if comparison.compute_regressed(COMPUTE_REGRESSION_WARN_PCT) {
+        warn!(
+            previous = previous.compute_score,
+            current = current.compute_score,
+            "Compute score regressed since the last benchmark"
+        );
+        println!();
+        println!(
+            "Warning: compute score dropped more than {:.0}% since the last run. \
+             This often means thermal throttling or a driver change.",
+            COMPUTE_REGRESSION_WARN_PCT
+        );
+    }
+}
+
+/// Replay a recorded session, printing each entry and the client events
+/// its coordinator frames produce
+fn run_replay(path: &str, strict: bool) -> Result<()> {
+    let entries = recording::read_recording(std::path::Path::new(path))?;
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| Error::Internal(format!("Failed to create runtime: {}", e)))?;
+
+    rt.block_on(async {
+        let mut replay = coordinator::ReplaySession::new(strict);
+        for entry in &entries {
+            let ts = entry.timestamp.format("%H:%M:%S%.3f");
+            match entry.kind {
+                RecordKind::Inbound => {
+                    let message_type = entry.data["type"].as_str().unwrap_or("<malformed>");
+                    println!("{} inbound  {}", ts, message_type);
+                    if let Some(frame) = entry.frame() {
+                        for event in replay.feed(&frame).await {
+                            println!("    -> {:?}", event);
+                        }
+                    }
+                }
+                RecordKind::Outbound => {
+                    let message_type = entry.data["type"].as_str().unwrap_or("?");
+                    let task_id = entry.data["task_id"].as_str().unwrap_or("");
+                    println!("{} outbound {} {}", ts, message_type, task_id);
+                }
+                RecordKind::Peer => {
+                    let event = entry.data["event"].as_str().unwrap_or("?");
+                    println!("{} peer     {} {}", ts, event, entry.data);
+                }
+            }
+        }
+    });
+
+    println!("{} entries replayed", entries.len());
+    Ok(())
+}
+
+/// Run one synthetic text completion through the executor, served by
+/// `backend`, and print what happened
+fn run_self_test(
+    config_path: Option<&str>,
+    backend: &str,
+    model: Option<&str>,
+    prompt: &str,
+) -> Result<()> {
+    let config = WorkerConfig::load(config_path)?;
+    let backend_type = BackendType::from_str(backend)
+        .ok_or_else(|| Error::Config(format!("Unknown backend '{}' (expected mock, cpu, or openai)", backend)))?;
+    let model_id = match model {
+        Some(model) => model.to_string(),
+        None if backend_type == BackendType::OpenAi => config.openai.default_model.clone(),
+        None => "self-test".to_string(),
+    };
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| Error::Internal(format!("Failed to create runtime: {}", e)))?;
+
+    rt.block_on(async {
+        let crawl_egress = sandbox::EgressPolicy::new(&config.sandbox.egress_allowlist)?;
+        let registry = Arc::new(RwLock::new(new_backend_registry(
+            &config,
+            &DomainDenylist::new(&config.crawler.domain_denylist),
+            &HostRateLimiter::new(
+                Duration::from_millis(config.crawler.rate_limit_ms),
+                Duration::from_millis(config.crawler.rate_limit_jitter_ms),
+            ),
+            &crawl_egress,
+        )));
+
+        let capabilities = build_worker_capabilities(&registry, &config);
+        let json = serde_json::to_string_pretty(&capabilities)
+            .map_err(|e| Error::Internal(format!("Failed to serialize capabilities: {}", e)))?;
+        println!("Capabilities:\n{}\n", json);
+
+        // Only the chosen backend may serve the task
+        {
+            let reg = registry.read();
+            for other in reg.registered_backends() {
+                if other != backend_type {
+                    reg.unregister(other);
+                }
+            }
+            if !reg.supports_task(TaskType::TextCompletion) {
+                return Err(Error::NotSupported(format!(
+                    "Backend '{}' is not available to serve text completion. \
+                     Check that it is enabled in the config (openai.enabled) or built in (--features llama for cpu).",
+                    backend_type
+                )));
+            }
+        }
+
+        let (executor, mut result_rx) = TaskExecutor::new(
+            ExecutorConfig {
+                model_dir: Some(config.model_dir()),
+                model_sources: config.model_sources(),
+                ..Default::default()
+            },
+            registry.clone(),
+            "self-test".to_string(),
+        );
+        let assignment = protocol::TaskAssignmentMessage {
+            task_id: format!("self-test-{}", &uuid::Uuid::new_v4().to_string()[..8]),
+            block_id: None,
+            day_id: None,
+            priority: protocol::TaskPriority::Normal,
+            deadline: None,
+            model_id: model_id.clone(),
+            input: types::TaskInput::TextCompletion(types::TextCompletionInput {
+                prompt: prompt.to_string(),
+                system_prompt: None,
+                params: types::GenerationParams {
+                    max_tokens: 32,
+                    ..types::GenerationParams::default()
+                },
+            }),
+            is_canary: false,
+            expected_hash: None,
+            timeout_secs: 120,
+            group_id: None,
+            stream: false,
+            required_tags: vec![],
+        };
+        executor.submit(assignment).await?;
+
+        let result = result_rx
+            .recv()
+            .await
+            .ok_or_else(|| Error::Internal("Executor stopped without a result".to_string()))?;
+        if let Some(err) = result.error {
+            return Err(Error::Execution(format!(
+                "self-test on backend '{}' failed with {}: {}",
+                backend_type, err.code, err.message
+            )));
+        }
 
+        let text = match result.output {
+            Some(types::TaskOutput::TextCompletion(output)) => output.text,
+            other => return Err(Error::Internal(format!("Unexpected task output: {:?}", other))),
+        };
+        println!("Backend:  {}", backend_type);
+        println!("Model:    {}", model_id);
+        println!(
+            "Time:     {} ms ({} ms executing)",
+            result.metrics.total_time_ms, result.metrics.execution_time_ms
+        );
+        if let Some(tps) = result.metrics.tokens_per_second {
+            println!("Speed:    {:.1} tokens/s", tps);
+        }
+        println!("Output:   {}", text);
+        println!("\nSelf-test passed");
+        Ok(())
+    })
+}
+
+/// Print the detected hardware summary
+fn run_info(config_path: Option<&str>, json: bool) -> Result<()> {
+    let config = WorkerConfig::load(config_path)?;
+    let report = system::HardwareReport::collect(&config);
+
+    if json {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| Error::Internal(format!("Failed to serialize report: {}", e)))?;
+        println!("{}", json);
+    } else {
+        report.print();
+    }
+
+    Ok(())
+}
+
+/// Send `command` to a running worker's control socket and return its
+/// reply, exiting with a message if the worker can't be reached or refuses
+fn control_command(config_path: Option<&str>, addr: Option<&str>, command: &str) -> Result<serde_json::Value> {
+    let addr = match addr {
+        Some(addr) => addr.to_string(),
+        None => WorkerConfig::load(config_path)?.control.listen_addr,
+    };
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| Error::Internal(format!("Failed to create runtime: {}", e)))?;
+
+    let reply = match rt.block_on(control::request(&addr, command)) {
+        Ok(reply) => reply,
+        Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            eprintln!("No worker is listening on {}.", addr);
+            eprintln!("Is the worker running with `control.enabled = true`?");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Failed to query the worker at {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
+    if reply["ok"] != true {
+        let error = reply["error"].as_str().unwrap_or("unknown error");
+        eprintln!("Worker refused the {} request: {}", command, error);
+        std::process::exit(1);
+    }
+    Ok(reply)
+}
+
+/// Pause or resume a running worker through its control socket
+fn run_pause(config_path: Option<&str>, addr: Option<&str>, pause: bool) -> Result<()> {
+    let reply = control_command(config_path, addr, if pause { "pause" } else { "resume" })?;
+    if pause {
+        println!("Worker paused: running tasks will finish, no new tasks are taken.");
+    } else if reply["paused"] == true {
+        println!("Operator pause lifted, but the coordinator still has the worker paused.");
+    } else {
+        println!("Worker resumed.");
+    }
+    Ok(())
+}
+
+/// Query a running worker's control socket and print its status
+fn run_status(config_path: Option<&str>, addr: Option<&str>, json: bool) -> Result<()> {
+    let reply = control_command(config_path, addr, "stats")?;
+    let stats: WorkerStats = serde_json::from_value(reply["stats"].clone())
+        .map_err(|e| Error::Internal(format!("Unexpected status reply: {}", e)))?;
+
+    if json {
+        let json = serde_json::to_string_pretty(&stats)
+            .map_err(|e| Error::Internal(format!("Failed to serialize status: {}", e)))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    let uptime = stats.uptime_secs;
+    println!("Worker ID:     {}", stats.worker_id.as_deref().unwrap_or("(not registered)"));
+    println!("Connection:    {:?}", stats.connection_state);
+    println!("Running tasks: {}", stats.running_tasks);
+    println!("Queued tasks:  {}", stats.queued_tasks);
+    println!("Completed:     {}", stats.completed_tasks);
+    println!("Failed:        {}", stats.failed_tasks);
+    if stats.loaded_models.is_empty() {
+        println!("Models:        (none loaded)");
+    } else {
+        println!("Models:        {}", stats.loaded_models.join(", "));
+    }
+    println!("Peers:         {}", stats.peer_count);
+    println!("Uptime:        {}h {:02}m {:02}s", uptime / 3600, uptime / 60 % 60, uptime % 60);
     Ok(())
 }
 
@@ -1079,9 +2570,9 @@ fn handle_config_command(subcommand: cli::ConfigSubcommand) -> Result<()> {
     use cli::ConfigSubcommand;
 
     match subcommand {
-        ConfigSubcommand::Show { config } => {
+        ConfigSubcommand::Show { config, format } => {
             let cfg = WorkerConfig::load(config.as_deref())?;
-            println!("{}", toml::to_string_pretty(&cfg)?);
+            println!("{}", format.render(&cfg)?);
         }
         ConfigSubcommand::Init { path, force } => {
             config::init_config(path.as_deref(), force)?;
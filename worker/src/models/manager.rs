@@ -0,0 +1,452 @@
+//! Model download manager
+//!
+//! Fetches a model file that isn't on disk yet from its spec's `source_url`
+//! into the model directory, as `<model_id>.<ext>` (where the model loader
+//! looks for it). The download goes to a `.part` file first; an interrupted
+//! one resumes with a ranged request, on the next attempt or the next call.
+//! When the spec carries a `sha256` the file is checked before it's moved
+//! into place. Callers asking for the same model at once share a single
+//! download. With a sandbox write scope set, a model directory outside it
+//! is refused before anything is written.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use reqwest::StatusCode;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+use crate::error::{Error, Result};
+use crate::sandbox::WriteScope;
+use crate::types::ModelSpec;
+
+/// Attempts per download; each retry resumes where the last one stopped
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Pause before retrying an interrupted download
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Progress is logged this often when the server doesn't send a length (bytes)
+const PROGRESS_STEP: u64 = 100 * 1024 * 1024;
+
+/// Longest wait for the connection to the download server
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest wait for the next chunk of a download before it counts as
+/// interrupted
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Downloads missing model files into the model directory
+pub struct ModelManager {
+    /// Directory downloaded models are stored in
+    model_dir: PathBuf,
+    client: reqwest::Client,
+    /// Longest wait for the next chunk of a download
+    read_timeout: Duration,
+    /// Where downloads may be written
+    write_scope: WriteScope,
+    /// Per-model locks, held for the length of a download; an entry lives
+    /// only while some caller is downloading or waiting for that model
+    downloads: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// A caller's claim on a model's download lock. Dropping the last claim
+/// removes the lock from the map, so it doesn't grow with every model
+/// ever fetched.
+struct DownloadClaim<'a> {
+    downloads: &'a Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    model_id: &'a str,
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl<'a> DownloadClaim<'a> {
+    fn new(downloads: &'a Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>, model_id: &'a str) -> Self {
+        let lock = downloads.lock().entry(model_id.to_string()).or_default().clone();
+        Self { downloads, model_id, lock }
+    }
+}
+
+impl Drop for DownloadClaim<'_> {
+    fn drop(&mut self) {
+        // Claims are only taken under the map lock, so with it held the
+        // count can't grow: 2 means just the map and this claim
+        let mut downloads = self.downloads.lock();
+        if Arc::strong_count(&self.lock) == 2 {
+            downloads.remove(self.model_id);
+        }
+    }
+}
+
+impl ModelManager {
+    /// Create a manager storing models in `model_dir`
+    pub fn new(model_dir: impl Into<PathBuf>) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self::with_client(model_dir, client)
+    }
+
+    /// Create a manager downloading with `client`
+    pub fn with_client(model_dir: impl Into<PathBuf>, client: reqwest::Client) -> Self {
+        Self {
+            model_dir: model_dir.into(),
+            client,
+            read_timeout: READ_TIMEOUT,
+            write_scope: WriteScope::default(),
+            downloads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Refuse downloads that would be written outside `write_scope`
+    pub fn with_write_scope(mut self, write_scope: WriteScope) -> Self {
+        self.write_scope = write_scope;
+        self
+    }
+
+    /// Where the file for `spec` is stored once downloaded
+    pub fn local_path(&self, spec: &ModelSpec) -> PathBuf {
+        self.model_dir
+            .join(format!("{}.{}", spec.id, spec.format.extension()))
+    }
+
+    /// Local path of the model file for `spec`, downloading it first if it
+    /// isn't there. A file already at `spec.path` is used as is.
+    pub async fn ensure(&self, spec: &ModelSpec) -> Result<PathBuf> {
+        if spec.path.is_file() {
+            return Ok(spec.path.clone());
+        }
+        let dest = self.local_path(spec);
+        if dest.is_file() {
+            return Ok(dest);
+        }
+        let Some(url) = spec.source_url.as_deref() else {
+            return Err(Error::ModelNotFound { model_id: spec.id.clone() });
+        };
+
+        let claim = DownloadClaim::new(&self.downloads, &spec.id);
+        let _download = claim.lock.lock().await;
+        // Another caller may have finished it while this one waited
+        if dest.is_file() {
+            debug!(model = %spec.id, "Model downloaded by a concurrent request");
+            return Ok(dest);
+        }
+
+        let dest = self.write_scope.check(&dest)?;
+        self.download(spec, url, &dest).await?;
+        Ok(dest)
+    }
+
+    /// Download `url` to `dest` by way of a `.part` file, verifying its checksum
+    async fn download(&self, spec: &ModelSpec, url: &str, dest: &Path) -> Result<()> {
+        let failed = |message: String| Error::ModelLoadFailed {
+            model_id: spec.id.clone(),
+            message,
+        };
+        let model_dir = dest.parent().unwrap_or(&self.model_dir);
+        tokio::fs::create_dir_all(model_dir)
+            .await
+            .map_err(|e| failed(format!("Failed to create model directory: {}", e)))?;
+        let part = part_path(dest);
+
+        let mut attempt = 1;
+        while let Err(e) = self.fetch(spec, url, &part).await {
+            if attempt >= MAX_ATTEMPTS {
+                return Err(e);
+            }
+            warn!(model = %spec.id, attempt, error = %e, "Model download interrupted, resuming");
+            attempt += 1;
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+
+        if let Some(expected) = &spec.sha256 {
+            let actual = sha256_file(&part)
+                .await
+                .map_err(|e| failed(format!("Failed to read downloaded model: {}", e)))?;
+            if !expected.eq_ignore_ascii_case(&actual) {
+                let _ = tokio::fs::remove_file(&part).await;
+                return Err(Error::ModelCorrupted {
+                    model_id: spec.id.clone(),
+                    reason: format!("SHA-256 mismatch: expected {}, got {}", expected, actual),
+                });
+            }
+            debug!(model = %spec.id, "Model checksum verified");
+        }
+
+        tokio::fs::rename(&part, dest)
+            .await
+            .map_err(|e| failed(format!("Failed to move downloaded model into place: {}", e)))?;
+        info!(model = %spec.id, path = %dest.display(), "Model downloaded");
+        Ok(())
+    }
+
+    /// Fetch `url` into `part`, continuing after the bytes already in it
+    async fn fetch(&self, spec: &ModelSpec, url: &str, part: &Path) -> Result<()> {
+        let failed = |message: String| Error::ModelLoadFailed {
+            model_id: spec.id.clone(),
+            message,
+        };
+
+        let offset = tokio::fs::metadata(part).await.map(|m| m.len()).unwrap_or(0);
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| failed(format!("Download request failed: {}", e)))?;
+
+        let status = response.status();
+        if offset > 0 && status == StatusCode::RANGE_NOT_SATISFIABLE {
+            // The part file already holds the whole model
+            return Ok(());
+        }
+        if !status.is_success() {
+            return Err(failed(format!("HTTP error: {}", status)));
+        }
+
+        // A server ignoring the range sends the whole file again
+        let resumed = status == StatusCode::PARTIAL_CONTENT;
+        let mut options = tokio::fs::OpenOptions::new();
+        if resumed {
+            options.append(true);
+        } else {
+            options.write(true).truncate(true);
+        }
+        let mut file = options
+            .create(true)
+            .open(part)
+            .await
+            .map_err(|e| failed(format!("Failed to open download file: {}", e)))?;
+
+        let mut received = if resumed { offset } else { 0 };
+        let total = response.content_length().map(|len| received + len);
+        info!(
+            model = %spec.id,
+            url = %url,
+            resume_from = received,
+            total_bytes = ?total,
+            "Downloading model"
+        );
+
+        let step = total.map_or(PROGRESS_STEP, |t| (t / 10).max(1));
+        let mut next_report = received + step;
+        while let Some(chunk) = tokio::time::timeout(self.read_timeout, response.chunk())
+            .await
+            .map_err(|_| failed(format!("Download stalled for {:?}", self.read_timeout)))?
+            .map_err(|e| failed(format!("Download interrupted: {}", e)))?
+        {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| failed(format!("Failed to write model file: {}", e)))?;
+            received += chunk.len() as u64;
+            if received >= next_report {
+                info!(model = %spec.id, received_bytes = received, total_bytes = ?total, "Model download progress");
+                next_report = received + step;
+            }
+        }
+        file.flush()
+            .await
+            .map_err(|e| failed(format!("Failed to write model file: {}", e)))?;
+
+        match total {
+            Some(total) if received < total => Err(failed(format!(
+                "Download ended after {} of {} bytes",
+                received, total
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// In-progress download file for `dest`
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// SHA-256 of a file (hex), read in chunks off the async runtime
+async fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use tokio::sync::mpsc;
+
+    use crate::testing::{http_stub, Response};
+
+    fn model_bytes() -> Vec<u8> {
+        let mut bytes = b"GGUF".to_vec();
+        bytes.extend((0..64 * 1024).map(|i| (i % 251) as u8));
+        bytes
+    }
+
+    fn spec(url: &str, sha256: Option<String>) -> ModelSpec {
+        serde_json::from_value(serde_json::json!({
+            "id": "tiny-model",
+            "name": "Tiny Model",
+            "path": "",
+            "format": "gguf",
+            "sha256": sha256,
+            "source_url": url,
+        }))
+        .unwrap()
+    }
+
+    /// Minimal file server for `body` honoring `Range: bytes=N-`, reporting
+    /// each request's range start
+    async fn serve(body: Vec<u8>) -> (String, mpsc::UnboundedReceiver<Option<u64>>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let base_url = http_stub(move |request| {
+            let start: Option<u64> = request
+                .header("range")
+                .and_then(|r| r.strip_prefix("bytes="))
+                .and_then(|r| r.trim_end_matches('-').parse().ok());
+            let _ = tx.send(start);
+
+            let response = match start {
+                Some(start) => Response::new(206).header(
+                    "Content-Range",
+                    format!("bytes {}-{}/{}", start, body.len() - 1, body.len()),
+                ),
+                None => Response::new(200),
+            };
+            // Slow enough for concurrent requests to overlap
+            response
+                .body(&body[start.unwrap_or(0) as usize..])
+                .delay(Duration::from_millis(100))
+        })
+        .await;
+        (format!("{}/tiny-model.gguf", base_url), rx)
+    }
+
+    #[tokio::test]
+    async fn test_checksum_verified() {
+        let (url, _) = serve(model_bytes()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new(dir.path());
+
+        // Wrong checksum: rejected, nothing left behind
+        let err = manager.ensure(&spec(&url, Some("ab".repeat(32)))).await.unwrap_err();
+        assert!(matches!(err, Error::ModelCorrupted { .. }));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+        assert!(manager.downloads.lock().is_empty());
+
+        let digest = hex::encode(Sha256::digest(model_bytes()));
+        let path = manager.ensure(&spec(&url, Some(digest.to_uppercase()))).await.unwrap();
+        assert_eq!(path, dir.path().join("tiny-model.gguf"));
+        assert_eq!(std::fs::read(&path).unwrap(), model_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_share_one_download() {
+        let (url, mut requests) = serve(model_bytes()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new(dir.path());
+        let spec = spec(&url, None);
+
+        let (a, b) = tokio::join!(manager.ensure(&spec), manager.ensure(&spec));
+        assert_eq!(a.unwrap(), b.unwrap());
+        assert_eq!(requests.recv().await.unwrap(), None);
+        assert!(requests.try_recv().is_err());
+        // Finished downloads don't keep their lock around
+        assert!(manager.downloads.lock().is_empty());
+
+        // Already on disk: no request at all
+        manager.ensure(&spec).await.unwrap();
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_partial_download_resumed() {
+        let (url, mut requests) = serve(model_bytes()).await;
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new(dir.path());
+        let spec = spec(&url, Some(hex::encode(Sha256::digest(model_bytes()))));
+
+        let half = model_bytes().len() / 2;
+        let part = part_path(&manager.local_path(&spec));
+        std::fs::write(&part, &model_bytes()[..half]).unwrap();
+
+        let path = manager.ensure(&spec).await.unwrap();
+        assert_eq!(requests.recv().await.unwrap(), Some(half as u64));
+        assert_eq!(std::fs::read(path).unwrap(), model_bytes());
+        assert!(!part.exists());
+    }
+
+    #[tokio::test]
+    async fn test_stalled_download_times_out() {
+        // Sends half the file, then keeps the connection open and silent
+        let base_url = http_stub(|_| {
+            let body = model_bytes();
+            Response::new(200)
+                .header("Content-Length", body.len())
+                .body(&body[..body.len() / 2])
+                .hold_open()
+        })
+        .await;
+        let url = format!("{}/tiny-model.gguf", base_url);
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = ModelManager::new(dir.path());
+        manager.read_timeout = Duration::from_millis(200);
+        let err = tokio::time::timeout(Duration::from_secs(30), manager.ensure(&spec(&url, None)))
+            .await
+            .expect("stalled download was not abandoned")
+            .unwrap_err();
+        assert!(err.to_string().contains("stalled"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_download_outside_write_scope_rejected() {
+        let (url, mut requests) = serve(model_bytes()).await;
+        let data_dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let model_dir = outside.path().join("models");
+
+        let manager = ModelManager::new(&model_dir)
+            .with_write_scope(WriteScope::confined_to(data_dir.path()));
+        let err = manager.ensure(&spec(&url, None)).await.unwrap_err();
+        assert!(matches!(err, Error::SandboxViolation { .. }));
+        assert!(!model_dir.exists());
+        assert!(requests.try_recv().is_err());
+
+        // The same download inside the scope goes through
+        let manager = ModelManager::new(data_dir.path().join("models"))
+            .with_write_scope(WriteScope::confined_to(data_dir.path()));
+        let path = manager.ensure(&spec(&url, None)).await.unwrap();
+        assert!(path.starts_with(data_dir.path().canonicalize().unwrap()));
+        assert_eq!(std::fs::read(path).unwrap(), model_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_missing_model_without_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new(dir.path());
+        let mut spec = spec("", None);
+        spec.source_url = None;
+        assert!(matches!(manager.ensure(&spec).await, Err(Error::ModelNotFound { .. })));
+    }
+}
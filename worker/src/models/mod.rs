@@ -0,0 +1,8 @@
+//! Model file management
+//!
+//! Provides:
+//! - Model manager for downloading missing model files into the model cache
+
+mod manager;
+
+pub use manager::*;
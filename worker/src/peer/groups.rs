@@ -6,11 +6,14 @@
 //! - **General collaboration**: Any coordinated multi-worker activity
 
 use std::collections::HashMap;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use uuid::Uuid;
 
+use crate::error::{Error, Result};
+use crate::protocol::TaskAssignmentMessage;
 use crate::types::TaskType;
 
 // ─────────────────────────────────────────────────────────────────
@@ -60,6 +63,10 @@ pub struct GroupMember {
 
     /// Whether this member has signaled readiness
     pub ready: bool,
+
+    /// Whether this member accepts streamed pipeline chunks (negotiated
+    /// in its `GroupJoin`)
+    pub streaming: bool,
 }
 
 /// A work group containing multiple workers
@@ -78,6 +85,41 @@ pub struct WorkGroup {
     pub created_at: DateTime<Utc>,
 }
 
+impl WorkGroup {
+    /// Ready members needed before a task may run on this group.
+    ///
+    /// Shard groups need `shard_quorum` ready members (0 = every shard);
+    /// other groups don't gate tasks.
+    fn required_ready(&self, shard_quorum: u32) -> usize {
+        match self.purpose {
+            GroupPurpose::ModelShard { total_shards, .. } => {
+                let required = if shard_quorum == 0 {
+                    total_shards
+                } else {
+                    shard_quorum.min(total_shards)
+                };
+                required as usize
+            }
+            _ => 0,
+        }
+    }
+
+    fn ready_count(&self) -> usize {
+        self.members.iter().filter(|m| m.ready).count()
+    }
+}
+
+/// Outcome of offering a task to a group
+#[derive(Debug)]
+pub enum GroupAdmission {
+    /// Quorum met: run the task now
+    Dispatch(Box<TaskAssignmentMessage>),
+    /// Held until enough members are ready
+    Held { ready: usize, required: usize },
+    /// The task can't run on this group
+    Rejected(String),
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Group Manager
 // ─────────────────────────────────────────────────────────────────
@@ -85,7 +127,13 @@ pub struct WorkGroup {
 /// Manages work groups for this worker
 pub struct GroupManager {
     groups: RwLock<HashMap<String, WorkGroup>>,
+    /// Tasks waiting for their group to reach quorum, per group
+    held_tasks: RwLock<HashMap<String, Vec<TaskAssignmentMessage>>>,
     my_worker_id: String,
+    /// Ready shards required before dispatch (0 = all shards)
+    shard_quorum: u32,
+    /// How long a group may take to reach quorum before it is disbanded
+    ready_timeout: Duration,
 }
 
 impl GroupManager {
@@ -93,10 +141,20 @@ impl GroupManager {
     pub fn new(worker_id: String) -> Self {
         Self {
             groups: RwLock::new(HashMap::new()),
+            held_tasks: RwLock::new(HashMap::new()),
             my_worker_id: worker_id,
+            shard_quorum: 0,
+            ready_timeout: Duration::from_secs(120),
         }
     }
 
+    /// Set the shard quorum (0 = all shards) and readiness timeout
+    pub fn with_readiness(mut self, shard_quorum: u32, ready_timeout: Duration) -> Self {
+        self.shard_quorum = shard_quorum;
+        self.ready_timeout = ready_timeout;
+        self
+    }
+
     /// Create a new group and return its ID
     pub fn create_group(&self, purpose: GroupPurpose) -> String {
         let group_id = format!("grp-{}", &Uuid::new_v4().to_string()[..8]);
@@ -110,6 +168,7 @@ impl GroupManager {
                 shard_index: None,
                 pipeline_stage: None,
                 ready: false,
+                streaming: false,
             }],
             created_at: Utc::now(),
         };
@@ -143,6 +202,7 @@ impl GroupManager {
                 shard_index: None,
                 pipeline_stage: None,
                 ready: false,
+                streaming: false,
             });
         }
     }
@@ -161,9 +221,10 @@ impl GroupManager {
         }
     }
 
-    /// Remove a group entirely
-    pub fn remove_group(&self, group_id: &str) {
+    /// Remove a group entirely, returning any tasks held for it
+    pub fn remove_group(&self, group_id: &str) -> Vec<TaskAssignmentMessage> {
         self.groups.write().remove(group_id);
+        self.held_tasks.write().remove(group_id).unwrap_or_default()
     }
 
     /// Get groups this worker belongs to
@@ -203,6 +264,68 @@ impl GroupManager {
             .unwrap_or(false)
     }
 
+    /// Whether enough members are ready for tasks to run on the group
+    pub fn quorum_met(&self, group_id: &str) -> bool {
+        self.groups
+            .read()
+            .get(group_id)
+            .map(|g| g.ready_count() >= g.required_ready(self.shard_quorum))
+            .unwrap_or(false)
+    }
+
+    /// Offer a task to a group: dispatch it if the group has quorum,
+    /// otherwise hold it until the group assembles
+    pub fn admit_task(&self, group_id: &str, task: TaskAssignmentMessage) -> GroupAdmission {
+        let groups = self.groups.read();
+        let Some(group) = groups.get(group_id) else {
+            return GroupAdmission::Rejected(format!("unknown work group '{}'", group_id));
+        };
+
+        let ready = group.ready_count();
+        let required = group.required_ready(self.shard_quorum);
+        if ready >= required {
+            return GroupAdmission::Dispatch(Box::new(task));
+        }
+
+        self.held_tasks
+            .write()
+            .entry(group_id.to_string())
+            .or_default()
+            .push(task);
+        GroupAdmission::Held { ready, required }
+    }
+
+    /// Release the tasks held for a group once it has quorum
+    pub fn take_released_tasks(&self, group_id: &str) -> Vec<TaskAssignmentMessage> {
+        if !self.quorum_met(group_id) {
+            return Vec::new();
+        }
+        self.held_tasks.write().remove(group_id).unwrap_or_default()
+    }
+
+    /// Disband groups that failed to reach quorum within the readiness
+    /// timeout, returning each one's ID with the tasks held for it
+    pub fn disband_unassembled(&self) -> Vec<(String, Vec<TaskAssignmentMessage>)> {
+        let timeout = chrono::Duration::from_std(self.ready_timeout).unwrap_or(chrono::Duration::MAX);
+        let now = Utc::now();
+        let expired: Vec<String> = self
+            .groups
+            .read()
+            .values()
+            .filter(|g| g.ready_count() < g.required_ready(self.shard_quorum))
+            .filter(|g| now - g.created_at >= timeout)
+            .map(|g| g.group_id.clone())
+            .collect();
+
+        expired
+            .into_iter()
+            .map(|group_id| {
+                let held = self.remove_group(&group_id);
+                (group_id, held)
+            })
+            .collect()
+    }
+
     /// Get the worker responsible for the next pipeline stage
     pub fn next_in_pipeline(
         &self,
@@ -217,6 +340,49 @@ impl GroupManager {
         })
     }
 
+    /// This worker's stage in a task pipeline group
+    pub fn my_pipeline_stage(&self, group_id: &str) -> Option<usize> {
+        self.groups.read().get(group_id).and_then(|g| {
+            g.members
+                .iter()
+                .find(|m| m.worker_id == self.my_worker_id)
+                .and_then(|m| m.pipeline_stage)
+        })
+    }
+
+    /// The member running `stage` of a task pipeline group
+    pub fn pipeline_stage_member(&self, group_id: &str, stage: usize) -> Option<String> {
+        self.groups.read().get(group_id).and_then(|g| {
+            g.members
+                .iter()
+                .find(|m| m.pipeline_stage == Some(stage))
+                .map(|m| m.worker_id.clone())
+        })
+    }
+
+    /// Task type run by `stage` of a task pipeline group
+    pub fn pipeline_stage_type(&self, group_id: &str, stage: usize) -> Option<TaskType> {
+        match &self.groups.read().get(group_id)?.purpose {
+            GroupPurpose::TaskPipeline { stages, .. } => stages.get(stage).copied(),
+            _ => None,
+        }
+    }
+
+    /// The next pipeline stage's worker, if it accepts streamed chunks;
+    /// otherwise output goes over as a whole `PipelineOutput`
+    pub fn next_streaming_stage(
+        &self,
+        group_id: &str,
+        current_stage: usize,
+    ) -> Option<String> {
+        self.groups.read().get(group_id).and_then(|g| {
+            g.members
+                .iter()
+                .find(|m| m.pipeline_stage == Some(current_stage + 1) && m.streaming)
+                .map(|m| m.worker_id.clone())
+        })
+    }
+
     /// Get the worker that owns a specific shard
     pub fn shard_owner(&self, group_id: &str, shard_index: u32) -> Option<String> {
         self.groups.read().get(group_id).and_then(|g| {
@@ -227,6 +393,32 @@ impl GroupManager {
         })
     }
 
+    /// The worker owning each shard of a model shard group, in layer order
+    /// (`None` if `group_id` is not a model shard group we belong to)
+    pub fn shard_owners(&self, group_id: &str) -> Result<Option<Vec<String>>> {
+        let groups = self.groups.read();
+        let Some(group) = groups.get(group_id) else {
+            return Ok(None);
+        };
+        let GroupPurpose::ModelShard { total_shards, .. } = group.purpose else {
+            return Ok(None);
+        };
+        (0..total_shards)
+            .map(|index| {
+                let owner = group
+                    .members
+                    .iter()
+                    .find(|m| m.shard_index == Some(index))
+                    .ok_or_else(|| Error::GroupNotReady {
+                        group_id: group_id.to_string(),
+                        reason: format!("shard {} has no owner", index),
+                    })?;
+                Ok(owner.worker_id.clone())
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Some)
+    }
+
     /// Set the shard index for a member
     pub fn set_shard_index(
         &self,
@@ -278,11 +470,25 @@ impl GroupManager {
                     shard_index: None,
                     pipeline_stage: None,
                     ready: false,
+                    streaming: false,
                 });
             }
         }
     }
 
+    /// Record whether a member accepts streamed pipeline chunks
+    pub fn set_member_streaming(&self, group_id: &str, worker_id: &str, streaming: bool) {
+        if let Some(group) = self.groups.write().get_mut(group_id) {
+            if let Some(member) = group
+                .members
+                .iter_mut()
+                .find(|m| m.worker_id == worker_id)
+            {
+                member.streaming = streaming;
+            }
+        }
+    }
+
     /// Remove a member from a group (peer left)
     pub fn remove_member(&self, group_id: &str, worker_id: &str) {
         if let Some(group) = self.groups.write().get_mut(group_id) {
@@ -341,8 +547,16 @@ mod tests {
 
         mgr.set_shard_index(&gid, "w1", 0);
         mgr.set_shard_index(&gid, "w2", 1);
+        // A shard without an owner can't run
+        assert!(matches!(mgr.shard_owners(&gid), Err(Error::GroupNotReady { .. })));
         mgr.set_shard_index(&gid, "w3", 2);
 
+        assert_eq!(
+            mgr.shard_owners(&gid).unwrap(),
+            Some(vec!["w1".to_string(), "w2".to_string(), "w3".to_string()])
+        );
+        assert_eq!(mgr.shard_owners("unknown").unwrap(), None);
+
         assert_eq!(mgr.shard_owner(&gid, 0), Some("w1".to_string()));
         assert_eq!(mgr.shard_owner(&gid, 1), Some("w2".to_string()));
         assert_eq!(mgr.shard_owner(&gid, 2), Some("w3".to_string()));
@@ -364,6 +578,16 @@ mod tests {
 
         assert_eq!(mgr.next_in_pipeline(&gid, 0), Some("w2".to_string()));
         assert_eq!(mgr.next_in_pipeline(&gid, 1), None);
+        assert_eq!(mgr.my_pipeline_stage(&gid), Some(0));
+        assert_eq!(mgr.pipeline_stage_type(&gid, 1), Some(TaskType::Classification));
+        assert_eq!(mgr.pipeline_stage_type(&gid, 2), None);
+        assert_eq!(mgr.pipeline_stage_member(&gid, 1), Some("w2".to_string()));
+        assert_eq!(mgr.pipeline_stage_member(&gid, 2), None);
+
+        // Streaming only once the next stage has negotiated it
+        assert_eq!(mgr.next_streaming_stage(&gid, 0), None);
+        mgr.set_member_streaming(&gid, "w2", true);
+        assert_eq!(mgr.next_streaming_stage(&gid, 0), Some("w2".to_string()));
     }
 
     #[test]
@@ -381,6 +605,95 @@ mod tests {
         assert!(mgr.all_members_ready(&gid));
     }
 
+    fn make_task(task_id: &str) -> TaskAssignmentMessage {
+        TaskAssignmentMessage {
+            task_id: task_id.to_string(),
+            block_id: None,
+            day_id: None,
+            priority: crate::protocol::TaskPriority::Normal,
+            deadline: None,
+            model_id: "llama-70b".to_string(),
+            input: crate::types::TaskInput::TextCompletion(crate::types::TextCompletionInput {
+                prompt: "Hello".to_string(),
+                system_prompt: None,
+                params: Default::default(),
+            }),
+            is_canary: false,
+            expected_hash: None,
+            timeout_secs: 60,
+            group_id: None,
+            stream: false,
+            required_tags: vec![],
+        }
+    }
+
+    fn make_shard_group(mgr: &GroupManager) -> String {
+        let gid = mgr.create_group(GroupPurpose::ModelShard {
+            model_id: "llama-70b".to_string(),
+            total_shards: 3,
+        });
+        mgr.add_member(&gid, "w2", GroupRole::Member);
+        mgr.add_member(&gid, "w3", GroupRole::Member);
+        gid
+    }
+
+    #[test]
+    fn test_task_held_until_quorum() {
+        let mgr = GroupManager::new("w1".to_string());
+        let gid = make_shard_group(&mgr);
+
+        mgr.set_member_ready(&gid, "w1");
+        match mgr.admit_task(&gid, make_task("t1")) {
+            GroupAdmission::Held { ready, required } => {
+                assert_eq!((ready, required), (1, 3));
+            }
+            other => panic!("Expected task to be held, got {:?}", other),
+        }
+
+        mgr.set_member_ready(&gid, "w2");
+        assert!(mgr.take_released_tasks(&gid).is_empty());
+
+        mgr.set_member_ready(&gid, "w3");
+        let released = mgr.take_released_tasks(&gid);
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].task_id, "t1");
+
+        // Once assembled, tasks dispatch immediately
+        assert!(matches!(mgr.admit_task(&gid, make_task("t2")), GroupAdmission::Dispatch(_)));
+        assert!(matches!(
+            mgr.admit_task("grp-missing", make_task("t3")),
+            GroupAdmission::Rejected(_)
+        ));
+    }
+
+    #[test]
+    fn test_configurable_shard_quorum() {
+        let mgr = GroupManager::new("w1".to_string()).with_readiness(2, Duration::from_secs(60));
+        let gid = make_shard_group(&mgr);
+
+        mgr.set_member_ready(&gid, "w1");
+        assert!(!mgr.quorum_met(&gid));
+        mgr.set_member_ready(&gid, "w3");
+        assert!(mgr.quorum_met(&gid));
+
+        // Non-shard groups don't gate tasks
+        let general = mgr.create_group(GroupPurpose::General);
+        assert!(matches!(mgr.admit_task(&general, make_task("t1")), GroupAdmission::Dispatch(_)));
+    }
+
+    #[test]
+    fn test_unassembled_group_disbanded() {
+        let mgr = GroupManager::new("w1".to_string()).with_readiness(0, Duration::ZERO);
+        let gid = make_shard_group(&mgr);
+        mgr.admit_task(&gid, make_task("t1"));
+
+        let disbanded = mgr.disband_unassembled();
+        assert_eq!(disbanded.len(), 1);
+        assert_eq!(disbanded[0].0, gid);
+        assert_eq!(disbanded[0].1[0].task_id, "t1");
+        assert!(mgr.get_group(&gid).is_none());
+    }
+
     #[test]
     fn test_leave_last_member_removes_group() {
         let mgr = GroupManager::new("w1".to_string());
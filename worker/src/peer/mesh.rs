@@ -4,20 +4,47 @@
 //! Uses length-prefixed JSON framing over TCP.
 //!
 //! Wire format:  [4-byte big-endian length][JSON payload]
+//!
+//! Each connection pings its peer every `ping_interval` and records the
+//! round trip of the matching pong as the peer's latency in the registry.
+//!
+//! Model-shard groups pass activations between shards as `ShardInput` /
+//! `ShardOutput` messages. The sender waits for each output (up to
+//! `shard_timeout`); on the receiving side inputs queue for the local shard
+//! in a short bounded queue. An input arriving while it is full is answered
+//! at once with a busy error rather than waited on, so a slow shard never
+//! stops the connection being read (and its pings answered). Outputs are
+//! matched to inputs by group, task and first layer. [`MeshShardRoute`]
+//! sends a group task's activations through the group's shards this way.
+//!
+//! With an account key configured, connections are encrypted by a Noise
+//! handshake before `Hello` (see [`super::noise`]); `require_encryption`
+//! turns away peers that connect in plaintext.
+//!
+//! `Hello` offers the compression algorithms this worker supports and
+//! `HelloAck` names the one chosen; from then on large messages (shard
+//! tensors, results) travel compressed (see [`crate::protocol::Compression`]).
 
-use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use parking_lot::RwLock;
+use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use tracing::{debug, error, info, warn};
 
-use crate::protocol::{PeerMessage, WorkerCapabilities};
+use crate::backend::LayerOutput;
+use crate::error::{Error, Result};
+use crate::executor::{ShardHop, ShardRoute};
+use crate::protocol::{Compression, PeerMessage, WorkerCapabilities};
 
+use super::noise::{self, MeshKey};
+use super::GroupManager;
 use super::PeerInfo;
 use super::PeerRegistry;
 
@@ -31,6 +58,10 @@ pub struct MeshConfig {
     /// Port to listen on (0 = OS-assigned)
     pub listen_port: u16,
 
+    /// IP address or interface name to listen on, which is also the
+    /// address announced to peers (empty = all interfaces)
+    pub listen_host: String,
+
     /// Maximum number of peer connections
     pub max_peers: usize,
 
@@ -42,20 +73,72 @@ pub struct MeshConfig {
 
     /// Remove peers that haven't responded within this duration
     pub stale_timeout: Duration,
+
+    /// Unanswered pings in a row, the oldest older than `stale_timeout`,
+    /// after which a connected peer is marked stale
+    pub max_missed_pings: u32,
+
+    /// Retries after a failed connection attempt before giving up on a peer
+    pub max_connect_retries: u32,
+
+    /// Delay before the first retry (doubles on each further failure)
+    pub retry_base_delay: Duration,
+
+    /// Upper bound on the delay between retries
+    pub retry_max_delay: Duration,
+
+    /// Drop a peer that sends nothing (not even a ping) for this long
+    pub read_timeout: Duration,
+
+    /// Drop a peer whose socket doesn't accept a message within this long
+    pub write_timeout: Duration,
+
+    /// Fail a group task when a peer shard returns no output within this long
+    pub shard_timeout: Duration,
+
+    /// Key shared by this account's workers; connections are encrypted
+    /// when set
+    pub mesh_key: Option<MeshKey>,
+
+    /// Reject peers that connect without encryption
+    pub require_encryption: bool,
+
+    /// Offer and accept compression of large messages
+    pub compression: bool,
 }
 
 impl Default for MeshConfig {
     fn default() -> Self {
         Self {
             listen_port: 0,
+            listen_host: String::new(),
             max_peers: 32,
             connection_timeout: Duration::from_secs(10),
             ping_interval: Duration::from_secs(15),
             stale_timeout: Duration::from_secs(60),
+            max_missed_pings: 3,
+            max_connect_retries: 5,
+            retry_base_delay: Duration::from_secs(1),
+            retry_max_delay: Duration::from_secs(60),
+            read_timeout: Duration::from_secs(45),
+            write_timeout: Duration::from_secs(30),
+            shard_timeout: Duration::from_secs(60),
+            mesh_key: None,
+            require_encryption: false,
+            compression: true,
         }
     }
 }
 
+impl MeshConfig {
+    /// Backoff delay before retry number `attempt` (0-based)
+    pub fn retry_delay(&self, attempt: u32) -> Duration {
+        self.retry_base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.retry_max_delay)
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Events emitted by the mesh to the main loop
 // ─────────────────────────────────────────────────────────────────
@@ -83,6 +166,9 @@ pub enum PeerEvent {
 
     /// TCP listener is ready
     ListenerReady { addr: SocketAddr },
+
+    /// A connected peer stopped answering pings
+    Stale { worker_id: String, missed_pings: u32 },
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -97,8 +183,146 @@ struct PeerConnection {
     /// When this connection was established
     connected_at: Instant,
 
-    /// Handle to the connection task (for shutdown)
-    _task: tokio::task::JoinHandle<()>,
+    /// Connection task (reader, writer and keepalive)
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for PeerConnection {
+    fn drop(&mut self) {
+        // Dropping the connection (disconnect/shutdown) closes the socket
+        self.task.abort();
+    }
+}
+
+/// Shard inputs queued for the local shard before more are turned away
+const SHARD_QUEUE_DEPTH: usize = 8;
+
+/// Activations a peer sent for this worker's shard to run
+#[derive(Debug)]
+pub struct ShardRequest {
+    /// Peer to return the output to
+    pub from: String,
+    pub group_id: String,
+    pub task_id: String,
+    pub layer_start: u32,
+    pub tensor_data: Vec<u8>,
+}
+
+impl ShardRequest {
+    /// Return this shard's output, or why it couldn't run, to the sender
+    pub async fn respond(self, mesh: &PeerMesh, result: Result<LayerOutput>) -> anyhow::Result<()> {
+        let output = shard_output(self.group_id, self.task_id, self.layer_start, result);
+        mesh.send(&self.from, output).await
+    }
+}
+
+/// `ShardOutput` answering the input for `layer_start` with `result`
+fn shard_output(
+    group_id: String,
+    task_id: String,
+    layer_start: u32,
+    result: Result<LayerOutput>,
+) -> PeerMessage {
+    let (layer_end, tensor_data, error, unsupported) = match result {
+        Ok(output) => (output.layer_end, output.tensor_data, None, false),
+        Err(e) => {
+            let unsupported = matches!(e, Error::NotSupported(_));
+            (layer_start, Vec::new(), Some(e.to_string()), unsupported)
+        }
+    };
+    PeerMessage::ShardOutput { group_id, task_id, layer_start, layer_end, tensor_data, error, unsupported }
+}
+
+/// Runs the peer shards of a shard group task over the mesh
+pub struct MeshShardRoute {
+    mesh: Arc<PeerMesh>,
+    groups: Arc<GroupManager>,
+}
+
+impl MeshShardRoute {
+    pub fn new(mesh: Arc<PeerMesh>, groups: Arc<GroupManager>) -> Self {
+        Self { mesh, groups }
+    }
+}
+
+#[async_trait]
+impl ShardRoute for MeshShardRoute {
+    fn shard_hops(&self, group_id: &str) -> Result<Option<Vec<ShardHop>>> {
+        let owners = self.groups.shard_owners(group_id)?;
+        Ok(owners.map(|owners| {
+            owners
+                .into_iter()
+                .map(|owner| {
+                    if owner == self.mesh.worker_id {
+                        ShardHop::Local
+                    } else {
+                        ShardHop::Peer(owner)
+                    }
+                })
+                .collect()
+        }))
+    }
+
+    async fn forward(
+        &self,
+        peer_id: &str,
+        group_id: &str,
+        task_id: &str,
+        layer_start: u32,
+        tensor_data: Vec<u8>,
+    ) -> Result<LayerOutput> {
+        self.mesh
+            .forward_shard(peer_id, group_id, task_id, layer_start, tensor_data)
+            .await
+    }
+}
+
+/// Forwarded shard inputs awaiting output, by (group, task, first layer),
+/// with the peer expected to answer
+type ShardWaiters = HashMap<(String, String, u32), (String, oneshot::Sender<Result<LayerOutput>>)>;
+
+/// Most unanswered pings remembered per connection
+const MAX_OUTSTANDING_PINGS: usize = 64;
+
+/// Pings sent on one connection and not yet answered
+#[derive(Debug, Default)]
+struct LatencyProbe {
+    /// Sequence number and send time, oldest first
+    outstanding: VecDeque<(u64, Instant)>,
+    /// Already reported stale since the last pong
+    stale: bool,
+}
+
+impl LatencyProbe {
+    /// Record a ping as sent
+    fn sent(&mut self, seq: u64) {
+        if self.outstanding.len() >= MAX_OUTSTANDING_PINGS {
+            self.outstanding.pop_front();
+        }
+        self.outstanding.push_back((seq, Instant::now()));
+    }
+
+    /// Round trip of the ping a pong with `seq` answers. Earlier pings
+    /// still outstanding are given up on.
+    fn answered(&mut self, seq: u64) -> Option<Duration> {
+        let index = self.outstanding.iter().position(|(s, _)| *s == seq)?;
+        let (_, sent_at) = self.outstanding[index];
+        self.outstanding.drain(..=index);
+        self.stale = false;
+        Some(sent_at.elapsed())
+    }
+
+    /// Number of pings missed, if that has just made the peer stale: at
+    /// least `max_missed` unanswered, the oldest for over `stale_timeout`
+    fn newly_stale(&mut self, max_missed: u32, stale_timeout: Duration) -> Option<u32> {
+        let (_, oldest) = self.outstanding.front()?;
+        let missed = self.outstanding.len() as u32;
+        if self.stale || missed < max_missed || oldest.elapsed() <= stale_timeout {
+            return None;
+        }
+        self.stale = true;
+        Some(missed)
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -113,7 +337,12 @@ pub struct PeerMesh {
     registry: Arc<PeerRegistry>,
     listener_addr: RwLock<Option<SocketAddr>>,
     connections: RwLock<HashMap<String, PeerConnection>>,
+    /// Peers with a retry loop in progress, by failed attempt count
+    retrying: RwLock<HashMap<String, u32>>,
     event_tx: mpsc::Sender<PeerEvent>,
+    /// Queue for inbound shard inputs (`None` = passed on as events)
+    shard_tx: RwLock<Option<mpsc::Sender<ShardRequest>>>,
+    shard_waiters: Mutex<ShardWaiters>,
 }
 
 impl PeerMesh {
@@ -132,14 +361,20 @@ impl PeerMesh {
             registry,
             listener_addr: RwLock::new(None),
             connections: RwLock::new(HashMap::new()),
+            retrying: RwLock::new(HashMap::new()),
             event_tx,
+            shard_tx: RwLock::new(None),
+            shard_waiters: Mutex::new(HashMap::new()),
         }
     }
 
     /// Start the TCP listener and return the bound address
     pub async fn start(self: &Arc<Self>) -> std::io::Result<SocketAddr> {
-        let bind_addr = format!("0.0.0.0:{}", self.config.listen_port);
-        let listener = TcpListener::bind(&bind_addr).await?;
+        let ip = resolve_listen_ip(&self.config.listen_host)?;
+        let bind_addr = SocketAddr::new(ip, self.config.listen_port);
+        let listener = TcpListener::bind(bind_addr).await.map_err(|e| {
+            std::io::Error::new(e.kind(), format!("cannot listen on {}: {}", bind_addr, e))
+        })?;
         let addr = listener.local_addr()?;
 
         *self.listener_addr.write() = Some(addr);
@@ -185,22 +420,39 @@ impl PeerMesh {
     }
 
     /// Handle an inbound connection — wait for Hello, then set up connection
-    async fn handle_inbound(self: Arc<Self>, mut stream: TcpStream) -> anyhow::Result<()> {
+    async fn handle_inbound(self: Arc<Self>, stream: TcpStream) -> anyhow::Result<()> {
         // Read the first message (should be Hello)
-        let msg = read_framed_message(&mut stream).await?;
+        let (reader, mut writer, msg) =
+            tokio::time::timeout(self.config.read_timeout, self.accept_transport(stream))
+                .await
+                .map_err(|_| anyhow::anyhow!("Hello timeout"))??;
 
         match msg {
-            PeerMessage::Hello { worker_id, capabilities } => {
-                info!(peer = %worker_id, "Peer connected (inbound)");
-
-                // Send HelloAck
+            PeerMessage::Hello { worker_id, capabilities, compression } => {
+                let compression = if self.config.compression {
+                    Compression::negotiate(&compression)
+                } else {
+                    None
+                };
+                info!(
+                    peer = %worker_id,
+                    encrypted = writer.is_encrypted(),
+                    compression = ?compression,
+                    "Peer connected (inbound)"
+                );
+
+                // Send HelloAck, then compress from here on
                 let ack = PeerMessage::HelloAck {
                     worker_id: self.worker_id.clone(),
+                    compression,
                 };
-                write_framed_message(&mut stream, &ack).await?;
+                tokio::time::timeout(self.config.write_timeout, writer.write(&ack))
+                    .await
+                    .map_err(|_| anyhow::anyhow!("HelloAck write timeout"))??;
+                writer.compression = compression;
 
                 // Set up the bidirectional connection
-                self.setup_connection(worker_id, capabilities, stream).await;
+                self.setup_connection(worker_id, capabilities, reader, writer).await;
             }
             other => {
                 warn!(msg_type = %other.type_name(), "Expected Hello, got something else");
@@ -211,6 +463,32 @@ impl PeerMesh {
         Ok(())
     }
 
+    /// Run the encrypted handshake if the peer starts one, then read its
+    /// first message
+    async fn accept_transport(
+        &self,
+        mut stream: TcpStream,
+    ) -> anyhow::Result<(MessageReader, MessageWriter, PeerMessage)> {
+        let first = stream.read_u32().await?;
+        if first == noise::HANDSHAKE_MAGIC {
+            let key = self.config.mesh_key.as_ref().ok_or_else(|| {
+                anyhow::anyhow!("Peer requested encryption, but no account key is configured")
+            })?;
+            let session = noise::respond(&mut stream, key).await?;
+            let (mut reader, writer) = split_stream(stream, Some(session));
+            let msg = reader.read().await?;
+            Ok((reader, writer, msg))
+        } else if self.config.require_encryption {
+            warn!("Rejecting unencrypted peer (encryption required)");
+            Err(anyhow::anyhow!("Unencrypted peer rejected"))
+        } else {
+            // Plaintext peer: the first four bytes were the Hello's length
+            let (mut reader, writer) = split_stream(stream, None);
+            let msg = reader.read_body(first).await?;
+            Ok((reader, writer, msg))
+        }
+    }
+
     /// Connect to a peer by address
     pub async fn connect(self: &Arc<Self>, peer: &PeerInfo) -> anyhow::Result<()> {
         // Don't connect to ourselves
@@ -232,28 +510,51 @@ impl PeerMesh {
         .await
         .map_err(|_| anyhow::anyhow!("Connection timeout"))??;
 
+        // Encrypt before anything else is sent
+        let session = match &self.config.mesh_key {
+            Some(key) => Some(
+                tokio::time::timeout(self.config.connection_timeout, noise::initiate(&mut stream, key))
+                    .await
+                    .map_err(|_| anyhow::anyhow!("Encryption handshake timeout"))??,
+            ),
+            None if self.config.require_encryption => {
+                return Err(anyhow::anyhow!("Encryption required, but no account key is configured"));
+            }
+            None => None,
+        };
+        let (mut reader, mut writer) = split_stream(stream, session);
+
         // Send Hello
+        let offer = if self.config.compression { Compression::SUPPORTED } else { &[] };
         let hello = PeerMessage::Hello {
             worker_id: self.worker_id.clone(),
             capabilities: self.worker_capabilities.clone(),
+            compression: offer.to_vec(),
         };
-        write_framed_message(&mut stream, &hello).await?;
+        tokio::time::timeout(self.config.write_timeout, writer.write(&hello))
+            .await
+            .map_err(|_| anyhow::anyhow!("Hello write timeout"))??;
 
         // Wait for HelloAck
-        let ack = tokio::time::timeout(
-            Duration::from_secs(5),
-            read_framed_message(&mut stream),
-        )
-        .await
-        .map_err(|_| anyhow::anyhow!("HelloAck timeout"))??;
+        let ack = tokio::time::timeout(Duration::from_secs(5), reader.read())
+            .await
+            .map_err(|_| anyhow::anyhow!("HelloAck timeout"))??;
 
         match ack {
-            PeerMessage::HelloAck { worker_id: peer_id } => {
-                info!(peer = %peer_id, "Peer handshake complete (outbound)");
+            PeerMessage::HelloAck { worker_id: peer_id, compression } => {
+                // Only ever use an algorithm that was offered
+                writer.compression = compression.filter(|c| offer.contains(c));
+                info!(
+                    peer = %peer_id,
+                    encrypted = writer.is_encrypted(),
+                    compression = ?writer.compression,
+                    "Peer handshake complete (outbound)"
+                );
                 self.setup_connection(
                     peer_id,
                     peer.capabilities.clone(),
-                    stream,
+                    reader,
+                    writer,
                 ).await;
             }
             _ => {
@@ -264,46 +565,94 @@ impl PeerMesh {
         Ok(())
     }
 
+    /// Connect to a peer, retrying failed attempts with exponential backoff
+    ///
+    /// Gives up after `max_connect_retries` retries, or when the peer is
+    /// disconnected via [`disconnect`](Self::disconnect) meanwhile. Only one
+    /// retry loop runs per peer; further calls return immediately while one
+    /// is in progress.
+    pub async fn connect_with_retry(self: &Arc<Self>, peer: &PeerInfo) -> anyhow::Result<()> {
+        match self.retrying.write().entry(peer.worker_id.clone()) {
+            Entry::Occupied(_) => return Ok(()),
+            Entry::Vacant(entry) => {
+                entry.insert(0);
+            }
+        }
+
+        let mut attempt = 0;
+        loop {
+            let err = match self.connect(peer).await {
+                Ok(()) => {
+                    // Success resets the peer's backoff
+                    self.retrying.write().remove(&peer.worker_id);
+                    return Ok(());
+                }
+                Err(e) => e,
+            };
+
+            if attempt >= self.config.max_connect_retries {
+                self.retrying.write().remove(&peer.worker_id);
+                warn!(peer = %peer.worker_id, attempts = attempt + 1, error = %err, "Giving up connecting to peer");
+                return Err(err);
+            }
+
+            let delay = self.config.retry_delay(attempt);
+            debug!(
+                peer = %peer.worker_id,
+                attempt = attempt + 1,
+                retry_in_ms = delay.as_millis() as u64,
+                error = %err,
+                "Peer connection failed, retrying"
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+
+            match self.retrying.write().get_mut(&peer.worker_id) {
+                Some(failures) => *failures = attempt,
+                None => return Err(err), // Cancelled by disconnect()
+            }
+        }
+    }
+
     /// Set up a bidirectional connection after handshake
     async fn setup_connection(
         self: &Arc<Self>,
         peer_worker_id: String,
         capabilities: WorkerCapabilities,
-        stream: TcpStream,
+        reader: MessageReader,
+        writer: MessageWriter,
     ) {
-        let (read_half, write_half) = stream.into_split();
         let (write_tx, write_rx) = mpsc::channel::<PeerMessage>(64);
 
-        // Spawn the writer task
-        let peer_id_w = peer_worker_id.clone();
-        let writer_handle = tokio::spawn(async move {
-            write_loop(peer_id_w, write_half, write_rx).await;
-        });
-
-        // Spawn the reader task
+        // One task drives both halves; whichever fails first (EOF, error,
+        // or a timeout) ends the connection
         let mesh = Arc::clone(self);
-        let peer_id_r = peer_worker_id.clone();
-        let event_tx = self.event_tx.clone();
-        let reader_handle = tokio::spawn(async move {
-            read_loop(peer_id_r.clone(), read_half, event_tx.clone()).await;
-            // When reader exits, the connection is done
-            let _ = event_tx
+        let peer_id = peer_worker_id.clone();
+        let reply_tx = write_tx.clone();
+        let task = tokio::spawn(async move {
+            let probe = Mutex::new(LatencyProbe::default());
+            let reason = tokio::select! {
+                reason = read_loop(&peer_id, reader, &mesh, reply_tx, &probe) => reason,
+                reason = write_loop(&peer_id, writer, write_rx, &mesh, &probe) => reason,
+            };
+            info!(peer = %peer_id, reason = %reason, "Peer connection dropped");
+            // Shard outputs still expected from the peer won't arrive
+            mesh.shard_waiters.lock().retain(|_, (from, _)| *from != peer_id);
+            let _ = mesh
+                .event_tx
                 .send(PeerEvent::Disconnected {
-                    worker_id: peer_id_r.clone(),
-                    reason: "Connection closed".to_string(),
+                    worker_id: peer_id.clone(),
+                    reason,
                 })
                 .await;
-            mesh.connections.write().remove(&peer_id_r);
+            mesh.connections.write().remove(&peer_id);
         });
 
-        // We only track the writer handle; if the reader exits, it cleans up
-        let _ = reader_handle; // Let it run independently
-
         // Store connection
         let conn = PeerConnection {
             write_tx,
             connected_at: Instant::now(),
-            _task: writer_handle,
+            task,
         };
         self.connections
             .write()
@@ -321,6 +670,8 @@ impl PeerMesh {
                 status: crate::protocol::WorkerStatus::Ready,
                 last_seen: Instant::now(),
                 latency_ms: None,
+                stale: false,
+                capacity_pct: None,
                 groups: vec![],
             });
         }
@@ -335,11 +686,14 @@ impl PeerMesh {
 
     /// Send a message to a specific peer
     pub async fn send(&self, worker_id: &str, msg: PeerMessage) -> anyhow::Result<()> {
-        let conns = self.connections.read();
-        let conn = conns
+        // Cloned out so the lock isn't held while waiting for queue room
+        let write_tx = self
+            .connections
+            .read()
             .get(worker_id)
+            .map(|conn| conn.write_tx.clone())
             .ok_or_else(|| anyhow::anyhow!("Not connected to peer {}", worker_id))?;
-        conn.write_tx
+        write_tx
             .send(msg)
             .await
             .map_err(|_| anyhow::anyhow!("Peer write channel closed"))?;
@@ -376,8 +730,76 @@ impl PeerMesh {
         }
     }
 
-    /// Disconnect from a specific peer
+    /// Take over inbound shard inputs, which otherwise arrive as
+    /// [`PeerEvent::MessageReceived`]. Each request must be answered with
+    /// [`ShardRequest::respond`]; while the short queue is full, further
+    /// inputs are answered with a busy error.
+    pub fn serve_shards(&self) -> mpsc::Receiver<ShardRequest> {
+        let (tx, rx) = mpsc::channel(SHARD_QUEUE_DEPTH);
+        *self.shard_tx.write() = Some(tx);
+        rx
+    }
+
+    /// Send activations for `task_id` to the shard on `peer_id` and wait
+    /// for its output. Fails if the shard reports an error, the peer
+    /// disconnects, or no output arrives within `shard_timeout`.
+    pub async fn forward_shard(
+        &self,
+        peer_id: &str,
+        group_id: &str,
+        task_id: &str,
+        layer_start: u32,
+        tensor_data: Vec<u8>,
+    ) -> Result<LayerOutput> {
+        if task_id.is_empty() {
+            return Err(Error::Protocol("shard input needs a task ID".to_string()));
+        }
+        let key = (group_id.to_string(), task_id.to_string(), layer_start);
+        let (reply_tx, reply_rx) = oneshot::channel();
+        match self.shard_waiters.lock().entry(key.clone()) {
+            Entry::Occupied(_) => {
+                return Err(Error::Protocol(format!(
+                    "task {} already waiting on layer {} of group {}",
+                    task_id, layer_start, group_id
+                )));
+            }
+            Entry::Vacant(slot) => {
+                slot.insert((peer_id.to_string(), reply_tx));
+            }
+        }
+
+        let input = PeerMessage::ShardInput {
+            group_id: group_id.to_string(),
+            task_id: task_id.to_string(),
+            layer_start,
+            tensor_data,
+        };
+        // Waits for room in the peer's write queue when the link is slow
+        if let Err(e) = self.send(peer_id, input).await {
+            self.shard_waiters.lock().remove(&key);
+            return Err(Error::Connection(e.to_string()));
+        }
+
+        let timeout = self.config.shard_timeout;
+        let reply = tokio::time::timeout(timeout, reply_rx).await;
+        self.shard_waiters.lock().remove(&key);
+        match reply {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(Error::Connection(format!(
+                "Peer {} disconnected before returning shard output",
+                peer_id
+            ))),
+            Err(_) => Err(Error::ShardTimeout {
+                group_id: group_id.to_string(),
+                peer_id: peer_id.to_string(),
+                timeout_secs: timeout.as_secs(),
+            }),
+        }
+    }
+
+    /// Disconnect from a specific peer (also stops any pending retries)
     pub fn disconnect(&self, worker_id: &str) {
+        self.retrying.write().remove(worker_id);
         self.connections.write().remove(worker_id);
     }
 
@@ -391,8 +813,9 @@ impl PeerMesh {
         *self.listener_addr.read()
     }
 
-    /// Shut down the mesh (drops all connections)
+    /// Shut down the mesh (drops all connections and pending retries)
     pub fn shutdown(&self) {
+        self.retrying.write().clear();
         self.connections.write().clear();
     }
 }
@@ -407,75 +830,390 @@ const MAX_MESSAGE_SIZE: u32 = 64 * 1024 * 1024; // 64 MB (for tensor data)
 async fn read_framed_message<R: AsyncReadExt + Unpin>(reader: &mut R) -> anyhow::Result<PeerMessage> {
     // Read 4-byte big-endian length
     let len = reader.read_u32().await?;
-    if len > MAX_MESSAGE_SIZE {
-        return Err(anyhow::anyhow!(
-            "Message too large: {} bytes (max {})",
-            len,
-            MAX_MESSAGE_SIZE
-        ));
-    }
+    let buf = read_frame_body(reader, len, MAX_MESSAGE_SIZE).await?;
+    Ok(PeerMessage::from_wire(&buf)?)
+}
 
-    // Read the JSON payload
+/// Read the `len`-byte payload of a frame
+async fn read_frame_body<R: AsyncReadExt + Unpin>(reader: &mut R, len: u32, max: u32) -> anyhow::Result<Vec<u8>> {
+    if len > max {
+        return Err(anyhow::anyhow!("Message too large: {} bytes (max {})", len, max));
+    }
     let mut buf = vec![0u8; len as usize];
     reader.read_exact(&mut buf).await?;
-
-    let msg: PeerMessage = serde_json::from_slice(&buf)?;
-    Ok(msg)
+    Ok(buf)
 }
 
 /// Write a length-prefixed JSON message to a stream
+#[cfg(test)]
 async fn write_framed_message<W: AsyncWriteExt + Unpin>(
     writer: &mut W,
     msg: &PeerMessage,
 ) -> anyhow::Result<()> {
-    let json = serde_json::to_vec(msg)?;
+    write_frame(writer, &serde_json::to_vec(msg)?).await
+}
+
+/// Write `json` to a stream behind its length prefix
+async fn write_frame<W: AsyncWriteExt + Unpin>(writer: &mut W, json: &[u8]) -> anyhow::Result<()> {
     let len = json.len() as u32;
 
     writer.write_u32(len).await?;
-    writer.write_all(&json).await?;
+    writer.write_all(json).await?;
     writer.flush().await?;
 
     Ok(())
 }
 
-/// Background task: reads messages from a peer and forwards to event channel
+/// Split a handshaken stream, encrypting frames if a session was set up
+fn split_stream(stream: TcpStream, session: Option<noise::Session>) -> (MessageReader, MessageWriter) {
+    let (read_half, write_half) = stream.into_split();
+    let (recv, send) = match session {
+        Some(session) => (Some(session.recv), Some(session.send)),
+        None => (None, None),
+    };
+    (
+        MessageReader { half: read_half, cipher: recv },
+        MessageWriter { half: write_half, cipher: send, compression: None },
+    )
+}
+
+/// Receiving side of a peer connection
+struct MessageReader {
+    half: tokio::net::tcp::OwnedReadHalf,
+    cipher: Option<noise::RecvCipher>,
+}
+
+impl MessageReader {
+    async fn read(&mut self) -> anyhow::Result<PeerMessage> {
+        if self.cipher.is_none() {
+            return read_framed_message(&mut self.half).await;
+        }
+        let len = self.half.read_u32().await?;
+        self.read_body(len).await
+    }
+
+    /// Read a message whose length prefix has already been read
+    async fn read_body(&mut self, len: u32) -> anyhow::Result<PeerMessage> {
+        let buf = match &mut self.cipher {
+            Some(cipher) => {
+                let max_len = noise::sealed_len(MAX_MESSAGE_SIZE as usize) as u32;
+                let sealed = read_frame_body(&mut self.half, len, max_len).await?;
+                cipher.open(sealed)?
+            }
+            None => read_frame_body(&mut self.half, len, MAX_MESSAGE_SIZE).await?,
+        };
+        Ok(PeerMessage::from_wire(&buf)?)
+    }
+}
+
+/// Sending side of a peer connection
+struct MessageWriter {
+    half: tokio::net::tcp::OwnedWriteHalf,
+    cipher: Option<noise::SendCipher>,
+    /// Algorithm agreed in the handshake for large messages
+    compression: Option<Compression>,
+}
+
+impl MessageWriter {
+    fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    async fn write(&mut self, msg: &PeerMessage) -> anyhow::Result<()> {
+        let json = msg.to_wire(self.compression)?;
+        let Some(cipher) = &mut self.cipher else {
+            return write_frame(&mut self.half, &json).await;
+        };
+        let sealed = cipher.seal(json)?;
+        self.half.write_u32(sealed.len() as u32).await?;
+        self.half.write_all(&sealed).await?;
+        self.half.flush().await?;
+        Ok(())
+    }
+}
+
+/// Read messages from a peer and forward them to the event channel,
+/// answering pings and recording latency from pongs. Returns why the
+/// connection ended.
 async fn read_loop(
-    peer_id: String,
-    mut reader: tokio::net::tcp::OwnedReadHalf,
-    event_tx: mpsc::Sender<PeerEvent>,
-) {
+    peer_id: &str,
+    mut reader: MessageReader,
+    mesh: &PeerMesh,
+    reply_tx: mpsc::Sender<PeerMessage>,
+    probe: &Mutex<LatencyProbe>,
+) -> String {
+    let read_timeout = mesh.config.read_timeout;
     loop {
-        match read_framed_message(&mut reader).await {
-            Ok(msg) => {
-                let _ = event_tx
-                    .send(PeerEvent::MessageReceived {
-                        from: peer_id.clone(),
-                        message: msg,
-                    })
-                    .await;
-            }
-            Err(e) => {
+        let msg = match tokio::time::timeout(read_timeout, reader.read()).await {
+            Ok(Ok(msg)) => msg,
+            Ok(Err(e)) => {
                 debug!(peer = %peer_id, error = %e, "Peer read error");
-                break;
+                return "Connection closed".to_string();
             }
+            Err(_) => {
+                warn!(
+                    peer = %peer_id,
+                    timeout_ms = read_timeout.as_millis() as u64,
+                    "Peer went silent"
+                );
+                return "read timeout".to_string();
+            }
+        };
+
+        let msg = match msg {
+            PeerMessage::Ping { seq } => {
+                let _ = reply_tx.send(PeerMessage::Pong { seq }).await;
+                msg
+            }
+            PeerMessage::ShardInput { group_id, task_id, layer_start, tensor_data } => {
+                let shard_tx = mesh.shard_tx.read().clone();
+                match shard_tx {
+                    Some(shard_tx) => {
+                        let request = ShardRequest {
+                            from: peer_id.to_string(),
+                            group_id,
+                            task_id,
+                            layer_start,
+                            tensor_data,
+                        };
+                        queue_shard_input(&shard_tx, &reply_tx, request).await;
+                        continue;
+                    }
+                    None => PeerMessage::ShardInput { group_id, task_id, layer_start, tensor_data },
+                }
+            }
+            PeerMessage::ShardOutput {
+                group_id,
+                task_id,
+                layer_start,
+                layer_end,
+                tensor_data,
+                error,
+                unsupported,
+            } => {
+                let waiter = {
+                    let mut waiters = mesh.shard_waiters.lock();
+                    let key = (group_id, task_id, layer_start);
+                    match waiters.get(&key) {
+                        Some((from, _)) if from == peer_id => waiters.remove(&key).map(|w| (key, w.1)),
+                        _ => None,
+                    }
+                };
+                match waiter {
+                    Some(((_, task_id, _), waiter_tx)) => {
+                        let reply = match error {
+                            Some(message) if unsupported => Err(Error::NotSupported(format!(
+                                "Shard on peer {} can't run split layers: {}",
+                                peer_id, message
+                            ))),
+                            Some(message) => Err(Error::ExecutionFailed {
+                                task_id: Some(task_id),
+                                message: format!("Shard on peer {} failed: {}", peer_id, message),
+                            }),
+                            None => Ok(LayerOutput { layer_end, tensor_data }),
+                        };
+                        let _ = waiter_tx.send(reply);
+                    }
+                    None => debug!(peer = %peer_id, "Shard output with no pending input"),
+                }
+                continue;
+            }
+            PeerMessage::Pong { seq } => {
+                let rtt = probe.lock().answered(seq);
+                match rtt {
+                    Some(rtt) => {
+                        let latency_ms = rtt.as_millis().min(u32::MAX as u128) as u32;
+                        debug!(peer = %peer_id, seq, latency_ms, "Peer pong");
+                        mesh.registry.update_latency(peer_id, latency_ms);
+                    }
+                    None => debug!(peer = %peer_id, seq, "Pong for an unknown ping"),
+                }
+                continue;
+            }
+            other => other,
+        };
+        let _ = mesh
+            .event_tx
+            .send(PeerEvent::MessageReceived {
+                from: peer_id.to_string(),
+                message: msg,
+            })
+            .await;
+    }
+}
+
+/// Queue a peer's shard input for the local shard, answering at once with
+/// an error if it has no task ID or the queue is full. Never waits for the
+/// shard, so the peer's pings are still read while it works.
+async fn queue_shard_input(
+    shard_tx: &mpsc::Sender<ShardRequest>,
+    reply_tx: &mpsc::Sender<PeerMessage>,
+    request: ShardRequest,
+) {
+    if request.task_id.is_empty() {
+        let refusal = Error::Protocol("shard input needs a task ID".to_string());
+        return refuse(reply_tx, request, refusal).await;
+    }
+    match shard_tx.try_send(request) {
+        Ok(()) => {}
+        Err(mpsc::error::TrySendError::Full(request)) => {
+            warn!(peer = %request.from, task_id = %request.task_id, "Shard queue full, input refused");
+            refuse(reply_tx, request, Error::ResourceLimit("shard queue full".to_string())).await;
+        }
+        Err(mpsc::error::TrySendError::Closed(request)) => {
+            debug!(peer = %request.from, "Shard input dropped, shard no longer served");
         }
     }
 }
 
-/// Background task: writes messages to a peer from a channel
+/// Answer a shard input with `error` instead of running it
+async fn refuse(reply_tx: &mpsc::Sender<PeerMessage>, request: ShardRequest, error: Error) {
+    let output = shard_output(request.group_id, request.task_id, request.layer_start, Err(error));
+    let _ = reply_tx.send(output).await;
+}
+
+/// Write queued messages to a peer, pinging it every `ping_interval` to
+/// measure latency and keep its read timeout from firing. Returns why the
+/// connection ended.
 async fn write_loop(
-    peer_id: String,
-    mut writer: tokio::net::tcp::OwnedWriteHalf,
+    peer_id: &str,
+    mut writer: MessageWriter,
     mut write_rx: mpsc::Receiver<PeerMessage>,
-) {
-    while let Some(msg) = write_rx.recv().await {
-        if let Err(e) = write_framed_message(&mut writer, &msg).await {
-            debug!(peer = %peer_id, error = %e, "Peer write error");
-            break;
+    mesh: &PeerMesh,
+    probe: &Mutex<LatencyProbe>,
+) -> String {
+    let config = &mesh.config;
+    let ping_interval = config.ping_interval;
+    let mut ping_timer =
+        tokio::time::interval_at(tokio::time::Instant::now() + ping_interval, ping_interval);
+    let mut seq = 0;
+
+    loop {
+        let msg = tokio::select! {
+            msg = write_rx.recv() => match msg {
+                Some(msg) => msg,
+                None => return "Connection closed".to_string(),
+            },
+            _ = ping_timer.tick() => {
+                let missed = probe
+                    .lock()
+                    .newly_stale(config.max_missed_pings, config.stale_timeout);
+                if let Some(missed_pings) = missed {
+                    warn!(peer = %peer_id, missed_pings, "Peer stopped answering pings");
+                    mesh.registry.mark_stale(peer_id);
+                    let _ = mesh
+                        .event_tx
+                        .send(PeerEvent::Stale {
+                            worker_id: peer_id.to_string(),
+                            missed_pings,
+                        })
+                        .await;
+                }
+                seq += 1;
+                probe.lock().sent(seq);
+                PeerMessage::Ping { seq }
+            }
+        };
+
+        match tokio::time::timeout(config.write_timeout, writer.write(&msg)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                debug!(peer = %peer_id, error = %e, "Peer write error");
+                return "Connection closed".to_string();
+            }
+            Err(_) => return "write timeout".to_string(),
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Listen address
+// ─────────────────────────────────────────────────────────────────
+
+/// Address to bind the mesh listener to: an IP literal, the first address
+/// of a network interface given by name (IPv4 preferred), or all
+/// interfaces if `host` is empty
+pub fn resolve_listen_ip(host: &str) -> std::io::Result<IpAddr> {
+    let host = host.trim();
+    if host.is_empty() {
+        return Ok(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    }
+    if let Ok(ip) = host.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>() {
+        return Ok(ip);
+    }
+    interface_ip(host)
+}
+
+#[cfg(unix)]
+fn interface_ip(name: &str) -> std::io::Result<IpAddr> {
+    use std::ffi::CStr;
+    use std::io::{Error, ErrorKind};
+    use std::net::Ipv6Addr;
+
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: on success `list` points to a linked list owned by us until
+    // it is released with freeifaddrs below
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        return Err(Error::last_os_error());
+    }
+
+    let mut found = false;
+    let mut v4 = None;
+    let mut v6 = None;
+    let mut node = list;
+    while !node.is_null() {
+        // SAFETY: `node` is a non-null entry of the getifaddrs list, whose
+        // name is a NUL-terminated string and whose address (if any)
+        // matches its family
+        let entry = unsafe { &*node };
+        node = entry.ifa_next;
+        if entry.ifa_name.is_null() || unsafe { CStr::from_ptr(entry.ifa_name) }.to_bytes() != name.as_bytes() {
+            continue;
+        }
+        found = true;
+        if entry.ifa_addr.is_null() {
+            continue;
         }
+        match unsafe { (*entry.ifa_addr).sa_family } as i32 {
+            libc::AF_INET => {
+                let addr = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in) };
+                v4.get_or_insert(IpAddr::V4(Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr))));
+            }
+            libc::AF_INET6 => {
+                let addr = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in6) };
+                let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+                // Link-local addresses need a scope ID peers can't use
+                if ip.segments()[0] & 0xffc0 != 0xfe80 {
+                    v6.get_or_insert(IpAddr::V6(ip));
+                }
+            }
+            _ => {}
+        }
+    }
+    // SAFETY: `list` came from a successful getifaddrs and isn't used after
+    unsafe { libc::freeifaddrs(list) };
+
+    match (found, v4.or(v6)) {
+        (_, Some(ip)) => Ok(ip),
+        (true, None) => Err(Error::new(
+            ErrorKind::AddrNotAvailable,
+            format!("network interface '{}' has no usable IP address", name),
+        )),
+        (false, None) => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("'{}' is neither an IP address nor a network interface", name),
+        )),
     }
 }
 
+#[cfg(not(unix))]
+fn interface_ip(name: &str) -> std::io::Result<IpAddr> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("'{}' is not an IP address (interface names need a Unix host)", name),
+    ))
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────
@@ -483,6 +1221,7 @@ async fn write_loop(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::peer::testing;
 
     #[test]
     fn test_mesh_config_defaults() {
@@ -492,6 +1231,659 @@ mod tests {
         assert_eq!(config.ping_interval, Duration::from_secs(15));
     }
 
+    #[test]
+    fn test_retry_delay_backoff() {
+        let config = MeshConfig {
+            retry_base_delay: Duration::from_millis(100),
+            retry_max_delay: Duration::from_millis(500),
+            ..MeshConfig::default()
+        };
+        assert_eq!(config.retry_delay(0), Duration::from_millis(100));
+        assert_eq!(config.retry_delay(1), Duration::from_millis(200));
+        assert_eq!(config.retry_delay(2), Duration::from_millis(400));
+        assert_eq!(config.retry_delay(3), Duration::from_millis(500));
+        assert_eq!(config.retry_delay(40), Duration::from_millis(500));
+    }
+
+    /// A local port with nothing listening on it
+    fn unused_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    #[tokio::test]
+    async fn test_listen_on_configured_address() {
+        let mesh = testing::mesh(
+            "w1",
+            MeshConfig {
+                listen_host: "127.0.0.1".to_string(),
+                ..MeshConfig::default()
+            },
+        ).0;
+        let addr = mesh.start().await.unwrap();
+
+        assert_eq!(addr.ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_ne!(addr.port(), 0);
+        // The announced address is the bound one
+        assert_eq!(mesh.listen_addr(), Some(addr));
+        TcpStream::connect(addr).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_listen_address_fails() {
+        for host in ["no-such-interface0", "192.0.2.1"] {
+            let mesh = testing::mesh(
+                "w1",
+                MeshConfig {
+                    listen_host: host.to_string(),
+                    ..MeshConfig::default()
+                },
+            ).0;
+            assert!(mesh.start().await.is_err(), "{} bound", host);
+            assert_eq!(mesh.listen_addr(), None);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_resolve_interface_name() {
+        assert_eq!(resolve_listen_ip("lo").unwrap(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(resolve_listen_ip("").unwrap(), IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(resolve_listen_ip("[::1]").unwrap(), "::1".parse::<IpAddr>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_connect_retry_after_refusal() {
+        let port = unused_port();
+
+        let mesh = testing::mesh(
+            "w1",
+            MeshConfig {
+                retry_base_delay: Duration::from_millis(200),
+                ..MeshConfig::default()
+            },
+        ).0;
+        let peer = testing::peer("w2", port);
+
+        let retry = {
+            let mesh = mesh.clone();
+            let peer = peer.clone();
+            tokio::spawn(async move { mesh.connect_with_retry(&peer).await })
+        };
+
+        // First attempt is refused; the peer comes up before the retry
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(mesh.connected_peers().is_empty());
+        assert_eq!(mesh.retrying.read().get("w2"), Some(&0));
+
+        let remote = testing::mesh(
+            "w2",
+            MeshConfig {
+                listen_port: port,
+                ..MeshConfig::default()
+            },
+        ).0;
+        remote.start().await.unwrap();
+
+        retry.await.unwrap().unwrap();
+        assert_eq!(mesh.connected_peers(), vec!["w2".to_string()]);
+        // Success resets the backoff state
+        assert!(mesh.retrying.read().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_connect_retry_gives_up() {
+        let port = unused_port();
+
+        let mesh = testing::mesh(
+            "w1",
+            MeshConfig {
+                max_connect_retries: 2,
+                retry_base_delay: Duration::from_millis(10),
+                ..MeshConfig::default()
+            },
+        ).0;
+        let peer = testing::peer("w2", port);
+
+        assert!(mesh.connect_with_retry(&peer).await.is_err());
+        assert!(mesh.retrying.read().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_silent_peer_read_timeout() {
+        let (mesh, mut event_rx) = testing::mesh(
+            "w1",
+            MeshConfig {
+                read_timeout: Duration::from_millis(300),
+                ..MeshConfig::default()
+            },
+        );
+        let addr = mesh.start().await.unwrap();
+
+        // Handshake, then go silent without closing
+        let mut stream = TcpStream::connect(("127.0.0.1", addr.port())).await.unwrap();
+        let hello = PeerMessage::Hello {
+            worker_id: "w2".to_string(),
+            capabilities: testing::capabilities(),
+            compression: vec![],
+        };
+        write_framed_message(&mut stream, &hello).await.unwrap();
+        let ack = read_framed_message(&mut stream).await.unwrap();
+        assert!(matches!(ack, PeerMessage::HelloAck { .. }));
+
+        let reason = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                match event_rx.recv().await.unwrap() {
+                    PeerEvent::Disconnected { worker_id, reason } => {
+                        assert_eq!(worker_id, "w2");
+                        return reason;
+                    }
+                    _ => continue,
+                }
+            }
+        })
+        .await
+        .expect("silent peer was never dropped");
+        assert_eq!(reason, "read timeout");
+        assert!(mesh.connected_peers().is_empty());
+
+        // The mesh closed its end of the socket
+        let mut buf = [0u8; 1];
+        let n = tokio::time::timeout(Duration::from_secs(1), stream.read(&mut buf))
+            .await
+            .unwrap()
+            .unwrap_or(0);
+        assert_eq!(n, 0);
+    }
+
+    /// Connect to `addr` as worker `worker_id` over a raw socket
+    async fn handshake(addr: SocketAddr, worker_id: &str) -> TcpStream {
+        let mut stream = TcpStream::connect(("127.0.0.1", addr.port())).await.unwrap();
+        let hello = PeerMessage::Hello {
+            worker_id: worker_id.to_string(),
+            capabilities: testing::capabilities(),
+            compression: vec![],
+        };
+        write_framed_message(&mut stream, &hello).await.unwrap();
+        let ack = read_framed_message(&mut stream).await.unwrap();
+        assert!(matches!(ack, PeerMessage::HelloAck { .. }));
+        stream
+    }
+
+    #[tokio::test]
+    async fn test_ping_pong_records_latency() {
+        let registry = Arc::new(PeerRegistry::new());
+        let (event_tx, _event_rx) = mpsc::channel(100);
+        let mesh = Arc::new(PeerMesh::new(
+            MeshConfig {
+                ping_interval: Duration::from_millis(100),
+                ..MeshConfig::default()
+            },
+            "w1".to_string(),
+            testing::capabilities(),
+            registry.clone(),
+            event_tx,
+        ));
+        let addr = mesh.start().await.unwrap();
+        let mut stream = handshake(addr, "w2").await;
+
+        // Answer the first ping after a delay
+        let seq = loop {
+            if let PeerMessage::Ping { seq } = read_framed_message(&mut stream).await.unwrap() {
+                break seq;
+            }
+        };
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        write_framed_message(&mut stream, &PeerMessage::Pong { seq }).await.unwrap();
+
+        let latency = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(ms) = registry.get("w2").and_then(|p| p.latency_ms) {
+                    return ms;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("latency was never recorded");
+        assert!((40..5000).contains(&latency), "latency {}ms", latency);
+        assert!(!registry.get("w2").unwrap().stale);
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_pings_mark_peer_stale() {
+        let registry = Arc::new(PeerRegistry::new());
+        let (event_tx, mut event_rx) = mpsc::channel(100);
+        let mesh = Arc::new(PeerMesh::new(
+            MeshConfig {
+                ping_interval: Duration::from_millis(50),
+                stale_timeout: Duration::from_millis(120),
+                max_missed_pings: 2,
+                ..MeshConfig::default()
+            },
+            "w1".to_string(),
+            testing::capabilities(),
+            registry.clone(),
+            event_tx,
+        ));
+        let addr = mesh.start().await.unwrap();
+        let _stream = handshake(addr, "w2").await;
+
+        let missed = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let PeerEvent::Stale { worker_id, missed_pings } = event_rx.recv().await.unwrap() {
+                    assert_eq!(worker_id, "w2");
+                    return missed_pings;
+                }
+            }
+        })
+        .await
+        .expect("silent peer was never marked stale");
+        assert!(missed >= 2, "{} missed", missed);
+        assert!(registry.get("w2").unwrap().stale);
+        // Still connected; only the read timeout drops it
+        assert_eq!(mesh.connected_peers(), vec!["w2".to_string()]);
+    }
+
+    /// Meshes for a group coordinator "w1" connected to shard "w2"
+    async fn shard_pair(shard_timeout: Duration) -> (Arc<PeerMesh>, Arc<PeerMesh>) {
+        let coordinator = testing::mesh(
+            "w1",
+            MeshConfig {
+                shard_timeout,
+                ..MeshConfig::default()
+            },
+        ).0;
+        let shard = testing::mesh("w2", MeshConfig::default()).0;
+        let addr = shard.start().await.unwrap();
+        coordinator.connect(&testing::peer("w2", addr.port())).await.unwrap();
+        (coordinator, shard)
+    }
+
+    #[tokio::test]
+    async fn test_shard_forward_one_hop() {
+        use crate::backend::{BackendConfig, InferenceBackend, MockBackend, MockConfig};
+
+        let (coordinator, shard) = shard_pair(Duration::from_secs(5)).await;
+        let mut requests = shard.serve_shards();
+        let backend = MockBackend::with_config(
+            MockConfig { shard_layers: 4, token_latency_ms: 0, ..Default::default() },
+            BackendConfig::default(),
+        );
+        tokio::spawn({
+            let shard = shard.clone();
+            async move {
+                while let Some(mut request) = requests.recv().await {
+                    let tensor = std::mem::take(&mut request.tensor_data);
+                    let result = backend.forward_layers(request.layer_start, tensor).await;
+                    request.respond(&shard, result).await.unwrap();
+                }
+            }
+        });
+
+        let output = coordinator
+            .forward_shard("w2", "g1", "t1", 8, vec![1, 2, 3, 255])
+            .await
+            .unwrap();
+        assert_eq!(output, LayerOutput { layer_end: 12, tensor_data: vec![5, 6, 7, 3] });
+        assert!(coordinator.shard_waiters.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_group_task_runs_across_shards() {
+        use crate::backend::{BackendConfig, BackendRegistry, BackendType, InferenceBackend, MockBackend, MockConfig};
+        use crate::executor::{ExecutorConfig, TaskExecutor};
+        use crate::peer::{GroupPurpose, GroupRole};
+        use crate::protocol::TaskAssignmentMessage;
+        use crate::types::{GenerationParams, TaskInput, TaskOutput, TextCompletionInput};
+
+        let (coordinator, shard) = shard_pair(Duration::from_secs(5)).await;
+
+        // w2 runs shard 1 of 2, four layers
+        let mut requests = shard.serve_shards();
+        let backend = MockBackend::with_config(
+            MockConfig { shard_layers: 4, token_latency_ms: 0, ..Default::default() },
+            BackendConfig::default(),
+        );
+        tokio::spawn({
+            let shard = shard.clone();
+            async move {
+                while let Some(mut request) = requests.recv().await {
+                    let tensor = std::mem::take(&mut request.tensor_data);
+                    let result = backend.forward_layers(request.layer_start, tensor).await;
+                    request.respond(&shard, result).await.unwrap();
+                }
+            }
+        });
+
+        // w1 runs shard 0, two layers, and the task
+        let mut mock = MockBackend::with_config(
+            MockConfig { shard_layers: 2, token_latency_ms: 0, ..Default::default() },
+            BackendConfig::default(),
+        );
+        mock.load_model_from_path(std::path::Path::new("/models/llama-shard.gguf"))
+            .await
+            .unwrap();
+        let registry = BackendRegistry::new();
+        registry.register_boxed(BackendType::Mock, Box::new(mock));
+        let (executor, mut results) = TaskExecutor::new(
+            ExecutorConfig::default(),
+            Arc::new(RwLock::new(registry)),
+            "w1".to_string(),
+        );
+        let groups = Arc::new(GroupManager::new("w1".to_string()));
+        let group_id = groups.create_group(GroupPurpose::ModelShard {
+            model_id: "llama-shard".to_string(),
+            total_shards: 2,
+        });
+        groups.add_member(&group_id, "w2", GroupRole::Member);
+        groups.set_shard_index(&group_id, "w1", 0);
+        groups.set_shard_index(&group_id, "w2", 1);
+        executor.set_shard_route(Arc::new(MeshShardRoute::new(coordinator.clone(), groups)));
+
+        executor
+            .submit(TaskAssignmentMessage {
+                task_id: "t1".to_string(),
+                block_id: None,
+                day_id: None,
+                priority: crate::protocol::TaskPriority::Normal,
+                deadline: None,
+                model_id: "llama-shard".to_string(),
+                input: TaskInput::TextCompletion(TextCompletionInput {
+                    prompt: "hi".to_string(),
+                    system_prompt: None,
+                    params: GenerationParams::default(),
+                }),
+                is_canary: false,
+                expected_hash: None,
+                timeout_secs: 10,
+                group_id: Some(group_id),
+                stream: false,
+                required_tags: vec![],
+            })
+            .await
+            .unwrap();
+
+        let result = results.recv().await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+        // "hi" is [104, 105]; +2 on w1 and +4 on w2 give [110, 111], which
+        // the mock decodes to these words
+        match result.output {
+            Some(TaskOutput::TextCompletion(output)) => assert_eq!(output.text, "need consider"),
+            other => panic!("unexpected output: {:?}", other),
+        }
+        assert!(coordinator.shard_waiters.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_silent_shard_times_out() {
+        let (coordinator, shard) = shard_pair(Duration::from_millis(200)).await;
+        // Inputs are accepted but never answered
+        let _requests = shard.serve_shards();
+
+        let err = coordinator
+            .forward_shard("w2", "g1", "t1", 0, vec![0; 16])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ShardTimeout { ref peer_id, .. } if peer_id == "w2"), "{}", err);
+        assert_eq!(err.code(), crate::error::ErrorCode::ExecutionShardTimeout);
+        assert!(coordinator.shard_waiters.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_full_shard_queue_refuses_without_blocking() {
+        let (coordinator, shard) = shard_pair(Duration::from_secs(30)).await;
+        // Inputs are queued but never taken
+        let _requests = shard.serve_shards();
+
+        let mut pending = tokio::task::JoinSet::new();
+        for i in 0..=SHARD_QUEUE_DEPTH {
+            let coordinator = coordinator.clone();
+            pending.spawn(async move {
+                coordinator
+                    .forward_shard("w2", "g1", &format!("t{}", i), 0, vec![0; 16])
+                    .await
+            });
+        }
+        // The input past the queue is answered right away
+        let refused = tokio::time::timeout(Duration::from_secs(5), pending.join_next())
+            .await
+            .expect("overflowing input was not refused")
+            .unwrap()
+            .unwrap()
+            .unwrap_err();
+        assert!(refused.to_string().contains("shard queue full"), "{}", refused);
+        assert_eq!(coordinator.connected_peers(), vec!["w2".to_string()]);
+
+        // Outputs are matched by task, so an input without one is refused
+        let err = coordinator
+            .forward_shard("w2", "g1", "", 0, vec![0; 16])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Protocol(_)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_shard_falls_back_to_local_run() {
+        use crate::backend::{BackendConfig, BackendRegistry, BackendType, InferenceBackend, MockBackend, MockConfig};
+        use crate::executor::{ExecutorConfig, TaskExecutor};
+        use crate::peer::{GroupPurpose, GroupRole};
+        use crate::protocol::TaskAssignmentMessage;
+        use crate::types::{GenerationParams, TaskInput, TaskOutput, TextCompletionInput};
+
+        let (coordinator, shard) = shard_pair(Duration::from_secs(5)).await;
+
+        // w2 runs shard 0 on a backend without split-layer support
+        let mut requests = shard.serve_shards();
+        let backend = MockBackend::with_config(
+            MockConfig { shard_layers: 0, token_latency_ms: 0, ..Default::default() },
+            BackendConfig::default(),
+        );
+        tokio::spawn({
+            let shard = shard.clone();
+            async move {
+                while let Some(mut request) = requests.recv().await {
+                    let tensor = std::mem::take(&mut request.tensor_data);
+                    let result = backend.forward_layers(request.layer_start, tensor).await;
+                    request.respond(&shard, result).await.unwrap();
+                }
+            }
+        });
+
+        let mut mock = MockBackend::with_config(
+            MockConfig { shard_layers: 2, token_latency_ms: 0, ..Default::default() },
+            BackendConfig::default(),
+        );
+        mock.load_model_from_path(std::path::Path::new("/models/llama-shard.gguf"))
+            .await
+            .unwrap();
+        let registry = BackendRegistry::new();
+        registry.register_boxed(BackendType::Mock, Box::new(mock));
+        let (executor, mut results) = TaskExecutor::new(
+            ExecutorConfig::default(),
+            Arc::new(RwLock::new(registry)),
+            "w1".to_string(),
+        );
+        let groups = Arc::new(GroupManager::new("w1".to_string()));
+        let group_id = groups.create_group(GroupPurpose::ModelShard {
+            model_id: "llama-shard".to_string(),
+            total_shards: 2,
+        });
+        groups.add_member(&group_id, "w2", GroupRole::Member);
+        groups.set_shard_index(&group_id, "w2", 0);
+        groups.set_shard_index(&group_id, "w1", 1);
+        executor.set_shard_route(Arc::new(MeshShardRoute::new(coordinator.clone(), groups)));
+
+        executor
+            .submit(TaskAssignmentMessage {
+                task_id: "t1".to_string(),
+                block_id: None,
+                day_id: None,
+                priority: crate::protocol::TaskPriority::Normal,
+                deadline: None,
+                model_id: "llama-shard".to_string(),
+                input: TaskInput::TextCompletion(TextCompletionInput {
+                    prompt: "hi".to_string(),
+                    system_prompt: None,
+                    params: GenerationParams { max_tokens: 8, ..Default::default() },
+                }),
+                is_canary: false,
+                expected_hash: None,
+                timeout_secs: 10,
+                group_id: Some(group_id),
+                stream: false,
+                required_tags: vec![],
+            })
+            .await
+            .unwrap();
+
+        // Run whole on w1 instead of failing
+        let result = results.recv().await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+        match result.output {
+            Some(TaskOutput::TextCompletion(output)) => assert!(!output.text.is_empty()),
+            other => panic!("unexpected output: {:?}", other),
+        }
+        assert!(coordinator.shard_waiters.lock().is_empty());
+    }
+
+    fn large_shard_input() -> PeerMessage {
+        PeerMessage::ShardInput {
+            group_id: "g1".to_string(),
+            task_id: "t1".to_string(),
+            layer_start: 0,
+            tensor_data: (0..64 * 1024).map(|i| (i % 7) as u8).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compression_negotiated_in_hello() {
+        for (enabled, expected) in [(true, Some(Compression::Gzip)), (false, None)] {
+            let (mesh, mut events) =
+                testing::mesh("w2", MeshConfig { compression: enabled, ..MeshConfig::default() });
+            let addr = mesh.start().await.unwrap();
+
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let hello = PeerMessage::Hello {
+                worker_id: "w1".to_string(),
+                capabilities: testing::capabilities(),
+                compression: vec![Compression::Gzip],
+            };
+            write_framed_message(&mut stream, &hello).await.unwrap();
+            match read_framed_message(&mut stream).await.unwrap() {
+                PeerMessage::HelloAck { compression, .. } => assert_eq!(compression, expected),
+                other => panic!("Expected HelloAck, got {}", other.type_name()),
+            }
+
+            // A compressed frame arrives as the message it holds
+            let json = large_shard_input().to_wire(expected).unwrap();
+            assert_eq!(json.len() < 16 * 1024, enabled);
+            write_frame(&mut stream, &json).await.unwrap();
+            let (from, message) = testing::next_message(&mut events).await;
+            assert_eq!(from, "w1");
+            assert_eq!(
+                serde_json::to_value(&message).unwrap(),
+                serde_json::to_value(large_shard_input()).unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_message_round_trip_with_and_without_compression() {
+        for (initiator_on, responder_on) in [(true, true), (true, false), (false, true), (false, false)] {
+            let (initiator, _) =
+                testing::mesh("w1", MeshConfig { compression: initiator_on, ..MeshConfig::default() });
+            let (responder, mut events) =
+                testing::mesh("w2", MeshConfig { compression: responder_on, ..MeshConfig::default() });
+            let addr = responder.start().await.unwrap();
+            initiator.connect(&testing::peer("w2", addr.port())).await.unwrap();
+
+            initiator.send("w2", large_shard_input()).await.unwrap();
+            let (from, message) = testing::next_message(&mut events).await;
+            assert_eq!(from, "w1");
+            assert_eq!(
+                serde_json::to_value(&message).unwrap(),
+                serde_json::to_value(large_shard_input()).unwrap()
+            );
+        }
+    }
+
+    /// A mesh requiring encryption with `account`'s key, and its events
+    fn encrypted_mesh(worker_id: &str, account: &str) -> (Arc<PeerMesh>, mpsc::Receiver<PeerEvent>) {
+        let credentials = crate::coordinator::PeerCredentials {
+            account_id: account.to_string(),
+            secret_key: hex::encode(vec![7u8; pqcrypto_dilithium::dilithium3::secret_key_bytes()]),
+        };
+        testing::mesh(
+            worker_id,
+            MeshConfig {
+                mesh_key: MeshKey::from_credentials(&credentials),
+                require_encryption: true,
+                ..MeshConfig::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_connection() {
+        let (initiator, _) = encrypted_mesh("w1", "acct");
+        let (responder, mut events) = encrypted_mesh("w2", "acct");
+        let addr = responder.start().await.unwrap();
+        initiator.connect(&testing::peer("w2", addr.port())).await.unwrap();
+
+        let input = PeerMessage::ShardInput {
+            group_id: "g1".to_string(),
+            task_id: "t1".to_string(),
+            layer_start: 0,
+            tensor_data: vec![1, 2, 3],
+        };
+        initiator.send("w2", input).await.unwrap();
+        let received = testing::next_message(&mut events).await;
+        assert_eq!(received.0, "w1");
+        assert!(matches!(received.1, PeerMessage::ShardInput { tensor_data, .. } if tensor_data == [1, 2, 3]));
+    }
+
+    #[tokio::test]
+    async fn test_unencrypted_peer_rejected() {
+        let (responder, _) = encrypted_mesh("w2", "acct");
+        let addr = responder.start().await.unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", addr.port())).await.unwrap();
+        let hello = PeerMessage::Hello {
+            worker_id: "w1".to_string(),
+            capabilities: testing::capabilities(),
+            compression: vec![],
+        };
+        write_framed_message(&mut stream, &hello).await.unwrap();
+        assert!(read_framed_message(&mut stream).await.is_err());
+        assert!(responder.connected_peers().is_empty());
+
+        // Without a key of its own, a peer can't connect either
+        let plain = testing::mesh(
+            "w3",
+            MeshConfig {
+                require_encryption: true,
+                ..MeshConfig::default()
+            },
+        ).0;
+        let err = plain.connect(&testing::peer("w2", addr.port())).await.unwrap_err();
+        assert!(err.to_string().contains("no account key"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_other_account_rejected() {
+        let (initiator, _) = encrypted_mesh("w1", "acct");
+        let (responder, _) = encrypted_mesh("w2", "other");
+        let addr = responder.start().await.unwrap();
+
+        assert!(initiator.connect(&testing::peer("w2", addr.port())).await.is_err());
+        assert!(initiator.connected_peers().is_empty());
+        assert!(responder.connected_peers().is_empty());
+    }
+
     #[tokio::test]
     async fn test_framed_message_roundtrip() {
         let msg = PeerMessage::Ping { seq: 42 };
@@ -531,7 +1923,9 @@ mod tests {
                 gpu_memory_mb: None,
                 max_context_length: 4096,
                 worker_version: "0.1.0".to_string(),
+                supports_streaming: false,
             },
+            compression: vec![],
         };
 
         let json = serde_json::to_string(&msg).unwrap();
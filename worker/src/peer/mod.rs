@@ -4,14 +4,23 @@
 //! - Model sharding (splitting large models across machines)
 //! - Task collaboration (pipeline processing between workers)
 //! - Work coordination (health gossip, load awareness)
+//! - Work redistribution (offloading queued tasks to idle peers)
 //!
 //! Workers discover each other through the coordinator, then
 //! establish direct TCP connections for low-latency data transfer.
 
 pub mod groups;
 pub mod mesh;
+pub mod noise;
+pub mod offload;
+pub mod pipeline;
 pub mod registry;
+#[cfg(test)]
+pub(crate) mod testing;
 
 pub use groups::*;
 pub use mesh::*;
+pub use noise::MeshKey;
+pub use offload::*;
+pub use pipeline::*;
 pub use registry::*;
@@ -0,0 +1,352 @@
+//! Encrypted peer transport
+//!
+//! Peer connections can be encrypted with a Noise `NNpsk0` handshake
+//! (X25519, ChaCha20-Poly1305, SHA-256, via the `snow` crate) run right
+//! after TCP connect and before `Hello`. The pre-shared key is derived from
+//! the account's ML-DSA-65 secret key, the same one used to register with
+//! the coordinator, so only workers under the same account complete the
+//! handshake: a peer with a different key fails to decrypt the first
+//! handshake message.
+//!
+//! Handshake wire format:
+//!
+//! ```text
+//! initiator -> [4-byte HANDSHAKE_MAGIC][4-byte length][e | sealed payload]
+//! responder -> [4-byte length][e | sealed payload]
+//! ```
+//!
+//! The magic can never be a plaintext frame length (it exceeds the largest
+//! message), so a listener tells encrypted and plain peers apart from the
+//! first four bytes. Afterwards every frame carries one message, sealed as
+//! consecutive Noise transport messages of at most 65535 bytes each, since
+//! frames can be far larger than one Noise message.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _};
+use pqcrypto_dilithium::dilithium3;
+use pqcrypto_traits::sign::SecretKey as PqSecretKey;
+use ring::digest;
+use snow::{HandshakeState, StatelessTransportState};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::coordinator::PeerCredentials;
+
+/// First four bytes sent by a peer starting the encrypted handshake
+pub const HANDSHAKE_MAGIC: u32 = u32::from_be_bytes(*b"AI4N");
+
+/// Authentication tag added to every Noise message
+const TAG_LEN: usize = 16;
+
+const PROTOCOL_NAME: &str = "Noise_NNpsk0_25519_ChaChaPoly_SHA256";
+const PROLOGUE: &[u8] = b"AI4ALL peer mesh v1";
+const DH_LEN: usize = 32;
+
+/// Handshake messages are an ephemeral key and an empty sealed payload
+const HANDSHAKE_MESSAGE_LEN: usize = DH_LEN + TAG_LEN;
+
+/// Largest Noise message
+const MAX_NOISE_MESSAGE: usize = 65535;
+
+/// Plaintext carried by one full Noise message
+const MAX_CHUNK: usize = MAX_NOISE_MESSAGE - TAG_LEN;
+
+/// Key authenticating peers of one account
+#[derive(Clone, PartialEq, Eq)]
+pub struct MeshKey([u8; 32]);
+
+impl std::fmt::Debug for MeshKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MeshKey([REDACTED])")
+    }
+}
+
+impl MeshKey {
+    /// Derive the key from account credentials. `None` if the secret key
+    /// isn't a hex-encoded ML-DSA-65 key.
+    pub fn from_credentials(credentials: &PeerCredentials) -> Option<Self> {
+        let sk_bytes = hex::decode(&credentials.secret_key).ok()?;
+        let sk = dilithium3::SecretKey::from_bytes(&sk_bytes).ok()?;
+        let mut ctx = digest::Context::new(&digest::SHA256);
+        ctx.update(b"AI4ALL:peer-psk:v1:");
+        ctx.update(credentials.account_id.as_bytes());
+        ctx.update(b":");
+        ctx.update(sk.as_bytes());
+        let mut key = [0u8; 32];
+        key.copy_from_slice(ctx.finish().as_ref());
+        Some(Self(key))
+    }
+}
+
+/// Size of a `plaintext_len`-byte message once sealed
+pub fn sealed_len(plaintext_len: usize) -> usize {
+    plaintext_len + plaintext_len.div_ceil(MAX_CHUNK).max(1) * TAG_LEN
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Transport ciphers
+// ─────────────────────────────────────────────────────────────────
+
+/// Nonce for the next Noise message in one direction
+fn next_nonce(nonce: &mut u64) -> anyhow::Result<u64> {
+    if *nonce == u64::MAX {
+        return Err(anyhow!("Encrypted connection exhausted its nonces"));
+    }
+    *nonce += 1;
+    Ok(*nonce - 1)
+}
+
+/// Seals messages sent to the peer
+pub struct SendCipher {
+    transport: Arc<StatelessTransportState>,
+    nonce: u64,
+}
+
+impl SendCipher {
+    /// Encrypt one message, adding a tag per Noise message
+    pub fn seal(&mut self, plaintext: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let mut sealed = vec![0u8; sealed_len(plaintext.len())];
+        let mut written = 0;
+        // An empty message still takes one (tag-only) Noise message
+        for chunk in plaintext.chunks(MAX_CHUNK).chain(plaintext.is_empty().then_some(&[][..])) {
+            let nonce = next_nonce(&mut self.nonce)?;
+            written += self
+                .transport
+                .write_message(nonce, chunk, &mut sealed[written..])
+                .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+        }
+        Ok(sealed)
+    }
+}
+
+/// Opens messages received from the peer
+pub struct RecvCipher {
+    transport: Arc<StatelessTransportState>,
+    nonce: u64,
+}
+
+impl RecvCipher {
+    /// Decrypt one message, failing if it was tampered with, reordered or
+    /// sealed with another key
+    pub fn open(&mut self, ciphertext: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let mut plaintext = vec![0u8; ciphertext.len()];
+        let mut read = 0;
+        for chunk in ciphertext.chunks(MAX_NOISE_MESSAGE) {
+            let nonce = next_nonce(&mut self.nonce)?;
+            read += self
+                .transport
+                .read_message(nonce, chunk, &mut plaintext[read..])
+                .map_err(|_| anyhow!("Message failed authentication"))?;
+        }
+        plaintext.truncate(read);
+        Ok(plaintext)
+    }
+}
+
+/// Ciphers for both directions once the handshake is done
+pub struct Session {
+    pub send: SendCipher,
+    pub recv: RecvCipher,
+}
+
+impl Session {
+    fn new(handshake: HandshakeState) -> anyhow::Result<Self> {
+        let transport = Arc::new(handshake.into_stateless_transport_mode()?);
+        Ok(Self {
+            send: SendCipher { transport: transport.clone(), nonce: 0 },
+            recv: RecvCipher { transport, nonce: 0 },
+        })
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Handshake
+// ─────────────────────────────────────────────────────────────────
+
+fn builder(key: &MeshKey) -> snow::Builder<'_> {
+    let params = PROTOCOL_NAME.parse().expect("supported Noise protocol");
+    snow::Builder::new(params).prologue(PROLOGUE).psk(0, &key.0)
+}
+
+async fn write_handshake_message<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    handshake: &mut HandshakeState,
+) -> anyhow::Result<()> {
+    let mut message = [0u8; HANDSHAKE_MESSAGE_LEN];
+    let len = handshake.write_message(&[], &mut message)?;
+    stream.write_u32(len as u32).await?;
+    stream.write_all(&message[..len]).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+async fn read_handshake_message<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    handshake: &mut HandshakeState,
+) -> anyhow::Result<()> {
+    let len = stream.read_u32().await? as usize;
+    if len != HANDSHAKE_MESSAGE_LEN {
+        return Err(anyhow!("Malformed handshake message ({} bytes)", len));
+    }
+    let mut message = [0u8; HANDSHAKE_MESSAGE_LEN];
+    stream.read_exact(&mut message).await?;
+    handshake
+        .read_message(&message, &mut [])
+        .map_err(|_| anyhow!("Peer is not under the same account (handshake authentication failed)"))?;
+    Ok(())
+}
+
+/// Run the handshake as the connecting side
+pub async fn initiate<S>(stream: &mut S, key: &MeshKey) -> anyhow::Result<Session>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut handshake = builder(key).build_initiator()?;
+
+    // -> psk, e
+    stream.write_u32(HANDSHAKE_MAGIC).await?;
+    write_handshake_message(stream, &mut handshake).await?;
+
+    // <- e, ee
+    read_handshake_message(stream, &mut handshake)
+        .await
+        .context("Peer refused the encrypted handshake")?;
+
+    Session::new(handshake)
+}
+
+/// Run the handshake as the accepting side, after [`HANDSHAKE_MAGIC`] has
+/// been read
+pub async fn respond<S>(stream: &mut S, key: &MeshKey) -> anyhow::Result<Session>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut handshake = builder(key).build_responder()?;
+
+    // -> psk, e
+    read_handshake_message(stream, &mut handshake).await?;
+
+    // <- e, ee
+    write_handshake_message(stream, &mut handshake).await?;
+
+    Session::new(handshake)
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(account_id: &str) -> MeshKey {
+        MeshKey::from_credentials(&PeerCredentials {
+            account_id: account_id.to_string(),
+            secret_key: hex::encode(vec![7u8; dilithium3::secret_key_bytes()]),
+        })
+        .unwrap()
+    }
+
+    /// Handshake over an in-memory pipe, returning both sides' results
+    async fn handshake(
+        initiator_key: MeshKey,
+        responder_key: MeshKey,
+    ) -> (anyhow::Result<Session>, anyhow::Result<Session>) {
+        let (mut a, mut b) = tokio::io::duplex(1024);
+        let initiator = tokio::spawn(async move { initiate(&mut a, &initiator_key).await });
+        let responder = async {
+            assert_eq!(b.read_u32().await.unwrap(), HANDSHAKE_MAGIC);
+            let result = respond(&mut b, &responder_key).await;
+            // Unblocks an initiator still waiting for a reply
+            drop(b);
+            result
+        };
+        let responder = responder.await;
+        (initiator.await.unwrap(), responder)
+    }
+
+    #[tokio::test]
+    async fn test_same_account_handshake() {
+        let (initiator, responder) = handshake(key("acct"), key("acct")).await;
+        let (mut initiator, mut responder) = (initiator.unwrap(), responder.unwrap());
+
+        let large = vec![3u8; 2 * MAX_CHUNK + 5];
+        for message in [&b"hello"[..], b"", b"world", &large] {
+            let sealed = initiator.send.seal(message.to_vec()).unwrap();
+            assert_eq!(sealed.len(), sealed_len(message.len()));
+            assert_eq!(responder.recv.open(sealed).unwrap(), message);
+
+            let sealed = responder.send.seal(message.to_vec()).unwrap();
+            assert_eq!(initiator.recv.open(sealed).unwrap(), message);
+        }
+        assert_eq!(sealed_len(5), 5 + TAG_LEN);
+        assert_eq!(sealed_len(large.len()), large.len() + 3 * TAG_LEN);
+    }
+
+    #[tokio::test]
+    async fn test_other_account_rejected() {
+        let (initiator, responder) = handshake(key("acct"), key("other")).await;
+        let err = responder.err().unwrap();
+        assert!(err.to_string().contains("same account"), "{}", err);
+        assert!(initiator.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_message_rejected() {
+        let (initiator, responder) = handshake(key("acct"), key("acct")).await;
+        let (mut initiator, mut responder) = (initiator.unwrap(), responder.unwrap());
+        let mut sealed = initiator.send.seal(b"tensor".to_vec()).unwrap();
+        sealed[0] ^= 1;
+        assert!(responder.recv.open(sealed).is_err());
+
+        // Messages must arrive in the order they were sealed
+        let (initiator, responder) = handshake(key("acct"), key("acct")).await;
+        let (mut initiator, mut responder) = (initiator.unwrap(), responder.unwrap());
+        let _first = initiator.send.seal(b"first".to_vec()).unwrap();
+        let second = initiator.send.seal(b"second".to_vec()).unwrap();
+        assert!(responder.recv.open(second).is_err());
+    }
+
+    /// A plain Noise peer, with only the framing in common, interoperates
+    #[tokio::test]
+    async fn test_interop_with_plain_noise_peer() {
+        let psk = key("acct");
+        let (mut a, mut b) = tokio::io::duplex(1024);
+        let initiator = tokio::spawn(async move { initiate(&mut a, &psk).await.map(|s| (a, s)) });
+
+        let mut noise = snow::Builder::new("Noise_NNpsk0_25519_ChaChaPoly_SHA256".parse().unwrap())
+            .prologue(b"AI4ALL peer mesh v1")
+            .psk(0, &key("acct").0)
+            .build_responder()
+            .unwrap();
+        let mut buf = vec![0u8; MAX_NOISE_MESSAGE];
+        assert_eq!(b.read_u32().await.unwrap(), HANDSHAKE_MAGIC);
+        let len = b.read_u32().await.unwrap() as usize;
+        let mut message = vec![0u8; len];
+        b.read_exact(&mut message).await.unwrap();
+        noise.read_message(&message, &mut buf).unwrap();
+        let len = noise.write_message(&[], &mut buf).unwrap();
+        b.write_u32(len as u32).await.unwrap();
+        b.write_all(&buf[..len]).await.unwrap();
+        let mut noise = noise.into_transport_mode().unwrap();
+
+        let (_a, mut session) = initiator.await.unwrap().unwrap();
+        let sealed = session.send.seal(b"hello".to_vec()).unwrap();
+        let len = noise.read_message(&sealed, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"hello");
+
+        let len = noise.write_message(b"world", &mut buf).unwrap();
+        assert_eq!(session.recv.open(buf[..len].to_vec()).unwrap(), b"world");
+    }
+
+    #[test]
+    fn test_mesh_key_redacted() {
+        assert_eq!(format!("{:?}", key("acct")), "MeshKey([REDACTED])");
+        assert!(MeshKey::from_credentials(&PeerCredentials {
+            account_id: "acct".to_string(),
+            secret_key: "not hex".to_string(),
+        })
+        .is_none());
+    }
+}
@@ -0,0 +1,540 @@
+//! Task offloading between peers
+//!
+//! A worker whose queue is saturated offers new tasks (`TaskOffer`) to the
+//! peer that last reported the most free capacity in its `PeerStatus`. On
+//! `TaskAccept` the assignment follows as `TaskData`; the peer runs it and
+//! answers with `TaskResultForward`, which goes to the coordinator as this
+//! worker's own result. A `TaskReject` (to the offer, or for a run that
+//! failed) brings the task back to run locally, as does an offer left
+//! unanswered, a forwarded task that overruns its timeout, or a peer that
+//! disconnects.
+//!
+//! Each task is offered at most once, and tasks run for a peer are never
+//! offered on, so a task can't bounce between busy workers.
+//!
+//! On the accepting side, a `TaskAccept` holds a slot until the `TaskData`
+//! arrives. That slot is given back when the data doesn't come within the
+//! offer timeout, when the offerer answers with `TaskReject` (its offer had
+//! already expired), or when the offerer disconnects.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::protocol::{PeerMessage, TaskAssignmentMessage, TaskMetrics, TaskResultMessage};
+use crate::types::TaskOutput;
+
+/// How long a peer has to answer an offer before the task runs locally
+pub const OFFER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A task handed (or being handed) to a peer
+struct Outgoing {
+    peer_id: String,
+    assignment: TaskAssignmentMessage,
+    since: Instant,
+}
+
+/// A task accepted from a peer
+struct Incoming {
+    peer_id: String,
+    since: Instant,
+    /// Its `TaskData` arrived and it was handed to the executor
+    running: bool,
+}
+
+/// What a disconnected peer leaves behind
+#[derive(Debug, Default)]
+pub struct PeerLoss {
+    /// Tasks offered or forwarded to the peer, to run locally
+    pub reclaimed: Vec<TaskAssignmentMessage>,
+    /// Tasks running here for the peer, which nobody wants any more
+    pub orphaned: Vec<String>,
+}
+
+/// Tracks tasks offered to peers and tasks accepted from them
+pub struct Offloader {
+    worker_id: String,
+    /// Offers waiting for an answer
+    offered: HashMap<String, Outgoing>,
+    /// Tasks a peer accepted and is running for us
+    forwarded: HashMap<String, Outgoing>,
+    /// Every task offered until it finishes; never offered twice
+    ever_offered: HashSet<String>,
+    /// Tasks accepted from peers
+    incoming: HashMap<String, Incoming>,
+    /// Tasks run for a peer that disconnected; their results are dropped
+    orphaned: HashSet<String>,
+}
+
+impl Offloader {
+    pub fn new(worker_id: impl Into<String>) -> Self {
+        Self {
+            worker_id: worker_id.into(),
+            offered: HashMap::new(),
+            forwarded: HashMap::new(),
+            ever_offered: HashSet::new(),
+            incoming: HashMap::new(),
+            orphaned: HashSet::new(),
+        }
+    }
+
+    // ─── Offering side ──────────────────────────────────────────
+
+    /// Whether `assignment` may go to a peer: it hasn't been offered before
+    /// and isn't a canary or work group task, which must run here
+    pub fn can_offer(&self, assignment: &TaskAssignmentMessage) -> bool {
+        !assignment.is_canary
+            && assignment.group_id.is_none()
+            && !self.ever_offered.contains(&assignment.task_id)
+            && !self.incoming.contains_key(&assignment.task_id)
+    }
+
+    /// Record an offer of `assignment` to `peer_id`, returning the message to send
+    pub fn offer(&mut self, peer_id: &str, assignment: TaskAssignmentMessage) -> PeerMessage {
+        let msg = PeerMessage::TaskOffer {
+            task_id: assignment.task_id.clone(),
+            task_type: assignment.input.task_type(),
+            priority: assignment.priority as u32,
+        };
+        self.ever_offered.insert(assignment.task_id.clone());
+        self.offered.insert(
+            assignment.task_id.clone(),
+            Outgoing { peer_id: peer_id.to_string(), assignment, since: Instant::now() },
+        );
+        msg
+    }
+
+    /// Take back an offer that couldn't be sent
+    pub fn withdraw(&mut self, task_id: &str) -> Option<TaskAssignmentMessage> {
+        self.offered.remove(task_id).map(|o| o.assignment)
+    }
+
+    /// `peer_id` accepted an offer: `Ok` with the `TaskData` carrying the
+    /// assignment, or `Err` with a `TaskReject` when there is no such offer
+    /// to that peer (it expired and the task runs here), so the peer frees
+    /// the slot it held for it
+    pub fn accepted(&mut self, peer_id: &str, task_id: &str) -> Result<PeerMessage, PeerMessage> {
+        let reject = || PeerMessage::TaskReject {
+            task_id: task_id.to_string(),
+            reason: "offer expired".to_string(),
+        };
+        let Some(outgoing) = self.offered.get(task_id).filter(|o| o.peer_id == peer_id) else {
+            return Err(reject());
+        };
+        let data = serde_json::to_vec(&outgoing.assignment).map_err(|_| reject())?;
+        let mut outgoing = self.offered.remove(task_id).expect("offer just looked up");
+        outgoing.since = Instant::now();
+        self.forwarded.insert(task_id.to_string(), outgoing);
+        Ok(PeerMessage::TaskData { task_id: task_id.to_string(), data })
+    }
+
+    /// `peer_id` turned down an offer, or couldn't run a task it accepted:
+    /// the assignment, to run locally. A rejection of our own acceptance
+    /// (the offer expired before it arrived) frees the slot held for it.
+    pub fn rejected(&mut self, peer_id: &str, task_id: &str) -> Option<TaskAssignmentMessage> {
+        for pending in [&mut self.offered, &mut self.forwarded] {
+            if pending.get(task_id).is_some_and(|o| o.peer_id == peer_id) {
+                return pending.remove(task_id).map(|o| o.assignment);
+            }
+        }
+        if self.incoming.get(task_id).is_some_and(|i| i.peer_id == peer_id && !i.running) {
+            self.incoming.remove(task_id);
+        }
+        None
+    }
+
+    /// A peer's output for a task it ran for us, as this worker's result
+    pub fn completed(&mut self, peer_id: &str, task_id: &str, output: TaskOutput) -> Option<TaskResultMessage> {
+        if self.forwarded.get(task_id)?.peer_id != peer_id {
+            return None;
+        }
+        let outgoing = self.forwarded.remove(task_id)?;
+        self.ever_offered.remove(task_id);
+        let elapsed_ms = outgoing.since.elapsed().as_millis() as u64;
+        Some(TaskResultMessage {
+            task_id: task_id.to_string(),
+            worker_id: self.worker_id.clone(),
+            success: true,
+            output: Some(output),
+            error: None,
+            metrics: TaskMetrics {
+                execution_time_ms: elapsed_ms,
+                total_time_ms: elapsed_ms,
+                ..TaskMetrics::default()
+            },
+            result_id: None,
+        })
+    }
+
+    /// Forget everything exchanged with a peer that went away: tasks
+    /// offered or forwarded to it come back to run locally, and tasks
+    /// accepted from it are dropped (those already running are orphaned,
+    /// for the caller to cancel)
+    pub fn peer_lost(&mut self, peer_id: &str) -> PeerLoss {
+        let mut loss = PeerLoss::default();
+        for pending in [&mut self.offered, &mut self.forwarded] {
+            let lost: Vec<String> = pending
+                .iter()
+                .filter(|(_, o)| o.peer_id == peer_id)
+                .map(|(id, _)| id.clone())
+                .collect();
+            loss.reclaimed.extend(lost.iter().filter_map(|id| pending.remove(id)).map(|o| o.assignment));
+        }
+        let orphaned = &mut self.orphaned;
+        self.incoming.retain(|task_id, incoming| {
+            if incoming.peer_id != peer_id {
+                return true;
+            }
+            if incoming.running {
+                orphaned.insert(task_id.clone());
+                loss.orphaned.push(task_id.clone());
+            }
+            false
+        });
+        loss
+    }
+
+    /// Offers unanswered for `offer_timeout`, and forwarded tasks past their
+    /// own timeout, to run locally. Tasks accepted from peers whose data
+    /// hasn't arrived within `offer_timeout` are dropped.
+    pub fn expired(&mut self, offer_timeout: Duration) -> Vec<TaskAssignmentMessage> {
+        let now = Instant::now();
+        self.incoming
+            .retain(|_, i| i.running || now.duration_since(i.since) < offer_timeout);
+        let mut reclaimed = Vec::new();
+        self.offered.retain(|_, o| {
+            let keep = now.duration_since(o.since) < offer_timeout;
+            if !keep {
+                reclaimed.push(o.assignment.clone());
+            }
+            keep
+        });
+        self.forwarded.retain(|_, o| {
+            let limit = Duration::from_secs(o.assignment.timeout_secs as u64) + offer_timeout;
+            let keep = now.duration_since(o.since) < limit;
+            if !keep {
+                reclaimed.push(o.assignment.clone());
+            }
+            keep
+        });
+        reclaimed
+    }
+
+    /// Forget a task the coordinator cancelled; a peer's late result for it
+    /// is dropped
+    pub fn cancel(&mut self, task_id: &str) {
+        self.offered.remove(task_id);
+        self.forwarded.remove(task_id);
+        self.ever_offered.remove(task_id);
+    }
+
+    // ─── Accepting side ─────────────────────────────────────────
+
+    /// Answer an offer from `peer_id`, accepting it if this worker `has_room`
+    /// and the task isn't one it offered, already accepted, or already runs
+    /// (`running_here`, as the executor tracks it)
+    pub fn answer_offer(
+        &mut self,
+        peer_id: &str,
+        task_id: &str,
+        has_room: bool,
+        running_here: bool,
+    ) -> PeerMessage {
+        let reason = if running_here
+            || self.ever_offered.contains(task_id)
+            || self.incoming.contains_key(task_id)
+        {
+            Some("task already offered")
+        } else if !has_room {
+            Some("no spare capacity")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => PeerMessage::TaskReject {
+                task_id: task_id.to_string(),
+                reason: reason.to_string(),
+            },
+            None => {
+                self.incoming.insert(
+                    task_id.to_string(),
+                    Incoming { peer_id: peer_id.to_string(), since: Instant::now(), running: false },
+                );
+                PeerMessage::TaskAccept { task_id: task_id.to_string() }
+            }
+        }
+    }
+
+    /// Tasks accepted from peers whose data hasn't arrived yet. Once handed
+    /// to the executor they count among its own tasks.
+    pub fn incoming_count(&self) -> usize {
+        self.incoming.values().filter(|i| !i.running).count()
+    }
+
+    /// The assignment sent in `TaskData` for a task accepted from `peer_id`;
+    /// `None` if no such task was accepted. Progress can't be relayed to the
+    /// coordinator from here, so the task doesn't stream.
+    pub fn task_data(
+        &mut self,
+        peer_id: &str,
+        task_id: &str,
+        data: &[u8],
+    ) -> Option<serde_json::Result<TaskAssignmentMessage>> {
+        let incoming = self.incoming.get_mut(task_id).filter(|i| i.peer_id == peer_id && !i.running)?;
+        let parsed = serde_json::from_slice(data).map(|mut assignment: TaskAssignmentMessage| {
+            assignment.task_id = task_id.to_string();
+            assignment.stream = false;
+            assignment
+        });
+        incoming.running = parsed.is_ok();
+        Some(parsed)
+    }
+
+    /// Whether `result` is for a task run for a peer that has since
+    /// disconnected, and should be dropped
+    pub fn take_orphaned(&mut self, result: &TaskResultMessage) -> bool {
+        self.orphaned.remove(&result.task_id)
+    }
+
+    /// If `result` is for a task run for a peer: that peer, and the message
+    /// taking the outcome back to it
+    pub fn finished(&mut self, result: &TaskResultMessage) -> Option<(String, PeerMessage)> {
+        self.ever_offered.remove(&result.task_id);
+        let peer_id = self.incoming.remove(&result.task_id)?.peer_id;
+        let msg = match (&result.output, result.success) {
+            (Some(output), true) => PeerMessage::TaskResultForward {
+                task_id: result.task_id.clone(),
+                output: output.clone(),
+            },
+            _ => PeerMessage::TaskReject {
+                task_id: result.task_id.clone(),
+                reason: result
+                    .error
+                    .as_ref()
+                    .map(|e| e.message.clone())
+                    .unwrap_or_else(|| "task failed".to_string()),
+            },
+        };
+        Some((peer_id, msg))
+    }
+
+    /// Give up a task accepted from a peer before it ran: that peer, and the
+    /// `TaskReject` to send it
+    pub fn abandon(&mut self, task_id: &str, reason: impl Into<String>) -> Option<(String, PeerMessage)> {
+        let peer_id = self.incoming.remove(task_id)?.peer_id;
+        Some((
+            peer_id,
+            PeerMessage::TaskReject { task_id: task_id.to_string(), reason: reason.into() },
+        ))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer::testing::{self, next_message};
+    use crate::peer::{MeshConfig, PeerInfo, PeerRegistry};
+    use crate::protocol::TaskPriority;
+    use crate::types::{DebugInput, DebugOp, DebugOutput, TaskInput, TaskType};
+
+    fn assignment(task_id: &str) -> TaskAssignmentMessage {
+        TaskAssignmentMessage {
+            task_id: task_id.to_string(),
+            block_id: None,
+            day_id: None,
+            priority: TaskPriority::High,
+            deadline: None,
+            model_id: "debug".to_string(),
+            input: TaskInput::Debug(DebugInput { op: DebugOp::Uppercase, text: "hello".to_string() }),
+            is_canary: false,
+            expected_hash: None,
+            timeout_secs: 30,
+            group_id: None,
+            stream: false,
+            required_tags: vec![],
+        }
+    }
+
+    #[test]
+    fn test_task_offered_once() {
+        let mut offloader = Offloader::new("w1");
+        assert!(offloader.can_offer(&assignment("t1")));
+        offloader.offer("w2", assignment("t1"));
+        assert!(!offloader.can_offer(&assignment("t1")));
+
+        // The peer turns it down: it runs here and isn't offered again
+        assert!(offloader.rejected("w3", "t1").is_none());
+        assert_eq!(offloader.rejected("w2", "t1").unwrap().task_id, "t1");
+        assert!(!offloader.can_offer(&assignment("t1")));
+
+        // Canary and group tasks always run here
+        let mut canary = assignment("t2");
+        canary.is_canary = true;
+        assert!(!offloader.can_offer(&canary));
+        let mut grouped = assignment("t3");
+        grouped.group_id = Some("g1".to_string());
+        assert!(!offloader.can_offer(&grouped));
+    }
+
+    #[test]
+    fn test_offer_loop_rejected() {
+        // A task this worker offered, or already holds, is never accepted back
+        let mut offloader = Offloader::new("w1");
+        offloader.offer("w2", assignment("t1"));
+        assert!(matches!(offloader.answer_offer("w2", "t1", true, false), PeerMessage::TaskReject { .. }));
+
+        assert!(matches!(offloader.answer_offer("w3", "t2", true, false), PeerMessage::TaskAccept { .. }));
+        assert!(matches!(offloader.answer_offer("w2", "t2", true, false), PeerMessage::TaskReject { .. }));
+        assert!(matches!(offloader.answer_offer("w2", "t3", false, false), PeerMessage::TaskReject { .. }));
+        // Nor is one the executor already runs
+        assert!(matches!(offloader.answer_offer("w2", "t4", true, true), PeerMessage::TaskReject { .. }));
+        assert_eq!(offloader.incoming_count(), 1);
+    }
+
+    #[test]
+    fn test_unanswered_work_reclaimed() {
+        let mut offloader = Offloader::new("w1");
+        offloader.offer("w2", assignment("t1"));
+        offloader.offer("w3", assignment("t2"));
+        offloader.accepted("w3", "t2").unwrap();
+
+        assert!(offloader.expired(OFFER_TIMEOUT).is_empty());
+        let reclaimed = offloader.expired(Duration::ZERO);
+        assert_eq!(reclaimed.len(), 1);
+        assert_eq!(reclaimed[0].task_id, "t1");
+
+        // Forwarded tasks come back when their peer goes away
+        let loss = offloader.peer_lost("w3");
+        assert_eq!(loss.reclaimed.len(), 1);
+        assert_eq!(loss.reclaimed[0].task_id, "t2");
+        assert!(offloader.completed("w3", "t2", TaskOutput::Debug(DebugOutput { text: "X".into() })).is_none());
+
+        // A late accept for the expired offer is turned down
+        assert!(matches!(offloader.accepted("w2", "t1"), Err(PeerMessage::TaskReject { .. })));
+        assert!(matches!(offloader.accepted("w3", "t9"), Err(PeerMessage::TaskReject { .. })));
+    }
+
+    #[test]
+    fn test_incoming_slots_released() {
+        let data = serde_json::to_vec(&assignment("t1")).unwrap();
+
+        // Task data that never arrives
+        let mut offloader = Offloader::new("w1");
+        offloader.answer_offer("w2", "t1", true, false);
+        assert_eq!(offloader.incoming_count(), 1);
+        offloader.expired(OFFER_TIMEOUT);
+        assert_eq!(offloader.incoming_count(), 1);
+        offloader.expired(Duration::ZERO);
+        assert_eq!(offloader.incoming_count(), 0);
+        assert!(offloader.task_data("w2", "t1", &data).is_none());
+
+        // The offerer turns down our late accept
+        offloader.answer_offer("w2", "t2", true, false);
+        assert!(offloader.rejected("w2", "t2").is_none());
+        assert_eq!(offloader.incoming_count(), 0);
+
+        // The offerer disconnects, before or after its data arrived
+        offloader.answer_offer("w2", "t3", true, false);
+        offloader.answer_offer("w2", "t4", true, false);
+        offloader.answer_offer("w3", "t5", true, false);
+        offloader.task_data("w2", "t4", &data).unwrap().unwrap();
+        assert_eq!(offloader.incoming_count(), 2);
+        let loss = offloader.peer_lost("w2");
+        assert!(loss.reclaimed.is_empty());
+        assert_eq!(loss.orphaned, vec!["t4".to_string()]);
+        assert_eq!(offloader.incoming_count(), 1);
+
+        // The orphaned task's result is dropped, not returned to anyone
+        let result = TaskResultMessage {
+            task_id: "t4".to_string(),
+            worker_id: "w1".to_string(),
+            success: false,
+            output: None,
+            error: None,
+            metrics: TaskMetrics::default(),
+            result_id: None,
+        };
+        assert!(offloader.take_orphaned(&result));
+        assert!(offloader.finished(&result).is_none());
+        assert!(!offloader.take_orphaned(&result));
+    }
+
+    #[tokio::test]
+    async fn test_busy_node_offloads_to_idle_peer() {
+        let (busy_mesh, mut busy_events) = testing::mesh("busy", MeshConfig::default());
+        let (idle_mesh, mut idle_events) = testing::mesh("idle", MeshConfig::default());
+        let addr = idle_mesh.start().await.unwrap();
+
+        // The idle peer advertised spare capacity
+        let registry = PeerRegistry::new();
+        registry.register(PeerInfo { capacity_pct: Some(0.0), ..testing::peer("idle", addr.port()) });
+        registry.update_capacity("idle", 0.75);
+        let target = registry.best_peer_for_offload(TaskType::Debug).unwrap();
+        busy_mesh.connect(&target).await.unwrap();
+
+        let mut busy = Offloader::new("busy");
+        let mut idle = Offloader::new("idle");
+
+        // Busy node offers the task
+        let offer = busy.offer(&target.worker_id, assignment("t1"));
+        busy_mesh.send("idle", offer).await.unwrap();
+
+        // Idle node accepts it
+        let (from, message) = next_message(&mut idle_events).await;
+        let PeerMessage::TaskOffer { task_id, task_type, priority } = message else {
+            panic!("Expected TASK_OFFER, got {}", message.type_name());
+        };
+        assert_eq!(task_type, TaskType::Debug);
+        assert_eq!(priority, TaskPriority::High as u32);
+        idle_mesh.send(&from, idle.answer_offer(&from, &task_id, true, false)).await.unwrap();
+
+        // Busy node forwards the input
+        let (from, message) = next_message(&mut busy_events).await;
+        let PeerMessage::TaskAccept { task_id } = message else {
+            panic!("Expected TASK_ACCEPT, got {}", message.type_name());
+        };
+        let data = busy.accepted(&from, &task_id).unwrap();
+        busy_mesh.send(&from, data).await.unwrap();
+
+        // Idle node runs it and sends the output back
+        let (from, message) = next_message(&mut idle_events).await;
+        let PeerMessage::TaskData { task_id, data } = message else {
+            panic!("Expected TASK_DATA, got {}", message.type_name());
+        };
+        let received = idle.task_data(&from, &task_id, &data).unwrap().unwrap();
+        let TaskInput::Debug(input) = &received.input else {
+            panic!("Expected debug input");
+        };
+        let result = TaskResultMessage {
+            task_id: received.task_id.clone(),
+            worker_id: "idle".to_string(),
+            success: true,
+            output: Some(TaskOutput::Debug(DebugOutput { text: input.text.to_uppercase() })),
+            error: None,
+            metrics: TaskMetrics::default(),
+            result_id: None,
+        };
+        let (origin, reply) = idle.finished(&result).unwrap();
+        assert_eq!(origin, "busy");
+        assert_eq!(idle.incoming_count(), 0);
+        idle_mesh.send(&origin, reply).await.unwrap();
+
+        // Busy node reports the peer's output as its own result
+        let (from, message) = next_message(&mut busy_events).await;
+        let PeerMessage::TaskResultForward { task_id, output } = message else {
+            panic!("Expected TASK_RESULT_FORWARD, got {}", message.type_name());
+        };
+        let result = busy.completed(&from, &task_id, output).unwrap();
+        assert_eq!(result.task_id, "t1");
+        assert_eq!(result.worker_id, "busy");
+        assert!(result.success);
+        match result.output {
+            Some(TaskOutput::Debug(output)) => assert_eq!(output.text, "HELLO"),
+            other => panic!("Unexpected output {:?}", other),
+        }
+        assert!(busy.expired(Duration::ZERO).is_empty());
+    }
+}
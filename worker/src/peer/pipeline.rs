@@ -0,0 +1,551 @@
+//! Streamed pipeline chunks between stages
+//!
+//! A pipeline stage can forward partial output to the next stage as it is
+//! produced instead of one `PipelineOutput` at the end. Chunks travel as
+//! `PipelineChunk` peer messages numbered by `seq`; the receiving side
+//! reassembles them in order. Streaming is only used toward peers that
+//! negotiated it in their `GroupJoin`.
+//!
+//! A stage streams the text its task generates; once the stream is
+//! complete, the next stage runs on it (see [`next_stage_assignment`]).
+//! Only the last stage reports the task's result to the coordinator.
+//!
+//! The first chunk carries the coordinator's assignment details
+//! ([`PipelineTask`]), so later stages run on the same model and
+//! parameters. A worker only takes streams from the member running the
+//! stage before its own, and streams are capped in size and dropped once
+//! they go idle.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::protocol::{PeerMessage, PipelineTask, TaskAssignmentMessage};
+use crate::types::{
+    default_summary_length, EmbeddingsInput, GenerationParams, SummarizationInput,
+    SummarizationStyle, TaskInput, TaskOutput, TaskType, TextCompletionInput,
+};
+
+use super::{GroupManager, PeerMesh};
+
+/// Most data one stream may carry (bytes)
+const MAX_STREAM_BYTES: usize = 16 * 1024 * 1024;
+
+/// Most chunks one stream may be split into
+const MAX_STREAM_CHUNKS: u64 = 65_536;
+
+// ─────────────────────────────────────────────────────────────────
+// Sending
+// ─────────────────────────────────────────────────────────────────
+
+/// Streams one task's stage output to the next stage's worker
+pub struct PipelineStreamWriter {
+    mesh: Arc<PeerMesh>,
+    peer_id: String,
+    group_id: String,
+    stage: u32,
+    task_id: String,
+    next_seq: u64,
+    finished: bool,
+    /// Everything sent so far
+    sent: Vec<u8>,
+    /// Sent with the first chunk
+    task: Option<PipelineTask>,
+}
+
+impl PipelineStreamWriter {
+    /// Open a stream of `stage`'s output for `task_id` to `peer_id`
+    pub fn new(
+        mesh: Arc<PeerMesh>,
+        peer_id: String,
+        group_id: String,
+        stage: u32,
+        task_id: String,
+        task: PipelineTask,
+    ) -> Self {
+        Self {
+            mesh,
+            peer_id,
+            group_id,
+            stage,
+            task_id,
+            next_seq: 0,
+            finished: false,
+            sent: Vec::new(),
+            task: Some(task),
+        }
+    }
+
+    /// Send the next chunk
+    pub async fn send(&mut self, data: Vec<u8>) -> anyhow::Result<()> {
+        self.send_chunk(data, false).await
+    }
+
+    /// Send the last chunk, closing the stream
+    pub async fn finish(&mut self, data: Vec<u8>) -> anyhow::Result<()> {
+        self.send_chunk(data, true).await
+    }
+
+    /// Close the stream with the stage's complete `output`, sending
+    /// whatever of it was not streamed yet. Output that no longer extends
+    /// what was streamed (post-processing rewrote it) adds nothing.
+    pub async fn finish_output(&mut self, output: &[u8]) -> anyhow::Result<()> {
+        let rest = output
+            .strip_prefix(self.sent.as_slice())
+            .map(<[u8]>::to_vec)
+            .unwrap_or_default();
+        self.finish(rest).await
+    }
+
+    async fn send_chunk(&mut self, data: Vec<u8>, is_final: bool) -> anyhow::Result<()> {
+        if self.finished {
+            return Err(anyhow::anyhow!("Pipeline stream for task {} already finished", self.task_id));
+        }
+        self.sent.extend_from_slice(&data);
+        let chunk = PeerMessage::PipelineChunk {
+            group_id: self.group_id.clone(),
+            stage: self.stage,
+            task_id: self.task_id.clone(),
+            seq: self.next_seq,
+            data,
+            is_final,
+            task: self.task.take(),
+        };
+        self.mesh.send(&self.peer_id, chunk).await?;
+        self.next_seq += 1;
+        self.finished = is_final;
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Receiving
+// ─────────────────────────────────────────────────────────────────
+
+/// Data released by a chunk, in stream order
+#[derive(Debug, Default)]
+pub struct StreamProgress {
+    /// Bytes now contiguous with what was released before
+    pub data: Vec<u8>,
+
+    /// Whether the stream's final chunk has been released
+    pub complete: bool,
+
+    /// The whole stream and the task it belongs to, once complete
+    pub payload: Option<(PipelineTask, Vec<u8>)>,
+}
+
+/// One task's partially received stream
+struct StreamState {
+    task: PipelineTask,
+    last_chunk: Instant,
+    next_seq: u64,
+    /// Data released so far
+    released: Vec<u8>,
+    /// Chunks that arrived ahead of `next_seq`
+    pending: BTreeMap<u64, (Vec<u8>, bool)>,
+    /// Bytes released or pending
+    bytes: usize,
+}
+
+/// Reorders streamed chunks per (group, task)
+pub struct PipelineReassembler {
+    streams: HashMap<(String, String), StreamState>,
+    max_bytes: usize,
+    max_chunks: u64,
+}
+
+impl Default for PipelineReassembler {
+    fn default() -> Self {
+        Self::with_limits(MAX_STREAM_BYTES, MAX_STREAM_CHUNKS)
+    }
+}
+
+impl PipelineReassembler {
+    /// Create an empty reassembler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a reassembler capping each stream at `max_bytes` of data in
+    /// at most `max_chunks` chunks
+    pub fn with_limits(max_bytes: usize, max_chunks: u64) -> Self {
+        Self {
+            streams: HashMap::new(),
+            max_bytes,
+            max_chunks,
+        }
+    }
+
+    /// Accept a chunk, returning whatever data is now in order. Duplicate
+    /// and already-released chunks are ignored.
+    ///
+    /// A stream opens with its first chunk, which carries its `task`;
+    /// chunks of streams that were never opened are rejected, and a stream
+    /// that outgrows the limits is dropped.
+    pub fn push(
+        &mut self,
+        group_id: &str,
+        task_id: &str,
+        seq: u64,
+        data: Vec<u8>,
+        is_final: bool,
+        task: Option<PipelineTask>,
+    ) -> Result<StreamProgress> {
+        let key = (group_id.to_string(), task_id.to_string());
+        if !self.streams.contains_key(&key) {
+            match task {
+                Some(task) if seq == 0 => {
+                    self.streams.insert(
+                        key.clone(),
+                        StreamState {
+                            task,
+                            last_chunk: Instant::now(),
+                            next_seq: 0,
+                            released: Vec::new(),
+                            pending: BTreeMap::new(),
+                            bytes: 0,
+                        },
+                    );
+                }
+                _ => {
+                    return Err(Error::Protocol(format!(
+                        "Chunk {} of unknown pipeline task {}",
+                        seq, task_id
+                    )))
+                }
+            }
+        }
+
+        let state = self.streams.get_mut(&key).expect("stream opened above");
+        state.last_chunk = Instant::now();
+        if seq >= state.next_seq && !state.pending.contains_key(&seq) {
+            if seq >= self.max_chunks || state.bytes + data.len() > self.max_bytes {
+                self.streams.remove(&key);
+                return Err(Error::Protocol(format!(
+                    "Pipeline stream for task {} exceeds {} bytes or {} chunks",
+                    task_id, self.max_bytes, self.max_chunks
+                )));
+            }
+            state.bytes += data.len();
+            state.pending.insert(seq, (data, is_final));
+        }
+
+        let mut progress = StreamProgress::default();
+        while let Some((data, is_final)) = state.pending.remove(&state.next_seq) {
+            progress.data.extend(data);
+            state.next_seq += 1;
+            if is_final {
+                progress.complete = true;
+                break;
+            }
+        }
+
+        state.released.extend_from_slice(&progress.data);
+        if progress.complete {
+            progress.payload = self
+                .streams
+                .remove(&key)
+                .map(|state| (state.task, state.released));
+        }
+        Ok(progress)
+    }
+
+    /// Drop streams that received nothing for `max_idle`, whose upstream
+    /// stage failed or went away, returning how many were dropped
+    pub fn expire(&mut self, max_idle: Duration) -> usize {
+        let before = self.streams.len();
+        self.streams.retain(|_, state| state.last_chunk.elapsed() <= max_idle);
+        before - self.streams.len()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Stage Hand-off
+// ─────────────────────────────────────────────────────────────────
+
+/// Open a stream of a pipeline stage task's output to the next stage, if
+/// the task is one and that stage takes streams. The task is marked
+/// streaming so its tokens reach the writer as they are generated.
+pub fn open_stage_stream(
+    mesh: &Arc<PeerMesh>,
+    groups: &GroupManager,
+    assignment: &mut TaskAssignmentMessage,
+) -> Option<PipelineStreamWriter> {
+    let group_id = assignment.group_id.clone()?;
+    let stage = groups.my_pipeline_stage(&group_id)?;
+    let next = groups.next_streaming_stage(&group_id, stage)?;
+    assignment.stream = true;
+    let task = PipelineTask {
+        model_id: assignment.model_id.clone(),
+        params: assignment.input.generation_params().cloned(),
+        priority: assignment.priority,
+        deadline: assignment.deadline,
+        timeout_secs: assignment.timeout_secs,
+    };
+    Some(PipelineStreamWriter::new(
+        mesh.clone(),
+        next,
+        group_id,
+        stage as u32,
+        assignment.task_id.clone(),
+        task,
+    ))
+}
+
+/// Whether `from` runs `stage` of the group and this worker the stage
+/// after it, so `from`'s stream is ours to take
+pub fn from_upstream_stage(groups: &GroupManager, group_id: &str, from: &str, stage: u32) -> bool {
+    let stage = stage as usize;
+    groups.pipeline_stage_member(group_id, stage).as_deref() == Some(from)
+        && groups.my_pipeline_stage(group_id) == Some(stage + 1)
+}
+
+/// This worker's stage of `task_id`, running on the previous stage's
+/// `text` with the original assignment's model and parameters. `None` if
+/// the stage can't be fed text.
+pub fn next_stage_assignment(
+    groups: &GroupManager,
+    group_id: &str,
+    task_id: &str,
+    task: PipelineTask,
+    text: String,
+) -> Option<TaskAssignmentMessage> {
+    let stage = groups.my_pipeline_stage(group_id)?;
+    let task_type = groups.pipeline_stage_type(group_id, stage)?;
+    let params = task.params.unwrap_or_default();
+    Some(TaskAssignmentMessage {
+        task_id: task_id.to_string(),
+        block_id: None,
+        day_id: None,
+        priority: task.priority,
+        deadline: task.deadline,
+        model_id: task.model_id,
+        input: stage_input(task_type, text, params)?,
+        is_canary: false,
+        expected_hash: None,
+        timeout_secs: task.timeout_secs,
+        group_id: Some(group_id.to_string()),
+        stream: false,
+        required_tags: vec![],
+    })
+}
+
+/// The text a stage's output hands to the next stage, if it has one
+pub fn stage_output_text(output: &TaskOutput) -> Option<&str> {
+    match output {
+        TaskOutput::TextCompletion(o) => Some(&o.text),
+        TaskOutput::Summarization(o) => Some(&o.summary),
+        TaskOutput::QuestionAnswering(o) => Some(&o.answer),
+        _ => None,
+    }
+}
+
+/// Input for a stage of `task_type` running on the previous stage's text.
+/// Stages that need more than one text (labels, a question) can't be fed
+/// this way.
+pub fn stage_input(task_type: TaskType, text: String, params: GenerationParams) -> Option<TaskInput> {
+    match task_type {
+        TaskType::TextCompletion => Some(TaskInput::TextCompletion(TextCompletionInput {
+            prompt: text,
+            system_prompt: None,
+            params,
+        })),
+        TaskType::Summarization => Some(TaskInput::Summarization(SummarizationInput {
+            text,
+            target_length: default_summary_length(),
+            style: SummarizationStyle::default(),
+            params,
+        })),
+        TaskType::Embeddings => Some(TaskInput::Embeddings(EmbeddingsInput {
+            texts: vec![text],
+            normalize: true,
+        })),
+        _ => None,
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer::testing;
+    use crate::peer::{GroupManager, GroupPurpose, GroupRole, MeshConfig, PeerEvent};
+    use crate::protocol::TaskPriority;
+
+    fn task() -> PipelineTask {
+        PipelineTask {
+            model_id: "model".to_string(),
+            params: None,
+            priority: TaskPriority::Normal,
+            deadline: None,
+            timeout_secs: 30,
+        }
+    }
+
+    #[test]
+    fn test_reassembly_reorders_chunks() {
+        let mut reassembler = PipelineReassembler::new();
+
+        let progress = reassembler.push("g", "t", 0, b"Hel".to_vec(), false, Some(task())).unwrap();
+        assert_eq!(progress.data, b"Hel");
+
+        let progress = reassembler.push("g", "t", 2, b"world".to_vec(), true, None).unwrap();
+        assert!(progress.data.is_empty());
+
+        let progress = reassembler.push("g", "t", 1, b"lo ".to_vec(), false, None).unwrap();
+        assert_eq!(progress.data, b"lo world");
+        assert!(progress.complete);
+        let (task, payload) = progress.payload.unwrap();
+        assert_eq!(task.model_id, "model");
+        assert_eq!(payload, b"Hello world");
+        assert!(reassembler.streams.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_and_oversized_streams_rejected() {
+        let mut reassembler = PipelineReassembler::with_limits(8, 3);
+
+        // Only a first chunk carrying its task opens a stream
+        assert!(reassembler.push("g", "t", 0, b"a".to_vec(), false, None).is_err());
+        assert!(reassembler.push("g", "t", 1, b"a".to_vec(), false, Some(task())).is_err());
+        assert!(reassembler.streams.is_empty());
+
+        reassembler.push("g", "t", 0, b"abc".to_vec(), false, Some(task())).unwrap();
+        // Duplicates don't count toward the limits
+        reassembler.push("g", "t", 0, b"abc".to_vec(), false, None).unwrap();
+        reassembler.push("g", "t", 1, b"defg".to_vec(), false, None).unwrap();
+        assert!(reassembler.push("g", "t", 2, b"hi".to_vec(), false, None).is_err());
+        assert!(reassembler.streams.is_empty());
+
+        reassembler.push("g", "u", 0, b"a".to_vec(), false, Some(task())).unwrap();
+        assert!(reassembler.push("g", "u", 3, b"b".to_vec(), false, None).is_err());
+        assert!(reassembler.streams.is_empty());
+    }
+
+    #[test]
+    fn test_idle_streams_expire() {
+        let mut reassembler = PipelineReassembler::new();
+        reassembler.push("g", "t", 0, b"partial".to_vec(), false, Some(task())).unwrap();
+
+        assert_eq!(reassembler.expire(Duration::from_secs(60)), 0);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(reassembler.expire(Duration::from_millis(10)), 1);
+        assert!(reassembler.streams.is_empty());
+    }
+
+    #[test]
+    fn test_stage_input_from_text() {
+        let params = GenerationParams {
+            max_tokens: 64,
+            ..GenerationParams::default()
+        };
+        match stage_input(TaskType::Summarization, "long text".to_string(), params) {
+            Some(TaskInput::Summarization(input)) => {
+                assert_eq!(input.text, "long text");
+                assert_eq!(input.target_length, default_summary_length());
+                assert_eq!(input.params.max_tokens, 64);
+            }
+            other => panic!("unexpected input: {:?}", other),
+        }
+        // A classifier needs labels as well as text
+        assert!(stage_input(TaskType::Classification, "text".to_string(), GenerationParams::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_stage0_to_stage1() {
+        let (stage0, _stage0_events) = testing::mesh("w1", MeshConfig::default());
+        let (stage1, mut stage1_events) = testing::mesh("w2", MeshConfig::default());
+        let addr = stage1.start().await.unwrap();
+        stage0.connect(&testing::peer("w2", addr.port())).await.unwrap();
+
+        // Stage 1 negotiated streaming when it joined the group
+        let groups = GroupManager::new("w1".to_string());
+        let gid = groups.create_group(GroupPurpose::TaskPipeline {
+            pipeline_id: "generate-summarize".to_string(),
+            stages: vec![TaskType::TextCompletion, TaskType::Summarization],
+        });
+        groups.add_member(&gid, "w2", GroupRole::Member);
+        groups.set_pipeline_stage(&gid, "w1", 0);
+        groups.set_pipeline_stage(&gid, "w2", 1);
+        groups.set_member_streaming(&gid, "w2", true);
+
+        let params = GenerationParams {
+            temperature: 0.2,
+            ..GenerationParams::default()
+        };
+        let mut assignment = TaskAssignmentMessage {
+            task_id: "t1".to_string(),
+            block_id: None,
+            day_id: None,
+            priority: TaskPriority::High,
+            deadline: None,
+            model_id: "model".to_string(),
+            input: stage_input(TaskType::TextCompletion, "Tell a story".to_string(), params).unwrap(),
+            is_canary: false,
+            expected_hash: None,
+            timeout_secs: 30,
+            group_id: Some(gid.clone()),
+            stream: false,
+            required_tags: vec![],
+        };
+        let mut writer = open_stage_stream(&stage0, &groups, &mut assignment).unwrap();
+        assert!(assignment.stream);
+
+        // Tokens stream as generated; the rest goes with the final output
+        for token in ["The ", "quick ", "brown "] {
+            writer.send(token.as_bytes().to_vec()).await.unwrap();
+        }
+        writer.finish_output(b"The quick brown fox").await.unwrap();
+        assert!(writer.send(b"late".to_vec()).await.is_err());
+
+        let next = GroupManager::new("w2".to_string());
+        next.add_group(groups.get_group(&gid).unwrap());
+        let mut reassembler = PipelineReassembler::new();
+        let mut seqs = Vec::new();
+        let (task, payload) = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let Some(PeerEvent::MessageReceived { from, message }) = stage1_events.recv().await else {
+                    continue;
+                };
+                if let PeerMessage::PipelineChunk { group_id, stage, task_id, seq, data, is_final, task } = message {
+                    assert!(from_upstream_stage(&next, &group_id, &from, stage));
+                    assert_eq!(task.is_some(), seq == 0);
+                    seqs.push(seq);
+                    let progress = reassembler.push(&group_id, &task_id, seq, data, is_final, task).unwrap();
+                    if let Some(payload) = progress.payload {
+                        return payload;
+                    }
+                }
+            }
+        })
+        .await
+        .expect("stream never completed");
+        assert_eq!(seqs, vec![0, 1, 2, 3]);
+
+        // Only stage 0 feeds stage 1
+        assert!(!from_upstream_stage(&next, &gid, "w3", 0));
+        assert!(!from_upstream_stage(&next, &gid, "w1", 1));
+
+        // Stage 1 summarizes what stage 0 generated, as the same task
+        let text = String::from_utf8(payload).unwrap();
+        let assignment = next_stage_assignment(&next, &gid, "t1", task, text).unwrap();
+        assert_eq!(assignment.model_id, "model");
+        assert_eq!(assignment.priority, TaskPriority::High);
+        assert_eq!(assignment.timeout_secs, 30);
+        match &assignment.input {
+            TaskInput::Summarization(input) => {
+                assert_eq!(input.text, "The quick brown fox");
+                assert_eq!(input.params.temperature, 0.2);
+            }
+            other => panic!("unexpected input: {:?}", other),
+        }
+        // The last stage streams nowhere
+        let mut assignment = assignment;
+        assert!(open_stage_stream(&stage1, &next, &mut assignment).is_none());
+    }
+}
@@ -8,8 +8,9 @@ use std::net::SocketAddr;
 use std::time::{Duration, Instant};
 
 use parking_lot::RwLock;
+use tracing::warn;
 
-use crate::protocol::{WorkerCapabilities, WorkerStatus};
+use crate::protocol::{PeerDirectoryEntry, WorkerCapabilities, WorkerStatus};
 use crate::types::TaskType;
 
 // ─────────────────────────────────────────────────────────────────
@@ -40,6 +41,12 @@ pub struct PeerInfo {
     /// Measured round-trip latency (ms)
     pub latency_ms: Option<u32>,
 
+    /// Connected but leaving pings unanswered; cleared by the next pong
+    pub stale: bool,
+
+    /// Share of its task slots the peer last reported free (`PeerStatus`)
+    pub capacity_pct: Option<f32>,
+
     /// Work groups this peer belongs to
     pub groups: Vec<String>,
 }
@@ -48,6 +55,16 @@ pub struct PeerInfo {
 // Peer Registry
 // ─────────────────────────────────────────────────────────────────
 
+/// Outcome of registering a coordinator peer directory
+#[derive(Debug, Default)]
+pub struct DirectoryImport {
+    /// Peers registered or refreshed
+    pub registered: usize,
+
+    /// Worker IDs of entries dropped for an unparseable `listen_addr`
+    pub malformed: Vec<String>,
+}
+
 /// Thread-safe registry of known peers
 pub struct PeerRegistry {
     peers: RwLock<HashMap<String, PeerInfo>>,
@@ -66,6 +83,42 @@ impl PeerRegistry {
         self.peers.write().insert(info.worker_id.clone(), info);
     }
 
+    /// Register the peers of a coordinator directory, skipping `self_id`.
+    /// Entries with an unparseable address are logged and reported back
+    /// instead of registered.
+    pub fn register_directory(&self, entries: &[PeerDirectoryEntry], self_id: &str) -> DirectoryImport {
+        let mut import = DirectoryImport::default();
+        for entry in entries.iter().filter(|e| e.worker_id != self_id) {
+            match entry.listen_addr.parse() {
+                Ok(listen_addr) => {
+                    self.register(PeerInfo {
+                        worker_id: entry.worker_id.clone(),
+                        name: entry.name.clone(),
+                        listen_addr,
+                        capabilities: entry.capabilities.clone(),
+                        status: WorkerStatus::Ready,
+                        last_seen: Instant::now(),
+                        latency_ms: None,
+                        stale: false,
+                        capacity_pct: None,
+                        groups: vec![],
+                    });
+                    import.registered += 1;
+                }
+                Err(e) => {
+                    warn!(
+                        peer = %entry.worker_id,
+                        listen_addr = %entry.listen_addr,
+                        error = %e,
+                        "Skipping peer directory entry with invalid address"
+                    );
+                    import.malformed.push(entry.worker_id.clone());
+                }
+            }
+        }
+        import
+    }
+
     /// Remove a peer by worker ID
     pub fn remove(&self, worker_id: &str) -> Option<PeerInfo> {
         self.peers.write().remove(worker_id)
@@ -114,14 +167,29 @@ impl PeerRegistry {
         }
     }
 
+    /// Record the free capacity a peer reported in its status
+    pub fn update_capacity(&self, worker_id: &str, capacity_pct: f32) {
+        if let Some(peer) = self.peers.write().get_mut(worker_id) {
+            peer.capacity_pct = Some(capacity_pct);
+        }
+    }
+
     /// Update a peer's measured latency
     pub fn update_latency(&self, worker_id: &str, latency_ms: u32) {
         if let Some(peer) = self.peers.write().get_mut(worker_id) {
             peer.latency_ms = Some(latency_ms);
+            peer.stale = false;
             peer.last_seen = Instant::now();
         }
     }
 
+    /// Mark a peer as no longer answering pings
+    pub fn mark_stale(&self, worker_id: &str) {
+        if let Some(peer) = self.peers.write().get_mut(worker_id) {
+            peer.stale = true;
+        }
+    }
+
     /// Touch a peer's last_seen timestamp
     pub fn touch(&self, worker_id: &str) {
         if let Some(peer) = self.peers.write().get_mut(worker_id) {
@@ -169,11 +237,33 @@ impl PeerRegistry {
             .values()
             .filter(|p| {
                 p.status == WorkerStatus::Ready
+                    && !p.stale
                     && p.capabilities.supported_tasks.contains(&task_type)
             })
             .min_by_key(|p| p.latency_ms.unwrap_or(u32::MAX))
             .cloned()
     }
+
+    /// The responsive peer supporting a task type that reported the most
+    /// free capacity, ties going to the lowest latency
+    pub fn best_peer_for_offload(&self, task_type: TaskType) -> Option<PeerInfo> {
+        self.peers
+            .read()
+            .values()
+            .filter(|p| {
+                p.status == WorkerStatus::Ready
+                    && !p.stale
+                    && p.capacity_pct.is_some_and(|c| c > 0.0)
+                    && p.capabilities.supported_tasks.contains(&task_type)
+            })
+            .max_by(|a, b| {
+                a.capacity_pct
+                    .partial_cmp(&b.capacity_pct)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| b.latency_ms.unwrap_or(u32::MAX).cmp(&a.latency_ms.unwrap_or(u32::MAX)))
+            })
+            .cloned()
+    }
 }
 
 impl Default for PeerRegistry {
@@ -205,10 +295,13 @@ mod tests {
                 gpu_memory_mb: None,
                 max_context_length: 4096,
                 worker_version: "0.1.0".to_string(),
+                supports_streaming: false,
             },
             status: WorkerStatus::Ready,
             last_seen: Instant::now(),
             latency_ms: None,
+            stale: false,
+            capacity_pct: None,
             groups: vec![],
         }
     }
@@ -281,6 +374,77 @@ mod tests {
         assert_eq!(g1_peers.len(), 1);
     }
 
+    #[test]
+    fn test_register_directory_reports_malformed_entries() {
+        let registry = PeerRegistry::new();
+        let caps = make_peer("x", vec![TaskType::TextCompletion]).capabilities;
+        let entry = |id: &str, addr: &str| PeerDirectoryEntry {
+            worker_id: id.to_string(),
+            name: String::new(),
+            listen_addr: addr.to_string(),
+            capabilities: caps.clone(),
+            status: WorkerStatus::Ready,
+        };
+        let directory = vec![
+            entry("self", "127.0.0.1:9000"),
+            entry("w-good", "127.0.0.1:9100"),
+            entry("w-bad", "not-an-address"),
+        ];
+
+        let import = registry.register_directory(&directory, "self");
+
+        assert_eq!(import.registered, 1);
+        assert_eq!(import.malformed, vec!["w-bad".to_string()]);
+        assert_eq!(registry.get("w-good").unwrap().listen_addr.port(), 9100);
+        assert!(registry.get("w-bad").is_none());
+        assert!(registry.get("self").is_none());
+    }
+
+    #[test]
+    fn test_best_peer_by_latency() {
+        let registry = PeerRegistry::new();
+        registry.register(make_peer("near", vec![TaskType::TextCompletion]));
+        registry.register(make_peer("far", vec![TaskType::TextCompletion]));
+        registry.update_latency("near", 5);
+        registry.update_latency("far", 80);
+        let best = || registry.best_peer_for_task(TaskType::TextCompletion).unwrap().worker_id;
+        assert_eq!(best(), "near");
+
+        // A stale peer is passed over until it answers again
+        registry.mark_stale("near");
+        assert_eq!(best(), "far");
+        registry.update_latency("near", 6);
+        assert_eq!(best(), "near");
+    }
+
+    #[test]
+    fn test_best_peer_for_offload_by_capacity() {
+        let registry = PeerRegistry::new();
+        registry.register(make_peer("full", vec![TaskType::TextCompletion]));
+        registry.register(make_peer("half", vec![TaskType::TextCompletion]));
+        registry.register(make_peer("idle", vec![TaskType::TextCompletion]));
+        registry.register(make_peer("embedder", vec![TaskType::Embeddings]));
+        let best = || {
+            registry
+                .best_peer_for_offload(TaskType::TextCompletion)
+                .map(|p| p.worker_id)
+        };
+
+        // Nobody has reported spare capacity yet
+        assert_eq!(best(), None);
+
+        registry.update_capacity("full", 0.0);
+        registry.update_capacity("half", 50.0);
+        registry.update_capacity("idle", 100.0);
+        registry.update_capacity("embedder", 100.0);
+        assert_eq!(best().as_deref(), Some("idle"));
+
+        registry.update_status("idle", WorkerStatus::Draining);
+        assert_eq!(best().as_deref(), Some("half"));
+        registry.mark_stale("half");
+        assert_eq!(best(), None);
+    }
+
     #[test]
     fn test_prune_stale() {
         let registry = PeerRegistry::new();
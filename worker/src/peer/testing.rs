@@ -0,0 +1,68 @@
+//! Fixtures shared by the peer module's tests
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+use crate::protocol::{PeerMessage, WorkerCapabilities, WorkerStatus};
+use crate::types::TaskType;
+
+use super::{MeshConfig, PeerEvent, PeerInfo, PeerMesh, PeerRegistry};
+
+/// Capabilities of every test worker
+pub fn capabilities() -> WorkerCapabilities {
+    WorkerCapabilities {
+        supported_tasks: vec![TaskType::Debug],
+        max_concurrent_tasks: 4,
+        available_memory_mb: 8192,
+        gpu_available: false,
+        gpu_device: None,
+        gpu_memory_mb: None,
+        max_context_length: 4096,
+        worker_version: "0.1.0".to_string(),
+        supports_streaming: false,
+    }
+}
+
+/// A mesh for `worker_id`, not yet listening, and its events
+pub fn mesh(worker_id: &str, config: MeshConfig) -> (Arc<PeerMesh>, mpsc::Receiver<PeerEvent>) {
+    let (event_tx, event_rx) = mpsc::channel(100);
+    let mesh = Arc::new(PeerMesh::new(
+        config,
+        worker_id.to_string(),
+        capabilities(),
+        Arc::new(PeerRegistry::new()),
+        event_tx,
+    ));
+    (mesh, event_rx)
+}
+
+/// `worker_id` listening on a local `port`
+pub fn peer(worker_id: &str, port: u16) -> PeerInfo {
+    PeerInfo {
+        worker_id: worker_id.to_string(),
+        name: format!("Peer {}", worker_id),
+        listen_addr: format!("127.0.0.1:{}", port).parse().unwrap(),
+        capabilities: capabilities(),
+        status: WorkerStatus::Ready,
+        last_seen: Instant::now(),
+        latency_ms: None,
+        stale: false,
+        capacity_pct: None,
+        groups: vec![],
+    }
+}
+
+/// Next peer message delivered to `events`
+pub async fn next_message(events: &mut mpsc::Receiver<PeerEvent>) -> (String, PeerMessage) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(PeerEvent::MessageReceived { from, message }) = events.recv().await {
+                return (from, message);
+            }
+        }
+    })
+    .await
+    .unwrap()
+}
@@ -5,10 +5,14 @@ use std::path::{Path, PathBuf};
 
 use tracing::{debug, error, info, warn};
 
+use crate::config::PluginSettings;
 use crate::error::{Error, Result};
 use crate::gpu::{GpuInfo, GpuVendor};
 
-use super::{LoadedPlugin, PluginInfo, PluginRegistry, PluginState, PLUGIN_API_VERSION};
+use super::{
+    LoadedPlugin, PluginInfo, PluginManifest, PluginMetadata, PluginRegistry, PluginState,
+    PLUGIN_API_VERSION,
+};
 
 // ─────────────────────────────────────────────────────────────────
 // Plugin Manager Configuration
@@ -26,11 +30,21 @@ pub struct PluginManagerConfig {
     /// Custom registry URL (None = use default)
     pub registry_url: Option<String>,
 
-    /// Whether to verify checksums
+    /// Whether to verify checksums (plugins without one are refused)
     pub verify_checksums: bool,
 
+    /// Pinned ed25519 key of the registry; when set, plugin manifests must
+    /// carry a valid signature by it
+    pub registry_public_key: Option<Vec<u8>>,
+
     /// Connection timeout for downloads (seconds)
     pub download_timeout_secs: u64,
+
+    /// Plugin names allowed to be downloaded or loaded (empty = any)
+    pub allowed_plugins: Vec<String>,
+
+    /// GPU vendors whose plugins may be downloaded or loaded (empty = any)
+    pub allowed_vendors: Vec<GpuVendor>,
 }
 
 impl Default for PluginManagerConfig {
@@ -45,7 +59,36 @@ impl Default for PluginManagerConfig {
             auto_download: true,
             registry_url: None,
             verify_checksums: true,
+            registry_public_key: None,
             download_timeout_secs: 300,
+            allowed_plugins: vec![],
+            allowed_vendors: vec![],
+        }
+    }
+}
+
+impl PluginManagerConfig {
+    /// Build from the `[plugins]` config section
+    pub fn from_settings(settings: &PluginSettings) -> Self {
+        Self {
+            plugin_dir: PathBuf::from(&settings.plugin_dir),
+            auto_download: settings.auto_download,
+            registry_url: if settings.registry_url.is_empty() {
+                None
+            } else {
+                Some(settings.registry_url.clone())
+            },
+            verify_checksums: settings.verify_checksums,
+            registry_public_key: hex::decode(&settings.registry_public_key)
+                .ok()
+                .filter(|key| key.len() == 32),
+            download_timeout_secs: settings.download_timeout_secs,
+            allowed_plugins: settings.allowed_plugins.clone(),
+            allowed_vendors: settings
+                .allowed_vendors
+                .iter()
+                .filter_map(|v| GpuVendor::from_name(v))
+                .collect(),
         }
     }
 }
@@ -123,9 +166,139 @@ impl PluginManager {
         })
     }
 
-    /// Find the best plugin for a GPU
+    /// Find the best trusted plugin for a GPU
+    ///
+    /// Walks the registry's preference order and skips plugins rejected by
+    /// the trust policy, so e.g. Vulkan is picked on an AMD box where only
+    /// `vulkan-backend` is allowed.
     pub fn find_plugin_for_gpu(&self, gpu: &GpuInfo) -> Option<&PluginInfo> {
-        self.registry.find_best_for_gpu(gpu)
+        if !self.config.allowed_vendors.is_empty()
+            && !self.config.allowed_vendors.contains(&gpu.vendor)
+        {
+            debug!(vendor = %gpu.vendor, "GPU vendor not in allowed_vendors, skipping plugins");
+            return None;
+        }
+
+        self.registry
+            .candidates_for_gpu(gpu)
+            .into_iter()
+            .find(|plugin| match self.check_trusted(plugin) {
+                Ok(()) => true,
+                Err(e) => {
+                    debug!(plugin = %plugin.name, reason = %e, "Skipping untrusted plugin");
+                    false
+                }
+            })
+    }
+
+    /// Check a plugin against the `allowed_plugins` / `allowed_vendors` policy
+    pub fn check_trusted(&self, plugin: &PluginInfo) -> Result<()> {
+        if !self.config.allowed_plugins.is_empty()
+            && !self.config.allowed_plugins.iter().any(|n| n == &plugin.name)
+        {
+            return Err(Error::PluginNotTrusted {
+                name: plugin.name.clone(),
+                reason: "not listed in allowed_plugins".to_string(),
+            });
+        }
+
+        if !self.config.allowed_vendors.is_empty()
+            && !plugin
+                .supported_vendors
+                .iter()
+                .any(|v| self.config.allowed_vendors.contains(v))
+        {
+            return Err(Error::PluginNotTrusted {
+                name: plugin.name.clone(),
+                reason: "supports no vendor in allowed_vendors".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check a plugin's manifest against this worker's version and the
+    /// plugin ABI before anything is loaded
+    pub fn check_compatible(&self, plugin: &PluginInfo) -> Result<()> {
+        check_compatibility(plugin, env!("CARGO_PKG_VERSION"))
+    }
+
+    /// Check a plugin file against the registry's checksum and, with a
+    /// pinned registry key, the manifest signature. Called before the
+    /// library is ever loaded.
+    pub fn verify_plugin_file(&self, plugin: &PluginInfo, path: &Path) -> Result<()> {
+        if !self.config.verify_checksums && self.config.registry_public_key.is_none() {
+            return Ok(());
+        }
+        let digest = sha256_file(path).map_err(|e| Error::PluginLoadFailed {
+            name: plugin.name.clone(),
+            message: format!("Failed to read plugin file: {}", e),
+            path: Some(path.to_path_buf()),
+        })?;
+        self.verify_digest(plugin, &digest)
+    }
+
+    /// Check a plugin's SHA-256 (hex) against its manifest
+    fn verify_digest(&self, plugin: &PluginInfo, digest: &str) -> Result<()> {
+        let signing_key = self.config.registry_public_key.as_deref();
+        if !self.config.verify_checksums && signing_key.is_none() {
+            return Ok(());
+        }
+
+        if plugin.checksum.is_empty() {
+            return Err(Error::PluginUnverified {
+                name: plugin.name.clone(),
+                reason: "registry published no checksum".to_string(),
+            });
+        }
+        if !plugin.checksum.eq_ignore_ascii_case(digest) {
+            return Err(Error::PluginChecksumMismatch {
+                name: plugin.name.clone(),
+                expected: plugin.checksum.clone(),
+                actual: digest.to_string(),
+            });
+        }
+        debug!(plugin = %plugin.name, "Checksum verified");
+
+        // The signed manifest covers the checksum, and so the file
+        if let Some(key) = signing_key {
+            verify_manifest_signature(plugin, key)?;
+            debug!(plugin = %plugin.name, "Manifest signature verified");
+        }
+        Ok(())
+    }
+
+    /// Fetch the registry manifest, which carries the checksums (and
+    /// signatures) built-in plugins are verified against. Returns how many
+    /// plugins it covered.
+    pub async fn refresh_manifest(&mut self) -> Result<usize> {
+        let url = self.registry.manifest_url();
+        let failed = |message: String| Error::PluginDownloadFailed {
+            name: "manifest".to_string(),
+            message,
+            url: Some(url.clone()),
+        };
+
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(self.config.download_timeout_secs))
+            .build()
+            .map_err(|e| failed(format!("Failed to create HTTP client: {}", e)))?;
+        let response = client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| failed(format!("Manifest request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(failed(format!("HTTP error: {}", response.status())));
+        }
+        let manifest: PluginManifest = response
+            .json()
+            .await
+            .map_err(|e| failed(format!("Invalid manifest: {}", e)))?;
+
+        let updated = self.registry.apply_manifest(&manifest);
+        debug!(updated, "Plugin manifest applied");
+        Ok(updated)
     }
 
     /// Check if we have a plugin for a GPU vendor
@@ -138,6 +311,7 @@ impl PluginManager {
     pub async fn download_plugin(&self, plugin: &PluginInfo) -> Result<PathBuf> {
         use sha2::{Digest, Sha256};
 
+        self.check_trusted(plugin)?;
         self.ensure_plugin_dir()?;
 
         let url = plugin.get_download_url();
@@ -182,21 +356,8 @@ impl PluginManager {
                 url: Some(url.clone()),
             })?;
 
-        // Verify checksum if required
-        if self.config.verify_checksums && !plugin.checksum.is_empty() {
-            let mut hasher = Sha256::new();
-            hasher.update(&bytes);
-            let hash = hex::encode(hasher.finalize());
-
-            if hash != plugin.checksum {
-                return Err(Error::PluginChecksumMismatch {
-                    name: plugin.name.clone(),
-                    expected: plugin.checksum.clone(),
-                    actual: hash,
-                });
-            }
-            debug!(plugin = %plugin.name, "Checksum verified");
-        }
+        // Verify before anything reaches the plugin directory
+        self.verify_digest(plugin, &hex::encode(Sha256::digest(&bytes)))?;
 
         // Write to file
         std::fs::write(&dest_path, &bytes)
@@ -236,12 +397,19 @@ impl PluginManager {
             .ok_or_else(|| Error::PluginNotFound { name: name.to_string() })?
             .clone();
 
+        self.check_trusted(&plugin_info)?;
+
         let plugin_path = self.config.plugin_dir.join(plugin_info.full_file_name());
 
         if !plugin_path.exists() {
             return Err(Error::PluginNotFound { name: name.to_string() });
         }
 
+        // Never load a library that doesn't match the registry's manifest
+        // or that this worker can't talk to
+        self.verify_plugin_file(&plugin_info, &plugin_path)?;
+        self.check_compatible(&plugin_info)?;
+
         info!(
             plugin = %name,
             path = %plugin_path.display(),
@@ -258,10 +426,11 @@ impl PluginManager {
                 })?
         };
 
-        // Verify plugin API version
+        // Verify what the library reports about itself; the library is
+        // dropped (unloaded) on mismatch
         if let Err(e) = self.verify_plugin_api(&library, &plugin_info) {
             warn!(plugin = %name, error = %e, "Plugin API verification failed");
-            // Continue anyway for now, but log warning
+            return Err(e);
         }
 
         let loaded = LoadedPlugin {
@@ -309,7 +478,15 @@ impl PluginManager {
             });
         }
 
-        Ok(())
+        let metadata: libloading::Symbol<unsafe extern "C" fn() -> PluginMetadata> = unsafe {
+            library.get(b"plugin_metadata")
+                .map_err(|_| Error::PluginIncompatible {
+                    name: info.name.clone(),
+                    reason: "Missing plugin_metadata export".to_string(),
+                })?
+        };
+
+        check_metadata(info, &unsafe { metadata() })
     }
 
     /// Ensure a plugin is available (download if needed)
@@ -432,6 +609,101 @@ pub struct PluginStatus {
     pub path: Option<PathBuf>,
 }
 
+// ─────────────────────────────────────────────────────────────────
+// Verification Helpers
+// ─────────────────────────────────────────────────────────────────
+
+/// Check a manifest's API version and minimum worker version
+fn check_compatibility(plugin: &PluginInfo, worker_version: &str) -> Result<()> {
+    let incompatible = |reason: String| Error::PluginIncompatible {
+        name: plugin.name.clone(),
+        reason,
+    };
+
+    if plugin.api_version != PLUGIN_API_VERSION {
+        return Err(incompatible(format!(
+            "built for plugin API {}, worker speaks {}",
+            plugin.api_version, PLUGIN_API_VERSION
+        )));
+    }
+
+    let required = parse_version(&plugin.min_worker_version).ok_or_else(|| {
+        incompatible(format!("invalid min_worker_version {:?}", plugin.min_worker_version))
+    })?;
+    let current = parse_version(worker_version).ok_or_else(|| {
+        Error::Internal(format!("invalid worker version {:?}", worker_version))
+    })?;
+    if current < required {
+        return Err(incompatible(format!(
+            "requires worker {} or newer, this is {}",
+            plugin.min_worker_version, worker_version
+        )));
+    }
+    Ok(())
+}
+
+/// Check what a loaded library reports against its manifest
+fn check_metadata(plugin: &PluginInfo, metadata: &PluginMetadata) -> Result<()> {
+    if metadata.api_version != plugin.api_version {
+        return Err(Error::PluginIncompatible {
+            name: plugin.name.clone(),
+            reason: format!(
+                "library reports API version {}, manifest says {}",
+                metadata.api_version, plugin.api_version
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Semver precedence key for `MAJOR.MINOR.PATCH[-pre][+build]`. A
+/// pre-release sorts before its release; pre-release identifiers are not
+/// compared with each other.
+fn parse_version(version: &str) -> Option<(u64, u64, u64, bool)> {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split('+').next()?;
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+    let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    let patch = parts.next()??;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch, pre.is_none()))
+}
+
+/// SHA-256 of a file (hex), read in chunks
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Check the registry's ed25519 signature over a plugin's manifest
+fn verify_manifest_signature(plugin: &PluginInfo, public_key: &[u8]) -> Result<()> {
+    use ring::signature::{UnparsedPublicKey, ED25519};
+
+    let unverified = |reason: &str| Error::PluginUnverified {
+        name: plugin.name.clone(),
+        reason: reason.to_string(),
+    };
+    if plugin.signature.is_empty() {
+        return Err(unverified("manifest is not signed"));
+    }
+    let signature = hex::decode(&plugin.signature)
+        .map_err(|_| unverified("manifest signature is not valid hex"))?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(plugin.manifest_message().as_bytes(), &signature)
+        .map_err(|_| unverified("manifest signature does not match the registry key"))
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────
@@ -468,6 +740,186 @@ mod tests {
         assert!(available.iter().any(|p| p.name == "vulkan-backend"));
     }
 
+    fn make_gpu(vendor: GpuVendor) -> GpuInfo {
+        GpuInfo {
+            id: 0,
+            name: format!("{} GPU", vendor),
+            vendor,
+            vendor_id: vendor.vendor_id(),
+            device_id: 0x1234,
+            total_memory_mb: 16384,
+            driver_version: "1.0".to_string(),
+            api_support: vec![crate::gpu::GpuApi::Vulkan],
+            vulkan_version: Some("1.3".to_string()),
+            is_discrete: true,
+            compute_capable: true,
+        }
+    }
+
+    #[test]
+    fn test_non_allowed_vendor_skipped() {
+        let manager = PluginManager::new(PluginManagerConfig {
+            allowed_vendors: vec![GpuVendor::Amd],
+            ..Default::default()
+        });
+
+        // NVIDIA box: nothing is selected for auto-download
+        assert!(manager.find_plugin_for_gpu(&make_gpu(GpuVendor::Nvidia)).is_none());
+
+        // CUDA plugin only supports NVIDIA, so it's rejected outright
+        let cuda = manager.registry().find_by_name("cuda-backend").unwrap();
+        assert!(matches!(
+            manager.check_trusted(cuda),
+            Err(Error::PluginNotTrusted { .. })
+        ));
+
+        // AMD box still gets its preferred plugin
+        let best = manager.find_plugin_for_gpu(&make_gpu(GpuVendor::Amd)).unwrap();
+        assert_eq!(best.name, "rocm-backend");
+    }
+
+    #[test]
+    fn test_allowed_plugins_falls_back_to_trusted() {
+        let manager = PluginManager::new(PluginManagerConfig {
+            allowed_plugins: vec!["vulkan-backend".to_string()],
+            ..Default::default()
+        });
+
+        // ROCm is preferred on AMD but not allowed; Vulkan is picked instead
+        let best = manager.find_plugin_for_gpu(&make_gpu(GpuVendor::Amd)).unwrap();
+        assert_eq!(best.name, "vulkan-backend");
+    }
+
+    fn write_plugin_file(contents: &[u8]) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plugin.so");
+        std::fs::write(&path, contents).unwrap();
+        (dir, path)
+    }
+
+    fn plugin_with_checksum(contents: &[u8]) -> PluginInfo {
+        use sha2::{Digest, Sha256};
+
+        let manager = PluginManager::with_defaults();
+        let mut plugin = manager.registry().find_by_name("vulkan-backend").unwrap().clone();
+        plugin.checksum = hex::encode(Sha256::digest(contents));
+        plugin
+    }
+
+    #[test]
+    fn test_verify_plugin_checksum() {
+        let (_dir, path) = write_plugin_file(b"plugin bytes");
+        let manager = PluginManager::with_defaults();
+
+        let plugin = plugin_with_checksum(b"plugin bytes");
+        assert!(manager.verify_plugin_file(&plugin, &path).is_ok());
+
+        // Tampered file
+        let plugin = plugin_with_checksum(b"other bytes");
+        assert!(matches!(
+            manager.verify_plugin_file(&plugin, &path),
+            Err(Error::PluginChecksumMismatch { .. })
+        ));
+
+        // No published checksum is refused rather than skipped
+        let mut plugin = plugin_with_checksum(b"plugin bytes");
+        plugin.checksum.clear();
+        assert!(matches!(
+            manager.verify_plugin_file(&plugin, &path),
+            Err(Error::PluginUnverified { .. })
+        ));
+
+        // Unless verification is switched off
+        let manager = PluginManager::new(PluginManagerConfig {
+            verify_checksums: false,
+            ..Default::default()
+        });
+        assert!(manager.verify_plugin_file(&plugin, &path).is_ok());
+    }
+
+    #[test]
+    fn test_verify_plugin_signature() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7u8; 32]).unwrap();
+        let manager = PluginManager::new(PluginManagerConfig {
+            registry_public_key: Some(key_pair.public_key().as_ref().to_vec()),
+            ..Default::default()
+        });
+        let (_dir, path) = write_plugin_file(b"plugin bytes");
+
+        let mut plugin = plugin_with_checksum(b"plugin bytes");
+        assert!(matches!(
+            manager.verify_plugin_file(&plugin, &path),
+            Err(Error::PluginUnverified { .. })
+        ));
+
+        plugin.signature = hex::encode(key_pair.sign(plugin.manifest_message().as_bytes()));
+        assert!(manager.verify_plugin_file(&plugin, &path).is_ok());
+
+        // A signature over a different version doesn't carry over
+        plugin.version = "9.9.9".to_string();
+        assert!(matches!(
+            manager.verify_plugin_file(&plugin, &path),
+            Err(Error::PluginUnverified { .. })
+        ));
+    }
+
+    fn plugin_requiring(min_worker_version: &str, api_version: u32) -> PluginInfo {
+        let manager = PluginManager::with_defaults();
+        let mut plugin = manager.registry().find_by_name("vulkan-backend").unwrap().clone();
+        plugin.min_worker_version = min_worker_version.to_string();
+        plugin.api_version = api_version;
+        plugin
+    }
+
+    #[test]
+    fn test_worker_too_old_for_plugin() {
+        let plugin = plugin_requiring("0.3.0", PLUGIN_API_VERSION);
+        assert!(matches!(
+            check_compatibility(&plugin, "0.2.9"),
+            Err(Error::PluginIncompatible { .. })
+        ));
+        assert!(matches!(
+            check_compatibility(&plugin, "0.3.0-rc.1"),
+            Err(Error::PluginIncompatible { .. })
+        ));
+        assert!(check_compatibility(&plugin, "0.3.0").is_ok());
+        assert!(check_compatibility(&plugin, "0.10.0").is_ok());
+
+        // The bundled registry must be loadable by this build
+        let manager = PluginManager::with_defaults();
+        for plugin in manager.registry().plugins() {
+            assert!(manager.check_compatible(plugin).is_ok(), "{}", plugin.name);
+        }
+    }
+
+    #[test]
+    fn test_worker_too_new_for_plugin() {
+        // Plugin built against an API this worker has moved past
+        let plugin = plugin_requiring("0.1.0", PLUGIN_API_VERSION - 1);
+        let err = check_compatibility(&plugin, "1.0.0").unwrap_err();
+        assert!(matches!(err, Error::PluginIncompatible { .. }));
+        assert!(err.to_string().contains("plugin API"));
+    }
+
+    #[test]
+    fn test_plugin_metadata_api_mismatch() {
+        let plugin = plugin_requiring("0.1.0", PLUGIN_API_VERSION);
+        let mut metadata = PluginMetadata {
+            name: [0; 64],
+            version: [0; 32],
+            api_version: PLUGIN_API_VERSION,
+        };
+        assert!(check_metadata(&plugin, &metadata).is_ok());
+
+        metadata.api_version = PLUGIN_API_VERSION + 1;
+        assert!(matches!(
+            check_metadata(&plugin, &metadata),
+            Err(Error::PluginIncompatible { .. })
+        ));
+    }
+
     #[test]
     fn test_config_default() {
         let config = PluginManagerConfig::default();
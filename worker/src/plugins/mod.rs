@@ -50,6 +50,11 @@ pub struct PluginInfo {
 
     /// Plugin API version (for compatibility)
     pub api_version: u32,
+
+    /// Hex ed25519 signature of [`PluginInfo::manifest_message`] by the
+    /// registry's key
+    #[serde(default)]
+    pub signature: String,
 }
 
 impl PluginInfo {
@@ -85,6 +90,17 @@ impl PluginInfo {
             .replace("{ext}", Self::platform_extension().trim_start_matches('.'))
     }
 
+    /// Bytes the registry signs: name, version and checksum, so a valid
+    /// signature vouches for the exact file
+    pub fn manifest_message(&self) -> String {
+        format!(
+            "AI4ALL-PLUGIN:v1:{}:{}:{}",
+            self.name,
+            self.version,
+            self.checksum.to_lowercase()
+        )
+    }
+
     /// Check if this plugin supports a GPU vendor
     pub fn supports_vendor(&self, vendor: GpuVendor) -> bool {
         self.supported_vendors.contains(&vendor)
@@ -201,6 +217,7 @@ mod tests {
             file_name: "test_plugin".to_string(),
             min_worker_version: "0.1.0".to_string(),
             api_version: 1,
+            signature: String::new(),
         };
 
         let url = info.get_download_url();
@@ -221,6 +238,7 @@ mod tests {
             file_name: "vulkan_backend".to_string(),
             min_worker_version: "0.1.0".to_string(),
             api_version: 1,
+            signature: String::new(),
         };
 
         assert!(info.supports_vendor(GpuVendor::Amd));
@@ -1,6 +1,11 @@
 //! Plugin registry with known plugin metadata
 //!
 //! Provides a registry of known/official plugins that can be downloaded.
+//! Built-in entries carry no checksum or signature; those come from the
+//! registry's published manifest (see [`PluginRegistry::apply_manifest`]),
+//! so a built-in plugin can't pass verification until it has been fetched.
+
+use serde::Deserialize;
 
 use crate::gpu::{GpuInfo, GpuVendor};
 
@@ -42,10 +47,11 @@ impl PluginRegistry {
                     "{}/v{{version}}/vulkan-backend-{{platform}}-{{arch}}.{{ext}}",
                     base_url
                 ),
-                checksum: "".to_string(), // Will be fetched from manifest
+                checksum: String::new(), // From the registry manifest
                 file_name: "vulkan_backend".to_string(),
                 min_worker_version: "0.1.0".to_string(),
                 api_version: 1,
+                signature: String::new(),
             },
 
             // CUDA backend (NVIDIA only) - future
@@ -58,10 +64,11 @@ impl PluginRegistry {
                     "{}/v{{version}}/cuda-backend-{{platform}}-{{arch}}.{{ext}}",
                     base_url
                 ),
-                checksum: "".to_string(),
+                checksum: String::new(),
                 file_name: "cuda_backend".to_string(),
                 min_worker_version: "0.1.0".to_string(),
                 api_version: 1,
+                signature: String::new(),
             },
 
             // ROCm backend (AMD only) - future
@@ -74,10 +81,11 @@ impl PluginRegistry {
                     "{}/v{{version}}/rocm-backend-{{platform}}-{{arch}}.{{ext}}",
                     base_url
                 ),
-                checksum: "".to_string(),
+                checksum: String::new(),
                 file_name: "rocm_backend".to_string(),
                 min_worker_version: "0.1.0".to_string(),
                 api_version: 1,
+                signature: String::new(),
             },
         ];
 
@@ -111,35 +119,24 @@ impl PluginRegistry {
     /// For AMD: ROCm > Vulkan
     /// For NVIDIA: CUDA > Vulkan
     pub fn find_best_for_gpu(&self, gpu: &GpuInfo) -> Option<&PluginInfo> {
-        let compatible = self.find_for_vendor(gpu.vendor);
+        self.candidates_for_gpu(gpu).into_iter().next()
+    }
 
-        if compatible.is_empty() {
-            return None;
-        }
+    /// Plugins usable with a specific GPU, in order of preference
+    pub fn candidates_for_gpu(&self, gpu: &GpuInfo) -> Vec<&PluginInfo> {
+        let compatible = self.find_for_vendor(gpu.vendor);
 
         // Prefer vendor-specific backends
-        match gpu.vendor {
-            GpuVendor::Amd => {
-                // ROCm first, then Vulkan
-                compatible.iter()
-                    .find(|p| p.name == "rocm-backend")
-                    .or_else(|| compatible.iter().find(|p| p.name == "vulkan-backend"))
-                    .copied()
-            }
-            GpuVendor::Nvidia => {
-                // CUDA first, then Vulkan
-                compatible.iter()
-                    .find(|p| p.name == "cuda-backend")
-                    .or_else(|| compatible.iter().find(|p| p.name == "vulkan-backend"))
-                    .copied()
-            }
-            _ => {
-                // Vulkan for everyone else
-                compatible.iter()
-                    .find(|p| p.name == "vulkan-backend")
-                    .copied()
-            }
-        }
+        let preference: &[&str] = match gpu.vendor {
+            GpuVendor::Amd => &["rocm-backend", "vulkan-backend"],
+            GpuVendor::Nvidia => &["cuda-backend", "vulkan-backend"],
+            _ => &["vulkan-backend"],
+        };
+
+        preference
+            .iter()
+            .filter_map(|name| compatible.iter().find(|p| p.name == *name).copied())
+            .collect()
     }
 
     /// Get the base URL
@@ -152,16 +149,49 @@ impl PluginRegistry {
         self.plugins.push(plugin);
     }
 
-    /// Update checksums from manifest
-    pub fn update_checksums(&mut self, checksums: &[(String, String)]) {
-        for (name, checksum) in checksums {
-            if let Some(plugin) = self.plugins.iter_mut().find(|p| &p.name == name) {
-                plugin.checksum = checksum.clone();
+    /// URL of the registry's published manifest
+    pub fn manifest_url(&self) -> String {
+        format!("{}/manifest.json", self.base_url)
+    }
+
+    /// Take checksums and signatures from the registry manifest. Entries
+    /// for another version than the one known here are ignored. Returns
+    /// how many plugins were updated.
+    pub fn apply_manifest(&mut self, manifest: &PluginManifest) -> usize {
+        let mut updated = 0;
+        for entry in &manifest.plugins {
+            if let Some(plugin) = self
+                .plugins
+                .iter_mut()
+                .find(|p| p.name == entry.name && p.version == entry.version)
+            {
+                plugin.checksum = entry.checksum.clone();
+                plugin.signature = entry.signature.clone();
+                updated += 1;
             }
         }
+        updated
     }
 }
 
+/// The registry's `manifest.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginManifest {
+    pub plugins: Vec<ManifestEntry>,
+}
+
+/// Published checksum (and signature) of one plugin version
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub version: String,
+    /// SHA-256 (hex) of the plugin file
+    pub checksum: String,
+    /// Hex ed25519 signature of [`PluginInfo::manifest_message`]
+    #[serde(default)]
+    pub signature: String,
+}
+
 impl Default for PluginRegistry {
     fn default() -> Self {
         Self::new()
@@ -258,6 +288,27 @@ mod tests {
         assert_eq!(best.unwrap().name, "cuda-backend");
     }
 
+    #[test]
+    fn test_manifest_fills_builtin_checksums() {
+        let mut registry = PluginRegistry::with_base_url("https://example.com/plugins");
+        assert!(registry.plugins().iter().all(|p| p.checksum.is_empty()));
+        assert_eq!(registry.manifest_url(), "https://example.com/plugins/manifest.json");
+
+        let manifest: PluginManifest = serde_json::from_value(serde_json::json!({
+            "plugins": [
+                { "name": "vulkan-backend", "version": "0.1.0", "checksum": "ab12", "signature": "cd34" },
+                { "name": "rocm-backend", "version": "9.9.9", "checksum": "ef56" },
+            ]
+        }))
+        .unwrap();
+        assert_eq!(registry.apply_manifest(&manifest), 1);
+
+        let vulkan = registry.find_by_name("vulkan-backend").unwrap();
+        assert_eq!((vulkan.checksum.as_str(), vulkan.signature.as_str()), ("ab12", "cd34"));
+        // Another version's checksum would never match this file
+        assert!(registry.find_by_name("rocm-backend").unwrap().checksum.is_empty());
+    }
+
     #[test]
     fn test_custom_base_url() {
         let registry = PluginRegistry::with_base_url("https://custom.example.com");
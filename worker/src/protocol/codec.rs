@@ -0,0 +1,46 @@
+//! Wire formats for coordinator messages
+//!
+//! Envelopes are JSON by default. A worker may offer MessagePack when it
+//! registers; if the coordinator picks it in `RegisterAck`, both sides send
+//! binary WebSocket frames holding MessagePack from then on. Text frames
+//! are always JSON, so a coordinator that never answers the offer (or
+//! mixes in text frames) keeps working.
+//!
+//! MessagePack is written with struct fields as map keys and in
+//! human-readable mode (UUIDs and timestamps as strings), the same shape as
+//! the JSON, so flattened envelopes and tagged enums decode the same way.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Encoding of protocol messages on the wire
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    /// JSON text frames
+    #[default]
+    Json,
+    /// MessagePack binary frames
+    Msgpack,
+}
+
+impl WireFormat {
+    /// Formats this worker supports, most preferred first
+    pub const SUPPORTED: &'static [WireFormat] = &[WireFormat::Msgpack, WireFormat::Json];
+}
+
+/// Serialize `value` as MessagePack
+pub fn to_msgpack<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    let mut buf = Vec::new();
+    let mut serializer = rmp_serde::Serializer::new(&mut buf)
+        .with_struct_map()
+        .with_human_readable();
+    value.serialize(&mut serializer)?;
+    Ok(buf)
+}
+
+/// Deserialize a value written by [`to_msgpack`]
+pub fn from_msgpack<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, rmp_serde::decode::Error> {
+    let mut deserializer = rmp_serde::Deserializer::from_read_ref(bytes).with_human_readable();
+    T::deserialize(&mut deserializer)
+}
@@ -0,0 +1,124 @@
+//! Per-message payload compression
+//!
+//! Large messages (task results, tensor blobs) can be compressed once both
+//! ends have agreed on an algorithm: peers in `Hello` / `HelloAck`, the
+//! coordinator in `Register` / `RegisterAck`. A compressed message travels
+//! as a `COMPRESSED` message naming the algorithm and carrying the original
+//! JSON, compressed and base64-encoded. Messages under
+//! [`COMPRESSION_THRESHOLD`], or that don't shrink, are sent as they are.
+
+use std::io::{self, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Smallest serialized message worth compressing (bytes)
+pub const COMPRESSION_THRESHOLD: usize = 8 * 1024;
+
+/// Largest decompressed message accepted (bytes)
+pub const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Compression algorithm for message payloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+}
+
+impl Compression {
+    /// Algorithms this worker supports, most preferred first
+    pub const SUPPORTED: &'static [Compression] = &[Compression::Gzip];
+
+    /// The algorithm to use with a side offering `offered`, if any
+    pub fn negotiate(offered: &[Compression]) -> Option<Compression> {
+        Self::SUPPORTED.iter().copied().find(|c| offered.contains(c))
+    }
+
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+
+    /// Decompress `data`, failing past [`MAX_DECOMPRESSED_SIZE`] bytes
+    pub fn decompress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        match self {
+            Compression::Gzip => {
+                flate2::read::GzDecoder::new(data)
+                    .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+                    .read_to_end(&mut out)?;
+            }
+        }
+        if out.len() > MAX_DECOMPRESSED_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("decompressed message exceeds {} bytes", MAX_DECOMPRESSED_SIZE),
+            ));
+        }
+        Ok(out)
+    }
+
+    /// `json` compressed, if it is at least [`COMPRESSION_THRESHOLD`] bytes
+    /// and still smaller once base64-encoded
+    pub(crate) fn compress_if_worthwhile(self, json: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        if json.len() < COMPRESSION_THRESHOLD {
+            return Ok(None);
+        }
+        let compressed = self.compress(json)?;
+        let encoded_len = compressed.len().div_ceil(3) * 4;
+        Ok((encoded_len < json.len()).then_some(compressed))
+    }
+}
+
+/// Deserialize an offer (of algorithms, or wire formats), skipping names
+/// this worker doesn't know so a newer peer's offer still parses
+pub(crate) fn deserialize_offer<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let names = Vec::<String>::deserialize(deserializer)?;
+    Ok(names
+        .into_iter()
+        .filter_map(|name| serde_json::from_value(serde_json::Value::String(name)).ok())
+        .collect())
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Compression::negotiate(&[Compression::Gzip]), Some(Compression::Gzip));
+        assert_eq!(Compression::negotiate(&[]), None);
+
+        // Algorithms this worker doesn't know are skipped
+        let mut de = serde_json::Deserializer::from_str(r#"["lzma", "gzip"]"#);
+        let offered: Vec<Compression> = deserialize_offer(&mut de).unwrap();
+        assert_eq!(offered, [Compression::Gzip]);
+    }
+
+    #[test]
+    fn test_gzip_round_trip_and_threshold() {
+        let json = "{\"text\":\"".to_string() + &"all work and no play ".repeat(1000) + "\"}";
+        let gzip = Compression::Gzip;
+
+        let compressed = gzip.compress_if_worthwhile(json.as_bytes()).unwrap().unwrap();
+        assert!(compressed.len() < json.len() / 10);
+        assert_eq!(gzip.decompress(&compressed).unwrap(), json.as_bytes());
+
+        assert!(gzip.compress_if_worthwhile(b"{\"type\":\"PING\"}").unwrap().is_none());
+        assert!(gzip.decompress(b"not gzip").is_err());
+    }
+}
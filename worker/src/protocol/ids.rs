@@ -0,0 +1,152 @@
+//! Block and day identifiers
+//!
+//! The coordinator schedules work in days (`YYYY-MM-DD`) split into blocks
+//! (`block_3_1`, ...). These newtypes parse and check the wire strings once
+//! so code handling an assignment can rely on well-formed values.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::warn;
+
+use crate::error::{Error, Result};
+
+/// Longest block ID accepted
+const MAX_BLOCK_ID_LEN: usize = 128;
+
+/// Identifier of a block of work within a day
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlockId(String);
+
+impl FromStr for BlockId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.is_empty() || s.len() > MAX_BLOCK_ID_LEN {
+            return Err(Error::Protocol(format!(
+                "Invalid block_id '{}': must be 1-{} characters",
+                s, MAX_BLOCK_ID_LEN
+            )));
+        }
+        if !s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':')) {
+            return Err(Error::Protocol(format!(
+                "Invalid block_id '{}': only letters, digits, '-', '_', '.' and ':' are allowed",
+                s
+            )));
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Identifier of a scheduling day, an ISO date (`2026-01-30`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DayId(NaiveDate);
+
+impl FromStr for DayId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // Exactly YYYY-MM-DD; chrono alone would also take unpadded fields
+        let well_formed = s.len() == 10
+            && s.bytes().enumerate().all(|(i, b)| match i {
+                4 | 7 => b == b'-',
+                _ => b.is_ascii_digit(),
+            });
+        let date = well_formed
+            .then(|| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+            .flatten()
+            .ok_or_else(|| {
+                Error::Protocol(format!("Invalid day_id '{}': expected an ISO date (YYYY-MM-DD)", s))
+            })?;
+        Ok(Self(date))
+    }
+}
+
+impl fmt::Display for DayId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.format("%Y-%m-%d"))
+    }
+}
+
+macro_rules! string_serde {
+    ($ty:ty) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                let raw = String::deserialize(deserializer)?;
+                raw.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+string_serde!(BlockId);
+string_serde!(DayId);
+
+/// Deserialize an optional ID, logging and dropping a malformed one rather
+/// than rejecting the whole message
+pub(crate) fn lenient<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = Error>,
+{
+    let raw = Option::<String>::deserialize(deserializer)?;
+    Ok(raw.and_then(|raw| match raw.parse() {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warn!(error = %e, "Ignoring malformed identifier on task assignment");
+            None
+        }
+    }))
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_ids() {
+        let day: DayId = "2026-01-30".parse().unwrap();
+        assert_eq!(day.0, NaiveDate::from_ymd_opt(2026, 1, 30).unwrap());
+        assert_eq!(day.to_string(), "2026-01-30");
+
+        for id in ["block_3_1", "block-456", "b.7:2"] {
+            assert_eq!(id.parse::<BlockId>().unwrap().to_string(), id);
+        }
+    }
+
+    #[test]
+    fn test_reject_malformed_ids() {
+        for day in ["", "2026-1-30", "2026/01/30", "2026-02-30", "30-01-2026", "2026-01-30T00:00:00Z"] {
+            assert!(day.parse::<DayId>().is_err(), "{} accepted", day);
+        }
+        let too_long = "b".repeat(MAX_BLOCK_ID_LEN + 1);
+        for block in ["", "block 1", "block/1", too_long.as_str()] {
+            assert!(block.parse::<BlockId>().is_err(), "{} accepted", block);
+        }
+    }
+
+    #[test]
+    fn test_serde_uses_wire_strings() {
+        let day: DayId = serde_json::from_str("\"2026-01-30\"").unwrap();
+        assert_eq!(serde_json::to_string(&day).unwrap(), "\"2026-01-30\"");
+        assert!(serde_json::from_str::<DayId>("\"yesterday\"").is_err());
+    }
+}
@@ -1,14 +1,19 @@
 //! Protocol message definitions
 //!
 //! All message types for worker-coordinator communication.
-//! Messages are serialized as JSON with a type discriminator.
+//! Messages are serialized as JSON (or MessagePack, see [`super::codec`])
+//! with a type discriminator.
+
+use std::io;
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
-use crate::types::{TaskInput, TaskOutput, TaskType};
-use super::ProtocolVersion;
+use crate::types::{GenerationParams, TaskInput, TaskOutput, TaskType};
+use super::codec::{self, WireFormat};
+use super::compression::{self, Compression};
+use super::{BlockId, DayId, ProtocolVersion};
 
 // ─────────────────────────────────────────────────────────────────
 // Message Envelope
@@ -26,6 +31,11 @@ pub struct MessageEnvelope {
     /// Protocol version
     pub version: ProtocolVersion,
 
+    /// Set when the payload is a `COMPRESSED` message holding the real one.
+    /// (Not `compression`: that key belongs to `REGISTER` / `REGISTER_ACK`.)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<Compression>,
+
     /// The actual message payload
     #[serde(flatten)]
     pub payload: Message,
@@ -38,6 +48,7 @@ impl MessageEnvelope {
             id: Uuid::new_v4(),
             timestamp: Utc::now(),
             version: ProtocolVersion::default(),
+            encoding: None,
             payload,
         }
     }
@@ -48,6 +59,7 @@ impl MessageEnvelope {
             id: Uuid::new_v4(),
             timestamp: Utc::now(),
             version,
+            encoding: None,
             payload,
         }
     }
@@ -71,9 +83,15 @@ pub enum Message {
     /// Task result submission
     TaskResult(TaskResultMessage),
 
+    /// Tokens generated so far by a streaming task
+    TaskProgress(TaskProgressMessage),
+
     /// Worker status update
     StatusUpdate(StatusUpdateMessage),
 
+    /// Advertised capabilities changed since registration
+    CapabilitiesUpdate(CapabilitiesUpdateMessage),
+
     /// Worker graceful shutdown notification
     Shutdown(ShutdownMessage),
 
@@ -90,6 +108,9 @@ pub enum Message {
     /// Task cancellation request
     TaskCancel(TaskCancelMessage),
 
+    /// Task result receipt (when result acknowledgment is required)
+    TaskResultAck(TaskResultAckMessage),
+
     /// Configuration update from coordinator
     ConfigUpdate(ConfigUpdateMessage),
 
@@ -103,35 +124,62 @@ pub enum Message {
     /// Coordinator sends directory of available peers
     PeerDirectory(PeerDirectoryMessage),
 
+    /// Worker asks for a fresh peer directory
+    PeerDirectoryRequest(PeerDirectoryRequestMessage),
+
     /// Coordinator assigns worker to a group
     GroupAssigned(GroupAssignedMessage),
 
     /// Coordinator updates group membership
     GroupUpdate(GroupUpdateMessage),
+
+    // ─── Either Direction ───────────────────────────────────────
+    /// Another message, compressed (see [`MessageEnvelope::encoding`])
+    Compressed(CompressedMessage),
 }
 
-impl Message {
-    /// Get the message type name
-    pub fn type_name(&self) -> &'static str {
-        match self {
-            Message::Register(_) => "REGISTER",
-            Message::RegisterAck(_) => "REGISTER_ACK",
-            Message::Heartbeat(_) => "HEARTBEAT",
-            Message::HeartbeatAck(_) => "HEARTBEAT_ACK",
-            Message::TaskAssignment(_) => "TASK_ASSIGNMENT",
-            Message::TaskResult(_) => "TASK_RESULT",
-            Message::TaskCancel(_) => "TASK_CANCEL",
-            Message::StatusUpdate(_) => "STATUS_UPDATE",
-            Message::ConfigUpdate(_) => "CONFIG_UPDATE",
-            Message::Shutdown(_) => "SHUTDOWN",
-            Message::Error(_) => "ERROR",
-            Message::PeerDiscover(_) => "PEER_DISCOVER",
-            Message::PeerDirectory(_) => "PEER_DIRECTORY",
-            Message::GroupAssigned(_) => "GROUP_ASSIGNED",
-            Message::GroupUpdate(_) => "GROUP_UPDATE",
+/// Defines [`Message::type_name`] and [`Message::TYPE_NAMES`] from one list,
+/// so the exhaustive match keeps the list complete
+macro_rules! message_type_names {
+    ($($variant:ident => $name:literal,)*) => {
+        impl Message {
+            /// Wire names of all message types
+            pub const TYPE_NAMES: &'static [&'static str] = &[$($name),*];
+
+            /// Get the message type name
+            pub fn type_name(&self) -> &'static str {
+                match self {
+                    $(Message::$variant(_) => $name,)*
+                }
+            }
         }
-    }
+    };
+}
 
+message_type_names! {
+    Register => "REGISTER",
+    RegisterAck => "REGISTER_ACK",
+    Heartbeat => "HEARTBEAT",
+    HeartbeatAck => "HEARTBEAT_ACK",
+    TaskAssignment => "TASK_ASSIGNMENT",
+    TaskResult => "TASK_RESULT",
+    TaskProgress => "TASK_PROGRESS",
+    TaskCancel => "TASK_CANCEL",
+    TaskResultAck => "TASK_RESULT_ACK",
+    StatusUpdate => "STATUS_UPDATE",
+    CapabilitiesUpdate => "CAPABILITIES_UPDATE",
+    ConfigUpdate => "CONFIG_UPDATE",
+    Shutdown => "SHUTDOWN",
+    Error => "ERROR",
+    PeerDiscover => "PEER_DISCOVER",
+    PeerDirectory => "PEER_DIRECTORY",
+    PeerDirectoryRequest => "PEER_DIRECTORY_REQUEST",
+    GroupAssigned => "GROUP_ASSIGNED",
+    GroupUpdate => "GROUP_UPDATE",
+    Compressed => "COMPRESSED",
+}
+
+impl Message {
     /// Check if this is a request message (worker → coordinator)
     pub fn is_request(&self) -> bool {
         matches!(
@@ -139,9 +187,12 @@ impl Message {
             Message::Register(_)
                 | Message::Heartbeat(_)
                 | Message::TaskResult(_)
+                | Message::TaskProgress(_)
                 | Message::StatusUpdate(_)
+                | Message::CapabilitiesUpdate(_)
                 | Message::Shutdown(_)
                 | Message::PeerDiscover(_)
+                | Message::PeerDirectoryRequest(_)
         )
     }
 
@@ -156,7 +207,7 @@ impl Message {
 // ─────────────────────────────────────────────────────────────────
 
 /// Worker capabilities for registration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkerCapabilities {
     /// Supported task types
     pub supported_tasks: Vec<TaskType>,
@@ -183,6 +234,11 @@ pub struct WorkerCapabilities {
 
     /// Worker software version
     pub worker_version: String,
+
+    /// Whether the worker streams generated tokens of tasks that ask for
+    /// it as TASK_PROGRESS messages
+    #[serde(default)]
+    pub supports_streaming: bool,
 }
 
 /// Worker registration request
@@ -205,6 +261,23 @@ pub struct RegisterRequest {
     /// Authentication token (if required)
     #[serde(default)]
     pub auth_token: Option<String>,
+
+    /// Compression algorithms the worker accepts for large messages
+    #[serde(
+        default,
+        deserialize_with = "compression::deserialize_offer",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub compression: Vec<Compression>,
+
+    /// Wire formats the worker can use after registering, most preferred
+    /// first
+    #[serde(
+        default,
+        deserialize_with = "compression::deserialize_offer",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub wire_formats: Vec<WireFormat>,
 }
 
 /// Registration acknowledgment from coordinator
@@ -229,6 +302,14 @@ pub struct RegisterAckResponse {
     /// Any error message
     #[serde(default)]
     pub error: Option<String>,
+
+    /// Compression chosen from the worker's offer, used both ways from now
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+
+    /// Wire format chosen from the worker's offer (JSON if absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wire_format: Option<WireFormat>,
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -280,6 +361,11 @@ pub struct HeartbeatRequest {
 
     /// Uptime in seconds
     pub uptime_secs: u64,
+
+    /// Models declined after failing to run here (coordinator should not
+    /// route them to this worker until they drop off the list)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub declined_models: Vec<String>,
 }
 
 /// Worker status
@@ -316,6 +402,11 @@ pub struct HeartbeatAckResponse {
     /// Any pending actions for the worker
     #[serde(default)]
     pub pending_actions: Vec<PendingAction>,
+
+    /// Tasks the coordinator reassigned away from this worker (e.g. because
+    /// it ran too slowly); the worker should stop working on them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reclaimed_tasks: Vec<String>,
 }
 
 /// Actions the coordinator wants the worker to take
@@ -360,13 +451,14 @@ pub struct TaskAssignmentMessage {
     /// Unique task ID
     pub task_id: String,
 
-    /// Block ID (for AI4All block assignments)
-    #[serde(default)]
-    pub block_id: Option<String>,
+    /// Block ID (for AI4All block assignments); a malformed value is
+    /// logged and dropped
+    #[serde(default, deserialize_with = "super::ids::lenient")]
+    pub block_id: Option<BlockId>,
 
-    /// Day ID for this task
-    #[serde(default)]
-    pub day_id: Option<String>,
+    /// Day ID for this task; a malformed value is logged and dropped
+    #[serde(default, deserialize_with = "super::ids::lenient")]
+    pub day_id: Option<DayId>,
 
     /// Task priority
     #[serde(default)]
@@ -393,10 +485,47 @@ pub struct TaskAssignmentMessage {
     /// Maximum execution time (seconds)
     #[serde(default = "default_timeout")]
     pub timeout_secs: u32,
+
+    /// Work group the task runs on; held until the group has quorum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+
+    /// Send generated tokens as TASK_PROGRESS messages while the task runs
+    /// (honored by workers advertising `supports_streaming`)
+    #[serde(default)]
+    pub stream: bool,
+
+    /// Tags a worker must carry (see `RegisterRequest::tags`) to run the task
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_tags: Vec<String>,
 }
 
 fn default_timeout() -> u32 { 300 } // 5 minutes
 
+/// What the next pipeline stage needs from the coordinator's original
+/// assignment to run its part of the task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineTask {
+    /// Model the task was assigned to
+    pub model_id: String,
+
+    /// Generation parameters of the original input, if it generates text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<GenerationParams>,
+
+    /// Task priority
+    #[serde(default)]
+    pub priority: TaskPriority,
+
+    /// Task deadline (if any)
+    #[serde(default)]
+    pub deadline: Option<DateTime<Utc>>,
+
+    /// Maximum execution time (seconds)
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u32,
+}
+
 /// Task result submission
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskResultMessage {
@@ -419,6 +548,23 @@ pub struct TaskResultMessage {
 
     /// Execution metrics
     pub metrics: TaskMetrics,
+
+    /// Delivery ID the coordinator echoes in `TASK_RESULT_ACK`; set only
+    /// when result acknowledgment is required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
+}
+
+/// Text generated by a streaming task since its previous progress message.
+/// The concatenated deltas match the final result's text, before any
+/// post-processing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskProgressMessage {
+    /// Task ID this output belongs to
+    pub task_id: String,
+
+    /// Newly generated text
+    pub delta: String,
 }
 
 /// Task error details
@@ -481,6 +627,17 @@ pub struct TaskCancelMessage {
     pub force: bool,
 }
 
+/// Coordinator receipt for a task result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskResultAckMessage {
+    /// `result_id` of the acknowledged result
+    pub result_id: String,
+
+    /// Task the result was for
+    #[serde(default)]
+    pub task_id: String,
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Status & Control Messages
 // ─────────────────────────────────────────────────────────────────
@@ -499,6 +656,17 @@ pub struct StatusUpdateMessage {
     pub reason: Option<String>,
 }
 
+/// Replacement for the capabilities sent at registration (e.g. a backend
+/// was disabled or recovered)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitiesUpdateMessage {
+    /// Worker ID
+    pub worker_id: String,
+
+    /// Current capabilities
+    pub capabilities: WorkerCapabilities,
+}
+
 /// Configuration update from coordinator
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigUpdateMessage {
@@ -569,6 +737,18 @@ pub struct PeerDirectoryMessage {
     pub peers: Vec<PeerDirectoryEntry>,
 }
 
+/// Worker asks the coordinator to send the peer directory again, e.g.
+/// because the last one had entries it couldn't use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerDirectoryRequestMessage {
+    /// Worker ID
+    pub worker_id: String,
+
+    /// Entries of the last directory that couldn't be parsed
+    #[serde(default)]
+    pub malformed_entries: u32,
+}
+
 /// A single peer entry in the directory
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeerDirectoryEntry {
@@ -641,6 +821,14 @@ pub struct GroupUpdateMessage {
     pub disbanded: bool,
 }
 
+/// A compressed message: the JSON of the original, compressed with the
+/// envelope's algorithm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressedMessage {
+    #[serde(with = "base64_bytes")]
+    pub data: Vec<u8>,
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Peer-to-Peer Messages (Direct TCP between workers)
 // ─────────────────────────────────────────────────────────────────
@@ -654,11 +842,28 @@ pub enum PeerMessage {
     Hello {
         worker_id: String,
         capabilities: WorkerCapabilities,
+        /// Compression algorithms the sender accepts for large messages
+        #[serde(
+            default,
+            deserialize_with = "compression::deserialize_offer",
+            skip_serializing_if = "Vec::is_empty"
+        )]
+        compression: Vec<Compression>,
     },
 
     /// Acknowledgment of hello
     HelloAck {
         worker_id: String,
+        /// Compression chosen from the hello's offer, used both ways
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compression: Option<Compression>,
+    },
+
+    /// Another message, compressed with `compression`
+    Compressed {
+        compression: Compression,
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
     },
 
     // ─── Health / Status ────────────────────────────────────────
@@ -726,6 +931,9 @@ pub enum PeerMessage {
     /// Send tensor data to the next shard in the pipeline
     ShardInput {
         group_id: String,
+        /// Group task the activations belong to
+        #[serde(default)]
+        task_id: String,
         layer_start: u32,
         #[serde(with = "base64_bytes")]
         tensor_data: Vec<u8>,
@@ -734,9 +942,20 @@ pub enum PeerMessage {
     /// Receive tensor output from a shard
     ShardOutput {
         group_id: String,
+        #[serde(default)]
+        task_id: String,
+        /// `layer_start` of the input this answers
+        #[serde(default)]
+        layer_start: u32,
         layer_end: u32,
         #[serde(with = "base64_bytes")]
         tensor_data: Vec<u8>,
+        /// Why the shard couldn't run its layers (no tensor data then)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        /// The shard's backend can't run split layers at all
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        unsupported: bool,
     },
 
     // ─── Pipeline Collaboration ─────────────────────────────────
@@ -756,11 +975,31 @@ pub enum PeerMessage {
         output: TaskOutput,
     },
 
+    /// Partial output of a pipeline stage, streamed to the next stage as
+    /// it is produced. Only sent to peers that negotiated streaming.
+    PipelineChunk {
+        group_id: String,
+        stage: u32,
+        task_id: String,
+        /// Position in the task's stream, from 0
+        seq: u64,
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+        /// Last chunk of the stream
+        is_final: bool,
+        /// The task the stream belongs to; sent with the first chunk only
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        task: Option<PipelineTask>,
+    },
+
     // ─── Group Coordination ─────────────────────────────────────
     /// Join a work group
     GroupJoin {
         group_id: String,
         role: String,
+        /// Whether the sender accepts streamed pipeline chunks
+        #[serde(default)]
+        streaming: bool,
     },
 
     /// Leave a work group
@@ -781,6 +1020,7 @@ impl PeerMessage {
         match self {
             PeerMessage::Hello { .. } => "HELLO",
             PeerMessage::HelloAck { .. } => "HELLO_ACK",
+            PeerMessage::Compressed { .. } => "COMPRESSED",
             PeerMessage::Ping { .. } => "PING",
             PeerMessage::Pong { .. } => "PONG",
             PeerMessage::PeerStatus { .. } => "PEER_STATUS",
@@ -795,6 +1035,7 @@ impl PeerMessage {
             PeerMessage::ShardOutput { .. } => "SHARD_OUTPUT",
             PeerMessage::PipelineInput { .. } => "PIPELINE_INPUT",
             PeerMessage::PipelineOutput { .. } => "PIPELINE_OUTPUT",
+            PeerMessage::PipelineChunk { .. } => "PIPELINE_CHUNK",
             PeerMessage::GroupJoin { .. } => "GROUP_JOIN",
             PeerMessage::GroupLeave { .. } => "GROUP_LEAVE",
             PeerMessage::GroupSync { .. } => "GROUP_SYNC",
@@ -852,6 +1093,108 @@ impl MessageEnvelope {
     pub fn from_json_bytes(bytes: &[u8]) -> Result<Self, serde_json::Error> {
         serde_json::from_slice(bytes)
     }
+
+    /// Serialize to MessagePack
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        codec::to_msgpack(self)
+    }
+
+    /// Deserialize from MessagePack bytes
+    pub fn from_msgpack_bytes(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        codec::from_msgpack(bytes)
+    }
+
+    /// Serialize to JSON, compressing a large payload with `compression`
+    /// (see [`Compression::compress_if_worthwhile`])
+    pub fn to_wire_json(&self, compression: Option<Compression>) -> io::Result<String> {
+        if let Some(compression) = compression {
+            let payload = serde_json::to_vec(&self.payload)?;
+            if let Some(envelope) = self.compressed(compression, &payload)? {
+                return Ok(serde_json::to_string(&envelope)?);
+            }
+        }
+        Ok(self.to_json()?)
+    }
+
+    /// Serialize to MessagePack, compressing a large payload with
+    /// `compression`. The compressed data is the payload's MessagePack.
+    pub fn to_wire_msgpack(&self, compression: Option<Compression>) -> io::Result<Vec<u8>> {
+        if let Some(compression) = compression {
+            let payload = codec::to_msgpack(&self.payload).map_err(invalid_data)?;
+            if let Some(envelope) = self.compressed(compression, &payload)? {
+                return envelope.to_msgpack().map_err(invalid_data);
+            }
+        }
+        self.to_msgpack().map_err(invalid_data)
+    }
+
+    /// This envelope with `payload` (its serialized payload) compressed, if
+    /// that's worthwhile
+    fn compressed(&self, compression: Compression, payload: &[u8]) -> io::Result<Option<Self>> {
+        Ok(compression.compress_if_worthwhile(payload)?.map(|data| MessageEnvelope {
+            id: self.id,
+            timestamp: self.timestamp,
+            version: self.version,
+            encoding: Some(compression),
+            payload: Message::Compressed(CompressedMessage { data }),
+        }))
+    }
+
+    /// Replace a compressed payload with the message it holds, which is in
+    /// `format` like the envelope was
+    pub fn decompressed(self, format: WireFormat) -> io::Result<Self> {
+        let Some(compression) = self.encoding else {
+            return Ok(self);
+        };
+        let Message::Compressed(compressed) = self.payload else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("compressed envelope carries a {} message", self.payload.type_name()),
+            ));
+        };
+        let data = compression.decompress(&compressed.data)?;
+        let payload: Message = match format {
+            WireFormat::Json => serde_json::from_slice(&data)?,
+            WireFormat::Msgpack => codec::from_msgpack(&data).map_err(invalid_data)?,
+        };
+        if let Message::Compressed(_) = payload {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "nested compressed message"));
+        }
+        Ok(MessageEnvelope { encoding: None, payload, ..self })
+    }
+}
+
+fn invalid_data<E: std::error::Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+impl PeerMessage {
+    /// Serialize for the mesh, compressing a large message with
+    /// `compression` (see [`Compression::compress_if_worthwhile`])
+    pub fn to_wire(&self, compression: Option<Compression>) -> io::Result<Vec<u8>> {
+        let json = serde_json::to_vec(self)?;
+        if let Some(compression) = compression {
+            if let Some(data) = compression.compress_if_worthwhile(&json)? {
+                return Ok(serde_json::to_vec(&PeerMessage::Compressed { compression, data })?);
+            }
+        }
+        Ok(json)
+    }
+
+    /// Parse a mesh frame, decompressing a compressed message
+    pub fn from_wire(bytes: &[u8]) -> io::Result<Self> {
+        match serde_json::from_slice(bytes)? {
+            PeerMessage::Compressed { compression, data } => {
+                match serde_json::from_slice(&compression.decompress(&data)?)? {
+                    PeerMessage::Compressed { .. } => {
+                        Err(io::Error::new(io::ErrorKind::InvalidData, "nested compressed message"))
+                    }
+                    msg => Ok(msg),
+                }
+            }
+            msg => Ok(msg),
+        }
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -877,9 +1220,12 @@ mod tests {
                 gpu_memory_mb: None,
                 max_context_length: 4096,
                 worker_version: "0.1.0".to_string(),
+                supports_streaming: false,
             },
             tags: vec!["test".to_string()],
             auth_token: None,
+            compression: vec![],
+            wire_formats: vec![],
         });
 
         let envelope = MessageEnvelope::new(msg);
@@ -906,6 +1252,7 @@ mod tests {
             active_tasks: vec![],
             completed_task_count: 0,
             uptime_secs: 3600,
+            declined_models: vec![],
         });
 
         let envelope = MessageEnvelope::new(msg);
@@ -931,8 +1278,8 @@ mod tests {
 
         let msg = Message::TaskAssignment(TaskAssignmentMessage {
             task_id: "task-123".to_string(),
-            block_id: Some("block-456".to_string()),
-            day_id: Some("2026-01-30".to_string()),
+            block_id: Some("block-456".parse().unwrap()),
+            day_id: Some("2026-01-30".parse().unwrap()),
             priority: TaskPriority::High,
             deadline: None,
             model_id: "llama-7b".to_string(),
@@ -940,6 +1287,9 @@ mod tests {
             is_canary: false,
             expected_hash: None,
             timeout_secs: 300,
+            group_id: None,
+            stream: false,
+            required_tags: vec![],
         });
 
         let envelope = MessageEnvelope::new(msg);
@@ -948,6 +1298,21 @@ mod tests {
         assert!(json.contains("TASK_ASSIGNMENT"));
         assert!(json.contains("task-123"));
         assert!(json.contains("llama-7b"));
+        assert!(json.contains("\"day_id\":\"2026-01-30\""));
+    }
+
+    #[test]
+    fn test_task_assignment_malformed_ids_dropped() {
+        let json = serde_json::json!({
+            "task_id": "task-123",
+            "block_id": "block_3_1",
+            "day_id": "30/01/2026",
+            "model_id": "llama-7b",
+            "input": { "task_type": "DEBUG", "op": "echo", "text": "hi" },
+        });
+        let assignment: TaskAssignmentMessage = serde_json::from_value(json).unwrap();
+        assert_eq!(assignment.block_id.unwrap().to_string(), "block_3_1");
+        assert!(assignment.day_id.is_none());
     }
 
     #[test]
@@ -976,9 +1341,12 @@ mod tests {
                 gpu_memory_mb: None,
                 max_context_length: 4096,
                 worker_version: "0.1.0".to_string(),
+                supports_streaming: false,
             },
             tags: vec![],
             auth_token: None,
+            compression: vec![],
+            wire_formats: vec![],
         });
 
         assert_eq!(msg.type_name(), "REGISTER");
@@ -1001,4 +1369,289 @@ mod tests {
         assert!(json.contains("ERROR"));
         assert!(json.contains("AUTH_FAILED"));
     }
+
+    #[test]
+    fn test_task_result_ack_roundtrip() {
+        let json = r#"{"type": "TASK_RESULT_ACK", "id": "00000000-0000-0000-0000-000000000000",
+            "timestamp": "2026-01-01T00:00:00Z", "version": {"major": 1, "minor": 0, "patch": 0},
+            "result_id": "r-1", "task_id": "t-1"}"#;
+        let envelope = MessageEnvelope::from_json(json).unwrap();
+        match envelope.payload {
+            Message::TaskResultAck(ack) => {
+                assert_eq!(ack.result_id, "r-1");
+                assert_eq!(ack.task_id, "t-1");
+            }
+            other => panic!("Expected TASK_RESULT_ACK, got {}", other.type_name()),
+        }
+        assert!(Message::TYPE_NAMES.contains(&"TASK_RESULT_ACK"));
+    }
+
+    #[test]
+    fn test_capabilities_update_roundtrip() {
+        let capabilities = WorkerCapabilities {
+            supported_tasks: vec![TaskType::TextCompletion, TaskType::Embeddings],
+            max_concurrent_tasks: 2,
+            available_memory_mb: 16384,
+            gpu_available: true,
+            gpu_device: Some("RTX 4090".to_string()),
+            gpu_memory_mb: Some(24576),
+            max_context_length: 8192,
+            worker_version: "0.1.0".to_string(),
+            supports_streaming: false,
+        };
+        let msg = Message::CapabilitiesUpdate(CapabilitiesUpdateMessage {
+            worker_id: "worker-1".to_string(),
+            capabilities: capabilities.clone(),
+        });
+        assert_eq!(msg.type_name(), "CAPABILITIES_UPDATE");
+        assert!(msg.is_request());
+        assert!(Message::TYPE_NAMES.contains(&"CAPABILITIES_UPDATE"));
+
+        let json = MessageEnvelope::new(msg).to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["type"], "CAPABILITIES_UPDATE");
+        assert_eq!(value["worker_id"], "worker-1");
+        assert_eq!(value["capabilities"]["gpu_device"], "RTX 4090");
+
+        match MessageEnvelope::from_json(&json).unwrap().payload {
+            Message::CapabilitiesUpdate(update) => {
+                assert_eq!(update.worker_id, "worker-1");
+                assert_eq!(update.capabilities, capabilities);
+            }
+            other => panic!("Expected CAPABILITIES_UPDATE, got {}", other.type_name()),
+        }
+    }
+
+    #[test]
+    fn test_task_progress_roundtrip() {
+        let progress = TaskProgressMessage {
+            task_id: "t-1".to_string(),
+            delta: " world".to_string(),
+        };
+        let msg = Message::TaskProgress(progress.clone());
+        assert_eq!(msg.type_name(), "TASK_PROGRESS");
+        assert!(msg.is_request());
+        assert!(Message::TYPE_NAMES.contains(&"TASK_PROGRESS"));
+
+        let json = MessageEnvelope::new(msg).to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["type"], "TASK_PROGRESS");
+        assert_eq!(value["delta"], " world");
+
+        match MessageEnvelope::from_json(&json).unwrap().payload {
+            Message::TaskProgress(decoded) => assert_eq!(decoded, progress),
+            other => panic!("Expected TASK_PROGRESS, got {}", other.type_name()),
+        }
+    }
+
+    fn large_task_data() -> PeerMessage {
+        PeerMessage::TaskData {
+            task_id: "t-1".to_string(),
+            data: (0..64 * 1024).map(|i| (i % 7) as u8).collect(),
+        }
+    }
+
+    #[test]
+    fn test_peer_message_wire_round_trip() {
+        let msg = large_task_data();
+        let plain = msg.to_wire(None).unwrap();
+        let compressed = msg.to_wire(Some(Compression::Gzip)).unwrap();
+        assert!(compressed.len() < plain.len() / 4);
+
+        let value: serde_json::Value = serde_json::from_slice(&compressed).unwrap();
+        assert_eq!(value["type"], "COMPRESSED");
+        assert_eq!(value["compression"], "gzip");
+
+        for wire in [&plain, &compressed] {
+            match PeerMessage::from_wire(wire).unwrap() {
+                PeerMessage::TaskData { task_id, data } => {
+                    assert_eq!(task_id, "t-1");
+                    assert_eq!(data, (0..64 * 1024).map(|i| (i % 7) as u8).collect::<Vec<u8>>());
+                }
+                other => panic!("Expected TASK_DATA, got {}", other.type_name()),
+            }
+        }
+
+        // Small control messages are never compressed
+        let ping = PeerMessage::Ping { seq: 1 };
+        assert_eq!(ping.to_wire(Some(Compression::Gzip)).unwrap(), ping.to_wire(None).unwrap());
+    }
+
+    #[test]
+    fn test_envelope_wire_round_trip() {
+        let progress = TaskProgressMessage {
+            task_id: "t-1".to_string(),
+            delta: "token ".repeat(4096),
+        };
+        let envelope = MessageEnvelope::new(Message::TaskProgress(progress.clone()));
+
+        let plain = envelope.to_wire_json(None).unwrap();
+        let compressed = envelope.to_wire_json(Some(Compression::Gzip)).unwrap();
+        assert!(compressed.len() < plain.len() / 4);
+
+        let value: serde_json::Value = serde_json::from_str(&compressed).unwrap();
+        assert_eq!(value["type"], "COMPRESSED");
+        assert_eq!(value["encoding"], "gzip");
+        assert_eq!(value["id"], envelope.id.to_string());
+        assert!(serde_json::from_str::<serde_json::Value>(&plain).unwrap().get("encoding").is_none());
+
+        for wire in [&plain, &compressed] {
+            let decoded = MessageEnvelope::from_json(wire).unwrap().decompressed(WireFormat::Json).unwrap();
+            assert_eq!(decoded.id, envelope.id);
+            assert!(decoded.encoding.is_none());
+            match decoded.payload {
+                Message::TaskProgress(decoded) => assert_eq!(decoded, progress),
+                other => panic!("Expected TASK_PROGRESS, got {}", other.type_name()),
+            }
+        }
+
+        // A compressed envelope must carry a COMPRESSED payload
+        let mut bogus = MessageEnvelope::new(Message::TaskProgress(progress));
+        bogus.encoding = Some(Compression::Gzip);
+        assert!(bogus.decompressed(WireFormat::Json).is_err());
+    }
+
+    /// One message of each type, as JSON payloads
+    fn sample_payloads() -> Vec<serde_json::Value> {
+        use serde_json::json;
+
+        let caps = json!({
+            "supported_tasks": ["TEXT_COMPLETION", "EMBEDDINGS"],
+            "max_concurrent_tasks": 2,
+            "available_memory_mb": 16384,
+            "gpu_available": true,
+            "gpu_device": "RTX 4090",
+            "gpu_memory_mb": 24576,
+            "max_context_length": 8192,
+            "worker_version": "0.1.0",
+            "supports_streaming": true
+        });
+        let members = json!([
+            { "worker_id": "w-1", "role": "shard", "shard_index": 0 },
+            { "worker_id": "w-2", "role": "stage", "pipeline_stage": 1 }
+        ]);
+        vec![
+            json!({
+                "type": "REGISTER", "worker_id": "w-1", "name": "Worker", "capabilities": caps,
+                "tags": ["eu"], "auth_token": "tok", "compression": ["gzip"],
+                "wire_formats": ["msgpack", "json"]
+            }),
+            json!({
+                "type": "REGISTER_ACK", "success": true, "worker_id": "w-1", "session_token": "s",
+                "heartbeat_interval_secs": 30, "coordinator_version": { "major": 1, "minor": 2, "patch": 3 },
+                "compression": "gzip", "wire_format": "msgpack"
+            }),
+            json!({
+                "type": "HEARTBEAT", "worker_id": "w-1", "status": "BUSY",
+                "resources": {
+                    "cpu_percent": 12.5, "memory_used_mb": 2048, "memory_available_mb": 8192,
+                    "gpu_percent": 50.0, "active_threads": 8
+                },
+                "active_tasks": ["t-1"], "completed_task_count": 3, "uptime_secs": 3600,
+                "declined_models": ["big-model"]
+            }),
+            json!({
+                "type": "HEARTBEAT_ACK", "accepted": true, "next_heartbeat": "2026-01-30T12:00:00.250Z",
+                "pending_actions": [
+                    { "action": "CANCEL_TASK", "task_id": "t-1" },
+                    { "action": "UPDATE_CONFIG", "config": { "max_tasks": 2, "ratio": 0.5, "tags": null } }
+                ],
+                "reclaimed_tasks": ["t-2"]
+            }),
+            json!({
+                "type": "TASK_ASSIGNMENT", "task_id": "t-1", "block_id": "block_3_1", "day_id": "2026-01-30",
+                "priority": "HIGH", "deadline": "2026-01-30T13:00:00Z", "model_id": "m",
+                "input": { "task_type": "TEXT_COMPLETION", "prompt": "Hi", "params": { "max_tokens": 16 } },
+                "is_canary": true, "expected_hash": "abc", "timeout_secs": 60, "group_id": "g-1",
+                "stream": true, "required_tags": ["gpu"]
+            }),
+            json!({
+                "type": "TASK_RESULT", "task_id": "t-1", "worker_id": "w-1", "success": true,
+                "output": {
+                    "task_type": "TEXT_COMPLETION", "text": "Hello", "finish_reason": "stop",
+                    "usage": { "prompt_tokens": 1, "completion_tokens": 2, "total_tokens": 3 }
+                },
+                "metrics": {
+                    "queue_time_ms": 1, "execution_time_ms": 2, "total_time_ms": 3,
+                    "tokens_processed": 3, "tokens_per_second": 1.5
+                },
+                "result_id": "r-1"
+            }),
+            json!({ "type": "TASK_PROGRESS", "task_id": "t-1", "delta": "Hel" }),
+            json!({ "type": "TASK_CANCEL", "task_id": "t-1", "reason": "timeout", "force": true }),
+            json!({ "type": "TASK_RESULT_ACK", "result_id": "r-1", "task_id": "t-1" }),
+            json!({ "type": "STATUS_UPDATE", "worker_id": "w-1", "status": "DRAINING", "reason": "update" }),
+            json!({ "type": "CAPABILITIES_UPDATE", "worker_id": "w-1", "capabilities": caps }),
+            json!({ "type": "CONFIG_UPDATE", "config": { "nested": [1, -2, 3.25, "x"] }, "persist": true }),
+            json!({ "type": "SHUTDOWN", "worker_id": "w-1", "reason": "bye", "graceful": true, "abandoned_tasks": ["t-3"] }),
+            json!({
+                "type": "ERROR", "code": "E", "message": "failed", "fatal": true,
+                "related_message_id": "5b0f0e6e-6c2a-4b51-9a4e-1f8d2f6f7a10"
+            }),
+            json!({ "type": "PEER_DISCOVER", "worker_id": "w-1", "listen_addr": "10.0.0.1:4000", "capabilities": caps }),
+            json!({
+                "type": "PEER_DIRECTORY",
+                "peers": [{
+                    "worker_id": "w-2", "name": "Peer", "listen_addr": "10.0.0.2:4000",
+                    "capabilities": caps, "status": "READY"
+                }]
+            }),
+            json!({ "type": "PEER_DIRECTORY_REQUEST", "worker_id": "w-1", "malformed_entries": 2 }),
+            json!({
+                "type": "GROUP_ASSIGNED", "group_id": "g-1",
+                "purpose": { "type": "MODEL_SHARD", "model_id": "m", "total_shards": 2 },
+                "members": members
+            }),
+            json!({ "type": "GROUP_UPDATE", "group_id": "g-1", "members": members, "disbanded": true }),
+            json!({ "type": "COMPRESSED", "data": "H4sIAAAAAAAA/w==" }),
+        ]
+    }
+
+    #[test]
+    fn test_msgpack_round_trip_matches_json() {
+        let mut covered = Vec::new();
+        for sample in sample_payloads() {
+            let payload: Message = serde_json::from_value(sample.clone()).unwrap();
+            assert_eq!(payload.type_name(), sample["type"]);
+            covered.push(payload.type_name());
+            let envelope = MessageEnvelope::new(payload);
+            let expected = serde_json::to_value(&envelope).unwrap();
+
+            let via_json = MessageEnvelope::from_json(&envelope.to_json().unwrap()).unwrap();
+            let bytes = envelope.to_msgpack().unwrap();
+            let via_msgpack = MessageEnvelope::from_msgpack_bytes(&bytes).unwrap();
+
+            assert_eq!(serde_json::to_value(&via_json).unwrap(), expected, "{}", sample["type"]);
+            assert_eq!(serde_json::to_value(&via_msgpack).unwrap(), expected, "{}", sample["type"]);
+            assert_eq!(via_msgpack.id, envelope.id);
+            assert_eq!(via_msgpack.timestamp, envelope.timestamp);
+        }
+        covered.sort_unstable();
+        let mut all = Message::TYPE_NAMES.to_vec();
+        all.sort_unstable();
+        assert_eq!(covered, all);
+    }
+
+    #[test]
+    fn test_compressed_msgpack_round_trip() {
+        let progress = TaskProgressMessage {
+            task_id: "t-1".to_string(),
+            delta: "token ".repeat(4096),
+        };
+        let envelope = MessageEnvelope::new(Message::TaskProgress(progress.clone()));
+        let plain = envelope.to_wire_msgpack(None).unwrap();
+        let compressed = envelope.to_wire_msgpack(Some(Compression::Gzip)).unwrap();
+        assert!(compressed.len() < plain.len() / 4);
+
+        for wire in [&plain, &compressed] {
+            let decoded = MessageEnvelope::from_msgpack_bytes(wire)
+                .unwrap()
+                .decompressed(WireFormat::Msgpack)
+                .unwrap();
+            match decoded.payload {
+                Message::TaskProgress(decoded) => assert_eq!(decoded, progress),
+                other => panic!("Expected TASK_PROGRESS, got {}", other.type_name()),
+            }
+        }
+    }
 }
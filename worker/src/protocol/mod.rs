@@ -1,10 +1,18 @@
 //! Protocol module for coordinator communication
 //!
 //! Defines the message types and serialization for the worker-coordinator protocol.
-//! The protocol uses JSON over WebSocket with versioning support.
+//! The protocol uses JSON (or, once negotiated, MessagePack) over
+//! WebSocket with versioning support, and optional compression of large
+//! messages.
 
+mod codec;
+mod compression;
+mod ids;
 mod messages;
 mod version;
 
+pub use codec::{from_msgpack, WireFormat};
+pub use compression::Compression;
+pub use ids::{BlockId, DayId};
 pub use messages::*;
 pub use version::*;
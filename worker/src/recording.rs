@@ -0,0 +1,256 @@
+//! Session recording for debugging
+//!
+//! With `debug.record_session` set to a directory, the worker appends every
+//! inbound coordinator frame, outbound task result and peer event to a
+//! `session-<time>.jsonl` file there, one timestamped JSON object per line.
+//! `ai4all-worker replay <file>` feeds the recorded coordinator frames back
+//! through the client's message handlers to reproduce what the worker saw.
+//!
+//! Values under credential-like keys (`session_token`, `api_key`, ...) are
+//! replaced with `[REDACTED]` before anything is written.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::error::{Error, Result};
+use crate::peer::PeerEvent;
+
+/// Placeholder written in place of secret values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Keys whose values are always secret
+const SECRET_KEYS: &[&str] = &["token", "secret", "password", "authorization", "api_key", "apikey"];
+
+/// Key suffixes marking secret values (`session_token`, `auth_token`, ...)
+const SECRET_SUFFIXES: &[&str] = &["_token", "_secret", "_password", "_api_key", "_private_key"];
+
+// ─────────────────────────────────────────────────────────────────
+// Entries
+// ─────────────────────────────────────────────────────────────────
+
+/// What a recorded line holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    /// Frame received from the coordinator
+    Inbound,
+    /// Task result sent to the coordinator
+    Outbound,
+    /// Event from the peer mesh
+    Peer,
+}
+
+/// One line of a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordEntry {
+    pub timestamp: DateTime<Utc>,
+    pub kind: RecordKind,
+    /// The message as JSON; frames that weren't valid JSON are kept as a
+    /// string so malformed input replays too
+    pub data: Value,
+}
+
+impl RecordEntry {
+    /// The coordinator frame to replay, for inbound entries
+    pub fn frame(&self) -> Option<String> {
+        match (self.kind, &self.data) {
+            (RecordKind::Inbound, Value::String(raw)) => Some(raw.clone()),
+            (RecordKind::Inbound, value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Recorder
+// ─────────────────────────────────────────────────────────────────
+
+/// Appends entries to a session file
+#[derive(Debug)]
+pub struct SessionRecorder {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl SessionRecorder {
+    /// Start a new session file in `dir`, creating the directory if needed
+    pub fn create(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let name = format!("session-{}.jsonl", Utc::now().format("%Y%m%d-%H%M%S%.3f"));
+        let path = dir.join(name);
+        let file = OpenOptions::new().create_new(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Path of the session file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a frame received from the coordinator
+    pub fn record_inbound(&self, frame: &[u8]) {
+        let data = serde_json::from_slice(frame)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(frame).into_owned()));
+        self.record(RecordKind::Inbound, data);
+    }
+
+    /// Record a message sent to the coordinator
+    pub fn record_outbound<T: Serialize>(&self, message: &T) {
+        match serde_json::to_value(message) {
+            Ok(data) => self.record(RecordKind::Outbound, data),
+            Err(e) => warn!(error = %e, "Failed to serialize message for session recording"),
+        }
+    }
+
+    /// Record a peer mesh event
+    pub fn record_peer_event(&self, event: &PeerEvent) {
+        let data = match event {
+            PeerEvent::Connected { worker_id } => json!({
+                "event": "connected",
+                "worker_id": worker_id,
+            }),
+            PeerEvent::Disconnected { worker_id, reason } => json!({
+                "event": "disconnected",
+                "worker_id": worker_id,
+                "reason": reason,
+            }),
+            PeerEvent::MessageReceived { from, message } => json!({
+                "event": "message",
+                "from": from,
+                "message": message,
+            }),
+            PeerEvent::Error { worker_id, error } => json!({
+                "event": "error",
+                "worker_id": worker_id,
+                "error": error,
+            }),
+            PeerEvent::ListenerReady { addr } => json!({
+                "event": "listener_ready",
+                "addr": addr.to_string(),
+            }),
+            PeerEvent::Stale { worker_id, missed_pings } => json!({
+                "event": "stale",
+                "worker_id": worker_id,
+                "missed_pings": missed_pings,
+            }),
+        };
+        self.record(RecordKind::Peer, data);
+    }
+
+    fn record(&self, kind: RecordKind, mut data: Value) {
+        redact(&mut data);
+        let entry = RecordEntry {
+            timestamp: Utc::now(),
+            kind,
+            data,
+        };
+        let line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize session recording entry");
+                return;
+            }
+        };
+        if let Err(e) = writeln!(self.file.lock(), "{}", line) {
+            warn!(path = %self.path.display(), error = %e, "Failed to write session recording");
+        }
+    }
+}
+
+/// Replace the values of credential-like keys, at any depth
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEYS.contains(&key.as_str()) || SECRET_SUFFIXES.iter().any(|s| key.ends_with(s))
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Reading
+// ─────────────────────────────────────────────────────────────────
+
+/// Load a session file
+pub fn read_recording(path: &Path) -> Result<Vec<RecordEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| {
+            Error::Internal(format!("{}:{}: invalid recording entry: {}", path.display(), i + 1, e))
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_nested_secrets() {
+        let mut value = json!({
+            "type": "REGISTER_ACK",
+            "payload": {
+                "session_token": "abc123",
+                "worker_id": "w1",
+                "nested": [{ "api_key": "sk-1", "max_tokens": 256 }],
+                "auth_token": null,
+            }
+        });
+        redact(&mut value);
+        assert_eq!(value["payload"]["session_token"], REDACTED);
+        assert_eq!(value["payload"]["nested"][0]["api_key"], REDACTED);
+        // Token counts aren't secrets, and absent values stay absent
+        assert_eq!(value["payload"]["nested"][0]["max_tokens"], 256);
+        assert_eq!(value["payload"]["auth_token"], Value::Null);
+        assert_eq!(value["payload"]["worker_id"], "w1");
+    }
+
+    #[test]
+    fn test_recording_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = SessionRecorder::create(dir.path()).unwrap();
+        recorder.record_inbound(br#"{"type":"HEARTBEAT_ACK","payload":{"session_token":"s"}}"#);
+        recorder.record_inbound(b"not json");
+        recorder.record_peer_event(&PeerEvent::Connected { worker_id: "w2".to_string() });
+
+        let entries = read_recording(recorder.path()).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].frame().as_deref(), Some("not json"));
+        assert!(entries[0].frame().unwrap().contains(REDACTED));
+        assert_eq!(entries[2].kind, RecordKind::Peer);
+        assert_eq!(entries[2].frame(), None);
+    }
+}
@@ -0,0 +1,326 @@
+//! Task execution sandbox
+//!
+//! Operator limits on what crawl and training tasks may touch, configured
+//! under `[sandbox]`:
+//! - an egress allowlist of domains and CIDR ranges. The crawler's HTTP
+//!   client checks it before each request, on every redirect and on the
+//!   addresses a hostname resolves to, so a permitted name can't be pointed
+//!   at a forbidden network.
+//! - a write scope confining files written by task handlers to
+//!   `storage.data_dir`.
+//!
+//! Violations are reported as errors naming the blocked host or path rather
+//! than silently skipped.
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use hyper::client::connect::dns::Name;
+use ipnet::IpNet;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use url::{Host, Url};
+
+use crate::error::{Error, Result};
+
+/// Redirects followed per request, as with reqwest's default policy
+const MAX_REDIRECTS: usize = 10;
+
+// ─────────────────────────────────────────────────────────────────
+// Egress allowlist
+// ─────────────────────────────────────────────────────────────────
+
+/// A connection the egress allowlist doesn't permit
+#[derive(Debug, Clone)]
+pub struct EgressDenied(pub String);
+
+impl fmt::Display for EgressDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not on the sandbox egress allowlist", self.0)
+    }
+}
+
+impl std::error::Error for EgressDenied {}
+
+/// Whether `err` or one of its sources is an [`EgressDenied`]
+pub fn is_egress_denied(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(err);
+    while let Some(e) = current {
+        if e.is::<EgressDenied>() {
+            return true;
+        }
+        current = e.source();
+    }
+    false
+}
+
+/// Hosts outbound task requests may reach. Empty means unrestricted.
+#[derive(Debug, Clone, Default)]
+pub struct EgressPolicy {
+    /// Allowed domains, subdomains included
+    domains: Vec<String>,
+    /// Allowed address ranges (single addresses as /32 or /128)
+    networks: Vec<IpNet>,
+}
+
+impl EgressPolicy {
+    /// Build a policy from `sandbox.egress_allowlist` entries: domains
+    /// (`example.com`), addresses (`10.0.0.5`) or CIDR ranges (`10.0.0.0/8`)
+    pub fn new(entries: &[String]) -> Result<Self> {
+        let mut policy = Self::default();
+        for entry in entries.iter().map(|e| e.trim()).filter(|e| !e.is_empty()) {
+            if entry.contains('/') {
+                let net = entry.parse::<IpNet>().map_err(|e| {
+                    Error::Config(format!("Invalid sandbox.egress_allowlist range '{}': {}", entry, e))
+                })?;
+                policy.networks.push(net.trunc());
+            } else if let Ok(ip) = entry.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>() {
+                policy.networks.push(IpNet::from(ip));
+            } else {
+                let domain = entry.trim_start_matches("*.").trim_matches('.').to_lowercase();
+                if domain.is_empty() || domain.contains(|c: char| c.is_whitespace() || c == ':') {
+                    return Err(Error::Config(format!(
+                        "Invalid sandbox.egress_allowlist entry '{}'",
+                        entry
+                    )));
+                }
+                policy.domains.push(domain);
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Whether every host is allowed
+    pub fn is_unrestricted(&self) -> bool {
+        self.domains.is_empty() && self.networks.is_empty()
+    }
+
+    fn domain_allowed(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_lowercase();
+        self.domains
+            .iter()
+            .any(|d| host == *d || host.ends_with(&format!(".{}", d)))
+    }
+
+    fn ip_allowed(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Check a URL before it is requested. A hostname outside the allowed
+    /// domains passes only if CIDR ranges are configured, in which case its
+    /// resolved addresses are checked when connecting.
+    pub fn check_url(&self, url: &Url) -> std::result::Result<(), EgressDenied> {
+        if self.is_unrestricted() {
+            return Ok(());
+        }
+        let allowed = match url.host() {
+            Some(Host::Domain(domain)) => self.domain_allowed(domain) || !self.networks.is_empty(),
+            Some(Host::Ipv4(ip)) => self.ip_allowed(IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => self.ip_allowed(IpAddr::V6(ip)),
+            None => false,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(EgressDenied(url.host_str().unwrap_or(url.as_str()).to_string()))
+        }
+    }
+
+    /// Enforce the policy on an HTTP client: redirects are checked like
+    /// the original URL, and hostnames resolve only to allowed addresses
+    pub fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if self.is_unrestricted() {
+            return builder;
+        }
+        let policy = self.clone();
+        let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+            if let Err(denied) = policy.check_url(attempt.url()) {
+                attempt.error(denied)
+            } else if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        });
+        builder
+            .redirect(redirects)
+            .dns_resolver(Arc::new(AllowlistResolver { policy: self.clone() }))
+    }
+}
+
+/// DNS resolver dropping addresses outside the allowlist for hostnames
+/// that aren't allowed by domain
+struct AllowlistResolver {
+    policy: EgressPolicy,
+}
+
+impl Resolve for AllowlistResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if policy.domain_allowed(&host) {
+                return Ok(Box::new(addrs.into_iter()) as Addrs);
+            }
+            let allowed: Vec<SocketAddr> = addrs.into_iter().filter(|a| policy.ip_allowed(a.ip())).collect();
+            if allowed.is_empty() {
+                return Err(Box::new(EgressDenied(host)) as Box<dyn std::error::Error + Send + Sync>);
+            }
+            Ok(Box::new(allowed.into_iter()) as Addrs)
+        })
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Write scope
+// ─────────────────────────────────────────────────────────────────
+
+/// Directory task handlers may write files under (`None` = anywhere)
+#[derive(Debug, Clone, Default)]
+pub struct WriteScope {
+    root: Option<PathBuf>,
+}
+
+impl WriteScope {
+    /// Confine writes to `root`
+    pub fn confined_to(root: impl Into<PathBuf>) -> Self {
+        Self { root: Some(root.into()) }
+    }
+
+    /// Resolve a path a task wants to write, rejecting it if it lies outside
+    /// the scope. Relative paths are taken relative to the scope root;
+    /// `..` components and symlinks in existing parents are resolved first.
+    pub fn check(&self, path: &Path) -> Result<PathBuf> {
+        let Some(root) = &self.root else {
+            return Ok(path.to_path_buf());
+        };
+        let root = resolve(root);
+        let target = resolve(&root.join(path));
+        if target.starts_with(&root) {
+            Ok(target)
+        } else {
+            Err(Error::SandboxViolation {
+                message: format!(
+                    "write to {} is outside the sandbox write scope {}",
+                    path.display(),
+                    root.display()
+                ),
+            })
+        }
+    }
+}
+
+/// `path` with `.`/`..` removed and the longest existing prefix
+/// canonicalized (following symlinks)
+fn resolve(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+
+    let mut existing = normalized.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest.iter().rev().fold(canonical, |acc, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return normalized,
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(entries: &[&str]) -> EgressPolicy {
+        EgressPolicy::new(&entries.iter().map(|e| e.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    fn allowed(policy: &EgressPolicy, url: &str) -> bool {
+        policy.check_url(&Url::parse(url).unwrap()).is_ok()
+    }
+
+    #[test]
+    fn test_domain_allowlist() {
+        let policy = policy(&["example.com"]);
+        assert!(allowed(&policy, "https://example.com/"));
+        assert!(allowed(&policy, "https://docs.EXAMPLE.com/a"));
+        assert!(!allowed(&policy, "https://notexample.com/"));
+        assert!(!allowed(&policy, "http://127.0.0.1:8080/"));
+        assert!(allowed(&EgressPolicy::default(), "http://127.0.0.1:8080/"));
+    }
+
+    #[test]
+    fn test_cidr_allowlist() {
+        let policy = policy(&["10.0.0.0/8", "192.168.1.7", "fd00::/8"]);
+        assert!(allowed(&policy, "http://10.1.2.3/"));
+        assert!(allowed(&policy, "http://192.168.1.7:9000/"));
+        assert!(allowed(&policy, "http://[fd00::1]/"));
+        assert!(!allowed(&policy, "http://192.168.1.8/"));
+        // Hostnames are checked once resolved
+        assert!(allowed(&policy, "http://internal.example/"));
+    }
+
+    #[test]
+    fn test_invalid_entries_rejected() {
+        for entry in ["10.0.0.0/33", "not a domain", "host:80"] {
+            assert!(EgressPolicy::new(&[entry.to_string()]).is_err(), "{} accepted", entry);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolved_address_outside_ranges_denied() {
+        let policy = policy(&["10.0.0.0/8"]);
+        let resolver = AllowlistResolver { policy };
+        let err = match resolver.resolve("localhost".parse().unwrap()).await {
+            Ok(_) => panic!("localhost resolved to an allowed address"),
+            Err(e) => e,
+        };
+        assert!(is_egress_denied(err.as_ref()));
+    }
+
+    #[test]
+    fn test_write_outside_scope_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let scope = WriteScope::confined_to(dir.path());
+
+        let inside = scope.check(Path::new("adapters/lora.safetensors")).unwrap();
+        assert!(inside.starts_with(dir.path().canonicalize().unwrap()));
+        assert!(scope.check(&dir.path().join("pages/../page.json")).is_ok());
+
+        for path in ["../escape.txt", "/etc/ai4all-test", "adapters/../../escape.txt"] {
+            let err = scope.check(Path::new(path)).unwrap_err();
+            assert!(matches!(err, Error::SandboxViolation { .. }), "{} allowed", path);
+        }
+        assert!(WriteScope::default().check(Path::new("/tmp/anywhere")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_out_of_scope_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+
+        let scope = WriteScope::confined_to(dir.path());
+        assert!(scope.check(Path::new("link/file.txt")).is_err());
+    }
+}
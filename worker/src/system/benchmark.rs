@@ -1,17 +1,24 @@
 //! Performance benchmarking system
 //!
-//! Provides CPU and memory benchmarks for capability assessment.
-//! Used for first-run experience and periodic health checks.
+//! Provides CPU and memory benchmarks for capability assessment, plus a
+//! GPU phase (with the `gpu` feature) when a compute-capable GPU is found.
+//! Used for first-run experience and periodic health checks. Every saved
+//! run is also appended to a JSON Lines history next to the results file,
+//! so runs can be compared over time.
 
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
-use tracing::{info, debug};
+use tracing::{info, debug, warn};
 
 use crate::error::{Error, Result};
 
+#[cfg(feature = "gpu")]
+use super::info::ESTIMATE_QUANTIZATION;
+
 // ─────────────────────────────────────────────────────────────────
 // Benchmark Results
 // ─────────────────────────────────────────────────────────────────
@@ -28,6 +35,10 @@ pub struct BenchmarkResults {
     /// Memory benchmark results
     pub memory: MemoryBenchmarkResult,
 
+    /// GPU benchmark results, when a compute-capable GPU was benchmarked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<GpuBenchmarkResult>,
+
     /// Overall compute score (0-1000)
     pub compute_score: u32,
 
@@ -73,6 +84,104 @@ pub struct MemoryBenchmarkResult {
     pub score: u32,
 }
 
+/// GFLOPS that earn the maximum GPU score
+const GPU_MAX_SCORE_GFLOPS: f64 = 20_000.0;
+
+/// Weights read per generated token for a 7B model at Q4 (GB)
+const Q4_7B_WEIGHTS_GB: f64 = 4.0;
+
+/// GPU benchmark results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuBenchmarkResult {
+    /// GPU the benchmark ran on
+    pub device_name: String,
+
+    /// Single-precision fused multiply-add throughput (GFLOPS)
+    #[serde(alias = "matmul_gflops")]
+    pub fma_gflops: f64,
+
+    /// Device-local copy bandwidth (GB/s)
+    pub vram_bandwidth_gbps: f64,
+
+    /// GPU score (0-1000)
+    pub score: u32,
+
+    /// Estimated tokens per second for a 7B Q4 model on this GPU
+    pub estimated_tokens_per_second: f32,
+}
+
+impl GpuBenchmarkResult {
+    /// Score a measurement. Token generation is bound by VRAM bandwidth, so
+    /// the TPS estimate comes from it, kept within a factor of two of
+    /// `anchor_tps` (the detected GPU's spec-based estimate) so a skewed
+    /// measurement can't run away.
+    pub fn from_measurement(
+        device_name: &str,
+        fma_gflops: f64,
+        vram_bandwidth_gbps: f64,
+        anchor_tps: u32,
+    ) -> Self {
+        let score = ((fma_gflops / GPU_MAX_SCORE_GFLOPS) * 1000.0).min(1000.0) as u32;
+        let measured_tps = (vram_bandwidth_gbps / Q4_7B_WEIGHTS_GB) as f32;
+        let anchor = anchor_tps as f32;
+        let estimated_tokens_per_second = if anchor > 0.0 {
+            measured_tps.clamp(anchor / 2.0, anchor * 2.0)
+        } else {
+            measured_tps
+        };
+
+        Self {
+            device_name: device_name.to_string(),
+            fma_gflops,
+            vram_bandwidth_gbps,
+            score,
+            estimated_tokens_per_second,
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Run Comparison
+// ─────────────────────────────────────────────────────────────────
+
+/// Compute score drop (percent) since the previous run worth a warning;
+/// usually thermal throttling or a driver change
+pub const COMPUTE_REGRESSION_WARN_PCT: f64 = 15.0;
+
+/// Percentage change of each headline figure between two runs (`None`
+/// when the previous value was zero)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkComparison {
+    pub compute_score_pct: Option<f64>,
+    pub tokens_per_second_pct: Option<f64>,
+    pub memory_read_pct: Option<f64>,
+    pub memory_write_pct: Option<f64>,
+}
+
+impl BenchmarkComparison {
+    /// Compare `current` against `previous`
+    pub fn between(previous: &BenchmarkResults, current: &BenchmarkResults) -> Self {
+        Self {
+            compute_score_pct: pct_change(previous.compute_score as f64, current.compute_score as f64),
+            tokens_per_second_pct: pct_change(
+                previous.estimated_tokens_per_second as f64,
+                current.estimated_tokens_per_second as f64,
+            ),
+            memory_read_pct: pct_change(previous.memory.seq_read_mbps, current.memory.seq_read_mbps),
+            memory_write_pct: pct_change(previous.memory.seq_write_mbps, current.memory.seq_write_mbps),
+        }
+    }
+
+    /// Whether the compute score dropped by more than `threshold_pct`
+    pub fn compute_regressed(&self, threshold_pct: f64) -> bool {
+        self.compute_score_pct.is_some_and(|pct| pct < -threshold_pct)
+    }
+}
+
+fn pct_change(previous: f64, current: f64) -> Option<f64> {
+    (previous != 0.0).then(|| (current - previous) / previous * 100.0)
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Benchmark Runner
 // ─────────────────────────────────────────────────────────────────
@@ -84,6 +193,9 @@ pub struct BenchmarkRunner {
 
     /// Benchmark results storage path
     results_path: Option<PathBuf>,
+
+    /// JSON Lines file each run is appended to
+    history_path: Option<PathBuf>,
 }
 
 impl BenchmarkRunner {
@@ -92,15 +204,24 @@ impl BenchmarkRunner {
         Self {
             iterations,
             results_path: None,
+            history_path: None,
         }
     }
 
-    /// Set the path to store benchmark results
+    /// Set the path to store benchmark results; runs are also appended to
+    /// the history next to it (see [`BenchmarkRunner::history_path_for`])
     pub fn with_results_path(mut self, path: PathBuf) -> Self {
+        self.history_path = Some(Self::history_path_for(&path));
         self.results_path = Some(path);
         self
     }
 
+    /// Set the history file runs are appended to
+    pub fn with_history_path(mut self, path: PathBuf) -> Self {
+        self.history_path = Some(path);
+        self
+    }
+
     /// Run all benchmarks
     pub fn run(&self) -> Result<BenchmarkResults> {
         info!(iterations = self.iterations, "Starting benchmarks");
@@ -122,14 +243,22 @@ impl BenchmarkRunner {
             "Memory benchmark complete"
         );
 
+        // Run GPU benchmarks when a compute-capable GPU is present
+        #[cfg(feature = "gpu")]
+        let gpu = self.run_gpu_benchmarks();
+        #[cfg(not(feature = "gpu"))]
+        let gpu: Option<GpuBenchmarkResult> = None;
+
         // Calculate overall score
-        let compute_score = self.calculate_compute_score(&cpu, &memory);
-        let estimated_tokens_per_second = self.estimate_tokens_per_second(compute_score);
+        let compute_score = self.calculate_compute_score(&cpu, &memory, gpu.as_ref());
+        let estimated_tokens_per_second =
+            self.estimate_tokens_per_second(compute_score, gpu.as_ref());
 
         let results = BenchmarkResults {
             timestamp: chrono::Utc::now(),
             cpu,
             memory,
+            gpu,
             compute_score,
             estimated_tokens_per_second,
             duration_secs: start.elapsed().as_secs_f32(),
@@ -146,6 +275,9 @@ impl BenchmarkRunner {
         if let Some(ref path) = self.results_path {
             self.save_results(&results, path)?;
         }
+        if let Some(ref path) = self.history_path {
+            Self::append_history(&results, path)?;
+        }
 
         Ok(results)
     }
@@ -336,20 +468,68 @@ impl BenchmarkRunner {
         elapsed.as_nanos() as f64 / accesses as f64
     }
 
+    /// Benchmark the best compute-capable GPU, if there is one
+    #[cfg(feature = "gpu")]
+    fn run_gpu_benchmarks(&self) -> Option<GpuBenchmarkResult> {
+        let gpus = match crate::gpu::detect_gpus() {
+            Ok(gpus) => gpus,
+            Err(e) => {
+                debug!(error = %e, "GPU detection failed, skipping GPU benchmark");
+                return None;
+            }
+        };
+        let gpu = crate::gpu::select_best_gpu(&gpus)?;
+
+        match crate::gpu::measure_throughput(gpu, self.iterations) {
+            Ok(throughput) => {
+                let result = GpuBenchmarkResult::from_measurement(
+                    &gpu.name,
+                    throughput.gflops,
+                    throughput.vram_bandwidth_gbps,
+                    gpu.estimated_tokens_per_sec(ESTIMATE_QUANTIZATION),
+                );
+                debug!(
+                    gpu = %gpu.name,
+                    gflops = result.fma_gflops,
+                    vram_gbps = result.vram_bandwidth_gbps,
+                    score = result.score,
+                    "GPU benchmark complete"
+                );
+                Some(result)
+            }
+            Err(e) => {
+                warn!(gpu = %gpu.name, error = %e, "GPU benchmark failed, scoring CPU and memory only");
+                None
+            }
+        }
+    }
+
     /// Calculate overall compute score
     fn calculate_compute_score(
         &self,
         cpu: &CpuBenchmarkResult,
         memory: &MemoryBenchmarkResult,
+        gpu: Option<&GpuBenchmarkResult>,
     ) -> u32 {
         // Weighted average: 60% CPU, 40% memory
         let cpu_score = (cpu.single_thread_score + cpu.multi_thread_score) / 2;
-        let combined = (cpu_score as f64 * 0.6 + memory.score as f64 * 0.4) as u32;
-        combined.min(1000)
+        let combined = cpu_score as f64 * 0.6 + memory.score as f64 * 0.4;
+
+        // Inference runs on the GPU when there is one, so it dominates
+        let combined = match gpu {
+            Some(gpu) => combined * 0.3 + gpu.score as f64 * 0.7,
+            None => combined,
+        };
+        (combined as u32).min(1000)
     }
 
-    /// Estimate tokens per second based on compute score
-    fn estimate_tokens_per_second(&self, compute_score: u32) -> f32 {
+    /// Estimate tokens per second based on compute score, or on the GPU
+    /// measurement when there is one
+    fn estimate_tokens_per_second(&self, compute_score: u32, gpu: Option<&GpuBenchmarkResult>) -> f32 {
+        if let Some(gpu) = gpu {
+            return gpu.estimated_tokens_per_second;
+        }
+
         // Rough estimation based on compute score
         // Score 500 ~= 30 tokens/sec for 7B model
         // This is a rough approximation; actual depends on model, quantization, etc.
@@ -393,6 +573,69 @@ impl BenchmarkRunner {
     pub fn results_exist(path: &Path) -> bool {
         path.exists()
     }
+
+    /// History file kept next to a results file
+    /// (`benchmark.json` -> `benchmark-history.jsonl`)
+    pub fn history_path_for(results_path: &Path) -> PathBuf {
+        let stem = results_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "benchmark".to_string());
+        results_path.with_file_name(format!("{}-history.jsonl", stem))
+    }
+
+    /// Append `results` to the history file as one JSON line
+    pub fn append_history(results: &BenchmarkResults, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| Error::IoWrite {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
+        }
+
+        let line = serde_json::to_string(results)
+            .map_err(|e| Error::Config(e.to_string()))?;
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| Error::IoWrite {
+                path: path.to_path_buf(),
+                source: e,
+            })?;
+
+        debug!(path = %path.display(), "Benchmark run appended to history");
+        Ok(())
+    }
+
+    /// Load every run in a history file, oldest first. Lines that don't
+    /// parse are skipped.
+    pub fn load_history(path: &Path) -> Result<Vec<BenchmarkResults>> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(Error::IoRead {
+                    path: path.to_path_buf(),
+                    source: e,
+                })
+            }
+        };
+
+        Ok(content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(i, line)| match serde_json::from_str(line) {
+                Ok(results) => Some(results),
+                Err(e) => {
+                    warn!(path = %path.display(), line = i + 1, error = %e, "Skipping malformed benchmark history line");
+                    None
+                }
+            })
+            .collect())
+    }
 }
 
 impl Default for BenchmarkRunner {
@@ -538,6 +781,117 @@ mod tests {
         assert_eq!(results.compute_score, loaded.compute_score);
     }
 
+    fn results_with(compute_score: u32, tokens_per_second: f32, read_mbps: f64) -> BenchmarkResults {
+        BenchmarkResults {
+            timestamp: chrono::Utc::now(),
+            cpu: CpuBenchmarkResult {
+                single_thread_score: compute_score,
+                multi_thread_score: compute_score,
+                thread_count: 4,
+                hashes_per_second: 1.0,
+                matrix_ops_per_second: 1.0,
+            },
+            memory: MemoryBenchmarkResult {
+                seq_read_mbps: read_mbps,
+                seq_write_mbps: 0.0,
+                random_access_ns: 10.0,
+                score: compute_score,
+            },
+            gpu: None,
+            compute_score,
+            estimated_tokens_per_second: tokens_per_second,
+            duration_secs: 1.0,
+        }
+    }
+
+    fn assert_pct(actual: Option<f64>, expected: f64) {
+        assert!((actual.unwrap() - expected).abs() < 1e-4, "{:?} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_gpu_phase_folds_into_score() {
+        let runner = BenchmarkRunner::new(1);
+        let base = results_with(400, 0.0, 0.0);
+
+        // No GPU: unchanged CPU/memory scoring
+        assert_eq!(runner.calculate_compute_score(&base.cpu, &base.memory, None), 400);
+        assert_eq!(runner.estimate_tokens_per_second(400, None), 24.0);
+
+        // 10 TFLOPS scores 500; 400 GB/s gives 100 tokens/sec, inside the anchor band
+        let gpu = GpuBenchmarkResult::from_measurement("Test GPU", 10_000.0, 400.0, 120);
+        assert_eq!(gpu.score, 500);
+        assert_eq!(gpu.estimated_tokens_per_second, 100.0);
+        assert_eq!(runner.calculate_compute_score(&base.cpu, &base.memory, Some(&gpu)), 470);
+        assert_eq!(runner.estimate_tokens_per_second(470, Some(&gpu)), 100.0);
+
+        // Outliers are pulled back to within 2x of the anchor
+        let slow = GpuBenchmarkResult::from_measurement("Test GPU", 100_000.0, 4.0, 120);
+        assert_eq!(slow.score, 1000);
+        assert_eq!(slow.estimated_tokens_per_second, 60.0);
+        let fast = GpuBenchmarkResult::from_measurement("Test GPU", 0.0, 4000.0, 120);
+        assert_eq!(fast.estimated_tokens_per_second, 240.0);
+
+        // Old history lines without a GPU section still parse
+        let json = serde_json::to_value(&base).unwrap();
+        assert!(json.get("gpu").is_none());
+        let parsed: BenchmarkResults = serde_json::from_value(json).unwrap();
+        assert!(parsed.gpu.is_none());
+
+        // Results saved before the field was renamed still parse
+        let mut json = serde_json::to_value(&gpu).unwrap();
+        let gflops = json.as_object_mut().unwrap().remove("fma_gflops").unwrap();
+        json["matmul_gflops"] = gflops;
+        let parsed: GpuBenchmarkResult = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.fma_gflops, 10_000.0);
+    }
+
+    #[test]
+    fn test_comparison_deltas() {
+        let previous = results_with(500, 30.0, 10_000.0);
+
+        let comparison = BenchmarkComparison::between(&previous, &results_with(400, 33.0, 12_500.0));
+        assert_pct(comparison.compute_score_pct, -20.0);
+        assert_pct(comparison.tokens_per_second_pct, 10.0);
+        assert_pct(comparison.memory_read_pct, 25.0);
+        // No previous figure to compare against
+        assert_eq!(comparison.memory_write_pct, None);
+        assert!(comparison.compute_regressed(COMPUTE_REGRESSION_WARN_PCT));
+
+        // A 10% drop is within the noise
+        let comparison = BenchmarkComparison::between(&previous, &results_with(450, 27.0, 10_000.0));
+        assert_pct(comparison.compute_score_pct, -10.0);
+        assert!(!comparison.compute_regressed(COMPUTE_REGRESSION_WARN_PCT));
+    }
+
+    #[test]
+    fn test_history_appended_next_to_results() {
+        let dir = tempdir().unwrap();
+        let results_path = dir.path().join("benchmark.json");
+        let history = BenchmarkRunner::history_path_for(&results_path);
+        assert_eq!(history, dir.path().join("benchmark-history.jsonl"));
+        assert!(BenchmarkRunner::load_history(&history).unwrap().is_empty());
+
+        // An existing history keeps its runs, even past a damaged line
+        BenchmarkRunner::append_history(&results_with(500, 30.0, 10_000.0), &history).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&history)
+            .unwrap()
+            .write_all(b"{truncated\n")
+            .unwrap();
+
+        let results = BenchmarkRunner::new(1)
+            .with_results_path(results_path)
+            .run()
+            .unwrap();
+
+        let runs = BenchmarkRunner::load_history(&history).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].compute_score, 500);
+        assert_eq!(runs[1].compute_score, results.compute_score);
+        assert_eq!(std::fs::read_to_string(&history).unwrap().lines().count(), 3);
+    }
+
     #[test]
     fn test_first_run_experience() {
         let dir = tempdir().unwrap();
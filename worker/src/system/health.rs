@@ -168,22 +168,8 @@ impl HealthMonitor {
     /// Get memory used in MB
     fn get_memory_used_mb(&self) -> u64 {
         // Simplified: would use sysinfo crate for accurate values
-        // Try to estimate based on process memory
-        #[cfg(target_os = "linux")]
-        {
-            if let Ok(content) = std::fs::read_to_string("/proc/self/statm") {
-                let parts: Vec<&str> = content.split_whitespace().collect();
-                if let Some(pages_str) = parts.get(1) {
-                    if let Ok(pages) = pages_str.parse::<u64>() {
-                        // Page size is typically 4KB
-                        return (pages * 4) / 1024;
-                    }
-                }
-            }
-        }
-
-        // Default: assume some base usage
-        256
+        // Estimate based on process memory, else assume some base usage
+        process_memory_mb().unwrap_or(256)
     }
 
     /// Get available memory in MB
@@ -193,14 +179,12 @@ impl HealthMonitor {
 
     /// Get GPU usage percentage
     fn get_gpu_usage(&self) -> Option<f32> {
-        // Would require platform-specific GPU libraries (nvidia-ml, rocm-smi, etc.)
-        None
+        gpu_utilization_percent()
     }
 
     /// Get GPU memory used in MB
     fn get_gpu_memory_used_mb(&self) -> Option<u64> {
-        // Would require platform-specific GPU libraries
-        None
+        gpu_memory_used_mb()
     }
 
     /// Check if system is healthy
@@ -299,6 +283,53 @@ pub struct HealthCheck {
     pub detail: Option<String>,
 }
 
+// ─────────────────────────────────────────────────────────────────
+// Memory probes
+// ─────────────────────────────────────────────────────────────────
+
+/// Resident memory of this process in MB (`None` where unsupported)
+pub fn process_memory_mb() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let content = std::fs::read_to_string("/proc/self/statm").ok()?;
+        let pages = content.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+        // Page size is typically 4KB
+        Some((pages * 4) / 1024)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// GPU utilization percentage (averaged) and memory in use in MB (summed)
+/// over GPUs that report them, from one reading. Calls into the GPU
+/// driver, so async code should run it on the blocking pool.
+pub fn gpu_usage() -> (Option<f32>, Option<u64>) {
+    #[cfg(feature = "gpu")]
+    {
+        crate::gpu::total_usage()
+            .map(|usage| (Some(usage.utilization_percent), Some(usage.memory_used_mb)))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    {
+        (None, None)
+    }
+}
+
+/// GPU memory in use in MB, summed over GPUs that report it
+pub fn gpu_memory_used_mb() -> Option<u64> {
+    gpu_usage().1
+}
+
+/// GPU utilization percentage, averaged over GPUs that report it
+pub fn gpu_utilization_percent() -> Option<f32> {
+    gpu_usage().0
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────
@@ -0,0 +1,322 @@
+//! Hardware summary report
+//!
+//! Aggregates system info, detected GPUs, and backend capabilities into a
+//! single report for the `info` command.
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+    BackendCapabilities, BackendFactory, BackendType, CrawlerBackend, InferenceBackend,
+    OpenAiRouter,
+};
+use crate::config::WorkerConfig;
+use crate::types::TaskType;
+
+use super::{FirstRunExperience, SystemInfo};
+
+/// Quantization assumed for GPU throughput estimates
+pub(crate) const ESTIMATE_QUANTIZATION: &str = "Q4_K_M";
+
+// ─────────────────────────────────────────────────────────────────
+// Report Types
+// ─────────────────────────────────────────────────────────────────
+
+/// A detected GPU
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuReport {
+    /// Human-readable summary (vendor, name, VRAM, APIs)
+    pub summary: String,
+
+    /// Total video memory (MB)
+    pub total_memory_mb: u64,
+
+    /// Whether the device can run compute workloads
+    pub compute_capable: bool,
+
+    /// Estimated inference throughput (Q4 quantization)
+    pub estimated_tokens_per_sec: u32,
+}
+
+#[cfg(feature = "gpu")]
+impl From<&crate::gpu::GpuInfo> for GpuReport {
+    fn from(gpu: &crate::gpu::GpuInfo) -> Self {
+        Self {
+            summary: gpu.summary(),
+            total_memory_mb: gpu.total_memory_mb,
+            compute_capable: gpu.compute_capable,
+            estimated_tokens_per_sec: gpu.estimated_tokens_per_sec(ESTIMATE_QUANTIZATION),
+        }
+    }
+}
+
+/// A backend supported by this build
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendReport {
+    /// Backend name
+    pub name: String,
+
+    /// Task types the backend handles (empty if it could not be created)
+    pub supported_tasks: Vec<TaskType>,
+
+    /// Why the backend is unusable, if it is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unavailable_reason: Option<String>,
+}
+
+/// Everything the machine offers, as seen by the worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareReport {
+    /// Host system information
+    pub system: SystemInfo,
+
+    /// Whether GPU detection is compiled in (`--features gpu`)
+    pub gpu_detection: bool,
+
+    /// Detected GPUs
+    pub gpus: Vec<GpuReport>,
+
+    /// Backends supported by this build
+    pub backends: Vec<BackendReport>,
+
+    /// CPU throughput estimate from saved benchmark results
+    pub cpu_tokens_per_sec: Option<f32>,
+
+    /// Best estimated throughput across all devices
+    pub best_tokens_per_sec: Option<f32>,
+}
+
+impl HardwareReport {
+    /// Aggregate already-collected inputs into a report
+    pub fn build(
+        system: SystemInfo,
+        gpu_detection: bool,
+        gpus: Vec<GpuReport>,
+        backends: Vec<BackendReport>,
+        cpu_tokens_per_sec: Option<f32>,
+    ) -> Self {
+        let best_tokens_per_sec = gpus
+            .iter()
+            .filter(|g| g.compute_capable)
+            .map(|g| g.estimated_tokens_per_sec as f32)
+            .chain(cpu_tokens_per_sec)
+            .reduce(f32::max);
+
+        Self {
+            system,
+            gpu_detection,
+            gpus,
+            backends,
+            cpu_tokens_per_sec,
+            best_tokens_per_sec,
+        }
+    }
+
+    /// Detect hardware and backends on this machine
+    pub fn collect(config: &WorkerConfig) -> Self {
+        let cpu_tokens_per_sec = FirstRunExperience::new(&config.data_dir())
+            .load_benchmark_results()
+            .ok()
+            .map(|r| r.estimated_tokens_per_second);
+
+        Self::build(
+            SystemInfo::collect(),
+            cfg!(feature = "gpu"),
+            detect_gpus(),
+            detect_backends(config),
+            cpu_tokens_per_sec,
+        )
+    }
+
+    /// Print a human-readable report
+    pub fn print(&self) {
+        let sys = &self.system;
+        println!("System:");
+        println!("  Hostname:  {}", sys.hostname);
+        println!("  OS:        {} {} ({})", sys.os_name, sys.os_version, sys.arch);
+        println!("  CPU cores: {}", sys.cpu_count);
+        println!("  Memory:    {} MB", sys.total_memory_mb);
+        match self.cpu_tokens_per_sec {
+            Some(tps) => println!("  CPU estimate: ~{:.0} tokens/sec", tps),
+            None => println!("  CPU estimate: unknown (run `ai4all-worker benchmark`)"),
+        }
+
+        println!();
+        println!("GPUs:");
+        if !self.gpu_detection {
+            println!("  GPU detection not compiled (use --features gpu)");
+        } else if self.gpus.is_empty() {
+            println!("  No GPUs detected");
+        }
+        for (i, gpu) in self.gpus.iter().enumerate() {
+            println!(
+                "  [{}] {} - ~{} tokens/sec{}",
+                i,
+                gpu.summary,
+                gpu.estimated_tokens_per_sec,
+                if gpu.compute_capable { "" } else { " (not compute-capable)" }
+            );
+        }
+
+        println!();
+        println!("Backends:");
+        for backend in &self.backends {
+            match &backend.unavailable_reason {
+                Some(reason) => println!("  {:<8} unavailable: {}", backend.name, reason),
+                None => println!(
+                    "  {:<8} {}",
+                    backend.name,
+                    backend
+                        .supported_tasks
+                        .iter()
+                        .map(|t| t.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+        }
+
+        if let Some(best) = self.best_tokens_per_sec {
+            println!();
+            println!("Best estimated throughput: ~{:.0} tokens/sec", best);
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Detection
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(feature = "gpu")]
+fn detect_gpus() -> Vec<GpuReport> {
+    match crate::gpu::detect_gpus() {
+        Ok(gpus) => gpus.iter().map(GpuReport::from).collect(),
+        Err(e) => {
+            tracing::warn!(error = %e, "GPU detection failed");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(not(feature = "gpu"))]
+fn detect_gpus() -> Vec<GpuReport> {
+    Vec::new()
+}
+
+/// Report each backend compiled into this build (the mock backend excluded)
+fn detect_backends(config: &WorkerConfig) -> Vec<BackendReport> {
+    BackendFactory::available_backends()
+        .into_iter()
+        .filter(|t| *t != BackendType::Mock)
+        .map(|backend_type| {
+            let capabilities = match backend_type {
+                BackendType::Crawler => {
+                    Ok(CrawlerBackend::new(&config.crawler, &config.openai).capabilities())
+                }
+                BackendType::OpenAi => Ok(OpenAiRouter::from_settings(&config.openai).capabilities()),
+                _ => BackendFactory::create(backend_type, Default::default())
+                    .map(|b| b.capabilities()),
+            };
+            backend_report(backend_type, capabilities)
+        })
+        .collect()
+}
+
+fn backend_report(
+    backend_type: BackendType,
+    capabilities: crate::error::Result<BackendCapabilities>,
+) -> BackendReport {
+    match capabilities {
+        Ok(caps) => BackendReport {
+            name: backend_type.name().to_string(),
+            supported_tasks: caps.supported_tasks,
+            unavailable_reason: None,
+        },
+        Err(e) => BackendReport {
+            name: backend_type.name().to_string(),
+            supported_tasks: Vec::new(),
+            unavailable_reason: Some(e.to_string()),
+        },
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn mock_system() -> SystemInfo {
+        SystemInfo {
+            cpu_count: 16,
+            total_memory_mb: 65536,
+            os_name: "linux".to_string(),
+            os_version: "6.8".to_string(),
+            arch: "x86_64".to_string(),
+            hostname: "rig-01".to_string(),
+        }
+    }
+
+    fn mock_gpu(summary: &str, tps: u32, compute_capable: bool) -> GpuReport {
+        GpuReport {
+            summary: summary.to_string(),
+            total_memory_mb: 24576,
+            compute_capable,
+            estimated_tokens_per_sec: tps,
+        }
+    }
+
+    #[test]
+    fn test_report_aggregation() {
+        let backends = vec![
+            backend_report(
+                BackendType::Cpu,
+                Ok(BackendCapabilities {
+                    name: "cpu",
+                    supported_tasks: vec![TaskType::TextCompletion],
+                    supports_training: false,
+                    supports_streaming: true,
+                    max_context_length: 4096,
+                    max_batch_size: 512,
+                    gpu_available: false,
+                    gpu_device: None,
+                    gpu_memory_mb: None,
+                }),
+            ),
+            backend_report(
+                BackendType::Cuda,
+                Err(Error::NotSupported("CUDA backend requires 'cuda' feature".to_string())),
+            ),
+        ];
+        let gpus = vec![
+            mock_gpu("NVIDIA RTX 4090 (24576MB) - Vulkan, CUDA", 198, true),
+            // Not compute-capable: excluded from the best estimate
+            mock_gpu("Intel UHD 770 (2048MB) - Vulkan", 500, false),
+        ];
+
+        let report = HardwareReport::build(mock_system(), true, gpus, backends, Some(25.0));
+
+        assert_eq!(report.system.cpu_count, 16);
+        assert_eq!(report.gpus.len(), 2);
+        assert_eq!(report.best_tokens_per_sec, Some(198.0));
+        assert_eq!(report.backends[0].supported_tasks, vec![TaskType::TextCompletion]);
+        assert!(report.backends[1].unavailable_reason.is_some());
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["system"]["hostname"], "rig-01");
+        assert_eq!(json["gpus"][0]["estimated_tokens_per_sec"], 198);
+        assert!(json["backends"][0].get("unavailable_reason").is_none());
+    }
+
+    #[test]
+    fn test_report_cpu_only() {
+        let report = HardwareReport::build(mock_system(), false, vec![], vec![], None);
+        assert!(report.gpus.is_empty());
+        assert_eq!(report.best_tokens_per_sec, None);
+
+        let report = HardwareReport::build(mock_system(), false, vec![], vec![], Some(30.0));
+        assert_eq!(report.best_tokens_per_sec, Some(30.0));
+    }
+}
@@ -5,9 +5,15 @@
 //! - System capability detection
 //! - Performance benchmarking
 //! - First-run experience
+//! - Hardware summary reporting
+//! - CPU topology detection
 
 mod health;
 mod benchmark;
+mod info;
+mod topology;
 
 pub use health::*;
 pub use benchmark::*;
+pub use info::*;
+pub use topology::*;
@@ -0,0 +1,43 @@
+//! CPU topology detection
+//!
+//! Reports physical vs logical core counts so backends can size inference
+//! thread pools. Hyperthreads share execution units, so compute-bound
+//! inference often runs faster with one thread per physical core.
+
+use serde::{Deserialize, Serialize};
+
+/// Core counts of the host CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuTopology {
+    /// Physical cores
+    pub physical_cores: usize,
+
+    /// Logical cores (hardware threads)
+    pub logical_cores: usize,
+}
+
+impl CpuTopology {
+    /// Detect the host topology
+    pub fn detect() -> Self {
+        let logical_cores = num_cpus::get().max(1);
+        Self {
+            physical_cores: num_cpus::get_physical().clamp(1, logical_cores),
+            logical_cores,
+        }
+    }
+
+    /// Whether cores run more than one hardware thread
+    pub fn has_smt(&self) -> bool {
+        self.logical_cores > self.physical_cores
+    }
+
+    /// Inference threads to use when none are configured
+    pub fn inference_threads(&self, physical_only: bool) -> u32 {
+        let cores = if physical_only {
+            self.physical_cores
+        } else {
+            self.logical_cores
+        };
+        cores.max(1) as u32
+    }
+}
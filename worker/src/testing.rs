@@ -0,0 +1,239 @@
+//! Test servers
+//!
+//! Local stubs for tests that talk to a real socket: [`http_stub`] answers
+//! HTTP/1.1 requests through a handler, and [`ws_stub`] does the same for
+//! WebSocket frames. Both accept any number of connections, each served on
+//! its own task, and run until the test's runtime shuts down.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+/// Longest request head accepted
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+
+/// A request received by an [`http_stub`]
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercased
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of the header `name` (any case)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers.iter().find(|(n, _)| *n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// The body parsed as JSON
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).expect("request body is not JSON")
+    }
+}
+
+/// A reply from an [`http_stub`] handler. `Content-Length` is filled in
+/// from the body unless set explicitly.
+#[derive(Debug, Clone)]
+pub struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Duration,
+    hold_open: bool,
+}
+
+impl Response {
+    /// Empty response with `status`
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+            delay: Duration::ZERO,
+            hold_open: false,
+        }
+    }
+
+    /// 200 with `body` as `content_type`
+    pub fn ok(content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self::new(200).header("Content-Type", content_type).body(body)
+    }
+
+    /// `status` with a JSON body
+    pub fn json(status: u16, body: impl ToString) -> Self {
+        Self::new(status)
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+    }
+
+    pub fn header(mut self, name: &str, value: impl ToString) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Wait this long before replying
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Keep the connection open and silent after the reply, as a stalled
+    /// server would
+    pub fn hold_open(mut self) -> Self {
+        self.hold_open = true;
+        self
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let reason = reqwest::StatusCode::from_u16(self.status)
+            .ok()
+            .and_then(|s| s.canonical_reason())
+            .unwrap_or("");
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason);
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if !self.headers.iter().any(|(n, _)| n.eq_ignore_ascii_case("content-length")) {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        head.push_str("Connection: close\r\n\r\n");
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+/// Serve HTTP on a local port, answering each request with `handler`.
+/// Returns the base URL (`http://127.0.0.1:<port>`).
+pub async fn http_stub<F>(handler: F) -> String
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    let (listener, addr) = listen().await;
+    accept_each(listener, move |mut stream| {
+        let handler = handler.clone();
+        async move {
+            let Some(request) = read_request(&mut stream).await else {
+                return;
+            };
+            let response = handler(request);
+            tokio::time::sleep(response.delay).await;
+            if stream.write_all(&response.encode()).await.is_err() || !response.hold_open {
+                return;
+            }
+            // Wait for the client to give up
+            let mut buf = [0u8; 1024];
+            while matches!(stream.read(&mut buf).await, Ok(n) if n > 0) {}
+        }
+    });
+    format!("http://{}", addr)
+}
+
+/// Serve WebSockets on a local port, answering each text or binary frame
+/// with the frames `handler` returns. Returns the URL (`ws://127.0.0.1:<port>`).
+pub async fn ws_stub<F>(handler: F) -> String
+where
+    F: Fn(WsMessage) -> Vec<WsMessage> + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    let (listener, addr) = listen().await;
+    accept_each(listener, move |stream| {
+        let handler = handler.clone();
+        async move {
+            let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+                return;
+            };
+            while let Some(Ok(frame)) = ws.next().await {
+                if !(frame.is_text() || frame.is_binary()) {
+                    continue;
+                }
+                for reply in handler(frame) {
+                    if ws.send(reply).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+    format!("ws://{}", addr)
+}
+
+async fn listen() -> (TcpListener, std::net::SocketAddr) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    (listener, addr)
+}
+
+/// Serve each accepted connection with `serve` on a task of its own
+fn accept_each<F, Fut>(listener: TcpListener, serve: F)
+where
+    F: Fn(TcpStream) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve(stream));
+        }
+    });
+}
+
+/// Read one request, head and `Content-Length` body; `None` if the client
+/// went away first
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let split = loop {
+        if let Some(split) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break split;
+        }
+        if buf.len() > MAX_HEAD_BYTES {
+            return None;
+        }
+        let mut chunk = [0u8; 4096];
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..split]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = buf.split_off(split + 4);
+    while body.len() < length {
+        let mut chunk = [0u8; 4096];
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => body.extend_from_slice(&chunk[..n]),
+        }
+    }
+    body.truncate(length);
+
+    Some(Request { method, path, headers, body })
+}
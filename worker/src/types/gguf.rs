@@ -0,0 +1,382 @@
+//! GGUF header parsing
+//!
+//! Reads the fixed header and key-value metadata section at the start of a
+//! GGUF file to fill in [`GgufMetadata`]. Tensor info and tensor data that
+//! follow the metadata are never read, so parsing stays fast regardless of
+//! model size.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+use super::GgufMetadata;
+
+/// File magic, "GGUF"
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+/// Longest metadata string accepted (chat templates can run to tens of KB)
+const MAX_STRING_LEN: u64 = 16 * 1024 * 1024;
+
+/// Most metadata entries accepted; real models have a few dozen
+const MAX_KV_COUNT: u64 = 65_536;
+
+/// Deepest array nesting accepted; real models use flat arrays
+const MAX_ARRAY_DEPTH: u32 = 2;
+
+/// Metadata value types
+const TYPE_U8: u32 = 0;
+const TYPE_I8: u32 = 1;
+const TYPE_U16: u32 = 2;
+const TYPE_I16: u32 = 3;
+const TYPE_U32: u32 = 4;
+const TYPE_I32: u32 = 5;
+const TYPE_F32: u32 = 6;
+const TYPE_BOOL: u32 = 7;
+const TYPE_STRING: u32 = 8;
+const TYPE_ARRAY: u32 = 9;
+const TYPE_U64: u32 = 10;
+const TYPE_I64: u32 = 11;
+const TYPE_F64: u32 = 12;
+
+/// A metadata value kept for [`GgufMetadata`]; other values are skipped
+enum Value {
+    Int(u64),
+    Str(String),
+    /// Array length (elements are not kept)
+    Array(u64),
+    Other,
+}
+
+/// Reader over the header, tracking the GGUF version's integer widths
+struct HeaderReader<R> {
+    inner: R,
+    /// Version 1 used 32-bit counts and string lengths
+    wide_counts: bool,
+}
+
+impl<R: Read> HeaderReader<R> {
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.inner.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    /// A count or length, 64-bit from version 2 on
+    fn count(&mut self) -> io::Result<u64> {
+        if self.wide_counts {
+            self.u64()
+        } else {
+            self.u32().map(u64::from)
+        }
+    }
+
+    fn skip(&mut self, len: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut self.inner.by_ref().take(len), &mut io::sink())?;
+        if skipped < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.count()?;
+        if len > MAX_STRING_LEN {
+            return Err(invalid(format!("string of {} bytes", len)));
+        }
+        let mut buf = vec![0u8; len as usize];
+        self.inner.read_exact(&mut buf)?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    /// Read a value of type `value_type`, keeping integers and strings
+    fn value(&mut self, value_type: u32) -> io::Result<Value> {
+        self.nested_value(value_type, 0)
+    }
+
+    /// Read a value inside `depth` enclosing arrays
+    fn nested_value(&mut self, value_type: u32, depth: u32) -> io::Result<Value> {
+        Ok(match value_type {
+            TYPE_U8 | TYPE_I8 | TYPE_BOOL => Value::Int(self.bytes::<1>()?[0] as u64),
+            TYPE_U16 | TYPE_I16 => Value::Int(u16::from_le_bytes(self.bytes()?) as u64),
+            TYPE_U32 | TYPE_I32 => Value::Int(self.u32()? as u64),
+            TYPE_U64 | TYPE_I64 => Value::Int(self.u64()?),
+            TYPE_F32 => {
+                self.skip(4)?;
+                Value::Other
+            }
+            TYPE_F64 => {
+                self.skip(8)?;
+                Value::Other
+            }
+            TYPE_STRING => Value::Str(self.string()?),
+            TYPE_ARRAY => {
+                if depth >= MAX_ARRAY_DEPTH {
+                    return Err(invalid(format!("arrays nested over {} deep", MAX_ARRAY_DEPTH)));
+                }
+                let element_type = self.u32()?;
+                let len = self.count()?;
+                match fixed_size(element_type) {
+                    Some(size) => self.skip(len.saturating_mul(size))?,
+                    None => {
+                        for _ in 0..len {
+                            self.nested_value(element_type, depth + 1)?;
+                        }
+                    }
+                }
+                Value::Array(len)
+            }
+            other => return Err(invalid(format!("unknown value type {}", other))),
+        })
+    }
+}
+
+/// Encoded size of a fixed-width value type
+fn fixed_size(value_type: u32) -> Option<u64> {
+    match value_type {
+        TYPE_U8 | TYPE_I8 | TYPE_BOOL => Some(1),
+        TYPE_U16 | TYPE_I16 => Some(2),
+        TYPE_U32 | TYPE_I32 | TYPE_F32 => Some(4),
+        TYPE_U64 | TYPE_I64 | TYPE_F64 => Some(8),
+        _ => None,
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Quantization name for a `general.file_type` value
+fn file_type_name(file_type: u64) -> Option<&'static str> {
+    Some(match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        19 => "IQ2_XXS",
+        20 => "IQ2_XS",
+        21 => "Q2_K_S",
+        22 => "IQ3_XS",
+        23 => "IQ3_XXS",
+        24 => "IQ1_S",
+        25 => "IQ4_NL",
+        26 => "IQ3_S",
+        27 => "IQ3_M",
+        28 => "IQ2_S",
+        29 => "IQ2_M",
+        30 => "IQ4_XS",
+        31 => "IQ1_M",
+        32 => "BF16",
+        _ => return None,
+    })
+}
+
+impl GgufMetadata {
+    /// Read the metadata from the header of the GGUF file at `path`
+    pub fn read(path: &Path) -> Result<Self> {
+        let model_id = path.display().to_string();
+        let file = File::open(path).map_err(|e| Error::ModelLoadFailed {
+            model_id: model_id.clone(),
+            message: e.to_string(),
+        })?;
+        Self::from_reader(BufReader::new(file), &model_id)
+    }
+
+    /// Read the metadata from a GGUF header. `model_id` names the model in
+    /// errors.
+    pub fn from_reader(reader: impl Read, model_id: &str) -> Result<Self> {
+        let corrupted = |e: io::Error| Error::ModelCorrupted {
+            model_id: model_id.to_string(),
+            reason: match e.kind() {
+                io::ErrorKind::UnexpectedEof => "truncated GGUF header".to_string(),
+                _ => format!("invalid GGUF header: {}", e),
+            },
+        };
+
+        let mut reader = HeaderReader { inner: reader, wide_counts: true };
+        let magic: [u8; 4] = reader.bytes().map_err(corrupted)?;
+        if &magic != GGUF_MAGIC {
+            return Err(Error::ModelCorrupted {
+                model_id: model_id.to_string(),
+                reason: format!("not a GGUF file (magic {:02x?})", magic),
+            });
+        }
+        let version = reader.u32().map_err(corrupted)?;
+        if !(1..=3).contains(&version) {
+            return Err(Error::ModelIncompatible {
+                model_id: model_id.to_string(),
+                reason: format!("unsupported GGUF version {}", version),
+            });
+        }
+        reader.wide_counts = version >= 2;
+
+        let _tensor_count = reader.count().map_err(corrupted)?;
+        let kv_count = reader.count().map_err(corrupted)?;
+        if kv_count > MAX_KV_COUNT {
+            return Err(corrupted(invalid(format!("{} metadata entries", kv_count))));
+        }
+
+        let mut values = HashMap::new();
+        for _ in 0..kv_count {
+            let key = reader.string().map_err(corrupted)?;
+            let value_type = reader.u32().map_err(corrupted)?;
+            let value = reader.value(value_type).map_err(corrupted)?;
+            values.insert(key, value);
+        }
+
+        let string = |key: &str| match values.get(key) {
+            Some(Value::Str(s)) => Some(s.clone()),
+            _ => None,
+        };
+        let int = |key: &str| match values.get(key) {
+            Some(Value::Int(n)) => u32::try_from(*n).ok(),
+            _ => None,
+        };
+
+        let architecture = string("general.architecture");
+        let arch_int = |suffix: &str| {
+            architecture
+                .as_ref()
+                .and_then(|arch| int(&format!("{}.{}", arch, suffix)))
+        };
+
+        Ok(GgufMetadata {
+            name: string("general.name"),
+            author: string("general.author"),
+            license: string("general.license"),
+            description: string("general.description"),
+            quantization: values
+                .get("general.file_type")
+                .and_then(|v| match v {
+                    Value::Int(n) => file_type_name(*n),
+                    _ => None,
+                })
+                .map(str::to_string),
+            context_length: arch_int("context_length"),
+            embedding_length: arch_int("embedding_length"),
+            head_count: arch_int("attention.head_count"),
+            block_count: arch_int("block_count"),
+            vocab_size: match values.get("tokenizer.ggml.tokens") {
+                Some(Value::Array(len)) => u32::try_from(*len).ok(),
+                _ => arch_int("vocab_size"),
+            },
+            bos_token_id: int("tokenizer.ggml.bos_token_id"),
+            eos_token_id: int("tokenizer.ggml.eos_token_id"),
+            chat_template: string("tokenizer.chat_template"),
+            architecture,
+        })
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/tiny_header.gguf"
+    ));
+
+    #[test]
+    fn test_parse_fixture_header() {
+        let metadata = GgufMetadata::from_reader(FIXTURE, "tiny").unwrap();
+        assert_eq!(metadata.architecture.as_deref(), Some("llama"));
+        assert_eq!(metadata.name.as_deref(), Some("TinyLlama 1.1B Chat"));
+        assert_eq!(metadata.quantization.as_deref(), Some("Q4_K_M"));
+        assert_eq!(metadata.context_length, Some(2048));
+        assert_eq!(metadata.embedding_length, Some(2048));
+        assert_eq!(metadata.block_count, Some(22));
+        assert_eq!(metadata.head_count, Some(32));
+        assert_eq!(metadata.vocab_size, Some(4));
+        assert_eq!(metadata.bos_token_id, Some(1));
+        assert_eq!(metadata.eos_token_id, Some(2));
+        assert!(metadata.chat_template.is_none());
+    }
+
+    #[test]
+    fn test_bad_magic_rejected() {
+        let mut bytes = FIXTURE.to_vec();
+        bytes[..4].copy_from_slice(b"GGML");
+        let err = GgufMetadata::from_reader(&bytes[..], "tiny").unwrap_err();
+        assert!(matches!(err, Error::ModelCorrupted { .. }));
+        assert!(err.to_string().contains("not a GGUF file"), "{}", err);
+    }
+
+    #[test]
+    fn test_truncated_header_rejected() {
+        for len in [2, 10, 30, FIXTURE.len() - 1] {
+            let err = GgufMetadata::from_reader(&FIXTURE[..len], "tiny").unwrap_err();
+            assert!(err.to_string().contains("truncated GGUF header"), "{} bytes: {}", len, err);
+        }
+    }
+
+    /// Version 3 header holding one array value, `depth` arrays deep
+    fn nested_array_header(depth: usize) -> Vec<u8> {
+        let key = b"test.nested";
+        let mut bytes = b"GGUF".to_vec();
+        bytes.extend(3u32.to_le_bytes());
+        bytes.extend(0u64.to_le_bytes());
+        bytes.extend(1u64.to_le_bytes());
+        bytes.extend((key.len() as u64).to_le_bytes());
+        bytes.extend(key);
+        bytes.extend(TYPE_ARRAY.to_le_bytes());
+        for _ in 1..depth {
+            bytes.extend(TYPE_ARRAY.to_le_bytes());
+            bytes.extend(1u64.to_le_bytes());
+        }
+        bytes.extend(TYPE_U32.to_le_bytes());
+        bytes.extend(1u64.to_le_bytes());
+        bytes.extend(7u32.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_deeply_nested_arrays_rejected() {
+        for depth in 1..=MAX_ARRAY_DEPTH as usize {
+            assert!(GgufMetadata::from_reader(&nested_array_header(depth)[..], "tiny").is_ok());
+        }
+        let bytes = nested_array_header(MAX_ARRAY_DEPTH as usize + 1);
+        let err = GgufMetadata::from_reader(&bytes[..], "tiny").unwrap_err();
+        assert!(matches!(err, Error::ModelCorrupted { .. }));
+        assert!(err.to_string().contains("arrays nested"), "{}", err);
+    }
+
+    #[test]
+    fn test_read_stops_at_metadata() {
+        // Tensor info and data after the metadata are never touched
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.gguf");
+        let mut bytes = FIXTURE.to_vec();
+        bytes.extend(std::iter::repeat_n(0xff, 4096));
+        std::fs::write(&path, &bytes).unwrap();
+
+        let metadata = GgufMetadata::read(&path).unwrap();
+        assert_eq!(metadata.block_count, Some(22));
+    }
+}
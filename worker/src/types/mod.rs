@@ -5,6 +5,7 @@
 
 mod task;
 mod model;
+mod gguf;
 
 pub use task::*;
 pub use model::*;
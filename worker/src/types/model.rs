@@ -3,7 +3,10 @@
 //! Defines model specifications, capabilities, and metadata.
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
 
 // ─────────────────────────────────────────────────────────────────
 // Model Format
@@ -21,8 +24,16 @@ pub enum ModelFormat {
     SafeTensors,
     /// PyTorch format
     Pytorch,
+    /// ONNX format
+    Onnx,
 }
 
+/// Bytes read from the start of a model file for format detection
+const MAGIC_LEN: usize = 16;
+
+/// Largest plausible SafeTensors JSON header (bytes)
+const MAX_SAFETENSORS_HEADER: u64 = 100 * 1024 * 1024;
+
 impl ModelFormat {
     /// Get the file extension for this format
     pub fn extension(&self) -> &'static str {
@@ -31,6 +42,7 @@ impl ModelFormat {
             ModelFormat::Ggml => "ggml",
             ModelFormat::SafeTensors => "safetensors",
             ModelFormat::Pytorch => "pt",
+            ModelFormat::Onnx => "onnx",
         }
     }
 
@@ -43,9 +55,88 @@ impl ModelFormat {
                 "ggml" | "bin" => Some(ModelFormat::Ggml),
                 "safetensors" => Some(ModelFormat::SafeTensors),
                 "pt" | "pth" => Some(ModelFormat::Pytorch),
+                "onnx" => Some(ModelFormat::Onnx),
                 _ => None,
             })
     }
+
+    /// Detect format from a file's leading bytes.
+    ///
+    /// `file_len` is the full file size, used to sanity-check the
+    /// SafeTensors header length prefix.
+    pub fn from_magic(header: &[u8], file_len: u64) -> Option<Self> {
+        if header.starts_with(b"GGUF") {
+            return Some(ModelFormat::Gguf);
+        }
+        // Legacy GGML magics ("ggml", "ggmf", "ggjt" as little-endian u32)
+        if [b"lmgg", b"fmgg", b"tjgg"].iter().any(|m| header.starts_with(*m)) {
+            return Some(ModelFormat::Ggml);
+        }
+        // u64 little-endian JSON header length, then the JSON object
+        if header.len() > 8 && header[8] == b'{' {
+            let len = u64::from_le_bytes(header[..8].try_into().ok()?);
+            if len > 0 && len <= MAX_SAFETENSORS_HEADER && 8 + len <= file_len {
+                return Some(ModelFormat::SafeTensors);
+            }
+        }
+        // Zip archive (torch.save) or raw pickle stream
+        if header.starts_with(b"PK\x03\x04")
+            || (header.len() >= 2 && header[0] == 0x80 && (2..=5).contains(&header[1]))
+        {
+            return Some(ModelFormat::Pytorch);
+        }
+        // ModelProto starts with ir_version (field 1, varint)
+        if header.len() >= 2 && header[0] == 0x08 && (1..=20).contains(&header[1]) {
+            return Some(ModelFormat::Onnx);
+        }
+        None
+    }
+
+    /// Detect a model file's format from its content, overriding the
+    /// extension guess when the two disagree
+    pub fn detect(path: &Path) -> Result<Self> {
+        let model_id = path.display().to_string();
+        let mut file = std::fs::File::open(path).map_err(|e| Error::ModelLoadFailed {
+            model_id: model_id.clone(),
+            message: e.to_string(),
+        })?;
+        let file_len = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        let mut header = Vec::with_capacity(MAGIC_LEN);
+        file.by_ref()
+            .take(MAGIC_LEN as u64)
+            .read_to_end(&mut header)
+            .map_err(|e| Error::ModelLoadFailed {
+                model_id: model_id.clone(),
+                message: e.to_string(),
+            })?;
+
+        let by_extension = Self::from_path(path);
+        match Self::from_magic(&header, file_len) {
+            Some(format) => {
+                if by_extension.is_some_and(|ext| ext != format) {
+                    tracing::warn!(
+                        path = %path.display(),
+                        extension = ?by_extension,
+                        detected = ?format,
+                        "Model file extension doesn't match its content"
+                    );
+                }
+                Ok(format)
+            }
+            None => {
+                let found: Vec<String> =
+                    header.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+                let expected = by_extension
+                    .map(|f| format!(" in a .{} file", f.extension()))
+                    .unwrap_or_default();
+                Err(Error::ModelCorrupted {
+                    model_id,
+                    reason: format!("unrecognized header [{}]{}", found.join(" "), expected),
+                })
+            }
+        }
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -133,6 +224,114 @@ impl QuantizationType {
     }
 }
 
+// ─────────────────────────────────────────────────────────────────
+// Model Family
+// ─────────────────────────────────────────────────────────────────
+
+/// Model family, used to pick prompt templates and per-family defaults
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ModelFamily {
+    Llama,
+    Mistral,
+    Qwen,
+    Gemma,
+    Phi,
+    Falcon,
+    DeepSeek,
+    StarCoder,
+    CommandR,
+    /// Architecture not recognized (lowercased as reported)
+    Unknown(String),
+}
+
+/// Name prefixes identifying each known family
+const FAMILY_PREFIXES: &[(&str, ModelFamily)] = &[
+    ("llama", ModelFamily::Llama),
+    ("mistral", ModelFamily::Mistral),
+    ("mixtral", ModelFamily::Mistral),
+    ("qwen", ModelFamily::Qwen),
+    ("gemma", ModelFamily::Gemma),
+    ("phi", ModelFamily::Phi),
+    ("falcon", ModelFamily::Falcon),
+    ("deepseek", ModelFamily::DeepSeek),
+    ("starcoder", ModelFamily::StarCoder),
+    ("command-r", ModelFamily::CommandR),
+    ("cohere", ModelFamily::CommandR),
+];
+
+impl ModelFamily {
+    /// Map a GGUF `general.architecture` value (e.g. "qwen2", "gemma3",
+    /// "phi3") to a family
+    pub fn from_architecture(arch: &str) -> Self {
+        let arch = arch.trim().to_lowercase();
+        Self::match_prefix(&arch).unwrap_or(ModelFamily::Unknown(arch))
+    }
+
+    /// Infer the family from GGUF `general.architecture` and `general.name`.
+    ///
+    /// Many fine-tunes reuse the `llama` architecture, so for those the model
+    /// name is consulted for a more specific family (e.g. Mistral 7B).
+    pub fn from_metadata(architecture: Option<&str>, name: Option<&str>) -> Option<Self> {
+        let from_name = name.and_then(|name| {
+            name.to_lowercase()
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+                .flat_map(|word| std::iter::once(word).chain(word.split('-')))
+                .find_map(Self::match_prefix)
+        });
+
+        match architecture.map(Self::from_architecture) {
+            Some(ModelFamily::Llama) => Some(from_name.unwrap_or(ModelFamily::Llama)),
+            Some(ModelFamily::Unknown(arch)) => {
+                Some(from_name.unwrap_or(ModelFamily::Unknown(arch)))
+            }
+            Some(family) => Some(family),
+            None => from_name,
+        }
+    }
+
+    /// Family name as used in config and metadata
+    pub fn as_str(&self) -> &str {
+        match self {
+            ModelFamily::Llama => "llama",
+            ModelFamily::Mistral => "mistral",
+            ModelFamily::Qwen => "qwen",
+            ModelFamily::Gemma => "gemma",
+            ModelFamily::Phi => "phi",
+            ModelFamily::Falcon => "falcon",
+            ModelFamily::DeepSeek => "deepseek",
+            ModelFamily::StarCoder => "starcoder",
+            ModelFamily::CommandR => "command-r",
+            ModelFamily::Unknown(arch) => arch,
+        }
+    }
+
+    fn match_prefix(s: &str) -> Option<Self> {
+        FAMILY_PREFIXES
+            .iter()
+            .find(|(prefix, _)| s.starts_with(prefix))
+            .map(|(_, family)| family.clone())
+    }
+}
+
+impl std::fmt::Display for ModelFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<String> for ModelFamily {
+    fn from(s: String) -> Self {
+        Self::from_architecture(&s)
+    }
+}
+
+impl From<ModelFamily> for String {
+    fn from(family: ModelFamily) -> Self {
+        family.as_str().to_string()
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Model Specification
 // ─────────────────────────────────────────────────────────────────
@@ -148,7 +347,7 @@ pub struct ModelSpec {
 
     /// Model family (e.g., "llama", "mistral", "phi")
     #[serde(default)]
-    pub family: Option<String>,
+    pub family: Option<ModelFamily>,
 
     /// Model file path
     pub path: PathBuf,
@@ -191,11 +390,38 @@ pub struct ModelSpec {
     /// SHA256 hash of the model file
     #[serde(default)]
     pub sha256: Option<String>,
+
+    /// URL to download the model file from when it isn't present locally
+    /// (e.g. a Hugging Face GGUF)
+    #[serde(default)]
+    pub source_url: Option<String>,
 }
 
 fn default_context_length() -> u32 { 4096 }
 
 impl ModelSpec {
+    /// Spec for a GGUF model known only by its ID and where to download it,
+    /// to be stored at `path`
+    pub fn remote_gguf(id: &str, source_url: &str, sha256: Option<String>, path: PathBuf) -> Self {
+        Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            family: None,
+            path,
+            format: ModelFormat::Gguf,
+            quantization: None,
+            parameters_b: None,
+            context_length: default_context_length(),
+            vocab_size: None,
+            embedding_dim: None,
+            num_layers: None,
+            num_heads: None,
+            file_size: 0,
+            sha256,
+            source_url: Some(source_url.to_string()),
+        }
+    }
+
     /// Estimate VRAM required to load this model (in MB)
     pub fn estimated_vram_mb(&self) -> u64 {
         if let (Some(params), Some(quant)) = (self.parameters_b, &self.quantization) {
@@ -278,6 +504,13 @@ pub struct GgufMetadata {
     pub chat_template: Option<String>,
 }
 
+impl GgufMetadata {
+    /// Model family inferred from `general.architecture` / `general.name`
+    pub fn model_family(&self) -> Option<ModelFamily> {
+        ModelFamily::from_metadata(self.architecture.as_deref(), self.name.as_deref())
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Loaded Model Info
 // ─────────────────────────────────────────────────────────────────
@@ -325,6 +558,45 @@ mod tests {
         assert_eq!(ModelFormat::from_path(path), Some(ModelFormat::SafeTensors));
     }
 
+    /// Write `bytes` to `name` in `dir`
+    fn fixture(dir: &tempfile::TempDir, name: &str, bytes: &[u8]) -> PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_model_format_detect_overrides_extension() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = fixture(&dir, "model.safetensors", b"GGUF\x03\x00\x00\x00");
+        assert_eq!(ModelFormat::detect(&path).unwrap(), ModelFormat::Gguf);
+
+        let header = br#"{"__metadata__":{}}"#;
+        let mut safetensors = (header.len() as u64).to_le_bytes().to_vec();
+        safetensors.extend_from_slice(header);
+        let path = fixture(&dir, "model.gguf", &safetensors);
+        assert_eq!(ModelFormat::detect(&path).unwrap(), ModelFormat::SafeTensors);
+
+        let path = fixture(&dir, "weights.bin", &[0x08, 0x07, 0x12, 0x04]);
+        assert_eq!(ModelFormat::detect(&path).unwrap(), ModelFormat::Onnx);
+
+        let path = fixture(&dir, "model", b"PK\x03\x04archive");
+        assert_eq!(ModelFormat::detect(&path).unwrap(), ModelFormat::Pytorch);
+    }
+
+    #[test]
+    fn test_model_format_detect_unrecognized() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fixture(&dir, "model.gguf", b"\x7fELF\x02\x01");
+
+        let err = ModelFormat::detect(&path).unwrap_err();
+        assert!(matches!(err, Error::ModelCorrupted { .. }));
+        let message = err.to_string();
+        assert!(message.contains("7f 45 4c 46"), "{}", message);
+        assert!(message.contains(".gguf"), "{}", message);
+    }
+
     #[test]
     fn test_quantization_bits() {
         assert!((QuantizationType::Q4_K_M.bits_per_weight() - 4.5).abs() < 0.1);
@@ -338,12 +610,67 @@ mod tests {
         assert_eq!(QuantizationType::from_str("unknown"), QuantizationType::Unknown);
     }
 
+    #[test]
+    fn test_model_family_from_architecture() {
+        let cases = [
+            ("llama", ModelFamily::Llama),
+            ("qwen2", ModelFamily::Qwen),
+            ("qwen2moe", ModelFamily::Qwen),
+            ("gemma3", ModelFamily::Gemma),
+            ("phi3", ModelFamily::Phi),
+            ("Falcon", ModelFamily::Falcon),
+            ("deepseek2", ModelFamily::DeepSeek),
+            ("starcoder2", ModelFamily::StarCoder),
+            ("command-r", ModelFamily::CommandR),
+            ("mamba", ModelFamily::Unknown("mamba".to_string())),
+        ];
+        for (arch, family) in cases {
+            assert_eq!(ModelFamily::from_architecture(arch), family, "arch {}", arch);
+        }
+    }
+
+    #[test]
+    fn test_model_family_from_metadata() {
+        // llama architecture refined by name
+        assert_eq!(
+            ModelFamily::from_metadata(Some("llama"), Some("Mistral-7B-Instruct-v0.2")),
+            Some(ModelFamily::Mistral)
+        );
+        assert_eq!(
+            ModelFamily::from_metadata(Some("llama"), Some("Meta-Llama-3-8B-Instruct")),
+            Some(ModelFamily::Llama)
+        );
+        // A specific architecture wins over the name
+        assert_eq!(
+            ModelFamily::from_metadata(Some("qwen2"), Some("Llama-distilled")),
+            Some(ModelFamily::Qwen)
+        );
+        // Name only; "dolphin" must not match "phi"
+        assert_eq!(
+            ModelFamily::from_metadata(None, Some("dolphin-2.9-gemma-2b")),
+            Some(ModelFamily::Gemma)
+        );
+        assert_eq!(
+            ModelFamily::from_metadata(Some("rwkv6"), Some("dolphin")),
+            Some(ModelFamily::Unknown("rwkv6".to_string()))
+        );
+        assert_eq!(ModelFamily::from_metadata(None, None), None);
+    }
+
+    #[test]
+    fn test_model_family_serde() {
+        let json = serde_json::to_string(&ModelFamily::CommandR).unwrap();
+        assert_eq!(json, "\"command-r\"");
+        let family: ModelFamily = serde_json::from_str("\"mamba\"").unwrap();
+        assert_eq!(family, ModelFamily::Unknown("mamba".to_string()));
+    }
+
     #[test]
     fn test_model_spec_vram_estimate() {
         let spec = ModelSpec {
             id: "test".to_string(),
             name: "Test Model".to_string(),
-            family: Some("llama".to_string()),
+            family: Some(ModelFamily::Llama),
             path: PathBuf::from("/test.gguf"),
             format: ModelFormat::Gguf,
             quantization: Some(QuantizationType::Q4_K_M),
@@ -355,6 +682,7 @@ mod tests {
             num_heads: Some(32),
             file_size: 4_000_000_000,
             sha256: None,
+            source_url: None,
         };
 
         let vram = spec.estimated_vram_mb();
@@ -29,6 +29,9 @@ pub enum TaskType {
     Validation,
     /// Web crawl: fetch and extract text from URLs
     WebCrawl,
+    /// Deterministic text transform for pipeline testing (never advertised
+    /// unless debug tasks are enabled)
+    Debug,
 }
 
 impl TaskType {
@@ -43,6 +46,7 @@ impl TaskType {
             TaskType::TrainingBatch,
             TaskType::Validation,
             TaskType::WebCrawl,
+            TaskType::Debug,
         ]
     }
 
@@ -62,6 +66,7 @@ impl TaskType {
             TaskType::TrainingBatch => 8192,
             TaskType::Validation => 4096,
             TaskType::WebCrawl => 0,
+            TaskType::Debug => 0,
         }
     }
 }
@@ -77,6 +82,7 @@ impl std::fmt::Display for TaskType {
             TaskType::TrainingBatch => write!(f, "training_batch"),
             TaskType::Validation => write!(f, "validation"),
             TaskType::WebCrawl => write!(f, "web_crawl"),
+            TaskType::Debug => write!(f, "debug"),
         }
     }
 }
@@ -165,6 +171,63 @@ fn default_temperature() -> f32 { 0.7 }
 fn default_top_p() -> f32 { 0.9 }
 fn default_repetition_penalty() -> f32 { 1.1 }
 
+/// Allowed temperature range
+pub const TEMPERATURE_RANGE: (f32, f32) = (0.0, 2.0);
+
+/// Smallest top-p kept; the range is (0, 1]
+pub const MIN_TOP_P: f32 = 0.01;
+
+/// Largest top-k kept
+pub const MAX_TOP_K: u32 = 1000;
+
+/// Allowed repetition penalty range
+pub const REPETITION_PENALTY_RANGE: (f32, f32) = (0.5, 2.0);
+
+impl GenerationParams {
+    /// Bring sampling parameters into the ranges backends handle, logging
+    /// each one that was out of range. `max_tokens` is capped at
+    /// `max_context_length` (0 = no cap). NaN falls back to the default.
+    ///
+    /// Returns whether anything was changed.
+    pub fn validate_and_clamp(&mut self, max_context_length: u32) -> bool {
+        let mut changed = false;
+        changed |= clamp_f32("temperature", &mut self.temperature, TEMPERATURE_RANGE, default_temperature());
+        changed |= clamp_f32("top_p", &mut self.top_p, (MIN_TOP_P, 1.0), default_top_p());
+        changed |= clamp_f32(
+            "repetition_penalty",
+            &mut self.repetition_penalty,
+            REPETITION_PENALTY_RANGE,
+            default_repetition_penalty(),
+        );
+        if self.top_k > MAX_TOP_K {
+            tracing::warn!(requested = self.top_k, clamped = MAX_TOP_K, "top_k out of range, clamping");
+            self.top_k = MAX_TOP_K;
+            changed = true;
+        }
+        if max_context_length > 0 && self.max_tokens > max_context_length {
+            tracing::warn!(
+                requested = self.max_tokens,
+                clamped = max_context_length,
+                "max_tokens exceeds the backend's context length, clamping"
+            );
+            self.max_tokens = max_context_length;
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// Clamp `value` into `(min, max)`, or reset it to `default` if NaN
+fn clamp_f32(name: &str, value: &mut f32, (min, max): (f32, f32), default: f32) -> bool {
+    let clamped = if value.is_nan() { default } else { value.clamp(min, max) };
+    if clamped == *value {
+        return false;
+    }
+    tracing::warn!(param = name, requested = *value, clamped, "Sampling parameter out of range, clamping");
+    *value = clamped;
+    true
+}
+
 impl Default for GenerationParams {
     fn default() -> Self {
         Self {
@@ -245,6 +308,25 @@ pub struct EmbeddingsOutput {
     pub usage: TokenUsage,
 }
 
+impl EmbeddingsOutput {
+    /// Scale every vector to unit L2 length (zero vectors are left as-is)
+    pub fn l2_normalize(&mut self) {
+        for embedding in &mut self.embeddings {
+            l2_normalize(embedding);
+        }
+    }
+}
+
+/// Scale `vector` to unit L2 length; a zero vector is left as-is
+pub fn l2_normalize(vector: &mut [f32]) {
+    let magnitude: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if magnitude > 0.0 {
+        for x in vector.iter_mut() {
+            *x /= magnitude;
+        }
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Classification
 // ─────────────────────────────────────────────────────────────────
@@ -273,12 +355,29 @@ pub struct ClassificationPrediction {
     pub score: f32,
 }
 
+/// How classification scores were produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreCalibration {
+    /// Scores are whatever the backend emitted
+    #[default]
+    Raw,
+    /// Softmax over text/label embedding similarities
+    Softmax,
+    /// Token probabilities over the label set, renormalized
+    Logprob,
+}
+
 /// Output from classification task
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassificationOutput {
     /// Predictions sorted by confidence (highest first)
     pub predictions: Vec<ClassificationPrediction>,
 
+    /// How the prediction scores were calibrated
+    #[serde(default)]
+    pub calibration: ScoreCalibration,
+
     /// Token usage
     pub usage: TokenUsage,
 }
@@ -311,7 +410,8 @@ pub struct QuestionAnsweringOutput {
     #[serde(default)]
     pub confidence: Option<f32>,
 
-    /// Relevant spans from context (start, end character indices)
+    /// Relevant spans from context, as (start, end) byte offsets on char
+    /// boundaries, so `&context[start..end]` is the supporting text
     #[serde(default)]
     pub evidence_spans: Vec<(usize, usize)>,
 
@@ -342,7 +442,7 @@ pub struct SummarizationInput {
     pub params: GenerationParams,
 }
 
-fn default_summary_length() -> u32 { 100 }
+pub(crate) fn default_summary_length() -> u32 { 100 }
 
 /// Style of summarization
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -486,6 +586,28 @@ pub struct WebCrawlInput {
     /// Restrict link-following to these domains (empty = no restriction)
     #[serde(default)]
     pub allowed_domains: Vec<String>,
+
+    /// Order of the returned pages
+    #[serde(default)]
+    pub sort_by: CrawlSortBy,
+
+    /// Return at most this many pages after sorting (distinct from `max_pages`,
+    /// which caps fetching)
+    #[serde(default)]
+    pub max_pages_returned: Option<u32>,
+}
+
+/// Ordering applied to crawled pages before they are returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlSortBy {
+    /// Order in which pages were fetched (BFS order)
+    #[default]
+    CrawlOrder,
+    /// Longest extracted text first
+    TextLength,
+    /// Shallowest pages (closest to the seed) first
+    Depth,
 }
 
 fn default_max_depth() -> u32 { 1 }
@@ -508,6 +630,9 @@ pub struct CrawledPage {
     pub fetched_at: String,
     /// Hex SHA-256 of the extracted text (for dedup)
     pub content_hash: String,
+    /// BFS depth at which the page was found (0 = seed)
+    #[serde(default)]
+    pub depth: u32,
 }
 
 /// Output from web crawl task
@@ -517,10 +642,110 @@ pub struct WebCrawlOutput {
     pub pages: Vec<CrawledPage>,
     /// Total pages fetched (including any that were empty/skipped)
     pub total_fetched: u32,
-    /// Sum of all page text lengths in characters
+    /// Sum of all fetched page text lengths in characters
     pub total_text_chars: u64,
-    /// Non-fatal errors (e.g., individual pages that failed to fetch)
+    /// Non-fatal errors (e.g., individual pages that failed to fetch),
+    /// rendered as `"<url>: <message>"`
     pub errors: Vec<String>,
+    /// The same errors with their kind, for aggregation
+    #[serde(default)]
+    pub crawl_errors: Vec<CrawlError>,
+}
+
+/// Category of a non-fatal crawl error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlErrorKind {
+    /// Server answered with a non-success status
+    HttpStatus,
+    /// Request or body read timed out
+    Timeout,
+    /// Excluded by robots.txt or meta robots directives
+    RobotsDisallowed,
+    /// Page larger than the crawler accepts
+    TooLarge,
+    /// Body unreadable or not HTML
+    ParseFailed,
+    /// Links outside `allowed_domains` were skipped
+    OffDomain,
+    /// Connection-level failure (DNS, refused, TLS)
+    Network,
+    /// Domain is on the operator's `crawler.domain_denylist`
+    Denylisted,
+    /// Host is outside the operator's `sandbox.egress_allowlist`
+    EgressBlocked,
+}
+
+/// A non-fatal crawl error
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlError {
+    /// Page the error occurred on
+    pub url: String,
+    /// Error category
+    pub kind: CrawlErrorKind,
+    /// Human-readable detail
+    pub message: String,
+}
+
+impl CrawlError {
+    /// Create an error for `url`
+    pub fn new(url: &str, kind: CrawlErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            url: url.to_string(),
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for CrawlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.url, self.message)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Debug
+// ─────────────────────────────────────────────────────────────────
+
+/// Transform applied by a debug task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugOp {
+    /// Return the text unchanged
+    Echo,
+    /// Reverse the text by character
+    Reverse,
+    /// Uppercase the text
+    Uppercase,
+}
+
+impl DebugOp {
+    /// Apply the transform to `text`
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            DebugOp::Echo => text.to_string(),
+            DebugOp::Reverse => text.chars().rev().collect(),
+            DebugOp::Uppercase => text.to_uppercase(),
+        }
+    }
+}
+
+/// Input for debug task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugInput {
+    /// Transform to apply
+    pub op: DebugOp,
+
+    /// Text to transform
+    pub text: String,
+}
+
+/// Output from debug task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugOutput {
+    /// Transformed text
+    pub text: String,
 }
 
 // ─────────────────────────────────────────────────────────────────
@@ -547,6 +772,8 @@ pub enum TaskInput {
     Validation(ValidationInput),
     #[serde(rename = "WEB_CRAWL")]
     WebCrawl(WebCrawlInput),
+    #[serde(rename = "DEBUG")]
+    Debug(DebugInput),
 }
 
 impl TaskInput {
@@ -561,6 +788,27 @@ impl TaskInput {
             TaskInput::TrainingBatch(_) => TaskType::TrainingBatch,
             TaskInput::Validation(_) => TaskType::Validation,
             TaskInput::WebCrawl(_) => TaskType::WebCrawl,
+            TaskInput::Debug(_) => TaskType::Debug,
+        }
+    }
+
+    /// Generation parameters, for task types that generate text
+    pub fn generation_params(&self) -> Option<&GenerationParams> {
+        match self {
+            TaskInput::TextCompletion(input) => Some(&input.params),
+            TaskInput::QuestionAnswering(input) => Some(&input.params),
+            TaskInput::Summarization(input) => Some(&input.params),
+            _ => None,
+        }
+    }
+
+    /// Generation parameters, for task types that generate text
+    pub fn generation_params_mut(&mut self) -> Option<&mut GenerationParams> {
+        match self {
+            TaskInput::TextCompletion(input) => Some(&mut input.params),
+            TaskInput::QuestionAnswering(input) => Some(&mut input.params),
+            TaskInput::Summarization(input) => Some(&mut input.params),
+            _ => None,
         }
     }
 }
@@ -585,6 +833,8 @@ pub enum TaskOutput {
     Validation(ValidationOutput),
     #[serde(rename = "WEB_CRAWL")]
     WebCrawl(WebCrawlOutput),
+    #[serde(rename = "DEBUG")]
+    Debug(DebugOutput),
 }
 
 impl TaskOutput {
@@ -599,6 +849,7 @@ impl TaskOutput {
             TaskOutput::TrainingBatch(_) => TaskType::TrainingBatch,
             TaskOutput::Validation(_) => TaskType::Validation,
             TaskOutput::WebCrawl(_) => TaskType::WebCrawl,
+            TaskOutput::Debug(_) => TaskType::Debug,
         }
     }
 
@@ -613,6 +864,7 @@ impl TaskOutput {
             TaskOutput::TrainingBatch(_) => None,
             TaskOutput::Validation(_) => None,
             TaskOutput::WebCrawl(_) => None,
+            TaskOutput::Debug(_) => None,
         }
     }
 }
@@ -653,6 +905,69 @@ mod tests {
         assert!((params.temperature - 0.7).abs() < 0.01);
     }
 
+    #[test]
+    fn test_generation_params_clamp_bounds() {
+        let mut params = GenerationParams::default();
+        assert!(!params.validate_and_clamp(4096));
+
+        // Boundaries themselves are kept
+        params.temperature = 0.0;
+        params.top_p = 1.0;
+        params.top_k = MAX_TOP_K;
+        params.repetition_penalty = 2.0;
+        params.max_tokens = 4096;
+        assert!(!params.validate_and_clamp(4096));
+        params.temperature = 2.0;
+        params.top_p = MIN_TOP_P;
+        params.repetition_penalty = 0.5;
+        assert!(!params.validate_and_clamp(4096));
+    }
+
+    #[test]
+    fn test_generation_params_clamped_low() {
+        let mut params = GenerationParams {
+            temperature: -1.0,
+            top_p: 0.0,
+            repetition_penalty: 0.1,
+            ..Default::default()
+        };
+        assert!(params.validate_and_clamp(4096));
+        assert_eq!(params.temperature, 0.0);
+        assert_eq!(params.top_p, MIN_TOP_P);
+        assert_eq!(params.repetition_penalty, 0.5);
+    }
+
+    #[test]
+    fn test_generation_params_clamped_high() {
+        let mut params = GenerationParams {
+            temperature: 2.5,
+            top_p: 5.0,
+            top_k: MAX_TOP_K + 1,
+            repetition_penalty: 3.0,
+            max_tokens: 100_000,
+            ..Default::default()
+        };
+        assert!(params.validate_and_clamp(4096));
+        assert_eq!(params.temperature, 2.0);
+        assert_eq!(params.top_p, 1.0);
+        assert_eq!(params.top_k, MAX_TOP_K);
+        assert_eq!(params.repetition_penalty, 2.0);
+        assert_eq!(params.max_tokens, 4096);
+
+        // No context length known: max_tokens is left alone
+        params.max_tokens = 100_000;
+        assert!(!params.validate_and_clamp(0));
+        assert_eq!(params.max_tokens, 100_000);
+    }
+
+    #[test]
+    fn test_generation_params_nan_reset() {
+        let mut params = GenerationParams { temperature: f32::NAN, top_p: f32::NAN, ..Default::default() };
+        assert!(params.validate_and_clamp(4096));
+        assert_eq!(params.temperature, default_temperature());
+        assert_eq!(params.top_p, default_top_p());
+    }
+
     #[test]
     fn test_text_completion_input_serialize() {
         let input = TextCompletionInput {
@@ -698,4 +1013,19 @@ mod tests {
         let input: EmbeddingsInput = serde_json::from_str(json).unwrap();
         assert!(input.normalize);
     }
+
+    #[test]
+    fn test_embeddings_l2_normalize() {
+        let mut output = EmbeddingsOutput {
+            embeddings: vec![vec![3.0, 4.0], vec![0.0, 0.0]],
+            dimensions: 2,
+            usage: TokenUsage::new(2, 0),
+        };
+        output.l2_normalize();
+
+        let magnitude: f32 = output.embeddings[0].iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((magnitude - 1.0).abs() < 1e-6);
+        assert_eq!(output.embeddings[0], vec![0.6, 0.8]);
+        assert_eq!(output.embeddings[1], vec![0.0, 0.0]);
+    }
 }
@@ -66,6 +66,16 @@ fn test_config_show_default() {
         .stdout(predicate::str::contains("[storage]"));
 }
 
+#[test]
+fn test_config_show_json() {
+    worker_cmd()
+        .args(["config", "show", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"coordinator\": {"))
+        .stdout(predicate::str::contains("[worker]").not());
+}
+
 #[test]
 fn test_config_validate_default() {
     // Default config should always be valid
@@ -139,6 +149,36 @@ fn test_benchmark_custom_iterations() {
         .stdout(predicate::str::contains("5 iterations"));
 }
 
+// ─────────────────────────────────────────────────────────────────
+// Info Command Tests
+// ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_info_command() {
+    worker_cmd()
+        .arg("info")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("System:"))
+        .stdout(predicate::str::contains("GPUs:"))
+        .stdout(predicate::str::contains("Backends:"));
+}
+
+#[test]
+fn test_info_json() {
+    let output = worker_cmd().arg("info").arg("--json").output().unwrap();
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(report["system"]["cpu_count"].as_u64().unwrap() > 0);
+    assert!(report["gpus"].is_array());
+    assert!(report["backends"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|b| b["name"] == "cpu"));
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Run Command Tests
 // ─────────────────────────────────────────────────────────────────
@@ -164,6 +204,111 @@ fn test_run_with_invalid_config() {
         .failure();
 }
 
+#[test]
+fn test_run_exits_when_coordinator_unreachable() {
+    let dir = tempfile::tempdir().unwrap();
+    // Nothing listens on a port freed right after binding
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let config = dir.path().join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            r#"
+[coordinator]
+url = "ws://127.0.0.1:{port}"
+reconnect_interval_ms = 100
+connect_timeout_ms = 500
+exit_after_failed_secs = 1
+
+[storage]
+data_dir = '{dir}/data'
+model_dir = '{dir}/models'
+temp_dir = '{dir}/tmp'
+"#,
+            port = port,
+            dir = dir.path().display(),
+        ),
+    )
+    .unwrap();
+
+    worker_cmd()
+        .arg("run")
+        .arg("--config")
+        .arg(&config)
+        .timeout(std::time::Duration::from_secs(120))
+        .assert()
+        .code(31)
+        .stderr(predicate::str::contains("E305"));
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Self-Test Command Tests
+// ─────────────────────────────────────────────────────────────────
+
+/// Config with storage confined to `dir` and the OpenAI backend off
+fn write_offline_config(dir: &std::path::Path) -> std::path::PathBuf {
+    let config = dir.join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            r#"
+[storage]
+data_dir = '{dir}/data'
+model_dir = '{dir}/models'
+temp_dir = '{dir}/tmp'
+
+[openai]
+enabled = false
+"#,
+            dir = dir.display(),
+        ),
+    )
+    .unwrap();
+    config
+}
+
+#[test]
+fn test_self_test_with_mock_backend() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = write_offline_config(dir.path());
+
+    worker_cmd()
+        .arg("self-test")
+        .arg("--config")
+        .arg(&config)
+        .arg("--prompt")
+        .arg("hello")
+        .timeout(std::time::Duration::from_secs(60))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Capabilities:"))
+        .stdout(predicate::str::contains("TEXT_COMPLETION"))
+        .stdout(predicate::str::contains("Backend:  mock"))
+        .stdout(predicate::str::contains("Output:"))
+        .stdout(predicate::str::contains("Self-test passed"));
+}
+
+#[test]
+fn test_self_test_fails_without_backend() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = write_offline_config(dir.path());
+
+    // The OpenAI backend is turned off in the config
+    worker_cmd()
+        .arg("self-test")
+        .arg("--config")
+        .arg(&config)
+        .arg("--backend")
+        .arg("openai")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not available to serve text completion"));
+}
+
 // ─────────────────────────────────────────────────────────────────
 // Verbosity Flag Tests
 // ─────────────────────────────────────────────────────────────────